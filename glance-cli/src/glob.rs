@@ -0,0 +1,84 @@
+//! Minimal glob expansion for input paths, so patterns work the same on every shell.
+
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Returns true if `name` matches `pattern`, where `*` matches any run of characters and `?`
+/// matches a single character.
+pub fn matches(pattern: &str, name: &str) -> bool {
+    let (pattern, name): (Vec<char>, Vec<char>) =
+        (pattern.chars().collect(), name.chars().collect());
+    let (mut p, mut n) = (0, 0);
+    // Position after the last `*` and the name position it was tried at, for backtracking
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((after_star, tried)) => {
+                    p = after_star;
+                    n = tried + 1;
+                    star = Some((after_star, tried + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn has_wildcards(s: &str) -> bool {
+    s.contains(['*', '?'])
+}
+
+/// Expands a path pattern with wildcards in any component into the sorted list of existing
+/// paths it matches. Patterns without wildcards are returned unchanged, even if they do not
+/// exist, so that opening them reports a useful error.
+pub fn expand(pattern: &str) -> io::Result<Vec<PathBuf>> {
+    if !has_wildcards(pattern) {
+        return Ok(vec![PathBuf::from(pattern)]);
+    }
+
+    let mut paths = vec![PathBuf::new()];
+    for component in Path::new(pattern).components() {
+        let name = component.as_os_str().to_string_lossy();
+        if !matches!(component, Component::Normal(_)) || !has_wildcards(&name) {
+            paths.iter_mut().for_each(|p| p.push(component));
+            continue;
+        }
+
+        let mut expanded = Vec::new();
+        for dir in &paths {
+            let listing = if dir.as_os_str().is_empty() {
+                fs::read_dir(".")
+            } else {
+                fs::read_dir(dir)
+            };
+            let Ok(entries) = listing else {
+                continue;
+            };
+            for entry in entries {
+                let entry = entry?;
+                let file_name = entry.file_name().to_string_lossy().into_owned();
+                // Like shells, wildcards do not match hidden files
+                if !file_name.starts_with('.') && matches(&name, &file_name) {
+                    expanded.push(dir.join(file_name));
+                }
+            }
+        }
+        paths = expanded;
+    }
+
+    paths.retain(|p| p.exists());
+    paths.sort();
+    Ok(paths)
+}
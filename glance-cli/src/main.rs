@@ -0,0 +1,164 @@
+mod glob;
+mod ops;
+
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use glance_core::img::Image;
+use glance_core::img::pixel::Rgba;
+
+use crate::ops::{Operation, Result, USAGE};
+
+/// Parsed command line: the operation chain, expanded inputs and the output template.
+#[derive(Debug)]
+struct Invocation {
+    operations: Vec<Operation>,
+    inputs: Vec<PathBuf>,
+    output: Option<String>,
+}
+
+/// Splits the arguments into `-i`/`-o` options and the `+` separated operations.
+fn parse_args(args: &[String]) -> Result<Invocation> {
+    let mut patterns = Vec::new();
+    let mut output = None;
+    let mut chain: Vec<Vec<String>> = vec![Vec::new()];
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-i" | "--input" => patterns.push(args.next().ok_or("-i needs a path")?.clone()),
+            "-o" | "--output" => {
+                output = Some(args.next().ok_or("-o needs a path")?.clone());
+            }
+            "+" => chain.push(Vec::new()),
+            _ => chain.last_mut().unwrap().push(arg.clone()),
+        }
+    }
+
+    let operations = chain
+        .iter()
+        .map(|op| Operation::parse(op))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut inputs = Vec::new();
+    for pattern in &patterns {
+        let expanded = glob::expand(pattern)?;
+        if expanded.is_empty() {
+            return Err(format!("no files match '{pattern}'").into());
+        }
+        inputs.extend(expanded);
+    }
+    if inputs.is_empty() {
+        return Err("no inputs, use -i INPUT".into());
+    }
+    if let Some(template) = &output
+        && inputs.len() > 1
+        && !template.contains("{stem}")
+        && !template.contains("{index}")
+    {
+        return Err("multiple inputs need {stem} or {index} in the output path".into());
+    }
+
+    Ok(Invocation {
+        operations,
+        inputs,
+        output,
+    })
+}
+
+/// Fills the `{stem}` and `{index}` placeholders of the output template.
+fn output_path(template: &str, input: &Path, index: usize) -> PathBuf {
+    let stem = input
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    PathBuf::from(
+        template
+            .replace("{stem}", &stem)
+            .replace("{index}", &index.to_string()),
+    )
+}
+
+fn run(args: &[String]) -> Result<()> {
+    let invocation = parse_args(args)?;
+
+    for (index, input) in invocation.inputs.iter().enumerate() {
+        let mut image = Image::<Rgba>::open(input)
+            .map_err(|e| format!("cannot open {}: {e}", input.display()))?;
+        for operation in &invocation.operations {
+            image = operation.apply(image, input)?;
+        }
+
+        if let Some(template) = &invocation.output {
+            let path = output_path(template, input, index);
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            image
+                .save(&path)
+                .map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("error: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob::matches("*.png", "flower.png"));
+        assert!(glob::matches("f?ower*", "flower.jpg"));
+        assert!(glob::matches("*a*b*", "xxaxxbxx"));
+        assert!(!glob::matches("*.png", "flower.jpg"));
+        assert!(!glob::matches("?", ""));
+
+        let inputs = glob::expand("../media/test_imgs/*.png").unwrap();
+        assert!(!inputs.is_empty());
+        assert!(inputs.iter().all(|p| p.extension().unwrap() == "png"));
+        assert!(inputs.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn parse_chain() -> Result<()> {
+        let args: Vec<String> =
+            "blur 1.5 + resize 50% + draw text 2 2 hi #00ff00 -i a.png -o {stem}.jpg"
+                .split(' ')
+                .map(String::from)
+                .collect();
+        let invocation = parse_args(&args)?;
+        assert_eq!(invocation.operations.len(), 3);
+        assert_eq!(invocation.operations[0], Operation::Blur(1.5));
+        assert_eq!(
+            output_path(
+                invocation.output.as_deref().unwrap(),
+                Path::new("dir/a.png"),
+                0
+            ),
+            PathBuf::from("a.jpg")
+        );
+
+        let bad: Vec<String> = ["resize", "fifty", "-i", "a.png"]
+            .map(String::from)
+            .to_vec();
+        assert!(parse_args(&bad).is_err());
+        Ok(())
+    }
+}
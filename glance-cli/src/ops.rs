@@ -0,0 +1,344 @@
+//! The operations that can be chained on the command line.
+
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use glance_core::colors;
+use glance_core::drawing::shapes::{AABB, Circle, Line};
+use glance_core::drawing::text::Text;
+use glance_core::img::Image;
+use glance_core::img::pixel::{Luma, Rgba};
+use glance_imgproc::affine::{AffineTransformationsExt, Interpolation};
+use glance_imgproc::linear_filters::LinearFilterExtRgba;
+use glance_imgproc::metrics::{DifferenceSummary, delta_e};
+use glance_imgproc::point_ops::{PointOpsExtLuma, PointOpsExtRgba, ThresholdType};
+
+pub type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+/// Target size of [`Operation::Resize`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Size {
+    Exact(usize, usize),
+    Percent(f32),
+}
+
+/// A shape for [`Operation::Draw`], in pixel coordinates.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Rect {
+        x: usize,
+        y: usize,
+        w: usize,
+        h: usize,
+    },
+    Circle {
+        x: usize,
+        y: usize,
+        r: u32,
+    },
+    Line {
+        x0: usize,
+        y0: usize,
+        x1: usize,
+        y1: usize,
+    },
+    Text {
+        x: usize,
+        y: usize,
+        text: String,
+    },
+}
+
+/// A single step of the processing chain.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operation {
+    /// Changes the color mode, or only re-encodes the image if `gray` is false
+    Convert {
+        gray: bool,
+    },
+    Resize(Size),
+    Blur(f32),
+    Threshold(f32),
+    Draw {
+        shape: Shape,
+        color: Rgba,
+    },
+    /// Prints the size and per channel statistics
+    Stats,
+    /// Prints the CIEDE2000 difference to another image
+    Diff(PathBuf),
+    /// Shows the image in a window
+    View,
+}
+
+pub const USAGE: &str = "\
+Usage: glance-cli OPERATION [ARGS] [+ OPERATION [ARGS]]... -i INPUT... [-o OUTPUT]
+
+Applies the chain of operations to every input and writes the result to OUTPUT.
+INPUT may contain * and ? wildcards. OUTPUT may contain {stem} (the input file name without
+extension) and {index} (the position of the input), and is required for multiple inputs.
+
+Operations:
+  convert [gray]                    re-encode in the format of OUTPUT, optionally as grayscale
+  resize WxH | P%                   resize to an exact size or by a percentage
+  blur SIGMA                        gaussian blur
+  threshold T                       binarize the grayscale image at T in [0, 1]
+  draw rect X Y W H [#RRGGBB]       draw a rectangle outline
+  draw circle X Y R [#RRGGBB]       draw a circle outline
+  draw line X0 Y0 X1 Y1 [#RRGGBB]   draw a line
+  draw text X Y TEXT [#RRGGBB]      draw text
+  stats                             print size and channel statistics
+  diff OTHER                        print the color difference (delta E) to OTHER
+  view                              display the image";
+
+fn parse_number<T: std::str::FromStr>(value: &str, what: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid {what} '{value}'").into())
+}
+
+fn parse_color(value: &str) -> Result<Rgba> {
+    Rgba::from_hex(value).map_err(|_| format!("invalid color '{value}', expected #RRGGBB").into())
+}
+
+impl Operation {
+    /// Parses one operation from its name and arguments.
+    pub fn parse(args: &[String]) -> Result<Operation> {
+        let (name, args) = args.split_first().ok_or("empty operation")?;
+        let expect_args = |count: usize| -> Result<()> {
+            if args.len() != count {
+                return Err(
+                    format!("'{name}' takes {count} argument(s), got {}", args.len()).into(),
+                );
+            }
+            Ok(())
+        };
+
+        let operation = match name.as_str() {
+            "convert" => match args {
+                [] => Operation::Convert { gray: false },
+                [mode] if mode == "gray" => Operation::Convert { gray: true },
+                _ => return Err("'convert' takes an optional 'gray'".into()),
+            },
+            "resize" => {
+                expect_args(1)?;
+                let size = &args[0];
+                if let Some(percent) = size.strip_suffix('%') {
+                    let percent: f32 = parse_number(percent, "percentage")?;
+                    if !(percent > 0.0 && percent.is_finite()) {
+                        return Err(
+                            format!("resize percentage must be positive, got '{size}'").into()
+                        );
+                    }
+                    Operation::Resize(Size::Percent(percent))
+                } else {
+                    let (w, h) = size
+                        .split_once('x')
+                        .ok_or_else(|| format!("invalid size '{size}', expected WxH or P%"))?;
+                    let (w, h) = (parse_number(w, "width")?, parse_number(h, "height")?);
+                    if w == 0 || h == 0 {
+                        return Err(format!("resize size must be positive, got '{size}'").into());
+                    }
+                    Operation::Resize(Size::Exact(w, h))
+                }
+            }
+            "blur" => {
+                expect_args(1)?;
+                Operation::Blur(parse_number(&args[0], "sigma")?)
+            }
+            "threshold" => {
+                expect_args(1)?;
+                Operation::Threshold(parse_number(&args[0], "threshold")?)
+            }
+            "draw" => Self::parse_draw(args)?,
+            "stats" => {
+                expect_args(0)?;
+                Operation::Stats
+            }
+            "diff" => {
+                expect_args(1)?;
+                Operation::Diff(PathBuf::from(&args[0]))
+            }
+            "view" => {
+                expect_args(0)?;
+                Operation::View
+            }
+            _ => return Err(format!("unknown operation '{name}'").into()),
+        };
+        Ok(operation)
+    }
+
+    fn parse_draw(args: &[String]) -> Result<Operation> {
+        let (kind, args) = args.split_first().ok_or("'draw' needs a shape")?;
+        let count = match kind.as_str() {
+            "rect" | "line" => 4,
+            "circle" | "text" => 3,
+            _ => return Err(format!("unknown shape '{kind}'").into()),
+        };
+        let color = match &args[count.min(args.len())..] {
+            [] => colors::RED,
+            [color] => parse_color(color)?,
+            _ => return Err(format!("too many arguments for 'draw {kind}'").into()),
+        };
+        if args.len() < count {
+            return Err(format!("'draw {kind}' takes {count} arguments").into());
+        }
+
+        let n = |i: usize| parse_number::<usize>(&args[i], "coordinate");
+        let shape = match kind.as_str() {
+            "rect" => Shape::Rect {
+                x: n(0)?,
+                y: n(1)?,
+                w: n(2)?,
+                h: n(3)?,
+            },
+            "line" => Shape::Line {
+                x0: n(0)?,
+                y0: n(1)?,
+                x1: n(2)?,
+                y1: n(3)?,
+            },
+            "circle" => Shape::Circle {
+                x: n(0)?,
+                y: n(1)?,
+                r: parse_number(&args[2], "radius")?,
+            },
+            _ => Shape::Text {
+                x: n(0)?,
+                y: n(1)?,
+                text: args[2].clone(),
+            },
+        };
+        Ok(Operation::Draw { shape, color })
+    }
+
+    /// Applies the operation to the image loaded from `path`.
+    pub fn apply(&self, image: Image<Rgba>, path: &Path) -> Result<Image<Rgba>> {
+        let image = match self {
+            Operation::Convert { gray: false } => image,
+            Operation::Convert { gray: true } => to_rgba(&image.grayscale()),
+            Operation::Resize(size) => {
+                let (width, height) = image.dimensions();
+                let (sx, sy) = match *size {
+                    Size::Exact(w, h) => (w as f32 / width as f32, h as f32 / height as f32),
+                    Size::Percent(p) => (p / 100.0, p / 100.0),
+                };
+                let resized = image.scale(sx, sy, Interpolation::Bilinear)?;
+                let (new_width, new_height) = resized.dimensions();
+                if new_width == 0 || new_height == 0 {
+                    return Err(
+                        format!("resizing the {width}x{height} image leaves no pixels").into(),
+                    );
+                }
+                resized
+            }
+            Operation::Blur(sigma) => image.gaussian_blur(*sigma),
+            Operation::Threshold(t) => {
+                to_rgba(&image.grayscale().threshold(*t, 1.0, ThresholdType::Binary))
+            }
+            Operation::Draw { shape, color } => {
+                let mut image = image;
+                draw(&mut image, shape, *color)?;
+                image
+            }
+            Operation::Stats => {
+                println!("{}", stats(&image, path));
+                image
+            }
+            Operation::Diff(other) => {
+                let reference = Image::<Rgba>::open(other)?;
+                if reference.dimensions() != image.dimensions() {
+                    return Err(format!(
+                        "cannot compare {} ({:?}) with {} ({:?}) of a different size",
+                        path.display(),
+                        image.dimensions(),
+                        other.display(),
+                        reference.dimensions()
+                    )
+                    .into());
+                }
+                let summary = DifferenceSummary::from(&delta_e(&reference, &image)?);
+                println!(
+                    "{}: delta E mean {:.3} median {:.3} p95 {:.3} max {:.3}",
+                    path.display(),
+                    summary.mean,
+                    summary.median,
+                    summary.p95,
+                    summary.max
+                );
+                image
+            }
+            Operation::View => {
+                image.display(&path.display().to_string())?;
+                image
+            }
+        };
+        Ok(image)
+    }
+}
+
+fn to_rgba(image: &Image<Luma>) -> Image<Rgba> {
+    let (width, height) = image.dimensions();
+    let data = image
+        .pixels()
+        .map(|p| Rgba {
+            r: p.l,
+            g: p.l,
+            b: p.l,
+            a: 1.0,
+        })
+        .collect();
+    Image::from_data(width, height, data).unwrap()
+}
+
+fn draw(image: &mut Image<Rgba>, shape: &Shape, color: Rgba) -> Result<()> {
+    match shape {
+        Shape::Rect { x, y, w, h } => image.draw(AABB {
+            position: (*x, *y),
+            size: (*w, *h),
+            color,
+            filled: false,
+            thickness: 1,
+        })?,
+        Shape::Circle { x, y, r } => image.draw(Circle {
+            position: (*x, *y),
+            color,
+            radius: *r,
+            filled: false,
+            thickness: 1,
+        })?,
+        Shape::Line { x0, y0, x1, y1 } => image.draw(Line {
+            start: (*x0, *y0),
+            end: (*x1, *y1),
+            color,
+            thickness: 1,
+        })?,
+        Shape::Text { x, y, text } => image.draw(Text {
+            position: (*x, *y),
+            text: text.clone(),
+            color,
+            scale: 1,
+        })?,
+    }
+    Ok(())
+}
+
+/// Formats the size and per channel mean, minimum and maximum of the image.
+fn stats(image: &Image<Rgba>, path: &Path) -> String {
+    let (width, height) = image.dimensions();
+    let n = (width * height).max(1) as f32;
+    let mut lines = vec![format!("{}: {width}x{height}", path.display())];
+    for (name, channel) in [("r", 0), ("g", 1), ("b", 2), ("a", 3)] {
+        let values = image.pixels().map(|p| [p.r, p.g, p.b, p.a][channel]);
+        let (sum, min, max) = values.fold((0.0, f32::MAX, f32::MIN), |(s, lo, hi), v| {
+            (s + v, lo.min(v), hi.max(v))
+        });
+        lines.push(format!(
+            "  {name}: mean {:.4} min {:.4} max {:.4}",
+            sum / n,
+            min,
+            max
+        ));
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,77 @@
+use std::process::Command;
+
+use glance_core::img::Image;
+use glance_core::img::pixel::Luma;
+
+fn glance_cli(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_glance-cli"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("failed to run glance-cli")
+}
+
+#[test]
+fn chained_operations() {
+    let dir = std::env::temp_dir().join("glance-cli-chained");
+    let output = dir.join("{stem}_binary.png");
+    let result = glance_cli(&[
+        "blur",
+        "1",
+        "+",
+        "resize",
+        "25%",
+        "+",
+        "threshold",
+        "0.5",
+        "-i",
+        "../media/test_imgs/flower.jpg",
+        "-o",
+        output.to_str().unwrap(),
+    ]);
+    assert!(
+        result.status.success(),
+        "{}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+
+    let image = Image::<Luma>::open(dir.join("flower_binary.png")).unwrap();
+    assert!(image.pixels().all(|p| p.l == 0.0 || p.l == 1.0));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn stats_and_diff() {
+    let result = glance_cli(&[
+        "stats",
+        "+",
+        "diff",
+        "../media/test_imgs/flower.jpg",
+        "-i",
+        "../media/test_imgs/flower.jpg",
+    ]);
+    assert!(result.status.success());
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    assert!(stdout.contains("r: mean"));
+    assert!(stdout.contains("delta E mean 0.000"));
+
+    let result = glance_cli(&["blur", "-i", "../media/test_imgs/flower.jpg"]);
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("'blur' takes 1 argument"));
+}
+
+#[test]
+fn rejects_empty_resize() {
+    for size in ["0%", "-50%", "0x10", "10x0"] {
+        let result = glance_cli(&["resize", size, "-i", "../media/test_imgs/flower.jpg"]);
+        assert!(!result.status.success(), "{size}");
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        assert!(
+            stderr.contains(size) && !stderr.contains("panicked"),
+            "{stderr}"
+        );
+    }
+
+    let result = glance_cli(&["resize", "0.01%", "-i", "../media/test_imgs/flower.jpg"]);
+    assert!(String::from_utf8_lossy(&result.stderr).contains("leaves no pixels"));
+}
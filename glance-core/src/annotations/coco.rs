@@ -0,0 +1,198 @@
+//! COCO object detection JSON: one file with the images, categories and annotations of a whole
+//! dataset. Boxes are `[x, y, width, height]` in pixels and segmentations are lists of polygons
+//! with flattened `[x0, y0, x1, y1, ...]` vertices.
+//!
+//! Run-length encoded masks of crowd annotations are not supported, such annotations are read
+//! with their box only.
+
+use std::collections::HashMap;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Annotation, BoundingBox, Dataset, ImageAnnotations, Keypoint, Visibility};
+use crate::{CoreError, Result};
+
+#[derive(Serialize, Deserialize)]
+struct CocoFile {
+    images: Vec<CocoImage>,
+    #[serde(default)]
+    annotations: Vec<CocoAnnotation>,
+    categories: Vec<CocoCategory>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CocoImage {
+    id: u64,
+    file_name: String,
+    width: usize,
+    height: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CocoCategory {
+    id: u64,
+    name: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Segmentation {
+    Polygons(Vec<Vec<f32>>),
+    Rle(serde_json::Value),
+}
+
+#[derive(Serialize, Deserialize)]
+struct CocoAnnotation {
+    id: u64,
+    image_id: u64,
+    category_id: u64,
+    bbox: [f32; 4],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    segmentation: Option<Segmentation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    keypoints: Vec<f32>,
+    #[serde(default)]
+    area: f32,
+    #[serde(default)]
+    iscrowd: u8,
+}
+
+fn invalid(e: impl std::fmt::Display) -> CoreError {
+    CoreError::InvalidData(format!("invalid COCO annotations: {e}"))
+}
+
+/// Reads a COCO JSON file. Categories are ordered by their id.
+pub fn read(reader: impl Read) -> Result<Dataset> {
+    let mut file: CocoFile = serde_json::from_reader(BufReader::new(reader)).map_err(invalid)?;
+
+    file.categories.sort_by_key(|c| c.id);
+    let category_names: HashMap<u64, &str> = file
+        .categories
+        .iter()
+        .map(|c| (c.id, c.name.as_str()))
+        .collect();
+    let image_index: HashMap<u64, usize> = file
+        .images
+        .iter()
+        .enumerate()
+        .map(|(i, image)| (image.id, i))
+        .collect();
+
+    let mut images: Vec<ImageAnnotations> = file
+        .images
+        .iter()
+        .map(|image| ImageAnnotations {
+            file_name: image.file_name.clone(),
+            width: image.width,
+            height: image.height,
+            annotations: Vec::new(),
+        })
+        .collect();
+    for annotation in file.annotations {
+        let category = category_names
+            .get(&annotation.category_id)
+            .ok_or_else(|| invalid(format!("unknown category {}", annotation.category_id)))?;
+        let image = image_index
+            .get(&annotation.image_id)
+            .ok_or_else(|| invalid(format!("unknown image {}", annotation.image_id)))?;
+
+        let polygons = match annotation.segmentation {
+            Some(Segmentation::Polygons(polygons)) => polygons
+                .iter()
+                .map(|flat| flat.chunks_exact(2).map(|p| (p[0], p[1])).collect())
+                .collect(),
+            _ => Vec::new(),
+        };
+        let keypoints = annotation
+            .keypoints
+            .chunks_exact(3)
+            .map(|k| Keypoint {
+                x: k[0],
+                y: k[1],
+                visibility: match k[2] as u8 {
+                    1 => Visibility::Occluded,
+                    2 => Visibility::Visible,
+                    _ => Visibility::NotLabeled,
+                },
+            })
+            .collect();
+        let [x, y, width, height] = annotation.bbox;
+        images[*image].annotations.push(Annotation {
+            category: category.to_string(),
+            bbox: BoundingBox::new(x, y, width, height),
+            polygons,
+            keypoints,
+        });
+    }
+
+    Ok(Dataset {
+        categories: file.categories.into_iter().map(|c| c.name).collect(),
+        images,
+    })
+}
+
+/// Writes the dataset as COCO JSON. Images, categories and annotations are numbered from 1 in
+/// order, so category `i` of [`Dataset::categories`] gets the id `i + 1`.
+pub fn write(dataset: &Dataset, writer: impl Write) -> Result<()> {
+    let mut annotations = Vec::new();
+    for (image_index, image) in dataset.images.iter().enumerate() {
+        for annotation in &image.annotations {
+            let category = dataset.category_id(&annotation.category).ok_or_else(|| {
+                CoreError::InvalidData(format!("unknown category '{}'", annotation.category))
+            })?;
+            let bbox = annotation.bbox;
+            let segmentation = (!annotation.polygons.is_empty()).then(|| {
+                Segmentation::Polygons(
+                    annotation
+                        .polygons
+                        .iter()
+                        .map(|polygon| polygon.iter().flat_map(|&(x, y)| [x, y]).collect())
+                        .collect(),
+                )
+            });
+            annotations.push(CocoAnnotation {
+                id: annotations.len() as u64 + 1,
+                image_id: image_index as u64 + 1,
+                category_id: category as u64 + 1,
+                bbox: [bbox.x, bbox.y, bbox.width, bbox.height],
+                segmentation,
+                keypoints: annotation
+                    .keypoints
+                    .iter()
+                    .flat_map(|k| [k.x, k.y, k.visibility as u8 as f32])
+                    .collect(),
+                area: bbox.area(),
+                iscrowd: 0,
+            });
+        }
+    }
+
+    let file = CocoFile {
+        images: dataset
+            .images
+            .iter()
+            .enumerate()
+            .map(|(i, image)| CocoImage {
+                id: i as u64 + 1,
+                file_name: image.file_name.clone(),
+                width: image.width,
+                height: image.height,
+            })
+            .collect(),
+        annotations,
+        categories: dataset
+            .categories
+            .iter()
+            .enumerate()
+            .map(|(i, name)| CocoCategory {
+                id: i as u64 + 1,
+                name: name.clone(),
+            })
+            .collect(),
+    };
+    let mut writer = BufWriter::new(writer);
+    serde_json::to_writer(&mut writer, &file).map_err(std::io::Error::from)?;
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,243 @@
+//! Object annotations of image datasets: bounding boxes, polygons and keypoints, with readers and
+//! writers for the COCO JSON, YOLO txt and Pascal VOC XML formats and an [`Overlay`] to draw
+//! them onto images.
+//!
+//! All formats are converted to the same pixel-space representation, with categories referred to
+//! by name, so a dataset read in one format can be written in another.
+//!
+//! ```no_run
+//! # use std::fs::File;
+//! # use glance_core::annotations::{Overlay, coco};
+//! # use glance_core::img::{Image, pixel::Rgba};
+//! # fn main() -> glance_core::Result<()> {
+//! let dataset = coco::read(File::open("instances_val.json")?)?;
+//! for entry in &dataset.images {
+//!     let mut img = Image::<Rgba>::open(&entry.file_name)?;
+//!     img.draw(Overlay::new(&entry.annotations))?;
+//!     img.display(&entry.file_name)?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod coco;
+pub mod voc;
+pub mod yolo;
+
+use crate::Result;
+use crate::colors;
+use crate::drawing::shapes::{AABB, Circle, Line};
+use crate::drawing::text::Text;
+use crate::drawing::traits::Drawable;
+use crate::img::{Image, pixel::Pixel};
+
+/// An axis aligned box in pixel coordinates, the top-left corner of the image being (0, 0).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BoundingBox {
+    /// Left edge
+    pub x: f32,
+    /// Top edge
+    pub y: f32,
+    /// Width in pixels
+    pub width: f32,
+    /// Height in pixels
+    pub height: f32,
+}
+
+impl BoundingBox {
+    /// Creates a new [`BoundingBox`] from its top-left corner and size.
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        BoundingBox {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the smallest box containing all points, or None if there are none.
+    pub fn enclosing(points: &[(f32, f32)]) -> Option<Self> {
+        let (first, rest) = points.split_first()?;
+        let (mut min, mut max) = (*first, *first);
+        for &(x, y) in rest {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        Some(BoundingBox::new(min.0, min.1, max.0 - min.0, max.1 - min.1))
+    }
+
+    /// Returns the area of the box in square pixels.
+    pub fn area(&self) -> f32 {
+        self.width * self.height
+    }
+}
+
+/// Visibility flag of a [`Keypoint`], with the values of the COCO format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Visibility {
+    /// Not labeled, the position is meaningless
+    #[default]
+    NotLabeled = 0,
+    /// Labeled but not visible in the image
+    Occluded = 1,
+    /// Labeled and visible
+    Visible = 2,
+}
+
+/// A labeled point of an object, such as a joint of a person.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Keypoint {
+    pub x: f32,
+    pub y: f32,
+    pub visibility: Visibility,
+}
+
+/// A single annotated object.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Annotation {
+    /// Name of the object category
+    pub category: String,
+    /// Box around the object
+    pub bbox: BoundingBox,
+    /// Outlines of the object as closed polygons of (x, y) vertices, empty for box-only formats
+    pub polygons: Vec<Vec<(f32, f32)>>,
+    /// Keypoints of the object in the order of its category, empty if it has none
+    pub keypoints: Vec<Keypoint>,
+}
+
+/// The annotations of one image.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ImageAnnotations {
+    /// Path of the image, usually relative to the dataset root
+    pub file_name: String,
+    /// Width of the image in pixels
+    pub width: usize,
+    /// Height of the image in pixels
+    pub height: usize,
+    pub annotations: Vec<Annotation>,
+}
+
+/// Annotations of a set of images, with the list of categories that YOLO class ids and COCO
+/// category ids refer to.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Dataset {
+    /// Category names, ordered by class id
+    pub categories: Vec<String>,
+    pub images: Vec<ImageAnnotations>,
+}
+
+impl Dataset {
+    /// Returns the class id of the category with the given name.
+    pub fn category_id(&self, name: &str) -> Option<usize> {
+        self.categories.iter().position(|c| c == name)
+    }
+
+    /// Appends the categories used by the annotations which are not in [`Dataset::categories`]
+    /// yet, in order of first use.
+    pub fn collect_categories(&mut self) {
+        for annotation in self.images.iter().flat_map(|i| &i.annotations) {
+            if self.category_id(&annotation.category).is_none() {
+                self.categories.push(annotation.category.clone());
+            }
+        }
+    }
+}
+
+/// Draws annotations onto an image with one color per category: boxes as outlines, polygons as
+/// closed lines, visible keypoints as dots, and the category name above every box.
+pub struct Overlay<'a> {
+    pub annotations: &'a [Annotation],
+    /// Line thickness of boxes and polygons
+    pub thickness: u32,
+    /// Draw the category name of every box
+    pub labels: bool,
+}
+
+impl<'a> Overlay<'a> {
+    /// Creates an overlay with 2 pixel lines and labels.
+    pub fn new(annotations: &'a [Annotation]) -> Self {
+        Overlay {
+            annotations,
+            thickness: 2,
+            labels: true,
+        }
+    }
+}
+
+/// Returns a bright color for the category, the same one for every run.
+fn category_color<P: Pixel>(category: &str) -> P {
+    // FNV-1a, stable across platforms and releases unlike the std hasher
+    let hash = category.bytes().fold(0x811c9dc5u32, |h, b| {
+        (h ^ b as u32).wrapping_mul(0x01000193)
+    });
+    P::from_rgba8(colors::label(hash as usize).to_rgba8())
+}
+
+/// Rounds a coordinate to the nearest pixel, clamping negative values to 0.
+fn to_pixel(value: f32) -> usize {
+    value.round().max(0.0) as usize
+}
+
+impl<P: Pixel> Drawable<P> for Overlay<'_> {
+    fn draw_on(&self, image: &mut Image<P>) -> Result<()> {
+        for annotation in self.annotations {
+            let color: P = category_color(&annotation.category);
+            let bbox = annotation.bbox;
+            let position = (to_pixel(bbox.x), to_pixel(bbox.y));
+
+            for polygon in &annotation.polygons {
+                let vertices = polygon.iter().zip(polygon.iter().cycle().skip(1));
+                for (&(x0, y0), &(x1, y1)) in vertices {
+                    image.draw(Line {
+                        start: (to_pixel(x0), to_pixel(y0)),
+                        end: (to_pixel(x1), to_pixel(y1)),
+                        color,
+                        thickness: self.thickness,
+                    })?;
+                }
+            }
+            image.draw(AABB {
+                position,
+                size: (to_pixel(bbox.width), to_pixel(bbox.height)),
+                color,
+                filled: false,
+                thickness: self.thickness,
+            })?;
+            for keypoint in &annotation.keypoints {
+                if keypoint.visibility == Visibility::Visible {
+                    image.draw(Circle {
+                        position: (to_pixel(keypoint.x), to_pixel(keypoint.y)),
+                        color,
+                        radius: self.thickness + 1,
+                        filled: true,
+                        thickness: 1,
+                    })?;
+                }
+            }
+
+            if self.labels {
+                // Black text on a tag in the category color, above the box if there is room
+                let (width, height) = Text::<P>::measure(&annotation.category, 1);
+                let top = position.1.checked_sub(height + 2 + self.thickness as usize);
+                let tag = (
+                    position.0.saturating_sub(self.thickness as usize),
+                    top.unwrap_or(position.1),
+                );
+                image.draw(AABB {
+                    position: tag,
+                    size: (width + 2, height + 2),
+                    color,
+                    filled: true,
+                    thickness: 0,
+                })?;
+                image.draw(Text {
+                    position: (tag.0 + 1, tag.1 + 1),
+                    text: annotation.category.clone(),
+                    color: P::from_rgba8([0, 0, 0, 255]),
+                    scale: 1,
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
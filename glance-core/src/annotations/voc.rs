@@ -0,0 +1,132 @@
+//! Pascal VOC XML: one file per image with its size and a `<object>` element per object.
+//! Boxes are given by `xmin`, `ymin`, `xmax` and `ymax`, 1-based and inclusive, so a box starting
+//! in the first pixel column has `xmin` 1. VOC has no polygons or keypoints; they are not written.
+
+use std::io::{BufReader, Read, Write};
+
+use xml::reader::{EventReader, XmlEvent as ReadEvent};
+use xml::writer::{EmitterConfig, EventWriter, XmlEvent as WriteEvent};
+
+use super::{Annotation, BoundingBox, ImageAnnotations};
+use crate::{CoreError, Result};
+
+fn invalid(e: impl std::fmt::Display) -> CoreError {
+    CoreError::InvalidData(format!("invalid VOC annotation: {e}"))
+}
+
+fn parse<T: std::str::FromStr>(value: &str, name: &str) -> Result<T> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| invalid(format!("{name} is '{value}'")))
+}
+
+/// Box corners as read from the file, in VOC's 1-based inclusive coordinates.
+#[derive(Default)]
+struct Corners {
+    min: (f32, f32),
+    max: (f32, f32),
+}
+
+/// Reads a VOC annotation file.
+pub fn read(reader: impl Read) -> Result<ImageAnnotations> {
+    let mut image = ImageAnnotations::default();
+    let mut path: Vec<String> = Vec::new();
+    let mut corners = Corners::default();
+
+    for event in EventReader::new(BufReader::new(reader)) {
+        match event.map_err(invalid)? {
+            ReadEvent::StartElement { name, .. } => {
+                if name.local_name == "object" {
+                    image.annotations.push(Annotation::default());
+                    corners = Corners::default();
+                }
+                path.push(name.local_name);
+            }
+            ReadEvent::EndElement { .. } => {
+                if path.pop().as_deref() == Some("bndbox")
+                    && let Some(object) = image.annotations.last_mut()
+                {
+                    object.bbox = BoundingBox::new(
+                        corners.min.0 - 1.0,
+                        corners.min.1 - 1.0,
+                        corners.max.0 - corners.min.0 + 1.0,
+                        corners.max.1 - corners.min.1 + 1.0,
+                    );
+                }
+            }
+            ReadEvent::Characters(text) => {
+                let names: Vec<&str> = path.iter().map(String::as_str).collect();
+                match names[..] {
+                    ["annotation", "filename"] => image.file_name = text,
+                    ["annotation", "size", "width"] => image.width = parse(&text, "width")?,
+                    ["annotation", "size", "height"] => image.height = parse(&text, "height")?,
+                    ["annotation", "object", "name"] => {
+                        if let Some(object) = image.annotations.last_mut() {
+                            object.category = text.trim().to_string();
+                        }
+                    }
+                    ["annotation", "object", "bndbox", corner] => {
+                        let value = parse(&text, corner)?;
+                        match corner {
+                            "xmin" => corners.min.0 = value,
+                            "ymin" => corners.min.1 = value,
+                            "xmax" => corners.max.0 = value,
+                            "ymax" => corners.max.1 = value,
+                            _ => {}
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(image)
+}
+
+fn open<W: Write>(xml: &mut EventWriter<W>, name: &str) -> Result<()> {
+    xml.write(WriteEvent::start_element(name)).map_err(invalid)
+}
+
+fn close<W: Write>(xml: &mut EventWriter<W>) -> Result<()> {
+    xml.write(WriteEvent::end_element()).map_err(invalid)
+}
+
+/// Writes an element containing only text.
+fn leaf<W: Write>(xml: &mut EventWriter<W>, name: &str, value: impl ToString) -> Result<()> {
+    open(xml, name)?;
+    xml.write(WriteEvent::characters(&value.to_string()))
+        .map_err(invalid)?;
+    close(xml)
+}
+
+/// Writes a VOC annotation file for an RGB image.
+pub fn write(image: &ImageAnnotations, writer: impl Write) -> Result<()> {
+    let mut xml = EmitterConfig::new()
+        .perform_indent(true)
+        .create_writer(writer);
+
+    open(&mut xml, "annotation")?;
+    leaf(&mut xml, "filename", &image.file_name)?;
+    open(&mut xml, "size")?;
+    leaf(&mut xml, "width", image.width)?;
+    leaf(&mut xml, "height", image.height)?;
+    leaf(&mut xml, "depth", 3)?;
+    close(&mut xml)?;
+    for annotation in &image.annotations {
+        let b = annotation.bbox;
+        open(&mut xml, "object")?;
+        leaf(&mut xml, "name", &annotation.category)?;
+        leaf(&mut xml, "truncated", 0)?;
+        leaf(&mut xml, "difficult", 0)?;
+        open(&mut xml, "bndbox")?;
+        leaf(&mut xml, "xmin", b.x + 1.0)?;
+        leaf(&mut xml, "ymin", b.y + 1.0)?;
+        leaf(&mut xml, "xmax", b.x + b.width)?;
+        leaf(&mut xml, "ymax", b.y + b.height)?;
+        close(&mut xml)?;
+        close(&mut xml)?;
+    }
+    close(&mut xml)
+}
@@ -0,0 +1,112 @@
+//! YOLO txt labels: one file per image with a line per object. Detection lines are
+//! `class cx cy width height`, segmentation lines are `class x0 y0 x1 y1 ...`, with all values
+//! normalized to [0, 1] by the image size. Class ids index the category list, usually stored one
+//! name per line in `classes.txt`.
+
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+
+use super::{Annotation, BoundingBox, ImageAnnotations};
+use crate::{CoreError, Result};
+
+fn invalid(line: usize, message: impl std::fmt::Display) -> CoreError {
+    CoreError::InvalidData(format!("invalid YOLO label on line {line}: {message}"))
+}
+
+/// Reads category names, one per line, skipping empty lines.
+pub fn read_names(reader: impl Read) -> Result<Vec<String>> {
+    let mut names = Vec::new();
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            names.push(line.trim().to_string());
+        }
+    }
+    Ok(names)
+}
+
+/// Reads the labels of an image of the given size. Class ids are looked up in `categories`.
+pub fn read(
+    reader: impl Read,
+    categories: &[String],
+    width: usize,
+    height: usize,
+) -> Result<Vec<Annotation>> {
+    let (w, h) = (width as f32, height as f32);
+    let mut annotations = Vec::new();
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        let mut fields = line.split_whitespace();
+        let Some(class) = fields.next() else {
+            continue;
+        };
+        let category = class
+            .parse::<usize>()
+            .ok()
+            .and_then(|id| categories.get(id))
+            .ok_or_else(|| invalid(i + 1, format!("unknown class '{class}'")))?;
+        let values = fields
+            .map(|v| v.parse::<f32>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| invalid(i + 1, e))?;
+
+        let annotation = match values[..] {
+            [cx, cy, bw, bh] => Annotation {
+                category: category.clone(),
+                bbox: BoundingBox::new((cx - bw / 2.0) * w, (cy - bh / 2.0) * h, bw * w, bh * h),
+                ..Default::default()
+            },
+            _ if values.len() >= 6 && values.len().is_multiple_of(2) => {
+                let polygon: Vec<(f32, f32)> = values
+                    .chunks_exact(2)
+                    .map(|p| (p[0] * w, p[1] * h))
+                    .collect();
+                Annotation {
+                    category: category.clone(),
+                    bbox: BoundingBox::enclosing(&polygon).unwrap_or_default(),
+                    polygons: vec![polygon],
+                    keypoints: Vec::new(),
+                }
+            }
+            _ => return Err(invalid(i + 1, format!("{} values", values.len()))),
+        };
+        annotations.push(annotation);
+    }
+    Ok(annotations)
+}
+
+/// Writes the labels of an image. Annotations with a polygon are written as segmentation lines
+/// with their first polygon, all others as detection lines.
+pub fn write(image: &ImageAnnotations, categories: &[String], writer: impl Write) -> Result<()> {
+    let (w, h) = (image.width as f32, image.height as f32);
+    let mut writer = BufWriter::new(writer);
+    for annotation in &image.annotations {
+        let class = categories
+            .iter()
+            .position(|c| *c == annotation.category)
+            .ok_or_else(|| {
+                CoreError::InvalidData(format!("unknown category '{}'", annotation.category))
+            })?;
+        write!(writer, "{class}")?;
+        match annotation.polygons.first() {
+            Some(polygon) => {
+                for &(x, y) in polygon {
+                    write!(writer, " {:.6} {:.6}", x / w, y / h)?;
+                }
+            }
+            None => {
+                let b = annotation.bbox;
+                write!(
+                    writer,
+                    " {:.6} {:.6} {:.6} {:.6}",
+                    (b.x + b.width / 2.0) / w,
+                    (b.y + b.height / 2.0) / h,
+                    b.width / w,
+                    b.height / h
+                )?;
+            }
+        }
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,50 @@
+//! Named color constants, e.g. for drawing and annotations.
+//!
+//! ```
+//! use glance_core::colors;
+//! use glance_core::img::{Image, pixel::Rgba};
+//!
+//! let mut canvas = Image::solid(64, 64, colors::WHITE);
+//! canvas.set_pixel((32, 32), colors::RED).unwrap();
+//! assert_eq!(colors::label(3), colors::CATEGORICAL[3]);
+//! ```
+
+use crate::img::pixel::Rgba;
+
+/// Builds an opaque color from 8 bit channels.
+const fn rgb8(r: u8, g: u8, b: u8) -> Rgba {
+    Rgba::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0)
+}
+
+pub const BLACK: Rgba = Rgba::new(0.0, 0.0, 0.0, 1.0);
+pub const WHITE: Rgba = Rgba::new(1.0, 1.0, 1.0, 1.0);
+pub const GRAY: Rgba = Rgba::new(0.5, 0.5, 0.5, 1.0);
+pub const RED: Rgba = Rgba::new(1.0, 0.0, 0.0, 1.0);
+pub const GREEN: Rgba = Rgba::new(0.0, 1.0, 0.0, 1.0);
+pub const BLUE: Rgba = Rgba::new(0.0, 0.0, 1.0, 1.0);
+pub const YELLOW: Rgba = Rgba::new(1.0, 1.0, 0.0, 1.0);
+pub const CYAN: Rgba = Rgba::new(0.0, 1.0, 1.0, 1.0);
+pub const MAGENTA: Rgba = Rgba::new(1.0, 0.0, 1.0, 1.0);
+pub const ORANGE: Rgba = rgb8(255, 136, 0);
+/// Fully transparent black.
+pub const TRANSPARENT: Rgba = Rgba::new(0.0, 0.0, 0.0, 0.0);
+
+/// Bright, easily told apart colors for labeling classes or instances, see [`label`].
+pub const CATEGORICAL: [Rgba; 10] = [
+    rgb8(255, 56, 56),
+    rgb8(255, 157, 151),
+    rgb8(255, 112, 31),
+    rgb8(255, 178, 29),
+    rgb8(207, 210, 49),
+    rgb8(72, 249, 10),
+    rgb8(26, 147, 52),
+    rgb8(0, 212, 187),
+    rgb8(52, 171, 255),
+    rgb8(203, 56, 255),
+];
+
+/// Returns the [`CATEGORICAL`] color of label `index`, repeating the palette for larger
+/// indices.
+pub fn label(index: usize) -> Rgba {
+    CATEGORICAL[index % CATEGORICAL.len()]
+}
@@ -1,2 +1,3 @@
 pub mod shapes;
+pub mod text;
 pub mod traits;
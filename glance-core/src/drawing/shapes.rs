@@ -65,6 +65,80 @@ where
     }
 }
 
+/// An anti-aliased ellipse that can be drawn onto an image, optionally rotated. Circles are
+/// ellipses with equal radii. Pixels are blended with the image by the fraction of their area
+/// covered by the shape, estimated from their distance to its edge, so edges are smooth where
+/// [`Circle`] steps.
+pub struct Ellipse<P: Pixel> {
+    /// Center (x, y), where pixel centers lie on integer coordinates
+    pub center: (f32, f32),
+    /// Radii along the x and y axes of the ellipse (before rotation)
+    pub radii: (f32, f32),
+    /// Rotation in degrees, counter-clockwise on screen
+    pub rotation: f32,
+    /// Color as a struct that implements Pixel (like [`Rgba`], [`Luma`]), interpolated with the
+    /// image by coverage, including alpha
+    pub color: P,
+    /// Fill the shape (true) or draw outline (false)
+    pub filled: bool,
+    /// Outline thickness, centered on the edge (only used when `filled = false`)
+    pub thickness: f32,
+}
+
+impl<P: Pixel> Ellipse<P> {
+    /// Returns the approximate signed distance of (x, y) to the edge, negative inside.
+    fn edge_distance(&self, x: f32, y: f32) -> f32 {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let (dx, dy) = (x - self.center.0, y - self.center.1);
+        // Rotate into the frame of the ellipse, where y points down
+        let (u, v) = (cos * dx - sin * dy, sin * dx + cos * dy);
+        let (a, b) = (self.radii.0.max(1e-3), self.radii.1.max(1e-3));
+        let k = (u / a).hypot(v / b);
+        let gradient = (u / (a * a)).hypot(v / (b * b));
+        if gradient == 0.0 {
+            return -a.min(b);
+        }
+        // First order distance to the level set k = 1, exact for circles
+        (k - 1.0) * k / gradient
+    }
+}
+
+impl<P> Drawable<P> for Ellipse<P>
+where
+    P: Pixel,
+{
+    fn draw_on(&self, image: &mut Image<P>) -> Result<()> {
+        let (width, height) = image.dimensions();
+        let half_thickness = if self.filled {
+            0.0
+        } else {
+            self.thickness.max(0.0) / 2.0
+        };
+        let reach = self.radii.0.max(self.radii.1) + half_thickness + 1.0;
+        let x_range = (self.center.0 - reach).floor().max(0.0) as usize
+            ..((self.center.0 + reach).ceil().max(0.0) as usize + 1).min(width);
+        let y_range = (self.center.1 - reach).floor().max(0.0) as usize
+            ..((self.center.1 + reach).ceil().max(0.0) as usize + 1).min(height);
+
+        for y in y_range {
+            for x in x_range.clone() {
+                let distance = self.edge_distance(x as f32, y as f32);
+                let coverage = match self.filled {
+                    true => (0.5 - distance).clamp(0.0, 1.0),
+                    false => (0.5 + half_thickness - distance.abs())
+                        .clamp(0.0, 1.0)
+                        .min(self.thickness),
+                };
+                if coverage > 0.0 {
+                    let blended = image.get_pixel((x, y))?.lerp(&self.color, coverage);
+                    image.set_pixel((x, y), blended)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 /// An axis aligned bounding box that can be drawn onto an image.
 /// Can be either filled or drawn as an outline with a specified thickness.
 /// The color is specified in RGBA8 format.
@@ -209,3 +283,51 @@ where
         Ok(())
     }
 }
+
+/// An arrow from `start` to `end`, drawn as a [`Line`] with two short lines forming the head.
+/// The color is specified in RGBA8 format.
+pub struct Arrow<P: Pixel> {
+    /// Start point (x, y)
+    pub start: (usize, usize),
+    /// End point (x, y), where the head is
+    pub end: (usize, usize),
+    /// Color in RGBA8 format
+    pub color: P,
+    /// Line thickness
+    pub thickness: u32,
+    /// Length of the head lines relative to the arrow length
+    pub tip_length: f32,
+}
+
+impl<P> Drawable<P> for Arrow<P>
+where
+    P: Pixel,
+{
+    fn draw_on(&self, image: &mut Image<P>) -> Result<()> {
+        let line = |start, end| Line {
+            start,
+            end,
+            color: self.color,
+            thickness: self.thickness,
+        };
+        line(self.start, self.end).draw_on(image)?;
+
+        let (x0, y0) = (self.start.0 as f32, self.start.1 as f32);
+        let (x1, y1) = (self.end.0 as f32, self.end.1 as f32);
+        let length = (x1 - x0).hypot(y1 - y0);
+        if length == 0.0 {
+            return Ok(());
+        }
+
+        // Head lines point back from the tip at 30 degrees to either side of the shaft
+        let tip = length * self.tip_length;
+        let angle = (y0 - y1).atan2(x0 - x1);
+        for side in [-1.0f32, 1.0] {
+            let (sin, cos) = (angle + side * std::f32::consts::FRAC_PI_6).sin_cos();
+            let x = (x1 + tip * cos).round().max(0.0) as usize;
+            let y = (y1 + tip * sin).round().max(0.0) as usize;
+            line(self.end, (x, y)).draw_on(image)?;
+        }
+        Ok(())
+    }
+}
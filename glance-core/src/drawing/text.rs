@@ -0,0 +1,114 @@
+use super::traits::Drawable;
+use crate::{
+    Result,
+    img::{Image, pixel::Pixel},
+};
+
+/// Width of a glyph in font pixels
+const GLYPH_WIDTH: usize = 5;
+/// Height of a glyph in font pixels
+const GLYPH_HEIGHT: usize = 7;
+/// Horizontal and vertical space taken by a glyph, including spacing
+const CELL: (usize, usize) = (GLYPH_WIDTH + 1, GLYPH_HEIGHT + 1);
+
+/// Classic 5x7 bitmap font for the printable ASCII range (0x20..=0x7E). Each glyph is stored as
+/// five columns, where bit 0 is the top row.
+#[rustfmt::skip]
+const FONT: [[u8; GLYPH_WIDTH]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x5F, 0x00, 0x00], [0x00, 0x07, 0x00, 0x07, 0x00],
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], [0x24, 0x2A, 0x7F, 0x2A, 0x12], [0x23, 0x13, 0x08, 0x64, 0x62],
+    [0x36, 0x49, 0x55, 0x22, 0x50], [0x00, 0x05, 0x03, 0x00, 0x00], [0x00, 0x1C, 0x22, 0x41, 0x00],
+    [0x00, 0x41, 0x22, 0x1C, 0x00], [0x08, 0x2A, 0x1C, 0x2A, 0x08], [0x08, 0x08, 0x3E, 0x08, 0x08],
+    [0x00, 0x50, 0x30, 0x00, 0x00], [0x08, 0x08, 0x08, 0x08, 0x08], [0x00, 0x60, 0x60, 0x00, 0x00],
+    [0x20, 0x10, 0x08, 0x04, 0x02], [0x3E, 0x51, 0x49, 0x45, 0x3E], [0x00, 0x42, 0x7F, 0x40, 0x00],
+    [0x42, 0x61, 0x51, 0x49, 0x46], [0x21, 0x41, 0x45, 0x4B, 0x31], [0x18, 0x14, 0x12, 0x7F, 0x10],
+    [0x27, 0x45, 0x45, 0x45, 0x39], [0x3C, 0x4A, 0x49, 0x49, 0x30], [0x01, 0x71, 0x09, 0x05, 0x03],
+    [0x36, 0x49, 0x49, 0x49, 0x36], [0x06, 0x49, 0x49, 0x29, 0x1E], [0x00, 0x36, 0x36, 0x00, 0x00],
+    [0x00, 0x56, 0x36, 0x00, 0x00], [0x08, 0x14, 0x22, 0x41, 0x00], [0x14, 0x14, 0x14, 0x14, 0x14],
+    [0x00, 0x41, 0x22, 0x14, 0x08], [0x02, 0x01, 0x51, 0x09, 0x06], [0x32, 0x49, 0x79, 0x41, 0x3E],
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], [0x7F, 0x49, 0x49, 0x49, 0x36], [0x3E, 0x41, 0x41, 0x41, 0x22],
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], [0x7F, 0x49, 0x49, 0x49, 0x41], [0x7F, 0x09, 0x09, 0x09, 0x01],
+    [0x3E, 0x41, 0x49, 0x49, 0x7A], [0x7F, 0x08, 0x08, 0x08, 0x7F], [0x00, 0x41, 0x7F, 0x41, 0x00],
+    [0x20, 0x40, 0x41, 0x3F, 0x01], [0x7F, 0x08, 0x14, 0x22, 0x41], [0x7F, 0x40, 0x40, 0x40, 0x40],
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F], [0x7F, 0x04, 0x08, 0x10, 0x7F], [0x3E, 0x41, 0x41, 0x41, 0x3E],
+    [0x7F, 0x09, 0x09, 0x09, 0x06], [0x3E, 0x41, 0x51, 0x21, 0x5E], [0x7F, 0x09, 0x19, 0x29, 0x46],
+    [0x26, 0x49, 0x49, 0x49, 0x32], [0x01, 0x01, 0x7F, 0x01, 0x01], [0x3F, 0x40, 0x40, 0x40, 0x3F],
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], [0x3F, 0x40, 0x38, 0x40, 0x3F], [0x63, 0x14, 0x08, 0x14, 0x63],
+    [0x07, 0x08, 0x70, 0x08, 0x07], [0x61, 0x51, 0x49, 0x45, 0x43], [0x00, 0x7F, 0x41, 0x41, 0x00],
+    [0x02, 0x04, 0x08, 0x10, 0x20], [0x00, 0x41, 0x41, 0x7F, 0x00], [0x04, 0x02, 0x01, 0x02, 0x04],
+    [0x40, 0x40, 0x40, 0x40, 0x40], [0x00, 0x01, 0x02, 0x04, 0x00], [0x20, 0x54, 0x54, 0x54, 0x78],
+    [0x7F, 0x48, 0x44, 0x44, 0x38], [0x38, 0x44, 0x44, 0x44, 0x20], [0x38, 0x44, 0x44, 0x48, 0x7F],
+    [0x38, 0x54, 0x54, 0x54, 0x18], [0x08, 0x7E, 0x09, 0x01, 0x02], [0x0C, 0x52, 0x52, 0x52, 0x3E],
+    [0x7F, 0x08, 0x04, 0x04, 0x78], [0x00, 0x44, 0x7D, 0x40, 0x00], [0x20, 0x40, 0x44, 0x3D, 0x00],
+    [0x7F, 0x10, 0x28, 0x44, 0x00], [0x00, 0x41, 0x7F, 0x40, 0x00], [0x7C, 0x04, 0x18, 0x04, 0x78],
+    [0x7C, 0x08, 0x04, 0x04, 0x78], [0x38, 0x44, 0x44, 0x44, 0x38], [0x7C, 0x14, 0x14, 0x14, 0x08],
+    [0x08, 0x14, 0x14, 0x18, 0x7C], [0x7C, 0x08, 0x04, 0x04, 0x08], [0x48, 0x54, 0x54, 0x54, 0x20],
+    [0x04, 0x3F, 0x44, 0x40, 0x20], [0x3C, 0x40, 0x40, 0x20, 0x7C], [0x1C, 0x20, 0x40, 0x20, 0x1C],
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], [0x44, 0x28, 0x10, 0x28, 0x44], [0x0C, 0x50, 0x50, 0x50, 0x3C],
+    [0x44, 0x64, 0x54, 0x4C, 0x44], [0x00, 0x08, 0x36, 0x41, 0x00], [0x00, 0x00, 0x7F, 0x00, 0x00],
+    [0x00, 0x41, 0x36, 0x08, 0x00], [0x08, 0x04, 0x08, 0x10, 0x08],
+];
+
+/// Text rendered with a built-in 5x7 pixel bitmap font. Only printable ASCII is supported;
+/// other characters are drawn as `?`. Lines are separated by `\n`.
+pub struct Text<P: Pixel> {
+    /// Top-left corner of the first character (x, y)
+    pub position: (usize, usize),
+    /// The text to render
+    pub text: String,
+    /// Color as a struct that implements Pixel (like [`Rgba`], [`Luma`])
+    pub color: P,
+    /// Integer scale factor, each font pixel becomes a `scale` x `scale` square
+    pub scale: u32,
+}
+
+impl<P: Pixel> Text<P> {
+    /// Returns the size (width, height) in pixels the given text occupies at the given scale.
+    pub fn measure(text: &str, scale: u32) -> (usize, usize) {
+        let scale = scale.max(1) as usize;
+        let columns = text.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+        let rows = text.lines().count();
+        (columns * CELL.0 * scale, rows * CELL.1 * scale)
+    }
+}
+
+impl<P> Drawable<P> for Text<P>
+where
+    P: Pixel,
+{
+    fn draw_on(&self, image: &mut Image<P>) -> Result<()> {
+        let scale = self.scale.max(1) as usize;
+        let dims = image.dimensions();
+
+        for (row, line) in self.text.lines().enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                let glyph = match c {
+                    ' '..='~' => FONT[c as usize - 0x20],
+                    _ => FONT['?' as usize - 0x20],
+                };
+                let origin_x = self.position.0 + column * CELL.0 * scale;
+                let origin_y = self.position.1 + row * CELL.1 * scale;
+
+                for (gx, bits) in glyph.iter().enumerate() {
+                    for gy in 0..GLYPH_HEIGHT {
+                        if bits & (1 << gy) == 0 {
+                            continue;
+                        }
+                        for sy in 0..scale {
+                            for sx in 0..scale {
+                                let nx = origin_x + gx * scale + sx;
+                                let ny = origin_y + gy * scale + sy;
+                                // Skip out of bounds pixels
+                                if nx < dims.0 && ny < dims.1 {
+                                    image.set_pixel((nx, ny), self.color)?;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -9,6 +9,7 @@ pub enum CoreError {
     #[from]
     Image(image::ImageError),
 
+    #[cfg(feature = "display")]
     #[from]
     Minifb(minifb::Error),
 
@@ -19,6 +20,7 @@ pub enum CoreError {
 
     InvalidCast(String),
     InvalidData(String),
+    Unsupported(String),
 }
 
 impl core::fmt::Display for CoreError {
@@ -0,0 +1,144 @@
+//! Arithmetic operators between images of the same size and between an image and a scalar.
+//!
+//! The operators work on the color channels of every pixel, and pixels keep the alpha of the
+//! left operand. Values are not clamped, so intermediate results such as signed differences
+//! survive until the image is clamped with [`Image::clamp`] or saturated when converted to 8
+//! bits. The `saturating_*` methods, e.g. [`Image::saturating_add`], clamp the result to
+//! [0.0, 1.0] right away instead, like the arithmetic of 8 bit images in other libraries.
+//! Division by zero gives zero instead of infinity or NaN, e.g. for dead pixels of a flat
+//! field.
+
+use super::{Image, pixel::Pixel};
+use crate::par::*;
+use crate::{CoreError, Result};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
+
+fn divide(a: f32, b: f32) -> f32 {
+    match b == 0.0 {
+        true => 0.0,
+        false => a / b,
+    }
+}
+
+impl<P: Pixel> Image<P> {
+    /// Combines the color channels of every pixel with those of the pixel at the same position
+    /// in `other`. Panics if the dimensions differ.
+    fn combine(&mut self, other: &Image<P>, f: impl Fn(f32, f32) -> f32 + Sync) {
+        assert_eq!(
+            self.dimensions(),
+            other.dimensions(),
+            "Image arithmetic needs images of the same dimensions"
+        );
+        let alpha = P::alpha_channel();
+        self.data
+            .par_iter_mut()
+            .zip(other.data.par_iter())
+            .for_each(|(a, b)| {
+                for c in (0..P::channel_count()).filter(|&c| Some(c) != alpha) {
+                    a.set_channel(c, f(a.channel(c), b.channel(c)));
+                }
+            });
+    }
+
+    /// Clamps every channel, including alpha, to `range` = (low, high), e.g. (0.0, 1.0) after
+    /// arithmetic that left the valid range.
+    pub fn clamp(self, range: (f32, f32)) -> Self {
+        self.apply_with_alpha(|v| v.clamp(range.0, range.1), true)
+    }
+
+    /// Adds `other` like `+`, clamping the color channels to [0.0, 1.0]. Returns an error if
+    /// the dimensions differ.
+    pub fn saturating_add(self, other: &Image<P>) -> Result<Self> {
+        self.saturating(other, |a, b| a + b)
+    }
+
+    /// Subtracts `other` like `-`, clamping the color channels to [0.0, 1.0]. Returns an error
+    /// if the dimensions differ.
+    pub fn saturating_sub(self, other: &Image<P>) -> Result<Self> {
+        self.saturating(other, |a, b| a - b)
+    }
+
+    /// Multiplies by `other` like `*`, clamping the color channels to [0.0, 1.0]. Returns an
+    /// error if the dimensions differ.
+    pub fn saturating_mul(self, other: &Image<P>) -> Result<Self> {
+        self.saturating(other, |a, b| a * b)
+    }
+
+    /// Divides by `other` like `/`, clamping the color channels to [0.0, 1.0]. Returns an error
+    /// if the dimensions differ.
+    pub fn saturating_div(self, other: &Image<P>) -> Result<Self> {
+        self.saturating(other, divide)
+    }
+
+    fn saturating(mut self, other: &Image<P>, f: impl Fn(f32, f32) -> f32 + Sync) -> Result<Self> {
+        if self.dimensions() != other.dimensions() {
+            return Err(CoreError::InvalidData(format!(
+                "Image arithmetic needs images of the same dimensions, got {:?} and {:?}",
+                self.dimensions(),
+                other.dimensions()
+            )));
+        }
+        self.combine(other, |a, b| f(a, b).clamp(0.0, 1.0));
+        Ok(self)
+    }
+}
+
+macro_rules! image_operator {
+    ($trait:ident, $method:ident, $assign_trait:ident, $assign_method:ident, $op:expr) => {
+        /// Panics if the dimensions of the images differ.
+        impl<P: Pixel> $assign_trait<&Image<P>> for Image<P> {
+            fn $assign_method(&mut self, other: &Image<P>) {
+                self.combine(other, $op);
+            }
+        }
+
+        impl<P: Pixel> $assign_trait<f32> for Image<P> {
+            fn $assign_method(&mut self, scalar: f32) {
+                let op = $op;
+                self.par_pixels_mut()
+                    .for_each(|pixel| *pixel = pixel.map_colors(|v| op(v, scalar)));
+            }
+        }
+
+        /// Panics if the dimensions of the images differ.
+        impl<P: Pixel> $trait<&Image<P>> for Image<P> {
+            type Output = Image<P>;
+
+            fn $method(mut self, other: &Image<P>) -> Image<P> {
+                self.$assign_method(other);
+                self
+            }
+        }
+
+        /// Panics if the dimensions of the images differ.
+        impl<P: Pixel> $trait<&Image<P>> for &Image<P> {
+            type Output = Image<P>;
+
+            fn $method(self, other: &Image<P>) -> Image<P> {
+                self.clone().$method(other)
+            }
+        }
+
+        impl<P: Pixel> $trait<f32> for Image<P> {
+            type Output = Image<P>;
+
+            fn $method(mut self, scalar: f32) -> Image<P> {
+                self.$assign_method(scalar);
+                self
+            }
+        }
+
+        impl<P: Pixel> $trait<f32> for &Image<P> {
+            type Output = Image<P>;
+
+            fn $method(self, scalar: f32) -> Image<P> {
+                self.clone().$method(scalar)
+            }
+        }
+    };
+}
+
+image_operator!(Add, add, AddAssign, add_assign, |a: f32, b: f32| a + b);
+image_operator!(Sub, sub, SubAssign, sub_assign, |a: f32, b: f32| a - b);
+image_operator!(Mul, mul, MulAssign, mul_assign, |a: f32, b: f32| a * b);
+image_operator!(Div, div, DivAssign, div_assign, divide);
@@ -1,5 +1,5 @@
 use super::{Image, pixel::Pixel};
-use rayon::prelude::*;
+use crate::par::{self, *};
 
 pub struct PixelIter<'a, P: Pixel> {
     iter: std::slice::Iter<'a, P>,
@@ -45,19 +45,19 @@ impl<P> Image<P>
 where
     P: Pixel,
 {
-    pub fn pixels(&self) -> PixelIter<P> {
+    pub fn pixels(&self) -> PixelIter<'_, P> {
         PixelIter::new(self)
     }
 
-    pub fn pixels_mut(&mut self) -> PixelIterMut<P> {
+    pub fn pixels_mut(&mut self) -> PixelIterMut<'_, P> {
         PixelIterMut::new(self)
     }
 
-    pub fn par_pixels(&self) -> rayon::slice::Iter<'_, P> {
+    pub fn par_pixels(&self) -> par::SliceIter<'_, P> {
         self.data.par_iter()
     }
 
-    pub fn par_pixels_mut(&mut self) -> rayon::slice::IterMut<'_, P> {
+    pub fn par_pixels_mut(&mut self) -> par::SliceIterMut<'_, P> {
         self.data.par_iter_mut()
     }
 }
@@ -0,0 +1,368 @@
+//! EXIF and XMP metadata of image files. [`Metadata::read`] collects the metadata of a file, the
+//! setters edit individual EXIF fields, and [`super::SaveOptions::metadata`] writes it into JPEG
+//! and PNG files on save.
+
+use super::OpenOptions;
+use crate::{CoreError, Result};
+use exif::{Field, In, Rational, Tag, Value};
+use image::metadata::Orientation as ImageOrientation;
+use image::{ImageDecoder, ImageFormat, ImageReader};
+use std::io::Cursor;
+use std::path::Path;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+const JPEG_EXIF_HEADER: &[u8] = b"Exif\0\0";
+const JPEG_XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp\0";
+
+/// Metadata stored alongside the pixels of an image file.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Metadata {
+    /// EXIF fields in their TIFF encoding, starting with the byte order mark
+    pub exif: Option<Vec<u8>>,
+    /// XMP packet
+    pub xmp: Option<String>,
+}
+
+/// Text fields of the EXIF metadata, see [`Metadata::set_text`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextTag {
+    ImageDescription,
+    Artist,
+    Copyright,
+    Software,
+}
+
+impl TextTag {
+    fn tag(&self) -> Tag {
+        match self {
+            TextTag::ImageDescription => Tag::ImageDescription,
+            TextTag::Artist => Tag::Artist,
+            TextTag::Copyright => Tag::Copyright,
+            TextTag::Software => Tag::Software,
+        }
+    }
+}
+
+/// A position on Earth, as stored in the GPS fields of the EXIF metadata.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsPosition {
+    /// Degrees north of the equator, negative to the south
+    pub latitude: f64,
+    /// Degrees east of the prime meridian, negative to the west
+    pub longitude: f64,
+    /// Meters above sea level, negative below
+    pub altitude: Option<f64>,
+}
+
+impl Metadata {
+    /// Reads the EXIF and XMP metadata of the file at `path`, for saving with the image opened by
+    /// [`super::Image::open`]. As `open` turns the image upright, an EXIF orientation it applied
+    /// is reset to normal.
+    pub fn read<Pth: AsRef<Path>>(path: Pth) -> Result<Self> {
+        Self::read_with(path, OpenOptions::default())
+    }
+
+    /// Reads the metadata of the file at `path` like [`Metadata::read`], for the image opened by
+    /// [`super::Image::open_with`] with the same options.
+    pub fn read_with<Pth: AsRef<Path>>(path: Pth, options: OpenOptions) -> Result<Self> {
+        let bytes = std::fs::read(&path)?;
+        // Corrupt metadata is dropped rather than failing, like the decoders do
+        let exif = exif::Reader::new()
+            .read_from_container(&mut Cursor::new(&bytes))
+            .ok()
+            .map(|exif| exif.buf().to_vec());
+        let mut metadata = Metadata {
+            exif,
+            xmp: read_xmp(&bytes),
+        };
+
+        let oriented = options.auto_orient
+            && ImageReader::open(&path)?.into_decoder()?.orientation()?
+                != ImageOrientation::NoTransforms;
+        if oriented {
+            metadata.edit_exif(|fields| {
+                set_field(fields, Tag::Orientation, Value::Short(vec![1]));
+            })?;
+        }
+        Ok(metadata)
+    }
+
+    /// Returns whether there is no metadata to write.
+    pub fn is_empty(&self) -> bool {
+        self.exif.is_none() && self.xmp.is_none()
+    }
+
+    /// Returns the value of an EXIF text field.
+    pub fn text(&self, tag: TextTag) -> Option<String> {
+        match self.field(tag.tag())? {
+            Value::Ascii(values) => values
+                .first()
+                .map(|value| String::from_utf8_lossy(value).into_owned()),
+            _ => None,
+        }
+    }
+
+    /// Sets an EXIF text field, e.g. the software that wrote the file or its copyright notice.
+    pub fn set_text(&mut self, tag: TextTag, value: &str) -> Result<()> {
+        self.edit_exif(|fields| {
+            set_field(
+                fields,
+                tag.tag(),
+                Value::Ascii(vec![value.as_bytes().to_vec()]),
+            );
+        })
+    }
+
+    /// Returns the position stored in the EXIF GPS fields.
+    pub fn gps(&self) -> Option<GpsPosition> {
+        let coordinate = |tag, reference_tag, negative: &[u8]| {
+            let Value::Rational(parts) = self.field(tag)? else {
+                return None;
+            };
+            let degrees: f64 = parts
+                .iter()
+                .zip([1.0, 60.0, 3600.0])
+                .map(|(part, unit)| part.to_f64() / unit)
+                .sum();
+            match self.field(reference_tag) {
+                Some(Value::Ascii(reference)) if reference.first()? == negative => Some(-degrees),
+                _ => Some(degrees),
+            }
+        };
+        let altitude = match self.field(Tag::GPSAltitude) {
+            Some(Value::Rational(meters)) => {
+                let sign = match self.field(Tag::GPSAltitudeRef) {
+                    Some(Value::Byte(reference)) if reference.first() == Some(&1) => -1.0,
+                    _ => 1.0,
+                };
+                meters.first().map(|meters| sign * meters.to_f64())
+            }
+            _ => None,
+        };
+        Some(GpsPosition {
+            latitude: coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef, b"S")?,
+            longitude: coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef, b"W")?,
+            altitude,
+        })
+    }
+
+    /// Sets the EXIF GPS fields to `position`. Coordinates are stored to 1/1000 of an arc
+    /// second and the altitude to the centimeter.
+    pub fn set_gps(&mut self, position: GpsPosition) -> Result<()> {
+        self.edit_exif(|fields| {
+            let reference = |positive: &[u8], negative: &[u8], value: f64| {
+                Value::Ascii(vec![if value < 0.0 { negative } else { positive }.to_vec()])
+            };
+            set_field(fields, Tag::GPSVersionID, Value::Byte(vec![2, 3, 0, 0]));
+            set_field(
+                fields,
+                Tag::GPSLatitudeRef,
+                reference(b"N", b"S", position.latitude),
+            );
+            set_field(
+                fields,
+                Tag::GPSLatitude,
+                degrees_minutes_seconds(position.latitude),
+            );
+            set_field(
+                fields,
+                Tag::GPSLongitudeRef,
+                reference(b"E", b"W", position.longitude),
+            );
+            set_field(
+                fields,
+                Tag::GPSLongitude,
+                degrees_minutes_seconds(position.longitude),
+            );
+            fields.retain(|f| f.tag != Tag::GPSAltitudeRef && f.tag != Tag::GPSAltitude);
+            if let Some(altitude) = position.altitude {
+                let below = (altitude < 0.0) as u8;
+                let meters = Rational::from(((altitude.abs() * 100.0).round() as u32, 100));
+                set_field(fields, Tag::GPSAltitudeRef, Value::Byte(vec![below]));
+                set_field(fields, Tag::GPSAltitude, Value::Rational(vec![meters]));
+            }
+        })
+    }
+
+    /// Returns the value of an EXIF field of the primary image.
+    fn field(&self, tag: Tag) -> Option<Value> {
+        let exif = exif::Reader::new().read_raw(self.exif.clone()?).ok()?;
+        exif.get_field(tag, In::PRIMARY)
+            .map(|field| field.value.clone())
+    }
+
+    /// Rewrites the EXIF data with the fields changed by `edit`. The thumbnail and fields of
+    /// unknown types can't be written back and are dropped.
+    fn edit_exif(&mut self, edit: impl FnOnce(&mut Vec<Field>)) -> Result<()> {
+        let (mut fields, little_endian) = match &self.exif {
+            Some(exif) => exif::parse_exif(exif).map_err(exif_error)?,
+            None => (Vec::new(), true),
+        };
+        fields.retain(|f| f.ifd_num == In::PRIMARY && !matches!(f.value, Value::Unknown(..)));
+        edit(&mut fields);
+
+        let mut writer = exif::experimental::Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut buffer = Cursor::new(Vec::new());
+        writer
+            .write(&mut buffer, little_endian)
+            .map_err(exif_error)?;
+        self.exif = Some(buffer.into_inner());
+        Ok(())
+    }
+}
+
+fn exif_error(error: exif::Error) -> CoreError {
+    CoreError::InvalidData(format!("EXIF: {error}"))
+}
+
+/// Replaces the field `tag` of the primary image with `value`.
+fn set_field(fields: &mut Vec<Field>, tag: Tag, value: Value) {
+    fields.retain(|f| f.tag != tag);
+    fields.push(Field {
+        tag,
+        ifd_num: In::PRIMARY,
+        value,
+    });
+}
+
+/// Splits the magnitude of an angle into the degrees, minutes and seconds of a GPS coordinate.
+fn degrees_minutes_seconds(angle: f64) -> Value {
+    let milliseconds = (angle.abs() * 3_600_000.0).round() as u64;
+    let degrees = milliseconds / 3_600_000;
+    let minutes = milliseconds / 60_000 % 60;
+    let seconds = milliseconds % 60_000;
+    Value::Rational(vec![
+        Rational::from((degrees as u32, 1)),
+        Rational::from((minutes as u32, 1)),
+        Rational::from((seconds as u32, 1000)),
+    ])
+}
+
+/// Returns the XMP packet of a JPEG or PNG file.
+fn read_xmp(bytes: &[u8]) -> Option<String> {
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        jpeg_segments(bytes)
+            .filter(|&(marker, _)| marker == 0xE1)
+            .find_map(|(_, payload)| payload.strip_prefix(JPEG_XMP_HEADER))
+            .map(|xmp| String::from_utf8_lossy(xmp).into_owned())
+    } else if bytes.starts_with(PNG_SIGNATURE) {
+        png_chunks(bytes)
+            .filter(|&(kind, _)| kind == b"iTXt")
+            .find_map(|(_, data)| data.strip_prefix(PNG_XMP_KEYWORD))
+            .and_then(|text| {
+                // Compression flag and method, then the language tag and translated keyword
+                let (&[0, _], rest) = text.split_at_checked(2)? else {
+                    return None;
+                };
+                let mut parts = rest.splitn(3, |&b| b == 0);
+                let xmp = parts.nth(2)?;
+                Some(String::from_utf8_lossy(xmp).into_owned())
+            })
+    } else {
+        None
+    }
+}
+
+/// Iterates over the marker and payload of the JPEG segments before the image data.
+fn jpeg_segments(bytes: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    let mut position = 2;
+    std::iter::from_fn(move || {
+        let header = bytes.get(position..position + 4)?;
+        if header[0] != 0xFF || header[1] == 0xDA || header[1] == 0xD9 {
+            return None;
+        }
+        let length = u16::from_be_bytes([header[2], header[3]]) as usize;
+        let payload = bytes.get(position + 4..position + 2 + length.max(2))?;
+        position += 2 + length;
+        Some((header[1], payload))
+    })
+}
+
+/// Iterates over the type and data of the PNG chunks.
+fn png_chunks(bytes: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    let mut position = PNG_SIGNATURE.len();
+    std::iter::from_fn(move || {
+        let header = bytes.get(position..position + 8)?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        let data = bytes.get(position + 8..position + 8 + length)?;
+        position += 12 + length;
+        Some((&header[4..8], data))
+    })
+}
+
+/// Writes `metadata` into the encoded image `encoded`, a JPEG or PNG file.
+pub(super) fn embed(encoded: Vec<u8>, format: ImageFormat, metadata: &Metadata) -> Result<Vec<u8>> {
+    match format {
+        ImageFormat::Jpeg => embed_jpeg(encoded, metadata),
+        ImageFormat::Png => Ok(embed_png(encoded, metadata)),
+        _ => Err(CoreError::Unsupported(format!(
+            "Writing metadata to {format:?} files"
+        ))),
+    }
+}
+
+/// Inserts APP1 segments after the JFIF header of a JPEG file.
+fn embed_jpeg(encoded: Vec<u8>, metadata: &Metadata) -> Result<Vec<u8>> {
+    let mut segments = Vec::new();
+    let payloads = [
+        metadata
+            .exif
+            .as_deref()
+            .map(|exif| (JPEG_EXIF_HEADER, exif)),
+        metadata
+            .xmp
+            .as_deref()
+            .map(|xmp| (JPEG_XMP_HEADER, xmp.as_bytes())),
+    ];
+    for (header, data) in payloads.into_iter().flatten() {
+        let length = u16::try_from(2 + header.len() + data.len()).map_err(|_| {
+            CoreError::Unsupported("Metadata larger than a JPEG segment".to_string())
+        })?;
+        segments.extend_from_slice(&[0xFF, 0xE1]);
+        segments.extend_from_slice(&length.to_be_bytes());
+        segments.extend_from_slice(header);
+        segments.extend_from_slice(data);
+    }
+
+    let insert_at = 2 + jpeg_segments(&encoded)
+        .take_while(|&(marker, _)| marker == 0xE0)
+        .map(|(_, payload)| 4 + payload.len())
+        .sum::<usize>();
+    let mut bytes = encoded;
+    bytes.splice(insert_at..insert_at, segments);
+    Ok(bytes)
+}
+
+/// Inserts eXIf and iTXt chunks after the header chunk of a PNG file.
+fn embed_png(encoded: Vec<u8>, metadata: &Metadata) -> Vec<u8> {
+    let mut chunks = Vec::new();
+    let mut push_chunk = |kind: &[u8], data: &[u8]| {
+        chunks.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        chunks.extend_from_slice(kind);
+        chunks.extend_from_slice(data);
+        let mut crc = crc32fast::Hasher::new();
+        crc.update(kind);
+        crc.update(data);
+        chunks.extend_from_slice(&crc.finalize().to_be_bytes());
+    };
+    if let Some(exif) = &metadata.exif {
+        push_chunk(b"eXIf", exif);
+    }
+    if let Some(xmp) = &metadata.xmp {
+        // Uncompressed, without language tag and translated keyword
+        let data = [PNG_XMP_KEYWORD, &[0, 0, 0, 0], xmp.as_bytes()].concat();
+        push_chunk(b"iTXt", &data);
+    }
+
+    let insert_at = PNG_SIGNATURE.len()
+        + png_chunks(&encoded)
+            .next()
+            .map_or(0, |(_, data)| 12 + data.len());
+    let mut bytes = encoded;
+    bytes.splice(insert_at..insert_at, chunks);
+    bytes
+}
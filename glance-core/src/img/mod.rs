@@ -14,15 +14,110 @@
 //!     let _ = image.display("My Image");
 //! }
 //! ```
+mod arithmetic;
 pub mod iterators;
+mod metadata;
+mod orientation;
+mod palette;
+mod patterns;
 pub mod pixel;
+mod rect;
+mod view;
 
+use crate::par::*;
+use crate::profiling::OpSpan;
 use crate::{CoreError, Result, drawing::traits::Drawable};
-use image::{ImageBuffer, ImageReader, Rgba as ImageRgba};
+use image::metadata::Orientation as ImageOrientation;
+use image::{DynamicImage, ImageBuffer, ImageDecoder, ImageFormat, ImageReader, Rgba as ImageRgba};
+#[cfg(feature = "display")]
 use minifb::{Key, Window, WindowOptions};
-use pixel::{Luma, Pixel, Rgba};
-use rayon::prelude::*;
-use std::path::Path;
+use pixel::{FromPixel, Pixel, Quantization};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub use metadata::{GpsPosition, Metadata, TextTag};
+pub use orientation::Orientation;
+pub use palette::{MAX_PALETTE_COLORS, Palette};
+pub use rect::Rect;
+pub use view::{ImageView, ImageViewMut, Tile};
+
+/// Options for [`Image::open_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenOptions {
+    /// Rotate and flip the image upright according to its EXIF orientation tag. Supported for
+    /// JPEG, TIFF and WebP files.
+    pub auto_orient: bool,
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        Self { auto_orient: true }
+    }
+}
+
+/// Options for [`Image::save_with`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SaveOptions {
+    /// Conversion of the float channels to the 8 bits stored in the file
+    pub quantization: Quantization,
+    /// EXIF and XMP metadata to write into the file, e.g. read from the original with
+    /// [`Metadata::read`]. Supported for JPEG and PNG files; saving other formats with metadata
+    /// fails rather than dropping it. Without metadata, the file has none.
+    pub metadata: Option<Metadata>,
+    /// Save an indexed PNG or GIF with these colors, e.g. chosen by [`Palette::from_image`].
+    /// Every pixel takes the closest color after quantization; other formats fail.
+    pub palette: Option<Palette>,
+}
+
+/// What [`Image::display_with`] does when no window can be opened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DisplayFallback {
+    /// Save the image as a PNG in the temporary directory and print its path to stderr
+    #[default]
+    SaveTemp,
+    /// Return the error
+    Fail,
+}
+
+/// Options for [`Image::display_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DisplayOptions {
+    /// What to do when no window can be opened, e.g. without a display server
+    pub fallback: DisplayFallback,
+    /// Skip the window and go straight to the fallback, e.g. in CI where an open window would
+    /// wait for a user
+    pub headless: bool,
+}
+
+/// Number of images saved by the display fallback, to keep their file names apart.
+static DISPLAY_FALLBACK_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns a new path in the temporary directory for an image displayed as `title`.
+fn display_fallback_path(title: &str) -> PathBuf {
+    let name: String = title
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                true => c,
+                false => '_',
+            },
+        )
+        .take(64)
+        .collect();
+    let count = DISPLAY_FALLBACK_COUNT.fetch_add(1, Ordering::Relaxed);
+    std::env::temp_dir().join(format!("glance-{name}-{}-{count}.png", std::process::id()))
+}
+
+/// How [`Image::normalize_with`] finds the range of the values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Normalization {
+    /// Every channel is stretched by its own range
+    #[default]
+    PerChannel,
+    /// The color channels are stretched by their common range, keeping the ratios between them.
+    /// Alpha is stretched by its own range.
+    Joint,
+}
 
 /// Image struct represents an image with pixel data of type P
 /// where P implements the [`Pixel`] trait.
@@ -60,44 +155,249 @@ where
         })
     }
 
-    /// Creates a new [`Image`] instance from the given path.
+    /// Creates a new [`Image`] instance from the given path, turned upright according to its
+    /// EXIF orientation. Use [`Image::open_with`] to keep the stored orientation.
+    ///
+    /// Only the pixels are loaded: the EXIF and XMP metadata of the file are not kept on the
+    /// image, and [`Image::save`] writes files without any. To carry them over, read them
+    /// separately with [`Metadata::read`] and pass them to [`Image::save_with`]:
+    ///
+    /// ```no_run
+    /// # use glance_core::img::{Image, Metadata, SaveOptions, pixel::Rgba};
+    /// # fn main() -> glance_core::Result<()> {
+    /// let image = Image::<Rgba>::open("photo.jpg")?;
+    /// let metadata = Metadata::read("photo.jpg")?;
+    /// let options = SaveOptions {
+    ///     metadata: Some(metadata),
+    ///     ..Default::default()
+    /// };
+    /// image.save_with("copy.jpg", options)
+    /// # }
+    /// ```
     pub fn open<Pth: AsRef<Path>>(path: Pth) -> Result<Self> {
-        let image = ImageReader::open(path)?.decode()?.to_rgba8();
-        let (width, height) = image.dimensions();
-        let width = width as usize;
-        let height = height as usize;
+        Self::open_with(path, OpenOptions::default())
+    }
 
-        let data: Vec<P> = image.pixels().map(|p| P::from_rgba8(p.0)).collect();
+    /// Creates a new [`Image`] instance from the given path with the given options.
+    pub fn open_with<Pth: AsRef<Path>>(path: Pth, options: OpenOptions) -> Result<Self> {
+        let mut decoder = ImageReader::open(path)?.into_decoder()?;
+        let (width, height) = decoder.dimensions();
+        let _span = OpSpan::enter("open", (width as usize, height as usize));
+        let orientation = match options.auto_orient {
+            true => match decoder.orientation()? {
+                ImageOrientation::NoTransforms => Orientation::Normal,
+                ImageOrientation::Rotate90 => Orientation::Rotate90,
+                ImageOrientation::Rotate180 => Orientation::Rotate180,
+                ImageOrientation::Rotate270 => Orientation::Rotate270,
+                ImageOrientation::FlipHorizontal => Orientation::FlipHorizontal,
+                ImageOrientation::FlipVertical => Orientation::FlipVertical,
+                ImageOrientation::Rotate90FlipH => Orientation::Transpose,
+                ImageOrientation::Rotate270FlipH => Orientation::Transverse,
+            },
+            false => Orientation::Normal,
+        };
+        let image = DynamicImage::from_decoder(decoder)?;
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let data: Vec<P> = match P::bit_depth() > 8 {
+            true => image
+                .to_rgba16()
+                .pixels()
+                .map(|p| P::from_rgba16(p.0))
+                .collect(),
+            false => image
+                .to_rgba8()
+                .pixels()
+                .map(|p| P::from_rgba8(p.0))
+                .collect(),
+        };
 
-        Ok(Image {
+        let image = Image {
             width,
             height,
             data,
+        };
+        Ok(match orientation {
+            Orientation::Normal => image,
+            orientation => image.apply_orientation(orientation),
         })
     }
 
+    /// Creates a new [`Image`] from interleaved 8 bit RGBA bytes in row-major order, the layout
+    /// of browser `ImageData` and most raw framebuffers.
+    pub fn from_rgba8_bytes(width: usize, height: usize, bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != width * height * 4 {
+            return Err(CoreError::InvalidData(format!(
+                "Expected {} RGBA bytes for {width}x{height}, got {}",
+                width * height * 4,
+                bytes.len()
+            )));
+        }
+        let data = bytes
+            .chunks_exact(4)
+            .map(|p| P::from_rgba8([p[0], p[1], p[2], p[3]]))
+            .collect();
+        Image::from_data(width, height, data)
+    }
+
+    /// Returns the pixels as interleaved 8 bit RGBA bytes in row-major order, see
+    /// [`Image::from_rgba8_bytes`]. Channels are rounded to the nearest level, see
+    /// [`pixel::to_u8`].
+    pub fn to_rgba8_bytes(&self) -> Vec<u8> {
+        self.data
+            .iter()
+            .flat_map(|pixel| pixel.to_rgba8())
+            .collect()
+    }
+
+    /// Returns the pixels as interleaved 8 bit RGBA bytes like [`Image::to_rgba8_bytes`], with
+    /// the channels converted by `quantization`.
+    pub fn to_rgba8_bytes_with(&self, quantization: Quantization) -> Vec<u8> {
+        let width = self.width.max(1);
+        self.data
+            .iter()
+            .enumerate()
+            .flat_map(|(i, pixel)| {
+                let position = (i % width, i / width);
+                pixel.to_rgba8_with(|value| quantization.quantize(value, position))
+            })
+            .collect()
+    }
+
     /// Saves the image to the specified path. File format is determined by the file extension.
     /// See [`image::ImageBuffer::save`] for more details.
+    ///
+    /// The file has no metadata, even if the image was opened from a file with some. Pass the
+    /// metadata read with [`Metadata::read`] to [`Image::save_with`] to keep it, see
+    /// [`Image::open`].
     pub fn save<Pth: AsRef<Path>>(&self, path: Pth) -> Result<()> {
-        let rgba8_data: Vec<[u8; 4]> = self.data.iter().map(|pixel| pixel.to_rgba8()).collect();
-        let rgba8_bytes: Vec<u8> = rgba8_data.iter().flatten().copied().collect();
+        self.save_with(path, SaveOptions::default())
+    }
+
+    /// Saves the image to the specified path with the given options, see [`Image::save`].
+    /// Pixels of more than 8 bits, see [`Pixel::bit_depth`], are written with 16 bits per
+    /// channel to PNG and TIFF files, unless a palette is given.
+    pub fn save_with<Pth: AsRef<Path>>(&self, path: Pth, options: SaveOptions) -> Result<()> {
+        let _span = OpSpan::enter("save", self.dimensions());
+        let wide_format = matches!(
+            ImageFormat::from_path(&path),
+            Ok(ImageFormat::Png | ImageFormat::Tiff)
+        );
+        if P::bit_depth() > 8 && wide_format && options.palette.is_none() {
+            return self.save_rgba16(path, options.metadata);
+        }
+        let bytes = self.to_rgba8_bytes_with(options.quantization);
+        let metadata = options.metadata.filter(|metadata| !metadata.is_empty());
+        if let Some(palette) = &options.palette {
+            let format = ImageFormat::from_path(&path)?;
+            let mut encoded = palette::encode_indexed(&bytes, self.dimensions(), format, palette)?;
+            if let Some(metadata) = &metadata {
+                encoded = metadata::embed(encoded, format, metadata)?;
+            }
+            std::fs::write(path, encoded)?;
+            return Ok(());
+        }
+
+        let buffer =
+            ImageBuffer::<ImageRgba<u8>, _>::from_raw(self.width as u32, self.height as u32, bytes)
+                .ok_or_else(|| std::io::Error::other("Invalid buffer"))?;
+        let Some(metadata) = metadata else {
+            buffer.save(path)?;
+            return Ok(());
+        };
+
+        let format = ImageFormat::from_path(&path)?;
+        let mut encoded = std::io::Cursor::new(Vec::new());
+        match format {
+            // JPEG has no alpha channel
+            ImageFormat::Jpeg => DynamicImage::ImageRgba8(buffer)
+                .to_rgb8()
+                .write_to(&mut encoded, format)?,
+            _ => buffer.write_to(&mut encoded, format)?,
+        }
+        let bytes = metadata::embed(encoded.into_inner(), format, &metadata)?;
+        std::fs::write(path, bytes)?;
 
-        let buffer = ImageBuffer::<ImageRgba<u8>, _>::from_raw(
+        Ok(())
+    }
+
+    /// Saves the image with 16 bits per channel, see [`Image::save_with`].
+    fn save_rgba16<Pth: AsRef<Path>>(&self, path: Pth, metadata: Option<Metadata>) -> Result<()> {
+        let channels: Vec<u16> = self
+            .data
+            .iter()
+            .flat_map(|pixel| pixel.to_rgba16())
+            .collect();
+        let buffer = ImageBuffer::<ImageRgba<u16>, _>::from_raw(
             self.width as u32,
             self.height as u32,
-            rgba8_bytes,
+            channels,
         )
         .ok_or_else(|| std::io::Error::other("Invalid buffer"))?;
-        buffer.save(path)?;
-
+        let Some(metadata) = metadata.filter(|metadata| !metadata.is_empty()) else {
+            buffer.save(path)?;
+            return Ok(());
+        };
+        let format = ImageFormat::from_path(&path)?;
+        let mut encoded = std::io::Cursor::new(Vec::new());
+        buffer.write_to(&mut encoded, format)?;
+        let bytes = metadata::embed(encoded.into_inner(), format, &metadata)?;
+        std::fs::write(path, bytes)?;
         Ok(())
     }
 
-    /// Opens an [`Image`] instance and displays it in a window.
+    /// Displays the image in a window until it is closed or Escape is pressed. Without a window
+    /// system, or with the `GLANCE_HEADLESS` environment variable set, the image is saved to a
+    /// temporary PNG instead, see [`Image::display_with`].
     pub fn display(&self, title: &str) -> Result<()> {
-        let (width, height) = self.dimensions();
+        let options = DisplayOptions {
+            headless: std::env::var_os("GLANCE_HEADLESS").is_some(),
+            ..Default::default()
+        };
+        self.display_with(title, options)
+    }
+
+    /// Displays the image in a window with the given options. When the window cannot be
+    /// opened, e.g. without a display server or in builds without the `display` feature, the
+    /// [`DisplayFallback`] of the options applies.
+    pub fn display_with(&self, title: &str, options: DisplayOptions) -> Result<()> {
+        #[cfg(feature = "display")]
+        if !options.headless {
+            return match self.open_window(title) {
+                Ok(window) => self.show_window(window),
+                Err(error) => self.display_fallback(title, error, options.fallback),
+            };
+        }
+        let reason = match options.headless {
+            true => "headless display",
+            false => "built without the display feature",
+        };
+        let error = CoreError::Unsupported(reason.to_string());
+        self.display_fallback(title, error, options.fallback)
+    }
+
+    fn display_fallback(
+        &self,
+        title: &str,
+        error: CoreError,
+        fallback: DisplayFallback,
+    ) -> Result<()> {
+        match fallback {
+            DisplayFallback::Fail => Err(error),
+            DisplayFallback::SaveTemp => {
+                let path = display_fallback_path(title);
+                self.save(&path)?;
+                eprintln!(
+                    "{title}: cannot open a window ({error}), saved to {}",
+                    path.display()
+                );
+                Ok(())
+            }
+        }
+    }
 
-        // Create window
+    #[cfg(feature = "display")]
+    fn open_window(&self, title: &str) -> Result<Window> {
+        let (width, height) = self.dimensions();
         let mut window = Window::new(
             title,
             width,
@@ -108,6 +408,12 @@ where
             },
         )?;
         window.set_target_fps(30);
+        Ok(window)
+    }
+
+    #[cfg(feature = "display")]
+    fn show_window(&self, mut window: Window) -> Result<()> {
+        let (width, height) = self.dimensions();
 
         // Populate framebuffer
         let buffer: Vec<u32> = self
@@ -142,6 +448,62 @@ where
         })
     }
 
+    /// Samples the image at a subpixel position using bilinear interpolation, where (0.0, 0.0)
+    /// is the center of the top-left pixel. Positions outside the image are clamped to the
+    /// nearest edge pixel. Panics if the image is empty.
+    pub fn sample_bilinear(&self, x: f32, y: f32) -> P {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as isize, y0 as isize);
+        let taps = [
+            (self.clamped_pixel(x0, y0), (1.0 - fx) * (1.0 - fy)),
+            (self.clamped_pixel(x0 + 1, y0), fx * (1.0 - fy)),
+            (self.clamped_pixel(x0, y0 + 1), (1.0 - fx) * fy),
+            (self.clamped_pixel(x0 + 1, y0 + 1), fx * fy),
+        ];
+
+        let mut pixel = P::new();
+        for c in 0..P::channel_count() {
+            let value = taps.iter().map(|(p, w)| p.channel(c) * w).sum();
+            pixel.set_channel(c, value);
+        }
+        pixel
+    }
+
+    /// Samples the image at a subpixel position using bicubic (Catmull-Rom) interpolation over
+    /// the surrounding 4x4 pixels. Sharper than [`Image::sample_bilinear`], but values can
+    /// slightly overshoot near edges and are not clamped. Positions outside the image are
+    /// clamped to the nearest edge pixel. Panics if the image is empty.
+    pub fn sample_bicubic(&self, x: f32, y: f32) -> P {
+        let (x0, y0) = (x.floor(), y.floor());
+        let (wx, wy) = (catmull_rom_weights(x - x0), catmull_rom_weights(y - y0));
+        let (x0, y0) = (x0 as isize, y0 as isize);
+
+        let mut sums = [0.0f32; 4];
+        let channels = P::channel_count();
+        for (j, wy) in wy.iter().enumerate() {
+            for (i, wx) in wx.iter().enumerate() {
+                let px = self.clamped_pixel(x0 + i as isize - 1, y0 + j as isize - 1);
+                for (c, sum) in sums.iter_mut().enumerate().take(channels) {
+                    *sum += px.channel(c) * wx * wy;
+                }
+            }
+        }
+
+        let mut pixel = P::new();
+        for (c, sum) in sums.iter().enumerate().take(channels) {
+            pixel.set_channel(c, *sum);
+        }
+        pixel
+    }
+
+    /// Returns the pixel at (x, y), clamping the position into the image.
+    fn clamped_pixel(&self, x: isize, y: isize) -> P {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+        self.data[y * self.width + x]
+    }
+
     /// Sets the pixel at the specified position to the given color.
     /// Colors are of type P, which implements the [`Pixel`] trait.
     /// Returns an error if the position is out of bounds.
@@ -159,6 +521,117 @@ where
         Ok(())
     }
 
+    /// Returns a copy of the pixels inside `rect`.
+    /// Returns an error if the rectangle does not lie completely within the image.
+    pub fn crop(&self, rect: Rect) -> Result<Self> {
+        if rect.clip_to(self.dimensions()) != rect {
+            return Err(CoreError::OutOfBounds(format!(
+                "{rect:?} is out of bounds for image of size {:?}",
+                self.dimensions()
+            )));
+        }
+        let data = (rect.y..rect.y + rect.height)
+            .flat_map(|y| {
+                let start = y * self.width + rect.x;
+                self.data[start..start + rect.width].iter().copied()
+            })
+            .collect();
+        Image::from_data(rect.width, rect.height, data)
+    }
+
+    /// Crops away near-uniform margins such as scanner borders and letterbox bars, returning the
+    /// content and its [`Rect`] in this image. Starting from each edge, rows and columns are
+    /// trimmed while every channel of every pixel is within `tolerance` of the first pixel of the
+    /// outermost one, so each side may have its own margin color. A uniform image trims to an
+    /// empty one.
+    pub fn trim_borders(&self, tolerance: f32) -> (Self, Rect) {
+        let width = self.width;
+        let similar = |a: &P, b: &P| {
+            (0..P::channel_count()).all(|c| (a.channel(c) - b.channel(c)).abs() <= tolerance)
+        };
+        let row_uniform = |y: usize, reference: &P| {
+            self.data[y * width..(y + 1) * width]
+                .iter()
+                .all(|p| similar(p, reference))
+        };
+
+        let (mut top, mut bottom) = (0, self.height);
+        if let Some(reference) = self.data.first() {
+            while top < bottom && row_uniform(top, reference) {
+                top += 1;
+            }
+        }
+        if top < bottom {
+            let reference = self.data[(bottom - 1) * width];
+            while bottom > top && row_uniform(bottom - 1, &reference) {
+                bottom -= 1;
+            }
+        }
+        if top == bottom {
+            return (Image::new(0, 0), Rect::new(0, 0, 0, 0));
+        }
+
+        let column_uniform = |x: usize, reference: &P| {
+            (top..bottom).all(|y| similar(&self.data[y * width + x], reference))
+        };
+        let (mut left, mut right) = (0, width);
+        let reference = self.data[top * width];
+        while left < right && column_uniform(left, &reference) {
+            left += 1;
+        }
+        let reference = self.data[top * width + width - 1];
+        while right > left && column_uniform(right - 1, &reference) {
+            right -= 1;
+        }
+
+        let rect = Rect::new(left, top, right - left, bottom - top);
+        (self.crop(rect).unwrap(), rect)
+    }
+
+    /// Copies `image` into this image with its top-left corner at `position`. Pixels that fall
+    /// outside of this image are skipped.
+    pub fn paste(&mut self, position: (usize, usize), image: &Image<P>) {
+        let target =
+            Rect::new(position.0, position.1, image.width, image.height).clip_to(self.dimensions());
+        for row in 0..target.height {
+            let source = row * image.width;
+            let start = (target.y + row) * self.width + target.x;
+            self.data[start..start + target.width]
+                .copy_from_slice(&image.data[source..source + target.width]);
+        }
+    }
+
+    /// Composites `image` over this image with its top-left corner at `position`, blending by
+    /// the straight (not premultiplied) alpha of both like the "over" operator. Pixel types
+    /// without alpha are opaque, so for them this is the same as [`Image::paste`]. Pixels that
+    /// fall outside of this image are skipped.
+    pub fn composite(&mut self, position: (usize, usize), image: &Image<P>) {
+        let Some(alpha) = P::alpha_channel() else {
+            return self.paste(position, image);
+        };
+        let target =
+            Rect::new(position.0, position.1, image.width, image.height).clip_to(self.dimensions());
+        for row in 0..target.height {
+            let source = &image.data[row * image.width..row * image.width + target.width];
+            let start = (target.y + row) * self.width + target.x;
+            for (under, over) in self.data[start..start + target.width]
+                .iter_mut()
+                .zip(source)
+            {
+                let (a_over, a_under) = (over.channel(alpha), under.channel(alpha));
+                let a_out = a_over + a_under * (1.0 - a_over);
+                let mut out = *under;
+                for c in (0..P::channel_count()).filter(|&c| c != alpha) {
+                    let value =
+                        over.channel(c) * a_over + under.channel(c) * a_under * (1.0 - a_over);
+                    out.set_channel(c, if a_out > 0.0 { value / a_out } else { 0.0 });
+                }
+                out.set_channel(alpha, a_out);
+                *under = out;
+            }
+        }
+    }
+
     /// Draws a shape on the image. The shape must implement the [`Drawable`] trait.
     pub fn draw<D: Drawable<P>>(&mut self, shape: D) -> Result<()> {
         shape.draw_on(self)?;
@@ -174,74 +647,147 @@ where
     pub fn is_empty(&self) -> bool {
         self.data.is_empty()
     }
-}
 
-impl Image<Rgba> {
+    /// Applies `f` to every channel of every pixel, leaving alpha untouched.
+    pub fn apply(self, f: impl Fn(f32) -> f32 + Sync) -> Self {
+        self.apply_with_alpha(f, false)
+    }
+
+    /// Applies `f` to every channel of every pixel, and to alpha as well if `alpha` is true.
+    pub fn apply_with_alpha(mut self, f: impl Fn(f32) -> f32 + Sync, alpha: bool) -> Self {
+        self.par_pixels_mut().for_each(|pixel| {
+            *pixel = match alpha {
+                true => pixel.map(&f),
+                false => pixel.map_colors(&f),
+            };
+        });
+        self
+    }
+
+    /// Stretches every channel to [0.0, 1.0], see [`Image::normalize_with`].
     pub fn normalize(&self) -> Self {
-        // Find the maximum value in the pixel data for each channel
-        let (max_r, max_g, max_b, max_a) = self
-            .par_pixels()
-            .map(|pixel| (pixel.r, pixel.g, pixel.b, pixel.a))
-            .reduce(
-                || (0.0, 0.0, 0.0, 0.0),
-                |(max_r, max_g, max_b, max_a), (r, g, b, a)| {
-                    (max_r.max(r), max_g.max(g), max_b.max(b), max_a.max(a))
-                },
-            );
-
-        let (min_r, min_g, min_b, min_a) = self
-            .par_pixels()
-            .map(|pixel| (pixel.r, pixel.g, pixel.b, pixel.a))
-            .reduce(
-                || (f32::MAX, f32::MAX, f32::MAX, f32::MAX),
-                |(min_r, min_g, min_b, min_a), (r, g, b, a)| {
-                    (min_r.min(r), min_g.min(g), min_b.min(b), min_a.min(a))
-                },
-            );
-
-        // Normalize each pixel
-        let normalized = self
+        self.normalize_to((0.0, 1.0))
+    }
+
+    /// Stretches every channel to `range` = (low, high), see [`Image::normalize_with`].
+    pub fn normalize_to(&self, range: (f32, f32)) -> Self {
+        self.normalize_with(range, Normalization::PerChannel)
+    }
+
+    /// Maps the values of the image linearly so that the smallest becomes `range.0` and the
+    /// largest `range.1`, per channel or jointly depending on `mode`. Constant channels, whose
+    /// smallest and largest values are equal, are only clamped to the range.
+    pub fn normalize_with(&self, range: (f32, f32), mode: Normalization) -> Self {
+        let channels = P::channel_count();
+        let empty = vec![(f32::INFINITY, f32::NEG_INFINITY); channels];
+        let mut bounds = reduce_chunks(
+            &self.data,
+            |chunk| {
+                let mut bounds = empty.clone();
+                for pixel in chunk {
+                    for (c, (min, max)) in bounds.iter_mut().enumerate() {
+                        *min = min.min(pixel.channel(c));
+                        *max = max.max(pixel.channel(c));
+                    }
+                }
+                bounds
+            },
+            |a, b| {
+                a.iter()
+                    .zip(b)
+                    .map(|(a, b)| (a.0.min(b.0), a.1.max(b.1)))
+                    .collect()
+            },
+        )
+        .unwrap_or(empty);
+        if mode == Normalization::Joint {
+            let alpha = P::alpha_channel();
+            let is_color = |c: usize| Some(c) != alpha;
+            let joint = (0..channels)
+                .filter(|&c| is_color(c))
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), c| {
+                    (min.min(bounds[c].0), max.max(bounds[c].1))
+                });
+            for (c, channel_bounds) in bounds.iter_mut().enumerate() {
+                if is_color(c) {
+                    *channel_bounds = joint;
+                }
+            }
+        }
+
+        let (low, high) = range;
+        let data = self
             .par_pixels()
-            .map(|pixel| Rgba {
-                r: (pixel.r - min_r) / (max_r - min_r),
-                g: (pixel.g - min_g) / (max_g - min_g),
-                b: (pixel.b - min_b) / (max_b - min_b),
-                a: (pixel.a - min_a) / (max_a - min_a),
+            .map(|pixel| {
+                let mut normalized = *pixel;
+                for (c, &(min, max)) in bounds.iter().enumerate() {
+                    let value = pixel.channel(c);
+                    let value = match max > min {
+                        true => low + (value - min) * (high - low) / (max - min),
+                        false => value.clamp(low.min(high), low.max(high)),
+                    };
+                    normalized.set_channel(c, value);
+                }
+                normalized
             })
             .collect();
 
         Self {
             width: self.width,
             height: self.height,
-            data: normalized,
+            data,
         }
     }
-}
-
-impl Image<Luma> {
-    pub fn normalize(&self) -> Self {
-        // Find the maximum value in the pixel data for each channel
-        let max_l = self
-            .par_pixels()
-            .map(|pixel| pixel.l)
-            .reduce(|| 0.0, |max_l, l| (max_l.max(l)));
 
-        let min_l = self
-            .par_pixels()
-            .map(|pixel| pixel.l)
-            .reduce(|| f32::MAX, |min_l, l| (min_l.min(l)));
-        // Normalize each pixel
-        let normalized = self
-            .par_pixels()
-            .map(|pixel| Luma {
-                l: (pixel.l - min_l) / (max_l - min_l),
-            })
-            .collect();
-
-        Self {
+    /// Converts every pixel to the pixel type `Q`, e.g. `image.convert::<Luma>()` for the
+    /// luminance of a color image. See [`FromPixel`] for how the channels are mapped.
+    pub fn convert<Q: FromPixel<P>>(&self) -> Image<Q> {
+        Image {
             width: self.width,
             height: self.height,
-            data: normalized,
+            data: self.par_pixels().map(Q::from_pixel).collect(),
         }
     }
+
+    /// Returns the pixels in row-major order, e.g. to split them into rows with
+    /// `chunks_exact(width)` or to process them with the slice methods of [`crate::par`].
+    pub fn as_slice(&self) -> &[P] {
+        &self.data
+    }
+
+    /// Returns the pixels in row-major order for modification, see [`Image::as_slice`]. The
+    /// dimensions of the image cannot change through the slice.
+    pub fn as_mut_slice(&mut self) -> &mut [P] {
+        &mut self.data
+    }
+
+    /// Consumes the image and returns its pixels in row-major order, the inverse of
+    /// [`Image::from_data`].
+    pub fn into_vec(self) -> Vec<P> {
+        self.data
+    }
+}
+
+impl<P: Pixel> AsRef<[P]> for Image<P> {
+    fn as_ref(&self) -> &[P] {
+        self.as_slice()
+    }
+}
+
+impl<P: Pixel> AsMut<[P]> for Image<P> {
+    fn as_mut(&mut self) -> &mut [P] {
+        self.as_mut_slice()
+    }
+}
+
+/// Catmull-Rom weights for the four taps at offsets -1, 0, 1 and 2 from the sample position,
+/// where `t` is the fractional part of the position.
+fn catmull_rom_weights(t: f32) -> [f32; 4] {
+    let (t2, t3) = (t * t, t * t * t);
+    [
+        0.5 * (-t3 + 2.0 * t2 - t),
+        0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+        0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+        0.5 * (t3 - t2),
+    ]
 }
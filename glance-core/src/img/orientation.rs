@@ -0,0 +1,119 @@
+//! Lossless rotations and flips by multiples of 90 degrees, and the EXIF orientation tag that
+//! cameras use to record how a photo has to be turned for display.
+
+use super::Image;
+use super::pixel::Pixel;
+
+/// The transformation that brings a stored image upright, named after the EXIF orientation tag
+/// values 1 to 8. Rotations are clockwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Orientation {
+    /// Already upright (1)
+    #[default]
+    Normal,
+    /// Mirror left to right (2)
+    FlipHorizontal,
+    /// Rotate by 180 degrees (3)
+    Rotate180,
+    /// Mirror top to bottom (4)
+    FlipVertical,
+    /// Mirror along the main diagonal (5)
+    Transpose,
+    /// Rotate by 90 degrees (6)
+    Rotate90,
+    /// Mirror along the anti-diagonal (7)
+    Transverse,
+    /// Rotate by 270 degrees (8)
+    Rotate270,
+}
+
+impl Orientation {
+    /// Returns the orientation for a value of the EXIF orientation tag, or None if it is not
+    /// in 1..=8.
+    pub fn from_exif(value: u16) -> Option<Self> {
+        use Orientation::*;
+        [
+            Normal,
+            FlipHorizontal,
+            Rotate180,
+            FlipVertical,
+            Transpose,
+            Rotate90,
+            Transverse,
+            Rotate270,
+        ]
+        .get((value as usize).checked_sub(1)?)
+        .copied()
+    }
+
+    /// Returns the value of the EXIF orientation tag.
+    pub fn to_exif(self) -> u16 {
+        self as u16 + 1
+    }
+}
+
+impl<P: Pixel> Image<P> {
+    /// Creates an image of the given size, taking every pixel (x, y) from the position of this
+    /// image returned by `source`.
+    fn remap(&self, width: usize, height: usize, source: impl Fn(usize, usize) -> usize) -> Self {
+        let data = (0..width * height)
+            .map(|i| self.data[source(i % width, i / width)])
+            .collect();
+        Image {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Mirrors the image left to right.
+    pub fn flip_horizontal(&self) -> Self {
+        let (w, h) = (self.width, self.height);
+        self.remap(w, h, |x, y| y * w + (w - 1 - x))
+    }
+
+    /// Mirrors the image top to bottom.
+    pub fn flip_vertical(&self) -> Self {
+        let (w, h) = (self.width, self.height);
+        self.remap(w, h, |x, y| (h - 1 - y) * w + x)
+    }
+
+    /// Rotates the image by 90 degrees clockwise.
+    pub fn rotate_90(&self) -> Self {
+        let (w, h) = (self.width, self.height);
+        self.remap(h, w, |x, y| (h - 1 - x) * w + y)
+    }
+
+    /// Rotates the image by 180 degrees.
+    pub fn rotate_180(&self) -> Self {
+        let (w, h) = (self.width, self.height);
+        self.remap(w, h, |x, y| (h - 1 - y) * w + (w - 1 - x))
+    }
+
+    /// Rotates the image by 270 degrees clockwise, i.e. 90 degrees counter-clockwise.
+    pub fn rotate_270(&self) -> Self {
+        let (w, h) = (self.width, self.height);
+        self.remap(h, w, |x, y| x * w + (w - 1 - y))
+    }
+
+    /// Mirrors the image along its main diagonal, swapping rows and columns.
+    pub fn transpose(&self) -> Self {
+        let (w, h) = (self.width, self.height);
+        self.remap(h, w, |x, y| x * w + y)
+    }
+
+    /// Applies the transformation described by `orientation`, turning an image stored with
+    /// that EXIF orientation upright.
+    pub fn apply_orientation(&self, orientation: Orientation) -> Self {
+        match orientation {
+            Orientation::Normal => self.clone(),
+            Orientation::FlipHorizontal => self.flip_horizontal(),
+            Orientation::Rotate180 => self.rotate_180(),
+            Orientation::FlipVertical => self.flip_vertical(),
+            Orientation::Transpose => self.transpose(),
+            Orientation::Rotate90 => self.rotate_90(),
+            Orientation::Transverse => self.rotate_180().transpose(),
+            Orientation::Rotate270 => self.rotate_270(),
+        }
+    }
+}
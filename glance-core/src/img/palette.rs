@@ -0,0 +1,253 @@
+//! Indexed color: palettes of at most 256 colors, chosen by hand or by median cut quantization,
+//! and the indexed PNG and GIF encoders used by [`super::Image::save_with`].
+
+use super::Image;
+use super::pixel::Pixel;
+use crate::par::*;
+use crate::{CoreError, Result};
+use image::ImageFormat;
+use std::collections::HashMap;
+
+/// Most colors an indexed PNG or GIF can hold.
+pub const MAX_PALETTE_COLORS: usize = 256;
+
+/// Colors of an indexed image, as 8 bit RGBA.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    colors: Vec<[u8; 4]>,
+}
+
+impl Palette {
+    /// Creates a palette of the given colors, between 1 and [`MAX_PALETTE_COLORS`] of them.
+    pub fn new(colors: Vec<[u8; 4]>) -> Result<Self> {
+        if colors.is_empty() || colors.len() > MAX_PALETTE_COLORS {
+            return Err(CoreError::InvalidData(format!(
+                "Palettes hold 1 to {MAX_PALETTE_COLORS} colors, got {}",
+                colors.len()
+            )));
+        }
+        Ok(Self { colors })
+    }
+
+    /// Chooses at most `max_colors` colors (clamped to 1..=256) representing `image`. Images
+    /// with few enough distinct colors, like icons and screenshots, keep them exactly; others
+    /// are reduced by median cut, which splits the colors at the median of their widest channel
+    /// until there are `max_colors` groups, and takes the mean of every group.
+    pub fn from_image<P: Pixel>(image: &Image<P>, max_colors: usize) -> Self {
+        let max_colors = max_colors.clamp(1, MAX_PALETTE_COLORS);
+        let mut histogram = HashMap::new();
+        for pixel in image.pixels() {
+            *histogram.entry(pixel.to_rgba8()).or_insert(0u64) += 1;
+        }
+        let mut counts: Vec<([u8; 4], u64)> = histogram.into_iter().collect();
+        counts.sort_unstable();
+        if counts.is_empty() {
+            return Self {
+                colors: vec![[0, 0, 0, 255]],
+            };
+        }
+        if counts.len() <= max_colors {
+            return Self {
+                colors: counts.into_iter().map(|(color, _)| color).collect(),
+            };
+        }
+
+        let mut boxes = vec![counts];
+        while boxes.len() < max_colors {
+            // Split the box spanning the widest range of a channel
+            let widest = boxes
+                .iter()
+                .enumerate()
+                .filter(|(_, colors)| colors.len() > 1)
+                .map(|(i, colors)| (i, widest_channel(colors)))
+                .max_by_key(|&(_, (_, range))| range);
+            let Some((index, (channel, _))) = widest else {
+                break;
+            };
+            let mut colors = boxes.swap_remove(index);
+            colors.sort_unstable_by_key(|(color, _)| color[channel]);
+            let total: u64 = colors.iter().map(|(_, count)| count).sum();
+            let mut seen = 0;
+            let median = colors
+                .iter()
+                .position(|(_, count)| {
+                    seen += count;
+                    seen * 2 >= total
+                })
+                .unwrap_or(0)
+                .clamp(0, colors.len() - 2);
+            let upper = colors.split_off(median + 1);
+            boxes.push(colors);
+            boxes.push(upper);
+        }
+
+        let colors = boxes
+            .iter()
+            .map(|colors| {
+                let total: u64 = colors.iter().map(|(_, count)| count).sum();
+                std::array::from_fn(|c| {
+                    let sum: u64 = colors
+                        .iter()
+                        .map(|(color, count)| color[c] as u64 * count)
+                        .sum();
+                    ((sum + total / 2) / total) as u8
+                })
+            })
+            .collect();
+        Self { colors }
+    }
+
+    /// Returns the colors of the palette.
+    pub fn colors(&self) -> &[[u8; 4]] {
+        &self.colors
+    }
+
+    /// Returns the index of the palette color closest to `color`, by squared distance of the
+    /// RGBA channels.
+    pub fn nearest(&self, color: [u8; 4]) -> u8 {
+        self.nearest_where(color, |_| true)
+    }
+
+    /// Returns the index of the closest palette color passing `filter`, or 0 if none does.
+    fn nearest_where(&self, color: [u8; 4], filter: impl Fn(&[u8; 4]) -> bool) -> u8 {
+        let distance = |entry: &[u8; 4]| -> u32 {
+            entry
+                .iter()
+                .zip(color)
+                .map(|(&a, b)| (a as i32 - b as i32).pow(2) as u32)
+                .sum()
+        };
+        self.colors
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| filter(entry))
+            .min_by_key(|(_, entry)| distance(entry))
+            .map_or(0, |(i, _)| i as u8)
+    }
+}
+
+/// Returns the channel with the widest range of values in `colors`, and the range.
+fn widest_channel(colors: &[([u8; 4], u64)]) -> (usize, u8) {
+    (0..4)
+        .map(|c| {
+            let values = colors.iter().map(|(color, _)| color[c]);
+            let range = values.clone().max().unwrap_or(0) - values.min().unwrap_or(0);
+            (c, range)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or((0, 0))
+}
+
+/// Encodes interleaved 8 bit RGBA `bytes` as an indexed PNG or GIF with the colors of
+/// `palette`.
+pub(super) fn encode_indexed(
+    bytes: &[u8],
+    (width, height): (usize, usize),
+    format: ImageFormat,
+    palette: &Palette,
+) -> Result<Vec<u8>> {
+    match format {
+        ImageFormat::Png => encode_png(bytes, (width, height), palette),
+        ImageFormat::Gif => encode_gif(bytes, (width, height), palette),
+        _ => Err(CoreError::Unsupported(format!(
+            "Writing indexed {format:?} files, use PNG or GIF"
+        ))),
+    }
+}
+
+fn encode_png(bytes: &[u8], (width, height): (usize, usize), palette: &Palette) -> Result<Vec<u8>> {
+    let indices: Vec<u8> = bytes
+        .par_chunks(4)
+        .map(|p| palette.nearest([p[0], p[1], p[2], p[3]]))
+        .collect();
+
+    // Pack small palettes into fewer bits per pixel, starting every row on a byte
+    let (depth, bits) = match palette.colors.len() {
+        0..=2 => (png::BitDepth::One, 1),
+        3..=4 => (png::BitDepth::Two, 2),
+        5..=16 => (png::BitDepth::Four, 4),
+        _ => (png::BitDepth::Eight, 8),
+    };
+    let per_byte = 8 / bits;
+    let data: Vec<u8> = indices
+        .chunks(width.max(1))
+        .flat_map(|row| {
+            row.chunks(per_byte).map(|group| {
+                group.iter().enumerate().fold(0u8, |byte, (i, &index)| {
+                    byte | index << (8 - bits * (i + 1))
+                })
+            })
+        })
+        .collect();
+
+    let rgb: Vec<u8> = palette
+        .colors
+        .iter()
+        .flat_map(|c| [c[0], c[1], c[2]])
+        .collect();
+    // Entries past the last translucent one are opaque
+    let opaque = palette
+        .colors
+        .iter()
+        .rposition(|c| c[3] < 255)
+        .map_or(0, |i| i + 1);
+    let alpha: Vec<u8> = palette.colors[..opaque].iter().map(|c| c[3]).collect();
+
+    let mut encoded = Vec::new();
+    let mut encoder = png::Encoder::new(&mut encoded, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(depth);
+    encoder.set_palette(rgb);
+    if !alpha.is_empty() {
+        encoder.set_trns(alpha);
+    }
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    writer.write_image_data(&data).map_err(png_error)?;
+    writer.finish().map_err(png_error)?;
+    Ok(encoded)
+}
+
+fn png_error(error: png::EncodingError) -> CoreError {
+    CoreError::InvalidData(format!("PNG: {error}"))
+}
+
+/// GIF has a single fully transparent palette entry instead of per-entry alpha: pixels and
+/// palette colors with an alpha below one half are transparent, the rest opaque.
+fn encode_gif(bytes: &[u8], (width, height): (usize, usize), palette: &Palette) -> Result<Vec<u8>> {
+    let (Ok(gif_width), Ok(gif_height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(CoreError::Unsupported(format!(
+            "GIF images are at most 65535 pixels wide and high, got {width}x{height}"
+        )));
+    };
+    let transparent = palette.colors.iter().position(|c| c[3] < 128);
+    let indices: Vec<u8> = bytes
+        .par_chunks(4)
+        .map(|p| match transparent {
+            Some(index) if p[3] < 128 => index as u8,
+            _ => palette.nearest_where([p[0], p[1], p[2], 255], |c| c[3] >= 128),
+        })
+        .collect();
+
+    let rgb: Vec<u8> = palette
+        .colors
+        .iter()
+        .flat_map(|c| [c[0], c[1], c[2]])
+        .collect();
+    let mut encoded = Vec::new();
+    let mut encoder =
+        gif::Encoder::new(&mut encoded, gif_width, gif_height, &rgb).map_err(gif_error)?;
+    let frame = gif::Frame {
+        width: gif_width,
+        height: gif_height,
+        transparent: transparent.map(|index| index as u8),
+        buffer: indices.into(),
+        ..Default::default()
+    };
+    encoder.write_frame(&frame).map_err(gif_error)?;
+    drop(encoder);
+    Ok(encoded)
+}
+
+fn gif_error(error: gif::EncodingError) -> CoreError {
+    CoreError::InvalidData(format!("GIF: {error}"))
+}
@@ -0,0 +1,171 @@
+//! Deterministic test patterns, so filters can be checked against known images without binary
+//! media. Colors are given as pixels and blended channel by channel, so every pattern works for
+//! any [`Pixel`] type.
+
+use std::f32::consts::PI;
+
+use super::Image;
+use super::pixel::Pixel;
+
+fn gray<P: Pixel>(value: u8) -> P {
+    P::from_rgba8([value, value, value, 255])
+}
+
+impl<P: Pixel> Image<P> {
+    /// Creates an image by evaluating `f` at every (x, y) position.
+    fn generate(width: usize, height: usize, f: impl Fn(usize, usize) -> P) -> Self {
+        let data = (0..width * height)
+            .map(|i| f(i % width, i / width))
+            .collect();
+        Image {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Creates an image filled with `color`.
+    pub fn solid(width: usize, height: usize, color: P) -> Self {
+        Image {
+            width,
+            height,
+            data: vec![color; width * height],
+        }
+    }
+
+    /// Creates a checkerboard of square cells of `cell_size` pixels, starting with `first` in
+    /// the top-left corner.
+    pub fn checkerboard(
+        width: usize,
+        height: usize,
+        cell_size: usize,
+        first: P,
+        second: P,
+    ) -> Self {
+        let cell_size = cell_size.max(1);
+        Self::generate(width, height, |x, y| {
+            if (x / cell_size + y / cell_size).is_multiple_of(2) {
+                first
+            } else {
+                second
+            }
+        })
+    }
+
+    /// Creates a linear gradient from `from` to `to` along the direction `angle` (radians,
+    /// clockwise from the positive x axis). The ramp spans the whole image, so 0.0 gives `from`
+    /// in the left column and `to` in the right column.
+    pub fn linear_gradient(width: usize, height: usize, angle: f32, from: P, to: P) -> Self {
+        let (dx, dy) = (angle.cos(), angle.sin());
+        let (w, h) = (
+            width.saturating_sub(1) as f32,
+            height.saturating_sub(1) as f32,
+        );
+        // Projections of the four corners onto the direction bound the ramp
+        let corners = [0.0, w * dx, h * dy, w * dx + h * dy];
+        let min = corners.iter().copied().fold(f32::MAX, f32::min);
+        let max = corners.iter().copied().fold(f32::MIN, f32::max);
+        let span = (max - min).max(f32::EPSILON);
+        Self::generate(width, height, |x, y| {
+            from.lerp(&to, (x as f32 * dx + y as f32 * dy - min) / span)
+        })
+    }
+
+    /// Creates a radial gradient from `inner` at `center` to `outer` at `radius` pixels from
+    /// it. Pixels further away have the `outer` color.
+    pub fn radial_gradient(
+        width: usize,
+        height: usize,
+        center: (f32, f32),
+        radius: f32,
+        inner: P,
+        outer: P,
+    ) -> Self {
+        Self::generate(width, height, |x, y| {
+            let distance = (x as f32 - center.0).hypot(y as f32 - center.1);
+            inner.lerp(&outer, (distance / radius.max(f32::EPSILON)).min(1.0))
+        })
+    }
+
+    /// Creates a Siemens star: `spokes` black and as many white sectors around the center.
+    /// Spatial frequency rises towards the center, which shows the resolution limit of blurs,
+    /// resampling and lenses.
+    pub fn siemens_star(width: usize, height: usize, spokes: usize) -> Self {
+        let (cx, cy) = ((width as f32 - 1.0) / 2.0, (height as f32 - 1.0) / 2.0);
+        let (black, white) = (gray(0), gray(255));
+        Self::generate(width, height, |x, y| {
+            let angle = (y as f32 - cy).atan2(x as f32 - cx);
+            if (spokes as f32 * angle).sin() >= 0.0 {
+                white
+            } else {
+                black
+            }
+        })
+    }
+
+    /// Creates a circular zone plate: concentric rings whose frequency rises linearly from
+    /// zero at the center to the Nyquist limit (0.5 cycles per pixel) at the nearest image
+    /// border. Any aliasing shows up as extra ring centers.
+    pub fn zone_plate(width: usize, height: usize) -> Self {
+        let (cx, cy) = ((width as f32 - 1.0) / 2.0, (height as f32 - 1.0) / 2.0);
+        let radius = (width.min(height) as f32 / 2.0).max(1.0);
+        let (black, white) = (gray::<P>(0), gray::<P>(255));
+        Self::generate(width, height, |x, y| {
+            let r2 = (x as f32 - cx).powi(2) + (y as f32 - cy).powi(2);
+            black.lerp(&white, 0.5 + 0.5 * (PI * r2 / (2.0 * radius)).cos())
+        })
+    }
+
+    /// Creates SMPTE color bars: seven 75% bars on the top two thirds, the reversed blue bars
+    /// below them and the -I, white, +Q and PLUGE blocks in the bottom quarter.
+    pub fn color_bars(width: usize, height: usize) -> Self {
+        const TOP: [[u8; 3]; 7] = [
+            [192, 192, 192],
+            [192, 192, 0],
+            [0, 192, 192],
+            [0, 192, 0],
+            [192, 0, 192],
+            [192, 0, 0],
+            [0, 0, 192],
+        ];
+        const MIDDLE: [[u8; 3]; 7] = [
+            [0, 0, 192],
+            [19, 19, 19],
+            [192, 0, 192],
+            [19, 19, 19],
+            [0, 192, 192],
+            [19, 19, 19],
+            [192, 192, 192],
+        ];
+        // Blocks of the bottom row with their right edge in units of a seventh of the width
+        const BOTTOM: [(f32, [u8; 3]); 8] = [
+            (1.25, [0, 33, 76]),
+            (2.5, [255, 255, 255]),
+            (3.75, [50, 0, 106]),
+            (5.0, [19, 19, 19]),
+            (5.0 + 1.0 / 3.0, [9, 9, 9]),
+            (5.0 + 2.0 / 3.0, [19, 19, 19]),
+            (6.0, [29, 29, 29]),
+            (7.0, [19, 19, 19]),
+        ];
+
+        let rgb = |[r, g, b]: [u8; 3]| P::from_rgba8([r, g, b, 255]);
+        let (top, middle): (Vec<P>, Vec<P>) = (TOP.map(rgb).to_vec(), MIDDLE.map(rgb).to_vec());
+        let bottom: Vec<(f32, P)> = BOTTOM.iter().map(|&(edge, c)| (edge, rgb(c))).collect();
+
+        Self::generate(width, height, |x, y| {
+            let column = 7.0 * x as f32 / width as f32;
+            let row = y as f32 / height as f32;
+            if row < 2.0 / 3.0 {
+                top[(column as usize).min(6)]
+            } else if row < 0.75 {
+                middle[(column as usize).min(6)]
+            } else {
+                bottom
+                    .iter()
+                    .find(|(edge, _)| column < *edge)
+                    .map_or(bottom[7].1, |&(_, color)| color)
+            }
+        })
+    }
+}
@@ -0,0 +1,101 @@
+use super::{Lab, Luma, LumaA, LumaU8, LumaU16, Pixel, Rgba, RgbaU8, YCbCr};
+
+/// Conversion from pixels of type `P`, used by [`crate::img::Image::convert`]. Every pixel type
+/// converts from itself and from every other pixel type.
+///
+/// Conversions to gray take the luminance of the color, see [`Pixel::luminance`], and gray turns
+/// into a color of equal channels. Other color spaces convert through sRGB. Alpha is kept where both types have it, dropped where the
+/// target has none and opaque where the source has none. Integer channels are rounded to the
+/// nearest level.
+pub trait FromPixel<P: Pixel>: Pixel {
+    fn from_pixel(pixel: &P) -> Self;
+}
+
+impl<P: Pixel> FromPixel<P> for P {
+    fn from_pixel(pixel: &P) -> Self {
+        *pixel
+    }
+}
+
+/// Returns the alpha of the pixel, 1.0 for pixel types without alpha.
+fn alpha<P: Pixel>(pixel: &P) -> f32 {
+    P::alpha_channel().map_or(1.0, |c| pixel.channel(c))
+}
+
+/// Converts through the luminance, for conversions from or to gray.
+fn via_luminance<P: Pixel, Q: Pixel>(pixel: &P) -> Q {
+    let mut out = Q::from_luminance(pixel.luminance());
+    if let Some(c) = Q::alpha_channel() {
+        out.set_channel(c, alpha(pixel));
+    }
+    out
+}
+
+/// Copies the channels, for conversions between types with the same channels.
+fn by_channel<P: Pixel, Q: Pixel>(pixel: &P) -> Q {
+    let mut out = Q::new();
+    for c in 0..Q::channel_count() {
+        out.set_channel(c, pixel.channel(c));
+    }
+    out
+}
+
+/// Converts through [`Rgba`], for color spaces converting to and from sRGB.
+fn via_rgba<P: Pixel, Q: FromPixel<Rgba>>(pixel: &P) -> Q
+where
+    Rgba: FromPixel<P>,
+{
+    Q::from_pixel(&Rgba::from_pixel(pixel))
+}
+
+fn rgba_to_lab(pixel: &Rgba) -> Lab {
+    Lab::from_rgba(pixel)
+}
+
+fn lab_to_rgba(pixel: &Lab) -> Rgba {
+    pixel.to_rgba(1.0)
+}
+
+fn rgba_to_ycbcr(pixel: &Rgba) -> YCbCr {
+    YCbCr::from_rgba(pixel)
+}
+
+fn ycbcr_to_rgba(pixel: &YCbCr) -> Rgba {
+    pixel.to_rgba(1.0)
+}
+
+macro_rules! from_pixel {
+    ($convert:ident: $($from:ty => $to:ty),+ $(,)?) => {
+        $(
+            impl FromPixel<$from> for $to {
+                fn from_pixel(pixel: &$from) -> Self {
+                    $convert(pixel)
+                }
+            }
+        )+
+    };
+}
+
+from_pixel!(by_channel: Rgba => RgbaU8, RgbaU8 => Rgba);
+from_pixel!(rgba_to_lab: Rgba => Lab);
+from_pixel!(lab_to_rgba: Lab => Rgba);
+from_pixel!(rgba_to_ycbcr: Rgba => YCbCr);
+from_pixel!(ycbcr_to_rgba: YCbCr => Rgba);
+from_pixel!(
+    via_rgba:
+    RgbaU8 => Lab, Lab => RgbaU8,
+    RgbaU8 => YCbCr, YCbCr => RgbaU8, Lab => YCbCr, YCbCr => Lab,
+);
+from_pixel!(
+    via_luminance:
+    Rgba => Luma, Rgba => LumaA, Rgba => LumaU8, Rgba => LumaU16,
+    RgbaU8 => Luma, RgbaU8 => LumaA, RgbaU8 => LumaU8, RgbaU8 => LumaU16,
+    Luma => Rgba, Luma => RgbaU8, Luma => LumaA, Luma => LumaU8, Luma => LumaU16,
+    LumaA => Rgba, LumaA => RgbaU8, LumaA => Luma, LumaA => LumaU8, LumaA => LumaU16,
+    LumaU8 => Rgba, LumaU8 => RgbaU8, LumaU8 => Luma, LumaU8 => LumaA, LumaU8 => LumaU16,
+    LumaU16 => Rgba, LumaU16 => RgbaU8, LumaU16 => Luma, LumaU16 => LumaA, LumaU16 => LumaU8,
+    Lab => Luma, Lab => LumaA, Lab => LumaU8, Lab => LumaU16,
+    Luma => Lab, LumaA => Lab, LumaU8 => Lab, LumaU16 => Lab,
+    YCbCr => Luma, YCbCr => LumaA, YCbCr => LumaU8, YCbCr => LumaU16,
+    Luma => YCbCr, LumaA => YCbCr, LumaU8 => YCbCr, LumaU16 => YCbCr,
+);
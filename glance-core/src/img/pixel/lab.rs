@@ -0,0 +1,195 @@
+use super::{LUMINANCE_WEIGHTS, Pixel, Rgba, linear_to_srgb, srgb_to_linear};
+
+/// D65 reference white in CIE XYZ.
+const WHITE_D65: (f32, f32, f32) = (0.950_47, 1.0, 1.088_83);
+
+/// Offset and scale of a* and b* in [`Pixel::channel`], mapping [-128.0, 128.0] to [0.0, 1.0].
+const AB_OFFSET: f32 = 128.0;
+const AB_SCALE: f32 = 256.0;
+
+/// A color in the CIE L*a*b* space (D65 white point), where Euclidean distances roughly match
+/// perceived differences. L is in [0.0, 100.0], a and b are roughly in [-128.0, 127.0].
+///
+/// As a [`Pixel`] the channels are rescaled to [0.0, 1.0], L by 1/100 and a and b by 1/256
+/// around 0.5, so that generic operations like blurs run in Lab space. Lab has no alpha.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+}
+
+impl Lab {
+    /// Converts the sRGB color channels of an [`Rgba`] pixel to L*a*b*. Alpha is ignored.
+    pub fn from_rgba(pixel: &Rgba) -> Self {
+        let (r, g, b) = (
+            srgb_to_linear(pixel.r),
+            srgb_to_linear(pixel.g),
+            srgb_to_linear(pixel.b),
+        );
+        let x = 0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b;
+        let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b;
+        let z = 0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b;
+
+        let f = |t: f32| {
+            if t > 216.0 / 24389.0 {
+                t.cbrt()
+            } else {
+                (24389.0 / 27.0 * t + 16.0) / 116.0
+            }
+        };
+        let (fx, fy, fz) = (f(x / WHITE_D65.0), f(y / WHITE_D65.1), f(z / WHITE_D65.2));
+
+        Lab {
+            l: 116.0 * fy - 16.0,
+            a: 500.0 * (fx - fy),
+            b: 200.0 * (fy - fz),
+        }
+    }
+
+    /// Converts the L*a*b* color back to an sRGB [`Rgba`] pixel with the given alpha.
+    /// Colors outside the sRGB gamut are clamped.
+    pub fn to_rgba(&self, alpha: f32) -> Rgba {
+        let fy = (self.l + 16.0) / 116.0;
+        let (fx, fz) = (fy + self.a / 500.0, fy - self.b / 200.0);
+        let f_inv = |t: f32| {
+            if t > 6.0 / 29.0 {
+                t * t * t
+            } else {
+                (116.0 * t - 16.0) * 27.0 / 24389.0
+            }
+        };
+        let (x, y, z) = (
+            f_inv(fx) * WHITE_D65.0,
+            f_inv(fy) * WHITE_D65.1,
+            f_inv(fz) * WHITE_D65.2,
+        );
+
+        let r = 3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z;
+        let g = -0.969_266 * x + 1.876_010_8 * y + 0.041_556 * z;
+        let b = 0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z;
+        let encode = |v: f32| linear_to_srgb(v.clamp(0.0, 1.0));
+
+        Rgba {
+            r: encode(r),
+            g: encode(g),
+            b: encode(b),
+            a: alpha,
+        }
+    }
+
+    /// Returns the CIEDE2000 color difference (delta E) to `other`. A difference around 1.0 is
+    /// just noticeable, differences above 5.0 are clearly visible.
+    pub fn delta_e(&self, other: &Lab) -> f32 {
+        let (l1, a1, b1) = (self.l as f64, self.a as f64, self.b as f64);
+        let (l2, a2, b2) = (other.l as f64, other.a as f64, other.b as f64);
+
+        // Rescale a* to compensate for the poor hue spacing of Lab near the neutral axis
+        let c_mean = (a1.hypot(b1) + a2.hypot(b2)) / 2.0;
+        let c7 = c_mean.powi(7);
+        let g = 0.5 * (1.0 - (c7 / (c7 + 25f64.powi(7))).sqrt());
+        let (a1, a2) = (a1 * (1.0 + g), a2 * (1.0 + g));
+        let (c1, c2) = (a1.hypot(b1), a2.hypot(b2));
+        let hue = |a: f64, b: f64| {
+            if a == 0.0 && b == 0.0 {
+                0.0
+            } else {
+                b.atan2(a).to_degrees().rem_euclid(360.0)
+            }
+        };
+        let (h1, h2) = (hue(a1, b1), hue(a2, b2));
+
+        let dl = l2 - l1;
+        let dc = c2 - c1;
+        let dh = if c1 * c2 == 0.0 {
+            0.0
+        } else if (h2 - h1).abs() <= 180.0 {
+            h2 - h1
+        } else if h2 <= h1 {
+            h2 - h1 + 360.0
+        } else {
+            h2 - h1 - 360.0
+        };
+        let dh_big = 2.0 * (c1 * c2).sqrt() * (dh / 2.0).to_radians().sin();
+
+        let l_mean = (l1 + l2) / 2.0;
+        let c_mean = (c1 + c2) / 2.0;
+        let h_mean = if c1 * c2 == 0.0 {
+            h1 + h2
+        } else if (h1 - h2).abs() <= 180.0 {
+            (h1 + h2) / 2.0
+        } else if h1 + h2 < 360.0 {
+            (h1 + h2 + 360.0) / 2.0
+        } else {
+            (h1 + h2 - 360.0) / 2.0
+        };
+
+        let t = 1.0 - 0.17 * (h_mean - 30.0).to_radians().cos()
+            + 0.24 * (2.0 * h_mean).to_radians().cos()
+            + 0.32 * (3.0 * h_mean + 6.0).to_radians().cos()
+            - 0.20 * (4.0 * h_mean - 63.0).to_radians().cos();
+        let d_theta = 30.0 * (-((h_mean - 275.0) / 25.0).powi(2)).exp();
+        let c7 = c_mean.powi(7);
+        let rc = 2.0 * (c7 / (c7 + 25f64.powi(7))).sqrt();
+        let sl = 1.0 + 0.015 * (l_mean - 50.0).powi(2) / (20.0 + (l_mean - 50.0).powi(2)).sqrt();
+        let sc = 1.0 + 0.045 * c_mean;
+        let sh = 1.0 + 0.015 * c_mean * t;
+        let rt = -(2.0 * d_theta).to_radians().sin() * rc;
+
+        let (tl, tc, th) = (dl / sl, dc / sc, dh_big / sh);
+        (tl * tl + tc * tc + th * th + rt * tc * th).sqrt() as f32
+    }
+}
+
+impl Pixel for Lab {
+    type Scalar = f32;
+
+    fn channel_count() -> usize {
+        3
+    }
+
+    fn new() -> Self {
+        Lab {
+            l: 0.0,
+            a: 0.0,
+            b: 0.0,
+        }
+    }
+
+    fn from_rgba8(rgba: [u8; 4]) -> Self {
+        Lab::from_rgba(&Rgba::from_rgba8(rgba))
+    }
+
+    fn to_rgba8_with(&self, quantize: impl Fn(f32) -> u8) -> [u8; 4] {
+        self.to_rgba(1.0).to_rgba8_with(quantize)
+    }
+
+    fn channel(&self, i: usize) -> f32 {
+        match i {
+            0 => self.l / 100.0,
+            1 => (self.a + AB_OFFSET) / AB_SCALE,
+            2 => (self.b + AB_OFFSET) / AB_SCALE,
+            _ => panic!("Channel index {i} is out of bounds for Lab"),
+        }
+    }
+
+    fn set_channel(&mut self, i: usize, value: f32) {
+        match i {
+            0 => self.l = value * 100.0,
+            1 => self.a = value * AB_SCALE - AB_OFFSET,
+            2 => self.b = value * AB_SCALE - AB_OFFSET,
+            _ => panic!("Channel index {i} is out of bounds for Lab"),
+        }
+    }
+
+    fn luminance(&self) -> f32 {
+        let [wr, wg, wb] = LUMINANCE_WEIGHTS;
+        let rgba = self.to_rgba(1.0);
+        wr * rgba.r + wg * rgba.g + wb * rgba.b
+    }
+
+    fn from_luminance(luminance: f32) -> Self {
+        Lab::from_rgba(&Rgba::from_luminance(luminance))
+    }
+}
@@ -0,0 +1,93 @@
+use super::{Luma, LumaA, Pixel, Rgba, linear_to_srgb, srgb_to_linear};
+use crate::img::Image;
+use crate::par::*;
+
+/// Float pixel types whose color channels hold gamma-encoded sRGB values, the encoding of
+/// images as loaded from files. See [`Linear`] for their linear-light counterpart.
+pub trait SrgbPixel: Pixel {}
+
+impl SrgbPixel for Rgba {}
+impl SrgbPixel for Luma {}
+impl SrgbPixel for LumaA {}
+
+/// A pixel of type `P` whose color channels hold linear light instead of sRGB values, e.g.
+/// `Image<Linear<Rgba>>` from [`Image::to_linear`]. Averaging, blending and blurring are only
+/// physically correct in linear light, so operations expecting it can ask for `Linear` pixels
+/// in their signature. Alpha is the same in both encodings.
+///
+/// Channels are accessed like those of `P`, and converting to 8 bits (for saving or display)
+/// encodes them to sRGB, so linear images look the same as their sRGB originals.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Linear<P: SrgbPixel>(pub P);
+
+impl<P: SrgbPixel> Linear<P> {
+    /// Decodes an sRGB pixel to linear light.
+    pub fn from_srgb(pixel: &P) -> Self {
+        Linear(pixel.map_colors(srgb_to_linear))
+    }
+
+    /// Encodes the pixel back to sRGB.
+    pub fn to_srgb(&self) -> P {
+        self.0.map_colors(linear_to_srgb)
+    }
+}
+
+impl<P: SrgbPixel> Pixel for Linear<P> {
+    type Scalar = P::Scalar;
+
+    fn channel_count() -> usize {
+        P::channel_count()
+    }
+
+    fn alpha_channel() -> Option<usize> {
+        P::alpha_channel()
+    }
+
+    fn new() -> Self {
+        Linear(P::new())
+    }
+
+    fn from_rgba8(rgba: [u8; 4]) -> Self {
+        Linear::from_srgb(&P::from_rgba8(rgba))
+    }
+
+    fn to_rgba8_with(&self, quantize: impl Fn(f32) -> u8) -> [u8; 4] {
+        self.to_srgb().to_rgba8_with(quantize)
+    }
+
+    fn channel(&self, i: usize) -> f32 {
+        self.0.channel(i)
+    }
+
+    fn set_channel(&mut self, i: usize, value: f32) {
+        self.0.set_channel(i, value);
+    }
+
+    /// Returns the perceived brightness, that of the sRGB encoded pixel.
+    fn luminance(&self) -> f32 {
+        self.to_srgb().luminance()
+    }
+
+    fn from_luminance(luminance: f32) -> Self {
+        Linear::from_srgb(&P::from_luminance(luminance))
+    }
+}
+
+impl<P: SrgbPixel> Image<P> {
+    /// Decodes the sRGB values of the image to linear light, see [`Linear`].
+    pub fn to_linear(&self) -> Image<Linear<P>> {
+        let (width, height) = self.dimensions();
+        let data = self.par_pixels().map(Linear::from_srgb).collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+}
+
+impl<P: SrgbPixel> Image<Linear<P>> {
+    /// Encodes the linear-light values of the image back to sRGB.
+    pub fn to_srgb(&self) -> Image<P> {
+        let (width, height) = self.dimensions();
+        let data = self.par_pixels().map(Linear::to_srgb).collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+}
@@ -1,12 +1,21 @@
-use super::Pixel;
+use super::{LUMINANCE_WEIGHTS, Pixel};
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Luma {
     pub l: f32,
 }
 
+impl Luma {
+    /// Creates an intensity in [0.0, 1.0].
+    pub const fn new(l: f32) -> Self {
+        Luma { l }
+    }
+}
+
 impl Pixel for Luma {
+    type Scalar = f32;
+
     fn channel_count() -> usize {
         1
     }
@@ -16,14 +25,36 @@ impl Pixel for Luma {
     }
 
     fn from_rgba8(rgba: [u8; 4]) -> Self {
+        let [wr, wg, wb] = LUMINANCE_WEIGHTS;
         Luma {
-            l: (0.299f32 * rgba[0] as f32 + 0.587f32 * rgba[1] as f32 + 0.114f32 * rgba[2] as f32)
-                / 255.0,
+            l: (wr * rgba[0] as f32 + wg * rgba[1] as f32 + wb * rgba[2] as f32) / 255.0,
         }
     }
 
-    fn to_rgba8(&self) -> [u8; 4] {
-        let l = (self.l * 255.0).round() as u8;
+    fn to_rgba8_with(&self, quantize: impl Fn(f32) -> u8) -> [u8; 4] {
+        let l = quantize(self.l);
         [l, l, l, 255]
     }
+
+    fn channel(&self, i: usize) -> f32 {
+        match i {
+            0 => self.l,
+            _ => panic!("Channel index {i} is out of bounds for Luma"),
+        }
+    }
+
+    fn set_channel(&mut self, i: usize, value: f32) {
+        match i {
+            0 => self.l = value,
+            _ => panic!("Channel index {i} is out of bounds for Luma"),
+        }
+    }
+
+    fn luminance(&self) -> f32 {
+        self.l
+    }
+
+    fn from_luminance(luminance: f32) -> Self {
+        Luma { l: luminance }
+    }
 }
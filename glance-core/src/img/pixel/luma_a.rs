@@ -0,0 +1,68 @@
+use super::{LUMINANCE_WEIGHTS, Pixel};
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LumaA {
+    pub l: f32,
+    pub a: f32,
+}
+
+impl LumaA {
+    /// Creates an intensity with alpha, both in [0.0, 1.0].
+    pub const fn new(l: f32, a: f32) -> Self {
+        LumaA { l, a }
+    }
+}
+
+impl Pixel for LumaA {
+    type Scalar = f32;
+
+    fn channel_count() -> usize {
+        2
+    }
+
+    fn alpha_channel() -> Option<usize> {
+        Some(1)
+    }
+
+    fn new() -> Self {
+        LumaA { l: 0.0, a: 1.0 }
+    }
+
+    fn from_rgba8(rgba: [u8; 4]) -> Self {
+        let [wr, wg, wb] = LUMINANCE_WEIGHTS;
+        LumaA {
+            l: (wr * rgba[0] as f32 + wg * rgba[1] as f32 + wb * rgba[2] as f32) / 255.0,
+            a: rgba[3] as f32 / 255.0,
+        }
+    }
+
+    fn to_rgba8_with(&self, quantize: impl Fn(f32) -> u8) -> [u8; 4] {
+        let l = quantize(self.l);
+        [l, l, l, quantize(self.a)]
+    }
+
+    fn channel(&self, i: usize) -> f32 {
+        match i {
+            0 => self.l,
+            1 => self.a,
+            _ => panic!("Channel index {i} is out of bounds for LumaA"),
+        }
+    }
+
+    fn set_channel(&mut self, i: usize, value: f32) {
+        match i {
+            0 => self.l = value,
+            1 => self.a = value,
+            _ => panic!("Channel index {i} is out of bounds for LumaA"),
+        }
+    }
+
+    fn luminance(&self) -> f32 {
+        self.l
+    }
+
+    fn from_luminance(luminance: f32) -> Self {
+        LumaA::new(luminance, 1.0)
+    }
+}
@@ -0,0 +1,77 @@
+use super::{LUMINANCE_WEIGHTS, Pixel, to_u16};
+
+/// A 16 bit intensity, half the memory of [`super::Luma`], for 16 bit grayscale files such as
+/// depth maps and scientific images, which are read and written without losing precision.
+/// Values written through [`Pixel::set_channel`] are rounded to the nearest level.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LumaU16 {
+    pub l: u16,
+}
+
+impl LumaU16 {
+    /// Creates a 16 bit intensity.
+    pub const fn new(l: u16) -> Self {
+        LumaU16 { l }
+    }
+}
+
+impl Pixel for LumaU16 {
+    type Scalar = u16;
+
+    fn channel_count() -> usize {
+        1
+    }
+
+    fn new() -> Self {
+        LumaU16 { l: 0 }
+    }
+
+    fn bit_depth() -> u32 {
+        16
+    }
+
+    fn from_rgba8(rgba: [u8; 4]) -> Self {
+        Self::from_rgba16(rgba.map(|v| v as u16 * 257))
+    }
+
+    fn from_rgba16(rgba: [u16; 4]) -> Self {
+        let [wr, wg, wb] = LUMINANCE_WEIGHTS;
+        LumaU16 {
+            l: to_u16((wr * rgba[0] as f32 + wg * rgba[1] as f32 + wb * rgba[2] as f32) / 65535.0),
+        }
+    }
+
+    fn to_rgba8_with(&self, quantize: impl Fn(f32) -> u8) -> [u8; 4] {
+        let l = quantize(self.luminance());
+        [l, l, l, 255]
+    }
+
+    fn to_rgba16(&self) -> [u16; 4] {
+        [self.l, self.l, self.l, u16::MAX]
+    }
+
+    fn channel(&self, i: usize) -> f32 {
+        match i {
+            0 => self.luminance(),
+            _ => panic!("Channel index {i} is out of bounds for LumaU16"),
+        }
+    }
+
+    fn set_channel(&mut self, i: usize, value: f32) {
+        match i {
+            0 => self.l = to_u16(value),
+            _ => panic!("Channel index {i} is out of bounds for LumaU16"),
+        }
+    }
+
+    fn luminance(&self) -> f32 {
+        self.l as f32 / 65535.0
+    }
+
+    fn from_luminance(luminance: f32) -> Self {
+        LumaU16 {
+            l: to_u16(luminance),
+        }
+    }
+}
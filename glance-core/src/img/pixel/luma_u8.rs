@@ -0,0 +1,68 @@
+use super::{LUMINANCE_WEIGHTS, Pixel, to_u8};
+
+/// An 8 bit intensity, a quarter of the memory of [`super::Luma`]. Values written through
+/// [`Pixel::set_channel`] are rounded to the nearest level.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LumaU8 {
+    pub l: u8,
+}
+
+impl LumaU8 {
+    /// Creates an 8 bit intensity.
+    pub const fn new(l: u8) -> Self {
+        LumaU8 { l }
+    }
+}
+
+impl Pixel for LumaU8 {
+    type Scalar = u8;
+
+    fn channel_count() -> usize {
+        1
+    }
+
+    fn new() -> Self {
+        LumaU8 { l: 0 }
+    }
+
+    fn from_rgba8(rgba: [u8; 4]) -> Self {
+        let [wr, wg, wb] = LUMINANCE_WEIGHTS;
+        LumaU8 {
+            l: to_u8((wr * rgba[0] as f32 + wg * rgba[1] as f32 + wb * rgba[2] as f32) / 255.0),
+        }
+    }
+
+    fn to_rgba8_with(&self, quantize: impl Fn(f32) -> u8) -> [u8; 4] {
+        let l = quantize(self.l as f32 / 255.0);
+        [l, l, l, 255]
+    }
+
+    fn to_rgba8(&self) -> [u8; 4] {
+        [self.l, self.l, self.l, 255]
+    }
+
+    fn channel(&self, i: usize) -> f32 {
+        match i {
+            0 => self.l as f32 / 255.0,
+            _ => panic!("Channel index {i} is out of bounds for LumaU8"),
+        }
+    }
+
+    fn set_channel(&mut self, i: usize, value: f32) {
+        match i {
+            0 => self.l = to_u8(value),
+            _ => panic!("Channel index {i} is out of bounds for LumaU8"),
+        }
+    }
+
+    fn luminance(&self) -> f32 {
+        self.l as f32 / 255.0
+    }
+
+    fn from_luminance(luminance: f32) -> Self {
+        LumaU8 {
+            l: to_u8(luminance),
+        }
+    }
+}
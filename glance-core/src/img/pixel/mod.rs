@@ -1,15 +1,175 @@
 //! This module provides traits and types for working with different pixel formats
 //! It assumes a base pixel format of RGBA8, and allows conversion to and from that format.
+//! Pixels store f32 channels, except for [`RgbaU8`], [`LumaU8`] and [`LumaU16`], which store
+//! integers to save memory and keep 8 and 16 bit files lossless.
+//!
+//! Color channels of [`Rgba`], [`Luma`] and [`LumaA`] are sRGB encoded, while [`Linear`] pixels
+//! hold linear light.
+//!
+//! Float channels are converted to 8 bits by rounding to the nearest level after clamping to
+//! [0.0, 1.0], so out of range (e.g. HDR) values saturate instead of wrapping. See
+//! [`Quantization`] for dithering instead.
 
+/// Conversion policy from float channel values to 8 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Quantization {
+    /// Rounds to the nearest level.
+    #[default]
+    Round,
+    /// Ordered dithering with a 4x4 Bayer matrix, which trades banding in smooth gradients for a
+    /// fine, regular pattern. Every level is still reached exactly by its float value.
+    Dither,
+}
+
+/// Thresholds of the 4x4 Bayer matrix in sixteenths.
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+impl Quantization {
+    /// Converts a channel value of the pixel at `position` to 8 bits.
+    pub fn quantize(&self, value: f32, position: (usize, usize)) -> u8 {
+        match self {
+            Quantization::Round => to_u8(value),
+            Quantization::Dither => {
+                let threshold = BAYER_4X4[position.1 % 4][position.0 % 4] as f32 + 0.5;
+                quantize_with_offset(value, threshold / 16.0)
+            }
+        }
+    }
+}
+
+/// Converts a channel value in [0.0, 1.0] to 8 bits, rounding to the nearest level. Values out
+/// of range are clamped and NaN maps to 0.
+pub fn to_u8(value: f32) -> u8 {
+    quantize_with_offset(value, 0.5)
+}
+
+/// Converts a channel value in [0.0, 1.0] to 16 bits, rounding to the nearest level. Values out
+/// of range are clamped and NaN maps to 0.
+pub fn to_u16(value: f32) -> u16 {
+    (value.clamp(0.0, 1.0) * 65535.0 + 0.5).floor() as u16
+}
+
+/// Converts a gamma-encoded sRGB channel value in [0.0, 1.0] to linear light.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a linear-light channel value in [0.0, 1.0] to gamma-encoded sRGB.
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Scales the clamped value to [0.0, 255.0] and rounds down after adding `offset` in [0.0, 1.0).
+fn quantize_with_offset(value: f32, offset: f32) -> u8 {
+    // Casting saturates, and NaN becomes 0
+    (value.clamp(0.0, 1.0) * 255.0 + offset).floor() as u8
+}
+
+/// BT.601 weights of red, green and blue in the luminance of a color, the same ones used by
+/// [`Luma::from_rgba8`].
+pub const LUMINANCE_WEIGHTS: [f32; 3] = [0.299, 0.587, 0.114];
+
+/// A pixel format. Channels are accessed as f32 in [0.0, 1.0] regardless of how they are stored,
+/// so operations written against this trait work for every pixel type.
 pub trait Pixel: PartialEq + Copy + Clone + Send + Sync + 'static {
+    /// Storage type of a single channel
+    type Scalar: Copy + Send + Sync + 'static;
+
     fn channel_count() -> usize;
+    /// Returns the index of the alpha channel, or None for pixel types without alpha.
+    fn alpha_channel() -> Option<usize> {
+        None
+    }
     fn new() -> Self;
+    /// Bits per channel stored by the pixel. Pixels of more than 8 bits are read from and
+    /// written to files with 16 bits per channel where the format supports it.
+    fn bit_depth() -> u32 {
+        8
+    }
     fn from_rgba8(rgba: [u8; 4]) -> Self;
-    fn to_rgba8(&self) -> [u8; 4];
+    /// Converts from 16 bit RGBA, by default through [`Pixel::from_rgba8`].
+    fn from_rgba16(rgba: [u16; 4]) -> Self {
+        Self::from_rgba8(rgba.map(|v| ((v as u32 * 255 + 32767) / 65535) as u8))
+    }
+    /// Converts the pixel to 16 bit RGBA, by default through [`Pixel::to_rgba8`].
+    fn to_rgba16(&self) -> [u16; 4] {
+        self.to_rgba8().map(|v| v as u16 * 257)
+    }
+    /// Converts the pixel to 8 bit RGBA, quantizing every channel value with `quantize`.
+    fn to_rgba8_with(&self, quantize: impl Fn(f32) -> u8) -> [u8; 4];
+    /// Converts the pixel to 8 bit RGBA with [`to_u8`].
+    fn to_rgba8(&self) -> [u8; 4] {
+        self.to_rgba8_with(to_u8)
+    }
+    /// Returns the value of channel `i`, where `i < channel_count()`.
+    fn channel(&self, i: usize) -> f32;
+    /// Sets the value of channel `i`, where `i < channel_count()`.
+    fn set_channel(&mut self, i: usize, value: f32);
+    /// Returns the brightness of the pixel as perceived, see [`LUMINANCE_WEIGHTS`].
+    fn luminance(&self) -> f32;
+    /// Returns an opaque gray pixel of the given luminance.
+    fn from_luminance(luminance: f32) -> Self;
+
+    /// Applies `f` to every channel (including alpha).
+    fn map(&self, f: impl Fn(f32) -> f32) -> Self {
+        let mut pixel = *self;
+        for c in 0..Self::channel_count() {
+            pixel.set_channel(c, f(self.channel(c)));
+        }
+        pixel
+    }
+
+    /// Applies `f` to every channel except alpha, see [`Pixel::alpha_channel`].
+    fn map_colors(&self, f: impl Fn(f32) -> f32) -> Self {
+        let mut pixel = *self;
+        for c in (0..Self::channel_count()).filter(|&c| Some(c) != Self::alpha_channel()) {
+            pixel.set_channel(c, f(self.channel(c)));
+        }
+        pixel
+    }
+
+    /// Combines the channels of two pixels pairwise with `f`.
+    fn zip_map(&self, other: &Self, f: impl Fn(f32, f32) -> f32) -> Self {
+        let mut pixel = *self;
+        for c in 0..Self::channel_count() {
+            pixel.set_channel(c, f(self.channel(c), other.channel(c)));
+        }
+        pixel
+    }
+
+    /// Linearly interpolates every channel towards `other`, returning `self` for `t` = 0.0 and
+    /// `other` for `t` = 1.0.
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        self.zip_map(other, |a, b| a + t * (b - a))
+    }
 }
 
+mod convert;
+pub mod lab;
+pub mod linear;
 pub mod luma;
+pub mod luma_a;
+pub mod luma_u16;
+pub mod luma_u8;
 pub mod rgba;
+pub mod rgba_u8;
+pub mod ycbcr;
 
+pub use convert::FromPixel;
+pub use lab::*;
+pub use linear::*;
 pub use luma::*;
+pub use luma_a::*;
+pub use luma_u8::*;
+pub use luma_u16::*;
 pub use rgba::*;
+pub use rgba_u8::*;
+pub use ycbcr::*;
@@ -1,7 +1,8 @@
-use super::Pixel;
+use super::{LUMINANCE_WEIGHTS, Pixel};
+use crate::{CoreError, Result};
 
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Rgba {
     pub r: f32,
     pub g: f32,
@@ -9,11 +10,46 @@ pub struct Rgba {
     pub a: f32,
 }
 
+impl Rgba {
+    /// Creates a color from its channels in [0.0, 1.0].
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Rgba { r, g, b, a }
+    }
+
+    /// Parses a hex color of the form `#RGB`, `#RGBA`, `#RRGGBB` or `#RRGGBBAA`, with or without
+    /// the leading `#`. Colors without alpha are opaque.
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let invalid = || CoreError::InvalidData(format!("Invalid hex color '{hex}'"));
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.is_ascii() {
+            return Err(invalid());
+        }
+        let width = match digits.len() {
+            3 | 4 => 1,
+            6 | 8 => 2,
+            _ => return Err(invalid()),
+        };
+        let mut channels = [255u8; 4];
+        for (channel, i) in channels.iter_mut().zip((0..digits.len()).step_by(width)) {
+            let value = u8::from_str_radix(&digits[i..i + width], 16).map_err(|_| invalid())?;
+            // A single digit stands for itself repeated, #f80 is #ff8800
+            *channel = if width == 1 { value * 17 } else { value };
+        }
+        Ok(Rgba::from(channels))
+    }
+}
+
 impl Pixel for Rgba {
+    type Scalar = f32;
+
     fn channel_count() -> usize {
         4
     }
 
+    fn alpha_channel() -> Option<usize> {
+        Some(3)
+    }
+
     fn new() -> Self {
         Rgba {
             r: 0.0,
@@ -31,14 +67,44 @@ impl Pixel for Rgba {
             a: rgba[3] as f32 / 255.0,
         }
     }
-    fn to_rgba8(&self) -> [u8; 4] {
+
+    fn to_rgba8_with(&self, quantize: impl Fn(f32) -> u8) -> [u8; 4] {
         [
-            (self.r * 255.0) as u8,
-            (self.g * 255.0) as u8,
-            (self.b * 255.0) as u8,
-            (self.a * 255.0) as u8,
+            quantize(self.r),
+            quantize(self.g),
+            quantize(self.b),
+            quantize(self.a),
         ]
     }
+
+    fn channel(&self, i: usize) -> f32 {
+        match i {
+            0 => self.r,
+            1 => self.g,
+            2 => self.b,
+            3 => self.a,
+            _ => panic!("Channel index {i} is out of bounds for Rgba"),
+        }
+    }
+
+    fn set_channel(&mut self, i: usize, value: f32) {
+        match i {
+            0 => self.r = value,
+            1 => self.g = value,
+            2 => self.b = value,
+            3 => self.a = value,
+            _ => panic!("Channel index {i} is out of bounds for Rgba"),
+        }
+    }
+
+    fn luminance(&self) -> f32 {
+        let [wr, wg, wb] = LUMINANCE_WEIGHTS;
+        wr * self.r + wg * self.g + wb * self.b
+    }
+
+    fn from_luminance(luminance: f32) -> Self {
+        Rgba::new(luminance, luminance, luminance, 1.0)
+    }
 }
 
 impl From<[u8; 4]> for Rgba {
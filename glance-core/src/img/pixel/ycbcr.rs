@@ -0,0 +1,129 @@
+use super::{LUMINANCE_WEIGHTS, Pixel, Rgba};
+use crate::img::Image;
+
+/// A color as luma and blue and red difference chroma, full range BT.601 like in JPEG. All
+/// channels are in [0.0, 1.0], with neutral chroma at 0.5, and luma equals
+/// [`Pixel::luminance`] of the sRGB color. YCbCr has no alpha.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct YCbCr {
+    pub y: f32,
+    pub cb: f32,
+    pub cr: f32,
+}
+
+impl YCbCr {
+    /// Creates a color from its luma and chroma.
+    pub const fn new(y: f32, cb: f32, cr: f32) -> Self {
+        YCbCr { y, cb, cr }
+    }
+
+    /// Converts the sRGB color channels of an [`Rgba`] pixel to YCbCr. Alpha is ignored.
+    pub fn from_rgba(pixel: &Rgba) -> Self {
+        let [wr, wg, wb] = LUMINANCE_WEIGHTS;
+        let y = wr * pixel.r + wg * pixel.g + wb * pixel.b;
+        YCbCr {
+            y,
+            cb: 0.5 + (pixel.b - y) / (2.0 * (1.0 - wb)),
+            cr: 0.5 + (pixel.r - y) / (2.0 * (1.0 - wr)),
+        }
+    }
+
+    /// Converts the color back to an [`Rgba`] pixel with the given alpha. Colors outside the
+    /// RGB cube are not clamped.
+    pub fn to_rgba(&self, alpha: f32) -> Rgba {
+        let [wr, wg, wb] = LUMINANCE_WEIGHTS;
+        let r = self.y + 2.0 * (1.0 - wr) * (self.cr - 0.5);
+        let b = self.y + 2.0 * (1.0 - wb) * (self.cb - 0.5);
+        let g = (self.y - wr * r - wb * b) / wg;
+        Rgba::new(r, g, b, alpha)
+    }
+}
+
+impl Pixel for YCbCr {
+    type Scalar = f32;
+
+    fn channel_count() -> usize {
+        3
+    }
+
+    fn new() -> Self {
+        YCbCr::new(0.0, 0.5, 0.5)
+    }
+
+    fn from_rgba8(rgba: [u8; 4]) -> Self {
+        YCbCr::from_rgba(&Rgba::from_rgba8(rgba))
+    }
+
+    fn to_rgba8_with(&self, quantize: impl Fn(f32) -> u8) -> [u8; 4] {
+        self.to_rgba(1.0).to_rgba8_with(quantize)
+    }
+
+    fn channel(&self, i: usize) -> f32 {
+        match i {
+            0 => self.y,
+            1 => self.cb,
+            2 => self.cr,
+            _ => panic!("Channel index {i} is out of bounds for YCbCr"),
+        }
+    }
+
+    fn set_channel(&mut self, i: usize, value: f32) {
+        match i {
+            0 => self.y = value,
+            1 => self.cb = value,
+            2 => self.cr = value,
+            _ => panic!("Channel index {i} is out of bounds for YCbCr"),
+        }
+    }
+
+    fn luminance(&self) -> f32 {
+        self.y
+    }
+
+    fn from_luminance(luminance: f32) -> Self {
+        YCbCr::new(luminance, 0.5, 0.5)
+    }
+}
+
+/// Chroma resolution of [`Image::subsample_chroma`], in the J:a:b notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChromaSubsampling {
+    /// Full chroma resolution
+    Yuv444,
+    /// Half the horizontal chroma resolution
+    Yuv422,
+    /// Half the horizontal and vertical chroma resolution, as in most JPEG and video files
+    #[default]
+    Yuv420,
+}
+
+impl Image<YCbCr> {
+    /// Simulates storing the chroma at a lower resolution: chroma is averaged over blocks of
+    /// 2x1 or 2x2 pixels, while luma keeps the full resolution. Partial blocks at the right and
+    /// bottom edges average the pixels they have.
+    pub fn subsample_chroma(&self, subsampling: ChromaSubsampling) -> Self {
+        let (block_width, block_height) = match subsampling {
+            ChromaSubsampling::Yuv444 => return self.clone(),
+            ChromaSubsampling::Yuv422 => (2, 1),
+            ChromaSubsampling::Yuv420 => (2, 2),
+        };
+        let (width, height) = self.dimensions();
+        let mut out = self.clone();
+        for y0 in (0..height).step_by(block_height) {
+            for x0 in (0..width).step_by(block_width) {
+                let block: Vec<usize> = (y0..(y0 + block_height).min(height))
+                    .flat_map(|y| (x0..(x0 + block_width).min(width)).map(move |x| y * width + x))
+                    .collect();
+                let n = block.len() as f32;
+                let cb = block.iter().map(|&i| self.data[i].cb).sum::<f32>() / n;
+                let cr = block.iter().map(|&i| self.data[i].cr).sum::<f32>() / n;
+                for &i in &block {
+                    out.data[i].cb = cb;
+                    out.data[i].cr = cr;
+                }
+            }
+        }
+        out
+    }
+}
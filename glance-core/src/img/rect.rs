@@ -0,0 +1,49 @@
+/// An axis aligned rectangle in pixel coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    /// Left edge (inclusive)
+    pub x: usize,
+    /// Top edge (inclusive)
+    pub y: usize,
+    /// Width in pixels
+    pub width: usize,
+    /// Height in pixels
+    pub height: usize,
+}
+
+impl Rect {
+    /// Creates a new [`Rect`] from its top-left corner and size.
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Returns the area of the rectangle in pixels.
+    pub fn area(&self) -> usize {
+        self.width * self.height
+    }
+
+    /// Returns true if the rectangle has no area.
+    pub fn is_empty(&self) -> bool {
+        self.area() == 0
+    }
+
+    /// Returns the part of the rectangle that lies inside an image of the given dimensions.
+    pub fn clip_to(&self, dimensions: (usize, usize)) -> Self {
+        let x = self.x.min(dimensions.0);
+        let y = self.y.min(dimensions.1);
+        let right = (self.x + self.width).min(dimensions.0);
+        let bottom = (self.y + self.height).min(dimensions.1);
+
+        Rect {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! Borrowed rectangular windows of an [`Image`], to read or modify a region of interest in place
+//! instead of cropping a copy of it.
+
+use super::{Image, Rect, pixel::Pixel};
+use crate::par::*;
+use crate::{CoreError, Result};
+
+/// Returns the range of the image buffer covering `rect`, from its first pixel to its last.
+fn window(dimensions: (usize, usize), stride: usize, rect: Rect) -> Result<std::ops::Range<usize>> {
+    if rect.clip_to(dimensions) != rect {
+        return Err(CoreError::OutOfBounds(format!(
+            "{rect:?} is out of bounds for image of size {dimensions:?}"
+        )));
+    }
+    if rect.is_empty() {
+        return Ok(0..0);
+    }
+    let start = rect.y * stride + rect.x;
+    Ok(start..start + (rect.height - 1) * stride + rect.width)
+}
+
+fn out_of_bounds(position: (usize, usize), dimensions: (usize, usize)) -> CoreError {
+    CoreError::OutOfBounds(format!(
+        "{position:#?} is out of bounds for view of size {dimensions:#?}"
+    ))
+}
+
+/// A tile of [`Image::tiles`].
+#[derive(Debug, Clone, Copy)]
+pub struct Tile<'a, P: Pixel> {
+    /// The pixels of the tile including its overlap
+    pub view: ImageView<'a, P>,
+    /// Position and size of `view` in the image
+    pub rect: Rect,
+    /// The part of the tile without the overlap, in image coordinates. The cores of all tiles
+    /// cover the image exactly once.
+    pub core: Rect,
+}
+
+/// Returns the cores of the tiles covering an image of `dimensions`, row by row, and the same
+/// rectangles grown by `overlap` on every side within the image.
+fn tile_rects(
+    dimensions: (usize, usize),
+    tile_size: (usize, usize),
+    overlap: usize,
+) -> Result<Vec<(Rect, Rect)>> {
+    if tile_size.0 == 0 || tile_size.1 == 0 {
+        return Err(CoreError::InvalidData(format!(
+            "Tile size {tile_size:?} must not be zero"
+        )));
+    }
+    let (width, height) = dimensions;
+    Ok((0..height)
+        .step_by(tile_size.1)
+        .flat_map(|y| (0..width).step_by(tile_size.0).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let core = Rect::new(x, y, tile_size.0, tile_size.1).clip_to(dimensions);
+            let (left, top) = (x.saturating_sub(overlap), y.saturating_sub(overlap));
+            let rect = Rect::new(
+                left,
+                top,
+                core.x + core.width + overlap - left,
+                core.y + core.height + overlap - top,
+            )
+            .clip_to(dimensions);
+            (core, rect)
+        })
+        .collect())
+}
+
+/// A read-only rectangular window of an [`Image`], see [`Image::view`]. Positions are relative
+/// to the top-left corner of the window.
+#[derive(Debug, Clone, Copy)]
+pub struct ImageView<'a, P: Pixel> {
+    width: usize,
+    height: usize,
+    /// Distance between the starts of consecutive rows, the width of the image
+    stride: usize,
+    data: &'a [P],
+}
+
+impl<'a, P: Pixel> ImageView<'a, P> {
+    /// Returns the dimensions of the view as a tuple (width, height).
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns true if the view has no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Returns a reference to the pixel at the specified position.
+    /// Returns an error if the position is out of bounds.
+    pub fn get_pixel(&self, position: (usize, usize)) -> Result<&'a P> {
+        if position.0 >= self.width || position.1 >= self.height {
+            return Err(out_of_bounds(position, self.dimensions()));
+        }
+        Ok(&self.data[position.1 * self.stride + position.0])
+    }
+
+    /// Returns the rows of the view from top to bottom, each as a slice of `width` pixels.
+    pub fn rows(&self) -> impl Iterator<Item = &'a [P]> + use<'a, P> {
+        let (data, width, stride) = (self.data, self.width, self.stride);
+        (0..self.height).map(move |y| &data[y * stride..y * stride + width])
+    }
+
+    /// Returns the pixels in row-major order.
+    pub fn pixels(&self) -> impl Iterator<Item = P> + use<'a, P> {
+        self.rows().flat_map(|row| row.iter().copied())
+    }
+
+    /// Returns the part of this view inside `rect`, given relative to the view.
+    /// Returns an error if the rectangle does not lie completely within the view.
+    pub fn view(&self, rect: Rect) -> Result<ImageView<'a, P>> {
+        let range = window(self.dimensions(), self.stride, rect)?;
+        Ok(ImageView {
+            width: rect.width,
+            height: rect.height,
+            stride: self.stride,
+            data: &self.data[range],
+        })
+    }
+
+    /// Copies the pixels of the view into a new [`Image`], the same as [`Image::crop`].
+    pub fn to_image(&self) -> Image<P> {
+        Image::from_data(self.width, self.height, self.pixels().collect()).unwrap()
+    }
+}
+
+/// A mutable rectangular window of an [`Image`], see [`Image::view_mut`]. Positions are relative
+/// to the top-left corner of the window.
+#[derive(Debug)]
+pub struct ImageViewMut<'a, P: Pixel> {
+    width: usize,
+    height: usize,
+    /// Distance between the starts of consecutive rows, the width of the image
+    stride: usize,
+    data: &'a mut [P],
+}
+
+impl<'a, P: Pixel> ImageViewMut<'a, P> {
+    /// Returns a read-only view of the same window.
+    pub fn as_view(&self) -> ImageView<'_, P> {
+        ImageView {
+            width: self.width,
+            height: self.height,
+            stride: self.stride,
+            data: self.data,
+        }
+    }
+
+    /// Returns the dimensions of the view as a tuple (width, height).
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns true if the view has no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Returns a reference to the pixel at the specified position.
+    /// Returns an error if the position is out of bounds.
+    pub fn get_pixel(&self, position: (usize, usize)) -> Result<&P> {
+        if position.0 >= self.width || position.1 >= self.height {
+            return Err(out_of_bounds(position, self.dimensions()));
+        }
+        Ok(&self.data[position.1 * self.stride + position.0])
+    }
+
+    /// Sets the pixel at the specified position to the given color.
+    /// Returns an error if the position is out of bounds.
+    pub fn set_pixel(&mut self, position: (usize, usize), color: P) -> Result<()> {
+        if position.0 >= self.width || position.1 >= self.height {
+            return Err(out_of_bounds(position, self.dimensions()));
+        }
+        self.data[position.1 * self.stride + position.0] = color;
+        Ok(())
+    }
+
+    /// Returns the rows of the view from top to bottom, each as a slice of `width` pixels.
+    pub fn rows(&self) -> impl Iterator<Item = &[P]> {
+        let (data, width, stride) = (&*self.data, self.width, self.stride);
+        (0..self.height).map(move |y| &data[y * stride..y * stride + width])
+    }
+
+    /// Returns the rows of the view for modification, see [`ImageViewMut::rows`].
+    pub fn rows_mut(&mut self) -> impl Iterator<Item = &mut [P]> {
+        let width = self.width;
+        self.data
+            .chunks_mut(self.stride.max(1))
+            .take(self.height)
+            .map(move |row| &mut row[..width])
+    }
+
+    /// Returns the pixels in row-major order.
+    pub fn pixels(&self) -> impl Iterator<Item = P> {
+        self.rows().flat_map(|row| row.iter().copied())
+    }
+
+    /// Returns the pixels in row-major order for modification.
+    pub fn pixels_mut(&mut self) -> impl Iterator<Item = &mut P> {
+        self.rows_mut().flatten()
+    }
+
+    /// Returns the part of this view inside `rect`, given relative to the view, for
+    /// modification. Returns an error if the rectangle does not lie completely within the view.
+    pub fn view_mut(&mut self, rect: Rect) -> Result<ImageViewMut<'_, P>> {
+        let range = window(self.dimensions(), self.stride, rect)?;
+        Ok(ImageViewMut {
+            width: rect.width,
+            height: rect.height,
+            stride: self.stride,
+            data: &mut self.data[range],
+        })
+    }
+
+    /// Sets every pixel of the view to `color`.
+    pub fn fill(&mut self, color: P) {
+        self.rows_mut().for_each(|row| row.fill(color));
+    }
+
+    /// Copies `image` into the view with its top-left corner at the top-left corner of the
+    /// view. Pixels that fall outside of the view are skipped, like in [`Image::paste`].
+    pub fn copy_from(&mut self, image: ImageView<'_, P>) {
+        for (target, source) in self.rows_mut().zip(image.rows()) {
+            let width = target.len().min(source.len());
+            target[..width].copy_from_slice(&source[..width]);
+        }
+    }
+
+    /// Copies the pixels of the view into a new [`Image`].
+    pub fn to_image(&self) -> Image<P> {
+        self.as_view().to_image()
+    }
+}
+
+impl<P> Image<P>
+where
+    P: Pixel,
+{
+    /// Returns a view of the pixels inside `rect` that borrows them instead of copying them
+    /// like [`Image::crop`]. Returns an error if the rectangle does not lie completely within
+    /// the image.
+    pub fn view(&self, rect: Rect) -> Result<ImageView<'_, P>> {
+        let range = window(self.dimensions(), self.width, rect)?;
+        Ok(ImageView {
+            width: rect.width,
+            height: rect.height,
+            stride: self.width,
+            data: &self.data[range],
+        })
+    }
+
+    /// Returns a view of the whole image.
+    pub fn as_view(&self) -> ImageView<'_, P> {
+        self.view(Rect::new(0, 0, self.width, self.height)).unwrap()
+    }
+
+    /// Splits the image into tiles of `tile_size` = (width, height) pixels, row by row, for
+    /// processing large images piece by piece. Every tile extends `overlap` pixels into its
+    /// neighbors, e.g. the radius of a filter applied to it, and tiles at the right and bottom
+    /// edges may be smaller. Returns an error if a side of `tile_size` is zero.
+    pub fn tiles(
+        &self,
+        tile_size: (usize, usize),
+        overlap: usize,
+    ) -> Result<impl Iterator<Item = Tile<'_, P>>> {
+        Ok(tile_rects(self.dimensions(), tile_size, overlap)?
+            .into_iter()
+            .map(|(core, rect)| self.tile(core, rect)))
+    }
+
+    /// Returns the tiles of [`Image::tiles`] as a parallel iterator, in no particular order
+    /// with the `parallel` feature. Returns an error if a side of `tile_size` is zero.
+    pub fn par_tiles(
+        &self,
+        tile_size: (usize, usize),
+        overlap: usize,
+    ) -> Result<impl ParallelIterator<Item = Tile<'_, P>>> {
+        Ok(tile_rects(self.dimensions(), tile_size, overlap)?
+            .into_par_iter()
+            .map(|(core, rect)| self.tile(core, rect)))
+    }
+
+    fn tile(&self, core: Rect, rect: Rect) -> Tile<'_, P> {
+        Tile {
+            view: self.view(rect).unwrap(),
+            rect,
+            core,
+        }
+    }
+
+    /// Returns a view of the pixels inside `rect` for modification in place.
+    /// Returns an error if the rectangle does not lie completely within the image.
+    pub fn view_mut(&mut self, rect: Rect) -> Result<ImageViewMut<'_, P>> {
+        let range = window(self.dimensions(), self.width, rect)?;
+        Ok(ImageViewMut {
+            width: rect.width,
+            height: rect.height,
+            stride: self.width,
+            data: &mut self.data[range],
+        })
+    }
+}
+
+impl<'a, P: Pixel> From<ImageView<'a, P>> for Image<P> {
+    fn from(view: ImageView<'a, P>) -> Self {
+        view.to_image()
+    }
+}
@@ -1,17 +1,29 @@
+// Serial builds resolve the adapters of glance_core::par to Iterator, leaving its imports unused
+#![cfg_attr(not(feature = "parallel"), allow(unused_imports))]
+
+pub mod annotations;
+pub mod colors;
 pub mod drawing;
 mod error;
 pub mod img;
+pub mod par;
+pub mod profiling;
+pub mod raw;
+pub mod testing;
+pub mod video;
+#[cfg(target_arch = "wasm32")]
+pub mod web;
 
 pub use self::error::{CoreError, Result};
 
 #[cfg(test)]
 mod tests {
-    use rayon::iter::{IndexedParallelIterator, ParallelIterator};
-
     use super::*;
     use crate::drawing::shapes::Circle;
+    use crate::drawing::text::Text;
     use crate::img::Image;
-    use crate::img::pixel::{Luma, Rgba};
+    use crate::img::pixel::{Luma, Pixel, Rgba};
+    use crate::par::*;
     use std::path::PathBuf;
 
     // Open an image
@@ -66,7 +78,7 @@ mod tests {
             img.display("draw_shapes")?;
         }
 
-        assert!(img.get_pixel(center.into())? == &green);
+        assert!(img.get_pixel(center)? == &green);
         Ok(())
     }
 
@@ -79,7 +91,7 @@ mod tests {
         let mut img = Image::<Rgba>::open(&path)?;
         img.par_pixels_mut().for_each(|pixel| {
             let (r, g, b, _) = (pixel.r, pixel.g, pixel.b, pixel.a);
-            let l = 0.299f32 * r as f32 + 0.587f32 * g as f32 + 0.114f32 * b as f32;
+            let l = 0.299f32 * r + 0.587f32 * g + 0.114f32 * b;
             *pixel = Rgba {
                 r: l,
                 g: l,
@@ -154,4 +166,1281 @@ mod tests {
 
         Ok(())
     }
+
+    // Draw text with the built-in bitmap font
+    #[test]
+    fn draw_text() -> Result<()> {
+        let mut img = Image::<Luma>::new(64, 16);
+        img.draw(Text {
+            position: (1, 1),
+            text: "Hi!".to_string(),
+            color: Luma { l: 1.0 },
+            scale: 2,
+        })?;
+
+        assert_eq!(Text::<Luma>::measure("Hi!", 2), (36, 16));
+        // Left stem of the H
+        assert_eq!(img.get_pixel((1, 1))?.l, 1.0);
+        assert_eq!(img.get_pixel((1, 13))?.l, 1.0);
+        // Gap between the stems of the H
+        assert_eq!(img.get_pixel((5, 1))?.l, 0.0);
+        Ok(())
+    }
+
+    // Read values between pixel centers
+    #[test]
+    fn subpixel_sampling() -> Result<()> {
+        let img = Image::from_data(
+            3,
+            2,
+            vec![
+                Luma { l: 0.0 },
+                Luma { l: 0.2 },
+                Luma { l: 0.4 },
+                Luma { l: 0.6 },
+                Luma { l: 0.8 },
+                Luma { l: 1.0 },
+            ],
+        )?;
+
+        assert!((img.sample_bilinear(0.5, 0.5).l - 0.4).abs() < 1e-6);
+        assert!((img.sample_bilinear(1.0, 0.0).l - 0.2).abs() < 1e-6);
+        // Outside positions are clamped to the edge
+        assert!((img.sample_bilinear(-3.0, 5.0).l - 0.6).abs() < 1e-6);
+        // Bicubic reproduces pixel values exactly at pixel centers
+        assert!((img.sample_bicubic(2.0, 1.0).l - 1.0).abs() < 1e-6);
+        assert!((img.sample_bicubic(1.0, 0.5).l - 0.5).abs() < 1e-6);
+        Ok(())
+    }
+
+    // Write an animated GIF and read it back
+    #[test]
+    fn write_gif_animation() -> Result<()> {
+        use crate::video::{VideoOptions, VideoWriter};
+        use image::AnimationDecoder;
+        use image::codecs::gif::GifDecoder;
+
+        let mut buffer = Vec::new();
+        let mut writer = VideoWriter::new(
+            &mut buffer,
+            VideoOptions {
+                fps: 10.0,
+                ..Default::default()
+            },
+        )?;
+        for frame in 0..5 {
+            let mut img = Image::from_data(32, 16, vec![colors::BLACK; 32 * 16])?;
+            img.draw(Circle {
+                position: (4 + frame * 6, 8),
+                color: Rgba {
+                    r: 1.0,
+                    g: 1.0,
+                    b: 1.0,
+                    a: 1.0,
+                },
+                radius: 3,
+                filled: true,
+                thickness: 1,
+            })?;
+            writer.write_frame(&img)?;
+        }
+        // Frames of a different size are rejected
+        assert!(
+            writer
+                .write_frame(&Image::from_data(8, 8, vec![Luma { l: 0.0 }; 64])?)
+                .is_err()
+        );
+        writer.finish()?;
+
+        let frames = GifDecoder::new(std::io::Cursor::new(buffer))?
+            .into_frames()
+            .collect_frames()?;
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[0].delay().numer_denom_ms(), (100, 1));
+        assert_eq!(frames[4].buffer().get_pixel(28, 8).0, [255, 255, 255, 255]);
+
+        // Failing to flush the output fails the video
+        struct Unflushable(Vec<u8>);
+        impl std::io::Write for Unflushable {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.write(buf)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Err(std::io::Error::other("disk full"))
+            }
+        }
+        let mut writer = VideoWriter::new(Unflushable(Vec::new()), VideoOptions::default())?;
+        writer.write_frame(&Image::from_data(8, 8, vec![Luma { l: 0.5 }; 64])?)?;
+        assert!(matches!(writer.finish(), Err(CoreError::Io(_))));
+
+        // Formats without an encoder are reported
+        assert!(matches!(
+            VideoWriter::create("out.mp4", VideoOptions::default()),
+            Err(CoreError::Unsupported(_))
+        ));
+        Ok(())
+    }
+
+    // Round trip through interleaved RGBA bytes, the layout of canvas ImageData
+    #[test]
+    fn rgba8_bytes_round_trip() -> Result<()> {
+        let bytes: Vec<u8> = (0..2 * 3 * 4).map(|i| (i * 10) as u8).collect();
+        let img = Image::<Rgba>::from_rgba8_bytes(2, 3, &bytes)?;
+        assert_eq!(img.get_pixel((1, 0))?.to_rgba8(), [40, 50, 60, 70]);
+        assert_eq!(img.to_rgba8_bytes(), bytes);
+        assert!(Image::<Rgba>::from_rgba8_bytes(2, 2, &bytes).is_err());
+        Ok(())
+    }
+
+    // Constructors, hex parsing and named colors
+    #[test]
+    fn color_constructors() -> Result<()> {
+        assert_eq!(Rgba::new(1.0, 0.0, 0.0, 1.0), colors::RED);
+        assert_eq!(Luma::new(0.25).l, 0.25);
+        assert_eq!(Rgba::from_hex("#ff8800")?, colors::ORANGE);
+        assert_eq!(Rgba::from_hex("f80")?, colors::ORANGE);
+        assert_eq!(Rgba::from_hex("#FFFFFF00")?.to_rgba8(), [255, 255, 255, 0]);
+        assert_eq!(Rgba::from_hex("#0000")?, colors::TRANSPARENT);
+        for invalid in ["", "#12345", "#gg0000", "#ff88000", "#ffé00"] {
+            assert!(Rgba::from_hex(invalid).is_err(), "{invalid}");
+        }
+        assert_eq!(colors::label(12), colors::CATEGORICAL[2]);
+        Ok(())
+    }
+
+    // Generic per channel math through the Pixel trait
+    #[test]
+    fn pixel_channel_math() {
+        fn halve<P: Pixel>(pixel: P) -> P {
+            pixel.map(|v| v / 2.0)
+        }
+        assert_eq!(halve(Luma::new(0.5)), Luma::new(0.25));
+        assert_eq!(
+            halve(colors::WHITE),
+            Rgba::new(0.5, 0.5, 0.5, 0.5),
+            "alpha is a channel like the others"
+        );
+
+        let (from, to) = (Rgba::new(0.0, 0.2, 1.0, 1.0), Rgba::new(1.0, 0.4, 0.0, 0.0));
+        assert_eq!(from.lerp(&to, 0.0), from);
+        assert_eq!(from.lerp(&to, 1.0), to);
+        assert_eq!(from.lerp(&to, 0.5), Rgba::new(0.5, 0.3, 0.5, 0.5));
+        assert_eq!(from.zip_map(&to, f32::max), Rgba::new(1.0, 0.4, 1.0, 1.0));
+
+        assert!((colors::WHITE.luminance() - 1.0).abs() < 1e-6);
+        assert_eq!(colors::BLACK.luminance(), 0.0);
+        let gray = Rgba::from_luminance(0.3);
+        assert_eq!(gray, Rgba::new(0.3, 0.3, 0.3, 1.0));
+        assert!((gray.luminance() - Luma::from_luminance(0.3).luminance()).abs() < 1e-6);
+        // The luminance matches the conversion of 8 bit colors to Luma
+        let orange = colors::ORANGE;
+        assert!((Luma::from_rgba8(orange.to_rgba8()).l - orange.luminance()).abs() < 1e-6);
+    }
+
+    // Convert images between pixel types
+    #[test]
+    fn convert_pixel_types() -> Result<()> {
+        let colors = vec![Rgba::new(1.0, 0.0, 0.0, 0.5), Rgba::new(0.2, 0.4, 0.6, 1.0)];
+        let img = Image::from_data(2, 1, colors.clone())?;
+
+        let gray = img.convert::<Luma>();
+        assert_eq!(gray.dimensions(), (2, 1));
+        assert!((gray.get_pixel((0, 0))?.l - 0.299).abs() < 1e-6);
+        assert_eq!(gray.get_pixel((1, 0))?.l, colors[1].luminance());
+
+        // Gray converts back to opaque gray, and converting to the same type is a copy
+        let back = gray.convert::<Rgba>();
+        let l = gray.get_pixel((0, 0))?.l;
+        assert_eq!(*back.get_pixel((0, 0))?, Rgba::new(l, l, l, 1.0));
+        assert_eq!(img.convert::<Rgba>().as_slice(), &colors[..]);
+        Ok(())
+    }
+
+    // Gray masks with transparency, converted and composited without going through Rgba
+    #[test]
+    fn luma_alpha_compositing() -> Result<()> {
+        use crate::img::pixel::LumaA;
+
+        let mut background = Image::solid(3, 1, LumaA::new(0.2, 1.0));
+        let mask = Image::from_data(2, 1, vec![LumaA::new(1.0, 0.5), LumaA::new(1.0, 0.0)])?;
+        background.composite((1, 0), &mask);
+        let values: Vec<(f32, f32)> = background.pixels().map(|p| (p.l, p.a)).collect();
+        assert_eq!(values, [(0.2, 1.0), (0.6, 1.0), (0.2, 1.0)]);
+
+        // Over a transparent background the mask keeps its own color and alpha
+        let mut empty = Image::solid(2, 1, LumaA::new(0.0, 0.0));
+        empty.composite((0, 0), &mask);
+        assert_eq!(*empty.get_pixel((0, 0))?, LumaA::new(1.0, 0.5));
+
+        assert_eq!(
+            mask.to_rgba8_bytes(),
+            [255, 255, 255, 128, 255, 255, 255, 0]
+        );
+        let rgba = mask.convert::<Rgba>();
+        assert_eq!(*rgba.get_pixel((0, 0))?, Rgba::new(1.0, 1.0, 1.0, 0.5));
+        assert_eq!(rgba.convert::<LumaA>().as_slice(), mask.as_slice());
+        assert_eq!(mask.convert::<Luma>().get_pixel((1, 0))?.l, 1.0);
+        Ok(())
+    }
+
+    // Integer pixels are a fraction of the size and round trip through files without loss
+    #[test]
+    fn integer_pixels() -> Result<()> {
+        use crate::img::pixel::{LumaU8, LumaU16, RgbaU8};
+
+        assert_eq!(std::mem::size_of::<RgbaU8>(), 4);
+        assert_eq!(std::mem::size_of::<LumaU16>(), 2);
+
+        let depth: Vec<LumaU16> = (0..64).map(|i| LumaU16::new(i * 1021 + 7)).collect();
+        let img = Image::from_data(8, 8, depth)?;
+        let path = std::env::temp_dir().join(format!("glance_u16_{}.png", std::process::id()));
+        img.save(&path)?;
+        let read = Image::<LumaU16>::open(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(read.as_slice(), img.as_slice());
+
+        let colors: Vec<RgbaU8> = (0..64u8)
+            .map(|i| RgbaU8::new(i, 3 * i, 255 - i, 200))
+            .collect();
+        let img = Image::from_data(8, 8, colors)?;
+        let path = std::env::temp_dir().join(format!("glance_u8_{}.png", std::process::id()));
+        img.save(&path)?;
+        let read = Image::<RgbaU8>::open(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(read.as_slice(), img.as_slice());
+
+        // Channels read as floats and writes round to the nearest level
+        let mut pixel = *img.get_pixel((1, 0))?;
+        assert_eq!(pixel.channel(1), 3.0 / 255.0);
+        pixel.set_channel(0, 0.5);
+        assert_eq!(pixel.r, 128);
+        assert_eq!(img.convert::<Rgba>().get_pixel((1, 0))?.g, 3.0 / 255.0);
+        let gray = Image::from_data(1, 1, vec![LumaU8::new(51)])?;
+        assert_eq!(gray.convert::<LumaU16>().get_pixel((0, 0))?.l, 51 * 257);
+        assert_eq!(gray.convert::<Luma>().get_pixel((0, 0))?.l, 0.2);
+        Ok(())
+    }
+
+    // Convert to CIELAB and back, measure color differences and blend in Lab space
+    #[test]
+    fn lab_pixels() -> Result<()> {
+        use crate::img::pixel::Lab;
+
+        let colors = vec![
+            Rgba::new(1.0, 1.0, 1.0, 1.0),
+            Rgba::new(1.0, 0.0, 0.0, 1.0),
+            Rgba::new(0.2, 0.5, 0.8, 1.0),
+            Rgba::new(0.0, 0.0, 0.0, 1.0),
+        ];
+        let img = Image::from_data(2, 2, colors.clone())?;
+        let lab = img.convert::<Lab>();
+        let white = lab.get_pixel((0, 0))?;
+        assert!((white.l - 100.0).abs() < 0.01 && white.a.abs() < 0.01 && white.b.abs() < 0.01);
+        let red = lab.get_pixel((1, 0))?;
+        assert!((red.l - 53.24).abs() < 0.01);
+        assert!((red.a - 80.09).abs() < 0.01 && (red.b - 67.20).abs() < 0.01);
+
+        let back = lab.convert::<Rgba>();
+        for (a, b) in back.pixels().zip(colors) {
+            assert!((0..4).all(|c| (a.channel(c) - b.channel(c)).abs() < 1e-4));
+        }
+        assert_eq!(red.delta_e(red), 0.0);
+        assert!(red.delta_e(white) > 20.0);
+
+        // Halfway between black and white in Lab is perceptually mid gray, L* = 50, which is
+        // 18% of the light of white and darker than the sRGB value halfway
+        let mid = lab.get_pixel((1, 1))?.lerp(white, 0.5);
+        assert!((mid.l - 50.0).abs() < 0.01);
+        assert!((mid.luminance() - 0.466).abs() < 0.01);
+        Ok(())
+    }
+
+    // Convert to YCbCr and back, and halve the chroma resolution
+    #[test]
+    fn ycbcr_chroma_subsampling() -> Result<()> {
+        use crate::img::pixel::{ChromaSubsampling, YCbCr};
+
+        let colors = vec![
+            Rgba::new(1.0, 0.0, 0.0, 1.0),
+            Rgba::new(0.0, 0.0, 1.0, 1.0),
+            Rgba::new(0.5, 0.5, 0.5, 1.0),
+            Rgba::new(0.2, 0.7, 0.4, 1.0),
+            Rgba::new(1.0, 1.0, 1.0, 1.0),
+            Rgba::new(0.0, 0.0, 0.0, 1.0),
+        ];
+        let img = Image::from_data(3, 2, colors.clone())?;
+        let ycbcr = img.convert::<YCbCr>();
+        assert_eq!(ycbcr.get_pixel((0, 0))?.cr, 1.0);
+        assert_eq!(ycbcr.get_pixel((1, 0))?.cb, 1.0);
+        assert_eq!(*ycbcr.get_pixel((2, 0))?, YCbCr::new(0.5, 0.5, 0.5));
+        for (a, b) in ycbcr.pixels().zip(&colors) {
+            assert!((a.y - b.luminance()).abs() < 1e-6);
+            let rgba = a.to_rgba(1.0);
+            assert!((0..4).all(|c| (rgba.channel(c) - b.channel(c)).abs() < 1e-5));
+        }
+
+        // Luma is kept, chroma is shared by 2x2 blocks and the partial block at the edge
+        let subsampled = ycbcr.subsample_chroma(ChromaSubsampling::Yuv420);
+        let (first, corner) = (subsampled.get_pixel((0, 1))?, subsampled.get_pixel((1, 0))?);
+        assert_eq!((first.cb, first.cr), (corner.cb, corner.cr));
+        assert_eq!(first.y, ycbcr.get_pixel((0, 1))?.y);
+        let edge = ycbcr.get_pixel((2, 0))?.cb + ycbcr.get_pixel((2, 1))?.cb;
+        assert!((subsampled.get_pixel((2, 1))?.cb - edge / 2.0).abs() < 1e-6);
+        let full = ycbcr.subsample_chroma(ChromaSubsampling::Yuv444);
+        assert_eq!(full.as_slice(), ycbcr.as_slice());
+        Ok(())
+    }
+
+    // Blend in linear light and encode back to sRGB
+    #[test]
+    fn linear_light() -> Result<()> {
+        use crate::img::pixel::Linear;
+
+        let data = vec![
+            Luma::new(0.0),
+            Luma::new(1.0),
+            Luma::new(0.5),
+            Luma::new(0.2),
+        ];
+        let img = Image::from_data(2, 2, data.clone())?;
+        let linear = img.to_linear();
+        assert!((linear.get_pixel((0, 1))?.0.l - 0.214).abs() < 1e-3);
+
+        // Half black and half white is half the light, brighter than the sRGB midpoint
+        let mixed = linear.sample_bilinear(0.5, 0.0).to_srgb();
+        assert!((mixed.l - 0.735).abs() < 1e-3);
+        assert_eq!(img.sample_bilinear(0.5, 0.0).l, 0.5);
+
+        let back = linear.to_srgb();
+        assert!(
+            back.pixels()
+                .zip(data)
+                .all(|(a, b)| (a.l - b.l).abs() < 1e-6)
+        );
+        assert_eq!(linear.to_rgba8_bytes(), img.to_rgba8_bytes());
+
+        let color = Image::solid(1, 1, Rgba::new(0.5, 0.5, 0.5, 0.5)).to_linear();
+        assert_eq!(color.get_pixel((0, 0))?.0.a, 0.5);
+        assert_eq!(
+            *color.get_pixel((0, 0))?,
+            Linear::from_srgb(&Rgba::new(0.5, 0.5, 0.5, 0.5))
+        );
+        Ok(())
+    }
+
+    // Float to 8 bit conversion rounds and saturates, and dithering keeps the mean level
+    #[test]
+    fn float_to_u8_conversion() -> Result<()> {
+        use crate::img::pixel::{Quantization, to_u8};
+
+        let cases = [
+            (0.0, 0),
+            (1.0, 255),
+            (0.5, 128),
+            (1.0 / 255.0, 1),
+            (254.4 / 255.0, 254),
+            (254.6 / 255.0, 255),
+            (1.5, 255),
+            (-0.2, 0),
+            (f32::INFINITY, 255),
+            (f32::NAN, 0),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(to_u8(value), expected, "{value}");
+            assert_eq!(Luma { l: value }.to_rgba8()[0], expected, "{value}");
+        }
+        let hdr = Rgba {
+            r: 2.0,
+            g: 1.0,
+            b: -1.0,
+            a: 0.999,
+        };
+        assert_eq!(hdr.to_rgba8(), [255, 255, 0, 255]);
+
+        // Exact levels are kept, values in between alternate between their neighbours
+        let img = Image::<Luma>::solid(8, 8, Luma { l: 76.25 / 255.0 });
+        let dithered = img.to_rgba8_bytes_with(Quantization::Dither);
+        let levels: Vec<u8> = dithered.chunks_exact(4).map(|p| p[0]).collect();
+        assert!(levels.iter().all(|&l| l == 76 || l == 77));
+        assert_eq!(levels.iter().map(|&l| l as u32).sum::<u32>(), 76 * 64 + 16);
+        let exact = Image::<Luma>::solid(4, 4, Luma { l: 200.0 / 255.0 });
+        let bytes = exact.to_rgba8_bytes_with(Quantization::Dither);
+        assert!(bytes.chunks_exact(4).all(|p| p[0] == 200));
+        Ok(())
+    }
+
+    // Difference images, flat-field correction and masks with arithmetic operators
+    #[test]
+    fn image_arithmetic() -> Result<()> {
+        let image = |values: [f32; 4]| {
+            Image::from_data(2, 2, values.map(|v| Rgba::new(v, v, v, 1.0)).to_vec())
+        };
+        let red = |img: &Image<Rgba>| img.pixels().map(|p| p.r).collect::<Vec<_>>();
+        let raw = image([0.2, 0.4, 0.3, 0.5])?;
+        let dark = image([0.1, 0.1, 0.1, 0.1])?;
+        let flat = image([0.5, 1.0, 0.0, 0.8])?;
+
+        // Flat-field correction, dividing by zero gives zero
+        let corrected = (&raw - &dark) / &flat;
+        let expected = [0.2, 0.3, 0.0, 0.5];
+        assert!(
+            red(&corrected)
+                .iter()
+                .zip(expected)
+                .all(|(a, b)| (a - b).abs() < 1e-6)
+        );
+        assert!(corrected.pixels().all(|p| p.a == 1.0));
+
+        // Signed differences survive until clamped
+        let difference = &dark - &raw;
+        assert!(red(&difference).iter().all(|&v| v < 0.0));
+        assert!(red(&difference.clamp((0.0, 1.0))).iter().all(|&v| v == 0.0));
+
+        let mut scaled = raw * 2.0 + 0.1;
+        scaled *= &image([1.0, 0.0, 1.0, 0.0])?;
+        assert!((red(&scaled)[0] - 0.5).abs() < 1e-6);
+        assert_eq!(red(&scaled)[1], 0.0);
+
+        // Saturating variants clamp like 8 bit arithmetic
+        let bright = image([0.9, 0.6, 0.5, 1.0])?;
+        assert_eq!(red(&bright.saturating_add(&flat)?), [1.0, 1.0, 0.5, 1.0]);
+        assert_eq!(
+            red(&dark.clone().saturating_sub(&flat)?),
+            [0.0, 0.0, 0.1, 0.0]
+        );
+        assert_eq!(
+            red(&flat.clone().saturating_div(&dark)?),
+            [1.0, 1.0, 0.0, 1.0]
+        );
+        assert!(flat.saturating_mul(&Image::new(3, 2)).is_err());
+        Ok(())
+    }
+
+    // Direct access to the pixel buffer as a slice
+    #[test]
+    fn pixel_slices() -> Result<()> {
+        let data: Vec<Luma> = (0..12).map(|i| Luma::new(i as f32)).collect();
+        let mut img = Image::from_data(4, 3, data.clone())?;
+        assert_eq!(img.as_slice(), &data[..]);
+
+        let rows: Vec<f32> = img
+            .as_slice()
+            .chunks_exact(4)
+            .map(|row| row.iter().map(|p| p.l).sum())
+            .collect();
+        assert_eq!(rows, [6.0, 22.0, 38.0]);
+
+        img.as_mut_slice().reverse();
+        assert_eq!(img.get_pixel((0, 0))?.l, 11.0);
+        img.as_mut_slice()
+            .par_chunks_mut(4)
+            .for_each(|row| row.sort_by(|a, b| a.l.total_cmp(&b.l)));
+        assert_eq!(img.get_pixel((0, 0))?.l, 8.0);
+        let (top, _) = img.as_mut_slice().split_at_mut(4);
+        top.fill(Luma::new(0.0));
+        assert_eq!(img.get_pixel((3, 0))?.l, 0.0);
+        assert_eq!(img.into_vec().len(), 12);
+        Ok(())
+    }
+
+    // Copy a region out of an image and back in at another position
+    #[test]
+    fn crop_and_paste() -> Result<()> {
+        let data = (0..20).map(|i| Luma { l: i as f32 }).collect();
+        let mut img = Image::from_data(5, 4, data)?;
+
+        let region = img.crop(crate::img::Rect::new(1, 1, 2, 2))?;
+        assert_eq!(
+            region.pixels().map(|p| p.l).collect::<Vec<_>>(),
+            [6.0, 7.0, 11.0, 12.0]
+        );
+        assert!(img.crop(crate::img::Rect::new(4, 0, 2, 1)).is_err());
+
+        // Pasting partially outside the image clips the region
+        img.paste((4, 3), &region);
+        assert_eq!(img.get_pixel((4, 3))?.l, 6.0);
+        assert_eq!(img.get_pixel((3, 3))?.l, 18.0);
+        Ok(())
+    }
+
+    // Read and modify a region in place through borrowed views
+    #[test]
+    fn image_views() -> Result<()> {
+        use crate::img::Rect;
+
+        let data = (0..20).map(|i| Luma { l: i as f32 }).collect();
+        let mut img = Image::from_data(5, 4, data)?;
+
+        let view = img.view(Rect::new(1, 1, 3, 2))?;
+        assert_eq!(view.dimensions(), (3, 2));
+        assert_eq!(view.get_pixel((2, 1))?.l, 13.0);
+        assert!(view.get_pixel((3, 0)).is_err());
+        let values: Vec<f32> = view.pixels().map(|p| p.l).collect();
+        assert_eq!(values, [6.0, 7.0, 8.0, 11.0, 12.0, 13.0]);
+        let inner = view.view(Rect::new(1, 1, 2, 1))?;
+        assert_eq!(
+            inner.rows().next().unwrap(),
+            &[Luma::new(12.0), Luma::new(13.0)]
+        );
+        assert_eq!(
+            Image::from(view).as_slice(),
+            img.crop(Rect::new(1, 1, 3, 2))?.as_slice()
+        );
+        assert!(img.view(Rect::new(3, 3, 3, 1)).is_err());
+
+        // Writes through the view land in the image, the pixels around it are untouched
+        let mut view = img.view_mut(Rect::new(1, 1, 3, 2))?;
+        view.pixels_mut().for_each(|p| p.l = -p.l);
+        view.set_pixel((0, 1), Luma::new(100.0))?;
+        view.view_mut(Rect::new(2, 0, 1, 2))?.fill(Luma::new(0.0));
+        assert_eq!(img.get_pixel((1, 1))?.l, -6.0);
+        assert_eq!(img.get_pixel((1, 2))?.l, 100.0);
+        assert_eq!(img.get_pixel((3, 2))?.l, 0.0);
+        assert_eq!(img.get_pixel((4, 1))?.l, 9.0);
+        assert_eq!(img.get_pixel((1, 3))?.l, 16.0);
+
+        // Copy one region onto another of the same image
+        let source = img.crop(Rect::new(0, 0, 2, 2))?;
+        img.view_mut(Rect::new(3, 2, 2, 2))?
+            .copy_from(source.as_view());
+        assert_eq!(img.get_pixel((4, 3))?.l, -6.0);
+        Ok(())
+    }
+
+    // Split an image into overlapping tiles and put their cores back together
+    #[test]
+    fn overlapping_tiles() -> Result<()> {
+        use crate::img::Rect;
+
+        let data = (0..70).map(|i| Luma { l: i as f32 }).collect();
+        let img = Image::from_data(10, 7, data)?;
+
+        let tiles: Vec<_> = img.tiles((4, 3), 1)?.collect();
+        assert_eq!(tiles.len(), 9);
+        assert_eq!(tiles[0].rect, Rect::new(0, 0, 5, 4));
+        assert_eq!(tiles[4].rect, Rect::new(3, 2, 6, 5));
+        assert_eq!(tiles[4].core, Rect::new(4, 3, 4, 3));
+        assert_eq!(tiles[8].core, Rect::new(8, 6, 2, 1));
+        assert_eq!(tiles[8].view.get_pixel((0, 0))?.l, 57.0);
+
+        let mut stitched = Image::<Luma>::new(10, 7);
+        for tile in img.tiles((4, 3), 1)? {
+            let (x, y) = (tile.core.x - tile.rect.x, tile.core.y - tile.rect.y);
+            let core = tile
+                .view
+                .view(Rect::new(x, y, tile.core.width, tile.core.height))?;
+            stitched.paste((tile.core.x, tile.core.y), &core.to_image());
+        }
+        assert_eq!(stitched.as_slice(), img.as_slice());
+
+        let area: usize = img.par_tiles((4, 3), 2)?.map(|tile| tile.core.area()).sum();
+        assert_eq!(area, 70);
+
+        // Zero sized tiles would never cover the image
+        assert!(img.tiles((0, 3), 1).is_err());
+        assert!(img.par_tiles((4, 0), 1).is_err());
+        Ok(())
+    }
+
+    // Generate test patterns and check their defining properties
+    #[test]
+    fn test_patterns() -> Result<()> {
+        let white = Luma { l: 1.0 };
+        let black = Luma { l: 0.0 };
+
+        let board = Image::checkerboard(40, 30, 10, white, black);
+        assert_eq!(board.get_pixel((5, 5))?, &white);
+        assert_eq!(board.get_pixel((15, 5))?, &black);
+        assert_eq!(board.get_pixel((15, 15))?, &white);
+
+        let ramp = Image::linear_gradient(11, 4, 0.0, black, white);
+        assert_eq!(ramp.get_pixel((0, 3))?.l, 0.0);
+        assert!((ramp.get_pixel((5, 0))?.l - 0.5).abs() < 1e-6);
+        assert_eq!(ramp.get_pixel((10, 2))?.l, 1.0);
+
+        let radial = Image::radial_gradient(21, 21, (10.0, 10.0), 10.0, white, black);
+        assert_eq!(radial.get_pixel((10, 10))?.l, 1.0);
+        assert_eq!(radial.get_pixel((0, 0))?.l, 0.0);
+
+        // Opposite points of the star with an even number of spokes have the same value
+        let star = Image::<Luma>::siemens_star(101, 101, 16);
+        assert_eq!(star.get_pixel((80, 40))?, star.get_pixel((20, 60))?);
+        assert!(star.pixels().any(|p| p.l == 0.0) && star.pixels().any(|p| p.l == 1.0));
+
+        let plate = Image::<Luma>::zone_plate(101, 101);
+        assert_eq!(plate.get_pixel((50, 50))?.l, 1.0);
+
+        let bars = Image::<Rgba>::color_bars(140, 120);
+        assert_eq!(bars.get_pixel((30, 10))?.to_rgba8(), [192, 192, 0, 255]);
+        assert_eq!(bars.get_pixel((130, 85))?.to_rgba8(), [192, 192, 192, 255]);
+        assert_eq!(bars.get_pixel((40, 110))?.to_rgba8(), [255, 255, 255, 255]);
+
+        let swatch = Image::solid(3, 2, colors::BLACK);
+        assert!(swatch.pixels().all(|p| p == colors::BLACK));
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            bars.display("test_patterns")?;
+            plate.display("test_patterns")?;
+        }
+        Ok(())
+    }
+
+    // Compare against a golden, then detect a change and write the diff image
+    #[test]
+    fn golden_comparison() -> Result<()> {
+        use crate::testing::{UPDATE_ENV, assert_matches_reference};
+
+        // Regenerating goldens disables the comparison this test checks
+        if std::env::var_os(UPDATE_ENV).is_some() {
+            return Ok(());
+        }
+        let dir = std::env::temp_dir().join(format!("glance_golden_{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let golden = dir.join("star.png");
+        let star = Image::<Luma>::siemens_star(48, 48, 12);
+        star.save(&golden)?;
+
+        assert_matches_reference(&star, &golden, 0.0);
+
+        let mut changed = star.clone();
+        changed.set_pixel((3, 3), Luma { l: 0.5 })?;
+        // Within the tolerance
+        assert_matches_reference(&changed, &golden, 0.6);
+        let failure = std::panic::catch_unwind(|| assert_matches_reference(&changed, &golden, 0.1));
+        assert!(failure.is_err());
+
+        let diff = Image::<Rgba>::open(dir.join("star.diff.png"))?;
+        assert_eq!(diff.get_pixel((3, 3))?.to_rgba8()[..3], [191, 0, 0]);
+        assert_eq!(diff.get_pixel((4, 3))?.to_rgba8()[1], 0);
+        assert!(dir.join("star.actual.png").exists());
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    // Convert annotations between COCO, YOLO and VOC and draw them
+    #[test]
+    fn annotation_formats() -> Result<()> {
+        use crate::annotations::{
+            Annotation, BoundingBox, Dataset, ImageAnnotations, Keypoint, Overlay, Visibility,
+            coco, voc, yolo,
+        };
+
+        let dataset = Dataset {
+            categories: vec!["cat".to_string(), "dog".to_string()],
+            images: vec![ImageAnnotations {
+                file_name: "pets.png".to_string(),
+                width: 200,
+                height: 100,
+                annotations: vec![
+                    Annotation {
+                        category: "dog".to_string(),
+                        bbox: BoundingBox::new(20.0, 30.0, 60.0, 40.0),
+                        keypoints: vec![Keypoint {
+                            x: 50.0,
+                            y: 40.0,
+                            visibility: Visibility::Visible,
+                        }],
+                        ..Default::default()
+                    },
+                    Annotation {
+                        category: "cat".to_string(),
+                        bbox: BoundingBox::new(120.0, 20.0, 40.0, 40.0),
+                        polygons: vec![vec![(120.0, 60.0), (140.0, 20.0), (160.0, 60.0)]],
+                        ..Default::default()
+                    },
+                ],
+            }],
+        };
+
+        let mut json = Vec::new();
+        coco::write(&dataset, &mut json)?;
+        assert_eq!(coco::read(json.as_slice())?, dataset);
+
+        let image = &dataset.images[0];
+        let mut labels = Vec::new();
+        yolo::write(image, &dataset.categories, &mut labels)?;
+        assert_eq!(
+            String::from_utf8_lossy(&labels).lines().next(),
+            Some("1 0.250000 0.500000 0.300000 0.400000")
+        );
+        let boxes = yolo::read(labels.as_slice(), &dataset.categories, 200, 100)?;
+        // Normalized coordinates round trip up to float precision
+        let b = boxes[0].bbox;
+        assert_eq!(
+            [b.x, b.y, b.width, b.height].map(f32::round),
+            [20.0, 30.0, 60.0, 40.0]
+        );
+        assert_eq!(boxes[1].polygons[0].len(), 3);
+        assert!((boxes[1].bbox.x - 120.0).abs() < 1e-3);
+        assert!(yolo::read("2 0.5 0.5 0.1 0.1".as_bytes(), &dataset.categories, 10, 10).is_err());
+
+        let mut xml = Vec::new();
+        voc::write(image, &mut xml)?;
+        let from_voc = voc::read(xml.as_slice())?;
+        assert_eq!((from_voc.width, from_voc.height), (200, 100));
+        assert_eq!(from_voc.annotations[1].category, "cat");
+        assert_eq!(from_voc.annotations[1].bbox, image.annotations[1].bbox);
+
+        let mut img = Image::<Rgba>::new(200, 100);
+        img.draw(Overlay::new(&image.annotations))?;
+        if std::env::var("NO_DISPLAY").is_err() {
+            img.display("annotation_formats")?;
+        }
+        // Box outline, keypoint and the label tag above the box are drawn, the inside is not
+        assert_ne!(img.get_pixel((20, 50))?, &colors::BLACK);
+        assert_ne!(img.get_pixel((50, 40))?, &colors::BLACK);
+        assert_ne!(img.get_pixel((20, 20))?, &colors::BLACK);
+        assert_eq!(img.get_pixel((30, 60))?, &colors::BLACK);
+        Ok(())
+    }
+
+    // Lossless rotations, and a JPEG tagged as rotated is turned upright when opened
+    #[test]
+    fn exif_orientation() -> Result<()> {
+        use crate::img::{OpenOptions, Orientation};
+
+        let data = (0..6).map(|i| Luma { l: i as f32 }).collect();
+        let img = Image::from_data(3, 2, data)?;
+        let values = |img: &Image<Luma>| img.pixels().map(|p| p.l as u8).collect::<Vec<_>>();
+        assert_eq!(values(&img.rotate_90()), [3, 0, 4, 1, 5, 2]);
+        assert_eq!(values(&img.rotate_270()), [2, 5, 1, 4, 0, 3]);
+        assert_eq!(values(&img.transpose()), [0, 3, 1, 4, 2, 5]);
+        assert_eq!(values(&img.flip_horizontal()), [2, 1, 0, 5, 4, 3]);
+        assert_eq!(
+            values(&img.apply_orientation(Orientation::Transverse)),
+            [5, 2, 4, 1, 3, 0]
+        );
+        assert_eq!(Orientation::from_exif(6), Some(Orientation::Rotate90));
+        assert_eq!(Orientation::Rotate270.to_exif(), 8);
+
+        // Left half white, right half black, stored with orientation 6 (rotate 90 clockwise)
+        let stored = Image::checkerboard(32, 16, 16, Luma { l: 1.0 }, Luma { l: 0.0 });
+        let mut jpeg = Vec::new();
+        image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, 95).encode(
+            &stored.pixels().map(|p| p.to_rgba8()[0]).collect::<Vec<_>>(),
+            32,
+            16,
+            image::ExtendedColorType::L8,
+        )?;
+        let exif: &[u8] = &[
+            b'E', b'x', b'i', b'f', 0, 0, b'I', b'I', 42, 0, 8, 0, 0, 0, 1, 0, 0x12, 0x01, 3, 0, 1,
+            0, 0, 0, 6, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        let mut tagged = jpeg[..2].to_vec();
+        tagged.extend_from_slice(&[0xff, 0xe1, 0, exif.len() as u8 + 2]);
+        tagged.extend_from_slice(exif);
+        tagged.extend_from_slice(&jpeg[2..]);
+        let path = std::env::temp_dir().join(format!("glance_oriented_{}.jpg", std::process::id()));
+        std::fs::write(&path, tagged)?;
+
+        let upright = Image::<Luma>::open(&path)?;
+        let stored = Image::<Luma>::open_with(&path, OpenOptions { auto_orient: false })?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(stored.dimensions(), (32, 16));
+        assert_eq!(upright.dimensions(), (16, 32));
+        // The left half of the stored image is the top half of the upright one
+        assert!(upright.get_pixel((8, 4))?.l > 0.9);
+        assert!(upright.get_pixel((8, 28))?.l < 0.1);
+        Ok(())
+    }
+
+    // Fall back to a temporary PNG when no window can be opened
+    #[test]
+    fn headless_display_fallback() -> Result<()> {
+        use crate::img::{DisplayFallback, DisplayOptions};
+
+        let img = Image::solid(8, 4, colors::RED);
+        let options = DisplayOptions {
+            headless: true,
+            fallback: DisplayFallback::Fail,
+        };
+        assert!(img.display_with("headless fallback", options).is_err());
+
+        let prefix = format!("glance-headless_fallback_test-{}-", std::process::id());
+        let saved = || -> Result<Vec<PathBuf>> {
+            let mut paths = Vec::new();
+            for entry in std::fs::read_dir(std::env::temp_dir())? {
+                let path = entry?.path();
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                if name.starts_with(&prefix) {
+                    paths.push(path);
+                }
+            }
+            Ok(paths)
+        };
+        let options = DisplayOptions {
+            headless: true,
+            ..Default::default()
+        };
+        img.display_with("headless_fallback_test", options)?;
+        let paths = saved()?;
+        assert_eq!(paths.len(), 1);
+        let reopened = Image::<Rgba>::open(&paths[0])?;
+        std::fs::remove_file(&paths[0])?;
+        assert_eq!(reopened.dimensions(), (8, 4));
+        assert_eq!(reopened.get_pixel((0, 0))?.to_rgba8(), [255, 0, 0, 255]);
+        Ok(())
+    }
+
+    // Normalize per channel, jointly and to other ranges, without NaN for constant channels
+    #[test]
+    fn normalize_modes() -> Result<()> {
+        use crate::img::Normalization;
+
+        let img = Image::from_data(
+            2,
+            1,
+            vec![Rgba::new(0.2, 0.4, 0.5, 1.0), Rgba::new(0.6, 0.5, 0.5, 1.0)],
+        )?;
+        let per_channel = img.normalize();
+        assert_eq!(
+            *per_channel.get_pixel((0, 0))?,
+            Rgba::new(0.0, 0.0, 0.5, 1.0)
+        );
+        assert_eq!(
+            *per_channel.get_pixel((1, 0))?,
+            Rgba::new(1.0, 1.0, 0.5, 1.0)
+        );
+
+        let joint = img.normalize_with((0.0, 1.0), Normalization::Joint);
+        let [r, g, b, a] = [0, 1, 2, 3].map(|c| joint.get_pixel((0, 0)).unwrap().channel(c));
+        assert!(r.abs() < 1e-6 && (g - 0.5).abs() < 1e-6 && (b - 0.75).abs() < 1e-6);
+        assert_eq!(a, 1.0);
+
+        let signed = Image::from_data(2, 1, vec![Luma::new(0.25), Luma::new(0.75)])?;
+        let signed = signed.normalize_to((-1.0, 1.0));
+        assert_eq!(signed.as_slice(), &[Luma::new(-1.0), Luma::new(1.0)]);
+
+        let constant = Image::solid(3, 3, Luma::new(0.3)).normalize_to((0.5, 1.0));
+        assert!(constant.pixels().all(|p| p.l == 0.5));
+        Ok(())
+    }
+
+    // Apply a function to the channels, with and without alpha
+    #[test]
+    fn apply_alpha_policy() -> Result<()> {
+        let img = Image::solid(2, 2, Rgba::new(0.2, 0.4, 0.6, 0.5));
+        let colors = img.clone().apply(|v| 1.0 - v);
+        assert!(
+            colors
+                .pixels()
+                .all(|p| (p.r - 0.8).abs() < 1e-6 && p.a == 0.5)
+        );
+        let all = img.apply_with_alpha(|v| v * 2.0, true);
+        assert!(all.pixels().all(|p| (p.b - 1.2).abs() < 1e-6 && p.a == 1.0));
+
+        let gray = Image::solid(2, 2, Luma::new(0.25)).apply(|v| v.sqrt());
+        assert!(gray.pixels().all(|p| p.l == 0.5));
+        Ok(())
+    }
+
+    // Read and develop the Bayer mosaic of a DNG file
+    #[cfg(feature = "raw")]
+    #[test]
+    fn develop_dng() -> Result<()> {
+        use crate::raw::{CfaPattern, RawImage};
+
+        // 16 bit RGGB mosaic of a flat color, white balanced by (2, 1, 4)
+        let (black, white) = (256.0f32, 4095.0);
+        let color = [0.5, 0.25, 0.125];
+        let gains = [2.0, 1.0, 4.0];
+        let samples: Vec<u8> = (0..16)
+            .flat_map(|i| {
+                let c = CfaPattern::Rggb.color(i % 4, i / 4);
+                let raw = black + color[c] / gains[c] * (white - black);
+                (raw.round() as u16).to_le_bytes()
+            })
+            .collect();
+        let neutral: Vec<u8> = [(1u32, 2u32), (1, 1), (1, 4)]
+            .iter()
+            .flat_map(|&(n, d)| [n.to_le_bytes(), d.to_le_bytes()].concat())
+            .collect();
+        let entries: [(u16, u16, u32, Vec<u8>); 15] = [
+            (254, 4, 1, 0u32.to_le_bytes().to_vec()),
+            (256, 4, 1, 4u32.to_le_bytes().to_vec()),
+            (257, 4, 1, 4u32.to_le_bytes().to_vec()),
+            (258, 3, 1, 16u16.to_le_bytes().to_vec()),
+            (259, 3, 1, 1u16.to_le_bytes().to_vec()),
+            (262, 3, 1, 32803u16.to_le_bytes().to_vec()),
+            (273, 4, 1, Vec::new()),
+            (277, 3, 1, 1u16.to_le_bytes().to_vec()),
+            (278, 4, 1, 4u32.to_le_bytes().to_vec()),
+            (279, 4, 1, (samples.len() as u32).to_le_bytes().to_vec()),
+            (
+                33421,
+                3,
+                2,
+                [2u16.to_le_bytes(), 2u16.to_le_bytes()].concat(),
+            ),
+            (33422, 1, 4, vec![0, 1, 1, 2]),
+            (50714, 4, 1, (black as u32).to_le_bytes().to_vec()),
+            (50717, 4, 1, (white as u32).to_le_bytes().to_vec()),
+            (50728, 5, 3, Vec::new()),
+        ];
+        let neutral_at = 8 + 2 + 12 * entries.len() as u32 + 4;
+        let samples_at = neutral_at + neutral.len() as u32;
+        let mut dng = b"II*\0".to_vec();
+        dng.extend_from_slice(&8u32.to_le_bytes());
+        dng.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        for (tag, kind, count, mut value) in entries {
+            value = match tag {
+                273 => samples_at.to_le_bytes().to_vec(),
+                50728 => neutral_at.to_le_bytes().to_vec(),
+                _ => value,
+            };
+            value.resize(4, 0);
+            dng.extend_from_slice(&tag.to_le_bytes());
+            dng.extend_from_slice(&kind.to_le_bytes());
+            dng.extend_from_slice(&count.to_le_bytes());
+            dng.extend_from_slice(&value);
+        }
+        dng.extend_from_slice(&0u32.to_le_bytes());
+        dng.extend_from_slice(&neutral);
+        dng.extend_from_slice(&samples);
+
+        let raw = RawImage::from_dng(&dng)?;
+        assert_eq!(raw.cfa, CfaPattern::Rggb);
+        assert_eq!(raw.mosaic.dimensions(), (4, 4));
+        assert!((raw.black_level - black / white).abs() < 1e-6);
+        assert_eq!(raw.white_balance, gains);
+
+        let developed = raw.develop();
+        for pixel in developed.pixels() {
+            assert!((0..3).all(|c| (pixel.channel(c) - color[c]).abs() < 2e-3));
+            assert_eq!(pixel.a, 1.0);
+        }
+        assert!(RawImage::from_dng(&dng[..100]).is_err());
+        Ok(())
+    }
+
+    // Demosaic Bayer mosaics with every method
+    #[test]
+    fn demosaic_methods() -> Result<()> {
+        use crate::raw::{CfaPattern, DemosaicMethod, demosaic};
+
+        let methods = [
+            DemosaicMethod::Nearest,
+            DemosaicMethod::Bilinear,
+            DemosaicMethod::Malvar,
+        ];
+        // Sample a scene through the color filters of the pattern
+        let mosaic = |cfa: CfaPattern, scene: &dyn Fn(usize, usize) -> [f32; 3]| {
+            let data = (0..12 * 10)
+                .map(|i| Luma::new(scene(i % 12, i / 12)[cfa.color(i % 12, i / 12)]))
+                .collect();
+            Image::from_data(12, 10, data)
+        };
+
+        let flat = |_, _| [0.6, 0.3, 0.1];
+        for cfa in [CfaPattern::Rggb, CfaPattern::Gbrg] {
+            for method in methods {
+                let rgb = demosaic(&mosaic(cfa, &flat)?, cfa, method);
+                for p in rgb.pixels() {
+                    let expected = Rgba::new(0.6, 0.3, 0.1, 1.0);
+                    assert!((0..4).all(|c| (p.channel(c) - expected.channel(c)).abs() < 1e-6));
+                }
+            }
+        }
+
+        // Interpolation reproduces linear ramps away from the edges
+        let ramp = |x: usize, y: usize| {
+            let v = (x + 2 * y) as f32 / 40.0;
+            [v, 0.5 * v, 0.25 + 0.5 * v]
+        };
+        let raw = mosaic(CfaPattern::Grbg, &ramp)?;
+        for method in [DemosaicMethod::Bilinear, DemosaicMethod::Malvar] {
+            let rgb = demosaic(&raw, CfaPattern::Grbg, method);
+            for (x, y) in (2..8).flat_map(|y| (2..10).map(move |x| (x, y))) {
+                let (p, expected) = (rgb.get_pixel((x, y))?, ramp(x, y));
+                assert!((0..3).all(|c| (p.channel(c) - expected[c]).abs() < 1e-5));
+            }
+        }
+        Ok(())
+    }
+
+    // Carry EXIF and XMP metadata from open to save, with fields set programmatically
+    #[test]
+    fn metadata_round_trip() -> Result<()> {
+        use crate::img::{GpsPosition, Metadata, SaveOptions, TextTag};
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/flower.jpg");
+        let img: Image<Rgba> = Image::open(&path)?;
+
+        let mut metadata = Metadata::read(&path)?;
+        metadata.set_text(TextTag::Software, "glance")?;
+        metadata.set_text(TextTag::Copyright, "(c) glance contributors")?;
+        let position = GpsPosition {
+            latitude: 48.858_37,
+            longitude: -2.294_48,
+            altitude: Some(35.5),
+        };
+        metadata.set_gps(position)?;
+        metadata.xmp = Some("<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>".to_string());
+
+        for extension in ["jpg", "png"] {
+            let saved = std::env::temp_dir().join(format!(
+                "glance-metadata-{}.{extension}",
+                std::process::id()
+            ));
+            let options = SaveOptions {
+                metadata: Some(metadata.clone()),
+                ..Default::default()
+            };
+            img.save_with(&saved, options)?;
+            let reopened = Image::<Rgba>::open(&saved)?;
+            let read = Metadata::read(&saved)?;
+            std::fs::remove_file(&saved)?;
+
+            assert_eq!(reopened.dimensions(), img.dimensions());
+            assert_eq!(read.text(TextTag::Software).as_deref(), Some("glance"));
+            assert_eq!(
+                read.text(TextTag::Copyright).as_deref(),
+                Some("(c) glance contributors")
+            );
+            assert_eq!(read.xmp, metadata.xmp);
+            let gps = read.gps().unwrap();
+            assert!((gps.latitude - position.latitude).abs() < 1e-6);
+            assert!((gps.longitude - position.longitude).abs() < 1e-6);
+            assert_eq!(gps.altitude, Some(35.5));
+        }
+
+        // Plain saves strip the metadata
+        let saved = std::env::temp_dir().join(format!("glance-plain-{}.png", std::process::id()));
+        img.save(&saved)?;
+        let read = Metadata::read(&saved)?;
+        std::fs::remove_file(&saved)?;
+        assert!(read.is_empty());
+        Ok(())
+    }
+
+    // Save indexed PNG and GIF files with exact and quantized palettes
+    #[test]
+    fn indexed_palette_export() -> Result<()> {
+        use crate::img::{Palette, SaveOptions};
+        use std::collections::HashSet;
+
+        let temp =
+            |name: &str| std::env::temp_dir().join(format!("glance-{}-{name}", std::process::id()));
+
+        // Few colors are kept exactly, GIF keeps only full transparency
+        let mut icon = Image::solid(9, 5, Rgba::new(1.0, 0.0, 0.0, 1.0));
+        icon.set_pixel((1, 1), Rgba::new(0.0, 0.0, 1.0, 1.0))?;
+        icon.set_pixel((4, 2), Rgba::new(0.0, 0.0, 0.0, 0.0))?;
+        let palette = Palette::from_image(&icon, 256);
+        assert_eq!(palette.colors().len(), 3);
+        for extension in ["png", "gif"] {
+            let path = temp(&format!("icon.{extension}"));
+            let options = SaveOptions {
+                palette: Some(palette.clone()),
+                ..Default::default()
+            };
+            icon.save_with(&path, options)?;
+            let reopened = Image::<Rgba>::open(&path)?;
+            std::fs::remove_file(&path)?;
+            assert_eq!(reopened.to_rgba8_bytes(), icon.to_rgba8_bytes());
+        }
+
+        // Photos are reduced to the requested number of colors
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/lichtenstein.png");
+        let img: Image<Rgba> = Image::open(&path)?;
+        let palette = Palette::from_image(&img, 16);
+        assert_eq!(palette.colors().len(), 16);
+        let (full, indexed) = (temp("full.png"), temp("indexed.png"));
+        img.save(&full)?;
+        let options = SaveOptions {
+            palette: Some(palette.clone()),
+            ..Default::default()
+        };
+        img.save_with(&indexed, options.clone())?;
+        let sizes = (
+            std::fs::metadata(&full)?.len(),
+            std::fs::metadata(&indexed)?.len(),
+        );
+        let reopened = Image::<Rgba>::open(&indexed)?;
+        std::fs::remove_file(&full)?;
+        std::fs::remove_file(&indexed)?;
+        assert!(sizes.1 * 2 < sizes.0);
+        let colors: HashSet<[u8; 4]> = reopened.pixels().map(|p| p.to_rgba8()).collect();
+        assert!(colors.iter().all(|c| palette.colors().contains(c)));
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            reopened.display("indexed_palette_export")?;
+        }
+
+        // Explicit palettes are checked, other formats are refused
+        assert!(Palette::new(Vec::new()).is_err());
+        assert!(Palette::new(vec![[0, 0, 0, 255]; 257]).is_err());
+        assert!(img.save_with(temp("indexed.jpg"), options).is_err());
+        Ok(())
+    }
+
+    // Trim near-uniform margins and report the content rect
+    #[test]
+    fn trim_uniform_borders() -> Result<()> {
+        use crate::img::Rect;
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/lichtenstein.png");
+        let content: Image<Rgba> = Image::open(&path)?;
+        let (width, height) = content.dimensions();
+
+        // Slightly noisy gray scanner margin with a black letterbox bar at the bottom
+        let mut scan = Image::from_data(
+            width + 12,
+            height + 9,
+            (0..(width + 12) * (height + 9))
+                .map(|i| Rgba::new(0.8 + (i % 3) as f32 * 0.01, 0.8, 0.8, 1.0))
+                .collect(),
+        )?;
+        scan.paste((0, height + 3), &Image::solid(width + 12, 6, colors::BLACK));
+        scan.paste((5, 3), &content);
+
+        let (trimmed, rect) = scan.trim_borders(0.05);
+        assert_eq!(rect, Rect::new(5, 3, width, height));
+        assert_eq!(trimmed.as_slice(), content.as_slice());
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            trimmed.display("trim_uniform_borders")?;
+        }
+
+        // Nothing is trimmed without margins, everything from a uniform image
+        assert_eq!(content.trim_borders(0.05).1, Rect::new(0, 0, width, height));
+        let (empty, rect) = Image::solid(4, 4, colors::BLACK).trim_borders(0.0);
+        assert!(empty.is_empty() && rect.is_empty());
+        Ok(())
+    }
+
+    // Time instrumented operations and summarize them
+    #[test]
+    fn op_timer_summary() -> Result<()> {
+        use crate::profiling::OpTimer;
+        use std::time::Duration;
+
+        let timer = OpTimer::new();
+        let sum: u32 = timer.time("sum", || (0..100).sum());
+        timer.record("sum", Duration::from_millis(3));
+        assert_eq!(sum, 4950);
+        let stats = timer.get("sum").unwrap();
+        assert_eq!(stats.count, 2);
+        assert_eq!(stats.max, Duration::from_millis(3));
+        assert!(stats.min <= stats.mean() && stats.mean() <= stats.max);
+        assert!(timer.to_string().lines().nth(1).unwrap().starts_with("sum"));
+
+        // Disabled timers ignore runs
+        timer.set_enabled(false);
+        timer.record("sum", Duration::from_millis(3));
+        assert_eq!(timer.get("sum").unwrap().count, 2);
+        timer.reset();
+        assert!(timer.stats().is_empty());
+
+        // Opening and saving feed the global timer
+        OpTimer::global().set_enabled(true);
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/lichtenstein.png");
+        let img: Image<Rgba> = Image::open(&path)?;
+        let saved = std::env::temp_dir().join(format!("glance-timer-{}.png", std::process::id()));
+        img.save(&saved)?;
+        std::fs::remove_file(&saved)?;
+        for op in ["open", "save"] {
+            assert!(OpTimer::global().get(op).is_some_and(|s| s.count >= 1));
+        }
+        Ok(())
+    }
+
+    // Deterministic reductions give the same sums for every thread count
+    #[test]
+    fn deterministic_reductions() -> Result<()> {
+        use crate::par::{Reduction, par_sum_with, reduce_chunks};
+
+        let values: Vec<f32> = (0..100_003)
+            .map(|i| ((i * 7919) % 10007) as f32 * 1e-3 - 3.0)
+            .collect();
+        let sum = || par_sum_with(&values, |&v| v as f64, Reduction::Deterministic);
+        let expected = sum();
+        let serial: f64 = values.iter().map(|&v| v as f64).sum();
+        assert!((expected - serial).abs() < 1e-6);
+        #[cfg(feature = "parallel")]
+        for threads in [1, 3, 8] {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            assert_eq!(pool.install(sum).to_bits(), expected.to_bits());
+        }
+        let fast = par_sum_with(&values, |&v| v as f64, Reduction::Fast);
+        assert!((fast - expected).abs() < 1e-6);
+
+        // Chunk results are combined in order
+        let ordered = reduce_chunks(&values, |chunk| vec![chunk.len()], |a, b| [a, b].concat());
+        assert_eq!(ordered.unwrap().iter().sum::<usize>(), values.len());
+        assert_eq!(
+            reduce_chunks(&[] as &[f32], |c| c.len(), |a, b| a + b),
+            None
+        );
+
+        // Normalization finds its bounds with them
+        let img = Image::from_data(
+            1,
+            values.len(),
+            values.iter().map(|&l| Luma::new(l)).collect(),
+        )?;
+        let normalized = img.normalize();
+        let (min, max) = normalized
+            .pixels()
+            .fold((f32::MAX, f32::MIN), |(lo, hi), p| {
+                (lo.min(p.l), hi.max(p.l))
+            });
+        assert_eq!((min, max), (0.0, 1.0));
+        Ok(())
+    }
+
+    // Anti-aliased ellipses blend their edges by coverage
+    #[test]
+    fn draw_antialiased_ellipse() -> Result<()> {
+        use crate::drawing::shapes::Ellipse;
+        use std::f32::consts::PI;
+
+        let ellipse = |radii: (f32, f32), rotation: f32, filled: bool| Ellipse {
+            center: (32.0, 32.0),
+            radii,
+            rotation,
+            color: Luma::new(1.0),
+            filled,
+            thickness: 2.0,
+        };
+        let area = |shape: Ellipse<Luma>| -> Result<(Image<Luma>, f32)> {
+            let mut img = Image::solid(64, 64, Luma::new(0.0));
+            img.draw(shape)?;
+            let area = img.pixels().map(|p| p.l).sum();
+            Ok((img, area))
+        };
+
+        let (disc, disc_area) = area(ellipse((10.0, 10.0), 0.0, true))?;
+        assert!((disc_area - PI * 100.0).abs() < 1.0);
+        assert_eq!(disc.get_pixel((32, 32))?.l, 1.0);
+        assert!(disc.pixels().any(|p| p.l > 0.1 && p.l < 0.9));
+
+        let (_, ring_area) = area(ellipse((10.0, 10.0), 0.0, false))?;
+        assert!((ring_area - 2.0 * PI * 10.0 * 2.0).abs() < 2.0);
+
+        // A quarter turn swaps the radii
+        let (turned, turned_area) = area(ellipse((20.0, 8.0), 90.0, true))?;
+        let (upright, _) = area(ellipse((8.0, 20.0), 0.0, true))?;
+        assert!((turned_area - PI * 160.0).abs() < 1.0);
+        assert!(
+            turned
+                .pixels()
+                .zip(upright.pixels())
+                .all(|(a, b)| (a.l - b.l).abs() < 1e-3)
+        );
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            let (img, _) = area(ellipse((24.0, 12.0), 30.0, false))?;
+            img.display("draw_antialiased_ellipse")?;
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,206 @@
+//! Data parallelism behind the `parallel` feature. With the feature, this module re-exports the
+//! prelude of rayon. Without it, the same method names (`into_par_iter()`, `par_iter()`,
+//! `par_chunks_mut()`, ...) return the serial iterators of the standard library, so code written
+//! against this module builds single-threaded for embedded and wasm targets unchanged.
+//!
+//! Only the adapters rayon shares with [`Iterator`] are available in both builds, plus
+//! `flat_map_iter()`. Rayon's `reduce()` and `fold()` differ from their serial namesakes, so
+//! reductions stick to `sum()`, `min_by()`, `collect()` and the like.
+//!
+//! Floating point sums depend on the order in which their terms are added, and rayon splits work
+//! differently from run to run. [`reduce_chunks`] and [`par_sum`] reduce fixed chunks and combine
+//! their results in a fixed pairwise order instead, so their results are the same for every
+//! thread count and with or without the `parallel` feature. See [`Reduction`] to trade this for
+//! speed.
+
+#[cfg(feature = "parallel")]
+pub use rayon::prelude::*;
+
+/// Iterator over the elements of a slice, parallel with the `parallel` feature.
+#[cfg(feature = "parallel")]
+pub type SliceIter<'a, T> = rayon::slice::Iter<'a, T>;
+
+/// Iterator over mutable elements of a slice, parallel with the `parallel` feature.
+#[cfg(feature = "parallel")]
+pub type SliceIterMut<'a, T> = rayon::slice::IterMut<'a, T>;
+
+#[cfg(not(feature = "parallel"))]
+pub use serial::*;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Number of items reduced serially by every task of [`reduce_chunks`].
+pub const REDUCTION_CHUNK: usize = 4096;
+
+/// Whether [`par_sum`] uses [`Reduction::Fast`], see [`set_reduction`].
+static FAST_REDUCTIONS: AtomicBool = AtomicBool::new(false);
+
+/// Association order of parallel floating point sums.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Reduction {
+    /// Fixed chunks combined pairwise: the result is reproducible bit for bit and the rounding
+    /// error grows with the logarithm of the number of chunks
+    #[default]
+    Deterministic,
+    /// Whatever order the thread pool picks, which may differ between runs in the last bits
+    Fast,
+}
+
+/// Sets the [`Reduction`] used by [`par_sum`] in the whole process.
+pub fn set_reduction(reduction: Reduction) {
+    FAST_REDUCTIONS.store(reduction == Reduction::Fast, Ordering::Relaxed);
+}
+
+/// Returns the [`Reduction`] used by [`par_sum`], [`Reduction::Deterministic`] unless changed
+/// with [`set_reduction`].
+pub fn reduction() -> Reduction {
+    match FAST_REDUCTIONS.load(Ordering::Relaxed) {
+        true => Reduction::Fast,
+        false => Reduction::Deterministic,
+    }
+}
+
+/// Reduces `items` deterministically: every chunk of [`REDUCTION_CHUNK`] items is reduced by
+/// `map` in parallel, then the chunk results are combined pairwise, neighbours first, in a fixed
+/// order. Returns None for no items.
+pub fn reduce_chunks<T, A>(
+    items: &[T],
+    map: impl Fn(&[T]) -> A + Sync + Send,
+    combine: impl Fn(A, A) -> A,
+) -> Option<A>
+where
+    T: Sync,
+    A: Send,
+{
+    let mut partials: Vec<A> = items.par_chunks(REDUCTION_CHUNK).map(map).collect();
+    while partials.len() > 1 {
+        let mut pairs = Vec::with_capacity(partials.len().div_ceil(2));
+        let mut partials_iter = partials.into_iter();
+        while let Some(a) = partials_iter.next() {
+            pairs.push(match partials_iter.next() {
+                Some(b) => combine(a, b),
+                None => a,
+            });
+        }
+        partials = pairs;
+    }
+    partials.pop()
+}
+
+/// Sums `f` over `items` in parallel with the process wide [`reduction`].
+pub fn par_sum<T: Sync>(items: &[T], f: impl Fn(&T) -> f64 + Sync + Send) -> f64 {
+    par_sum_with(items, f, reduction())
+}
+
+/// Sums `f` over `items` in parallel with the given [`Reduction`].
+pub fn par_sum_with<T: Sync>(
+    items: &[T],
+    f: impl Fn(&T) -> f64 + Sync + Send,
+    reduction: Reduction,
+) -> f64 {
+    match reduction {
+        Reduction::Fast => items.par_iter().map(f).sum(),
+        Reduction::Deterministic => reduce_chunks(
+            items,
+            |chunk| chunk.iter().map(&f).sum::<f64>(),
+            |a, b| a + b,
+        )
+        .unwrap_or(0.0),
+    }
+}
+
+/// Iterator over the elements of a slice, parallel with the `parallel` feature.
+#[cfg(not(feature = "parallel"))]
+pub type SliceIter<'a, T> = std::slice::Iter<'a, T>;
+
+/// Iterator over mutable elements of a slice, parallel with the `parallel` feature.
+#[cfg(not(feature = "parallel"))]
+pub type SliceIterMut<'a, T> = std::slice::IterMut<'a, T>;
+
+/// Serial stand-ins for the traits of the rayon prelude.
+#[cfg(not(feature = "parallel"))]
+mod serial {
+    /// Serial counterpart of rayon's `IntoParallelIterator`.
+    pub trait IntoParallelIterator: IntoIterator + Sized {
+        fn into_par_iter(self) -> Self::IntoIter {
+            self.into_iter()
+        }
+    }
+
+    impl<T: IntoIterator> IntoParallelIterator for T {}
+
+    /// Serial counterpart of rayon's `IntoParallelRefIterator`.
+    pub trait IntoParallelRefIterator<'a> {
+        type Iter: Iterator;
+        fn par_iter(&'a self) -> Self::Iter;
+    }
+
+    impl<'a, I: 'a + ?Sized> IntoParallelRefIterator<'a> for I
+    where
+        &'a I: IntoIterator,
+    {
+        type Iter = <&'a I as IntoIterator>::IntoIter;
+
+        fn par_iter(&'a self) -> Self::Iter {
+            self.into_iter()
+        }
+    }
+
+    /// Serial counterpart of rayon's `IntoParallelRefMutIterator`.
+    pub trait IntoParallelRefMutIterator<'a> {
+        type Iter: Iterator;
+        fn par_iter_mut(&'a mut self) -> Self::Iter;
+    }
+
+    impl<'a, I: 'a + ?Sized> IntoParallelRefMutIterator<'a> for I
+    where
+        &'a mut I: IntoIterator,
+    {
+        type Iter = <&'a mut I as IntoIterator>::IntoIter;
+
+        fn par_iter_mut(&'a mut self) -> Self::Iter {
+            self.into_iter()
+        }
+    }
+
+    /// Serial counterpart of rayon's `ParallelIterator`, adding the adapters missing from
+    /// [`Iterator`].
+    pub trait ParallelIterator: Iterator + Sized {
+        fn flat_map_iter<U, F>(self, f: F) -> std::iter::FlatMap<Self, U, F>
+        where
+            U: IntoIterator,
+            F: FnMut(Self::Item) -> U,
+        {
+            self.flat_map(f)
+        }
+    }
+
+    impl<I: Iterator> ParallelIterator for I {}
+
+    /// Serial counterpart of rayon's `IndexedParallelIterator`.
+    pub trait IndexedParallelIterator: Iterator {}
+
+    impl<I: Iterator> IndexedParallelIterator for I {}
+
+    /// Serial counterpart of rayon's `ParallelSlice`.
+    pub trait ParallelSlice<T> {
+        fn par_chunks(&self, chunk_size: usize) -> std::slice::Chunks<'_, T>;
+    }
+
+    impl<T> ParallelSlice<T> for [T] {
+        fn par_chunks(&self, chunk_size: usize) -> std::slice::Chunks<'_, T> {
+            self.chunks(chunk_size)
+        }
+    }
+
+    /// Serial counterpart of rayon's `ParallelSliceMut`.
+    pub trait ParallelSliceMut<T> {
+        fn par_chunks_mut(&mut self, chunk_size: usize) -> std::slice::ChunksMut<'_, T>;
+    }
+
+    impl<T> ParallelSliceMut<T> for [T] {
+        fn par_chunks_mut(&mut self, chunk_size: usize) -> std::slice::ChunksMut<'_, T> {
+            self.chunks_mut(chunk_size)
+        }
+    }
+}
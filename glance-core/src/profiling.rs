@@ -0,0 +1,179 @@
+//! Timing of image operations. Major operations (open, save, convolution, scaling,
+//! morphology) run inside an [`OpSpan`], which feeds their durations to
+//! [`OpTimer::global`] once it is enabled, and with the `tracing` feature also enters a `tracing`
+//! span named `glance` with the operation name and image dimensions as fields.
+//!
+//! ```
+//! # use glance_core::img::{Image, pixel::Rgba};
+//! # use glance_core::profiling::OpTimer;
+//! OpTimer::global().set_enabled(true);
+//! let image = Image::solid(64, 64, Rgba::new(0.2, 0.4, 0.6, 1.0));
+//! let path = std::env::temp_dir().join("glance-profiling-doc.png");
+//! image.save(&path).unwrap();
+//! # std::fs::remove_file(&path).unwrap();
+//! print!("{}", OpTimer::global());
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Timer of all instrumented operations, see [`OpTimer::global`].
+static GLOBAL: OpTimer = OpTimer {
+    enabled: AtomicBool::new(false),
+    stats: Mutex::new(BTreeMap::new()),
+};
+
+/// Durations of the runs of one operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OpStats {
+    pub count: u64,
+    pub total: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl OpStats {
+    /// Returns the mean duration of a run.
+    pub fn mean(&self) -> Duration {
+        self.total / self.count.max(1) as u32
+    }
+}
+
+/// Summary of operation durations: count, total, mean, min and max per operation name.
+/// Displaying it prints a table sorted by name.
+#[derive(Debug)]
+pub struct OpTimer {
+    enabled: AtomicBool,
+    stats: Mutex<BTreeMap<&'static str, OpStats>>,
+}
+
+impl Default for OpTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OpTimer {
+    /// Creates an empty timer for operations timed by hand with [`OpTimer::time`].
+    pub fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(true),
+            stats: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the timer fed by the instrumented operations of glance. It is disabled at
+    /// startup, so instrumentation costs a single atomic load until
+    /// [`OpTimer::set_enabled`] turns it on.
+    pub fn global() -> &'static OpTimer {
+        &GLOBAL
+    }
+
+    /// Turns recording on or off. Disabled timers ignore [`OpTimer::record`].
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Returns whether the timer records durations.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Adds a run of `op` that took `elapsed`.
+    pub fn record(&self, op: &'static str, elapsed: Duration) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = stats.entry(op).or_insert(OpStats {
+            min: Duration::MAX,
+            ..Default::default()
+        });
+        entry.count += 1;
+        entry.total += elapsed;
+        entry.min = entry.min.min(elapsed);
+        entry.max = entry.max.max(elapsed);
+    }
+
+    /// Runs `f` and records its duration as a run of `op`.
+    pub fn time<T>(&self, op: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record(op, start.elapsed());
+        result
+    }
+
+    /// Returns the statistics of every operation recorded so far, sorted by name.
+    pub fn stats(&self) -> Vec<(&'static str, OpStats)> {
+        let stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        stats.iter().map(|(&op, &s)| (op, s)).collect()
+    }
+
+    /// Returns the statistics of `op`, if it was recorded.
+    pub fn get(&self, op: &str) -> Option<OpStats> {
+        let stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        stats.get(op).copied()
+    }
+
+    /// Forgets all recorded runs.
+    pub fn reset(&self) {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+impl fmt::Display for OpTimer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<20} {:>8} {:>12} {:>12} {:>12} {:>12}",
+            "operation", "count", "total", "mean", "min", "max"
+        )?;
+        for (op, s) in self.stats() {
+            writeln!(
+                f,
+                "{op:<20} {:>8} {:>12.3?} {:>12.3?} {:>12.3?} {:>12.3?}",
+                s.count,
+                s.total,
+                s.mean(),
+                s.min,
+                s.max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// An instrumented operation, timed until the span is dropped. See the module documentation.
+#[must_use = "the operation is timed until the span is dropped"]
+pub struct OpSpan {
+    op: &'static str,
+    start: Option<Instant>,
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+impl OpSpan {
+    /// Starts timing `op` on an image of the given dimensions.
+    pub fn enter(op: &'static str, dimensions: (usize, usize)) -> Self {
+        #[cfg(not(feature = "tracing"))]
+        let _ = dimensions;
+        Self {
+            op,
+            start: GLOBAL.is_enabled().then(Instant::now),
+            #[cfg(feature = "tracing")]
+            _span: tracing::debug_span!("glance", op, width = dimensions.0, height = dimensions.1)
+                .entered(),
+        }
+    }
+}
+
+impl Drop for OpSpan {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            GLOBAL.record(self.op, start.elapsed());
+        }
+    }
+}
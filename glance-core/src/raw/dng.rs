@@ -0,0 +1,304 @@
+//! Reader for the raw data of DNG files, the TIFF based RAW format of Adobe. Only uncompressed
+//! mosaics with a 2x2 color filter array are supported; lossless JPEG compressed DNGs are
+//! reported as [`CoreError::Unsupported`].
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use super::{CfaPattern, RawImage};
+use crate::img::{Image, pixel::Luma};
+use crate::{CoreError, Result};
+
+const NEW_SUBFILE_TYPE: u16 = 254;
+const IMAGE_WIDTH: u16 = 256;
+const IMAGE_LENGTH: u16 = 257;
+const BITS_PER_SAMPLE: u16 = 258;
+const COMPRESSION: u16 = 259;
+const PHOTOMETRIC_INTERPRETATION: u16 = 262;
+const STRIP_OFFSETS: u16 = 273;
+const SAMPLES_PER_PIXEL: u16 = 277;
+const ROWS_PER_STRIP: u16 = 278;
+const STRIP_BYTE_COUNTS: u16 = 279;
+const TILE_WIDTH: u16 = 322;
+const TILE_LENGTH: u16 = 323;
+const TILE_OFFSETS: u16 = 324;
+const TILE_BYTE_COUNTS: u16 = 325;
+const SUB_IFDS: u16 = 330;
+const CFA_REPEAT_PATTERN_DIM: u16 = 33421;
+const CFA_PATTERN: u16 = 33422;
+const BLACK_LEVEL: u16 = 50714;
+const WHITE_LEVEL: u16 = 50717;
+const AS_SHOT_NEUTRAL: u16 = 50728;
+
+/// Photometric interpretation of color filter array data.
+const PHOTOMETRIC_CFA: f64 = 32803.0;
+
+/// Limit on the number of directories read, against loops of offsets in broken files.
+const MAX_IFDS: usize = 64;
+
+/// The entries of an image file directory, every value converted to f64.
+type Ifd = HashMap<u16, Vec<f64>>;
+
+fn truncated() -> CoreError {
+    CoreError::InvalidData("Truncated DNG file".to_string())
+}
+
+/// Reads the numbers of a TIFF file in its byte order.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    little_endian: bool,
+}
+
+impl Reader<'_> {
+    fn slice(&self, offset: usize, len: usize) -> Result<&[u8]> {
+        let end = offset.checked_add(len).ok_or_else(truncated)?;
+        self.bytes.get(offset..end).ok_or_else(truncated)
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16> {
+        let bytes = self.slice(offset, 2)?.try_into().unwrap();
+        Ok(match self.little_endian {
+            true => u16::from_le_bytes(bytes),
+            false => u16::from_be_bytes(bytes),
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32> {
+        let bytes = self.slice(offset, 4)?.try_into().unwrap();
+        Ok(match self.little_endian {
+            true => u32::from_le_bytes(bytes),
+            false => u32::from_be_bytes(bytes),
+        })
+    }
+
+    /// Reads the `count` values of TIFF type `kind` stored at `offset`.
+    fn values(&self, kind: u16, count: usize, offset: usize) -> Result<Vec<f64>> {
+        let value = |i: usize| -> Result<f64> {
+            Ok(match kind {
+                1 | 2 | 7 => self.slice(offset + i, 1)?[0] as f64,
+                6 => self.slice(offset + i, 1)?[0] as i8 as f64,
+                3 => self.u16(offset + 2 * i)? as f64,
+                8 => self.u16(offset + 2 * i)? as i16 as f64,
+                4 => self.u32(offset + 4 * i)? as f64,
+                9 => self.u32(offset + 4 * i)? as i32 as f64,
+                5 => {
+                    let (n, d) = (self.u32(offset + 8 * i)?, self.u32(offset + 8 * i + 4)?);
+                    n as f64 / d.max(1) as f64
+                }
+                10 => {
+                    let n = self.u32(offset + 8 * i)? as i32;
+                    let d = self.u32(offset + 8 * i + 4)? as i32;
+                    n as f64 / if d == 0 { 1.0 } else { d as f64 }
+                }
+                11 => f32::from_bits(self.u32(offset + 4 * i)?) as f64,
+                12 => {
+                    let (a, b) = (self.u32(offset + 8 * i)?, self.u32(offset + 8 * i + 4)?);
+                    let (high, low) = if self.little_endian { (b, a) } else { (a, b) };
+                    f64::from_bits((high as u64) << 32 | low as u64)
+                }
+                _ => f64::NAN,
+            })
+        };
+        (0..count).map(value).collect()
+    }
+
+    /// Reads the directory at `offset`, returning its entries and the offset of the next one.
+    fn ifd(&self, offset: usize) -> Result<(Ifd, usize)> {
+        let count = self.u16(offset)? as usize;
+        let mut ifd = Ifd::new();
+        for i in 0..count {
+            let entry = offset + 2 + 12 * i;
+            let (tag, kind) = (self.u16(entry)?, self.u16(entry + 2)?);
+            let values = self.u32(entry + 4)? as usize;
+            let size = match kind {
+                1 | 2 | 6 | 7 => 1,
+                3 | 8 => 2,
+                4 | 9 | 11 => 4,
+                5 | 10 | 12 => 8,
+                _ => continue,
+            };
+            let at = match size * values <= 4 {
+                true => entry + 8,
+                false => self.u32(entry + 8)? as usize,
+            };
+            ifd.insert(tag, self.values(kind, values, at)?);
+        }
+        let next = self.u32(offset + 2 + 12 * count)? as usize;
+        Ok((ifd, next))
+    }
+}
+
+fn first(ifd: &Ifd, tag: u16) -> Option<f64> {
+    ifd.get(&tag).and_then(|values| values.first().copied())
+}
+
+fn required(ifd: &Ifd, tag: u16) -> Result<usize> {
+    first(ifd, tag)
+        .map(|value| value as usize)
+        .ok_or_else(|| CoreError::InvalidData(format!("DNG raw data lacks tag {tag}")))
+}
+
+/// Unpacks `count` samples of `bits` bits from the start of `data`. 16 bit samples are stored in
+/// the byte order of the file, other sizes are packed with the most significant bit first.
+fn unpack(data: &[u8], count: usize, bits: usize, little_endian: bool) -> Result<Vec<u16>> {
+    if data.len() < (count * bits).div_ceil(8) {
+        return Err(truncated());
+    }
+    Ok(match bits {
+        8 => data[..count].iter().map(|&b| b as u16).collect(),
+        16 => data
+            .chunks_exact(2)
+            .take(count)
+            .map(|b| match little_endian {
+                true => u16::from_le_bytes([b[0], b[1]]),
+                false => u16::from_be_bytes([b[0], b[1]]),
+            })
+            .collect(),
+        _ => (0..count)
+            .map(|i| {
+                (0..bits).fold(0u16, |sample, bit| {
+                    let position = i * bits + bit;
+                    let set = data[position / 8] >> (7 - position % 8) & 1;
+                    sample << 1 | set as u16
+                })
+            })
+            .collect(),
+    })
+}
+
+/// Reads the samples of the raw directory in row-major order, from strips or tiles.
+fn samples(reader: &Reader, ifd: &Ifd, (width, height): (usize, usize)) -> Result<Vec<u16>> {
+    let bits = required(ifd, BITS_PER_SAMPLE)?;
+    if !(1..=16).contains(&bits) {
+        return Err(CoreError::Unsupported(format!("{bits} bit DNG samples")));
+    }
+    let le = reader.little_endian;
+    let mut samples = vec![0u16; width * height];
+    if let Some(offsets) = ifd.get(&TILE_OFFSETS) {
+        let (tile_w, tile_h) = (required(ifd, TILE_WIDTH)?, required(ifd, TILE_LENGTH)?);
+        let counts = ifd.get(&TILE_BYTE_COUNTS).ok_or_else(truncated)?;
+        let row_bytes = (tile_w * bits).div_ceil(8);
+        let across = width.div_ceil(tile_w.max(1));
+        for (i, (&offset, &count)) in offsets.iter().zip(counts).enumerate() {
+            let data = reader.slice(offset as usize, count as usize)?;
+            let (x0, y0) = (i % across * tile_w, i / across * tile_h);
+            for row in 0..tile_h.min(height.saturating_sub(y0)) {
+                let start = (row * row_bytes).min(data.len());
+                let values = unpack(&data[start..], tile_w, bits, le)?;
+                let columns = tile_w.min(width.saturating_sub(x0));
+                let at = (y0 + row) * width + x0;
+                samples[at..at + columns].copy_from_slice(&values[..columns]);
+            }
+        }
+    } else {
+        let offsets = ifd.get(&STRIP_OFFSETS).ok_or_else(truncated)?;
+        let counts = ifd.get(&STRIP_BYTE_COUNTS).ok_or_else(truncated)?;
+        let rows_per_strip = first(ifd, ROWS_PER_STRIP).map_or(height, |r| r as usize);
+        let row_bytes = (width * bits).div_ceil(8);
+        for (strip, (&offset, &count)) in offsets.iter().zip(counts).enumerate() {
+            let data = reader.slice(offset as usize, count as usize)?;
+            let y0 = strip * rows_per_strip;
+            for row in 0..rows_per_strip.min(height.saturating_sub(y0)) {
+                let start = (row * row_bytes).min(data.len());
+                let values = unpack(&data[start..], width, bits, le)?;
+                let at = (y0 + row) * width;
+                samples[at..at + width].copy_from_slice(&values);
+            }
+        }
+    }
+    Ok(samples)
+}
+
+impl RawImage {
+    /// Reads the raw data of a DNG file.
+    pub fn open<Pth: AsRef<Path>>(path: Pth) -> Result<Self> {
+        Self::from_dng(&std::fs::read(path)?)
+    }
+
+    /// Reads the raw data of a DNG file in memory: the full resolution mosaic, its color filter
+    /// pattern, black and white level and the as-shot white balance.
+    pub fn from_dng(bytes: &[u8]) -> Result<Self> {
+        let little_endian = match bytes.get(..4) {
+            Some([b'I', b'I', 42, 0]) => true,
+            Some([b'M', b'M', 0, 42]) => false,
+            _ => return Err(CoreError::InvalidData("Not a DNG file".to_string())),
+        };
+        let reader = Reader {
+            bytes,
+            little_endian,
+        };
+
+        // The chain of main directories and, recursively, their sub directories
+        let mut offsets = vec![reader.u32(4)? as usize];
+        let mut ifds = Vec::new();
+        while let Some(offset) = offsets.pop() {
+            if offset == 0 || ifds.len() == MAX_IFDS {
+                continue;
+            }
+            let (ifd, next) = reader.ifd(offset)?;
+            offsets.push(next);
+            offsets.extend(
+                ifd.get(&SUB_IFDS)
+                    .into_iter()
+                    .flatten()
+                    .map(|&o| o as usize),
+            );
+            ifds.push(ifd);
+        }
+        let ifd = ifds
+            .iter()
+            .find(|ifd| {
+                first(ifd, PHOTOMETRIC_INTERPRETATION) == Some(PHOTOMETRIC_CFA)
+                    && first(ifd, NEW_SUBFILE_TYPE).unwrap_or(0.0) == 0.0
+            })
+            .ok_or_else(|| CoreError::InvalidData("DNG file without raw mosaic".to_string()))?;
+
+        let compression = first(ifd, COMPRESSION).unwrap_or(1.0);
+        if compression != 1.0 {
+            return Err(CoreError::Unsupported(format!(
+                "DNG compression {compression}, only uncompressed raw data is supported"
+            )));
+        }
+        if first(ifd, SAMPLES_PER_PIXEL).unwrap_or(1.0) != 1.0 {
+            return Err(CoreError::Unsupported("Linear DNG".to_string()));
+        }
+        let cfa = match (ifd.get(&CFA_REPEAT_PATTERN_DIM), ifd.get(&CFA_PATTERN)) {
+            (Some(dim), Some(pattern)) if dim[..] == [2.0, 2.0] => match pattern[..] {
+                [0.0, 1.0, 1.0, 2.0] => CfaPattern::Rggb,
+                [2.0, 1.0, 1.0, 0.0] => CfaPattern::Bggr,
+                [1.0, 0.0, 2.0, 1.0] => CfaPattern::Grbg,
+                [1.0, 2.0, 0.0, 1.0] => CfaPattern::Gbrg,
+                _ => return Err(CoreError::Unsupported("Non-Bayer CFA pattern".to_string())),
+            },
+            _ => return Err(CoreError::Unsupported("Non-Bayer CFA pattern".to_string())),
+        };
+
+        let (width, height) = (required(ifd, IMAGE_WIDTH)?, required(ifd, IMAGE_LENGTH)?);
+        let samples = samples(&reader, ifd, (width, height))?;
+        let bits = required(ifd, BITS_PER_SAMPLE)?;
+        let white = first(ifd, WHITE_LEVEL).unwrap_or(((1u32 << bits) - 1) as f64) as f32;
+        let white = white.max(1.0);
+        let black = ifd.get(&BLACK_LEVEL).map_or(0.0, |levels| {
+            levels.iter().sum::<f64>() as f32 / levels.len().max(1) as f32
+        });
+        // The white balance is stored with the main image rather than the raw data
+        let neutral = ifds.iter().find_map(|ifd| ifd.get(&AS_SHOT_NEUTRAL));
+        let white_balance = match neutral {
+            Some(neutral) if neutral.len() == 3 && neutral.iter().all(|&n| n > 0.0) => {
+                [0, 1, 2].map(|c| (neutral[1] / neutral[c]) as f32)
+            }
+            _ => [1.0; 3],
+        };
+
+        let mosaic = samples
+            .iter()
+            .map(|&s| Luma::new(s as f32 / white))
+            .collect();
+        Ok(RawImage {
+            mosaic: Image::from_data(width, height, mosaic)?,
+            cfa,
+            black_level: black / white,
+            white_balance,
+        })
+    }
+}
@@ -0,0 +1,137 @@
+//! Visual regression testing: compares images against stored golden files.
+//!
+//! Goldens are 8 bit PNGs, resolved relative to the working directory, which `cargo test` sets to
+//! the package root. Set `GLANCE_UPDATE_GOLDEN=1` to write the current output as the new golden
+//! instead of comparing, then review the changed files before committing them.
+//!
+//! ```no_run
+//! use glance_core::img::{Image, pixel::Luma};
+//! use glance_core::testing::assert_matches_reference;
+//!
+//! let plate = Image::<Luma>::zone_plate(64, 64);
+//! assert_matches_reference(&plate, "golden/zone_plate.png", 1.0 / 255.0);
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use crate::img::{Image, pixel::Pixel};
+
+/// Environment variable that makes [`assert_matches_reference`] regenerate the goldens.
+pub const UPDATE_ENV: &str = "GLANCE_UPDATE_GOLDEN";
+
+/// Differences between an image and its golden.
+#[derive(Debug)]
+struct Mismatch {
+    /// Largest absolute difference of any channel, in [0.0, 1.0]
+    max_difference: f32,
+    /// Number of pixels with a channel differing by more than the tolerance
+    differing_pixels: usize,
+}
+
+/// Returns the path `golden` with `suffix` inserted before the extension.
+fn sibling(golden: &Path, suffix: &str) -> PathBuf {
+    let stem = golden.file_stem().unwrap_or_default().to_string_lossy();
+    golden.with_file_name(format!("{stem}.{suffix}.png"))
+}
+
+/// Compares `image` against the golden at `golden`, allowing every RGBA channel to differ by
+/// `tolerance` (in [0.0, 1.0]) after quantization to 8 bits. Returns None if they match, or
+/// the error message describing the failure.
+fn compare<P: Pixel>(image: &Image<P>, golden: &Path, tolerance: f32) -> Option<String> {
+    let reference = match Image::<P>::open(golden) {
+        Ok(reference) => reference,
+        Err(e) => {
+            return Some(format!(
+                "cannot read golden {}: {e}. Run with {UPDATE_ENV}=1 to create it",
+                golden.display()
+            ));
+        }
+    };
+    if reference.dimensions() != image.dimensions() {
+        return Some(format!(
+            "image is {:?} but golden {} is {:?}",
+            image.dimensions(),
+            golden.display(),
+            reference.dimensions()
+        ));
+    }
+
+    let tolerance = (tolerance * 255.0).round() as i32;
+    let mut mismatch = Mismatch {
+        max_difference: 0.0,
+        differing_pixels: 0,
+    };
+    let (width, height) = image.dimensions();
+    let mut diff = vec![0u8; width * height * 4];
+    for ((actual, expected), out) in image
+        .to_rgba8_bytes()
+        .chunks_exact(4)
+        .zip(reference.to_rgba8_bytes().chunks_exact(4))
+        .zip(diff.chunks_exact_mut(4))
+    {
+        let largest = (0..4)
+            .map(|c| (actual[c] as i32 - expected[c] as i32).abs())
+            .max()
+            .unwrap_or(0);
+        mismatch.max_difference = mismatch.max_difference.max(largest as f32 / 255.0);
+        // Differing pixels are red, scaled by the difference, on a dimmed copy of the golden
+        let dim = |v: u8| v / 4;
+        out.copy_from_slice(&if largest > tolerance {
+            mismatch.differing_pixels += 1;
+            [(128 + largest / 2).min(255) as u8, 0, 0, 255]
+        } else {
+            [dim(expected[0]), dim(expected[1]), dim(expected[2]), 255]
+        });
+    }
+    let diff_path = sibling(golden, "diff");
+    let actual_path = sibling(golden, "actual");
+    if mismatch.differing_pixels == 0 {
+        // Artifacts of an earlier failure are stale now
+        let _ = std::fs::remove_file(&diff_path);
+        let _ = std::fs::remove_file(&actual_path);
+        return None;
+    }
+
+    let written = Image::<crate::img::pixel::Rgba>::from_rgba8_bytes(width, height, &diff)
+        .and_then(|diff| diff.save(&diff_path))
+        .and_then(|_| image.save(&actual_path));
+    let artifacts = match written {
+        Ok(()) => format!(
+            "wrote {} and {}",
+            diff_path.display(),
+            actual_path.display()
+        ),
+        Err(e) => format!("could not write the diff image: {e}"),
+    };
+    Some(format!(
+        "image differs from golden {}: {mismatch:?} with tolerance {tolerance}/255, {artifacts}",
+        golden.display()
+    ))
+}
+
+/// Asserts that `image` matches the golden PNG at `golden` within `tolerance` per channel.
+///
+/// On failure, writes `<name>.diff.png` (differing pixels in red) and `<name>.actual.png` next
+/// to the golden and panics. With `GLANCE_UPDATE_GOLDEN` set, saves `image` as the golden
+/// instead.
+#[track_caller]
+pub fn assert_matches_reference<P: Pixel>(
+    image: &Image<P>,
+    golden: impl AsRef<Path>,
+    tolerance: f32,
+) {
+    let golden = golden.as_ref();
+    if std::env::var_os(UPDATE_ENV).is_some() {
+        if let Some(parent) = golden.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .unwrap_or_else(|e| panic!("cannot create {}: {e}", parent.display()));
+        }
+        image
+            .save(golden)
+            .unwrap_or_else(|e| panic!("cannot write golden {}: {e}", golden.display()));
+        return;
+    }
+    if let Some(message) = compare(image, golden, tolerance) {
+        panic!("{message}");
+    }
+}
@@ -0,0 +1,172 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use gif::{DisposalMethod, Encoder, Frame, Repeat};
+
+use crate::img::{Image, pixel::Pixel};
+use crate::{CoreError, Result};
+
+/// Options for writing frame sequences with [`VideoWriter`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VideoOptions {
+    /// Playback rate in frames per second. GIF stores frame delays in hundredths of a second, so
+    /// rates above 50 are not played back faithfully by most viewers.
+    pub fps: f32,
+    /// Encoding quality from 1 to 100. For GIF this trades palette quality for encoding speed.
+    pub quality: u8,
+    /// Whether playback loops forever or stops after the last frame.
+    pub looping: bool,
+}
+
+impl Default for VideoOptions {
+    fn default() -> Self {
+        Self {
+            fps: 25.0,
+            quality: 80,
+            looping: true,
+        }
+    }
+}
+
+/// Writes a sequence of frames as an animation. Only animated GIF is supported; H.264/MP4 and
+/// animated WebP need encoders which are not available yet and are reported as
+/// [`CoreError::Unsupported`].
+///
+/// ```no_run
+/// # use glance_core::img::{Image, pixel::Rgba};
+/// # use glance_core::video::{VideoOptions, VideoWriter};
+/// # fn main() -> glance_core::Result<()> {
+/// let mut writer = VideoWriter::create("animation.gif", VideoOptions::default())?;
+/// for frame in 0..10 {
+///     let image = Image::from_data(64, 64, vec![Rgba { r: frame as f32 / 10.0, g: 0.0, b: 0.0, a: 1.0 }; 64 * 64])?;
+///     writer.write_frame(&image)?;
+/// }
+/// writer.finish()
+/// # }
+/// ```
+pub struct VideoWriter<W: Write> {
+    /// The output until the first frame fixes the size of the animation
+    writer: Option<W>,
+    encoder: Option<Encoder<W>>,
+    /// GIF speed from 1 (best palette) to 30 (fastest)
+    speed: i32,
+    repeat: Repeat,
+    /// Frame delay in hundredths of a second
+    delay: u16,
+    dimensions: Option<(usize, usize)>,
+}
+
+impl VideoWriter<BufWriter<File>> {
+    /// Creates the file at `path`, choosing the container from the extension.
+    pub fn create<Pth: AsRef<Path>>(path: Pth, options: VideoOptions) -> Result<Self> {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("gif") => Self::new(BufWriter::new(File::create(path)?), options),
+            Some(other) => Err(CoreError::Unsupported(format!(
+                "Writing .{other} videos is not supported, use .gif"
+            ))),
+            None => Err(CoreError::Unsupported(
+                "Video path needs an extension to pick the format".to_string(),
+            )),
+        }
+    }
+}
+
+impl<W: Write> VideoWriter<W> {
+    /// Starts an animated GIF in `writer`.
+    pub fn new(writer: W, options: VideoOptions) -> Result<Self> {
+        if options.fps.is_nan() || options.fps <= 0.0 {
+            return Err(CoreError::InvalidData(format!(
+                "Frame rate must be positive, got {}",
+                options.fps
+            )));
+        }
+
+        // GIF speed runs from 1 (best palette) to 30 (fastest)
+        let quality = options.quality.clamp(1, 100) as i32;
+        let speed = 30 - (quality - 1) * 29 / 99;
+
+        Ok(Self {
+            writer: Some(writer),
+            encoder: None,
+            speed,
+            repeat: if options.looping {
+                Repeat::Infinite
+            } else {
+                Repeat::Finite(0)
+            },
+            delay: (100.0 / options.fps).round() as u16,
+            dimensions: None,
+        })
+    }
+
+    /// Appends a frame. Every frame must have the dimensions of the first one.
+    pub fn write_frame<P: Pixel>(&mut self, image: &Image<P>) -> Result<()> {
+        let (width, height) = image.dimensions();
+        match self.dimensions {
+            Some(dimensions) if dimensions != (width, height) => {
+                return Err(CoreError::InvalidData(format!(
+                    "Frame of {width}x{height} does not match the video size {}x{}",
+                    dimensions.0, dimensions.1
+                )));
+            }
+            _ => {}
+        }
+        let (Ok(gif_width), Ok(gif_height)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(CoreError::InvalidData(format!(
+                "GIF frames are at most 65535 pixels on a side, got {width}x{height}"
+            )));
+        };
+
+        // The header holds the size of the animation, so it is written with the first frame
+        let encoder = match (&mut self.encoder, self.writer.take()) {
+            (Some(encoder), _) => encoder,
+            (None, writer) => {
+                let writer = writer.ok_or_else(lost_writer)?;
+                let mut encoder =
+                    Encoder::new(writer, gif_width, gif_height, &[]).map_err(gif_error)?;
+                encoder.set_repeat(self.repeat).map_err(gif_error)?;
+                self.dimensions = Some((width, height));
+                self.encoder.insert(encoder)
+            }
+        };
+        let mut pixels = image.to_rgba8_bytes();
+        let mut frame = Frame::from_rgba_speed(gif_width, gif_height, &mut pixels, self.speed);
+        frame.delay = self.delay;
+        frame.dispose = DisposalMethod::Background;
+        encoder.write_frame(&frame).map_err(gif_error)
+    }
+
+    /// Returns the dimensions of the video, or None before the first frame.
+    pub fn dimensions(&self) -> Option<(usize, usize)> {
+        self.dimensions
+    }
+
+    /// Finishes the animation, writing the trailer of the file and flushing the writer.
+    pub fn finish(self) -> Result<()> {
+        let mut writer = match (self.encoder, self.writer) {
+            (Some(encoder), _) => encoder.into_inner()?,
+            (None, Some(writer)) => writer,
+            (None, None) => return Err(lost_writer()),
+        };
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// The error for a writer lost to a failed header write.
+fn lost_writer() -> CoreError {
+    CoreError::InvalidData("Writing the GIF header failed earlier".to_string())
+}
+
+fn gif_error(error: gif::EncodingError) -> CoreError {
+    match error {
+        gif::EncodingError::Io(error) => CoreError::Io(error),
+        error => CoreError::InvalidData(format!("GIF: {error}")),
+    }
+}
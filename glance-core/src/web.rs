@@ -0,0 +1,57 @@
+//! Browser bridge for wasm32 builds. [`WebImage`] moves pixels between canvas `ImageData` and
+//! [`Image`], so processing written against glance runs unchanged in the browser:
+//!
+//! ```js
+//! const pixels = context.getImageData(0, 0, width, height);
+//! const image = new WebImage(pixels.width, pixels.height, pixels.data);
+//! // ... process the image with exported functions ...
+//! context.putImageData(new ImageData(image.data(), image.width, image.height), 0, 0);
+//! ```
+
+use wasm_bindgen::{Clamped, JsError, prelude::wasm_bindgen};
+
+use crate::img::{Image, pixel::Rgba};
+
+/// An RGBA image shared with JavaScript.
+#[wasm_bindgen]
+pub struct WebImage {
+    image: Image<Rgba>,
+}
+
+#[wasm_bindgen]
+impl WebImage {
+    /// Creates an image from the `data` of a canvas `ImageData`.
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: usize, height: usize, data: &[u8]) -> Result<WebImage, JsError> {
+        Ok(WebImage {
+            image: Image::from_rgba8_bytes(width, height, data)?,
+        })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> usize {
+        self.image.dimensions().0
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> usize {
+        self.image.dimensions().1
+    }
+
+    /// Returns the pixels as a `Uint8ClampedArray`, ready for `new ImageData(...)`.
+    pub fn data(&self) -> Clamped<Vec<u8>> {
+        Clamped(self.image.to_rgba8_bytes())
+    }
+}
+
+impl From<Image<Rgba>> for WebImage {
+    fn from(image: Image<Rgba>) -> Self {
+        WebImage { image }
+    }
+}
+
+impl From<WebImage> for Image<Rgba> {
+    fn from(web_image: WebImage) -> Self {
+        web_image.image
+    }
+}
@@ -0,0 +1,291 @@
+use glance_core::CoreError;
+use glance_core::img::{Image, pixel::Pixel};
+use glance_core::par::*;
+use glance_core::profiling::OpSpan;
+
+use crate::{Error, Result};
+
+/// A 2x3 affine transformation matrix in row-major order, mapping a point (x, y) to
+/// (m[0][0] * x + m[0][1] * y + m[0][2], m[1][0] * x + m[1][1] * y + m[1][2]).
+pub type AffineMatrix = [[f32; 3]; 2];
+
+/// Defines how pixel values are sampled between pixel centers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    /// Takes the value of the closest pixel.
+    Nearest,
+    /// Weights the four surrounding pixels by their distance.
+    Bilinear,
+    /// Fits a cubic through the sixteen surrounding pixels. Sharper than bilinear.
+    Bicubic,
+}
+
+/// A per pixel lookup of source positions for [`AffineTransformationsExt::remap`]: output pixel
+/// (x, y) takes the value sampled at `get((x, y))` in the source image. Used for warps without a
+/// closed form, such as lens undistortion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoordinateMap {
+    width: usize,
+    height: usize,
+    positions: Vec<(f32, f32)>,
+}
+
+impl CoordinateMap {
+    /// Creates a map from one source position per output pixel in row-major order.
+    /// Returns an error if the number of positions does not match the dimensions.
+    pub fn new(width: usize, height: usize, positions: Vec<(f32, f32)>) -> Result<Self> {
+        if positions.len() != width * height {
+            return Err(CoreError::InvalidData(format!(
+                "Coordinate map of {width}x{height} needs {} positions, got {}",
+                width * height,
+                positions.len()
+            ))
+            .into());
+        }
+        Ok(Self {
+            width,
+            height,
+            positions,
+        })
+    }
+
+    /// Creates a map by evaluating `f` for every output pixel (x, y).
+    pub fn from_fn(
+        width: usize,
+        height: usize,
+        f: impl Fn(usize, usize) -> (f32, f32) + Sync,
+    ) -> Self {
+        let positions = (0..width * height)
+            .into_par_iter()
+            .map(|idx| f(idx % width, idx / width))
+            .collect();
+        Self {
+            width,
+            height,
+            positions,
+        }
+    }
+
+    /// Returns the dimensions of the output image (width, height).
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the source position of output pixel (x, y), if inside the map.
+    pub fn get(&self, position: (usize, usize)) -> Option<(f32, f32)> {
+        let (x, y) = position;
+        (x < self.width && y < self.height).then(|| self.positions[y * self.width + x])
+    }
+}
+
+/// Returns the inverse of an affine transformation, or None if it is singular (e.g. a scale
+/// of 0).
+pub fn invert_affine(m: &AffineMatrix) -> Option<AffineMatrix> {
+    let det = m[0][0] * m[1][1] - m[0][1] * m[1][0];
+    if det == 0.0 {
+        return None;
+    }
+    let (a, b, c) = (m[1][1] / det, -m[0][1] / det, -m[1][0] / det);
+    let d = m[0][0] / det;
+    Some([
+        [a, b, -(a * m[0][2] + b * m[1][2])],
+        [c, d, -(c * m[0][2] + d * m[1][2])],
+    ])
+}
+
+/// Scale by (`sx`, `sy`) aligning pixel centers rather than corners, so the image does not
+/// drift. The inverse of a scale by (sx, sy) is the scale by (1 / sx, 1 / sy).
+pub(crate) fn scale_matrix(sx: f32, sy: f32) -> AffineMatrix {
+    [[sx, 0.0, (sx - 1.0) / 2.0], [0.0, sy, (sy - 1.0) / 2.0]]
+}
+
+/// Fails with [`Error::SingularTransformation`] unless both scale factors are positive and
+/// finite.
+pub(crate) fn check_scale(sx: f32, sy: f32) -> Result<()> {
+    match sx > 0.0 && sy > 0.0 && sx.is_finite() && sy.is_finite() {
+        true => Ok(()),
+        false => Err(Error::SingularTransformation {
+            matrix: scale_matrix(sx, sy),
+        }),
+    }
+}
+
+/// Output dimensions of a scale by (`sx`, `sy`), rounded to the nearest pixel.
+pub(crate) fn scaled_dimensions(dimensions: (usize, usize), sx: f32, sy: f32) -> (usize, usize) {
+    (
+        (dimensions.0 as f32 * sx).round() as usize,
+        (dimensions.1 as f32 * sy).round() as usize,
+    )
+}
+
+/// Scales like [`AffineTransformationsExt::scale`] for the factors computed by other
+/// operations, without checking them. Factors that are not positive give an empty image.
+pub(crate) fn scale_unchecked<P: Pixel>(
+    image: &Image<P>,
+    sx: f32,
+    sy: f32,
+    interpolation: Interpolation,
+) -> Image<P> {
+    let dimensions = scaled_dimensions(image.dimensions(), sx, sy);
+    let _span = OpSpan::enter("scale", image.dimensions());
+    sample_inverse(
+        image,
+        &scale_matrix(1.0 / sx, 1.0 / sy),
+        dimensions,
+        interpolation,
+    )
+}
+
+/// Rotation by `angle` degrees (counter-clockwise on screen, where y points down) about `center`.
+pub(crate) fn rotation_about(angle: f32, center: (f32, f32)) -> AffineMatrix {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let (cx, cy) = center;
+    [
+        [cos, sin, cx - cos * cx - sin * cy],
+        [-sin, cos, cy + sin * cx - cos * cy],
+    ]
+}
+
+/// Returns a pixel with every channel (including alpha) set to 0.0.
+fn zero<P: Pixel>() -> P {
+    P::new().map(|_| 0.0)
+}
+
+/// Samples the image at a non integer position. Positions more than half a pixel outside the
+/// image read as [`zero`].
+fn sample<P: Pixel>(image: &Image<P>, x: f32, y: f32, interpolation: Interpolation) -> P {
+    let (width, height) = image.dimensions();
+    if x < -0.5 || y < -0.5 || x >= width as f32 - 0.5 || y >= height as f32 - 0.5 {
+        return zero();
+    }
+
+    match interpolation {
+        Interpolation::Nearest => *image
+            .get_pixel((x.round() as usize, y.round() as usize))
+            .unwrap(),
+        Interpolation::Bilinear => image.sample_bilinear(x, y),
+        Interpolation::Bicubic => image.sample_bicubic(x, y),
+    }
+}
+
+/// Renders the `dimensions` sized output of `matrix` applied to `image`, failing with
+/// [`Error::SingularTransformation`] if the matrix has no inverse.
+fn warp<P: Pixel>(
+    image: &Image<P>,
+    matrix: &AffineMatrix,
+    dimensions: (usize, usize),
+    interpolation: Interpolation,
+) -> Result<Image<P>> {
+    let inverse = invert_affine(matrix).ok_or(Error::SingularTransformation { matrix: *matrix })?;
+    Ok(sample_inverse(image, &inverse, dimensions, interpolation))
+}
+
+/// Renders the `dimensions` sized output of the transformation whose inverse, mapping
+/// destination to source coordinates, is `inverse`.
+fn sample_inverse<P: Pixel>(
+    image: &Image<P>,
+    inverse: &AffineMatrix,
+    dimensions: (usize, usize),
+    interpolation: Interpolation,
+) -> Image<P> {
+    let (width, height) = dimensions;
+
+    let warped = (0..width * height)
+        .into_par_iter()
+        .map(|idx| {
+            let (x, y) = ((idx % width) as f32, (idx / width) as f32);
+            let sx = inverse[0][0] * x + inverse[0][1] * y + inverse[0][2];
+            let sy = inverse[1][0] * x + inverse[1][1] * y + inverse[1][2];
+            sample(image, sx, sy, interpolation)
+        })
+        .collect();
+
+    Image::from_data(width, height, warped).unwrap()
+}
+
+/// Extension trait for [`glance_core::img::Image`] to provide affine transformations for images
+/// of any pixel type. Areas of the output not covered by the transformed image have every
+/// channel set to 0.0 (transparent black for [`glance_core::img::pixel::Rgba`]).
+pub trait AffineTransformationsExt: Sized {
+    fn affine(self, matrix: AffineMatrix, interpolation: Interpolation) -> Result<Self>;
+    fn rotate(self, angle: f32, interpolation: Interpolation) -> Self;
+    fn rotate_about_center(self, angle: f32, interpolation: Interpolation, expand: bool) -> Self;
+    fn scale(self, sx: f32, sy: f32, interpolation: Interpolation) -> Result<Self>;
+    fn translate(self, dx: f32, dy: f32, interpolation: Interpolation) -> Self;
+    fn remap(self, map: &CoordinateMap, interpolation: Interpolation) -> Self;
+}
+
+impl<P> AffineTransformationsExt for Image<P>
+where
+    P: Pixel,
+{
+    /// Applies the affine transformation `matrix`, which maps source to destination
+    /// coordinates. The output has the same dimensions as the input. Fails with
+    /// [`Error::SingularTransformation`] if the matrix is not invertible.
+    fn affine(self, matrix: AffineMatrix, interpolation: Interpolation) -> Result<Self> {
+        let dimensions = self.dimensions();
+        warp(&self, &matrix, dimensions, interpolation)
+    }
+
+    /// Rotates the image by `angle` degrees counter-clockwise about its top-left corner
+    /// (the origin). See [`AffineTransformationsExt::rotate_about_center`] to keep the image
+    /// in frame.
+    fn rotate(self, angle: f32, interpolation: Interpolation) -> Self {
+        let dimensions = self.dimensions();
+        let inverse = rotation_about(-angle, (0.0, 0.0));
+        sample_inverse(&self, &inverse, dimensions, interpolation)
+    }
+
+    /// Rotates the image by `angle` degrees counter-clockwise about its center.
+    /// If `expand` is true the output canvas grows to contain the whole rotated image,
+    /// otherwise it keeps the input dimensions and the corners are cropped.
+    fn rotate_about_center(self, angle: f32, interpolation: Interpolation, expand: bool) -> Self {
+        let (width, height) = self.dimensions();
+        let center = ((width as f32 - 1.0) / 2.0, (height as f32 - 1.0) / 2.0);
+        let mut inverse = rotation_about(-angle, center);
+        let mut dimensions = (width, height);
+
+        if expand {
+            let (sin, cos) = angle.to_radians().sin_cos();
+            let (w, h) = (width as f32, height as f32);
+            let new_width = (w * cos.abs() + h * sin.abs() - 1e-3).ceil() as usize;
+            let new_height = (w * sin.abs() + h * cos.abs() - 1e-3).ceil() as usize;
+
+            // Move the rotated center to the center of the larger canvas, shifting the
+            // destination before it is rotated back
+            let (tx, ty) = ((new_width as f32 - w) / 2.0, (new_height as f32 - h) / 2.0);
+            for row in inverse.iter_mut() {
+                row[2] -= row[0] * tx + row[1] * ty;
+            }
+            dimensions = (new_width, new_height);
+        }
+
+        sample_inverse(&self, &inverse, dimensions, interpolation)
+    }
+
+    /// Scales the image by `sx` horizontally and `sy` vertically. The output dimensions are
+    /// scaled accordingly (rounded to the nearest pixel). Fails with
+    /// [`Error::SingularTransformation`] unless both factors are positive and finite.
+    fn scale(self, sx: f32, sy: f32, interpolation: Interpolation) -> Result<Self> {
+        check_scale(sx, sy)?;
+        Ok(scale_unchecked(&self, sx, sy, interpolation))
+    }
+
+    /// Shifts the image by (`dx`, `dy`) pixels. The output has the same dimensions as the input.
+    fn translate(self, dx: f32, dy: f32, interpolation: Interpolation) -> Self {
+        let dimensions = self.dimensions();
+        let inverse = [[1.0, 0.0, -dx], [0.0, 1.0, -dy]];
+        sample_inverse(&self, &inverse, dimensions, interpolation)
+    }
+
+    /// Samples the image at the positions of `map`. The output has the dimensions of the map.
+    fn remap(self, map: &CoordinateMap, interpolation: Interpolation) -> Self {
+        let (width, height) = map.dimensions();
+        let remapped = map
+            .positions
+            .par_iter()
+            .map(|&(x, y)| sample(&self, x, y, interpolation))
+            .collect();
+        Image::from_data(width, height, remapped).unwrap()
+    }
+}
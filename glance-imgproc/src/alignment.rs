@@ -0,0 +1,367 @@
+//! Intensity based image alignment with the enhanced correlation coefficient (ECC) of
+//! Evangelidis and Psarakis: the warp of the input image is refined by Gauss-Newton steps that
+//! maximize its zero-mean normalized correlation with the template, coarse to fine over an image
+//! pyramid. Unlike feature matching it needs no keypoints, and the correlation makes it
+//! insensitive to changes of brightness and contrast between the images.
+
+use glance_core::img::{Image, pixel::Luma};
+use glance_core::par::*;
+
+use crate::affine::{self, Interpolation};
+use crate::geometry::Homography;
+use crate::gradient::GradientExtLuma;
+use crate::linalg;
+use crate::linear_filters::LinearFilterExtLuma;
+
+/// Smallest side of the coarsest pyramid level.
+const MIN_LEVEL_SIZE: usize = 16;
+/// Standard deviation of the Gaussian smoothing both images before every level.
+const SMOOTHING_SIGMA: f32 = 1.0;
+
+/// Family of warps estimated by [`find_transform_ecc`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MotionModel {
+    /// Shift along x and y, 2 parameters
+    Translation,
+    /// Rotation and shift, 3 parameters
+    Euclidean,
+    /// Affine transformation, 6 parameters
+    Affine,
+    /// Perspective transformation, 8 parameters
+    Homography,
+}
+
+impl MotionModel {
+    /// Returns the parameters of the model closest to `warp`.
+    fn parameters(&self, warp: &Homography) -> Vec<f64> {
+        let h = warp.map(|row| row.map(|v| v as f64 / warp[2][2] as f64));
+        match self {
+            MotionModel::Translation => vec![h[0][2], h[1][2]],
+            MotionModel::Euclidean => vec![h[1][0].atan2(h[0][0]), h[0][2], h[1][2]],
+            MotionModel::Affine => vec![h[0][0], h[0][1], h[0][2], h[1][0], h[1][1], h[1][2]],
+            MotionModel::Homography => vec![
+                h[0][0], h[0][1], h[0][2], h[1][0], h[1][1], h[1][2], h[2][0], h[2][1],
+            ],
+        }
+    }
+
+    /// Returns the warp with the parameters `p`.
+    fn warp(&self, p: &[f64]) -> [[f64; 3]; 3] {
+        match self {
+            MotionModel::Translation => [[1.0, 0.0, p[0]], [0.0, 1.0, p[1]], [0.0, 0.0, 1.0]],
+            MotionModel::Euclidean => {
+                let (sin, cos) = p[0].sin_cos();
+                [[cos, -sin, p[1]], [sin, cos, p[2]], [0.0, 0.0, 1.0]]
+            }
+            MotionModel::Affine => [[p[0], p[1], p[2]], [p[3], p[4], p[5]], [0.0, 0.0, 1.0]],
+            MotionModel::Homography => [[p[0], p[1], p[2]], [p[3], p[4], p[5]], [p[6], p[7], 1.0]],
+        }
+    }
+
+    /// Writes the derivatives of the warped intensity at template position (x, y), which the
+    /// warp maps to (u, v) with the input gradient (gx, gy) there, with respect to `p`.
+    fn jacobian(
+        &self,
+        p: &[f64],
+        (x, y): (f64, f64),
+        (u, v): (f64, f64),
+        (gx, gy): (f64, f64),
+        out: &mut [f64],
+    ) {
+        match self {
+            MotionModel::Translation => out.copy_from_slice(&[gx, gy]),
+            MotionModel::Euclidean => {
+                let (sin, cos) = p[0].sin_cos();
+                let rotation = gx * (-sin * x - cos * y) + gy * (cos * x - sin * y);
+                out.copy_from_slice(&[rotation, gx, gy]);
+            }
+            MotionModel::Affine => out.copy_from_slice(&[gx * x, gx * y, gx, gy * x, gy * y, gy]),
+            MotionModel::Homography => {
+                let den = p[6] * x + p[7] * y + 1.0;
+                let (gx, gy) = (gx / den, gy / den);
+                let projective = -(gx * u + gy * v);
+                out.copy_from_slice(&[
+                    gx * x,
+                    gx * y,
+                    gx,
+                    gy * x,
+                    gy * y,
+                    gy,
+                    projective * x,
+                    projective * y,
+                ]);
+            }
+        }
+    }
+}
+
+/// Parameters of [`find_transform_ecc`].
+#[derive(Debug, Clone, Copy)]
+pub struct Ecc {
+    /// Family of the estimated warp
+    pub model: MotionModel,
+    /// Starting warp from template to input coordinates, e.g. from a previous frame
+    pub initial: Homography,
+    /// Maximum number of iterations per pyramid level
+    pub iterations: usize,
+    /// Iterations stop once the correlation improves by less than this
+    pub epsilon: f32,
+    /// Number of pyramid levels, including the full resolution one. More levels find larger
+    /// motions
+    pub levels: usize,
+}
+
+impl Default for Ecc {
+    fn default() -> Self {
+        Ecc {
+            model: MotionModel::Affine,
+            initial: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            iterations: 50,
+            epsilon: 1e-5,
+            levels: 3,
+        }
+    }
+}
+
+/// A warp found by [`find_transform_ecc`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EccAlignment {
+    /// Maps template coordinates onto input coordinates. The last row is (0, 0, 1) for all
+    /// models but [`MotionModel::Homography`]
+    pub warp: Homography,
+    /// Zero-mean normalized correlation of the aligned images, up to 1.0
+    pub correlation: f32,
+}
+
+/// Sums over the overlap of the template and the warped input of one row.
+struct Sums {
+    count: f64,
+    template: f64,
+    image: f64,
+    template_sq: f64,
+    image_sq: f64,
+    cross: f64,
+    /// Jᵀ·1, Jᵀ·template and Jᵀ·image
+    jacobian: Vec<f64>,
+    jacobian_template: Vec<f64>,
+    jacobian_image: Vec<f64>,
+    /// JᵀJ, row-major
+    hessian: Vec<f64>,
+}
+
+impl Sums {
+    fn new(n: usize) -> Self {
+        Sums {
+            count: 0.0,
+            template: 0.0,
+            image: 0.0,
+            template_sq: 0.0,
+            image_sq: 0.0,
+            cross: 0.0,
+            jacobian: vec![0.0; n],
+            jacobian_template: vec![0.0; n],
+            jacobian_image: vec![0.0; n],
+            hessian: vec![0.0; n * n],
+        }
+    }
+
+    fn add(&mut self, other: &Sums) {
+        self.count += other.count;
+        self.template += other.template;
+        self.image += other.image;
+        self.template_sq += other.template_sq;
+        self.image_sq += other.image_sq;
+        self.cross += other.cross;
+        let pairs = [
+            (&mut self.jacobian, &other.jacobian),
+            (&mut self.jacobian_template, &other.jacobian_template),
+            (&mut self.jacobian_image, &other.jacobian_image),
+            (&mut self.hessian, &other.hessian),
+        ];
+        for (a, b) in pairs {
+            a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+        }
+    }
+}
+
+/// Bilinearly samples a row-major plane at a position inside it.
+fn sample(plane: &[f32], width: usize, height: usize, x: f64, y: f64) -> f64 {
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (tx, ty) = (x - x0 as f64, y - y0 as f64);
+    let at = |x: usize, y: usize| plane[y * width + x] as f64;
+    (at(x0, y0) * (1.0 - tx) + at(x1, y0) * tx) * (1.0 - ty)
+        + (at(x0, y1) * (1.0 - tx) + at(x1, y1) * tx) * ty
+}
+
+fn plane(image: &Image<Luma>) -> Vec<f32> {
+    image.pixels().map(|p| p.l).collect()
+}
+
+/// Refines the parameters `p` on one pyramid level, returning the last correlation.
+fn refine_level(
+    template: &Image<Luma>,
+    input: &Image<Luma>,
+    params: &Ecc,
+    p: &mut [f64],
+) -> Option<f64> {
+    let n = p.len();
+    let template = template.clone().gaussian_blur(SMOOTHING_SIGMA);
+    let input = input.clone().gaussian_blur(SMOOTHING_SIGMA);
+    let (width, height) = template.dimensions();
+    let (input_width, input_height) = input.dimensions();
+    let gradient = input.sobel();
+    // Sobel responds to a unit slope with 8
+    let (gx, gy, values) = (plane(gradient.dx()), plane(gradient.dy()), plane(&input));
+    let template = plane(&template);
+    let (max_x, max_y) = ((input_width - 1) as f64, (input_height - 1) as f64);
+
+    let mut correlation = None::<f64>;
+    for _ in 0..params.iterations.max(1) {
+        let warp = params.model.warp(p);
+        let rows: Vec<Sums> = (0..height)
+            .into_par_iter()
+            .map(|y| {
+                let mut sums = Sums::new(n);
+                let mut jacobian = vec![0.0; n];
+                for x in 0..width {
+                    let (x, y) = (x as f64, y as f64);
+                    let w = warp[2][0] * x + warp[2][1] * y + warp[2][2];
+                    let u = (warp[0][0] * x + warp[0][1] * y + warp[0][2]) / w;
+                    let v = (warp[1][0] * x + warp[1][1] * y + warp[1][2]) / w;
+                    if !(0.0..=max_x).contains(&u) || !(0.0..=max_y).contains(&v) {
+                        continue;
+                    }
+                    let t = template[y as usize * width + x as usize] as f64;
+                    let i = sample(&values, input_width, input_height, u, v);
+                    let g = (
+                        sample(&gx, input_width, input_height, u, v) / 8.0,
+                        sample(&gy, input_width, input_height, u, v) / 8.0,
+                    );
+                    params.model.jacobian(p, (x, y), (u, v), g, &mut jacobian);
+
+                    sums.count += 1.0;
+                    sums.template += t;
+                    sums.image += i;
+                    sums.template_sq += t * t;
+                    sums.image_sq += i * i;
+                    sums.cross += t * i;
+                    for (a, &j) in jacobian.iter().enumerate() {
+                        sums.jacobian[a] += j;
+                        sums.jacobian_template[a] += j * t;
+                        sums.jacobian_image[a] += j * i;
+                        for (b, &k) in jacobian.iter().enumerate() {
+                            sums.hessian[a * n + b] += j * k;
+                        }
+                    }
+                }
+                sums
+            })
+            .collect();
+        let mut sums = Sums::new(n);
+        rows.iter().for_each(|row| sums.add(row));
+        if sums.count <= n as f64 {
+            return None;
+        }
+
+        // Zero-mean template and image over the overlap
+        let (mean_t, mean_i) = (sums.template / sums.count, sums.image / sums.count);
+        let cross = sums.cross - sums.count * mean_t * mean_i;
+        let image_sq = sums.image_sq - sums.count * mean_i * mean_i;
+        let template_sq = sums.template_sq - sums.count * mean_t * mean_t;
+        let zero_mean = |projection: &[f64], mean: f64| -> Vec<f64> {
+            projection
+                .iter()
+                .zip(&sums.jacobian)
+                .map(|(p, j)| p - mean * j)
+                .collect()
+        };
+        let image_projection = zero_mean(&sums.jacobian_image, mean_i);
+        let template_projection = zero_mean(&sums.jacobian_template, mean_t);
+
+        let previous = correlation;
+        let current = cross / (template_sq * image_sq).sqrt().max(f64::MIN_POSITIVE);
+        correlation = Some(current);
+        if previous.is_some_and(|previous| (current - previous).abs() < params.epsilon as f64) {
+            break;
+        }
+
+        let dot = |a: &[f64], b: &[f64]| a.iter().zip(b).map(|(a, b)| a * b).sum::<f64>();
+        let image_step = linalg::solve(&sums.hessian, &image_projection, n)?;
+        let template_step = linalg::solve(&sums.hessian, &template_projection, n)?;
+        let numerator = image_sq - dot(&image_projection, &image_step);
+        let denominator = cross - dot(&template_projection, &image_step);
+        if denominator <= 0.0 {
+            return None;
+        }
+        let lambda = numerator / denominator;
+        for (a, value) in p.iter_mut().enumerate() {
+            *value += lambda * template_step[a] - image_step[a];
+        }
+    }
+    correlation
+}
+
+/// Returns the warp `warp` between images `factor` times larger.
+fn rescale(warp: [[f64; 3]; 3], factor: f64) -> Homography {
+    let [[a, b, c], [d, e, f], [g, h, i]] = warp;
+    [
+        [a, b, c * factor],
+        [d, e, f * factor],
+        [g / factor, h / factor, i],
+    ]
+    .map(|row| row.map(|v| v as f32))
+}
+
+/// Estimates the warp aligning `input` to `template` by maximizing their enhanced correlation
+/// coefficient, so that the content at (x, y) in `template` is found at `warp`(x, y) in
+/// `input`; remap `input` through the warp to bring it onto the template. The images may
+/// differ in size, brightness and contrast, but must overlap for most of the template under
+/// the starting warp refined coarse to fine. Returns `None` if the iterations diverge.
+pub fn find_transform_ecc(
+    template: &Image<Luma>,
+    input: &Image<Luma>,
+    params: &Ecc,
+) -> Option<EccAlignment> {
+    if template.is_empty() || input.is_empty() {
+        return None;
+    }
+    let mut pyramid = vec![(template.clone(), input.clone())];
+    for _ in 1..params.levels.max(1) {
+        let (t, i) = pyramid.last().unwrap();
+        let (tw, th) = t.dimensions();
+        let (iw, ih) = i.dimensions();
+        if tw.min(th).min(iw).min(ih) / 2 < MIN_LEVEL_SIZE {
+            break;
+        }
+        let down = |image: &Image<Luma>| {
+            affine::scale_unchecked(
+                &image.clone().gaussian_blur(1.0),
+                0.5,
+                0.5,
+                Interpolation::Bilinear,
+            )
+        };
+        pyramid.push((down(t), down(i)));
+    }
+
+    let coarsest = 2f64.powi(pyramid.len() as i32 - 1);
+    let initial = rescale(
+        params.initial.map(|row| row.map(|v| v as f64)),
+        1.0 / coarsest,
+    );
+    let mut p = params.model.parameters(&initial);
+    let mut correlation = 0.0;
+    for (level, (t, i)) in pyramid.iter().enumerate().rev() {
+        correlation = refine_level(t, i, params, &mut p)?;
+        if level > 0 {
+            p = params
+                .model
+                .parameters(&rescale(params.model.warp(&p), 2.0));
+        }
+    }
+    Some(EccAlignment {
+        warp: rescale(params.model.warp(&p), 1.0),
+        correlation: correlation as f32,
+    })
+}
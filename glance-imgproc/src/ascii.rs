@@ -0,0 +1,197 @@
+//! Text renderings of images, for previews in logs and terminals without a display. Every
+//! character stands for a cell of pixels, averaged, with cells twice as tall as wide to match
+//! the usual proportions of terminal fonts.
+
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Pixel, Rgba},
+};
+
+use crate::point_ops::{GrayscaleMethod, gray_value};
+
+/// Ten ASCII characters from dark to bright, for light text on a dark terminal.
+pub const ASCII_RAMP: &str = " .:-=+*#%@";
+
+/// Unicode shade blocks from dark to bright.
+pub const BLOCK_RAMP: &str = " ░▒▓█";
+
+/// Height of a character cell relative to its width.
+const CELL_ASPECT: f32 = 2.0;
+
+/// Returns the mean pixels of a `columns` x `rows` grid of cells covering the image, row-major.
+fn cell_means<P: Pixel>(image: &Image<P>, columns: usize, rows: usize) -> Vec<P> {
+    let (width, height) = image.dimensions();
+    let span = |i: usize, cells: usize, len: usize| {
+        let start = i * len / cells;
+        start..((i + 1) * len / cells).max(start + 1).min(len)
+    };
+    let mut means = Vec::with_capacity(columns * rows);
+    for row in 0..rows {
+        for column in 0..columns {
+            let (xs, ys) = (span(column, columns, width), span(row, rows, height));
+            let count = (xs.len() * ys.len()).max(1) as f32;
+            let mut mean = P::new();
+            for c in 0..P::channel_count() {
+                let sum: f32 = ys
+                    .clone()
+                    .flat_map(|y| xs.clone().map(move |x| (x, y)))
+                    .map(|position| image.get_pixel(position).unwrap().channel(c))
+                    .sum();
+                mean.set_channel(c, sum / count);
+            }
+            means.push(mean);
+        }
+    }
+    means
+}
+
+/// Returns the number of character columns and rows for an image rendered `columns`
+/// characters wide, or zero for empty images.
+fn grid<P: Pixel>(image: &Image<P>, columns: usize) -> (usize, usize) {
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 || columns == 0 {
+        return (0, 0);
+    }
+    let rows = (height as f32 * columns as f32 / width as f32 / CELL_ASPECT).round();
+    (columns, (rows as usize).max(1))
+}
+
+/// Picks the character of `charset` for an intensity in [0.0, 1.0].
+fn ramp_char(charset: &[char], value: f32) -> char {
+    let last = charset.len() - 1;
+    charset[((value.clamp(0.0, 1.0) * last as f32).round() as usize).min(last)]
+}
+
+/// Builds the braille character of a 2x4 block of dots, given in row-major order.
+fn braille(dots: [bool; 8]) -> char {
+    // Bits of the dots in row-major order, following the Unicode braille numbering
+    const BITS: [u32; 8] = [0x01, 0x08, 0x02, 0x10, 0x04, 0x20, 0x40, 0x80];
+    let code = dots
+        .iter()
+        .zip(BITS)
+        .filter(|(dot, _)| **dot)
+        .fold(0, |code, (_, bit)| code | bit);
+    char::from_u32(0x2800 + code).unwrap()
+}
+
+/// Shared implementation: renders intensities on a grid of 2x4 dots per character, calling
+/// `cell` with the character and the index of the character cell.
+fn braille_lines(
+    intensities: &[f32],
+    columns: usize,
+    rows: usize,
+    threshold: f32,
+    mut cell: impl FnMut(&mut String, char, usize),
+) -> String {
+    let mut text = String::new();
+    for row in 0..rows {
+        for column in 0..columns {
+            let dots = std::array::from_fn(|i| {
+                let (x, y) = (2 * column + i % 2, 4 * row + i / 2);
+                intensities[y * 2 * columns + x] > threshold
+            });
+            cell(&mut text, braille(dots), row * columns + column);
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Appends a character colored with an ANSI 24-bit foreground escape sequence.
+fn push_colored(text: &mut String, character: char, color: &Rgba) {
+    let [r, g, b, _] = color.to_rgba8();
+    text.push_str(&format!("\x1b[38;2;{r};{g};{b}m{character}"));
+}
+
+/// Extension trait for [`glance_core::img::Image`] to render Luma images as text
+pub trait AsciiArtExtLuma {
+    fn to_ascii(&self, width: usize, charset: &str) -> String;
+    fn to_braille(&self, width: usize, threshold: f32) -> String;
+}
+
+/// Extension trait for [`glance_core::img::Image`] to render RGBA images as text, optionally
+/// colored with ANSI escape sequences
+pub trait AsciiArtExtRgba {
+    fn to_ascii(&self, width: usize, charset: &str, color: bool) -> String;
+    fn to_braille(&self, width: usize, threshold: f32, color: bool) -> String;
+}
+
+impl AsciiArtExtLuma for Image<Luma> {
+    /// Renders the image `width` characters wide, one line per row ending in a newline. Every
+    /// character is picked from `charset`, ordered from dark to bright, e.g. [`ASCII_RAMP`].
+    /// Returns an empty string for an empty charset.
+    fn to_ascii(&self, width: usize, charset: &str) -> String {
+        let charset: Vec<char> = charset.chars().collect();
+        if charset.is_empty() {
+            return String::new();
+        }
+        let (columns, rows) = grid(self, width);
+        let mut text = String::with_capacity((columns + 1) * rows);
+        for line in cell_means(self, columns, rows).chunks(columns.max(1)) {
+            text.extend(line.iter().map(|p| ramp_char(&charset, p.l)));
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Renders the image `width` characters wide with braille characters, each showing 2x4
+    /// dots that are set where the intensity is above `threshold`. Gives four times the
+    /// resolution of [`AsciiArtExtLuma::to_ascii`], suited to masks and line drawings.
+    fn to_braille(&self, width: usize, threshold: f32) -> String {
+        let (columns, rows) = grid(self, width);
+        let dots: Vec<f32> = cell_means(self, 2 * columns, 4 * rows)
+            .iter()
+            .map(|p| p.l)
+            .collect();
+        braille_lines(&dots, columns, rows, threshold, |text, c, _| text.push(c))
+    }
+}
+
+impl AsciiArtExtRgba for Image<Rgba> {
+    /// Renders the image like [`AsciiArtExtLuma::to_ascii`], picking characters by the
+    /// brightness of every cell. With `color`, every character is colored with the mean color
+    /// of its cell by an ANSI escape sequence for 24-bit color terminals.
+    fn to_ascii(&self, width: usize, charset: &str, color: bool) -> String {
+        let charset: Vec<char> = charset.chars().collect();
+        if charset.is_empty() {
+            return String::new();
+        }
+        let (columns, rows) = grid(self, width);
+        let mut text = String::new();
+        for line in cell_means(self, columns, rows).chunks(columns.max(1)) {
+            for pixel in line {
+                let character = ramp_char(&charset, gray_value(pixel, GrayscaleMethod::Bt601Gamma));
+                if color {
+                    push_colored(&mut text, character, pixel);
+                } else {
+                    text.push(character);
+                }
+            }
+            if color {
+                text.push_str("\x1b[0m");
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Renders the brightness of the image like [`AsciiArtExtLuma::to_braille`]. With `color`,
+    /// every character is colored with the mean color of its cell.
+    fn to_braille(&self, width: usize, threshold: f32, color: bool) -> String {
+        let (columns, rows) = grid(self, width);
+        let dots: Vec<f32> = cell_means(self, 2 * columns, 4 * rows)
+            .iter()
+            .map(|p| gray_value(p, GrayscaleMethod::Bt601Gamma))
+            .collect();
+        if !color {
+            return braille_lines(&dots, columns, rows, threshold, |text, c, _| text.push(c));
+        }
+        let colors = cell_means(self, columns, rows);
+        braille_lines(&dots, columns, rows, threshold, |text, c, i| {
+            push_colored(text, c, &colors[i]);
+            if (i + 1) % columns == 0 {
+                text.push_str("\x1b[0m");
+            }
+        })
+    }
+}
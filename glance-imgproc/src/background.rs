@@ -0,0 +1,230 @@
+use glance_core::img::{Image, pixel::Luma};
+use glance_core::par::*;
+
+use crate::{Error, Result};
+
+/// Variance assigned to new mixture components.
+const INITIAL_VARIANCE: f32 = 0.0025;
+/// Lower and upper bounds of the component variances, for intensities in [0.0, 1.0].
+const VARIANCE_RANGE: (f32, f32) = (0.0001, 0.05);
+/// Fraction of the total weight that may belong to foreground components.
+const FOREGROUND_WEIGHT: f32 = 0.1;
+
+/// How the background of every pixel is modeled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BackgroundModel {
+    /// Exponential running average. Pixels differing from the average by more than
+    /// `threshold` are foreground. Cheap, but only handles a static background.
+    RunningAverage { threshold: f32 },
+    /// Adaptive mixture of up to `components` Gaussians per pixel (similar to OpenCV's MOG2),
+    /// which also learns multimodal backgrounds like swaying trees or flickering screens.
+    /// Values further than `deviations` standard deviations from every background component
+    /// are foreground.
+    GaussianMixture { components: usize, deviations: f32 },
+}
+
+/// One Gaussian of a pixel's mixture.
+#[derive(Debug, Clone, Copy)]
+struct Component {
+    weight: f32,
+    mean: f32,
+    variance: f32,
+}
+
+#[derive(Debug, Clone)]
+enum State {
+    Average(Vec<f32>),
+    Mixture(Vec<Vec<Component>>),
+}
+
+/// Stateful foreground detector for a stream of frames of the same size.
+///
+/// ```
+/// use glance_core::img::{Image, pixel::Luma};
+/// use glance_imgproc::background::{BackgroundModel, BackgroundSubtractor};
+///
+/// let mut subtractor =
+///     BackgroundSubtractor::new(BackgroundModel::RunningAverage { threshold: 0.1 }, 0.05);
+/// let frame = Image::<Luma>::new(32, 32);
+/// let mask = subtractor.apply(&frame)?;
+/// assert_eq!(mask.dimensions(), (32, 32));
+/// # Ok::<(), glance_imgproc::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct BackgroundSubtractor {
+    model: BackgroundModel,
+    learning_rate: f32,
+    dimensions: (usize, usize),
+    state: Option<State>,
+}
+
+impl BackgroundSubtractor {
+    /// Creates a subtractor with the given model. `learning_rate` in [0.0, 1.0] sets how fast
+    /// the background adapts: 0.0 freezes it after the first frame, 1.0 replaces it with every
+    /// frame.
+    pub fn new(model: BackgroundModel, learning_rate: f32) -> Self {
+        BackgroundSubtractor {
+            model,
+            learning_rate: learning_rate.clamp(0.0, 1.0),
+            dimensions: (0, 0),
+            state: None,
+        }
+    }
+
+    /// Sets the learning rate, e.g. to stop adapting while an object is known to be present.
+    pub fn set_learning_rate(&mut self, learning_rate: f32) {
+        self.learning_rate = learning_rate.clamp(0.0, 1.0);
+    }
+
+    /// Discards the learned background.
+    pub fn reset(&mut self) {
+        self.state = None;
+    }
+
+    /// Classifies the pixels of `frame` and updates the background model with it.
+    /// Returns a mask that is 1.0 on foreground and 0.0 on background pixels. The first frame
+    /// initializes the model and is entirely background. Fails with
+    /// [`Error::DimensionMismatch`] if the frame size differs from the previous frames.
+    pub fn apply(&mut self, frame: &Image<Luma>) -> Result<Image<Luma>> {
+        let (width, height) = frame.dimensions();
+        let values: Vec<f32> = frame.pixels().map(|p| p.l).collect();
+        let alpha = self.learning_rate;
+
+        let Some(state) = &mut self.state else {
+            self.dimensions = (width, height);
+            self.state = Some(match self.model {
+                BackgroundModel::RunningAverage { .. } => State::Average(values),
+                BackgroundModel::GaussianMixture { .. } => State::Mixture(
+                    values
+                        .iter()
+                        .map(|&mean| {
+                            vec![Component {
+                                weight: 1.0,
+                                mean,
+                                variance: INITIAL_VARIANCE,
+                            }]
+                        })
+                        .collect(),
+                ),
+            });
+            return Ok(Image::new(width, height));
+        };
+
+        if self.dimensions != (width, height) {
+            return Err(Error::DimensionMismatch {
+                expected: self.dimensions,
+                actual: (width, height),
+            });
+        }
+
+        let mask: Vec<Luma> = match (state, self.model) {
+            (State::Average(average), BackgroundModel::RunningAverage { threshold }) => average
+                .par_iter_mut()
+                .zip(&values)
+                .map(|(background, &value)| {
+                    let foreground = (value - *background).abs() > threshold;
+                    *background += alpha * (value - *background);
+                    foreground
+                })
+                .map(to_mask)
+                .collect(),
+            (
+                State::Mixture(mixtures),
+                BackgroundModel::GaussianMixture {
+                    components,
+                    deviations,
+                },
+            ) => mixtures
+                .par_iter_mut()
+                .zip(&values)
+                .map(|(mixture, &value)| {
+                    update_mixture(mixture, value, alpha, components.max(1), deviations)
+                })
+                .map(to_mask)
+                .collect(),
+            _ => unreachable!("the state always matches the model"),
+        };
+
+        Ok(Image::from_data(width, height, mask)?)
+    }
+
+    /// Returns the current background estimate (the running average, or the mean of the
+    /// strongest component of every mixture), or None before the first frame.
+    pub fn background(&self) -> Option<Image<Luma>> {
+        let data = match self.state.as_ref()? {
+            State::Average(average) => average.iter().map(|&l| Luma { l }).collect(),
+            State::Mixture(mixtures) => mixtures.iter().map(|m| Luma { l: m[0].mean }).collect(),
+        };
+        Some(Image::from_data(self.dimensions.0, self.dimensions.1, data).unwrap())
+    }
+}
+
+fn to_mask(foreground: bool) -> Luma {
+    Luma {
+        l: if foreground { 1.0 } else { 0.0 },
+    }
+}
+
+/// Updates the mixture of one pixel with `value` and returns true if the value is foreground.
+/// Components are kept sorted by descending weight, the strongest ones holding
+/// `1.0 - FOREGROUND_WEIGHT` of the total weight are the background.
+fn update_mixture(
+    mixture: &mut Vec<Component>,
+    value: f32,
+    alpha: f32,
+    max_components: usize,
+    deviations: f32,
+) -> bool {
+    let matched = mixture
+        .iter()
+        .position(|c| (value - c.mean).powi(2) < deviations * deviations * c.variance);
+
+    // Background components are the leading ones up to the cumulative weight threshold
+    let mut cumulative = 0.0;
+    let background_count = mixture
+        .iter()
+        .take_while(|c| {
+            let include = cumulative < 1.0 - FOREGROUND_WEIGHT;
+            cumulative += c.weight;
+            include
+        })
+        .count();
+    let foreground = matched.is_none_or(|i| i >= background_count);
+
+    for (i, component) in mixture.iter_mut().enumerate() {
+        if Some(i) == matched {
+            component.weight += alpha * (1.0 - component.weight);
+            let rate = if component.weight > 0.0 {
+                (alpha / component.weight).min(1.0)
+            } else {
+                alpha
+            };
+            let difference = value - component.mean;
+            component.mean += rate * difference;
+            component.variance = (component.variance
+                + rate * (difference * difference - component.variance))
+                .clamp(VARIANCE_RANGE.0, VARIANCE_RANGE.1);
+        } else {
+            component.weight *= 1.0 - alpha;
+        }
+    }
+
+    if matched.is_none() {
+        let component = Component {
+            weight: alpha.max(f32::EPSILON),
+            mean: value,
+            variance: INITIAL_VARIANCE,
+        };
+        if mixture.len() < max_components {
+            mixture.push(component);
+        } else {
+            *mixture.last_mut().unwrap() = component;
+        }
+    }
+
+    let total: f32 = mixture.iter().map(|c| c.weight).sum();
+    mixture.iter_mut().for_each(|c| c.weight /= total);
+    mixture.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+
+    foreground
+}
@@ -0,0 +1,192 @@
+//! Multi-band blending of two images across a mask, after Burt and Adelson, "A Multiresolution
+//! Spline With Application to Image Mosaics" (1983).
+//!
+//! Both images are split into Laplacian pyramids, i.e. frequency bands, which are blended
+//! separately with a correspondingly blurred mask: low frequencies are mixed over a wide
+//! transition and fine detail over a narrow one, which hides the seam without ghosting.
+
+use glance_core::img::Image;
+use glance_core::img::pixel::{Luma, Pixel};
+
+use crate::{Error, Result};
+
+/// The binomial kernel used to build the pyramids.
+const KERNEL: [f32; 5] = [1.0 / 16.0, 4.0 / 16.0, 6.0 / 16.0, 4.0 / 16.0, 1.0 / 16.0];
+
+/// A single channel of an image, row-major.
+#[derive(Clone)]
+struct Plane {
+    width: usize,
+    height: usize,
+    data: Vec<f32>,
+}
+
+impl Plane {
+    fn from_channel<P: Pixel>(image: &Image<P>, channel: usize) -> Self {
+        let (width, height) = image.dimensions();
+        let data = image.pixels().map(|p| p.channel(channel)).collect();
+        Plane {
+            width,
+            height,
+            data,
+        }
+    }
+
+    fn transpose(&self) -> Self {
+        let (w, h) = (self.width, self.height);
+        let data = (0..w * h).map(|i| self.data[(i % h) * w + i / h]).collect();
+        Plane {
+            width: h,
+            height: w,
+            data,
+        }
+    }
+
+    /// Blurs and halves every row, rounding the width up.
+    fn reduce_rows(&self) -> Self {
+        let width = self.width.div_ceil(2);
+        let last = self.width as isize - 1;
+        let mut data = Vec::with_capacity(width * self.height);
+        for row in self.data.chunks_exact(self.width) {
+            data.extend((0..width).map(|x| {
+                KERNEL
+                    .iter()
+                    .enumerate()
+                    .map(|(m, w)| {
+                        w * row[(2 * x as isize + m as isize - 2).clamp(0, last) as usize]
+                    })
+                    .sum::<f32>()
+            }));
+        }
+        Plane {
+            width,
+            height: self.height,
+            data,
+        }
+    }
+
+    /// Upsamples every row to `width` by inserting zeros and interpolating with the kernel.
+    fn expand_rows(&self, width: usize) -> Self {
+        let last = self.width as isize - 1;
+        let mut data = Vec::with_capacity(width * self.height);
+        for row in self.data.chunks_exact(self.width) {
+            data.extend((0..width).map(|x| {
+                KERNEL
+                    .iter()
+                    .enumerate()
+                    .filter(|(m, _)| (x + m).is_multiple_of(2))
+                    .map(|(m, w)| {
+                        let source = (x as isize + 2 - m as isize).div_euclid(2);
+                        2.0 * w * row[source.clamp(0, last) as usize]
+                    })
+                    .sum::<f32>()
+            }));
+        }
+        Plane {
+            width,
+            height: self.height,
+            data,
+        }
+    }
+
+    fn reduce(&self) -> Self {
+        self.reduce_rows().transpose().reduce_rows().transpose()
+    }
+
+    fn expand(&self, width: usize, height: usize) -> Self {
+        self.expand_rows(width)
+            .transpose()
+            .expand_rows(height)
+            .transpose()
+    }
+
+    fn zip_with(&self, other: &Plane, f: impl Fn(f32, f32) -> f32) -> Self {
+        Plane {
+            data: self
+                .data
+                .iter()
+                .zip(&other.data)
+                .map(|(&a, &b)| f(a, b))
+                .collect(),
+            ..*self
+        }
+    }
+}
+
+/// Returns the Gaussian pyramid of the plane, finest level first.
+fn gaussian_pyramid(plane: Plane, levels: usize) -> Vec<Plane> {
+    let mut pyramid = vec![plane];
+    while pyramid.len() < levels {
+        let next = pyramid.last().unwrap().reduce();
+        pyramid.push(next);
+    }
+    pyramid
+}
+
+/// Converts a Gaussian pyramid into a Laplacian pyramid in place: every level but the coarsest
+/// keeps only the detail missing from the next coarser one.
+fn laplacian_pyramid(mut pyramid: Vec<Plane>) -> Vec<Plane> {
+    for i in 0..pyramid.len() - 1 {
+        let (width, height) = (pyramid[i].width, pyramid[i].height);
+        let coarse = pyramid[i + 1].expand(width, height);
+        pyramid[i] = pyramid[i].zip_with(&coarse, |fine, coarse| fine - coarse);
+    }
+    pyramid
+}
+
+/// Blends `a` and `b` with multi-band blending. `mask` is the weight of `a` in [0.0, 1.0], so
+/// a hard 0/1 mask chooses the image on each side of the seam. `levels` is the number of pyramid
+/// levels including the full resolution one; more levels blend low frequencies over a wider
+/// transition. It is limited by the image size, and 1 gives plain alpha blending.
+pub fn blend_multiband<P: Pixel>(
+    a: &Image<P>,
+    b: &Image<P>,
+    mask: &Image<Luma>,
+    levels: usize,
+) -> Result<Image<P>> {
+    let (width, height) = a.dimensions();
+    if let Some(actual) = [b.dimensions(), mask.dimensions()]
+        .into_iter()
+        .find(|&d| d != (width, height))
+    {
+        return Err(Error::DimensionMismatch {
+            expected: (width, height),
+            actual,
+        });
+    }
+    if width == 0 || height == 0 {
+        return Ok(a.clone());
+    }
+
+    // Stop once a level is a single pixel wide or high
+    let max_levels = width.min(height).ilog2() as usize + 1;
+    let levels = levels.clamp(1, max_levels);
+    let weights = gaussian_pyramid(Plane::from_channel(mask, 0), levels);
+
+    let mut blended = a.clone();
+    for channel in 0..P::channel_count() {
+        let bands_a = laplacian_pyramid(gaussian_pyramid(Plane::from_channel(a, channel), levels));
+        let bands_b = laplacian_pyramid(gaussian_pyramid(Plane::from_channel(b, channel), levels));
+        let mut bands: Vec<Plane> = bands_a
+            .iter()
+            .zip(&bands_b)
+            .zip(&weights)
+            .map(|((a, b), w)| Plane {
+                data: (a.data.iter().zip(&b.data).zip(&w.data))
+                    .map(|((a, b), w)| w * a + (1.0 - w) * b)
+                    .collect(),
+                ..*a
+            })
+            .collect();
+
+        // Collapse the pyramid from the coarsest level
+        let mut result = bands.pop().unwrap();
+        while let Some(band) = bands.pop() {
+            result = band.zip_with(&result.expand(band.width, band.height), |d, c| d + c);
+        }
+        for (pixel, value) in blended.pixels_mut().zip(result.data) {
+            pixel.set_channel(channel, value);
+        }
+    }
+    Ok(blended)
+}
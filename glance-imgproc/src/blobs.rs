@@ -0,0 +1,290 @@
+use glance_core::img::{Image, pixel::Luma};
+
+use crate::components::{ConnectedComponentsExtLuma, Connectivity};
+use crate::contours::ContourExtLuma;
+use crate::features::Keypoint;
+use crate::linear_filters::LinearFilterExtLuma;
+use crate::moments::moments;
+
+/// Which kind of blobs to detect.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlobPolarity {
+    /// Blobs brighter than their surroundings.
+    Bright,
+    /// Blobs darker than their surroundings.
+    Dark,
+    /// Both bright and dark blobs.
+    Both,
+}
+
+/// A blob found by [`BlobDetector::detect`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blob {
+    /// Center of the blob, with `size` the diameter at which the detector responded strongest
+    pub keypoint: Keypoint,
+    /// Scale normalized difference of Gaussians response, negative for bright blobs
+    pub response: f32,
+    /// Area of the segmented blob in pixels
+    pub area: f32,
+    /// 4π · area / perimeter², 1.0 for a perfect circle
+    pub circularity: f32,
+    /// Ratio of the minor to the major axis of inertia, 1.0 for round blobs and 0.0 for lines
+    pub inertia_ratio: f32,
+}
+
+/// Blob detector built on a difference of Gaussians (DoG) scale space, similar to OpenCV's
+/// `SimpleBlobDetector`.
+///
+/// Candidates are the extrema of the DoG pyramid over space and scale. Each candidate is then
+/// segmented at half its contrast to measure its area, circularity and inertia ratio, which can be
+/// filtered on to reject e.g. elongated or irregular shapes.
+#[derive(Debug, Clone, Copy)]
+pub struct BlobDetector {
+    /// Smallest Gaussian scale searched, in pixels. A disk of radius r responds at r / √2
+    pub min_sigma: f32,
+    /// Largest Gaussian scale searched, in pixels
+    pub max_sigma: f32,
+    /// Number of scales per octave (doubling of sigma)
+    pub scales_per_octave: usize,
+    /// Minimum absolute DoG response for a candidate, relative to an intensity range of 1.0
+    pub threshold: f32,
+    /// Which blobs to report
+    pub polarity: BlobPolarity,
+    /// Accepted range of blob areas in pixels (min, max)
+    pub area: Option<(f32, f32)>,
+    /// Minimum circularity
+    pub min_circularity: Option<f32>,
+    /// Minimum inertia ratio
+    pub min_inertia_ratio: Option<f32>,
+}
+
+impl Default for BlobDetector {
+    fn default() -> Self {
+        BlobDetector {
+            min_sigma: 1.0,
+            max_sigma: 16.0,
+            scales_per_octave: 3,
+            threshold: 0.02,
+            polarity: BlobPolarity::Both,
+            area: None,
+            min_circularity: None,
+            min_inertia_ratio: None,
+        }
+    }
+}
+
+impl BlobDetector {
+    /// Detects blobs in the image, strongest first. Overlapping detections of the same blob
+    /// at neighbouring scales are merged into the strongest one.
+    pub fn detect(&self, image: &Image<Luma>) -> Vec<Blob> {
+        let mut candidates = self.scale_space_extrema(image);
+        candidates.sort_by(|a, b| b.1.abs().total_cmp(&a.1.abs()));
+
+        let mut blobs: Vec<Blob> = Vec::new();
+        for ((x, y, sigma), response) in candidates {
+            let radius = sigma * std::f32::consts::SQRT_2;
+            let overlaps = blobs.iter().any(|b| {
+                let (bx, by) = b.keypoint.position;
+                (bx - x).hypot(by - y) < radius.max(b.keypoint.size / 2.0)
+            });
+            if overlaps {
+                continue;
+            }
+
+            let Some((centroid, area, circularity, inertia_ratio)) =
+                measure_shape(image, (x, y), radius, response < 0.0)
+            else {
+                continue;
+            };
+            let accepted = self
+                .area
+                .is_none_or(|(min, max)| (min..=max).contains(&area))
+                && self.min_circularity.is_none_or(|min| circularity >= min)
+                && self
+                    .min_inertia_ratio
+                    .is_none_or(|min| inertia_ratio >= min);
+            if accepted {
+                blobs.push(Blob {
+                    keypoint: Keypoint {
+                        position: centroid,
+                        size: 2.0 * radius,
+                        angle: 0.0,
+                    },
+                    response,
+                    area,
+                    circularity,
+                    inertia_ratio,
+                });
+            }
+        }
+
+        blobs
+    }
+
+    /// Returns the DoG extrema as ((x, y, sigma), response) in full resolution coordinates.
+    fn scale_space_extrema(&self, image: &Image<Luma>) -> Vec<((f32, f32, f32), f32)> {
+        let scales = self.scales_per_octave.max(1);
+        let k = 2f32.powf(1.0 / scales as f32);
+        let sigma0 = self.min_sigma.max(0.5);
+        let mut base = image.clone().gaussian_blur(sigma0);
+        let mut extrema = Vec::new();
+        let mut octave = 0;
+
+        while sigma0 * 2f32.powi(octave) <= self.max_sigma
+            && base.dimensions().0.min(base.dimensions().1) >= 8
+        {
+            let factor = 2f32.powi(octave);
+            let (width, height) = base.dimensions();
+
+            // Gaussian levels at sigma0 * k^j, each blurred incrementally from the previous one
+            let mut levels = vec![base.clone()];
+            for j in 1..scales + 3 {
+                let (previous, current) =
+                    (sigma0 * k.powi(j as i32 - 1), sigma0 * k.powi(j as i32));
+                let step = (current * current - previous * previous).sqrt();
+                levels.push(levels[j - 1].clone().gaussian_blur(step));
+            }
+            let planes: Vec<Vec<f32>> = levels
+                .iter()
+                .map(|l| l.pixels().map(|p| p.l).collect())
+                .collect();
+            // Scaling by 1 / (k - 1) approximates the scale normalized Laplacian
+            let dog: Vec<Vec<f32>> = planes
+                .windows(2)
+                .map(|w| {
+                    w[1].iter()
+                        .zip(&w[0])
+                        .map(|(b, a)| (b - a) / (k - 1.0))
+                        .collect()
+                })
+                .collect();
+
+            for j in 1..=scales {
+                let sigma = sigma0 * k.powi(j as i32 - 1) * factor;
+                if sigma > self.max_sigma {
+                    break;
+                }
+                for y in 1..height - 1 {
+                    for x in 1..width - 1 {
+                        let value = dog[j][y * width + x];
+                        let wanted = match self.polarity {
+                            BlobPolarity::Bright => value < -self.threshold,
+                            BlobPolarity::Dark => value > self.threshold,
+                            BlobPolarity::Both => value.abs() > self.threshold,
+                        };
+                        if wanted && is_extremum(&dog[j - 1..=j + 1], width, x, y, value) {
+                            extrema.push(((x as f32 * factor, y as f32 * factor, sigma), value));
+                        }
+                    }
+                }
+            }
+
+            // The next octave starts from the level at twice the base sigma, halved in size
+            let next = &levels[scales];
+            let (half_w, half_h) = (width / 2, height / 2);
+            let data = (0..half_w * half_h)
+                .map(|i| {
+                    *next
+                        .get_pixel(((i % half_w) * 2, (i / half_w) * 2))
+                        .unwrap()
+                })
+                .collect();
+            base = Image::from_data(half_w, half_h, data).unwrap();
+            octave += 1;
+        }
+
+        extrema
+    }
+}
+
+/// Returns true if `value` at (x, y) of the middle DoG plane is strictly larger (or smaller)
+/// than its 26 neighbours in space and scale.
+fn is_extremum(dog: &[Vec<f32>], width: usize, x: usize, y: usize, value: f32) -> bool {
+    let mut is_max = true;
+    let mut is_min = true;
+    for (s, plane) in dog.iter().enumerate() {
+        for ny in y - 1..=y + 1 {
+            for nx in x - 1..=x + 1 {
+                if s == 1 && nx == x && ny == y {
+                    continue;
+                }
+                let neighbour = plane[ny * width + nx];
+                is_max &= value > neighbour;
+                is_min &= value < neighbour;
+            }
+        }
+        if !is_max && !is_min {
+            return false;
+        }
+    }
+    true
+}
+
+/// Segments the blob at `center` by thresholding a window of three radii halfway between the
+/// center and the window border intensity, and returns (centroid, area, circularity, inertia
+/// ratio) of the component containing the center. The centroid refines the position found on
+/// coarse pyramid levels.
+fn measure_shape(
+    image: &Image<Luma>,
+    center: (f32, f32),
+    radius: f32,
+    bright: bool,
+) -> Option<((f32, f32), f32, f32, f32)> {
+    let (width, height) = image.dimensions();
+    let reach = (3.0 * radius).ceil() as isize;
+    let (cx, cy) = (center.0 as isize, center.1 as isize);
+    let (x0, y0) = ((cx - reach).max(0) as usize, (cy - reach).max(0) as usize);
+    let (x1, y1) = (
+        ((cx + reach) as usize).min(width - 1),
+        ((cy + reach) as usize).min(height - 1),
+    );
+    let (w, h) = (x1 - x0 + 1, y1 - y0 + 1);
+
+    let window: Vec<f32> = (0..w * h)
+        .map(|i| image.get_pixel((x0 + i % w, y0 + i / w)).unwrap().l)
+        .collect();
+    let border: Vec<f32> = (0..w * h)
+        .filter(|i| i % w == 0 || i % w == w - 1 || i / w == 0 || i / w == h - 1)
+        .map(|i| window[i])
+        .collect();
+    let surround = border.iter().sum::<f32>() / border.len() as f32;
+    let peak = window[(cy as usize - y0) * w + (cx as usize - x0)];
+    let level = (peak + surround) / 2.0;
+
+    let mask_data = window
+        .iter()
+        .map(|&v| Luma {
+            l: if (v > level) == bright { 1.0 } else { 0.0 },
+        })
+        .collect();
+    let mask = Image::from_data(w, h, mask_data).unwrap();
+    let labels = mask.connected_components(Connectivity::Eight);
+    let label = labels.label((cx as usize - x0, cy as usize - y0))?;
+    if label == 0 {
+        return None;
+    }
+    let blob = labels.mask(label);
+
+    let m = moments(&blob);
+    let contour = blob.find_contours().into_iter().next()?;
+    let perimeter = contour.perimeter();
+    let circularity = if perimeter > 0.0 {
+        (4.0 * std::f32::consts::PI * contour.area() / (perimeter * perimeter)).min(1.0)
+    } else {
+        1.0
+    };
+
+    // Eigenvalues of the second order central moments
+    let (a, b, c) = (m.mu20, m.mu11, m.mu02);
+    let spread = (((a - c) / 2.0).powi(2) + b * b).sqrt();
+    let (major, minor) = ((a + c) / 2.0 + spread, (a + c) / 2.0 - spread);
+    let inertia_ratio = if major > 0.0 {
+        (minor / major) as f32
+    } else {
+        1.0
+    };
+
+    let (mx, my) = m.centroid()?;
+    let centroid = (x0 as f32 + mx, y0 as f32 + my);
+    Some((centroid, m.area() as f32, circularity, inertia_ratio))
+}
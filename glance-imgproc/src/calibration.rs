@@ -0,0 +1,504 @@
+//! Camera calibration from several views of a planar target such as a chessboard, following
+//! Zhang's method: a closed form estimate of the intrinsics from the plane homographies, refined
+//! together with the lens distortion and the pose of every view by Levenberg-Marquardt.
+
+use crate::affine::CoordinateMap;
+use crate::estimation::estimate_homography;
+use crate::geometry::Point;
+use crate::linalg::{solve, symmetric_eigen};
+
+/// The pinhole camera matrix: focal lengths and principal point in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Intrinsics {
+    pub fx: f32,
+    pub fy: f32,
+    pub cx: f32,
+    pub cy: f32,
+}
+
+/// Brown-Conrady lens distortion with radial (`k1`, `k2`, `k3`) and tangential (`p1`, `p2`)
+/// coefficients, applied to normalized image coordinates.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Distortion {
+    pub k1: f32,
+    pub k2: f32,
+    pub p1: f32,
+    pub p2: f32,
+    pub k3: f32,
+}
+
+/// Position of the calibration target relative to the camera. Target points (x, y, 0) map to
+/// camera coordinates R · (x, y, 0) + t.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    /// Rotation as an axis scaled by the angle in radians (Rodrigues vector)
+    pub rotation: [f32; 3],
+    pub translation: [f32; 3],
+}
+
+/// A calibrated camera.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub intrinsics: Intrinsics,
+    pub distortion: Distortion,
+}
+
+/// Result of [`calibrate_camera`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibration {
+    pub camera: Camera,
+    /// Pose of the target in every view
+    pub poses: Vec<Pose>,
+    /// Root mean square reprojection error in pixels
+    pub rms_error: f32,
+}
+
+impl Camera {
+    /// Applies the lens distortion to a point in normalized image coordinates.
+    pub fn distort(&self, p: Point) -> Point {
+        let d = to_f64_distortion(&self.distortion);
+        let (x, y) = distort(&d, (p.0 as f64, p.1 as f64));
+        (x as f32, y as f32)
+    }
+
+    /// Projects a point of the target plane into the image of the view with `pose`.
+    pub fn project(&self, pose: &Pose, p: Point) -> Point {
+        let params = Params::pack(self, std::slice::from_ref(pose));
+        let (u, v) = params.project(0, (p.0 as f64, p.1 as f64));
+        (u as f32, v as f32)
+    }
+
+    /// Returns the map from an undistorted image of `dimensions` to the distorted source image,
+    /// to be used with [`crate::affine::AffineTransformationsExt::remap`]. The undistorted image
+    /// keeps the camera matrix of the source.
+    pub fn undistortion_map(&self, dimensions: (usize, usize)) -> CoordinateMap {
+        let Intrinsics { fx, fy, cx, cy } = self.intrinsics;
+        CoordinateMap::from_fn(dimensions.0, dimensions.1, |x, y| {
+            let normalized = ((x as f32 - cx) / fx, (y as f32 - cy) / fy);
+            let (xd, yd) = self.distort(normalized);
+            (fx * xd + cx, fy * yd + cy)
+        })
+    }
+}
+
+/// Returns the inner corner positions of a chessboard with `pattern_size` (columns, rows)
+/// inner corners and squares of `square_size`, in the order of
+/// [`crate::chessboard::ChessboardExtLuma::find_chessboard_corners`].
+pub fn chessboard_points(pattern_size: (usize, usize), square_size: f32) -> Vec<Point> {
+    (0..pattern_size.1)
+        .flat_map(|r| (0..pattern_size.0).map(move |c| (c as f32, r as f32)))
+        .map(|(c, r)| (c * square_size, r * square_size))
+        .collect()
+}
+
+/// Estimates the camera intrinsics, lens distortion and the target pose of every view from the
+/// target points `object_points` (on the plane z = 0) and their detected positions in each of
+/// the `views` taken with a camera of `image_size` (width, height). Needs at least 2 views of 4
+/// or more points each; returns `None` if the views are degenerate, e.g. all parallel to the
+/// image plane.
+pub fn calibrate_camera(
+    object_points: &[Point],
+    views: &[Vec<Point>],
+    image_size: (usize, usize),
+) -> Option<Calibration> {
+    if views.len() < 2
+        || object_points.len() < 4
+        || views.iter().any(|v| v.len() != object_points.len())
+    {
+        return None;
+    }
+
+    // Condition the homographies by mapping the image to roughly [-1, 1]
+    let scale = image_size.0.max(image_size.1) as f64 / 2.0;
+    let center = (image_size.0 as f64 / 2.0, image_size.1 as f64 / 2.0);
+    let homographies = views
+        .iter()
+        .map(|view| {
+            let correspondences: Vec<(Point, Point)> = object_points
+                .iter()
+                .zip(view)
+                .map(|(&p, &q)| {
+                    let q = (
+                        ((q.0 as f64 - center.0) / scale) as f32,
+                        ((q.1 as f64 - center.1) / scale) as f32,
+                    );
+                    (p, q)
+                })
+                .collect();
+            estimate_homography(&correspondences).map(|h| h.map(|row| row.map(|v| v as f64)))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let k = closed_form_intrinsics(&homographies)?;
+    let poses: Vec<[f64; 6]> = homographies
+        .iter()
+        .map(|h| pose_from_homography(&k, h))
+        .collect::<Option<_>>()?;
+
+    let initial = Params {
+        intrinsics: [
+            k.0 * scale,
+            k.1 * scale,
+            k.2 * scale + center.0,
+            k.3 * scale + center.1,
+        ],
+        distortion: [0.0; 5],
+        poses,
+    };
+    let observations: Vec<Vec<(f64, f64)>> = views
+        .iter()
+        .map(|v| v.iter().map(|p| (p.0 as f64, p.1 as f64)).collect())
+        .collect();
+    let objects: Vec<(f64, f64)> = object_points
+        .iter()
+        .map(|p| (p.0 as f64, p.1 as f64))
+        .collect();
+    let params = levenberg_marquardt(initial, &objects, &observations);
+
+    let residuals = params.residuals(&objects, &observations);
+    let rms_error =
+        (residuals.iter().map(|r| r * r).sum::<f64>() / (residuals.len() / 2) as f64).sqrt() as f32;
+
+    Some(Calibration {
+        camera: params.camera(),
+        poses: params.poses.iter().map(to_pose).collect(),
+        rms_error,
+    })
+}
+
+/// Parameters optimized during refinement, in f64.
+#[derive(Debug, Clone)]
+struct Params {
+    /// fx, fy, cx, cy
+    intrinsics: [f64; 4],
+    /// k1, k2, p1, p2, k3
+    distortion: [f64; 5],
+    /// Rodrigues rotation followed by the translation of every view
+    poses: Vec<[f64; 6]>,
+}
+
+impl Params {
+    fn pack(camera: &Camera, poses: &[Pose]) -> Self {
+        let Intrinsics { fx, fy, cx, cy } = camera.intrinsics;
+        Self {
+            intrinsics: [fx as f64, fy as f64, cx as f64, cy as f64],
+            distortion: to_f64_distortion(&camera.distortion),
+            poses: poses
+                .iter()
+                .map(|pose| {
+                    let (r, t) = (pose.rotation, pose.translation);
+                    [r[0], r[1], r[2], t[0], t[1], t[2]].map(|v| v as f64)
+                })
+                .collect(),
+        }
+    }
+
+    fn camera(&self) -> Camera {
+        let [fx, fy, cx, cy] = self.intrinsics.map(|v| v as f32);
+        let [k1, k2, p1, p2, k3] = self.distortion.map(|v| v as f32);
+        Camera {
+            intrinsics: Intrinsics { fx, fy, cx, cy },
+            distortion: Distortion { k1, k2, p1, p2, k3 },
+        }
+    }
+
+    fn len(&self) -> usize {
+        9 + 6 * self.poses.len()
+    }
+
+    fn get(&self, i: usize) -> f64 {
+        match i {
+            0..4 => self.intrinsics[i],
+            4..9 => self.distortion[i - 4],
+            _ => self.poses[(i - 9) / 6][(i - 9) % 6],
+        }
+    }
+
+    fn set(&mut self, i: usize, value: f64) {
+        match i {
+            0..4 => self.intrinsics[i] = value,
+            4..9 => self.distortion[i - 4] = value,
+            _ => self.poses[(i - 9) / 6][(i - 9) % 6] = value,
+        }
+    }
+
+    fn project(&self, view: usize, p: (f64, f64)) -> (f64, f64) {
+        let pose = &self.poses[view];
+        let r = rodrigues(&[pose[0], pose[1], pose[2]]);
+        let camera = [0, 1, 2].map(|i| r[i][0] * p.0 + r[i][1] * p.1 + pose[3 + i]);
+        let normalized = (camera[0] / camera[2], camera[1] / camera[2]);
+        let (x, y) = distort(&self.distortion, normalized);
+        let [fx, fy, cx, cy] = self.intrinsics;
+        (fx * x + cx, fy * y + cy)
+    }
+
+    /// Reprojection errors (x and y) of every point in every view.
+    fn residuals(&self, objects: &[(f64, f64)], observations: &[Vec<(f64, f64)>]) -> Vec<f64> {
+        observations
+            .iter()
+            .enumerate()
+            .flat_map(|(view, observed)| {
+                objects.iter().zip(observed).flat_map(move |(&p, &q)| {
+                    let (u, v) = self.project(view, p);
+                    [u - q.0, v - q.1]
+                })
+            })
+            .collect()
+    }
+}
+
+fn to_f64_distortion(d: &Distortion) -> [f64; 5] {
+    [d.k1, d.k2, d.p1, d.p2, d.k3].map(|v| v as f64)
+}
+
+fn to_pose(p: &[f64; 6]) -> Pose {
+    Pose {
+        rotation: [p[0] as f32, p[1] as f32, p[2] as f32],
+        translation: [p[3] as f32, p[4] as f32, p[5] as f32],
+    }
+}
+
+fn distort(d: &[f64; 5], (x, y): (f64, f64)) -> (f64, f64) {
+    let [k1, k2, p1, p2, k3] = *d;
+    let r2 = x * x + y * y;
+    let radial = 1.0 + r2 * (k1 + r2 * (k2 + r2 * k3));
+    (
+        x * radial + 2.0 * p1 * x * y + p2 * (r2 + 2.0 * x * x),
+        y * radial + p1 * (r2 + 2.0 * y * y) + 2.0 * p2 * x * y,
+    )
+}
+
+/// Converts a Rodrigues vector to a rotation matrix.
+fn rodrigues(r: &[f64; 3]) -> [[f64; 3]; 3] {
+    let theta = (r[0] * r[0] + r[1] * r[1] + r[2] * r[2]).sqrt();
+    if theta < 1e-12 {
+        return [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    }
+    let k = r.map(|v| v / theta);
+    let (sin, cos) = theta.sin_cos();
+    std::array::from_fn(|i| {
+        std::array::from_fn(|j| {
+            let identity = if i == j { 1.0 } else { 0.0 };
+            // Cross product matrix of the axis
+            let cross = match (i, j) {
+                (0, 1) => -k[2],
+                (0, 2) => k[1],
+                (1, 0) => k[2],
+                (1, 2) => -k[0],
+                (2, 0) => -k[1],
+                (2, 1) => k[0],
+                _ => 0.0,
+            };
+            cos * identity + sin * cross + (1.0 - cos) * k[i] * k[j]
+        })
+    })
+}
+
+/// Converts a rotation matrix to a Rodrigues vector.
+fn rotation_vector(r: &[[f64; 3]; 3]) -> [f64; 3] {
+    let cos = ((r[0][0] + r[1][1] + r[2][2] - 1.0) / 2.0).clamp(-1.0, 1.0);
+    let theta = cos.acos();
+    if theta < 1e-12 {
+        return [0.0; 3];
+    }
+    if std::f64::consts::PI - theta < 1e-6 {
+        // Near 180 degrees the axis follows from the diagonal of R = 2 k kᵀ - I
+        let axis: [f64; 3] = std::array::from_fn(|i| ((r[i][i] + 1.0) / 2.0).max(0.0).sqrt());
+        let largest = (0..3).max_by(|&a, &b| axis[a].total_cmp(&axis[b])).unwrap();
+        let signed: [f64; 3] = std::array::from_fn(|i| {
+            if i == largest || r[largest][i] >= 0.0 {
+                axis[i]
+            } else {
+                -axis[i]
+            }
+        });
+        return signed.map(|v| v * theta);
+    }
+    let factor = theta / (2.0 * theta.sin());
+    [
+        (r[2][1] - r[1][2]) * factor,
+        (r[0][2] - r[2][0]) * factor,
+        (r[1][0] - r[0][1]) * factor,
+    ]
+}
+
+/// Closed form estimate of (fx, fy, cx, cy) from the target homographies: every view constrains
+/// the image of the absolute conic B = K⁻ᵀ K⁻¹ through the orthonormality of the rotation.
+fn closed_form_intrinsics(homographies: &[[[f64; 3]; 3]]) -> Option<(f64, f64, f64, f64)> {
+    // v_ij of Zhang's paper for columns i and j of H, with b = (B11, B12, B22, B13, B23, B33)
+    let v = |h: &[[f64; 3]; 3], i: usize, j: usize| {
+        [
+            h[0][i] * h[0][j],
+            h[0][i] * h[1][j] + h[1][i] * h[0][j],
+            h[1][i] * h[1][j],
+            h[2][i] * h[0][j] + h[0][i] * h[2][j],
+            h[2][i] * h[1][j] + h[1][i] * h[2][j],
+            h[2][i] * h[2][j],
+        ]
+    };
+
+    let mut rows = Vec::new();
+    for h in homographies {
+        rows.push(v(h, 0, 1));
+        let (v11, v22) = (v(h, 0, 0), v(h, 1, 1));
+        rows.push(std::array::from_fn(|k| v11[k] - v22[k]));
+    }
+    // Assume square pixels without skew, which also makes two views sufficient
+    rows.push([0.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+
+    let mut vtv = [0.0; 36];
+    for row in &rows {
+        for i in 0..6 {
+            for j in 0..6 {
+                vtv[i * 6 + j] += row[i] * row[j];
+            }
+        }
+    }
+    let (_, vectors) = symmetric_eigen(&vtv, 6);
+    let b = &vectors[0];
+    // b is only known up to sign, B11 must come out positive
+    let b: Vec<f64> = if b[0] < 0.0 {
+        b.iter().map(|v| -v).collect()
+    } else {
+        b.clone()
+    };
+    let (b11, b12, b22, b13, b23, b33) = (b[0], b[1], b[2], b[3], b[4], b[5]);
+
+    let denominator = b11 * b22 - b12 * b12;
+    if denominator <= 0.0 {
+        return None;
+    }
+    let cy = (b12 * b13 - b11 * b23) / denominator;
+    let lambda = b33 - (b13 * b13 + cy * (b12 * b13 - b11 * b23)) / b11;
+    if lambda / b11 <= 0.0 {
+        return None;
+    }
+    let fx = (lambda / b11).sqrt();
+    let fy = (lambda * b11 / denominator).sqrt();
+    let skew = -b12 * fx * fx * fy / lambda;
+    let cx = skew * cy / fy - b13 * fx * fx / lambda;
+    Some((fx, fy, cx, cy))
+}
+
+/// Recovers the pose of a view from its homography H = K [r1 r2 t].
+fn pose_from_homography(k: &(f64, f64, f64, f64), h: &[[f64; 3]; 3]) -> Option<[f64; 6]> {
+    let &(fx, fy, cx, cy) = k;
+    let column = |j: usize| {
+        let (x, y, z) = (h[0][j], h[1][j], h[2][j]);
+        [(x - cx * z) / fx, (y - cy * z) / fy, z]
+    };
+    let (h1, h2, h3) = (column(0), column(1), column(2));
+    let norm = |v: &[f64; 3]| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    let mut lambda = 1.0 / norm(&h1);
+    if !lambda.is_finite() {
+        return None;
+    }
+    // The target lies in front of the camera
+    if h3[2] * lambda < 0.0 {
+        lambda = -lambda;
+    }
+
+    let r1 = h1.map(|v| v * lambda);
+    let r2 = h2.map(|v| v * lambda);
+    let t = h3.map(|v| v * lambda);
+
+    // Orthonormalize r1 and r2 symmetrically and complete the basis
+    let unit = |v: [f64; 3]| {
+        let n = norm(&v);
+        v.map(|x| x / n)
+    };
+    let (a, b) = (unit(r1), unit(r2));
+    let bisector = unit(std::array::from_fn(|i| a[i] + b[i]));
+    let other = unit(std::array::from_fn(|i| a[i] - b[i]));
+    let r1 = unit(std::array::from_fn(|i| bisector[i] + other[i]));
+    let r2 = unit(std::array::from_fn(|i| bisector[i] - other[i]));
+    let r3 = [
+        r1[1] * r2[2] - r1[2] * r2[1],
+        r1[2] * r2[0] - r1[0] * r2[2],
+        r1[0] * r2[1] - r1[1] * r2[0],
+    ];
+    let rotation = std::array::from_fn(|i| [r1[i], r2[i], r3[i]]);
+    let r = rotation_vector(&rotation);
+    Some([r[0], r[1], r[2], t[0], t[1], t[2]])
+}
+
+/// Minimizes the reprojection error over all parameters with Levenberg-Marquardt and a forward
+/// difference Jacobian.
+fn levenberg_marquardt(
+    mut params: Params,
+    objects: &[(f64, f64)],
+    observations: &[Vec<(f64, f64)>],
+) -> Params {
+    let n = params.len();
+    let mut residuals = params.residuals(objects, observations);
+    let mut cost: f64 = residuals.iter().map(|r| r * r).sum();
+    let mut damping = 1e-3;
+
+    for _ in 0..100 {
+        // Jacobian columns by forward differences
+        let jacobian: Vec<Vec<f64>> = (0..n)
+            .map(|i| {
+                let value = params.get(i);
+                let step = 1e-6 * value.abs().max(1e-2);
+                let mut shifted = params.clone();
+                shifted.set(i, value + step);
+                shifted
+                    .residuals(objects, observations)
+                    .iter()
+                    .zip(&residuals)
+                    .map(|(a, b)| (a - b) / step)
+                    .collect()
+            })
+            .collect();
+
+        let mut jtj = vec![0.0; n * n];
+        let mut jtr = vec![0.0; n];
+        for i in 0..n {
+            jtr[i] = -jacobian[i]
+                .iter()
+                .zip(&residuals)
+                .map(|(j, r)| j * r)
+                .sum::<f64>();
+            for k in i..n {
+                let value: f64 = jacobian[i]
+                    .iter()
+                    .zip(&jacobian[k])
+                    .map(|(a, b)| a * b)
+                    .sum();
+                jtj[i * n + k] = value;
+                jtj[k * n + i] = value;
+            }
+        }
+
+        // Increase the damping until a step lowers the cost
+        let mut improved = false;
+        while damping < 1e10 {
+            let mut damped = jtj.clone();
+            (0..n).for_each(|i| damped[i * n + i] *= 1.0 + damping);
+            let Some(delta) = solve(&damped, &jtr, n) else {
+                damping *= 10.0;
+                continue;
+            };
+
+            let mut candidate = params.clone();
+            (0..n).for_each(|i| candidate.set(i, params.get(i) + delta[i]));
+            let candidate_residuals = candidate.residuals(objects, observations);
+            let candidate_cost: f64 = candidate_residuals.iter().map(|r| r * r).sum();
+            if candidate_cost < cost {
+                let relative = (cost - candidate_cost) / cost.max(1e-300);
+                params = candidate;
+                residuals = candidate_residuals;
+                cost = candidate_cost;
+                damping = (damping / 10.0).max(1e-12);
+                improved = relative > 1e-12;
+                break;
+            }
+            damping *= 10.0;
+        }
+        if !improved {
+            break;
+        }
+    }
+
+    params
+}
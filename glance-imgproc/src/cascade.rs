@@ -0,0 +1,544 @@
+//! Cascade classifiers for object detection, such as the Haar and LBP face detectors shipped
+//! with OpenCV. A boosted cascade of weak classifiers is evaluated on a sliding window over an
+//! image pyramid; every feature is a sum of rectangles read from an integral image, so each
+//! window costs only a few lookups per feature, and most windows are rejected by the first
+//! stages.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use glance_core::CoreError;
+use glance_core::img::{Image, Rect, pixel::Luma};
+use glance_core::par::*;
+use xml::reader::{EventReader, XmlEvent};
+
+use crate::Result;
+use crate::affine::{self, Interpolation};
+use crate::integral::{IntegralImage, IntegralImageExtLuma};
+
+/// Haar windows with a standard deviation below this are skipped as featureless, as OpenCV
+/// does with 10 gray levels.
+const MIN_WINDOW_STD: f64 = 10.0 / 255.0;
+/// Relative size difference within which detections are grouped, see
+/// [`CascadeOptions::min_neighbors`].
+const GROUP_EPS: f32 = 0.2;
+/// Cell sums within this per pixel of the center count as equal in LBP codes, absorbing the
+/// rounding of integral image differences; well below one gray level of an 8-bit image.
+const LBP_TOLERANCE: f64 = 1e-4;
+
+fn invalid(e: impl std::fmt::Display) -> CoreError {
+    CoreError::InvalidData(format!("invalid cascade: {e}"))
+}
+
+/// Features compared by the weak classifiers of a cascade.
+#[derive(Debug, Clone, PartialEq)]
+enum Feature {
+    /// Weighted sum of rectangles, normalized by the standard deviation of the window
+    Haar(Vec<(Rect, f32)>),
+    /// 8 bit local binary pattern of a 3x3 grid of cells, the first cell being the rectangle
+    Lbp(Rect),
+}
+
+/// Test of a tree node: take the left branch if the feature is below the threshold (Haar) or
+/// if the pattern is in the 256 bit subset (LBP).
+#[derive(Debug, Clone, PartialEq)]
+enum Split {
+    Threshold(f32),
+    Subset([u32; 8]),
+}
+
+/// A decision tree node. Children above 0 are nodes, the others leaves at minus the index.
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+    feature: usize,
+    split: Split,
+    left: i32,
+    right: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct WeakClassifier {
+    nodes: Vec<Node>,
+    leaves: Vec<f32>,
+}
+
+/// Weak classifiers whose summed votes must reach the threshold to pass the stage.
+#[derive(Debug, Clone, PartialEq)]
+struct Stage {
+    threshold: f32,
+    classifiers: Vec<WeakClassifier>,
+}
+
+/// A boosted cascade classifier, as trained by `opencv_traincascade`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cascade {
+    window: (usize, usize),
+    stages: Vec<Stage>,
+    features: Vec<Feature>,
+}
+
+/// Internal nodes and leaf values of a weak classifier as read from the XML file.
+type RawWeakClassifier = (Vec<f64>, Vec<f32>);
+/// Weighted Haar rectangles or the LBP cell of a feature as read from the XML file.
+type RawFeature = (Vec<(Rect, f32)>, Option<Rect>);
+
+/// Values read from the XML file, checked and assembled by [`Cascade::read`].
+#[derive(Default)]
+struct RawCascade {
+    feature_type: String,
+    window: (usize, usize),
+    stages: Vec<(f32, Vec<RawWeakClassifier>)>,
+    features: Vec<RawFeature>,
+}
+
+fn numbers<T: std::str::FromStr>(text: &str) -> Result<Vec<T>> {
+    text.split_whitespace()
+        .map(|n| {
+            n.parse()
+                .map_err(|_| invalid(format!("'{n}' is not a number")).into())
+        })
+        .collect()
+}
+
+fn number<T: std::str::FromStr>(text: &str) -> Result<T> {
+    numbers(text)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| invalid("missing number").into())
+}
+
+fn rect(values: &[f32]) -> Result<Rect> {
+    match values {
+        [x, y, w, h, ..] if values.iter().take(4).all(|v| *v >= 0.0) => Ok(Rect::new(
+            *x as usize,
+            *y as usize,
+            *w as usize,
+            *h as usize,
+        )),
+        _ => Err(invalid(format!("bad rectangle {values:?}")).into()),
+    }
+}
+
+impl Cascade {
+    /// Opens an OpenCV cascade XML file, see [`Cascade::read`].
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::read(File::open(path).map_err(CoreError::from)?)
+    }
+
+    /// Reads a cascade in the XML format of `opencv_traincascade` (OpenCV 2.4 and later),
+    /// with Haar or LBP features and trees of any depth. Cascades in the older
+    /// `opencv-haar-classifier` format and Haar features tilted by 45° are not supported.
+    pub fn read(reader: impl Read) -> Result<Self> {
+        let mut raw = RawCascade::default();
+        let mut path: Vec<String> = Vec::new();
+        for event in EventReader::new(BufReader::new(reader)) {
+            match event.map_err(invalid)? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => {
+                    if path.len() == 1
+                        && attributes
+                            .iter()
+                            .any(|a| a.value == "opencv-haar-classifier")
+                    {
+                        return Err(CoreError::Unsupported(
+                            "Cascades in the old opencv-haar-classifier format, convert them \
+                             with opencv_traincascade"
+                                .to_string(),
+                        )
+                        .into());
+                    }
+                    let parent = path.last().map(String::as_str);
+                    match (parent, name.local_name.as_str()) {
+                        (Some("stages"), "_") => raw.stages.push((0.0, Vec::new())),
+                        (Some("weakClassifiers"), "_") => {
+                            if let Some(stage) = raw.stages.last_mut() {
+                                stage.1.push((Vec::new(), Vec::new()));
+                            }
+                        }
+                        (Some("features"), "_") => raw.features.push((Vec::new(), None)),
+                        _ => {}
+                    }
+                    path.push(name.local_name);
+                }
+                XmlEvent::EndElement { .. } => {
+                    path.pop();
+                }
+                XmlEvent::Characters(text) => {
+                    let names: Vec<&str> = path.iter().map(String::as_str).collect();
+                    let stage = raw.stages.last_mut();
+                    let feature = raw.features.last_mut();
+                    match names[..] {
+                        [_, _, "featureType"] => raw.feature_type = text.trim().to_string(),
+                        [_, _, "width"] => raw.window.0 = number(&text)?,
+                        [_, _, "height"] => raw.window.1 = number(&text)?,
+                        [.., "stages", "_", "stageThreshold"] => {
+                            if let Some(stage) = stage {
+                                stage.0 = number(&text)?;
+                            }
+                        }
+                        [.., "weakClassifiers", "_", "internalNodes"] => {
+                            if let Some(weak) = stage.and_then(|s| s.1.last_mut()) {
+                                weak.0 = numbers(&text)?;
+                            }
+                        }
+                        [.., "weakClassifiers", "_", "leafValues"] => {
+                            if let Some(weak) = stage.and_then(|s| s.1.last_mut()) {
+                                weak.1 = numbers(&text)?;
+                            }
+                        }
+                        [.., "features", "_", "rects", "_"] => {
+                            let values: Vec<f32> = numbers(&text)?;
+                            if let (Some(feature), Some(&weight)) = (feature, values.get(4)) {
+                                feature.0.push((rect(&values)?, weight));
+                            }
+                        }
+                        [.., "features", "_", "rect"] => {
+                            if let Some(feature) = feature {
+                                feature.1 = Some(rect(&numbers(&text)?)?);
+                            }
+                        }
+                        [.., "features", "_", "tilted"] if text.trim() != "0" => {
+                            return Err(
+                                CoreError::Unsupported("Tilted Haar features".to_string()).into()
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        Self::assemble(raw)
+    }
+
+    /// Builds the cascade from the values of the XML file, checking the references.
+    fn assemble(raw: RawCascade) -> Result<Self> {
+        let lbp = match raw.feature_type.as_str() {
+            "HAAR" => false,
+            "LBP" => true,
+            other => {
+                return Err(CoreError::Unsupported(format!("{other:?} cascade features")).into());
+            }
+        };
+        if raw.window.0 < 3 || raw.window.1 < 3 || raw.stages.is_empty() {
+            return Err(invalid("missing window size or stages").into());
+        }
+        let features: Vec<Feature> = raw
+            .features
+            .into_iter()
+            .map(|(rects, cells)| match (lbp, cells) {
+                (false, _) if !rects.is_empty() => Ok(Feature::Haar(rects)),
+                (true, Some(cells)) => Ok(Feature::Lbp(cells)),
+                _ => Err(invalid("feature without rectangles")),
+            })
+            .collect::<std::result::Result<_, _>>()?;
+
+        // Haar nodes are left, right, feature, threshold; LBP nodes have a 256 bit subset
+        // instead of the threshold
+        let node_len = if lbp { 11 } else { 4 };
+        let mut stages = Vec::with_capacity(raw.stages.len());
+        for (threshold, weak) in raw.stages {
+            let mut classifiers = Vec::with_capacity(weak.len());
+            for (values, leaves) in weak {
+                if values.is_empty() || values.len() % node_len != 0 {
+                    return Err(invalid("malformed internal nodes").into());
+                }
+                let nodes: Vec<Node> = values
+                    .chunks(node_len)
+                    .map(|v| Node {
+                        left: v[0] as i32,
+                        right: v[1] as i32,
+                        feature: v[2] as usize,
+                        split: match lbp {
+                            true => Split::Subset(std::array::from_fn(|i| v[3 + i] as i32 as u32)),
+                            false => Split::Threshold(v[3] as f32),
+                        },
+                    })
+                    .collect();
+                let valid = |child: i32| match child > 0 {
+                    true => (child as usize) < nodes.len(),
+                    false => ((-child) as usize) < leaves.len(),
+                };
+                if nodes
+                    .iter()
+                    .any(|n| n.feature >= features.len() || !valid(n.left) || !valid(n.right))
+                {
+                    return Err(invalid("node refers to a missing feature, node or leaf").into());
+                }
+                classifiers.push(WeakClassifier { nodes, leaves });
+            }
+            stages.push(Stage {
+                threshold,
+                classifiers,
+            });
+        }
+        Ok(Self {
+            window: raw.window,
+            stages,
+            features,
+        })
+    }
+
+    /// Returns the size of the detection window the cascade was trained for, as a tuple
+    /// (width, height).
+    pub fn window(&self) -> (usize, usize) {
+        self.window
+    }
+
+    /// Returns whether the window at (x, y) passes every stage.
+    fn accepts(
+        &self,
+        sums: &IntegralImage,
+        squares: &IntegralImage,
+        (x, y): (usize, usize),
+    ) -> bool {
+        let (width, height) = self.window;
+        let shifted = |r: &Rect| Rect::new(x + r.x, y + r.y, r.width, r.height);
+
+        // Haar features are normalized by the standard deviation of the window, without its
+        // outermost pixels
+        let norm = Rect::new(x + 1, y + 1, width - 2, height - 2);
+        let area = norm.area() as f64;
+        let sum = sums.sum_region(norm);
+        let spread = (area * squares.sum_region(norm) - sum * sum)
+            .max(0.0)
+            .sqrt();
+        let haar = self.features.iter().any(|f| matches!(f, Feature::Haar(_)));
+        if haar && spread <= area * MIN_WINDOW_STD {
+            return false;
+        }
+
+        let go_left = |node: &Node| match (&self.features[node.feature], &node.split) {
+            (Feature::Haar(rects), Split::Threshold(threshold)) => {
+                let value: f64 = rects
+                    .iter()
+                    .map(|(r, weight)| sums.sum_region(shifted(r)) * *weight as f64)
+                    .sum();
+                value / spread < *threshold as f64
+            }
+            (Feature::Lbp(cell), Split::Subset(subset)) => {
+                let sum_at = |i: usize| {
+                    let (cx, cy) = (
+                        cell.x + (i % 3) * cell.width,
+                        cell.y + (i / 3) * cell.height,
+                    );
+                    sums.sum_region(shifted(&Rect::new(cx, cy, cell.width, cell.height)))
+                };
+                let center = sum_at(4) - LBP_TOLERANCE * cell.area() as f64;
+                // Clockwise from the top left cell, the first one as the most significant bit
+                let code = [0, 1, 2, 5, 8, 7, 6, 3].iter().fold(0usize, |code, &i| {
+                    code << 1 | (sum_at(i) >= center) as usize
+                });
+                subset[code >> 5] & (1 << (code & 31)) != 0
+            }
+            _ => false,
+        };
+
+        self.stages.iter().all(|stage| {
+            let votes: f32 = stage
+                .classifiers
+                .iter()
+                .map(|weak| {
+                    let mut index = 0;
+                    loop {
+                        let node = &weak.nodes[index];
+                        let next = if go_left(node) { node.left } else { node.right };
+                        if next <= 0 {
+                            break weak.leaves[(-next) as usize];
+                        }
+                        index = next as usize;
+                    }
+                })
+                .sum();
+            votes >= stage.threshold
+        })
+    }
+}
+
+/// Options of [`CascadeDetectionExtLuma::detect_objects`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CascadeOptions {
+    /// Size ratio between consecutive pyramid levels, above 1.0
+    pub scale_factor: f32,
+    /// Detections of an object needed to report it. Overlapping windows of about the same
+    /// size are grouped and averaged; groups with fewer detections are dropped as false
+    /// positives. 0 returns every window the cascade accepted.
+    pub min_neighbors: usize,
+    /// Smallest object size (width, height) looked for, at least the cascade window
+    pub min_size: (usize, usize),
+    /// Largest object size (width, height) looked for, unlimited if None
+    pub max_size: Option<(usize, usize)>,
+}
+
+impl Default for CascadeOptions {
+    fn default() -> Self {
+        Self {
+            scale_factor: 1.1,
+            min_neighbors: 3,
+            min_size: (0, 0),
+            max_size: None,
+        }
+    }
+}
+
+/// Groups similar rectangles as OpenCV's `groupRectangles` does: rectangles within
+/// [`GROUP_EPS`] of each other are averaged, groups of `min_neighbors` or fewer dropped, and
+/// so are groups inside a larger group with more support.
+fn group_rectangles(rects: Vec<Rect>, min_neighbors: usize) -> Vec<Rect> {
+    if min_neighbors == 0 {
+        return rects;
+    }
+    let similar = |a: &Rect, b: &Rect| {
+        let delta = GROUP_EPS * (a.width.min(b.width) + a.height.min(b.height)) as f32 * 0.5;
+        let close = |p: usize, q: usize| (p as f32 - q as f32).abs() <= delta;
+        close(a.x, b.x)
+            && close(a.y, b.y)
+            && close(a.x + a.width, b.x + b.width)
+            && close(a.y + a.height, b.y + b.height)
+    };
+    // Union-find over the similarity relation
+    let mut parent: Vec<usize> = (0..rects.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for i in 0..rects.len() {
+        for j in 0..i {
+            if similar(&rects[i], &rects[j]) {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[a] = b;
+            }
+        }
+    }
+    let mut groups: Vec<(usize, [usize; 4], usize)> = Vec::new();
+    for (i, r) in rects.iter().enumerate() {
+        let label = root(&mut parent, i);
+        let group = match groups.iter_mut().find(|g| g.0 == label) {
+            Some(group) => group,
+            None => {
+                groups.push((label, [0; 4], 0));
+                groups.last_mut().unwrap()
+            }
+        };
+        for (sum, value) in group.1.iter_mut().zip([r.x, r.y, r.width, r.height]) {
+            *sum += value;
+        }
+        group.2 += 1;
+    }
+    let averaged: Vec<(Rect, usize)> = groups
+        .iter()
+        .map(|(_, sums, n)| {
+            let mean = |sum: usize| (sum as f32 / *n as f32).round() as usize;
+            (
+                Rect::new(mean(sums[0]), mean(sums[1]), mean(sums[2]), mean(sums[3])),
+                *n,
+            )
+        })
+        .collect();
+
+    averaged
+        .iter()
+        .enumerate()
+        .filter(|(i, (r, n))| {
+            *n > min_neighbors
+                && !averaged.iter().enumerate().any(|(j, (outer, m))| {
+                    let (dx, dy) = (
+                        (outer.width as f32 * GROUP_EPS).round() as usize,
+                        (outer.height as f32 * GROUP_EPS).round() as usize,
+                    );
+                    j != *i
+                        && *m > min_neighbors
+                        && r.x + dx >= outer.x
+                        && r.y + dy >= outer.y
+                        && r.x + r.width <= outer.x + outer.width + dx
+                        && r.y + r.height <= outer.y + outer.height + dy
+                        && (*m > (*n).max(3) || *n < 3)
+                })
+        })
+        .map(|(_, (r, _))| *r)
+        .collect()
+}
+
+/// Extension trait for [`glance_core::img::Image`] to detect objects with cascade classifiers
+/// in Luma images
+pub trait CascadeDetectionExtLuma {
+    fn detect_objects(&self, cascade: &Cascade, options: &CascadeOptions) -> Vec<Rect>;
+}
+
+impl CascadeDetectionExtLuma for Image<Luma> {
+    /// Detects the objects `cascade` was trained on, at every size between the options' minimum
+    /// and maximum. The image is scaled down by [`CascadeOptions::scale_factor`] per pyramid
+    /// level and scanned with the cascade window, every second position on levels scaled by
+    /// up to 2. Returns the bounding boxes of the objects, see
+    /// [`CascadeOptions::min_neighbors`].
+    fn detect_objects(&self, cascade: &Cascade, options: &CascadeOptions) -> Vec<Rect> {
+        let (width, height) = self.dimensions();
+        let window = cascade.window;
+        let scale_factor = options.scale_factor.max(1.01);
+        let mut detections = Vec::new();
+
+        let mut factor = 1.0f32;
+        loop {
+            let level = (
+                (width as f32 / factor).round() as usize,
+                (height as f32 / factor).round() as usize,
+            );
+            let object = (
+                (window.0 as f32 * factor).round() as usize,
+                (window.1 as f32 * factor).round() as usize,
+            );
+            let too_large = options
+                .max_size
+                .is_some_and(|max| object.0 > max.0 || object.1 > max.1);
+            if level.0 < window.0 || level.1 < window.1 || too_large {
+                break;
+            }
+            if object.0 >= options.min_size.0 && object.1 >= options.min_size.1 {
+                let scaled = match factor == 1.0 {
+                    true => self.clone(),
+                    false => affine::scale_unchecked(
+                        self,
+                        1.0 / factor,
+                        1.0 / factor,
+                        Interpolation::Bilinear,
+                    ),
+                };
+                let level = scaled.dimensions();
+                let sums = scaled.integral_image();
+                let squares: Vec<Luma> = scaled.pixels().map(|p| Luma { l: p.l * p.l }).collect();
+                let squares = Image::from_data(level.0, level.1, squares)
+                    .unwrap()
+                    .integral_image();
+                let step = if factor > 2.0 { 1 } else { 2 };
+                let positions: Vec<(usize, usize)> = (0..=level.1.saturating_sub(window.1))
+                    .step_by(step)
+                    .flat_map(|y| {
+                        (0..=level.0.saturating_sub(window.0))
+                            .step_by(step)
+                            .map(move |x| (x, y))
+                    })
+                    .collect();
+                let found: Vec<Rect> = positions
+                    .par_iter()
+                    .filter(|&&position| cascade.accepts(&sums, &squares, position))
+                    .map(|&(x, y)| {
+                        Rect::new(
+                            (x as f32 * factor).round() as usize,
+                            (y as f32 * factor).round() as usize,
+                            object.0,
+                            object.1,
+                        )
+                    })
+                    .collect();
+                detections.extend(found);
+            }
+            factor *= scale_factor;
+        }
+        group_rectangles(detections, options.min_neighbors)
+    }
+}
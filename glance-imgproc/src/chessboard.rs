@@ -0,0 +1,417 @@
+use std::collections::{HashMap, VecDeque};
+
+use glance_core::drawing::shapes::{Circle, Line};
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Rgba},
+};
+use glance_core::par::*;
+
+use crate::color::Hsv;
+use crate::geometry::{Point, distance};
+use crate::linear_filters::LinearFilterExtLuma;
+
+/// Smoothing applied before measuring the saddle response, in pixels.
+const SADDLE_SIGMA: f32 = 2.0;
+/// Candidates must reach this fraction of the strongest saddle response.
+const SADDLE_THRESHOLD: f32 = 0.1;
+/// Radius of the ring sampled around candidates to tell X-junctions from other corners.
+const RING_RADIUS: f32 = 5.0;
+/// Maximum distance between a predicted grid position and a candidate, relative to the grid
+/// step.
+const SNAP_TOLERANCE: f32 = 0.35;
+
+/// Returns the saddle response dxy² - dxx * dyy (minus the Hessian determinant) of every pixel,
+/// which peaks at the X-junctions between four chessboard squares.
+fn saddle_response(image: &Image<Luma>) -> (Vec<f32>, usize, usize) {
+    let (width, height) = image.dimensions();
+    let plane: Vec<f32> = image
+        .clone()
+        .gaussian_blur(SADDLE_SIGMA)
+        .pixels()
+        .map(|p| p.l)
+        .collect();
+    let at = |x: isize, y: isize| {
+        plane[y.clamp(0, height as isize - 1) as usize * width
+            + x.clamp(0, width as isize - 1) as usize]
+    };
+
+    let response = (0..width * height)
+        .into_par_iter()
+        .map(|idx| {
+            let (x, y) = ((idx % width) as isize, (idx / width) as isize);
+            let dxx = at(x + 1, y) - 2.0 * at(x, y) + at(x - 1, y);
+            let dyy = at(x, y + 1) - 2.0 * at(x, y) + at(x, y - 1);
+            let dxy =
+                (at(x + 1, y + 1) - at(x + 1, y - 1) - at(x - 1, y + 1) + at(x - 1, y - 1)) / 4.0;
+            dxy * dxy - dxx * dyy
+        })
+        .collect();
+    (response, width, height)
+}
+
+/// Returns the local maxima of the saddle response above the threshold as corner candidates.
+fn saddle_candidates(image: &Image<Luma>) -> Vec<Point> {
+    let (response, width, height) = saddle_response(image);
+    let max = response.iter().copied().fold(0.0, f32::max);
+    if max <= 0.0 {
+        return Vec::new();
+    }
+
+    let radius = 3isize;
+    (0..width * height)
+        .filter(|&idx| {
+            let value = response[idx];
+            if value < SADDLE_THRESHOLD * max {
+                return false;
+            }
+            let (x, y) = ((idx % width) as isize, (idx / width) as isize);
+            // Strict maximum against earlier pixels and non-strict against later ones, so
+            // plateaus yield a single candidate
+            (-radius..=radius).all(|dy| {
+                (-radius..=radius).all(|dx| {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
+                        return true;
+                    }
+                    let n = ny as usize * width + nx as usize;
+                    if n < idx {
+                        response[n] < value
+                    } else {
+                        response[n] <= value
+                    }
+                })
+            })
+        })
+        .map(|idx| ((idx % width) as f32, (idx / width) as f32))
+        .filter(|&p| is_x_junction(image, p))
+        .collect()
+}
+
+/// Checks that a ring around the point alternates between dark and bright exactly four times,
+/// which rejects the L-shaped corners along the outline of the board.
+fn is_x_junction(image: &Image<Luma>, center: Point) -> bool {
+    const SAMPLES: usize = 32;
+    let ring: Vec<f32> = (0..SAMPLES)
+        .map(|i| {
+            let angle = i as f32 * std::f32::consts::TAU / SAMPLES as f32;
+            let (sin, cos) = angle.sin_cos();
+            image
+                .sample_bilinear(center.0 + RING_RADIUS * cos, center.1 + RING_RADIUS * sin)
+                .l
+        })
+        .collect();
+    let (min, max) = ring
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let middle = (min + max) / 2.0;
+
+    let transitions = (0..SAMPLES)
+        .filter(|&i| (ring[i] > middle) != (ring[(i + 1) % SAMPLES] > middle))
+        .count();
+    transitions == 4
+}
+
+/// Grows a grid of corners from the candidate closest to the center of all candidates,
+/// predicting every neighbouring grid position from the corners found so far and snapping it
+/// to the nearest unused candidate. Returns the candidate index of every grid cell.
+fn grow_grid(candidates: &[Point]) -> HashMap<(i32, i32), usize> {
+    let mut grid = HashMap::new();
+    if candidates.len() < 3 {
+        return grid;
+    }
+
+    let n = candidates.len() as f32;
+    let centroid = candidates
+        .iter()
+        .fold((0.0, 0.0), |acc, p| (acc.0 + p.0 / n, acc.1 + p.1 / n));
+    let nearest_to = |target: Point, used: &[bool]| {
+        candidates
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !used[*i])
+            .min_by(|a, b| distance(*a.1, target).total_cmp(&distance(*b.1, target)))
+            .map(|(i, p)| (i, distance(*p, target)))
+    };
+
+    let mut used = vec![false; candidates.len()];
+    let (seed, _) = nearest_to(centroid, &used).unwrap();
+    used[seed] = true;
+
+    // The two grid axes from the nearest neighbours of the seed
+    let origin = candidates[seed];
+    let (first, _) = nearest_to(origin, &used).unwrap();
+    let u = (
+        candidates[first].0 - origin.0,
+        candidates[first].1 - origin.1,
+    );
+    let step = u.0.hypot(u.1);
+    let Some((second, _)) = candidates
+        .iter()
+        .enumerate()
+        .filter(|&(i, p)| {
+            let v = (p.0 - origin.0, p.1 - origin.1);
+            let length = v.0.hypot(v.1);
+            i != seed
+                && i != first
+                && length < 2.0 * step
+                && ((u.0 * v.0 + u.1 * v.1) / (step * length)).abs() < 0.5
+        })
+        .min_by(|a, b| distance(*a.1, origin).total_cmp(&distance(*b.1, origin)))
+    else {
+        return grid;
+    };
+    let v = (
+        candidates[second].0 - origin.0,
+        candidates[second].1 - origin.1,
+    );
+
+    grid.insert((0, 0), seed);
+    let mut queue = VecDeque::from([(0, 0)]);
+    while let Some((i, j)) = queue.pop_front() {
+        let p = candidates[grid[&(i, j)]];
+        for (di, dj) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let target = (i + di, j + dj);
+            if grid.contains_key(&target) {
+                continue;
+            }
+            // Continue the line through the opposite neighbour if there is one, which follows
+            // perspective and lens distortion, otherwise use the initial axes
+            let step_vector = match grid.get(&(i - di, j - dj)) {
+                Some(&opposite) => (p.0 - candidates[opposite].0, p.1 - candidates[opposite].1),
+                None => {
+                    let axis = if di != 0 { u } else { v };
+                    let sign = (di + dj) as f32;
+                    (axis.0 * sign, axis.1 * sign)
+                }
+            };
+            let predicted = (p.0 + step_vector.0, p.1 + step_vector.1);
+            let tolerance = SNAP_TOLERANCE * step_vector.0.hypot(step_vector.1);
+
+            if let Some((index, d)) = nearest_to(predicted, &used)
+                && d <= tolerance
+            {
+                used[index] = true;
+                grid.insert(target, index);
+                queue.push_back(target);
+            }
+        }
+    }
+
+    grid
+}
+
+/// Orders a grown grid of exactly `pattern_size` (columns, rows) corners row by row, starting
+/// at the top-left corner with rows running left to right. Returns None if the grid has a
+/// different shape or holes.
+fn order_grid(
+    grid: &HashMap<(i32, i32), usize>,
+    candidates: &[Point],
+    pattern_size: (usize, usize),
+) -> Option<Vec<Point>> {
+    let (cols, rows) = pattern_size;
+    let (min_i, max_i) = grid.keys().fold((i32::MAX, i32::MIN), |(lo, hi), k| {
+        (lo.min(k.0), hi.max(k.0))
+    });
+    let (min_j, max_j) = grid.keys().fold((i32::MAX, i32::MIN), |(lo, hi), k| {
+        (lo.min(k.1), hi.max(k.1))
+    });
+    let (extent_i, extent_j) = ((max_i - min_i + 1) as usize, (max_j - min_j + 1) as usize);
+    if grid.len() != cols * rows {
+        return None;
+    }
+
+    // Map (column, row) of the pattern to grid cells, transposing if needed
+    let transposed = if (extent_i, extent_j) == (cols, rows) {
+        false
+    } else if (extent_i, extent_j) == (rows, cols) {
+        true
+    } else {
+        return None;
+    };
+    let cell = |c: usize, r: usize, flip_c: bool, flip_r: bool| {
+        let c = if flip_c { cols - 1 - c } else { c } as i32;
+        let r = if flip_r { rows - 1 - r } else { r } as i32;
+        if transposed {
+            (min_i + r, min_j + c)
+        } else {
+            (min_i + c, min_j + r)
+        }
+    };
+    let point = |key: (i32, i32)| grid.get(&key).map(|&i| candidates[i]);
+
+    // Flip the axes so columns run to the right and rows run down on screen
+    let (first, last_in_row) = (
+        point(cell(0, 0, false, false))?,
+        point(cell(cols - 1, 0, false, false))?,
+    );
+    let last_in_column = point(cell(0, rows - 1, false, false))?;
+    let column_direction = (last_in_row.0 - first.0, last_in_row.1 - first.1);
+    let row_direction = (last_in_column.0 - first.0, last_in_column.1 - first.1);
+    let (flip_c, flip_r) =
+        if cols == 1 || rows == 1 || column_direction.0.abs() >= column_direction.1.abs() {
+            (column_direction.0 < 0.0, row_direction.1 < 0.0)
+        } else {
+            // The board is turned by about 90 degrees, keep the rows going down
+            (column_direction.1 > 0.0, row_direction.0 > 0.0)
+        };
+
+    let mut corners = Vec::with_capacity(cols * rows);
+    for r in 0..rows {
+        for c in 0..cols {
+            corners.push(point(cell(c, r, flip_c, flip_r))?);
+        }
+    }
+    Some(corners)
+}
+
+/// Extension trait for [`glance_core::img::Image`] to provide chessboard corner detection for
+/// Luma images, the first step of camera calibration
+pub trait ChessboardExtLuma {
+    fn find_chessboard_corners(&self, pattern_size: (usize, usize)) -> Option<Vec<Point>>;
+    fn refine_corners(&self, corners: &mut [Point], radius: usize);
+}
+
+impl ChessboardExtLuma for Image<Luma> {
+    /// Finds the inner corners of a chessboard with `pattern_size` (columns, rows) inner corners,
+    /// e.g. (8, 6) for a board of 9x7 squares. Corners are returned row by row starting at the
+    /// top-left, refined to subpixel accuracy. Returns None unless exactly the full pattern is
+    /// found.
+    fn find_chessboard_corners(&self, pattern_size: (usize, usize)) -> Option<Vec<Point>> {
+        if pattern_size.0 * pattern_size.1 == 0 {
+            return None;
+        }
+        let candidates = saddle_candidates(self);
+        let grid = grow_grid(&candidates);
+        let mut corners = order_grid(&grid, &candidates, pattern_size)?;
+
+        // Refine within a third of the smallest distance between neighbouring corners
+        let spacing = corners
+            .windows(2)
+            .map(|w| distance(w[0], w[1]))
+            .fold(f32::MAX, f32::min);
+        let radius = if spacing.is_finite() {
+            ((spacing / 3.0) as usize).clamp(2, 10)
+        } else {
+            5
+        };
+        self.refine_corners(&mut corners, radius);
+        Some(corners)
+    }
+
+    /// Refines corner positions to subpixel accuracy. Within the (2 * radius + 1)² window, the
+    /// image gradient at every pixel is orthogonal to the vector from the true corner to that
+    /// pixel; the corner is the least squares solution of these constraints, iterated until it
+    /// moves less than 0.01 pixels.
+    fn refine_corners(&self, corners: &mut [Point], radius: usize) {
+        let (width, height) = self.dimensions();
+        let plane: Vec<f32> = self
+            .clone()
+            .gaussian_blur(1.0)
+            .pixels()
+            .map(|p| p.l)
+            .collect();
+        let radius = radius as isize;
+        let sigma = radius as f32 / 2.0;
+
+        corners.par_iter_mut().for_each(|corner| {
+            for _ in 0..20 {
+                let (cx, cy) = (corner.0.round() as isize, corner.1.round() as isize);
+                let (mut a11, mut a12, mut a22, mut b1, mut b2) = (0.0f32, 0.0, 0.0, 0.0, 0.0);
+
+                for dy in -radius..=radius {
+                    for dx in -radius..=radius {
+                        let (x, y) = (cx + dx, cy + dy);
+                        if x < 1 || y < 1 || x >= width as isize - 1 || y >= height as isize - 1 {
+                            continue;
+                        }
+                        let at = |x: isize, y: isize| plane[y as usize * width + x as usize];
+                        let gx = (at(x + 1, y) - at(x - 1, y)) / 2.0;
+                        let gy = (at(x, y + 1) - at(x, y - 1)) / 2.0;
+                        let w = (-((dx * dx + dy * dy) as f32) / (2.0 * sigma * sigma)).exp();
+
+                        let (gxx, gxy, gyy) = (w * gx * gx, w * gx * gy, w * gy * gy);
+                        a11 += gxx;
+                        a12 += gxy;
+                        a22 += gyy;
+                        b1 += gxx * x as f32 + gxy * y as f32;
+                        b2 += gxy * x as f32 + gyy * y as f32;
+                    }
+                }
+
+                let det = a11 * a22 - a12 * a12;
+                if det.abs() < 1e-12 {
+                    break;
+                }
+                let refined = ((a22 * b1 - a12 * b2) / det, (a11 * b2 - a12 * b1) / det);
+                let shift = distance(refined, *corner);
+                // Reject runaway solutions on flat windows
+                if shift > radius as f32 {
+                    break;
+                }
+                *corner = refined;
+                if shift < 0.01 {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+/// Draws detected chessboard corners onto an image. If `found` is true the corners are drawn
+/// in a different color per row and connected in detection order, otherwise they are drawn as
+/// red circles.
+pub fn draw_chessboard_corners(
+    image: &mut Image<Rgba>,
+    pattern_size: (usize, usize),
+    corners: &[Point],
+    found: bool,
+) {
+    let (width, height) = image.dimensions();
+    let to_pixel = |p: Point| {
+        (
+            (p.0.round().max(0.0) as usize).min(width.saturating_sub(1)),
+            (p.1.round().max(0.0) as usize).min(height.saturating_sub(1)),
+        )
+    };
+    let red = Rgba {
+        r: 1.0,
+        g: 0.0,
+        b: 0.0,
+        a: 1.0,
+    };
+    let cols = pattern_size.0.max(1);
+
+    for (i, &corner) in corners.iter().enumerate() {
+        let color = if found {
+            Hsv {
+                h: (i / cols) as f32 * 360.0 / pattern_size.1.max(1) as f32,
+                s: 1.0,
+                v: 1.0,
+            }
+            .to_rgba(1.0)
+        } else {
+            red
+        };
+
+        if found && i > 0 {
+            image
+                .draw(Line {
+                    start: to_pixel(corners[i - 1]),
+                    end: to_pixel(corner),
+                    color,
+                    thickness: 1,
+                })
+                .unwrap();
+        }
+        image
+            .draw(Circle {
+                position: to_pixel(corner),
+                color,
+                radius: 4,
+                filled: false,
+                thickness: 1,
+            })
+            .unwrap();
+    }
+}
@@ -0,0 +1,446 @@
+//! EAN-13 and Code 128 barcodes, decoded from the runs of dark and light pixels along scan
+//! lines. Every symbol is matched against the known bar and space widths after normalizing its
+//! runs to its width in modules, so the bars may be any number of pixels wide.
+
+use std::collections::HashMap;
+
+use super::{Binary, DecodedCode, Symbology};
+use crate::geometry::Point;
+
+/// Lines scanned across the image in each direction, at most.
+const MAX_SCAN_LINES: usize = 128;
+/// Largest mean difference between a symbol's normalized runs and its pattern, in modules.
+const MAX_VARIANCE: f32 = 0.4;
+/// Light modules required before the first bar of a barcode.
+const QUIET_ZONE: f32 = 3.0;
+
+/// Widths of the space, bar, space and bar of the left hand odd parity (L) digits of EAN-13.
+/// The even parity (G) digits are the same runs reversed, and the right hand digits (R) the
+/// same runs starting with a bar.
+const EAN_DIGITS: [[u8; 4]; 10] = [
+    [3, 2, 1, 1],
+    [2, 2, 2, 1],
+    [2, 1, 2, 2],
+    [1, 4, 1, 1],
+    [1, 1, 3, 2],
+    [1, 2, 3, 1],
+    [1, 1, 1, 4],
+    [1, 3, 1, 2],
+    [1, 2, 1, 3],
+    [3, 1, 1, 2],
+];
+
+/// Parities of the six left hand digits encoding the first digit, a set bit meaning G.
+const EAN_FIRST_DIGIT: [u8; 10] = [
+    0b000000, 0b001011, 0b001101, 0b001110, 0b010011, 0b011001, 0b011100, 0b010101, 0b010110,
+    0b011010,
+];
+
+/// Bar and space widths of the Code 128 symbols, by value. 103 to 105 are the start codes.
+const CODE128_SYMBOLS: [[u8; 6]; 106] = [
+    [2, 1, 2, 2, 2, 2],
+    [2, 2, 2, 1, 2, 2],
+    [2, 2, 2, 2, 2, 1],
+    [1, 2, 1, 2, 2, 3],
+    [1, 2, 1, 3, 2, 2],
+    [1, 3, 1, 2, 2, 2],
+    [1, 2, 2, 2, 1, 3],
+    [1, 2, 2, 3, 1, 2],
+    [1, 3, 2, 2, 1, 2],
+    [2, 2, 1, 2, 1, 3],
+    [2, 2, 1, 3, 1, 2],
+    [2, 3, 1, 2, 1, 2],
+    [1, 1, 2, 2, 3, 2],
+    [1, 2, 2, 1, 3, 2],
+    [1, 2, 2, 2, 3, 1],
+    [1, 1, 3, 2, 2, 2],
+    [1, 2, 3, 1, 2, 2],
+    [1, 2, 3, 2, 2, 1],
+    [2, 2, 3, 2, 1, 1],
+    [2, 2, 1, 1, 3, 2],
+    [2, 2, 1, 2, 3, 1],
+    [2, 1, 3, 2, 1, 2],
+    [2, 2, 3, 1, 1, 2],
+    [3, 1, 2, 1, 3, 1],
+    [3, 1, 1, 2, 2, 2],
+    [3, 2, 1, 1, 2, 2],
+    [3, 2, 1, 2, 2, 1],
+    [3, 1, 2, 2, 1, 2],
+    [3, 2, 2, 1, 1, 2],
+    [3, 2, 2, 2, 1, 1],
+    [2, 1, 2, 1, 2, 3],
+    [2, 1, 2, 3, 2, 1],
+    [2, 3, 2, 1, 2, 1],
+    [1, 1, 1, 3, 2, 3],
+    [1, 3, 1, 1, 2, 3],
+    [1, 3, 1, 3, 2, 1],
+    [1, 1, 2, 3, 1, 3],
+    [1, 3, 2, 1, 1, 3],
+    [1, 3, 2, 3, 1, 1],
+    [2, 1, 1, 3, 1, 3],
+    [2, 3, 1, 1, 1, 3],
+    [2, 3, 1, 3, 1, 1],
+    [1, 1, 2, 1, 3, 3],
+    [1, 1, 2, 3, 3, 1],
+    [1, 3, 2, 1, 3, 1],
+    [1, 1, 3, 1, 2, 3],
+    [1, 1, 3, 3, 2, 1],
+    [1, 3, 3, 1, 2, 1],
+    [3, 1, 3, 1, 2, 1],
+    [2, 1, 1, 3, 3, 1],
+    [2, 3, 1, 1, 3, 1],
+    [2, 1, 3, 1, 1, 3],
+    [2, 1, 3, 3, 1, 1],
+    [2, 1, 3, 1, 3, 1],
+    [3, 1, 1, 1, 2, 3],
+    [3, 1, 1, 3, 2, 1],
+    [3, 3, 1, 1, 2, 1],
+    [3, 1, 2, 1, 1, 3],
+    [3, 1, 2, 3, 1, 1],
+    [3, 3, 2, 1, 1, 1],
+    [3, 1, 4, 1, 1, 1],
+    [2, 2, 1, 4, 1, 1],
+    [4, 3, 1, 1, 1, 1],
+    [1, 1, 1, 2, 2, 4],
+    [1, 1, 1, 4, 2, 2],
+    [1, 2, 1, 1, 2, 4],
+    [1, 2, 1, 4, 2, 1],
+    [1, 4, 1, 1, 2, 2],
+    [1, 4, 1, 2, 2, 1],
+    [1, 1, 2, 2, 1, 4],
+    [1, 1, 2, 4, 1, 2],
+    [1, 2, 2, 1, 1, 4],
+    [1, 2, 2, 4, 1, 1],
+    [1, 4, 2, 1, 1, 2],
+    [1, 4, 2, 2, 1, 1],
+    [2, 4, 1, 2, 1, 1],
+    [2, 2, 1, 1, 1, 4],
+    [4, 1, 3, 1, 1, 1],
+    [2, 4, 1, 1, 1, 2],
+    [1, 3, 4, 1, 1, 1],
+    [1, 1, 1, 2, 4, 2],
+    [1, 2, 1, 1, 4, 2],
+    [1, 2, 1, 2, 4, 1],
+    [1, 1, 4, 2, 1, 2],
+    [1, 2, 4, 1, 1, 2],
+    [1, 2, 4, 2, 1, 1],
+    [4, 1, 1, 2, 1, 2],
+    [4, 2, 1, 1, 1, 2],
+    [4, 2, 1, 2, 1, 1],
+    [2, 1, 2, 1, 4, 1],
+    [2, 1, 4, 1, 2, 1],
+    [4, 1, 2, 1, 2, 1],
+    [1, 1, 1, 1, 4, 3],
+    [1, 1, 1, 3, 4, 1],
+    [1, 3, 1, 1, 4, 1],
+    [1, 1, 4, 1, 1, 3],
+    [1, 1, 4, 3, 1, 1],
+    [4, 1, 1, 1, 1, 3],
+    [4, 1, 1, 3, 1, 1],
+    [1, 1, 3, 1, 4, 1],
+    [1, 1, 4, 1, 3, 1],
+    [3, 1, 1, 1, 4, 1],
+    [4, 1, 1, 1, 3, 1],
+    [2, 1, 1, 4, 1, 2],
+    [2, 1, 1, 2, 1, 4],
+    [2, 1, 1, 2, 3, 2],
+];
+
+/// Bar and space widths of the Code 128 stop pattern, which ends with a final bar.
+const CODE128_STOP: [u8; 7] = [2, 3, 3, 1, 1, 1, 2];
+
+/// A run of pixels of one color along a scan line.
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    dark: bool,
+    /// Position of the first pixel along the line
+    start: usize,
+    length: usize,
+}
+
+/// A barcode read along one scan line, between two positions along it.
+struct Reading {
+    symbology: Symbology,
+    payload: String,
+    start: usize,
+    end: usize,
+    /// Whether the barcode was read against the direction of the line
+    flipped: bool,
+}
+
+/// Returns the mean difference between `runs`, scaled to `modules` in total, and `pattern`.
+fn variance(runs: &[Run], pattern: &[u8], modules: f32) -> f32 {
+    let total: usize = runs.iter().map(|r| r.length).sum();
+    let scale = modules / total.max(1) as f32;
+    let difference: f32 = runs
+        .iter()
+        .zip(pattern)
+        .map(|(r, &p)| (r.length as f32 * scale - p as f32).abs())
+        .sum();
+    difference / pattern.len() as f32
+}
+
+/// Returns the index of the pattern closest to `runs`, if it's close enough.
+fn best_match<const N: usize>(runs: &[Run], patterns: &[[u8; N]], modules: f32) -> Option<usize> {
+    patterns
+        .iter()
+        .map(|pattern| variance(runs, pattern, modules))
+        .enumerate()
+        .filter(|&(_, v)| v < MAX_VARIANCE)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(i, _)| i)
+}
+
+/// Returns whether the light run before `runs[index]` is a quiet zone for bars `module` wide.
+fn quiet_before(runs: &[Run], index: usize, module: f32) -> bool {
+    index == 0 || runs[index - 1].length as f32 >= QUIET_ZONE * module
+}
+
+/// Reads an EAN-13 barcode whose start guard is `runs[index]`.
+fn read_ean13(line: &[Run], index: usize) -> Option<Reading> {
+    let runs = line.get(index..index + 59)?;
+    let total: usize = runs.iter().map(|r| r.length).sum();
+    if !runs[0].dark || !quiet_before(line, index, total as f32 / 95.0) {
+        return None;
+    }
+    let guard_ok =
+        |guard: &[Run]| variance(guard, &[1; 5][..guard.len()], guard.len() as f32) < MAX_VARIANCE;
+    if !guard_ok(&runs[..3]) || !guard_ok(&runs[27..32]) || !guard_ok(&runs[56..59]) {
+        return None;
+    }
+
+    let reversed: Vec<[u8; 4]> = EAN_DIGITS
+        .iter()
+        .map(|d| [d[3], d[2], d[1], d[0]])
+        .collect();
+    let mut digits = Vec::with_capacity(13);
+    let mut parities = 0u8;
+    for i in 0..6 {
+        let symbol = &runs[3 + 4 * i..7 + 4 * i];
+        let odd = best_match(symbol, &EAN_DIGITS, 7.0);
+        let even = best_match(symbol, &reversed, 7.0);
+        let (digit, parity) = match (odd, even) {
+            (Some(digit), None) => (digit, 0),
+            (None, Some(digit)) => (digit, 1),
+            (Some(o), Some(e)) => {
+                match variance(symbol, &EAN_DIGITS[o], 7.0) <= variance(symbol, &reversed[e], 7.0) {
+                    true => (o, 0),
+                    false => (e, 1),
+                }
+            }
+            (None, None) => return None,
+        };
+        digits.push(digit as u8);
+        parities = parities << 1 | parity;
+    }
+    let first = EAN_FIRST_DIGIT.iter().position(|&p| p == parities)?;
+    digits.insert(0, first as u8);
+    for i in 0..6 {
+        let symbol = &runs[32 + 4 * i..36 + 4 * i];
+        digits.push(best_match(symbol, &EAN_DIGITS, 7.0)? as u8);
+    }
+
+    let sum: u32 = digits[..12]
+        .iter()
+        .enumerate()
+        .map(|(i, &d)| d as u32 * if i % 2 == 0 { 1 } else { 3 })
+        .sum();
+    if (10 - sum % 10) % 10 != digits[12] as u32 {
+        return None;
+    }
+    Some(Reading {
+        symbology: Symbology::Ean13,
+        payload: digits.iter().map(|d| (b'0' + d) as char).collect(),
+        start: runs[0].start,
+        end: runs[58].start + runs[58].length,
+        flipped: false,
+    })
+}
+
+/// Code sets of Code 128, switched by code and shift symbols.
+#[derive(Clone, Copy, PartialEq)]
+enum CodeSet {
+    A,
+    B,
+    C,
+}
+
+/// Reads a Code 128 barcode whose start code is `runs[index..index + 6]`.
+fn read_code128(runs: &[Run], index: usize) -> Option<Reading> {
+    let start = runs.get(index..index + 6)?;
+    let total: usize = start.iter().map(|r| r.length).sum();
+    if !start[0].dark || !quiet_before(runs, index, total as f32 / 11.0) {
+        return None;
+    }
+    let start_value = best_match(start, &CODE128_SYMBOLS, 11.0).filter(|v| *v >= 103)?;
+
+    let mut values = Vec::new();
+    let mut position = index + 6;
+    let end = loop {
+        let symbol = runs.get(position..position + 6)?;
+        if let Some(stop) = runs.get(position..position + 7)
+            && variance(stop, &CODE128_STOP, 13.0) < MAX_VARIANCE
+        {
+            break stop[6].start + stop[6].length;
+        }
+        values.push(best_match(symbol, &CODE128_SYMBOLS, 11.0).filter(|v| *v < 103)?);
+        position += 6;
+    };
+
+    let (&check, values) = values.split_last()?;
+    let sum = values
+        .iter()
+        .enumerate()
+        .fold(start_value, |sum, (i, &v)| sum + (i + 1) * v);
+    if sum % 103 != check {
+        return None;
+    }
+
+    let mut set = match start_value {
+        103 => CodeSet::A,
+        104 => CodeSet::B,
+        _ => CodeSet::C,
+    };
+    let mut shifted = None;
+    let mut payload = String::new();
+    for &value in values {
+        let current = shifted.take().unwrap_or(set);
+        match (current, value) {
+            (CodeSet::C, 0..=99) => payload.push_str(&format!("{value:02}")),
+            (CodeSet::A, 0..=63) | (CodeSet::B, 0..=95) => payload.push((value as u8 + 32) as char),
+            (CodeSet::A, 64..=95) => payload.push((value as u8 - 64) as char),
+            (CodeSet::A, 98) => shifted = Some(CodeSet::B),
+            (CodeSet::B, 98) => shifted = Some(CodeSet::A),
+            (CodeSet::A | CodeSet::B, 99) => set = CodeSet::C,
+            (CodeSet::A, 100) | (CodeSet::C, 100) => set = CodeSet::B,
+            (CodeSet::B, 101) | (CodeSet::C, 101) => set = CodeSet::A,
+            // FNC1 to FNC4 carry no characters
+            _ => {}
+        }
+    }
+    Some(Reading {
+        symbology: Symbology::Code128,
+        payload,
+        start: runs[index].start,
+        end,
+        flipped: false,
+    })
+}
+
+/// Returns the runs of `pixels`, starting at position 0.
+fn runs(pixels: impl Iterator<Item = bool>) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    for (i, dark) in pixels.enumerate() {
+        match runs.last_mut() {
+            Some(run) if run.dark == dark => run.length += 1,
+            _ => runs.push(Run {
+                dark,
+                start: i,
+                length: 1,
+            }),
+        }
+    }
+    runs
+}
+
+/// Returns the barcodes read along a line of `length` pixels, in both directions.
+fn read_line(pixels: &[bool]) -> Vec<Reading> {
+    let length = pixels.len();
+    let mut readings = Vec::new();
+    for reversed in [false, true] {
+        let line = runs((0..length).map(|i| pixels[if reversed { length - 1 - i } else { i }]));
+        let mut index = 0;
+        while index < line.len() {
+            let reading = read_ean13(&line, index).or_else(|| read_code128(&line, index));
+            match reading {
+                Some(mut reading) => {
+                    index = line.partition_point(|r| r.start < reading.end);
+                    if reversed {
+                        (reading.start, reading.end) =
+                            (length - reading.end, length - reading.start);
+                        reading.flipped = true;
+                    }
+                    readings.push(reading);
+                }
+                None => index += 1,
+            }
+        }
+    }
+    readings
+}
+
+/// Where a barcode was read: the first and last scan lines and the span along them.
+struct Extent {
+    lines: usize,
+    first: usize,
+    last: usize,
+    start: usize,
+    end: usize,
+}
+
+/// Finds the barcodes crossing the rows or columns of `binary`.
+pub(super) fn detect(binary: &Binary) -> Vec<DecodedCode> {
+    let (width, height) = (binary.width, binary.height);
+    let mut found: HashMap<(Symbology, String, bool, bool), Extent> = HashMap::new();
+    for vertical in [false, true] {
+        let (lines, length) = if vertical {
+            (width, height)
+        } else {
+            (height, width)
+        };
+        let step = lines.div_ceil(MAX_SCAN_LINES).max(1);
+        for line in (0..lines).step_by(step) {
+            let pixels: Vec<bool> = (0..length)
+                .map(|i| match vertical {
+                    true => binary.dark[i * width + line],
+                    false => binary.dark[line * width + i],
+                })
+                .collect();
+            for reading in read_line(&pixels) {
+                let key = (
+                    reading.symbology,
+                    reading.payload,
+                    vertical,
+                    reading.flipped,
+                );
+                let extent = found.entry(key).or_insert(Extent {
+                    lines: 0,
+                    first: line,
+                    last: line,
+                    start: reading.start,
+                    end: reading.end,
+                });
+                extent.lines += 1;
+                extent.last = line;
+                extent.start = extent.start.min(reading.start);
+                extent.end = extent.end.max(reading.end);
+            }
+        }
+    }
+
+    let mut codes: Vec<DecodedCode> = found
+        .into_iter()
+        .filter(|(_, extent)| extent.lines >= 2)
+        .map(|((symbology, payload, vertical, flipped), extent)| {
+            let (first, last) = (extent.first as f32, extent.last as f32 + 1.0);
+            let (start, end) = (extent.start as f32, extent.end as f32);
+            // Rows run along the top edge of upright barcodes, columns along their left edge
+            let corners: [Point; 4] = match (vertical, flipped) {
+                (false, false) => [(start, first), (end, first), (end, last), (start, last)],
+                (false, true) => [(end, last), (start, last), (start, first), (end, first)],
+                (true, false) => [(last, start), (last, end), (first, end), (first, start)],
+                (true, true) => [(first, end), (first, start), (last, start), (last, end)],
+            };
+            DecodedCode {
+                symbology,
+                payload,
+                corners,
+            }
+        })
+        .collect();
+    codes.sort_by(|a, b| {
+        let (a, b) = (a.corners[0], b.corners[0]);
+        a.1.total_cmp(&b.1).then(a.0.total_cmp(&b.0))
+    });
+    codes
+}
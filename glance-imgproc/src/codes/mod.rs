@@ -0,0 +1,110 @@
+//! Detection and decoding of QR codes and 1D barcodes (EAN-13, which includes UPC-A, and
+//! Code 128) in grayscale images. Images are binarized against their local mean, QR codes are
+//! located by their three finder patterns and rectified with a homography before sampling, and
+//! barcodes are read from the runs of scan lines across the image.
+
+mod barcode;
+mod qr;
+mod reed_solomon;
+
+use glance_core::img::{Image, pixel::Luma};
+use glance_core::par::*;
+
+use crate::geometry::Point;
+use crate::integral::IntegralImageExtLuma;
+
+/// Dark pixels must be this much darker than their neighborhood mean.
+const BINARIZE_OFFSET: f32 = 0.02;
+
+/// Kind of a detected code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symbology {
+    QrCode,
+    /// EAN-13, and UPC-A as EAN-13 with a leading 0
+    Ean13,
+    Code128,
+}
+
+/// A decoded code and where it was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedCode {
+    pub symbology: Symbology,
+    pub payload: String,
+    /// Corners of the code in image coordinates, in the order top-left, top-right,
+    /// bottom-right, bottom-left of the upright code
+    pub corners: [Point; 4],
+}
+
+/// Dark and light pixels of a binarized image.
+struct Binary {
+    width: usize,
+    height: usize,
+    dark: Vec<bool>,
+}
+
+impl Binary {
+    /// Marks the pixels darker than the mean of a window an eighth of the image across, which
+    /// copes with uneven lighting better than a global threshold.
+    fn new(image: &Image<Luma>) -> Self {
+        let (width, height) = image.dimensions();
+        let radius = (width.max(height) / 8).max(8);
+        let mean = image.clone().box_blur_integral(radius);
+        let dark = image
+            .as_slice()
+            .par_iter()
+            .zip(mean.as_slice().par_iter())
+            .map(|(p, m)| p.l < m.l - BINARIZE_OFFSET)
+            .collect();
+        Self {
+            width,
+            height,
+            dark,
+        }
+    }
+
+    /// Returns whether the pixel at (x, y) is dark; pixels outside the image are light.
+    fn is_dark(&self, x: isize, y: isize) -> bool {
+        x >= 0
+            && y >= 0
+            && (x as usize) < self.width
+            && (y as usize) < self.height
+            && self.dark[y as usize * self.width + x as usize]
+    }
+
+    /// Returns whether the pixel nearest to `point` is dark.
+    fn is_dark_at(&self, point: Point) -> bool {
+        self.is_dark(point.0.floor() as isize, point.1.floor() as isize)
+    }
+}
+
+/// Extension trait for [`glance_core::img::Image`] to find and decode QR codes and barcodes in
+/// Luma images
+pub trait CodeDetectionExtLuma {
+    fn detect_codes(&self) -> Vec<DecodedCode>;
+    fn detect_qr_codes(&self) -> Vec<DecodedCode>;
+    fn detect_barcodes(&self) -> Vec<DecodedCode>;
+}
+
+impl CodeDetectionExtLuma for Image<Luma> {
+    /// Finds and decodes all QR codes and barcodes in the image, QR codes first.
+    fn detect_codes(&self) -> Vec<DecodedCode> {
+        let binary = Binary::new(self);
+        let mut codes = qr::detect(&binary);
+        codes.extend(barcode::detect(&binary));
+        codes
+    }
+
+    /// Finds and decodes the QR codes in the image. Codes need a quiet zone of light modules
+    /// around them and at least one pixel per module; they may be rotated or seen in
+    /// perspective. Kanji segments are not supported.
+    fn detect_qr_codes(&self) -> Vec<DecodedCode> {
+        qr::detect(&Binary::new(self))
+    }
+
+    /// Finds and decodes the EAN-13 and Code 128 barcodes in the image, read along the rows
+    /// and columns in both directions. A barcode must be crossed by at least two scan lines
+    /// decoding to the same payload, and its checksum must match.
+    fn detect_barcodes(&self) -> Vec<DecodedCode> {
+        barcode::detect(&Binary::new(self))
+    }
+}
@@ -0,0 +1,743 @@
+//! QR codes: finder patterns are found by scanning for their 1:1:3:1:1 runs and checking them
+//! across, triples of finders are sampled through the homography fitted to them and the bottom
+//! right alignment pattern, and the module grid is unmasked, error corrected and parsed.
+
+use super::reed_solomon;
+use super::{Binary, DecodedCode, Symbology};
+use crate::estimation::estimate_homography;
+use crate::geometry::{Homography, Point, distance, transform_homography};
+
+/// Most finder candidates combined into triples, the most often seen first.
+const MAX_FINDERS: usize = 24;
+/// Tolerance of the finder run widths, in modules.
+const FINDER_TOLERANCE: f32 = 0.5;
+/// Most differing bits when matching the format and version information.
+const MAX_INFO_ERRORS: u32 = 3;
+/// Mask applied to the format information.
+const FORMAT_MASK: u32 = 0x5412;
+
+/// Error correction codewords per block, by level (L, M, Q, H) and version.
+const ECC_PER_BLOCK: [[u8; 41]; 4] = [
+    [
+        0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28, 28,
+        30, 30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28,
+        28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28,
+    ],
+    [
+        0, 13, 22, 18, 26, 18, 24, 18, 22, 20, 24, 28, 26, 24, 20, 30, 24, 28, 28, 26, 30, 28, 30,
+        30, 30, 30, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+    [
+        0, 17, 28, 22, 16, 22, 28, 26, 26, 24, 28, 24, 28, 22, 24, 24, 30, 28, 28, 26, 28, 30, 24,
+        30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30,
+    ],
+];
+
+/// Error correction blocks, by level (L, M, Q, H) and version.
+const ECC_BLOCKS: [[u8; 41]; 4] = [
+    [
+        0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12, 13,
+        14, 15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25,
+    ],
+    [
+        0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21,
+        23, 25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49,
+    ],
+    [
+        0, 1, 1, 2, 2, 4, 4, 6, 6, 8, 8, 8, 10, 12, 16, 12, 17, 16, 18, 21, 20, 23, 23, 25, 27, 29,
+        34, 34, 35, 38, 40, 43, 45, 48, 51, 53, 56, 59, 62, 65, 68,
+    ],
+    [
+        0, 1, 1, 2, 4, 4, 4, 5, 6, 8, 8, 11, 11, 16, 16, 18, 16, 19, 21, 25, 25, 25, 34, 30, 32,
+        35, 37, 40, 42, 45, 48, 51, 54, 57, 60, 63, 66, 70, 74, 77, 81,
+    ],
+];
+
+const ALPHANUMERIC: &[u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// A finder pattern candidate: its center, module size and how many scans confirmed it.
+#[derive(Debug, Clone, Copy)]
+struct Finder {
+    center: Point,
+    module: f32,
+    hits: usize,
+}
+
+/// Returns whether the five runs have the 1:1:3:1:1 proportions of a finder pattern.
+fn finder_ratio(counts: &[usize; 5]) -> bool {
+    let total: usize = counts.iter().sum();
+    if total < 7 || counts.contains(&0) {
+        return false;
+    }
+    let module = total as f32 / 7.0;
+    let tolerance = module * FINDER_TOLERANCE;
+    counts
+        .iter()
+        .zip([1.0, 1.0, 3.0, 1.0, 1.0])
+        .all(|(&count, expected)| (count as f32 - module * expected).abs() < tolerance * expected)
+}
+
+/// Counts the five runs of a finder pattern through `center` along `step`, and returns the
+/// center of the middle run along the line and the total length, if the runs match.
+fn cross_check(
+    binary: &Binary,
+    center: Point,
+    step: (isize, isize),
+    max: usize,
+) -> Option<(f32, usize)> {
+    let (x, y) = (center.0 as isize, center.1 as isize);
+    let dark = |i: isize| binary.is_dark(x + i * step.0, y + i * step.1);
+    if !dark(0) {
+        return None;
+    }
+    let mut counts = [0usize; 5];
+    // Walk backwards through the middle, light and outer runs
+    let mut i = 0;
+    for (run, color) in [(2, true), (1, false), (0, true)] {
+        while dark(i) == color && counts[run] <= max {
+            counts[run] += 1;
+            i -= 1;
+        }
+    }
+    let first = i + 1;
+    let mut i = 1;
+    for (run, color) in [(2, true), (3, false), (4, true)] {
+        while dark(i) == color && counts[run] <= max {
+            counts[run] += 1;
+            i += 1;
+        }
+    }
+    if !finder_ratio(&counts) {
+        return None;
+    }
+    let middle = first as f32 + (counts[0] + counts[1]) as f32 + counts[2] as f32 / 2.0;
+    Some((middle, counts.iter().sum()))
+}
+
+/// Confirms a finder seen along a row around `center` by crossing it vertically and again
+/// horizontally, and returns its refined center and module size.
+fn confirm_finder(binary: &Binary, center: Point, total: usize) -> Option<(Point, f32)> {
+    let (dy, vertical) = cross_check(binary, center, (0, 1), 2 * total)?;
+    let center = (center.0, center.1.floor() + dy);
+    let (dx, horizontal) = cross_check(binary, center, (1, 0), 2 * total)?;
+    let center = (center.0.floor() + dx, center.1);
+    // Finders are square: the runs across must about match those along
+    let lengths = [total, vertical, horizontal].map(|l| l as f32);
+    if max_over_min(&lengths) > 1.5 {
+        return None;
+    }
+    Some((center, lengths.iter().sum::<f32>() / 21.0))
+}
+
+/// Returns the ratio of the largest to the smallest of `values`.
+fn max_over_min(values: &[f32]) -> f32 {
+    let min = values.iter().copied().fold(f32::MAX, f32::min);
+    let max = values.iter().copied().fold(0.0, f32::max);
+    max / min
+}
+
+/// Adds a confirmed finder to `finders`, merging it with a known one at the same place.
+fn add_finder(finders: &mut Vec<Finder>, center: Point, module: f32) {
+    let known = finders.iter_mut().find(|f| {
+        distance(f.center, center) <= f.module.max(module)
+            && (f.module - module).abs() <= f.module.max(1.0)
+    });
+    match known {
+        Some(f) => {
+            let weight = f.hits as f32;
+            f.center = (
+                (f.center.0 * weight + center.0) / (weight + 1.0),
+                (f.center.1 * weight + center.1) / (weight + 1.0),
+            );
+            f.module = (f.module * weight + module) / (weight + 1.0);
+            f.hits += 1;
+        }
+        None => finders.push(Finder {
+            center,
+            module,
+            hits: 1,
+        }),
+    }
+}
+
+/// Scans every row for finder patterns.
+fn find_finders(binary: &Binary) -> Vec<Finder> {
+    let mut finders = Vec::new();
+    for y in 0..binary.height {
+        let mut counts = [0usize; 5];
+        let mut state = 0;
+        for x in 0..=binary.width {
+            let dark = binary.is_dark(x as isize, y as isize);
+            if dark == (state % 2 == 0) {
+                counts[state] += 1;
+                continue;
+            }
+            if state < 4 {
+                state += 1;
+                counts[state] += 1;
+                continue;
+            }
+            // Light after the fifth run ends a candidate
+            if finder_ratio(&counts) {
+                let total = counts.iter().sum();
+                let center = (
+                    (x - counts[4] - counts[3]) as f32 - counts[2] as f32 / 2.0,
+                    y as f32 + 0.5,
+                );
+                if let Some((center, module)) = confirm_finder(binary, center, total) {
+                    add_finder(&mut finders, center, module);
+                }
+            }
+            counts = [counts[2], counts[3], counts[4], 1, 0];
+            state = 3;
+        }
+    }
+    finders
+}
+
+/// Orders three finders as top-left, top-right and bottom-left if they can be the finders of
+/// one code: the top-left one is at the right angle of an about isosceles right triangle.
+/// Returns them with a score that is lower for better triangles.
+fn order_finders(finders: [Finder; 3]) -> Option<([Finder; 3], f32)> {
+    let modules = finders.map(|f| f.module);
+    if max_over_min(&modules) > 1.5 {
+        return None;
+    }
+    let module = modules.iter().sum::<f32>() / 3.0;
+    // The corner is opposite the longest side
+    let sides =
+        [0, 1, 2].map(|i| distance(finders[(i + 1) % 3].center, finders[(i + 2) % 3].center));
+    let corner = (0..3).max_by(|&a, &b| sides[a].total_cmp(&sides[b]))?;
+    let [mut a, mut b] = [finders[(corner + 1) % 3], finders[(corner + 2) % 3]];
+    let top_left = finders[corner];
+    let (u, v) = (
+        (
+            a.center.0 - top_left.center.0,
+            a.center.1 - top_left.center.1,
+        ),
+        (
+            b.center.0 - top_left.center.0,
+            b.center.1 - top_left.center.1,
+        ),
+    );
+    // Top-right then bottom-left turns clockwise on screen, where y points down
+    if u.0 * v.1 - u.1 * v.0 < 0.0 {
+        std::mem::swap(&mut a, &mut b);
+    }
+    let (leg_a, leg_b, hypotenuse) = (
+        sides[(corner + 2) % 3],
+        sides[(corner + 1) % 3],
+        sides[corner],
+    );
+    let legs = leg_a.min(leg_b) / leg_a.max(leg_b);
+    let right_angle =
+        (hypotenuse.powi(2) - leg_a.powi(2) - leg_b.powi(2)).abs() / hypotenuse.powi(2);
+    // Version 1 codes have finders 14 modules apart
+    if legs < 0.7 || right_angle > 0.3 || leg_a.min(leg_b) < 10.0 * module {
+        return None;
+    }
+    Some(([top_left, a, b], (1.0 - legs) + right_angle))
+}
+
+/// Sample grid of a code candidate: the module to image homography and the side in modules.
+struct Grid {
+    homography: Homography,
+    size: usize,
+}
+
+impl Grid {
+    fn module(&self, binary: &Binary, row: usize, column: usize) -> bool {
+        let center = (column as f32 + 0.5, row as f32 + 0.5);
+        binary.is_dark_at(transform_homography(&self.homography, center))
+    }
+
+    fn version(&self) -> usize {
+        (self.size - 17) / 4
+    }
+}
+
+/// Looks for the alignment pattern around `predicted`, a dark module inside a light and a dark
+/// ring along the module axes `u` and `v`. Returns the center of the matches nearest to
+/// `predicted`.
+fn find_alignment(
+    binary: &Binary,
+    predicted: Point,
+    u: Point,
+    v: Point,
+    module: f32,
+) -> Option<Point> {
+    let radius = (4.0 * module).ceil() as isize;
+    let at = |p: Point, a: f32, b: f32| {
+        binary.is_dark_at((p.0 + a * u.0 + b * v.0, p.1 + a * u.1 + b * v.1))
+    };
+    let mut matches = Vec::new();
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let p = (
+                predicted.0.floor() + dx as f32 + 0.5,
+                predicted.1.floor() + dy as f32 + 0.5,
+            );
+            let ring = |r: f32| {
+                [
+                    (-r, -r),
+                    (0.0, -r),
+                    (r, -r),
+                    (-r, 0.0),
+                    (r, 0.0),
+                    (-r, r),
+                    (0.0, r),
+                    (r, r),
+                ]
+            };
+            if at(p, 0.0, 0.0)
+                && ring(1.0).iter().all(|&(a, b)| !at(p, a, b))
+                && ring(2.0).iter().all(|&(a, b)| at(p, a, b))
+            {
+                matches.push(p);
+            }
+        }
+    }
+    let nearest = *matches
+        .iter()
+        .min_by(|a, b| distance(**a, predicted).total_cmp(&distance(**b, predicted)))?;
+    let blob: Vec<Point> = matches
+        .into_iter()
+        .filter(|&p| distance(p, nearest) <= module)
+        .collect();
+    let n = blob.len() as f32;
+    Some((
+        blob.iter().map(|p| p.0).sum::<f32>() / n,
+        blob.iter().map(|p| p.1).sum::<f32>() / n,
+    ))
+}
+
+/// Fits the sample grid of a code of `size` modules to its finders.
+fn fit_grid(
+    binary: &Binary,
+    [top_left, top_right, bottom_left]: &[Finder; 3],
+    size: usize,
+) -> Option<Grid> {
+    let (tl, tr, bl) = (top_left.center, top_right.center, bottom_left.center);
+    let span = size as f32 - 7.0;
+    let u = ((tr.0 - tl.0) / span, (tr.1 - tl.1) / span);
+    let v = ((bl.0 - tl.0) / span, (bl.1 - tl.1) / span);
+    let far = size as f32 - 3.5;
+    let mut correspondences = vec![((3.5, 3.5), tl), ((far, 3.5), tr), ((3.5, far), bl)];
+
+    // Codes past version 1 have an alignment pattern 3 modules in from the bottom right finder
+    // position, which catches the perspective
+    let module = (top_left.module + top_right.module + bottom_left.module) / 3.0;
+    let inner = far - 3.0;
+    let predicted = (
+        tl.0 + (inner - 3.5) * (u.0 + v.0),
+        tl.1 + (inner - 3.5) * (u.1 + v.1),
+    );
+    match (size > 21)
+        .then(|| find_alignment(binary, predicted, u, v, module))
+        .flatten()
+    {
+        Some(alignment) => correspondences.push(((inner, inner), alignment)),
+        None => correspondences.push(((far, far), (tr.0 + bl.0 - tl.0, tr.1 + bl.1 - tl.1))),
+    }
+    Some(Grid {
+        homography: estimate_homography(&correspondences)?,
+        size,
+    })
+}
+
+/// Returns the BCH code of `data` for the generator polynomial `generator` of degree `degree`.
+fn bch(data: u32, generator: u32, degree: u32) -> u32 {
+    let mut remainder = data << degree;
+    while remainder != 0 && remainder.ilog2() >= degree {
+        remainder ^= generator << (remainder.ilog2() - degree);
+    }
+    data << degree | remainder
+}
+
+/// Returns the candidate whose code differs from `read` in the fewest bits, at most
+/// [`MAX_INFO_ERRORS`].
+fn closest(reads: &[u32], candidates: impl Iterator<Item = (u32, u32)>) -> Option<u32> {
+    candidates
+        .map(|(value, code)| {
+            let errors = reads
+                .iter()
+                .map(|r| (r ^ code).count_ones())
+                .min()
+                .unwrap_or(u32::MAX);
+            (value, errors)
+        })
+        .filter(|&(_, errors)| errors <= MAX_INFO_ERRORS)
+        .min_by_key(|&(_, errors)| errors)
+        .map(|(value, _)| value)
+}
+
+/// Reads the bits at `positions` (row, column), the first one as the most significant.
+fn read_bits(binary: &Binary, grid: &Grid, positions: impl Iterator<Item = (usize, usize)>) -> u32 {
+    positions.fold(0, |bits, (row, column)| {
+        bits << 1 | grid.module(binary, row, column) as u32
+    })
+}
+
+/// Reads the error correction level (0 to 3 for L, M, Q, H) and mask of the code.
+fn read_format(binary: &Binary, grid: &Grid) -> Option<(usize, usize)> {
+    let size = grid.size;
+    let first = read_bits(
+        binary,
+        grid,
+        (0..6)
+            .map(|c| (8, c))
+            .chain([(8, 7), (8, 8), (7, 8)])
+            .chain((0..6).rev().map(|r| (r, 8))),
+    );
+    let second = read_bits(
+        binary,
+        grid,
+        ((size - 7..size).rev().map(|r| (r, 8))).chain((size - 8..size).map(|c| (8, c))),
+    );
+    let format = closest(
+        &[first, second],
+        (0..32).map(|data| (data, bch(data, 0x537, 10) ^ FORMAT_MASK)),
+    )?;
+    // Levels are encoded as M, L, H, Q
+    let level = [1, 0, 3, 2][(format >> 3) as usize];
+    Some((level, (format & 7) as usize))
+}
+
+/// Reads the version information of codes from version 7 on.
+fn read_version(binary: &Binary, grid: &Grid) -> Option<usize> {
+    let size = grid.size;
+    let first = read_bits(
+        binary,
+        grid,
+        (0..6)
+            .rev()
+            .flat_map(|r| (size - 11..size - 8).rev().map(move |c| (r, c))),
+    );
+    let second = read_bits(
+        binary,
+        grid,
+        (0..6)
+            .rev()
+            .flat_map(|c| (size - 11..size - 8).rev().map(move |r| (r, c))),
+    );
+    closest(&[first, second], (7..=40).map(|v| (v, bch(v, 0x1F25, 12)))).map(|v| v as usize)
+}
+
+/// Returns the module positions of the alignment pattern centers along each axis.
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let step = (version * 8 + count * 3 + 5) / (count * 4 - 4) * 2;
+    let size = version * 4 + 17;
+    let mut positions: Vec<usize> = (0..count - 1).map(|i| size - 7 - i * step).collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+/// Returns which modules of a code of `version` belong to function patterns rather than data.
+fn function_modules(version: usize) -> Vec<bool> {
+    let size = version * 4 + 17;
+    let mut function = vec![false; size * size];
+    let mut mark = |rows: std::ops::Range<usize>, columns: std::ops::Range<usize>| {
+        for r in rows {
+            for c in columns.clone() {
+                function[r * size + c] = true;
+            }
+        }
+    };
+    // Finders with their separators and the format information, timing patterns
+    mark(0..9, 0..9);
+    mark(0..9, size - 8..size);
+    mark(size - 8..size, 0..9);
+    mark(6..7, 0..size);
+    mark(0..size, 6..7);
+    let positions = alignment_positions(version);
+    let last = positions.len().saturating_sub(1);
+    for (i, &r) in positions.iter().enumerate() {
+        for (j, &c) in positions.iter().enumerate() {
+            let on_finder = (i == 0 && (j == 0 || j == last)) || (i == last && j == 0);
+            if !on_finder {
+                mark(r - 2..r + 3, c - 2..c + 3);
+            }
+        }
+    }
+    if version >= 7 {
+        mark(0..6, size - 11..size - 8);
+        mark(size - 11..size - 8, 0..6);
+    }
+    function
+}
+
+fn mask_bit(mask: usize, row: usize, column: usize) -> bool {
+    let (i, j) = (row, column);
+    match mask {
+        0 => (i + j) % 2 == 0,
+        1 => i % 2 == 0,
+        2 => j % 3 == 0,
+        3 => (i + j) % 3 == 0,
+        4 => (i / 2 + j / 3) % 2 == 0,
+        5 => (i * j) % 2 + (i * j) % 3 == 0,
+        6 => ((i * j) % 2 + (i * j) % 3) % 2 == 0,
+        _ => ((i + j) % 2 + (i * j) % 3) % 2 == 0,
+    }
+}
+
+/// Reads the unmasked codewords in the zigzag order of the data modules.
+fn read_codewords(binary: &Binary, grid: &Grid, mask: usize) -> Vec<u8> {
+    let size = grid.size;
+    let function = function_modules(grid.version());
+    let mut codewords = Vec::new();
+    let (mut byte, mut bits) = (0u8, 0);
+    let mut right = size as isize - 1;
+    let mut upward = true;
+    while right > 0 {
+        // The vertical timing pattern shifts the column pairs left of it
+        if right == 6 {
+            right = 5;
+        }
+        for step in 0..size {
+            let row = if upward { size - 1 - step } else { step };
+            for column in [right as usize, right as usize - 1] {
+                if function[row * size + column] {
+                    continue;
+                }
+                let bit = grid.module(binary, row, column) ^ mask_bit(mask, row, column);
+                byte = byte << 1 | bit as u8;
+                bits += 1;
+                if bits == 8 {
+                    codewords.push(byte);
+                    (byte, bits) = (0, 0);
+                }
+            }
+        }
+        upward = !upward;
+        right -= 2;
+    }
+    codewords
+}
+
+/// Splits the interleaved codewords into blocks, corrects them and returns the data codewords.
+fn correct_codewords(codewords: &[u8], version: usize, level: usize) -> Option<Vec<u8>> {
+    let blocks = ECC_BLOCKS[level][version] as usize;
+    let ecc = ECC_PER_BLOCK[level][version] as usize;
+    let total = codewords.len();
+    let short_len = total / blocks;
+    let short_blocks = blocks - total % blocks;
+    let data_len = |block: usize| short_len - ecc + (block >= short_blocks) as usize;
+
+    let mut split: Vec<Vec<u8>> = (0..blocks)
+        .map(|b| Vec::with_capacity(data_len(b) + ecc))
+        .collect();
+    let mut next = codewords.iter();
+    for i in 0..short_len - ecc + 1 {
+        for (b, block) in split.iter_mut().enumerate() {
+            if i < data_len(b) {
+                block.push(*next.next()?);
+            }
+        }
+    }
+    for _ in 0..ecc {
+        for block in split.iter_mut() {
+            block.push(*next.next()?);
+        }
+    }
+
+    let mut data = Vec::new();
+    for (b, mut block) in split.into_iter().enumerate() {
+        if !reed_solomon::correct(&mut block, ecc) {
+            return None;
+        }
+        data.extend_from_slice(&block[..data_len(b)]);
+    }
+    Some(data)
+}
+
+/// Reads big endian bit fields of a byte slice.
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn read(&mut self, count: usize) -> Option<u32> {
+        if self.position + count > self.bytes.len() * 8 {
+            return None;
+        }
+        let value = (self.position..self.position + count).fold(0, |value, i| {
+            value << 1 | (self.bytes[i / 8] >> (7 - i % 8) & 1) as u32
+        });
+        self.position += count;
+        Some(value)
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() * 8 - self.position
+    }
+}
+
+/// Parses the segments of the data codewords into text. Byte segments are read as UTF-8,
+/// falling back to ISO 8859-1.
+fn parse_segments(data: &[u8], version: usize) -> Option<String> {
+    let mut reader = BitReader {
+        bytes: data,
+        position: 0,
+    };
+    let size_class = match version {
+        1..=9 => 0,
+        10..=26 => 1,
+        _ => 2,
+    };
+    let mut bytes = Vec::new();
+    while reader.remaining() >= 4 {
+        match reader.read(4)? {
+            0 => break,
+            // Numeric
+            1 => {
+                let mut count = reader.read([10, 12, 14][size_class])? as usize;
+                while count > 0 {
+                    let digits = count.min(3);
+                    let value = reader.read([4, 7, 10][digits - 1])?;
+                    bytes.extend(format!("{value:0digits$}").bytes());
+                    count -= digits;
+                }
+            }
+            // Alphanumeric
+            2 => {
+                let mut count = reader.read([9, 11, 13][size_class])? as usize;
+                while count >= 2 {
+                    let value = reader.read(11)? as usize;
+                    bytes.push(*ALPHANUMERIC.get(value / 45)?);
+                    bytes.push(ALPHANUMERIC[value % 45]);
+                    count -= 2;
+                }
+                if count == 1 {
+                    bytes.push(*ALPHANUMERIC.get(reader.read(6)? as usize)?);
+                }
+            }
+            // Byte
+            4 => {
+                let count = reader.read([8, 16, 16][size_class])?;
+                for _ in 0..count {
+                    bytes.push(reader.read(8)? as u8);
+                }
+            }
+            // Structured append header
+            3 => {
+                reader.read(16)?;
+            }
+            // Extended channel interpretation, whose designator is 1 to 3 bytes long
+            7 => {
+                let length = match reader.read(1)? {
+                    0 => 7,
+                    _ => match reader.read(1)? {
+                        0 => 14,
+                        _ => 22,
+                    },
+                };
+                reader.read(length)?;
+            }
+            // FNC1 markers, the second one with an application indicator
+            5 => {}
+            9 => {
+                reader.read(8)?;
+            }
+            // Kanji and unknown modes
+            _ => return None,
+        }
+    }
+    Some(
+        String::from_utf8(bytes)
+            .unwrap_or_else(|e| e.into_bytes().iter().map(|&b| b as char).collect()),
+    )
+}
+
+/// Decodes the code sampled by `grid`, returning its payload.
+fn decode_grid(binary: &Binary, grid: &Grid) -> Option<String> {
+    let (level, mask) = read_format(binary, grid)?;
+    let version = grid.version();
+    let codewords = read_codewords(binary, grid, mask);
+    let data = correct_codewords(&codewords, version, level)?;
+    parse_segments(&data, version)
+}
+
+/// Decodes the code of three ordered finders.
+fn decode_finders(binary: &Binary, finders: &[Finder; 3]) -> Option<DecodedCode> {
+    let [top_left, top_right, bottom_left] = finders;
+    let module = (top_left.module + top_right.module + bottom_left.module) / 3.0;
+    let span = (distance(top_left.center, top_right.center)
+        + distance(top_left.center, bottom_left.center))
+        / 2.0;
+    // Sides are 4 * version + 17 modules
+    let estimate = (span / module).round() as usize + 7;
+    let version = ((estimate.saturating_sub(17) as f32 / 4.0).round() as usize).clamp(1, 40);
+
+    for version in [version, version + 1, version.saturating_sub(1)] {
+        if !(1..=40).contains(&version) {
+            continue;
+        }
+        let Some(mut grid) = fit_grid(binary, finders, version * 4 + 17) else {
+            continue;
+        };
+        // Large codes state their version, which is more reliable than the estimate
+        if version >= 7
+            && let Some(read) = read_version(binary, &grid)
+            && read != version
+            && let Some(corrected) = fit_grid(binary, finders, read * 4 + 17)
+        {
+            grid = corrected;
+        }
+        if let Some(payload) = decode_grid(binary, &grid) {
+            let size = grid.size as f32;
+            let corners = [(0.0, 0.0), (size, 0.0), (size, size), (0.0, size)]
+                .map(|p| transform_homography(&grid.homography, p));
+            return Some(DecodedCode {
+                symbology: Symbology::QrCode,
+                payload,
+                corners,
+            });
+        }
+    }
+    None
+}
+
+/// Finds and decodes the QR codes of `binary`.
+pub(super) fn detect(binary: &Binary) -> Vec<DecodedCode> {
+    let mut finders: Vec<Finder> = find_finders(binary)
+        .into_iter()
+        .filter(|f| f.hits >= 2)
+        .collect();
+    finders.sort_by_key(|f| std::cmp::Reverse(f.hits));
+    finders.truncate(MAX_FINDERS);
+
+    let mut triples = Vec::new();
+    for i in 0..finders.len() {
+        for j in i + 1..finders.len() {
+            for k in j + 1..finders.len() {
+                if let Some((ordered, score)) = order_finders([finders[i], finders[j], finders[k]])
+                {
+                    triples.push(([i, j, k], ordered, score));
+                }
+            }
+        }
+    }
+    triples.sort_by(|a, b| a.2.total_cmp(&b.2));
+
+    let mut used = vec![false; finders.len()];
+    let mut codes = Vec::new();
+    for (indices, ordered, _) in triples {
+        if indices.iter().any(|&i| used[i]) {
+            continue;
+        }
+        if let Some(code) = decode_finders(binary, &ordered) {
+            indices.iter().for_each(|&i| used[i] = true);
+            codes.push(code);
+        }
+    }
+    codes
+}
@@ -0,0 +1,124 @@
+//! Reed-Solomon error correction over GF(256) as used by QR codes: the field polynomial is
+//! x⁸ + x⁴ + x³ + x² + 1 and the generator roots are α⁰ to α^(n-1).
+
+const FIELD_POLYNOMIAL: u16 = 0x11D;
+
+/// Powers of α, repeated once so products of logarithms need no modulo, and their logarithms.
+const TABLES: ([u8; 512], [u8; 256]) = {
+    let (mut exp, mut log) = ([0u8; 512], [0u8; 256]);
+    let mut value: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = value as u8;
+        exp[i + 255] = value as u8;
+        log[value as usize] = i as u8;
+        value <<= 1;
+        if value & 0x100 != 0 {
+            value ^= FIELD_POLYNOMIAL;
+        }
+        i += 1;
+    }
+    (exp, log)
+};
+
+fn exp(power: usize) -> u8 {
+    TABLES.0[power % 255]
+}
+
+fn log(value: u8) -> usize {
+    TABLES.1[value as usize] as usize
+}
+
+fn mul(a: u8, b: u8) -> u8 {
+    match (a, b) {
+        (0, _) | (_, 0) => 0,
+        _ => TABLES.0[log(a) + log(b)],
+    }
+}
+
+fn div(a: u8, b: u8) -> u8 {
+    match a {
+        0 => 0,
+        _ => TABLES.0[log(a) + 255 - log(b)],
+    }
+}
+
+/// Evaluates the polynomial with coefficients in increasing degree at `x`.
+fn eval(poly: &[u8], x: u8) -> u8 {
+    poly.iter().rev().fold(0, |acc, &c| mul(acc, x) ^ c)
+}
+
+/// Corrects up to `ecc_len / 2` erroneous bytes of `block` in place, whose last `ecc_len`
+/// bytes are error correction codewords. Returns false if the errors can't be corrected.
+pub(super) fn correct(block: &mut [u8], ecc_len: usize) -> bool {
+    let n = block.len();
+    // The first byte is the coefficient of the highest degree
+    let syndrome = |block: &[u8], i: usize| block.iter().fold(0, |acc, &c| mul(acc, exp(i)) ^ c);
+    let syndromes: Vec<u8> = (0..ecc_len).map(|i| syndrome(block, i)).collect();
+    if syndromes.iter().all(|&s| s == 0) {
+        return true;
+    }
+
+    // Error locator by Berlekamp-Massey, coefficients in increasing degree
+    let (mut locator, mut previous) = (vec![1u8], vec![1u8]);
+    let (mut errors, mut shift, mut last_discrepancy) = (0, 1, 1u8);
+    for step in 0..ecc_len {
+        let discrepancy = (1..=errors).fold(syndromes[step], |d, i| {
+            d ^ mul(*locator.get(i).unwrap_or(&0), syndromes[step - i])
+        });
+        if discrepancy == 0 {
+            shift += 1;
+            continue;
+        }
+        let scale = div(discrepancy, last_discrepancy);
+        let mut updated = locator.clone();
+        updated.resize(updated.len().max(previous.len() + shift), 0);
+        for (i, &c) in previous.iter().enumerate() {
+            updated[i + shift] ^= mul(scale, c);
+        }
+        if 2 * errors <= step {
+            previous = std::mem::replace(&mut locator, updated);
+            errors = step + 1 - errors;
+            last_discrepancy = discrepancy;
+            shift = 1;
+        } else {
+            locator = updated;
+            shift += 1;
+        }
+    }
+    if 2 * errors > ecc_len {
+        return false;
+    }
+
+    // Byte i is the coefficient of x^(n - 1 - i), its locator root is α^-(n - 1 - i)
+    let positions: Vec<usize> = (0..n)
+        .filter(|&i| eval(&locator, exp(255 - (n - 1 - i) % 255)) == 0)
+        .collect();
+    if positions.len() != errors {
+        return false;
+    }
+
+    // Error magnitudes by Forney's formula, with the extra X factor of a first root of α⁰
+    let mut evaluator = vec![0u8; ecc_len];
+    for (i, &s) in syndromes.iter().enumerate() {
+        for (j, &l) in locator.iter().enumerate().take(ecc_len - i) {
+            evaluator[i + j] ^= mul(s, l);
+        }
+    }
+    let derivative: Vec<u8> = locator
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, &c)| if i % 2 == 1 { c } else { 0 })
+        .collect();
+    for &i in &positions {
+        let x = exp(n - 1 - i);
+        let x_inverse = exp(255 - (n - 1 - i) % 255);
+        let denominator = eval(&derivative, x_inverse);
+        if denominator == 0 {
+            return false;
+        }
+        block[i] ^= mul(x, div(eval(&evaluator, x_inverse), denominator));
+    }
+    (0..ecc_len).all(|i| syndrome(block, i) == 0)
+}
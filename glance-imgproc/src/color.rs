@@ -0,0 +1,113 @@
+//! Color space helpers shared by the image processing operations.
+
+use glance_core::img::pixel::Rgba;
+
+pub use glance_core::img::pixel::{Lab, linear_to_srgb, srgb_to_linear};
+
+/// A color in the HSV (hue, saturation, value) space.
+/// Hue is in degrees in the [0.0, 360.0) range, saturation and value are in [0.0, 1.0].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    pub h: f32,
+    pub s: f32,
+    pub v: f32,
+}
+
+impl Hsv {
+    /// Converts the color channels of an [`Rgba`] pixel to HSV. Alpha is ignored.
+    pub fn from_rgba(pixel: &Rgba) -> Self {
+        let max = pixel.r.max(pixel.g).max(pixel.b);
+        let min = pixel.r.min(pixel.g).min(pixel.b);
+        let delta = max - min;
+
+        let h = if delta == 0.0 {
+            0.0
+        } else if max == pixel.r {
+            60.0 * ((pixel.g - pixel.b) / delta).rem_euclid(6.0)
+        } else if max == pixel.g {
+            60.0 * ((pixel.b - pixel.r) / delta + 2.0)
+        } else {
+            60.0 * ((pixel.r - pixel.g) / delta + 4.0)
+        };
+        let s = if max == 0.0 { 0.0 } else { delta / max };
+
+        Hsv { h, s, v: max }
+    }
+
+    /// Converts the HSV color back to an [`Rgba`] pixel with the given alpha.
+    pub fn to_rgba(&self, alpha: f32) -> Rgba {
+        let h = self.h.rem_euclid(360.0) / 60.0;
+        let c = self.v * self.s;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let m = self.v - c;
+
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+
+        Rgba {
+            r: r + m,
+            g: g + m,
+            b: b + m,
+            a: alpha,
+        }
+    }
+}
+
+/// CIEDE2000 color difference between two L*a*b* colors, see [`Lab::delta_e`].
+pub fn ciede2000(lab1: &Lab, lab2: &Lab) -> f32 {
+    lab1.delta_e(lab2)
+}
+
+/// Maps scalar values in [0.0, 1.0] to colors, for false color renderings of single channel
+/// data like histograms or heat maps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Colormap {
+    /// Black to white
+    Gray,
+    /// Dark purple over blue and green to yellow, perceptually uniform (matplotlib's default)
+    #[default]
+    Viridis,
+    /// Black over red and yellow to white
+    Hot,
+}
+
+/// Viridis sampled at nine evenly spaced points.
+const VIRIDIS: [[f32; 3]; 9] = [
+    [0.267_004, 0.004_874, 0.329_415],
+    [0.282_623, 0.140_926, 0.457_517],
+    [0.253_935, 0.265_254, 0.529_983],
+    [0.206_756, 0.371_758, 0.553_117],
+    [0.163_625, 0.471_133, 0.558_148],
+    [0.127_568, 0.566_949, 0.550_556],
+    [0.134_692, 0.658_636, 0.517_649],
+    [0.266_941, 0.748_751, 0.440_573],
+    [0.993_248, 0.906_157, 0.143_936],
+];
+
+impl Colormap {
+    /// Returns the opaque color of `value`, clamped to [0.0, 1.0].
+    pub fn map(&self, value: f32) -> Rgba {
+        let t = value.clamp(0.0, 1.0);
+        let [r, g, b] = match self {
+            Colormap::Gray => [t; 3],
+            Colormap::Viridis => {
+                let position = t * (VIRIDIS.len() - 1) as f32;
+                let i = (position as usize).min(VIRIDIS.len() - 2);
+                let f = position - i as f32;
+                std::array::from_fn(|c| VIRIDIS[i][c] + f * (VIRIDIS[i + 1][c] - VIRIDIS[i][c]))
+            }
+            Colormap::Hot => [
+                (3.0 * t).min(1.0),
+                (3.0 * t - 1.0).clamp(0.0, 1.0),
+                (3.0 * t - 2.0).clamp(0.0, 1.0),
+            ],
+        };
+        Rgba { r, g, b, a: 1.0 }
+    }
+}
@@ -0,0 +1,124 @@
+//! Color vision deficiency (color blindness) simulation and daltonization, to check that colors
+//! of charts and UIs stay distinguishable for everyone.
+//!
+//! Colors are converted to linear light and then to LMS cone responses. A dichromat lacks one
+//! cone type, so the missing response is reconstructed from the other two, following Viénot,
+//! Brettel and Mollon, "Digital video colourmaps for checking the legibility of displays by
+//! dichromats" (1999).
+
+use glance_core::img::{Image, pixel::Rgba};
+use glance_core::par::ParallelIterator;
+
+use crate::color::{linear_to_srgb, srgb_to_linear};
+
+type Matrix = [[f32; 3]; 3];
+
+/// Linear RGB to LMS cone responses.
+const RGB_TO_LMS: Matrix = [
+    [17.8824, 43.5161, 4.11935],
+    [3.45565, 27.1554, 3.86714],
+    [0.0299566, 0.184309, 1.46709],
+];
+
+/// LMS cone responses to linear RGB, the inverse of [`RGB_TO_LMS`].
+const LMS_TO_RGB: Matrix = [
+    [0.080_944_45, -0.130_504_41, 0.116_721_07],
+    [-0.010_248_533, 0.054_019_33, -0.113_614_71],
+    [-0.000_365_296_94, -0.004_121_614_7, 0.693_511_4],
+];
+
+/// A type of dichromacy, named after the missing cone type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CvdKind {
+    /// Missing long wavelength (red) cones
+    Protanopia,
+    /// Missing medium wavelength (green) cones
+    Deuteranopia,
+    /// Missing short wavelength (blue) cones
+    Tritanopia,
+}
+
+impl CvdKind {
+    /// The LMS projection replacing the response of the missing cone.
+    fn projection(self) -> Matrix {
+        match self {
+            CvdKind::Protanopia => [[0.0, 2.02344, -2.52581], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            CvdKind::Deuteranopia => [[1.0, 0.0, 0.0], [0.494207, 0.0, 1.24827], [0.0, 0.0, 1.0]],
+            CvdKind::Tritanopia => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [-0.395913, 0.801109, 0.0]],
+        }
+    }
+
+    /// Redistributes the color information lost to the deficiency onto the channels the viewer
+    /// can still tell apart.
+    fn error_shift(self) -> Matrix {
+        match self {
+            CvdKind::Protanopia | CvdKind::Deuteranopia => {
+                [[0.0, 0.0, 0.0], [0.7, 1.0, 0.0], [0.7, 0.0, 1.0]]
+            }
+            CvdKind::Tritanopia => [[1.0, 0.0, 0.7], [0.0, 1.0, 0.7], [0.0, 0.0, 0.0]],
+        }
+    }
+
+    /// The linear RGB transformation simulating the deficiency with the given severity.
+    fn simulation(self, severity: f32) -> Matrix {
+        let dichromat = multiply(&LMS_TO_RGB, &multiply(&self.projection(), &RGB_TO_LMS));
+        let severity = severity.clamp(0.0, 1.0);
+        std::array::from_fn(|i| {
+            std::array::from_fn(|j| {
+                let identity = if i == j { 1.0 } else { 0.0 };
+                identity + severity * (dichromat[i][j] - identity)
+            })
+        })
+    }
+}
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+fn apply(m: &Matrix, v: [f32; 3]) -> [f32; 3] {
+    std::array::from_fn(|i| m[i][0] * v[0] + m[i][1] * v[1] + m[i][2] * v[2])
+}
+
+/// Shared implementation: maps the linear RGB values of every pixel, keeping alpha.
+fn map_linear(mut image: Image<Rgba>, f: impl Fn([f32; 3]) -> [f32; 3] + Sync) -> Image<Rgba> {
+    image.par_pixels_mut().for_each(|pixel| {
+        let [r, g, b] = f([pixel.r, pixel.g, pixel.b].map(srgb_to_linear))
+            .map(|v| linear_to_srgb(v.clamp(0.0, 1.0)));
+        *pixel = Rgba {
+            r,
+            g,
+            b,
+            a: pixel.a,
+        };
+    });
+    image
+}
+
+/// Extension trait for [`glance_core::img::Image`] to simulate and compensate color vision
+/// deficiencies of RGBA images
+pub trait ColorVisionExtRgba {
+    fn simulate_cvd(self, kind: CvdKind, severity: f32) -> Self;
+    fn daltonize(self, kind: CvdKind, severity: f32) -> Self;
+}
+
+impl ColorVisionExtRgba for Image<Rgba> {
+    /// Returns the image as seen with the given deficiency. A `severity` of 1.0 simulates
+    /// dichromacy, smaller values interpolate towards normal vision for anomalous trichromacy.
+    fn simulate_cvd(self, kind: CvdKind, severity: f32) -> Self {
+        let simulation = kind.simulation(severity);
+        map_linear(self, |rgb| apply(&simulation, rgb))
+    }
+
+    /// Shifts colors that are confused with the given deficiency towards colors that can be told
+    /// apart: the difference to the simulated appearance is added back to the visible channels.
+    fn daltonize(self, kind: CvdKind, severity: f32) -> Self {
+        let simulation = kind.simulation(severity);
+        let shift = kind.error_shift();
+        map_linear(self, |rgb| {
+            let seen = apply(&simulation, rgb);
+            let correction = apply(&shift, std::array::from_fn(|i| rgb[i] - seen[i]));
+            std::array::from_fn(|i| rgb[i] + correction[i])
+        })
+    }
+}
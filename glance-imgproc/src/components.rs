@@ -0,0 +1,316 @@
+use glance_core::img::{Image, Rect, pixel::Luma};
+
+use crate::contours::ContourExtLuma;
+use crate::geometry::Point;
+use crate::moments::Moments;
+use crate::{Error, Result};
+
+/// Defines which neighbours of a pixel are considered connected to it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Connectivity {
+    /// Horizontal and vertical neighbours.
+    Four,
+    /// Horizontal, vertical and diagonal neighbours.
+    Eight,
+}
+
+/// Label map produced by [`ConnectedComponentsExtLuma::connected_components`]. Background pixels
+/// have label 0, components are numbered 1..=count in the order they are first met scanning
+/// row by row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelImage {
+    width: usize,
+    height: usize,
+    labels: Vec<u32>,
+    count: usize,
+}
+
+impl LabelImage {
+    /// Creates a label map from raw labels in row-major order, where labels are in 0..=count.
+    pub(crate) fn from_labels(width: usize, height: usize, labels: Vec<u32>, count: usize) -> Self {
+        LabelImage {
+            width,
+            height,
+            labels,
+            count,
+        }
+    }
+
+    /// Returns the dimensions of the label map as a tuple (width, height).
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the number of components, not counting the background.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the label at the specified position, or None if it is out of bounds.
+    pub fn label(&self, position: (usize, usize)) -> Option<u32> {
+        if position.0 >= self.width || position.1 >= self.height {
+            return None;
+        }
+        Some(self.labels[position.1 * self.width + position.0])
+    }
+
+    /// Returns all labels in row-major order.
+    pub fn labels(&self) -> &[u32] {
+        &self.labels
+    }
+
+    /// Returns the area in pixels of every label, indexed by label (index 0 is the background).
+    pub fn areas(&self) -> Vec<usize> {
+        let mut areas = vec![0; self.count + 1];
+        self.labels.iter().for_each(|&l| areas[l as usize] += 1);
+        areas
+    }
+
+    /// Returns a mask that is 1.0 where the label is `label` and 0.0 elsewhere.
+    pub fn mask(&self, label: u32) -> Image<Luma> {
+        let data = self
+            .labels
+            .iter()
+            .map(|&l| Luma {
+                l: if l == label { 1.0 } else { 0.0 },
+            })
+            .collect();
+        Image::from_data(self.width, self.height, data).unwrap()
+    }
+
+    /// Returns a mask that is 1.0 on pixels whose right or bottom neighbour has a different
+    /// label, tracing the borders between labels.
+    pub fn boundaries(&self) -> Image<Luma> {
+        let (width, height) = (self.width, self.height);
+        let data = (0..width * height)
+            .map(|idx| {
+                let (x, y) = (idx % width, idx / width);
+                let label = self.labels[idx];
+                let edge = (x + 1 < width && self.labels[idx + 1] != label)
+                    || (y + 1 < height && self.labels[idx + width] != label);
+                Luma {
+                    l: if edge { 1.0 } else { 0.0 },
+                }
+            })
+            .collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+
+    /// Converts the label map to an image where labels are spread evenly over (0.0, 1.0] and the
+    /// background is 0.0, useful to visualize the components.
+    pub fn to_luma(&self) -> Image<Luma> {
+        let scale = 1.0 / self.count.max(1) as f32;
+        let data = self
+            .labels
+            .iter()
+            .map(|&l| Luma {
+                l: l as f32 * scale,
+            })
+            .collect();
+        Image::from_data(self.width, self.height, data).unwrap()
+    }
+}
+
+/// Returns the root of `label`, compressing the path along the way.
+fn find(parents: &mut [u32], mut label: u32) -> u32 {
+    while parents[label as usize] != label {
+        parents[label as usize] = parents[parents[label as usize] as usize];
+        label = parents[label as usize];
+    }
+    label
+}
+
+/// Merges the sets of `a` and `b`, keeping the smaller root so labels stay in scan order.
+fn union(parents: &mut [u32], a: u32, b: u32) -> u32 {
+    let (a, b) = (find(parents, a), find(parents, b));
+    let (root, child) = (a.min(b), a.max(b));
+    parents[child as usize] = root;
+    root
+}
+
+/// Extension trait for [`glance_core::img::Image`] to provide connected component labeling for
+/// Luma images
+pub trait ConnectedComponentsExtLuma {
+    fn connected_components(&self, connectivity: Connectivity) -> LabelImage;
+}
+
+impl ConnectedComponentsExtLuma for Image<Luma> {
+    /// Labels the connected regions of non-zero pixels (e.g. the output of a threshold) using
+    /// the two-pass union-find algorithm.
+    fn connected_components(&self, connectivity: Connectivity) -> LabelImage {
+        let (width, height) = self.dimensions();
+        let pixels: Vec<Luma> = self.pixels().collect();
+        let mut labels = vec![0u32; width * height];
+        // parents[0] is the background
+        let mut parents = vec![0u32];
+
+        let neighbours: &[(isize, isize)] = match connectivity {
+            Connectivity::Four => &[(-1, 0), (0, -1)],
+            Connectivity::Eight => &[(-1, 0), (-1, -1), (0, -1), (1, -1)],
+        };
+
+        // First pass: provisional labels and equivalences
+        for y in 0..height {
+            for x in 0..width {
+                if pixels[y * width + x].l <= 0.0 {
+                    continue;
+                }
+
+                let mut current = 0;
+                for (dx, dy) in neighbours {
+                    let (nx, ny) = (x as isize + dx, y as isize + dy);
+                    if nx < 0 || ny < 0 || nx >= width as isize {
+                        continue;
+                    }
+                    let neighbour = labels[ny as usize * width + nx as usize];
+                    if neighbour == 0 {
+                        continue;
+                    }
+                    current = if current == 0 {
+                        find(&mut parents, neighbour)
+                    } else {
+                        union(&mut parents, current, neighbour)
+                    };
+                }
+
+                if current == 0 {
+                    current = parents.len() as u32;
+                    parents.push(current);
+                }
+                labels[y * width + x] = current;
+            }
+        }
+
+        // Second pass: resolve roots and renumber them consecutively
+        let mut renumbered = vec![0u32; parents.len()];
+        let mut count = 0;
+        for label in 1..parents.len() as u32 {
+            let root = find(&mut parents, label);
+            if root == label {
+                count += 1;
+                renumbered[label as usize] = count as u32;
+            }
+        }
+        for label in labels.iter_mut().filter(|l| **l != 0) {
+            *label = renumbered[find(&mut parents, *label) as usize];
+        }
+
+        LabelImage {
+            width,
+            height,
+            labels,
+            count,
+        }
+    }
+}
+
+/// Measurements of one component of a [`LabelImage`], see [`regionprops`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionProps {
+    pub label: u32,
+    /// Number of pixels
+    pub area: usize,
+    pub bounding_box: Rect,
+    /// Mean position of the pixels
+    pub centroid: Point,
+    /// Length of the outer boundary through the centers of the border pixels, see
+    /// [`crate::contours::Contour::perimeter`]. Holes don't count
+    pub perimeter: f32,
+    /// Eccentricity of the ellipse with the same second moments, from 0.0 for a circle towards
+    /// 1.0 for a line
+    pub eccentricity: f32,
+    /// Angle of the major axis in degrees, see [`Moments::orientation`]
+    pub orientation: f32,
+    /// Mean of the intensity image over the pixels, if one was given
+    pub mean_intensity: Option<f32>,
+}
+
+/// Measures every component of `labels`, in label order. With an `intensity` image, which
+/// must have the dimensions of the label map, the mean intensity of every component is
+/// measured as well.
+pub fn regionprops(
+    labels: &LabelImage,
+    intensity: Option<&Image<Luma>>,
+) -> Result<Vec<RegionProps>> {
+    let (width, height) = labels.dimensions();
+    if let Some(intensity) = intensity
+        && intensity.dimensions() != (width, height)
+    {
+        return Err(Error::DimensionMismatch {
+            expected: (width, height),
+            actual: intensity.dimensions(),
+        });
+    }
+
+    // Raw moments, bounds (x0, y0, x1, y1) and intensity sum of every label
+    let count = labels.count();
+    let mut raw = vec![[0.0f64; 10]; count + 1];
+    let mut bounds = vec![(usize::MAX, usize::MAX, 0, 0); count + 1];
+    let mut sums = vec![0.0f64; count + 1];
+    let values: Option<Vec<f32>> = intensity.map(|image| image.pixels().map(|p| p.l).collect());
+    for (idx, &label) in labels.labels().iter().enumerate().filter(|(_, l)| **l != 0) {
+        let label = label as usize;
+        let (x, y) = (idx % width, idx / width);
+        let b = &mut bounds[label];
+        *b = (b.0.min(x), b.1.min(y), b.2.max(x), b.3.max(y));
+        let (x, y) = (x as f64, y as f64);
+        let (x2, y2) = (x * x, y * y);
+        let terms = [1.0, x, y, x2, x * y, y2, x2 * x, x2 * y, x * y2, y2 * y];
+        raw[label].iter_mut().zip(terms).for_each(|(m, t)| *m += t);
+        if let Some(values) = &values {
+            sums[label] += values[idx] as f64;
+        }
+    }
+
+    Ok((1..=count)
+        .map(|label| {
+            let (x0, y0, x1, y1) = bounds[label];
+            let bounding_box = Rect::new(x0, y0, x1 - x0 + 1, y1 - y0 + 1);
+            let moments = Moments::from_raw(raw[label]);
+            let area = moments.m00;
+
+            // Eigenvalues of the covariance of the pixel positions
+            let (a, b, c) = (
+                moments.mu20 / area,
+                moments.mu11 / area,
+                moments.mu02 / area,
+            );
+            let spread = ((a - c).powi(2) + 4.0 * b * b).sqrt();
+            let (major, minor) = ((a + c + spread) / 2.0, (a + c - spread) / 2.0);
+            let eccentricity = match major > 0.0 {
+                true => (1.0 - minor.max(0.0) / major).sqrt() as f32,
+                false => 0.0,
+            };
+
+            // Trace the outline in a mask of the bounding box
+            let mask = Image::from_data(
+                bounding_box.width,
+                bounding_box.height,
+                (0..bounding_box.area())
+                    .map(|i| {
+                        let (x, y) = (x0 + i % bounding_box.width, y0 + i / bounding_box.width);
+                        let inside = labels.labels()[y * width + x] == label as u32;
+                        Luma::new(inside as u8 as f32)
+                    })
+                    .collect(),
+            )
+            .unwrap();
+            let perimeter = mask
+                .find_contours()
+                .iter()
+                .map(|contour| contour.perimeter())
+                .fold(0.0, f32::max);
+
+            RegionProps {
+                label: label as u32,
+                area: area as usize,
+                bounding_box,
+                centroid: moments.centroid().unwrap(),
+                perimeter,
+                eccentricity,
+                orientation: moments.orientation(),
+                mean_intensity: values.as_ref().map(|_| (sums[label] / area) as f32),
+            }
+        })
+        .collect())
+}
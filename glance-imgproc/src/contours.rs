@@ -0,0 +1,214 @@
+use glance_core::img::{Image, Rect, pixel::Luma};
+
+use crate::components::{ConnectedComponentsExtLuma, Connectivity};
+use crate::geometry::{self, Point, RotatedRect};
+use crate::moments::{Moments, polygon_moments};
+
+/// Neighbour offsets in clockwise order on screen, starting east.
+const DIRECTIONS: [(isize, isize); 8] = [
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+];
+
+/// The closed outer boundary of an object, as the centers of its border pixels in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contour {
+    pub points: Vec<Point>,
+}
+
+impl Contour {
+    /// Returns the area enclosed by the contour polygon (shoelace formula).
+    pub fn area(&self) -> f32 {
+        let n = self.points.len();
+        let twice: f32 = (0..n)
+            .map(|i| {
+                let (a, b) = (self.points[i], self.points[(i + 1) % n]);
+                a.0 * b.1 - b.0 * a.1
+            })
+            .sum();
+        twice.abs() / 2.0
+    }
+
+    /// Returns the length of the closed contour polygon.
+    pub fn perimeter(&self) -> f32 {
+        let n = self.points.len();
+        (0..n)
+            .map(|i| geometry::distance(self.points[i], self.points[(i + 1) % n]))
+            .sum()
+    }
+
+    /// Returns the smallest axis aligned [`Rect`] containing every contour pixel.
+    pub fn bounding_rect(&self) -> Rect {
+        if self.points.is_empty() {
+            return Rect::new(0, 0, 0, 0);
+        }
+        let (min_x, min_y, max_x, max_y) = self.points.iter().fold(
+            (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+            |(x0, y0, x1, y1), p| (x0.min(p.0), y0.min(p.1), x1.max(p.0), y1.max(p.1)),
+        );
+        Rect::new(
+            min_x as usize,
+            min_y as usize,
+            (max_x - min_x) as usize + 1,
+            (max_y - min_y) as usize + 1,
+        )
+    }
+
+    /// Returns the moments of the area enclosed by the contour polygon.
+    pub fn moments(&self) -> Moments {
+        polygon_moments(&self.points)
+    }
+
+    /// Returns the convex hull of the contour, see [`geometry::convex_hull`].
+    pub fn convex_hull(&self) -> Vec<Point> {
+        geometry::convex_hull(&self.points)
+    }
+
+    /// Returns the minimum area rotated rectangle around the contour, see
+    /// [`geometry::min_area_rect`].
+    pub fn min_area_rect(&self) -> Option<RotatedRect> {
+        geometry::min_area_rect(&self.points)
+    }
+
+    /// Returns the minimum enclosing circle (center, radius) of the contour, see
+    /// [`geometry::min_enclosing_circle`].
+    pub fn min_enclosing_circle(&self) -> Option<(Point, f32)> {
+        geometry::min_enclosing_circle(&self.points)
+    }
+}
+
+/// Extension trait for [`glance_core::img::Image`] to provide contour detection for Luma images
+pub trait ContourExtLuma {
+    fn find_contours(&self) -> Vec<Contour>;
+}
+
+impl ContourExtLuma for Image<Luma> {
+    /// Traces the outer boundary of every 8-connected region of non-zero pixels with Moore
+    /// neighbour tracing. Holes inside regions are not traced. Contours are returned in the
+    /// order of their topmost-leftmost pixel.
+    fn find_contours(&self) -> Vec<Contour> {
+        let labels = self.connected_components(Connectivity::Eight);
+        let (width, height) = labels.dimensions();
+        let labels_data = labels.labels();
+
+        // The first pixel of every label in raster order is its topmost-leftmost
+        let mut starts = vec![None; labels.count() + 1];
+        for (idx, &label) in labels_data.iter().enumerate() {
+            if label != 0 && starts[label as usize].is_none() {
+                starts[label as usize] = Some((idx % width, idx / width));
+            }
+        }
+
+        let is_label = |x: isize, y: isize, label: u32| {
+            x >= 0
+                && y >= 0
+                && (x as usize) < width
+                && (y as usize) < height
+                && labels_data[y as usize * width + x as usize] == label
+        };
+
+        starts
+            .iter()
+            .enumerate()
+            .skip(1)
+            .filter_map(|(label, start)| start.map(|s| (label as u32, s)))
+            .map(|(label, start)| {
+                let start = (start.0 as isize, start.1 as isize);
+                let mut points = vec![(start.0 as f32, start.1 as f32)];
+                let (mut current, mut search_from) = (start, 4);
+                let mut first_move = None;
+
+                loop {
+                    let next = (0..8).map(|i| (search_from + i) % 8).find(|&d| {
+                        let (dx, dy) = DIRECTIONS[d];
+                        is_label(current.0 + dx, current.1 + dy, label)
+                    });
+                    // Isolated pixel
+                    let Some(direction) = next else { break };
+
+                    // Jacob's stopping criterion: back at the start, leaving the same way
+                    if current == start {
+                        match first_move {
+                            None => first_move = Some(direction),
+                            Some(first) if first == direction => break,
+                            _ => {}
+                        }
+                    }
+
+                    let (dx, dy) = DIRECTIONS[direction];
+                    current = (current.0 + dx, current.1 + dy);
+                    if current != start {
+                        points.push((current.0 as f32, current.1 as f32));
+                    }
+                    // Resume the search from the last background pixel checked
+                    search_from = if direction % 2 == 0 {
+                        (direction + 6) % 8
+                    } else {
+                        (direction + 5) % 8
+                    };
+                }
+
+                Contour { points }
+            })
+            .collect()
+    }
+}
+
+/// Returns the outlines of the regions of non-zero pixels of a binary mask as polygons through
+/// the centers of their border pixels, simplified by `epsilon` pixels with
+/// [`geometry::simplify_polygon`]. Holes are not traced, see [`ContourExtLuma::find_contours`].
+pub fn mask_to_polygons(mask: &Image<Luma>, epsilon: f32) -> Vec<Vec<Point>> {
+    mask.find_contours()
+        .into_iter()
+        .map(|contour| geometry::simplify_polygon(&contour.points, epsilon))
+        .collect()
+}
+
+/// Rasterizes polygons into a binary mask of `size` = (width, height). Pixels are set to 1.0 if
+/// their center lies inside a polygon (by the even-odd rule) or on its outline, so that polygons
+/// returned by [`mask_to_polygons`] with an `epsilon` of 0.0 restore their regions.
+pub fn polygons_to_mask(polygons: &[Vec<Point>], size: (usize, usize)) -> Image<Luma> {
+    let (width, height) = size;
+    let mut mask = vec![Luma::new(0.0); width * height];
+    let mut set = |x: f32, y: f32| {
+        let (x, y) = (x.round(), y.round());
+        if x >= 0.0 && y >= 0.0 && (x as usize) < width && (y as usize) < height {
+            mask[y as usize * width + x as usize] = Luma::new(1.0);
+        }
+    };
+
+    for polygon in polygons {
+        let n = polygon.len();
+        let edges = || (0..n).map(|i| (polygon[i], polygon[(i + 1) % n]));
+        for y in 0..height {
+            let row = y as f32;
+            // Half-open crossings, so that vertices on the row are counted once
+            let mut crossings: Vec<f32> = edges()
+                .filter(|(a, b)| (a.1 <= row) != (b.1 <= row))
+                .map(|(a, b)| a.0 + (row - a.1) * (b.0 - a.0) / (b.1 - a.1))
+                .collect();
+            crossings.sort_by(f32::total_cmp);
+            for span in crossings.chunks_exact(2) {
+                let start = span[0].ceil().max(0.0) as usize;
+                let end = span[1].floor().min(width as f32 - 1.0);
+                if end >= 0.0 {
+                    (start..=end as usize).for_each(|x| set(x as f32, row));
+                }
+            }
+        }
+        for (a, b) in edges() {
+            let steps = (b.0 - a.0).abs().max((b.1 - a.1).abs()).ceil().max(1.0);
+            for s in 0..=steps as usize {
+                let t = s as f32 / steps;
+                set(a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1));
+            }
+        }
+    }
+    Image::from_data(width, height, mask).unwrap()
+}
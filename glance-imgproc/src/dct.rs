@@ -0,0 +1,204 @@
+//! Orthonormal 2D discrete cosine transform (DCT-II and its inverse) and block-based
+//! processing, as used by JPEG style codecs and perceptual hashes.
+
+use glance_core::CoreError;
+use glance_core::img::{Image, Rect, pixel::Luma};
+use glance_core::par::*;
+
+use crate::Result;
+
+/// Returns the orthonormal DCT-II basis as a row-major `n` x `n` matrix, where entry (k, i) is
+/// the weight of sample i in coefficient k.
+fn dct_basis(n: usize) -> Vec<f32> {
+    let mut basis = vec![0.0; n * n];
+    for k in 0..n {
+        let scale = if k == 0 {
+            (1.0 / n as f32).sqrt()
+        } else {
+            (2.0 / n as f32).sqrt()
+        };
+        for i in 0..n {
+            basis[k * n + i] = scale
+                * (std::f32::consts::PI * (2 * i + 1) as f32 * k as f32 / (2 * n) as f32).cos();
+        }
+    }
+    basis
+}
+
+/// Applies the separable transform `basis` (or its transpose for the inverse) to a row-major
+/// `width` x `height` buffer.
+fn transform_2d(data: &[f32], width: usize, height: usize, inverse: bool) -> Vec<f32> {
+    let basis_x = dct_basis(width);
+    let basis_y = dct_basis(height);
+    let weight = |basis: &[f32], n: usize, out: usize, inp: usize| {
+        if inverse {
+            basis[inp * n + out]
+        } else {
+            basis[out * n + inp]
+        }
+    };
+
+    // Rows first, then columns
+    let mut rows = vec![0.0; width * height];
+    for y in 0..height {
+        for u in 0..width {
+            rows[y * width + u] = (0..width)
+                .map(|x| weight(&basis_x, width, u, x) * data[y * width + x])
+                .sum();
+        }
+    }
+
+    let mut output = vec![0.0; width * height];
+    for v in 0..height {
+        for x in 0..width {
+            output[v * width + x] = (0..height)
+                .map(|y| weight(&basis_y, height, v, y) * rows[y * width + x])
+                .sum();
+        }
+    }
+    output
+}
+
+/// Computes the orthonormal 2D DCT-II of a row-major `width` x `height` buffer.
+pub fn dct_2d(data: &[f32], width: usize, height: usize) -> Vec<f32> {
+    transform_2d(data, width, height, false)
+}
+
+/// Computes the inverse of [`dct_2d`].
+pub fn idct_2d(data: &[f32], width: usize, height: usize) -> Vec<f32> {
+    transform_2d(data, width, height, true)
+}
+
+/// A rectangular block of intensities copied out of an image.
+#[derive(Debug, Clone)]
+pub struct Block {
+    /// Position and size of the block in the source image
+    pub rect: Rect,
+    /// Row-major intensities, `rect.width * rect.height` entries
+    pub data: Vec<f32>,
+}
+
+/// Iterator over the non-overlapping blocks of an image in row-major order.
+/// Blocks on the right and bottom edges are smaller when the image dimensions are not a
+/// multiple of the block size.
+pub struct Blocks<'a> {
+    image: &'a Image<Luma>,
+    block_size: usize,
+    next: (usize, usize),
+}
+
+impl Iterator for Blocks<'_> {
+    type Item = Block;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (width, height) = self.image.dimensions();
+        let (x, y) = self.next;
+        if y >= height || width == 0 {
+            return None;
+        }
+
+        let rect = Rect::new(x, y, self.block_size, self.block_size).clip_to((width, height));
+        let mut data = Vec::with_capacity(rect.area());
+        for by in rect.y..rect.y + rect.height {
+            for bx in rect.x..rect.x + rect.width {
+                data.push(self.image.get_pixel((bx, by)).unwrap().l);
+            }
+        }
+
+        self.next = if x + self.block_size < width {
+            (x + self.block_size, y)
+        } else {
+            (0, y + self.block_size)
+        };
+        Some(Block { rect, data })
+    }
+}
+
+/// Extension trait for [`glance_core::img::Image`] to provide DCT based operations for Luma
+/// images
+pub trait DctExtLuma {
+    fn dct(&self) -> Image<Luma>;
+    fn idct(&self) -> Image<Luma>;
+    fn blocks(&self, block_size: usize) -> Result<Blocks<'_>>;
+    fn block_dct(&self, block_size: usize) -> Result<Image<Luma>>;
+    fn block_idct(&self, block_size: usize) -> Result<Image<Luma>>;
+}
+
+/// Replaces every block of the image by `f(block)`, processing blocks in parallel.
+fn map_blocks(
+    image: &Image<Luma>,
+    block_size: usize,
+    f: impl Fn(&Block) -> Vec<f32> + Sync,
+) -> Result<Image<Luma>> {
+    let (width, height) = image.dimensions();
+    let blocks: Vec<Block> = image.blocks(block_size)?.collect();
+    let transformed: Vec<(Rect, Vec<f32>)> = blocks
+        .par_iter()
+        .map(|block| (block.rect, f(block)))
+        .collect();
+
+    let mut output = Image::new(width, height);
+    for (rect, data) in transformed {
+        for (i, l) in data.into_iter().enumerate() {
+            let position = (rect.x + i % rect.width, rect.y + i / rect.width);
+            output.set_pixel(position, Luma { l }).unwrap();
+        }
+    }
+    Ok(output)
+}
+
+impl DctExtLuma for Image<Luma> {
+    /// Returns the DCT coefficients of the whole image, with the DC coefficient at (0, 0).
+    fn dct(&self) -> Image<Luma> {
+        let (width, height) = self.dimensions();
+        let data: Vec<f32> = self.pixels().map(|p| p.l).collect();
+        let coefficients = dct_2d(&data, width, height)
+            .into_iter()
+            .map(|l| Luma { l })
+            .collect();
+        Image::from_data(width, height, coefficients).unwrap()
+    }
+
+    /// Reconstructs an image from the coefficients produced by [`DctExtLuma::dct`].
+    fn idct(&self) -> Image<Luma> {
+        let (width, height) = self.dimensions();
+        let data: Vec<f32> = self.pixels().map(|p| p.l).collect();
+        let samples = idct_2d(&data, width, height)
+            .into_iter()
+            .map(|l| Luma { l })
+            .collect();
+        Image::from_data(width, height, samples).unwrap()
+    }
+
+    /// Returns an iterator over the non-overlapping `block_size` x `block_size` blocks of the
+    /// image. JPEG uses a block size of 8. Fails with [`CoreError::InvalidData`] if the block
+    /// size is zero.
+    fn blocks(&self, block_size: usize) -> Result<Blocks<'_>> {
+        if block_size == 0 {
+            return Err(
+                CoreError::InvalidData("block size must be greater than zero".into()).into(),
+            );
+        }
+        Ok(Blocks {
+            image: self,
+            block_size,
+            next: (0, 0),
+        })
+    }
+
+    /// Transforms every block of the image independently. Each block's DC coefficient is stored
+    /// at its top-left pixel. Fails if the block size is zero.
+    fn block_dct(&self, block_size: usize) -> Result<Image<Luma>> {
+        map_blocks(self, block_size, |block| {
+            dct_2d(&block.data, block.rect.width, block.rect.height)
+        })
+    }
+
+    /// Reconstructs an image from the coefficients produced by [`DctExtLuma::block_dct`] with
+    /// the same block size. Fails if the block size is zero.
+    fn block_idct(&self, block_size: usize) -> Result<Image<Luma>> {
+        map_blocks(self, block_size, |block| {
+            idct_2d(&block.data, block.rect.width, block.rect.height)
+        })
+    }
+}
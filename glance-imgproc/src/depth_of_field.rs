@@ -0,0 +1,92 @@
+//! Synthetic depth of field: blurs a sharp image according to a depth map, as a lens focused at
+//! a given distance would. Every pixel spreads over its circle of confusion, whose radius grows
+//! with the distance to the focus plane, in linear light like [`crate::linear_filters`]'s
+//! `lens_blur()`.
+//!
+//! The blur is computed by gathering: a pixel collects every neighbour whose circle covers it.
+//! Neighbours behind it may only cover it as far as its own circle reaches, so blurred
+//! backgrounds don't bleed over sharp foreground edges, while blurred foregrounds still spread
+//! over the background.
+
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Rgba},
+};
+use glance_core::par::*;
+
+use crate::color::{linear_to_srgb, srgb_to_linear};
+use crate::{Error, Result};
+
+/// Renders depth of field for `color` with the per-pixel distances in `depth`, both of the same
+/// size. The lens is focused at `focus_distance`, in the units of the depth map. The circle of
+/// confusion at distance d has the radius `aperture * |d - focus_distance| / d` in pixels, so
+/// `aperture` is the blur radius of the far background; the blur in front of the focus plane is
+/// capped at the same radius.
+pub fn depth_of_field(
+    color: &Image<Rgba>,
+    depth: &Image<Luma>,
+    focus_distance: f32,
+    aperture: f32,
+) -> Result<Image<Rgba>> {
+    let (width, height) = color.dimensions();
+    if depth.dimensions() != (width, height) {
+        return Err(Error::DimensionMismatch {
+            expected: (width, height),
+            actual: depth.dimensions(),
+        });
+    }
+
+    let aperture = aperture.max(0.0);
+    let depths: Vec<f32> = depth.pixels().map(|p| p.l).collect();
+    let radii: Vec<f32> = depths
+        .iter()
+        .map(|&d| (aperture * (d - focus_distance).abs() / d.max(f32::EPSILON)).min(aperture))
+        .collect();
+    let linear: Vec<[f32; 4]> = color
+        .pixels()
+        .map(|p| {
+            [
+                srgb_to_linear(p.r),
+                srgb_to_linear(p.g),
+                srgb_to_linear(p.b),
+                p.a,
+            ]
+        })
+        .collect();
+
+    let reach = aperture.ceil() as isize;
+    let data = (0..width * height)
+        .into_par_iter()
+        .map(|i| {
+            let (x, y) = ((i % width) as isize, (i / width) as isize);
+            let mut sum = [0.0f32; 4];
+            let mut total = 0.0;
+            for ny in (y - reach).max(0)..(y + reach + 1).min(height as isize) {
+                for nx in (x - reach).max(0)..(x + reach + 1).min(width as isize) {
+                    let j = ny as usize * width + nx as usize;
+                    let distance = ((nx - x) as f32).hypot((ny - y) as f32);
+                    let radius = if depths[j] > depths[i] {
+                        radii[j].min(radii[i])
+                    } else {
+                        radii[j]
+                    };
+                    // Anti-aliased coverage, spread over the area of the circle
+                    let coverage = (radius - distance + 0.5).clamp(0.0, 1.0);
+                    let weight = coverage / radii[j].max(0.5).powi(2);
+                    for (s, v) in sum.iter_mut().zip(linear[j]) {
+                        *s += weight * v;
+                    }
+                    total += weight;
+                }
+            }
+            let [r, g, b, a] = sum.map(|s| s / total);
+            Rgba {
+                r: linear_to_srgb(r.clamp(0.0, 1.0)),
+                g: linear_to_srgb(g.clamp(0.0, 1.0)),
+                b: linear_to_srgb(b.clamp(0.0, 1.0)),
+                a,
+            }
+        })
+        .collect();
+    Ok(Image::from_data(width, height, data)?)
+}
@@ -0,0 +1,156 @@
+//! Skew correction of scanned documents. The skew is found with projection profiles: the ink
+//! of the page is projected onto the vertical axis of a candidate rotation, and at the angle of
+//! the text lines the profile alternates most sharply between lines and the gaps between them.
+
+use glance_core::img::{Image, pixel::Pixel};
+use glance_core::par::*;
+
+use crate::affine::rotation_about;
+
+/// Most ink pixels projected per candidate angle; larger pages are subsampled.
+const MAX_PROFILE_SAMPLES: usize = 100_000;
+/// Angle steps in degrees of the coarse search and of the refinement around its best angle.
+const COARSE_STEP: f32 = 0.5;
+const FINE_STEP: f32 = 0.05;
+
+/// Ink and background of a page: the pixels on the less common side of the midpoint between
+/// the darkest and brightest luminance are ink, so light text on dark pages works as well.
+struct Page {
+    /// Ink positions relative to the image center
+    ink: Vec<(f32, f32)>,
+    /// Indices of the background pixels
+    background: Vec<usize>,
+}
+
+fn split_page<P: Pixel>(image: &Image<P>) -> Option<Page> {
+    let (width, height) = image.dimensions();
+    let luminance: Vec<f32> = image.pixels().map(|p| p.luminance()).collect();
+    let (min, max) = luminance
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(lo, hi), &l| (lo.min(l), hi.max(l)));
+    if max - min < 1e-3 {
+        return None;
+    }
+    let threshold = (min + max) / 2.0;
+    let dark = luminance.iter().filter(|&&l| l < threshold).count();
+    let ink_is_dark = dark * 2 <= luminance.len();
+
+    let center = ((width as f32 - 1.0) / 2.0, (height as f32 - 1.0) / 2.0);
+    let (mut ink, mut background) = (Vec::new(), Vec::new());
+    for (i, &l) in luminance.iter().enumerate() {
+        if (l < threshold) == ink_is_dark {
+            let (x, y) = ((i % width) as f32, (i / width) as f32);
+            ink.push((x - center.0, y - center.1));
+        } else {
+            background.push(i);
+        }
+    }
+    let stride = ink.len().div_ceil(MAX_PROFILE_SAMPLES).max(1);
+    let ink = ink.into_iter().step_by(stride).collect();
+    Some(Page { ink, background })
+}
+
+/// Returns the sharpness of the projection profile of `ink` at `angle` degrees: the sum of the
+/// squared counts of one pixel high bins.
+fn profile_score(ink: &[(f32, f32)], angle: f32, reach: f32) -> u64 {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let last = 2 * reach.ceil() as usize + 1;
+    let mut bins = vec![0u64; last + 1];
+    for &(x, y) in ink {
+        let position = sin * x + cos * y + reach;
+        bins[(position.round().max(0.0) as usize).min(last)] += 1;
+    }
+    bins.iter().map(|&count| count * count).sum()
+}
+
+/// Returns the angle in `candidates` with the sharpest profile, the smallest one on ties.
+fn best_angle(ink: &[(f32, f32)], candidates: Vec<f32>, reach: f32) -> f32 {
+    let scores: Vec<u64> = candidates
+        .par_iter()
+        .map(|&angle| profile_score(ink, angle, reach))
+        .collect();
+    candidates
+        .iter()
+        .zip(scores)
+        .max_by(|(a, sa), (b, sb)| sa.cmp(sb).then(b.abs().total_cmp(&a.abs())))
+        .map_or(0.0, |(&angle, _)| angle)
+}
+
+/// Returns `count` angles at `step` degrees on both sides of `center`.
+fn angles_around(center: f32, step: f32, count: usize) -> Vec<f32> {
+    (0..=2 * count)
+        .map(|i| center + (i as f32 - count as f32) * step)
+        .collect()
+}
+
+/// Estimates the skew of the text lines or dominant horizontal edges of a document, in degrees
+/// counter-clockwise on screen (y points down) within `max_angle` of horizontal. Returns 0.0
+/// for blank pages.
+pub fn estimate_skew<P: Pixel>(image: &Image<P>, max_angle: f32) -> f32 {
+    let Some(page) = split_page(image) else {
+        return 0.0;
+    };
+    let max_angle = max_angle.clamp(0.0, 45.0);
+    let (width, height) = image.dimensions();
+    let reach = (width as f32).hypot(height as f32) / 2.0;
+
+    let coarse = angles_around(0.0, COARSE_STEP, (max_angle / COARSE_STEP) as usize);
+    let coarse = best_angle(&page.ink, coarse, reach);
+    let fine = angles_around(coarse, FINE_STEP, (COARSE_STEP / FINE_STEP) as usize)
+        .into_iter()
+        .filter(|angle| angle.abs() <= max_angle)
+        .collect();
+    best_angle(&page.ink, fine, reach)
+}
+
+/// Extension trait for [`glance_core::img::Image`] to straighten scanned documents of any pixel
+/// type.
+pub trait DeskewExt: Sized {
+    fn deskew(self, max_angle: f32) -> (Self, f32);
+}
+
+impl<P> DeskewExt for Image<P>
+where
+    P: Pixel,
+{
+    /// Rotates the image about its center to level the skew found by [`estimate_skew`] within
+    /// `max_angle` degrees, and returns it with the skew. The output keeps the input dimensions;
+    /// the corners uncovered by the rotation are filled with the mean background color.
+    fn deskew(self, max_angle: f32) -> (Self, f32) {
+        let angle = estimate_skew(&self, max_angle);
+        if angle == 0.0 {
+            return (self, 0.0);
+        }
+        let background = split_page(&self)
+            .map(|page| page.background)
+            .unwrap_or_default();
+        let mut fill = P::new();
+        for c in 0..P::channel_count() {
+            let sum: f32 = background
+                .iter()
+                .map(|&i| self.as_slice()[i].channel(c))
+                .sum();
+            fill.set_channel(c, sum / background.len().max(1) as f32);
+        }
+
+        let (width, height) = self.dimensions();
+        let center = ((width as f32 - 1.0) / 2.0, (height as f32 - 1.0) / 2.0);
+        // Undoing a rotation by -angle is rotating by angle
+        let inverse = rotation_about(angle, center);
+        let data = (0..width * height)
+            .into_par_iter()
+            .map(|i| {
+                let (x, y) = ((i % width) as f32, (i / width) as f32);
+                let sx = inverse[0][0] * x + inverse[0][1] * y + inverse[0][2];
+                let sy = inverse[1][0] * x + inverse[1][1] * y + inverse[1][2];
+                let inside =
+                    sx >= -0.5 && sy >= -0.5 && sx < width as f32 - 0.5 && sy < height as f32 - 0.5;
+                match inside {
+                    true => self.sample_bilinear(sx, sy),
+                    false => fill,
+                }
+            })
+            .collect();
+        (Image::from_data(width, height, data).unwrap(), angle)
+    }
+}
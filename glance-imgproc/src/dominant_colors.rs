@@ -0,0 +1,148 @@
+//! Dominant colors of an image, for theming, thumbnails and quick summaries of content. Colors
+//! are clustered with k-means in CIE L*a*b*, where distances follow perceived differences,
+//! starting from the median cut palette of [`glance_core::img::Palette`].
+
+use glance_core::img::pixel::{Pixel, Rgba};
+use glance_core::img::{Image, Palette};
+use glance_core::par::*;
+
+use crate::color::Lab;
+
+/// Most pixels clustered by k-means; the populations are still counted over every pixel.
+const MAX_SAMPLES: usize = 50_000;
+const MAX_ITERATIONS: usize = 20;
+/// Movement of the cluster centers in L*a*b* units below which k-means stops.
+const CONVERGENCE: f32 = 0.01;
+
+/// A color and the fraction of the image it represents.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DominantColor {
+    /// Mean color of the cluster, opaque
+    pub color: Rgba,
+    /// Fraction of the counted pixels in the cluster, in [0.0, 1.0]
+    pub fraction: f32,
+}
+
+fn lab_distance(a: &Lab, b: &Lab) -> f32 {
+    (a.l - b.l).powi(2) + (a.a - b.a).powi(2) + (a.b - b.b).powi(2)
+}
+
+fn nearest(centers: &[Lab], color: &Lab) -> usize {
+    centers
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| lab_distance(a, color).total_cmp(&lab_distance(b, color)))
+        .map_or(0, |(i, _)| i)
+}
+
+/// Returns the mean of every cluster of `colors`, or the previous center for empty clusters.
+fn cluster_means(colors: &[Lab], assignments: &[usize], centers: &[Lab]) -> Vec<Lab> {
+    let mut sums = vec![(0.0f64, 0.0f64, 0.0f64, 0usize); centers.len()];
+    for (color, &cluster) in colors.iter().zip(assignments) {
+        let sum = &mut sums[cluster];
+        sum.0 += color.l as f64;
+        sum.1 += color.a as f64;
+        sum.2 += color.b as f64;
+        sum.3 += 1;
+    }
+    sums.iter()
+        .zip(centers)
+        .map(|(&(l, a, b, count), &center)| match count {
+            0 => center,
+            _ => Lab {
+                l: (l / count as f64) as f32,
+                a: (a / count as f64) as f32,
+                b: (b / count as f64) as f32,
+            },
+        })
+        .collect()
+}
+
+/// Extension trait for [`glance_core::img::Image`] to find the dominant colors of RGBA images
+pub trait DominantColorsExtRgba {
+    fn dominant_colors(&self, n: usize) -> Vec<DominantColor>;
+}
+
+impl DominantColorsExtRgba for Image<Rgba> {
+    /// Returns up to `n` dominant colors, the most common first. Pixels more than half
+    /// transparent are ignored unless all of them are. Images with fewer distinct colors return
+    /// fewer.
+    fn dominant_colors(&self, n: usize) -> Vec<DominantColor> {
+        if n == 0 || self.is_empty() {
+            return Vec::new();
+        }
+        let opaque: Vec<Rgba> = self.pixels().filter(|p| p.a >= 0.5).collect();
+        let pixels = match opaque.is_empty() {
+            true => self.pixels().collect(),
+            false => opaque,
+        };
+        let colors: Vec<Lab> = pixels.par_iter().map(Lab::from_rgba).collect();
+
+        let seeds = Image::from_data(pixels.len(), 1, pixels).unwrap();
+        let mut centers: Vec<Lab> = Palette::from_image(&seeds, n)
+            .colors()
+            .iter()
+            .map(|&[r, g, b, _]| Lab::from_rgba(&Rgba::from_rgba8([r, g, b, 255])))
+            .collect();
+
+        let stride = colors.len().div_ceil(MAX_SAMPLES).max(1);
+        let samples: Vec<Lab> = colors.iter().copied().step_by(stride).collect();
+        for _ in 0..MAX_ITERATIONS {
+            let assignments: Vec<usize> = samples
+                .par_iter()
+                .map(|color| nearest(&centers, color))
+                .collect();
+            let updated = cluster_means(&samples, &assignments, &centers);
+            let movement = updated
+                .iter()
+                .zip(&centers)
+                .map(|(a, b)| lab_distance(a, b).sqrt())
+                .fold(0.0, f32::max);
+            centers = updated;
+            if movement < CONVERGENCE {
+                break;
+            }
+        }
+
+        let assignments: Vec<usize> = colors
+            .par_iter()
+            .map(|color| nearest(&centers, color))
+            .collect();
+        let centers = cluster_means(&colors, &assignments, &centers);
+        let mut counts = vec![0usize; centers.len()];
+        for &cluster in &assignments {
+            counts[cluster] += 1;
+        }
+
+        let mut dominant: Vec<DominantColor> = centers
+            .iter()
+            .zip(&counts)
+            .filter(|&(_, &count)| count > 0)
+            .map(|(center, &count)| DominantColor {
+                color: center.to_rgba(1.0),
+                fraction: count as f32 / colors.len() as f32,
+            })
+            .collect();
+        dominant.sort_by(|a, b| b.fraction.total_cmp(&a.fraction));
+        dominant
+    }
+}
+
+/// Renders `colors` as a strip of swatches of the given size, from left to right, each as wide
+/// as its share of the total fraction.
+pub fn render_swatches(colors: &[DominantColor], (width, height): (usize, usize)) -> Image<Rgba> {
+    let total: f32 = colors.iter().map(|c| c.fraction).sum();
+    let mut strip = Image::solid(width, height, Rgba::new(0.0, 0.0, 0.0, 0.0));
+    let mut cumulative = 0.0;
+    let mut left = 0;
+    for swatch in colors {
+        cumulative += swatch.fraction;
+        let right = ((cumulative / total.max(f32::EPSILON)) * width as f32).round() as usize;
+        let right = right.min(width);
+        if right > left {
+            strip.paste((left, 0), &Image::solid(right - left, height, swatch.color));
+        }
+        left = left.max(right);
+    }
+    strip
+}
@@ -0,0 +1,430 @@
+//! Enhancement of badly lit images, recovering detail where a global tone curve cannot.
+//!
+//! Retinex models an image as reflectance times illumination. The illumination is estimated by
+//! blurring the image with a large Gaussian and divided out in the log domain, which evens out
+//! strong illumination gradients while keeping local contrast.
+//!
+//! Dehazing inverts the haze model I = J t + A (1 - t), estimating the transmission t with the
+//! dark channel prior of He, Sun and Tang, "Single Image Haze Removal Using Dark Channel Prior"
+//! (2009): in haze-free outdoor images, most patches contain a pixel that is dark in at least
+//! one color channel.
+//!
+//! Low-light enhancement follows LIME (Guo, Li and Ling, "LIME: Low-Light Image Enhancement via
+//! Illumination Map Estimation", 2017): the illumination is the brightest channel in the
+//! neighborhood of every pixel, refined to follow the structure of the scene, and divided out.
+//! Bright areas are lit already and barely change, and the noise brought up in dark areas is
+//! filtered away.
+
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Pixel, Rgba},
+};
+
+use crate::integral::IntegralImageExtLuma;
+use crate::linear_filters::LinearFilterExtLuma;
+use crate::nonlinear_filters::NonLinearFilterExtLuma;
+use crate::point_ops::PointOpsExtRgba;
+use crate::{Error, Result};
+
+/// Offset avoiding the logarithm of zero for black pixels.
+const LOG_OFFSET: f32 = 1.0 / 255.0;
+
+/// Parameters of multi-scale Retinex with color restoration, see [`EnhanceExtRgba::msrcr`].
+/// The defaults are the ones of Jobson, Rahman and Woodell, "A multiscale retinex for bridging
+/// the gap between color images and the human observation of scenes" (1997).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Msrcr {
+    /// Standard deviations of the illumination estimates, from local to global
+    pub sigmas: Vec<f32>,
+    /// Strength of the nonlinearity of the color restoration
+    pub alpha: f32,
+    /// Gain of the color restoration
+    pub beta: f32,
+    /// Fraction of the values saturated at each end when stretching the result to [0.0, 1.0]
+    pub clip: f32,
+}
+
+impl Default for Msrcr {
+    fn default() -> Self {
+        Self {
+            sigmas: vec![15.0, 80.0, 250.0],
+            alpha: 125.0,
+            beta: 46.0,
+            clip: 0.01,
+        }
+    }
+}
+
+/// Parameters of [`EnhanceExtRgba::dehaze_with`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dehaze {
+    /// Side length of the square patches of the dark channel, odd (even sizes are rounded up)
+    pub patch_size: usize,
+    /// Fraction of the haze to remove, slightly below 1.0 keeps distant objects looking distant
+    pub strength: f32,
+    /// Lower bound of the transmission, limiting the noise amplification in dense haze
+    pub min_transmission: f32,
+    /// Window radius of the guided filter refining the transmission along edges
+    pub refine_radius: usize,
+    /// Regularization of the guided filter, larger values smooth more across weak edges
+    pub refine_epsilon: f32,
+}
+
+impl Default for Dehaze {
+    fn default() -> Self {
+        Self {
+            patch_size: 15,
+            strength: 0.95,
+            min_transmission: 0.1,
+            refine_radius: 30,
+            refine_epsilon: 1e-3,
+        }
+    }
+}
+
+/// Extracts one channel of an RGBA image.
+fn channel(image: &Image<Rgba>, c: usize) -> Image<Luma> {
+    let (width, height) = image.dimensions();
+    let data = image.pixels().map(|p| Luma { l: p.channel(c) }).collect();
+    Image::from_data(width, height, data).unwrap()
+}
+
+/// Averages log(I) - log(I * G_sigma) over all scales: the log reflectance of the plane.
+fn retinex_plane(image: &Image<Luma>, sigmas: &[f32]) -> Vec<f32> {
+    let (width, height) = image.dimensions();
+    let mut reflectance = vec![0.0; width * height];
+    for &sigma in sigmas {
+        let illumination = image.clone().gaussian_blur(sigma);
+        for ((r, p), i) in reflectance
+            .iter_mut()
+            .zip(image.pixels())
+            .zip(illumination.pixels())
+        {
+            *r += ((p.l + LOG_OFFSET).ln() - (i.l + LOG_OFFSET).ln()) / sigmas.len() as f32;
+        }
+    }
+    reflectance
+}
+
+/// Returns the values at the fractions `clip` and `1 - clip` of the sorted values.
+fn percentiles(values: &[f32], clip: f32) -> (f32, f32) {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable_by(f32::total_cmp);
+    let last = sorted.len().saturating_sub(1);
+    let index = |q: f32| ((q * last as f32).round() as usize).min(last);
+    let clip = clip.clamp(0.0, 0.5);
+    (sorted[index(clip)], sorted[index(1.0 - clip)])
+}
+
+/// Linearly maps `[low, high]` to [0.0, 1.0], clamping everything outside.
+fn stretch(value: f32, (low, high): (f32, f32)) -> f32 {
+    ((value - low) / (high - low).max(f32::EPSILON)).clamp(0.0, 1.0)
+}
+
+/// Shared implementation for RGBA: stretches the per-channel results jointly, keeping alpha.
+fn compose(image: Image<Rgba>, channels: [Vec<f32>; 3], clip: f32) -> Image<Rgba> {
+    let all: Vec<f32> = channels.iter().flatten().copied().collect();
+    let range = percentiles(&all, clip);
+    let (width, height) = image.dimensions();
+    let data = image
+        .pixels()
+        .enumerate()
+        .map(|(i, p)| Rgba {
+            r: stretch(channels[0][i], range),
+            g: stretch(channels[1][i], range),
+            b: stretch(channels[2][i], range),
+            a: p.a,
+        })
+        .collect();
+    Image::from_data(width, height, data).unwrap()
+}
+
+/// Returns the minimum of `f(pixel)` over the patches around every pixel. Even patch sizes are
+/// rounded up.
+fn dark_channel(image: &Image<Rgba>, patch_size: usize, f: impl Fn(&Rgba) -> f32) -> Image<Luma> {
+    let (width, height) = image.dimensions();
+    let minima = image.pixels().map(|p| Luma { l: f(&p) }).collect();
+    // The rank filters only fail for even kernel sizes
+    Image::from_data(width, height, minima)
+        .unwrap()
+        .min_filter(patch_size | 1)
+        .unwrap()
+}
+
+/// Estimates the color of the haze: the brightest pixel among the 0.1% of pixels with the
+/// haziest dark channel.
+fn atmospheric_light(image: &Image<Rgba>, dark: &Image<Luma>) -> Rgba {
+    let mut order: Vec<(usize, f32)> = dark.pixels().map(|p| p.l).enumerate().collect();
+    order.sort_unstable_by(|a, b| b.1.total_cmp(&a.1));
+    let candidates = (order.len() / 1000).max(1);
+    let pixels: Vec<Rgba> = image.pixels().collect();
+    order[..candidates]
+        .iter()
+        .map(|&(i, _)| pixels[i])
+        .max_by(|a, b| (a.r + a.g + a.b).total_cmp(&(b.r + b.g + b.b)))
+        .unwrap_or(Rgba {
+            r: 1.0,
+            g: 1.0,
+            b: 1.0,
+            a: 1.0,
+        })
+}
+
+/// Shared implementation of low-light enhancement: divides the planes by the illumination,
+/// estimated from the brightest channel `brightest`, raised to `strength` and denoises them
+/// where they were dark.
+fn brighten(planes: Vec<Image<Luma>>, brightest: Image<Luma>, strength: f32) -> Vec<Vec<f32>> {
+    let illumination: Vec<f32> = guided(
+        &brightest.clone().max_filter(15).unwrap(),
+        &brightest,
+        15,
+        1e-3,
+    )
+    .pixels()
+    .map(|t| t.l.clamp(0.02, 1.0))
+    .collect();
+    let strength = strength.clamp(0.0, 1.0);
+    planes
+        .into_iter()
+        .map(|plane| {
+            let (width, height) = plane.dimensions();
+            let data = plane
+                .pixels()
+                .zip(&illumination)
+                .map(|(p, t)| Luma {
+                    l: (p.l / t.powf(strength)).clamp(0.0, 1.0),
+                })
+                .collect();
+            let enhanced = Image::from_data(width, height, data).unwrap();
+            let denoised = guided(&enhanced, &enhanced, 2, 0.01);
+            enhanced
+                .pixels()
+                .zip(denoised.pixels())
+                .zip(&illumination)
+                .map(|((e, d), t)| (t * e.l + (1.0 - t) * d.l).clamp(0.0, 1.0))
+                .collect()
+        })
+        .collect()
+}
+
+/// Shared implementation of the guided filter, see [`EnhanceExtLuma::guided_filter`], for
+/// a guide of the same dimensions as `input`.
+fn guided(input: &Image<Luma>, guide: &Image<Luma>, radius: usize, epsilon: f32) -> Image<Luma> {
+    let (width, height) = input.dimensions();
+    let image = |data: Vec<f32>| {
+        Image::from_data(
+            width,
+            height,
+            data.into_iter().map(|l| Luma { l }).collect(),
+        )
+        .unwrap()
+    };
+    let mean = |values: Vec<f32>| -> Vec<f32> {
+        image(values)
+            .box_blur_integral(radius)
+            .pixels()
+            .map(|p| p.l)
+            .collect()
+    };
+    let g: Vec<f32> = guide.pixels().map(|p| p.l).collect();
+    let p: Vec<f32> = input.pixels().map(|p| p.l).collect();
+
+    let mean_g = mean(g.clone());
+    let mean_p = mean(p.clone());
+    let mean_gg = mean(g.iter().map(|g| g * g).collect());
+    let mean_gp = mean(g.iter().zip(&p).map(|(g, p)| g * p).collect());
+    let (slopes, offsets): (Vec<f32>, Vec<f32>) = (0..width * height)
+        .map(|i| {
+            let variance = mean_gg[i] - mean_g[i] * mean_g[i];
+            let covariance = mean_gp[i] - mean_g[i] * mean_p[i];
+            let slope = covariance / (variance + epsilon);
+            (slope, mean_p[i] - slope * mean_g[i])
+        })
+        .unzip();
+    let (slopes, offsets) = (mean(slopes), mean(offsets));
+    image(
+        (0..width * height)
+            .map(|i| slopes[i] * g[i] + offsets[i])
+            .collect(),
+    )
+}
+
+/// Extension trait for [`glance_core::img::Image`] to enhance Luma images
+pub trait EnhanceExtLuma: Sized {
+    fn single_scale_retinex(self, sigma: f32) -> Self;
+    fn multi_scale_retinex(self, sigmas: &[f32]) -> Self;
+    fn guided_filter(self, guide: &Image<Luma>, radius: usize, epsilon: f32) -> Result<Self>;
+    fn enhance_low_light(self, strength: f32) -> Self;
+}
+
+/// Extension trait for [`glance_core::img::Image`] to enhance RGBA images
+pub trait EnhanceExtRgba {
+    fn single_scale_retinex(self, sigma: f32) -> Self;
+    fn multi_scale_retinex(self, sigmas: &[f32]) -> Self;
+    fn msrcr(self, params: &Msrcr) -> Self;
+    fn dehaze(self) -> Self;
+    fn dehaze_with(self, params: &Dehaze) -> Self;
+    fn enhance_low_light(self, strength: f32) -> Self;
+}
+
+impl EnhanceExtLuma for Image<Luma> {
+    /// Removes the illumination estimated with a Gaussian of standard deviation `sigma` and
+    /// stretches the log reflectance to [0.0, 1.0], saturating 1% at each end. Small sigmas
+    /// enhance edges, large ones preserve the tonal balance.
+    fn single_scale_retinex(self, sigma: f32) -> Self {
+        self.multi_scale_retinex(&[sigma])
+    }
+
+    /// Averages single scale Retinex over several scales, which combines the dynamic range
+    /// compression of small scales with the tonal rendition of large ones.
+    fn multi_scale_retinex(self, sigmas: &[f32]) -> Self {
+        let reflectance = retinex_plane(&self, sigmas);
+        let range = percentiles(&reflectance, 0.01);
+        let (width, height) = self.dimensions();
+        let data = reflectance
+            .into_iter()
+            .map(|r| Luma {
+                l: stretch(r, range),
+            })
+            .collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+
+    /// Edge-preserving smoothing that transfers the edges of `guide` to the image (He, Sun and
+    /// Tang, "Guided Image Filtering", 2010). Within every window of radius `radius` the output
+    /// is a linear function of the guide; `epsilon` limits the slope in flat regions. Runs in
+    /// constant time per pixel regardless of the radius. Fails with
+    /// [`Error::DimensionMismatch`] if the guide and the image have different dimensions.
+    fn guided_filter(self, guide: &Image<Luma>, radius: usize, epsilon: f32) -> Result<Self> {
+        if guide.dimensions() != self.dimensions() {
+            return Err(Error::DimensionMismatch {
+                expected: self.dimensions(),
+                actual: guide.dimensions(),
+            });
+        }
+        Ok(guided(&self, guide, radius, epsilon))
+    }
+
+    /// Brightens a dark capture by dividing out its estimated illumination raised to `strength`
+    /// in [0.0, 1.0], where 0.0 only denoises and 0.8 is a good start. Unlike gamma or
+    /// brightness it barely changes bright areas, and it denoises the dark ones.
+    fn enhance_low_light(self, strength: f32) -> Self {
+        let (width, height) = self.dimensions();
+        let plane = brighten(vec![self.clone()], self, strength).remove(0);
+        let data = plane.into_iter().map(|l| Luma { l }).collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+}
+
+impl EnhanceExtRgba for Image<Rgba> {
+    /// Applies [`EnhanceExtLuma::single_scale_retinex`] to every color channel. Colors tend
+    /// towards gray in large uniform areas, see [`EnhanceExtRgba::msrcr`].
+    fn single_scale_retinex(self, sigma: f32) -> Self {
+        self.multi_scale_retinex(&[sigma])
+    }
+
+    /// Applies [`EnhanceExtLuma::multi_scale_retinex`] to every color channel, with a joint
+    /// stretch of all channels.
+    fn multi_scale_retinex(self, sigmas: &[f32]) -> Self {
+        let channels = std::array::from_fn(|c| retinex_plane(&channel(&self, c), sigmas));
+        compose(self, channels, 0.01)
+    }
+
+    /// Multi-scale Retinex with color restoration: weights every channel by its share of the
+    /// pixel intensity, restoring the saturation that plain Retinex washes out.
+    fn msrcr(self, params: &Msrcr) -> Self {
+        let mut channels: [Vec<f32>; 3] =
+            std::array::from_fn(|c| retinex_plane(&channel(&self, c), &params.sigmas));
+        for (i, p) in self.pixels().enumerate() {
+            let sum = p.r + p.g + p.b + 3.0 * LOG_OFFSET;
+            for (c, values) in channels.iter_mut().enumerate() {
+                let value = p.channel(c) + LOG_OFFSET;
+                values[i] *= params.beta * ((params.alpha * value).ln() - sum.ln());
+            }
+        }
+        compose(self, channels, params.clip)
+    }
+
+    /// Removes haze with the default [`Dehaze`] parameters.
+    fn dehaze(self) -> Self {
+        self.dehaze_with(&Dehaze::default())
+    }
+
+    /// Removes haze with the dark channel prior: estimates the haze color and, from the dark
+    /// channel, how much of it covers every pixel, refines that transmission map along the
+    /// image edges with a guided filter and inverts the haze model. Alpha is kept.
+    fn dehaze_with(self, params: &Dehaze) -> Self {
+        let dark = dark_channel(&self, params.patch_size, |p| p.r.min(p.g).min(p.b));
+        let air = atmospheric_light(&self, &dark);
+        let (ar, ag, ab) = (
+            air.r.max(f32::EPSILON),
+            air.g.max(f32::EPSILON),
+            air.b.max(f32::EPSILON),
+        );
+        let normalized = dark_channel(&self, params.patch_size, |p| {
+            (p.r / ar).min(p.g / ag).min(p.b / ab)
+        });
+        let (width, height) = self.dimensions();
+        let transmission = Image::from_data(
+            width,
+            height,
+            normalized
+                .pixels()
+                .map(|d| Luma {
+                    l: 1.0 - params.strength * d.l,
+                })
+                .collect(),
+        )
+        .unwrap();
+        let guide = self.clone().grayscale();
+        let transmission = guided(
+            &transmission,
+            &guide,
+            params.refine_radius,
+            params.refine_epsilon,
+        );
+
+        let data = self
+            .pixels()
+            .zip(transmission.pixels())
+            .map(|(p, t)| {
+                let t = t.l.max(params.min_transmission);
+                let recover =
+                    |value: f32, light: f32| ((value - light) / t + light).clamp(0.0, 1.0);
+                Rgba {
+                    r: recover(p.r, air.r),
+                    g: recover(p.g, air.g),
+                    b: recover(p.b, air.b),
+                    a: p.a,
+                }
+            })
+            .collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+
+    /// Brightens a dark capture like [`EnhanceExtLuma::enhance_low_light`], estimating the
+    /// illumination from the brightest channel so that colors keep their hue. Alpha is kept.
+    fn enhance_low_light(self, strength: f32) -> Self {
+        let (width, height) = self.dimensions();
+        let brightest = self
+            .pixels()
+            .map(|p| Luma {
+                l: p.r.max(p.g).max(p.b),
+            })
+            .collect();
+        let brightest = Image::from_data(width, height, brightest).unwrap();
+        let planes = (0..3).map(|c| channel(&self, c)).collect();
+        let [r, g, b]: [Vec<f32>; 3] = brighten(planes, brightest, strength).try_into().unwrap();
+        let data = self
+            .pixels()
+            .enumerate()
+            .map(|(i, p)| Rgba {
+                r: r[i],
+                g: g[i],
+                b: b[i],
+                a: p.a,
+            })
+            .collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+}
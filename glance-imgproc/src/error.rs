@@ -6,6 +6,21 @@ pub type Result<T> = core::result::Result<T, Error>;
 pub enum Error {
     #[from]
     CoreError(glance_core::CoreError),
+    /// Kernels are centered on a pixel, so their sides must have an odd length.
+    KernelSizeEven { width: usize, height: usize },
+    /// Two images that are combined pixel by pixel differ in size.
+    DimensionMismatch {
+        expected: (usize, usize),
+        actual: (usize, usize),
+    },
+    /// The kernel is larger than the image along both axes.
+    ImageSmallerThanKernel {
+        image: (usize, usize),
+        kernel: (usize, usize),
+    },
+    /// The geometric transformation maps the image onto a line or a point (e.g. a scale of 0),
+    /// so it has no inverse to sample the source with.
+    SingularTransformation { matrix: [[f32; 3]; 2] },
 }
 
 impl core::fmt::Display for Error {
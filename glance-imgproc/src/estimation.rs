@@ -0,0 +1,192 @@
+//! Estimation of geometric transformations from point correspondences `(p, q)`, where `q` is
+//! the observed position of `p` after the transformation. The estimated models plug directly
+//! into the warping APIs, e.g. [`crate::affine::AffineTransformationsExt::affine`].
+
+use rand::{SeedableRng, rngs::StdRng, seq::index::sample};
+
+use crate::affine::AffineMatrix;
+use crate::geometry::{Homography, Point, distance, transform_affine, transform_homography};
+use crate::linalg::{invert_3x3, least_squares, mul_3x3};
+
+/// Estimates the affine transformation mapping every `p` onto its `q` in the least squares
+/// sense. Needs at least 3 non collinear correspondences, returns `None` otherwise.
+pub fn estimate_affine(correspondences: &[(Point, Point)]) -> Option<AffineMatrix> {
+    if correspondences.len() < 3 {
+        return None;
+    }
+
+    // Each correspondence gives one equation for each output coordinate
+    let mut a = Vec::with_capacity(correspondences.len() * 3);
+    let (mut bx, mut by) = (Vec::new(), Vec::new());
+    for &(p, q) in correspondences {
+        a.extend_from_slice(&[p.0 as f64, p.1 as f64, 1.0]);
+        bx.push(q.0 as f64);
+        by.push(q.1 as f64);
+    }
+    let row_x = least_squares(&a, &bx, 3)?;
+    let row_y = least_squares(&a, &by, 3)?;
+
+    Some([
+        [row_x[0] as f32, row_x[1] as f32, row_x[2] as f32],
+        [row_y[0] as f32, row_y[1] as f32, row_y[2] as f32],
+    ])
+}
+
+/// Returns the similarity transform moving the centroid of `points` to the origin and scaling
+/// their mean distance to √2, which conditions the homography equations.
+fn normalization(points: impl Iterator<Item = Point> + Clone) -> [[f64; 3]; 3] {
+    let n = points.clone().count() as f64;
+    let (cx, cy) = points
+        .clone()
+        .fold((0.0, 0.0), |(sx, sy), p| (sx + p.0 as f64, sy + p.1 as f64));
+    let (cx, cy) = (cx / n, cy / n);
+    let mean_distance = points
+        .map(|p| (p.0 as f64 - cx).hypot(p.1 as f64 - cy))
+        .sum::<f64>()
+        / n;
+    let s = if mean_distance > 0.0 {
+        std::f64::consts::SQRT_2 / mean_distance
+    } else {
+        1.0
+    };
+    [[s, 0.0, -s * cx], [0.0, s, -s * cy], [0.0, 0.0, 1.0]]
+}
+
+/// Estimates the homography mapping every `p` onto its `q` with the normalized direct linear
+/// transform. Needs at least 4 correspondences, no three of them collinear; returns `None`
+/// otherwise.
+pub fn estimate_homography(correspondences: &[(Point, Point)]) -> Option<Homography> {
+    if correspondences.len() < 4 {
+        return None;
+    }
+
+    let t_src = normalization(correspondences.iter().map(|c| c.0));
+    let t_dst = normalization(correspondences.iter().map(|c| c.1));
+    let apply = |t: &[[f64; 3]; 3], p: Point| {
+        (
+            t[0][0] * p.0 as f64 + t[0][2],
+            t[1][1] * p.1 as f64 + t[1][2],
+        )
+    };
+
+    // Solve for the 8 unknowns of the normalized homography with h[2][2] fixed to 1
+    let mut a = Vec::with_capacity(correspondences.len() * 16);
+    let mut b = Vec::with_capacity(correspondences.len() * 2);
+    for &(p, q) in correspondences {
+        let (x, y) = apply(&t_src, p);
+        let (u, v) = apply(&t_dst, q);
+        a.extend_from_slice(&[x, y, 1.0, 0.0, 0.0, 0.0, -u * x, -u * y]);
+        b.push(u);
+        a.extend_from_slice(&[0.0, 0.0, 0.0, x, y, 1.0, -v * x, -v * y]);
+        b.push(v);
+    }
+    let h = least_squares(&a, &b, 8)?;
+    let normalized = [[h[0], h[1], h[2]], [h[3], h[4], h[5]], [h[6], h[7], 1.0]];
+
+    let h = mul_3x3(&mul_3x3(&invert_3x3(&t_dst)?, &normalized), &t_src);
+    if h[2][2].abs() < 1e-12 {
+        return None;
+    }
+    Some(std::array::from_fn(|i| {
+        std::array::from_fn(|j| (h[i][j] / h[2][2]) as f32)
+    }))
+}
+
+/// Parameters of the RANSAC robust estimator.
+#[derive(Debug, Clone, Copy)]
+pub struct Ransac {
+    /// Number of random minimal samples to evaluate
+    pub iterations: usize,
+    /// Maximum reprojection error in pixels for a correspondence to count as an inlier
+    pub threshold: f32,
+    /// Seed of the random sampler, making results reproducible
+    pub seed: u64,
+}
+
+impl Default for Ransac {
+    fn default() -> Self {
+        Ransac {
+            iterations: 1000,
+            threshold: 3.0,
+            seed: 0,
+        }
+    }
+}
+
+/// A model estimated by RANSAC together with the indices of the correspondences it explains.
+#[derive(Debug, Clone)]
+pub struct RansacResult<T> {
+    pub model: T,
+    pub inliers: Vec<usize>,
+}
+
+fn ransac<T>(
+    correspondences: &[(Point, Point)],
+    params: Ransac,
+    sample_size: usize,
+    fit: fn(&[(Point, Point)]) -> Option<T>,
+    error: fn(&T, Point, Point) -> f32,
+) -> Option<RansacResult<T>> {
+    if correspondences.len() < sample_size {
+        return None;
+    }
+
+    let mut rng = StdRng::seed_from_u64(params.seed);
+    let inliers_of = |model: &T| -> Vec<usize> {
+        correspondences
+            .iter()
+            .enumerate()
+            .filter(|(_, (p, q))| error(model, *p, *q) <= params.threshold)
+            .map(|(i, _)| i)
+            .collect()
+    };
+
+    let mut best: Option<Vec<usize>> = None;
+    for _ in 0..params.iterations {
+        let subset: Vec<(Point, Point)> = sample(&mut rng, correspondences.len(), sample_size)
+            .iter()
+            .map(|i| correspondences[i])
+            .collect();
+        let Some(model) = fit(&subset) else {
+            continue;
+        };
+
+        let inliers = inliers_of(&model);
+        if best.as_ref().is_none_or(|b| inliers.len() > b.len()) {
+            best = Some(inliers);
+        }
+    }
+
+    // Refit on all inliers of the best hypothesis
+    let best = best?;
+    let subset: Vec<(Point, Point)> = best.iter().map(|&i| correspondences[i]).collect();
+    let model = fit(&subset)?;
+    let inliers = inliers_of(&model);
+    Some(RansacResult { model, inliers })
+}
+
+/// Robustly estimates an affine transformation in the presence of outliers (e.g. wrong
+/// feature matches). Returns `None` if no valid model could be fitted.
+pub fn ransac_affine(
+    correspondences: &[(Point, Point)],
+    params: Ransac,
+) -> Option<RansacResult<AffineMatrix>> {
+    ransac(correspondences, params, 3, estimate_affine, |m, p, q| {
+        distance(transform_affine(m, p), q)
+    })
+}
+
+/// Robustly estimates a homography in the presence of outliers (e.g. wrong feature matches).
+/// Returns `None` if no valid model could be fitted.
+pub fn ransac_homography(
+    correspondences: &[(Point, Point)],
+    params: Ransac,
+) -> Option<RansacResult<Homography>> {
+    ransac(
+        correspondences,
+        params,
+        4,
+        estimate_homography,
+        |h, p, q| distance(transform_homography(h, p), q),
+    )
+}
@@ -0,0 +1,163 @@
+//! Keypoints and binary feature descriptors.
+
+use glance_core::img::{Image, Rect, pixel::Luma};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::geometry::Point;
+use crate::integral::{IntegralImage, IntegralImageExtLuma};
+
+/// An interest point found by a detector.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keypoint {
+    /// Position in (sub-)pixel coordinates
+    pub position: Point,
+    /// Diameter of the meaningful neighbourhood in pixels, 0.0 if unknown
+    pub size: f32,
+    /// Dominant orientation in degrees, counter-clockwise on screen (y down)
+    pub angle: f32,
+}
+
+impl Keypoint {
+    /// Creates an upright keypoint of unknown size at (x, y).
+    pub fn new(x: f32, y: f32) -> Self {
+        Keypoint {
+            position: (x, y),
+            size: 0.0,
+            angle: 0.0,
+        }
+    }
+}
+
+/// A binary descriptor, compared with the Hamming distance.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BinaryDescriptor {
+    /// Packed test results, bit `i` of the descriptor is bit `i % 64` of word `i / 64`
+    pub words: Vec<u64>,
+}
+
+impl BinaryDescriptor {
+    /// Returns the number of differing bits between two descriptors of the same length.
+    pub fn hamming(&self, other: &BinaryDescriptor) -> u32 {
+        self.words
+            .iter()
+            .zip(&other.words)
+            .map(|(a, b)| (a ^ b).count_ones())
+            .sum()
+    }
+}
+
+/// Half size of the box each test point is smoothed over (a 5x5 box), making the tests robust
+/// to noise without blurring the whole image.
+const SMOOTHING_RADIUS: isize = 2;
+
+/// Parameters of the BRIEF (Binary Robust Independent Elementary Features) descriptor.
+///
+/// Each bit compares the smoothed intensity at two points of a random pattern around the
+/// keypoint. If `oriented` is true the pattern is rotated by the keypoint angle (steered BRIEF,
+/// as used by ORB), making the descriptor rotation invariant given a good angle.
+#[derive(Debug, Clone, Copy)]
+pub struct Brief {
+    /// Side length of the square patch the test points are drawn from
+    pub patch_size: usize,
+    /// Number of binary tests, rounded up to a multiple of 64
+    pub bits: usize,
+    /// Rotate the test pattern by the keypoint angle
+    pub oriented: bool,
+    /// Seed of the test pattern. Descriptors are only comparable when computed with the same seed
+    pub seed: u64,
+}
+
+impl Default for Brief {
+    fn default() -> Self {
+        Brief {
+            patch_size: 31,
+            bits: 256,
+            oriented: false,
+            seed: 0,
+        }
+    }
+}
+
+impl Brief {
+    /// Returns the test point pairs, drawn from an isotropic Gaussian with a standard deviation
+    /// of 1/5 of the patch size and clipped to the patch.
+    fn pattern(&self) -> Vec<(Point, Point)> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let half = (self.patch_size / 2) as f32;
+        let sigma = self.patch_size as f32 / 5.0;
+        let mut gaussian = || {
+            // Box-Muller transform
+            let (u, v): (f32, f32) = (rng.random_range(f32::EPSILON..1.0), rng.random());
+            let value = (-2.0 * u.ln()).sqrt() * (std::f32::consts::TAU * v).cos() * sigma;
+            value.round().clamp(-half, half)
+        };
+
+        (0..self.bits.div_ceil(64) * 64)
+            .map(|_| ((gaussian(), gaussian()), (gaussian(), gaussian())))
+            .collect()
+    }
+
+    /// Computes a descriptor for every keypoint. Keypoints whose (rotated) patch does not fit
+    /// inside the image get `None`, so the output stays aligned with `keypoints`.
+    pub fn describe(
+        &self,
+        image: &Image<Luma>,
+        keypoints: &[Keypoint],
+    ) -> Vec<Option<BinaryDescriptor>> {
+        let integral = image.integral_image();
+        let pattern = self.pattern();
+
+        keypoints
+            .iter()
+            .map(|keypoint| self.describe_one(&integral, &pattern, keypoint))
+            .collect()
+    }
+
+    fn describe_one(
+        &self,
+        integral: &IntegralImage,
+        pattern: &[(Point, Point)],
+        keypoint: &Keypoint,
+    ) -> Option<BinaryDescriptor> {
+        let (width, height) = integral.dimensions();
+        let (sin, cos) = if self.oriented {
+            keypoint.angle.to_radians().sin_cos()
+        } else {
+            (0.0, 1.0)
+        };
+        let (kx, ky) = (
+            keypoint.position.0.round() as isize,
+            keypoint.position.1.round() as isize,
+        );
+
+        // The rotated patch must fit, including the smoothing box
+        let reach =
+            (self.patch_size / 2) as f32 * (sin.abs() + cos.abs()) + SMOOTHING_RADIUS as f32;
+        let reach = reach.ceil() as isize;
+        if kx < reach || ky < reach || kx + reach >= width as isize || ky + reach >= height as isize
+        {
+            return None;
+        }
+
+        let smoothed = |(x, y): (f32, f32)| {
+            // Same rotation convention as the affine transforms
+            let px = kx + (x * cos + y * sin).round() as isize;
+            let py = ky + (-x * sin + y * cos).round() as isize;
+            let side = (2 * SMOOTHING_RADIUS + 1) as usize;
+            integral.sum_region(Rect::new(
+                (px - SMOOTHING_RADIUS) as usize,
+                (py - SMOOTHING_RADIUS) as usize,
+                side,
+                side,
+            ))
+        };
+
+        let mut words = vec![0u64; pattern.len() / 64];
+        for (i, &(a, b)) in pattern.iter().enumerate() {
+            if smoothed(a) < smoothed(b) {
+                words[i / 64] |= 1 << (i % 64);
+            }
+        }
+        Some(BinaryDescriptor { words })
+    }
+}
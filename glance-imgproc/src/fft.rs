@@ -0,0 +1,130 @@
+//! Radix-2 fast Fourier transforms over complex buffers, used for frequency domain filtering.
+
+use std::ops::{Add, Mul, Sub};
+
+/// A complex number in double precision.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    /// Creates a new complex number.
+    pub fn new(re: f64, im: f64) -> Self {
+        Complex { re, im }
+    }
+
+    /// Returns the complex conjugate.
+    pub fn conj(&self) -> Self {
+        Complex::new(self.re, -self.im)
+    }
+
+    /// Returns the magnitude (modulus).
+    pub fn norm(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    /// Returns the phase angle in radians.
+    pub fn arg(&self) -> f64 {
+        self.im.atan2(self.re)
+    }
+
+    /// Creates a complex number from its magnitude and phase angle in radians.
+    pub fn from_polar(norm: f64, arg: f64) -> Self {
+        Complex::new(norm * arg.cos(), norm * arg.sin())
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+
+    fn add(self, other: Complex) -> Complex {
+        Complex::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Complex;
+
+    fn sub(self, other: Complex) -> Complex {
+        Complex::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Complex;
+
+    fn mul(self, other: Complex) -> Complex {
+        Complex::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+/// In-place 1D FFT. The length of `data` must be a power of two.
+/// The inverse transform is scaled by `1 / len`, so a forward and inverse pass round trip.
+pub fn fft(data: &mut [Complex], inverse: bool) {
+    let n = data.len();
+    if !n.is_power_of_two() {
+        panic!("FFT length must be a power of two, got {n}");
+    }
+
+    // Bit reversal permutation
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            data.swap(i, j);
+        }
+    }
+
+    // Iterative Cooley-Tukey butterflies
+    let sign = if inverse { 1.0 } else { -1.0 };
+    let mut len = 2;
+    while len <= n {
+        let step = Complex::from_polar(1.0, sign * 2.0 * std::f64::consts::PI / len as f64);
+        for start in (0..n).step_by(len) {
+            let mut twiddle = Complex::new(1.0, 0.0);
+            for k in 0..len / 2 {
+                let even = data[start + k];
+                let odd = data[start + k + len / 2] * twiddle;
+                data[start + k] = even + odd;
+                data[start + k + len / 2] = even - odd;
+                twiddle = twiddle * step;
+            }
+        }
+        len <<= 1;
+    }
+
+    if inverse {
+        let scale = 1.0 / n as f64;
+        data.iter_mut().for_each(|c| {
+            c.re *= scale;
+            c.im *= scale;
+        });
+    }
+}
+
+/// In-place 2D FFT of a row-major `width` x `height` buffer. Both dimensions must be powers
+/// of two.
+pub fn fft_2d(data: &mut [Complex], width: usize, height: usize, inverse: bool) {
+    data.chunks_mut(width).for_each(|row| fft(row, inverse));
+
+    let mut column = vec![Complex::default(); height];
+    for x in 0..width {
+        for y in 0..height {
+            column[y] = data[y * width + x];
+        }
+        fft(&mut column, inverse);
+        for y in 0..height {
+            data[y * width + x] = column[y];
+        }
+    }
+}
@@ -0,0 +1,267 @@
+//! Point based geometry shared by the estimation and shape analysis routines.
+
+use crate::affine::AffineMatrix;
+
+/// A point in (sub-)pixel coordinates (x, y).
+pub type Point = (f32, f32);
+
+/// A 3x3 projective transformation (homography) in row-major order, mapping (x, y) to
+/// ((h[0] · p) / (h[2] · p), (h[1] · p) / (h[2] · p)) with p = (x, y, 1).
+pub type Homography = [[f32; 3]; 3];
+
+/// Applies an affine transformation to a point.
+pub fn transform_affine(m: &AffineMatrix, p: Point) -> Point {
+    (
+        m[0][0] * p.0 + m[0][1] * p.1 + m[0][2],
+        m[1][0] * p.0 + m[1][1] * p.1 + m[1][2],
+    )
+}
+
+/// Applies a homography to a point. Points mapped to infinity yield non finite coordinates.
+pub fn transform_homography(h: &Homography, p: Point) -> Point {
+    let w = h[2][0] * p.0 + h[2][1] * p.1 + h[2][2];
+    (
+        (h[0][0] * p.0 + h[0][1] * p.1 + h[0][2]) / w,
+        (h[1][0] * p.0 + h[1][1] * p.1 + h[1][2]) / w,
+    )
+}
+
+/// Euclidean distance between two points.
+pub fn distance(a: Point, b: Point) -> f32 {
+    (a.0 - b.0).hypot(a.1 - b.1)
+}
+
+/// A rectangle rotated about its center, e.g. the tight bounding box of a tilted object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RotatedRect {
+    /// Center of the rectangle
+    pub center: Point,
+    /// Size (width, height), where the width runs along `angle`
+    pub size: (f32, f32),
+    /// Rotation of the width side in degrees in [0, 180), counter-clockwise on screen (y down)
+    pub angle: f32,
+}
+
+impl RotatedRect {
+    /// Returns the area of the rectangle.
+    pub fn area(&self) -> f32 {
+        self.size.0 * self.size.1
+    }
+
+    /// Returns the four corners, going around the rectangle.
+    pub fn corners(&self) -> [Point; 4] {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        // Width and height directions on screen, where y points down
+        let u = (cos * self.size.0 / 2.0, -sin * self.size.0 / 2.0);
+        let v = (sin * self.size.1 / 2.0, cos * self.size.1 / 2.0);
+        let (cx, cy) = self.center;
+        [
+            (cx - u.0 - v.0, cy - u.1 - v.1),
+            (cx + u.0 - v.0, cy + u.1 - v.1),
+            (cx + u.0 + v.0, cy + u.1 + v.1),
+            (cx - u.0 + v.0, cy - u.1 + v.1),
+        ]
+    }
+}
+
+/// Z component of the cross product of (a - o) and (b - o).
+fn cross(o: Point, a: Point, b: Point) -> f32 {
+    (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0)
+}
+
+/// Returns the convex hull of `points` using Andrew's monotone chain, starting at the leftmost
+/// point and going around the hull (clockwise on screen, where y points down). Collinear points
+/// on the hull edges are dropped.
+pub fn convex_hull(points: &[Point]) -> Vec<Point> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let chain = |points: &mut dyn Iterator<Item = &Point>| {
+        let mut chain: Vec<Point> = Vec::new();
+        for &p in points {
+            while chain.len() >= 2
+                && cross(chain[chain.len() - 2], chain[chain.len() - 1], p) <= 0.0
+            {
+                chain.pop();
+            }
+            chain.push(p);
+        }
+        // The last point starts the other chain
+        chain.pop();
+        chain
+    };
+
+    let mut hull = chain(&mut sorted.iter());
+    hull.extend(chain(&mut sorted.iter().rev()));
+    hull
+}
+
+/// Returns the rotated rectangle of minimum area containing all `points`, found with rotating
+/// calipers over the edges of the convex hull. Returns None if there are no points.
+pub fn min_area_rect(points: &[Point]) -> Option<RotatedRect> {
+    let hull = convex_hull(points);
+    match hull.len() {
+        0 => return None,
+        1 => {
+            return Some(RotatedRect {
+                center: hull[0],
+                size: (0.0, 0.0),
+                angle: 0.0,
+            });
+        }
+        _ => {}
+    }
+
+    let mut best: Option<RotatedRect> = None;
+    for i in 0..hull.len() {
+        let (a, b) = (hull[i], hull[(i + 1) % hull.len()]);
+        let length = distance(a, b);
+        let u = ((b.0 - a.0) / length, (b.1 - a.1) / length);
+        let v = (-u.1, u.0);
+
+        let (mut min_u, mut max_u, mut min_v, mut max_v) = (f32::MAX, f32::MIN, f32::MAX, f32::MIN);
+        for p in &hull {
+            let (pu, pv) = (p.0 * u.0 + p.1 * u.1, p.0 * v.0 + p.1 * v.1);
+            (min_u, max_u) = (min_u.min(pu), max_u.max(pu));
+            (min_v, max_v) = (min_v.min(pv), max_v.max(pv));
+        }
+
+        let size = (max_u - min_u, max_v - min_v);
+        if best.is_none_or(|r| size.0 * size.1 < r.area()) {
+            let (cu, cv) = ((min_u + max_u) / 2.0, (min_v + max_v) / 2.0);
+            best = Some(RotatedRect {
+                center: (cu * u.0 + cv * v.0, cu * u.1 + cv * v.1),
+                size,
+                // Screen y points down, so the angle is measured against -y
+                angle: (-u.1).atan2(u.0).to_degrees().rem_euclid(180.0),
+            });
+        }
+    }
+    best
+}
+
+/// Returns the smallest circle (center, radius) containing all `points`, using Welzl's
+/// algorithm on a deterministically shuffled copy of the points. Returns None if there are no
+/// points.
+pub fn min_enclosing_circle(points: &[Point]) -> Option<(Point, f32)> {
+    use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+
+    let mut points: Vec<(f64, f64)> = points.iter().map(|p| (p.0 as f64, p.1 as f64)).collect();
+    points.shuffle(&mut StdRng::seed_from_u64(0));
+
+    let first = *points.first()?;
+    let inside = |c: (f64, f64), r: f64, p: (f64, f64)| {
+        (p.0 - c.0).hypot(p.1 - c.1) <= r + 1e-7 * r.max(1.0)
+    };
+    let diameter = |a: (f64, f64), b: (f64, f64)| {
+        (
+            ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0),
+            (a.0 - b.0).hypot(a.1 - b.1) / 2.0,
+        )
+    };
+
+    let (mut center, mut radius) = (first, 0.0);
+    for i in 1..points.len() {
+        if inside(center, radius, points[i]) {
+            continue;
+        }
+        (center, radius) = (points[i], 0.0);
+        for j in 0..i {
+            if inside(center, radius, points[j]) {
+                continue;
+            }
+            (center, radius) = diameter(points[i], points[j]);
+            for k in 0..j {
+                if inside(center, radius, points[k]) {
+                    continue;
+                }
+                (center, radius) =
+                    circumcircle(points[i], points[j], points[k]).unwrap_or_else(|| {
+                        // Collinear points, the circle spans the two farthest ones
+                        [
+                            (points[i], points[j]),
+                            (points[i], points[k]),
+                            (points[j], points[k]),
+                        ]
+                        .into_iter()
+                        .map(|(a, b)| diameter(a, b))
+                        .max_by(|a, b| a.1.total_cmp(&b.1))
+                        .unwrap()
+                    });
+            }
+        }
+    }
+
+    Some(((center.0 as f32, center.1 as f32), radius as f32))
+}
+
+/// Circle through three points, or None if they are collinear.
+fn circumcircle(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> Option<((f64, f64), f64)> {
+    let (bx, by) = (b.0 - a.0, b.1 - a.1);
+    let (cx, cy) = (c.0 - a.0, c.1 - a.1);
+    let d = 2.0 * (bx * cy - by * cx);
+    if d.abs() < 1e-12 {
+        return None;
+    }
+    let (b2, c2) = (bx * bx + by * by, cx * cx + cy * cy);
+    let (ux, uy) = ((cy * b2 - by * c2) / d, (bx * c2 - cx * b2) / d);
+    Some(((a.0 + ux, a.1 + uy), ux.hypot(uy)))
+}
+
+/// Returns the distance of `p` to the segment from `a` to `b`.
+fn segment_distance(p: Point, a: Point, b: Point) -> f32 {
+    let length = distance(a, b);
+    if length == 0.0 {
+        return distance(p, a);
+    }
+    let t = ((p.0 - a.0) * (b.0 - a.0) + (p.1 - a.1) * (b.1 - a.1)) / (length * length);
+    match t {
+        // The cross product is exactly zero for collinear points
+        0.0..=1.0 => cross(a, b, p).abs() / length,
+        ..0.0 => distance(p, a),
+        _ => distance(p, b),
+    }
+}
+
+/// Simplifies the open polyline through `points` with the Douglas-Peucker algorithm, keeping its
+/// end points and every vertex farther than `epsilon` from the simplified line.
+fn simplify_polyline(points: &[Point], epsilon: f32, simplified: &mut Vec<Point>) {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let farthest = (1..points.len() - 1)
+        .map(|i| (i, segment_distance(points[i], first, last)))
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+    match farthest {
+        Some((i, d)) if d > epsilon => {
+            simplify_polyline(&points[..=i], epsilon, simplified);
+            simplified.pop();
+            simplify_polyline(&points[i..], epsilon, simplified);
+        }
+        _ => simplified.extend([first, last]),
+    }
+}
+
+/// Simplifies a closed polygon with the Douglas-Peucker algorithm: vertices within `epsilon` of
+/// the simplified outline are dropped. With an `epsilon` of 0.0 only collinear vertices are
+/// removed. The polygon is split at its first vertex and the vertex farthest from it, which are
+/// both kept.
+pub fn simplify_polygon(points: &[Point], epsilon: f32) -> Vec<Point> {
+    if points.len() < 4 {
+        return points.to_vec();
+    }
+    let far = (1..points.len())
+        .max_by(|&a, &b| distance(points[0], points[a]).total_cmp(&distance(points[0], points[b])))
+        .unwrap();
+    let mut closed = points.to_vec();
+    closed.push(points[0]);
+    let mut simplified = Vec::new();
+    simplify_polyline(&closed[..=far], epsilon, &mut simplified);
+    simplified.pop();
+    simplify_polyline(&closed[far..], epsilon, &mut simplified);
+    // The last point closes the polygon and repeats the first
+    simplified.pop();
+    simplified
+}
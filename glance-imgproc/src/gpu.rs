@@ -0,0 +1,485 @@
+//! Optional GPU execution backend (feature `gpu`). Convolution, morphology and warps run as
+//! wgpu compute shaders on a [`GpuImage`], which stays in GPU memory between operations so
+//! chains of filters only pay for one upload and one download.
+//!
+//! The methods mirror the CPU extension traits and follow the same conventions, so results
+//! match the CPU implementation up to floating point error.
+//!
+//! ```no_run
+//! use glance_core::img::{Image, pixel::Rgba};
+//! use glance_imgproc::gpu::GpuContext;
+//! use glance_imgproc::kernels;
+//! use glance_imgproc::linear_filters::BorderMode;
+//!
+//! let image = Image::<Rgba>::open("input.png")?;
+//! let gpu = GpuContext::new()?;
+//! let blurred = gpu
+//!     .upload(&image)
+//!     .gaussian_blur(2.0)
+//!     .dilate(&kernels::box_kernel(3), BorderMode::Replicate)?
+//!     .download()?;
+//! # Ok::<(), glance_imgproc::Error>(())
+//! ```
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use glance_core::CoreError;
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Pixel},
+};
+use wgpu::util::DeviceExt;
+
+use crate::affine::{self, AffineMatrix, Interpolation, invert_affine};
+use crate::kernels;
+use crate::linear_filters::{BorderMode, check_kernel};
+use crate::{Error, Result};
+
+/// Uniform parameters shared by all shaders, see `Params` in gpu.wgsl.
+struct Params {
+    src_dimensions: (usize, usize),
+    dst_dimensions: (usize, usize),
+    channels: usize,
+    kernel_dimensions: (usize, usize),
+    border: BorderMode,
+    interpolation: Interpolation,
+    inverse: AffineMatrix,
+}
+
+impl Params {
+    fn to_bytes(&self) -> Vec<u8> {
+        let (border, constant) = match self.border {
+            BorderMode::Constant(value) => (0, value),
+            BorderMode::Replicate => (1, 0.0),
+            BorderMode::Reflect => (2, 0.0),
+            BorderMode::Wrap => (3, 0.0),
+        };
+        let interpolation = match self.interpolation {
+            Interpolation::Nearest => 0,
+            Interpolation::Bilinear => 1,
+            Interpolation::Bicubic => 2,
+        };
+        let integers = [
+            self.src_dimensions.0,
+            self.src_dimensions.1,
+            self.dst_dimensions.0,
+            self.dst_dimensions.1,
+            self.channels,
+            self.kernel_dimensions.0,
+            self.kernel_dimensions.1,
+            border,
+        ];
+        let m = self.inverse;
+
+        let mut bytes: Vec<u8> = integers
+            .iter()
+            .flat_map(|&v| (v as u32).to_le_bytes())
+            .collect();
+        bytes.extend(constant.to_le_bytes());
+        bytes.extend((interpolation as u32).to_le_bytes());
+        for value in [m[0][0], m[0][1], m[0][2], m[1][0], m[1][1], m[1][2]] {
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+/// The compute pipelines, one per shader entry point.
+struct Pipelines {
+    convolve: wgpu::ComputePipeline,
+    dilate: wgpu::ComputePipeline,
+    erode: wgpu::ComputePipeline,
+    warp: wgpu::ComputePipeline,
+}
+
+struct Inner {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    layout: wgpu::BindGroupLayout,
+    pipelines: Pipelines,
+}
+
+/// A GPU device with the compiled shaders. Cheap to clone, clones share the device.
+#[derive(Clone)]
+pub struct GpuContext {
+    inner: Arc<Inner>,
+}
+
+impl std::fmt::Debug for GpuContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GpuContext").finish_non_exhaustive()
+    }
+}
+
+impl GpuContext {
+    /// Opens the default GPU adapter and compiles the shaders. Returns
+    /// [`CoreError::Unsupported`] if no adapter with compute support is available.
+    pub fn new() -> Result<Self> {
+        pollster::block_on(Self::request())
+    }
+
+    async fn request() -> Result<Self> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| CoreError::Unsupported(format!("No GPU adapter available: {e}")))?;
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor {
+                label: Some("glance"),
+                required_limits: adapter.limits(),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| CoreError::Unsupported(format!("Cannot open the GPU device: {e}")))?;
+
+        let storage = |binding: u32, read_only: bool| wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("glance"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                storage(1, true),
+                storage(2, true),
+                storage(3, false),
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("glance"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("glance"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("gpu.wgsl").into()),
+        });
+        let pipeline = |entry_point: &str| {
+            device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&pipeline_layout),
+                module: &module,
+                entry_point: Some(entry_point),
+                compilation_options: Default::default(),
+                cache: None,
+            })
+        };
+        let pipelines = Pipelines {
+            convolve: pipeline("convolve"),
+            dilate: pipeline("dilate"),
+            erode: pipeline("erode"),
+            warp: pipeline("warp"),
+        };
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                device,
+                queue,
+                layout,
+                pipelines,
+            }),
+        })
+    }
+
+    /// Creates a storage buffer holding `values`. Empty buffers get one padding value, as wgpu
+    /// does not bind zero sized buffers.
+    fn buffer(&self, values: &[f32], usage: wgpu::BufferUsages) -> wgpu::Buffer {
+        let contents: Vec<u8> = if values.is_empty() {
+            vec![0; 4]
+        } else {
+            values.iter().flat_map(|v| v.to_le_bytes()).collect()
+        };
+        self.inner
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: &contents,
+                usage,
+            })
+    }
+
+    /// Copies `image` into GPU memory.
+    pub fn upload<P: Pixel>(&self, image: &Image<P>) -> GpuImage<P> {
+        let (width, height) = image.dimensions();
+        let values: Vec<f32> = image
+            .pixels()
+            .flat_map(|p| (0..P::channel_count()).map(move |c| p.channel(c)))
+            .collect();
+        GpuImage {
+            context: self.clone(),
+            buffer: self.buffer(
+                &values,
+                wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            ),
+            width,
+            height,
+            _pixel: PhantomData,
+        }
+    }
+}
+
+/// An image in GPU memory, created by [`GpuContext::upload`]. Operations return new images
+/// and leave the input untouched.
+#[derive(Debug)]
+pub struct GpuImage<P: Pixel> {
+    context: GpuContext,
+    buffer: wgpu::Buffer,
+    width: usize,
+    height: usize,
+    _pixel: PhantomData<P>,
+}
+
+impl<P: Pixel> GpuImage<P> {
+    /// Returns the dimensions of the image as a tuple (width, height).
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Copies the image back into CPU memory, waiting for pending operations to finish.
+    pub fn download(&self) -> Result<Image<P>> {
+        let inner = &self.context.inner;
+        let size = self.buffer.size();
+        let staging = inner.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("download"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let mut encoder = inner.device.create_command_encoder(&Default::default());
+        encoder.copy_buffer_to_buffer(&self.buffer, 0, &staging, 0, size);
+        inner.queue.submit([encoder.finish()]);
+
+        let slice = staging.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        let readback_error = |e: &dyn std::fmt::Display| {
+            CoreError::InvalidData(format!("Reading the image back from the GPU failed: {e}"))
+        };
+        inner
+            .device
+            .poll(wgpu::PollType::Wait)
+            .map_err(|e| readback_error(&e))?;
+        receiver
+            .recv()
+            .map_err(|e| readback_error(&e))?
+            .map_err(|e| readback_error(&e))?;
+
+        let channels = P::channel_count();
+        let data = {
+            let bytes = slice.get_mapped_range();
+            bytes
+                .chunks_exact(4 * channels)
+                .take(self.width * self.height)
+                .map(|chunk| {
+                    let mut pixel = P::new();
+                    for (c, value) in chunk.chunks_exact(4).enumerate() {
+                        pixel.set_channel(c, f32::from_le_bytes(value.try_into().unwrap()));
+                    }
+                    pixel
+                })
+                .collect()
+        };
+        staging.unmap();
+        Ok(Image::from_data(self.width, self.height, data)?)
+    }
+
+    /// Runs one shader over an output of `params.dst_dimensions`.
+    fn dispatch(&self, pipeline: &wgpu::ComputePipeline, params: Params, weights: &[f32]) -> Self {
+        let context = &self.context;
+        let inner = &context.inner;
+        let (width, height) = params.dst_dimensions;
+        let uniform = inner
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("params"),
+                contents: &params.to_bytes(),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let weights = context.buffer(weights, wgpu::BufferUsages::STORAGE);
+        let output = context.buffer(
+            &vec![0.0; width * height * P::channel_count()],
+            wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+        );
+        let bind_group = inner.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &inner.layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: uniform.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: self.buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: weights.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: output.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = inner.device.create_command_encoder(&Default::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&Default::default());
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(width.div_ceil(8) as u32, height.div_ceil(8) as u32, 1);
+        }
+        inner.queue.submit([encoder.finish()]);
+
+        GpuImage {
+            context: context.clone(),
+            buffer: output,
+            width,
+            height,
+            _pixel: PhantomData,
+        }
+    }
+
+    /// Parameters of a neighbourhood operation with `kernel` that keeps the dimensions.
+    fn kernel_params(&self, kernel: &Image<Luma>, border: BorderMode) -> (Params, Vec<f32>) {
+        let (k_width, k_height) = kernel.dimensions();
+        let params = Params {
+            src_dimensions: self.dimensions(),
+            dst_dimensions: self.dimensions(),
+            channels: P::channel_count(),
+            kernel_dimensions: (k_width, k_height),
+            border,
+            interpolation: Interpolation::Nearest,
+            inverse: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+        };
+        (params, kernel.pixels().map(|p| p.l).collect())
+    }
+
+    /// Convolves every channel with the given kernel, see
+    /// [`crate::linear_filters::LinearFilterExtLuma::convolve_2d`].
+    pub fn convolve_2d(&self, kernel: &Image<Luma>, border: BorderMode) -> Result<Self> {
+        check_kernel(self.dimensions(), kernel.dimensions())?;
+        Ok(self.convolve_unchecked(kernel, border))
+    }
+
+    fn convolve_unchecked(&self, kernel: &Image<Luma>, border: BorderMode) -> Self {
+        let (params, weights) = self.kernel_params(kernel, border);
+        self.dispatch(&self.context.inner.pipelines.convolve, params, &weights)
+    }
+
+    /// Blurs every channel with a Gaussian of standard deviation `sigma` in two 1D passes with
+    /// replicated borders, see [`crate::linear_filters::LinearFilterExtLuma::gaussian_blur`].
+    pub fn gaussian_blur(&self, sigma: f32) -> Self {
+        let weights = if sigma > 0.0 {
+            kernels::gaussian_1d(sigma)
+        } else {
+            vec![1.0]
+        };
+        let row = Image::from_data(
+            weights.len(),
+            1,
+            weights.iter().map(|&l| Luma { l }).collect(),
+        )
+        .unwrap();
+        let column = Image::from_data(
+            1,
+            weights.len(),
+            weights.into_iter().map(|l| Luma { l }).collect(),
+        )
+        .unwrap();
+        self.convolve_unchecked(&row, BorderMode::Replicate)
+            .convolve_unchecked(&column, BorderMode::Replicate)
+    }
+
+    /// Dilates every channel: each value becomes the maximum over the non-zero kernel entries
+    /// centered on it, see [`crate::nonlinear_filters::NonLinearFilterExtLuma::dilate`].
+    pub fn dilate(&self, kernel: &Image<Luma>, border: BorderMode) -> Result<Self> {
+        check_kernel(self.dimensions(), kernel.dimensions())?;
+        let (params, weights) = self.kernel_params(kernel, border);
+        Ok(self.dispatch(&self.context.inner.pipelines.dilate, params, &weights))
+    }
+
+    /// Erodes every channel: each value becomes the minimum over the non-zero kernel entries
+    /// centered on it, see [`crate::nonlinear_filters::NonLinearFilterExtLuma::erode`].
+    pub fn erode(&self, kernel: &Image<Luma>, border: BorderMode) -> Result<Self> {
+        check_kernel(self.dimensions(), kernel.dimensions())?;
+        let (params, weights) = self.kernel_params(kernel, border);
+        Ok(self.dispatch(&self.context.inner.pipelines.erode, params, &weights))
+    }
+
+    /// Renders the `dimensions` sized output of `matrix` (mapping source to destination
+    /// coordinates) applied to the image. Uncovered areas are set to 0.0 in every channel.
+    /// Fails with [`Error::SingularTransformation`] if the matrix is not invertible.
+    pub fn warp(
+        &self,
+        matrix: AffineMatrix,
+        dimensions: (usize, usize),
+        interpolation: Interpolation,
+    ) -> Result<Self> {
+        let inverse = invert_affine(&matrix).ok_or(Error::SingularTransformation { matrix })?;
+        let params = Params {
+            src_dimensions: self.dimensions(),
+            dst_dimensions: dimensions,
+            channels: P::channel_count(),
+            kernel_dimensions: (0, 0),
+            border: BorderMode::Constant(0.0),
+            interpolation,
+            inverse,
+        };
+        Ok(self.dispatch(&self.context.inner.pipelines.warp, params, &[]))
+    }
+
+    /// Applies an affine transformation keeping the dimensions, see
+    /// [`crate::affine::AffineTransformationsExt::affine`].
+    pub fn affine(&self, matrix: AffineMatrix, interpolation: Interpolation) -> Result<Self> {
+        self.warp(matrix, self.dimensions(), interpolation)
+    }
+
+    /// Scales the image by `sx` horizontally and `sy` vertically, see
+    /// [`crate::affine::AffineTransformationsExt::scale`].
+    pub fn scale(&self, sx: f32, sy: f32, interpolation: Interpolation) -> Result<Self> {
+        affine::check_scale(sx, sy)?;
+        let dimensions = affine::scaled_dimensions(self.dimensions(), sx, sy);
+        self.warp(affine::scale_matrix(sx, sy), dimensions, interpolation)
+    }
+
+    /// Resizes the image to exactly `width` x `height` pixels. Fails with
+    /// [`Error::SingularTransformation`] if either the image or the requested size is
+    /// empty.
+    pub fn resize(
+        &self,
+        width: usize,
+        height: usize,
+        interpolation: Interpolation,
+    ) -> Result<Self> {
+        let (sx, sy) = (
+            width as f32 / self.width as f32,
+            height as f32 / self.height as f32,
+        );
+        affine::check_scale(sx, sy)?;
+        self.warp(affine::scale_matrix(sx, sy), (width, height), interpolation)
+    }
+}
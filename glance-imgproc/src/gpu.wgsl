@@ -0,0 +1,205 @@
+// Compute kernels of the GPU backend. Images are stored as interleaved f32 channels in row-major
+// order, the layout of the CPU pixel types. Every invocation writes one output pixel.
+
+struct Params {
+    src_width: u32,
+    src_height: u32,
+    dst_width: u32,
+    dst_height: u32,
+    channels: u32,
+    kernel_width: u32,
+    kernel_height: u32,
+    // 0 = constant, 1 = replicate, 2 = reflect, 3 = wrap
+    border: u32,
+    constant: f32,
+    // 0 = nearest, 1 = bilinear, 2 = bicubic
+    interpolation: u32,
+    // Inverse affine transformation, mapping output to source positions
+    m00: f32,
+    m01: f32,
+    m02: f32,
+    m10: f32,
+    m11: f32,
+    m12: f32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> src: array<f32>;
+@group(0) @binding(2) var<storage, read> weights: array<f32>;
+@group(0) @binding(3) var<storage, read_write> dst: array<f32>;
+
+// Largest finite f32, the identity of min and max for any pixel value
+const FLT_MAX: f32 = 3.40282347e38;
+
+// Remainder of a / b in [0, b) for positive b, also for negative a. Only takes the remainder
+// of non-negative values, some GL drivers compute it as if the operands were unsigned.
+fn euclid_rem(a: i32, b: i32) -> i32 {
+    if a >= 0 {
+        return a % b;
+    }
+    return b - 1 - (-a - 1) % b;
+}
+
+// Maps a possibly out of bounds coordinate onto an axis of length `len`, like
+// `BorderMode::resolve`. Returns -1 when the constant border should be sampled.
+fn resolve(coord: i32, len: i32) -> i32 {
+    if coord >= 0 && coord < len {
+        return coord;
+    }
+    if params.border == 1u {
+        return clamp(coord, 0, len - 1);
+    }
+    if params.border == 2u {
+        if len == 1 {
+            return 0;
+        }
+        let c = euclid_rem(coord, 2 * (len - 1));
+        return select(2 * (len - 1) - c, c, c < len);
+    }
+    if params.border == 3u {
+        return euclid_rem(coord, len);
+    }
+    return -1;
+}
+
+fn src_index(x: i32, y: i32, c: u32) -> u32 {
+    return (u32(y) * params.src_width + u32(x)) * params.channels + c;
+}
+
+fn dst_index(id: vec3<u32>, c: u32) -> u32 {
+    return (id.y * params.dst_width + id.x) * params.channels + c;
+}
+
+fn outside(id: vec3<u32>) -> bool {
+    return id.x >= params.dst_width || id.y >= params.dst_height;
+}
+
+@compute @workgroup_size(8, 8)
+fn convolve(@builtin(global_invocation_id) id: vec3<u32>) {
+    if outside(id) {
+        return;
+    }
+    let half_w = i32(params.kernel_width / 2u);
+    let half_h = i32(params.kernel_height / 2u);
+    for (var c = 0u; c < params.channels; c++) {
+        var sum = 0.0;
+        for (var ky = 0u; ky < params.kernel_height; ky++) {
+            let sy = resolve(i32(id.y) + i32(ky) - half_h, i32(params.src_height));
+            for (var kx = 0u; kx < params.kernel_width; kx++) {
+                let sx = resolve(i32(id.x) + i32(kx) - half_w, i32(params.src_width));
+                var value = params.constant;
+                if sx >= 0 && sy >= 0 {
+                    value = src[src_index(sx, sy, c)];
+                }
+                sum += weights[ky * params.kernel_width + kx] * value;
+            }
+        }
+        dst[dst_index(id, c)] = sum;
+    }
+}
+
+// Folds the neighbourhood selected by the non-zero weights with min or max.
+fn morphology(id: vec3<u32>, maximum: bool) {
+    if outside(id) {
+        return;
+    }
+    let half_w = i32(params.kernel_width / 2u);
+    let half_h = i32(params.kernel_height / 2u);
+    for (var c = 0u; c < params.channels; c++) {
+        var acc = select(FLT_MAX, -FLT_MAX, maximum);
+        for (var ky = 0u; ky < params.kernel_height; ky++) {
+            for (var kx = 0u; kx < params.kernel_width; kx++) {
+                if weights[ky * params.kernel_width + kx] == 0.0 {
+                    continue;
+                }
+                let sx = resolve(i32(id.x) + i32(kx) - half_w, i32(params.src_width));
+                let sy = resolve(i32(id.y) + i32(ky) - half_h, i32(params.src_height));
+                var value = params.constant;
+                if sx >= 0 && sy >= 0 {
+                    value = src[src_index(sx, sy, c)];
+                }
+                acc = select(min(acc, value), max(acc, value), maximum);
+            }
+        }
+        dst[dst_index(id, c)] = acc;
+    }
+}
+
+@compute @workgroup_size(8, 8)
+fn dilate(@builtin(global_invocation_id) id: vec3<u32>) {
+    morphology(id, true);
+}
+
+@compute @workgroup_size(8, 8)
+fn erode(@builtin(global_invocation_id) id: vec3<u32>) {
+    morphology(id, false);
+}
+
+// Reads a source value, clamping the position into the image.
+fn clamped(x: i32, y: i32, c: u32) -> f32 {
+    let cx = clamp(x, 0, i32(params.src_width) - 1);
+    let cy = clamp(y, 0, i32(params.src_height) - 1);
+    return src[src_index(cx, cy, c)];
+}
+
+fn catmull_rom(t: f32) -> vec4<f32> {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    return 0.5 * vec4<f32>(
+        -t3 + 2.0 * t2 - t,
+        3.0 * t3 - 5.0 * t2 + 2.0,
+        -3.0 * t3 + 4.0 * t2 + t,
+        t3 - t2,
+    );
+}
+
+fn sample(x: f32, y: f32, c: u32) -> f32 {
+    let x0 = floor(x);
+    let y0 = floor(y);
+    let fx = x - x0;
+    let fy = y - y0;
+    let ix = i32(x0);
+    let iy = i32(y0);
+    switch params.interpolation {
+        case 0u: {
+            // Rounds half away from zero like the CPU path, WGSL round() rounds to even
+            return clamped(i32(floor(x + 0.5)), i32(floor(y + 0.5)), c);
+        }
+        case 1u: {
+            let top = mix(clamped(ix, iy, c), clamped(ix + 1, iy, c), fx);
+            let bottom = mix(clamped(ix, iy + 1, c), clamped(ix + 1, iy + 1, c), fx);
+            return mix(top, bottom, fy);
+        }
+        default: {
+            let wx = catmull_rom(fx);
+            let wy = catmull_rom(fy);
+            var sum = 0.0;
+            for (var j = 0; j < 4; j++) {
+                for (var i = 0; i < 4; i++) {
+                    sum += clamped(ix + i - 1, iy + j - 1, c) * wx[i] * wy[j];
+                }
+            }
+            return sum;
+        }
+    }
+}
+
+@compute @workgroup_size(8, 8)
+fn warp(@builtin(global_invocation_id) id: vec3<u32>) {
+    if outside(id) {
+        return;
+    }
+    let x = f32(id.x);
+    let y = f32(id.y);
+    let sx = params.m00 * x + params.m01 * y + params.m02;
+    let sy = params.m10 * x + params.m11 * y + params.m12;
+    let inside = sx >= -0.5 && sy >= -0.5 && sx < f32(params.src_width) - 0.5
+        && sy < f32(params.src_height) - 0.5;
+    for (var c = 0u; c < params.channels; c++) {
+        var value = 0.0;
+        if inside {
+            value = sample(sx, sy, c);
+        }
+        dst[dst_index(id, c)] = value;
+    }
+}
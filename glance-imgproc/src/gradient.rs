@@ -0,0 +1,176 @@
+use std::sync::OnceLock;
+
+use glance_core::img::{Image, pixel::Luma};
+use glance_core::par::*;
+
+use crate::{Error, Result};
+
+use crate::kernels;
+use crate::linear_filters::BorderMode;
+
+/// Per-pixel image gradient, as returned by derivative operators like
+/// [`GradientExtLuma::sobel`]. The magnitude and orientation planes are computed from the
+/// derivatives on first use and kept, so consumers needing only some of them don't pay for the
+/// others.
+#[derive(Debug, Clone)]
+pub struct GradientField {
+    dx: Image<Luma>,
+    dy: Image<Luma>,
+    magnitude: OnceLock<Image<Luma>>,
+    orientation: OnceLock<Image<Luma>>,
+}
+
+impl GradientField {
+    /// Creates a field from its horizontal and vertical derivatives, which must have the same
+    /// dimensions.
+    pub fn new(dx: Image<Luma>, dy: Image<Luma>) -> Result<Self> {
+        if dx.dimensions() != dy.dimensions() {
+            return Err(Error::DimensionMismatch {
+                expected: dx.dimensions(),
+                actual: dy.dimensions(),
+            });
+        }
+        Ok(Self {
+            dx,
+            dy,
+            magnitude: OnceLock::new(),
+            orientation: OnceLock::new(),
+        })
+    }
+
+    /// Returns the dimensions of the field as a tuple (width, height).
+    pub fn dimensions(&self) -> (usize, usize) {
+        self.dx.dimensions()
+    }
+
+    /// Returns the horizontal derivative.
+    pub fn dx(&self) -> &Image<Luma> {
+        &self.dx
+    }
+
+    /// Returns the vertical derivative.
+    pub fn dy(&self) -> &Image<Luma> {
+        &self.dy
+    }
+
+    /// Returns the derivatives (dx, dy) at the specified position, or None if it is out of
+    /// bounds.
+    pub fn get(&self, position: (usize, usize)) -> Option<(f32, f32)> {
+        let dx = self.dx.get_pixel(position).ok()?;
+        let dy = self.dy.get_pixel(position).ok()?;
+        Some((dx.l, dy.l))
+    }
+
+    /// Returns the gradient magnitude, `sqrt(dx² + dy²)`.
+    pub fn magnitude(&self) -> &Image<Luma> {
+        self.magnitude
+            .get_or_init(|| self.combine(|dx, dy| dx.hypot(dy)))
+    }
+
+    /// Returns the gradient direction in radians in the (-π, π] range, as returned by
+    /// `atan2(dy, dx)`.
+    pub fn orientation(&self) -> &Image<Luma> {
+        self.orientation
+            .get_or_init(|| self.combine(|dx, dy| dy.atan2(dx)))
+    }
+
+    /// Returns the derivatives, dropping the computed planes.
+    pub fn into_parts(self) -> (Image<Luma>, Image<Luma>) {
+        (self.dx, self.dy)
+    }
+
+    fn combine(&self, f: impl Fn(f32, f32) -> f32 + Sync) -> Image<Luma> {
+        let (width, height) = self.dimensions();
+        let data = self
+            .dx
+            .as_slice()
+            .par_iter()
+            .zip(self.dy.as_slice().par_iter())
+            .map(|(dx, dy)| Luma { l: f(dx.l, dy.l) })
+            .collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+}
+
+/// 3x3 derivative operators supported by [`GradientExtLuma::gradient`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientOperator {
+    /// See [`kernels::sobel_x`]
+    Sobel,
+    /// See [`kernels::scharr_x`]
+    Scharr,
+    /// See [`kernels::prewitt_x`]
+    Prewitt,
+}
+
+impl GradientOperator {
+    /// Returns the horizontal and vertical derivative kernels of the operator.
+    pub fn kernels(&self) -> (Image<Luma>, Image<Luma>) {
+        match self {
+            GradientOperator::Sobel => (kernels::sobel_x(), kernels::sobel_y()),
+            GradientOperator::Scharr => (kernels::scharr_x(), kernels::scharr_y()),
+            GradientOperator::Prewitt => (kernels::prewitt_x(), kernels::prewitt_y()),
+        }
+    }
+}
+
+/// Extension trait for [`glance_core::img::Image`] to provide gradient operators for Luma images
+pub trait GradientExtLuma {
+    fn gradient(&self, operator: GradientOperator) -> GradientField;
+    fn sobel(&self) -> GradientField;
+    fn scharr(&self) -> GradientField;
+    fn prewitt(&self) -> GradientField;
+}
+
+impl GradientExtLuma for Image<Luma> {
+    /// Computes the horizontal and vertical derivatives with the given 3x3 operator.
+    /// Both derivatives are evaluated in a single pass over the image, with replicated borders.
+    fn gradient(&self, operator: GradientOperator) -> GradientField {
+        let (width, height) = self.dimensions();
+        let (kernel_x, kernel_y) = operator.kernels();
+        let kernel_x: Vec<f32> = kernel_x.pixels().map(|p| p.l).collect();
+        let kernel_y: Vec<f32> = kernel_y.pixels().map(|p| p.l).collect();
+
+        let (dx, dy): (Vec<Luma>, Vec<Luma>) = (0..width * height)
+            .into_par_iter()
+            .map(|idx| {
+                let (x, y) = ((idx % width) as isize, (idx / width) as isize);
+                let (mut dx, mut dy) = (0.0f32, 0.0f32);
+
+                for ky in 0..3 {
+                    for kx in 0..3 {
+                        let sx = BorderMode::Replicate.resolve(x + kx - 1, width).unwrap();
+                        let sy = BorderMode::Replicate.resolve(y + ky - 1, height).unwrap();
+                        let value = self.get_pixel((sx, sy)).unwrap().l;
+                        let k = (ky * 3 + kx) as usize;
+                        dx += kernel_x[k] * value;
+                        dy += kernel_y[k] * value;
+                    }
+                }
+
+                (Luma { l: dx }, Luma { l: dy })
+            })
+            .unzip();
+
+        GradientField::new(
+            Image::from_data(width, height, dx).unwrap(),
+            Image::from_data(width, height, dy).unwrap(),
+        )
+        .unwrap()
+    }
+
+    /// Computes the gradient with the Sobel operator. See [`GradientExtLuma::gradient`].
+    fn sobel(&self) -> GradientField {
+        self.gradient(GradientOperator::Sobel)
+    }
+
+    /// Computes the gradient with the Scharr operator. See [`GradientExtLuma::gradient`].
+    fn scharr(&self) -> GradientField {
+        self.gradient(GradientOperator::Scharr)
+    }
+
+    /// Computes the gradient with the Prewitt operator. See [`GradientExtLuma::gradient`].
+    fn prewitt(&self) -> GradientField {
+        self.gradient(GradientOperator::Prewitt)
+    }
+}
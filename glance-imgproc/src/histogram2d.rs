@@ -0,0 +1,244 @@
+//! Joint histograms of two values per pixel, e.g. hue against saturation or one channel against
+//! another, and their rendering as plots with axes. Densely populated 2D histograms double as
+//! scatter plots of the value pairs.
+
+use glance_core::drawing::shapes::Line;
+use glance_core::drawing::text::Text;
+use glance_core::img::{
+    Image,
+    pixel::{Pixel, Rgba},
+};
+
+use crate::color::{Colormap, Hsv};
+
+/// Gap between the plot area and the axis labels in pixels.
+const LABEL_GAP: usize = 4;
+
+/// Counts of value pairs in a grid of `bins_x` x `bins_y` equally sized bins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram2d {
+    bins: (usize, usize),
+    x_range: (f32, f32),
+    y_range: (f32, f32),
+    counts: Vec<u32>,
+}
+
+/// Appearance of a rendered [`Histogram2d`], see [`Histogram2d::render`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlotStyle {
+    /// Side length of a bin in pixels
+    pub bin_size: usize,
+    /// Colors of the bins from empty to the fullest
+    pub colormap: Colormap,
+    /// Scales the counts logarithmically, so that sparse bins remain visible next to full ones
+    pub log_scale: bool,
+    /// Label of the horizontal axis
+    pub x_label: String,
+    /// Label of the vertical axis
+    pub y_label: String,
+    /// Color of the axes and labels
+    pub axis_color: Rgba,
+    /// Color around the plot
+    pub background: Rgba,
+}
+
+impl Default for PlotStyle {
+    fn default() -> Self {
+        Self {
+            bin_size: 2,
+            colormap: Colormap::Viridis,
+            log_scale: true,
+            x_label: String::new(),
+            y_label: String::new(),
+            axis_color: Rgba {
+                r: 0.8,
+                g: 0.8,
+                b: 0.8,
+                a: 1.0,
+            },
+            background: Rgba {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 1.0,
+            },
+        }
+    }
+}
+
+/// Formats both bounds of a range for the axes, without decimals for whole numbers.
+fn bound_labels(range: (f32, f32)) -> [String; 2] {
+    [range.0, range.1].map(|value| {
+        if value.fract() == 0.0 {
+            format!("{value:.0}")
+        } else {
+            format!("{value:.2}")
+        }
+    })
+}
+
+impl Histogram2d {
+    /// Creates an empty histogram with `bins` = (bins_x, bins_y) bins covering `x_range` and
+    /// `y_range`. Both bounds are included.
+    pub fn new(bins: (usize, usize), x_range: (f32, f32), y_range: (f32, f32)) -> Self {
+        let bins = (bins.0.max(1), bins.1.max(1));
+        Histogram2d {
+            bins,
+            x_range,
+            y_range,
+            counts: vec![0; bins.0 * bins.1],
+        }
+    }
+
+    /// Creates a histogram of the given value pairs, see [`Histogram2d::new`].
+    pub fn from_pairs(
+        pairs: impl IntoIterator<Item = (f32, f32)>,
+        bins: (usize, usize),
+        x_range: (f32, f32),
+        y_range: (f32, f32),
+    ) -> Self {
+        let mut histogram = Self::new(bins, x_range, y_range);
+        pairs.into_iter().for_each(|(x, y)| histogram.add(x, y));
+        histogram
+    }
+
+    /// Histogram of hue (x, in degrees) against saturation (y) of the image, the usual model
+    /// for histogram backprojection of colored objects.
+    pub fn hue_saturation(image: &Image<Rgba>, bins: (usize, usize)) -> Self {
+        let pairs = image.pixels().map(|p| {
+            let hsv = Hsv::from_rgba(&p);
+            (hsv.h, hsv.s)
+        });
+        Self::from_pairs(pairs, bins, (0.0, 360.0), (0.0, 1.0))
+    }
+
+    /// Histogram of channel `x` against channel `y` of the image (0 to 3 for red, green, blue
+    /// and alpha), e.g. to find clipping or color casts.
+    pub fn channels(image: &Image<Rgba>, x: usize, y: usize, bins: (usize, usize)) -> Self {
+        let pairs = image.pixels().map(|p| (p.channel(x), p.channel(y)));
+        Self::from_pairs(pairs, bins, (0.0, 1.0), (0.0, 1.0))
+    }
+
+    /// Returns the number of bins as a tuple (bins_x, bins_y).
+    pub fn bins(&self) -> (usize, usize) {
+        self.bins
+    }
+
+    /// Returns the bin (column, row) holding the pair, or None if it is out of range.
+    pub fn bin(&self, x: f32, y: f32) -> Option<(usize, usize)> {
+        let index = |value: f32, (low, high): (f32, f32), bins: usize| {
+            if !(low..=high).contains(&value) {
+                return None;
+            }
+            let fraction = (value - low) / (high - low).max(f32::EPSILON);
+            Some(((fraction * bins as f32) as usize).min(bins - 1))
+        };
+        Some((
+            index(x, self.x_range, self.bins.0)?,
+            index(y, self.y_range, self.bins.1)?,
+        ))
+    }
+
+    /// Counts a value pair. Pairs out of range are ignored.
+    pub fn add(&mut self, x: f32, y: f32) {
+        if let Some((column, row)) = self.bin(x, y) {
+            self.counts[row * self.bins.0 + column] += 1;
+        }
+    }
+
+    /// Returns the count of the bin (column, row), or 0 if it is out of bounds.
+    pub fn count(&self, bin: (usize, usize)) -> u32 {
+        if bin.0 >= self.bins.0 || bin.1 >= self.bins.1 {
+            return 0;
+        }
+        self.counts[bin.1 * self.bins.0 + bin.0]
+    }
+
+    /// Returns all counts, row-major with the lowest y values first.
+    pub fn counts(&self) -> &[u32] {
+        &self.counts
+    }
+
+    /// Renders the histogram as a plot: bins colored by their count with the colormap of
+    /// `style`, x increasing to the right and y upwards, framed by axes labeled with the range
+    /// bounds and the axis labels.
+    pub fn render(&self, style: &PlotStyle) -> Image<Rgba> {
+        let bin_size = style.bin_size.max(1);
+        let (plot_w, plot_h) = (self.bins.0 * bin_size, self.bins.1 * bin_size);
+        let x_bounds = bound_labels(self.x_range);
+        let y_bounds = bound_labels(self.y_range);
+        let (_, text_h) = Text::<Rgba>::measure("0", 1);
+        let y_text_w = y_bounds
+            .iter()
+            .map(|b| Text::<Rgba>::measure(b, 1).0)
+            .max()
+            .unwrap_or(0);
+
+        // The y label above the axis, the bounds left of it and x bounds and label below
+        let left = y_text_w + LABEL_GAP + 1;
+        let top = if style.y_label.is_empty() {
+            LABEL_GAP
+        } else {
+            text_h + 2 * LABEL_GAP
+        };
+        let x_label_w = Text::<Rgba>::measure(&style.x_label, 1).0;
+        let x_bound_w = Text::<Rgba>::measure(&x_bounds[1], 1).0;
+        let y_label_w = Text::<Rgba>::measure(&style.y_label, 1).0;
+        let width = (left + plot_w.max(x_label_w)).max(y_label_w) + LABEL_GAP;
+        let height = top + plot_h + 1 + 2 * (LABEL_GAP + text_h) + LABEL_GAP;
+        let mut plot = Image::solid(width, height, style.background);
+
+        let max = self.counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+        let scale = |count: u32| {
+            if style.log_scale {
+                (1.0 + count as f32).ln() / (1.0 + max).ln()
+            } else {
+                count as f32 / max
+            }
+        };
+        for (i, &count) in self.counts.iter().enumerate() {
+            let (column, row) = (i % self.bins.0, i / self.bins.0);
+            let color = style.colormap.map(scale(count));
+            let (x0, y0) = (
+                left + column * bin_size,
+                top + (self.bins.1 - 1 - row) * bin_size,
+            );
+            plot.paste((x0, y0), &Image::solid(bin_size, bin_size, color));
+        }
+
+        let line = |start, end| Line {
+            start,
+            end,
+            color: style.axis_color,
+            thickness: 1,
+        };
+        let text = |position, text: &str| Text {
+            position,
+            text: text.to_string(),
+            color: style.axis_color,
+            scale: 1,
+        };
+        let (axis_x, axis_y) = (left - 1, top + plot_h);
+        // Drawing stays within the image, which was sized to fit everything
+        plot.draw(line((axis_x, top), (axis_x, axis_y))).unwrap();
+        plot.draw(line((axis_x, axis_y), (left + plot_w, axis_y)))
+            .unwrap();
+
+        let below = axis_y + 1 + LABEL_GAP;
+        plot.draw(text((left, below), &x_bounds[0])).unwrap();
+        let x_end = (left + plot_w).saturating_sub(x_bound_w);
+        plot.draw(text((x_end, below), &x_bounds[1])).unwrap();
+        let label_x = left + plot_w.saturating_sub(x_label_w) / 2;
+        plot.draw(text((label_x, below + text_h + LABEL_GAP), &style.x_label))
+            .unwrap();
+
+        let right_aligned = |bound: &str| y_text_w - Text::<Rgba>::measure(bound, 1).0;
+        plot.draw(text((right_aligned(&y_bounds[1]), top), &y_bounds[1]))
+            .unwrap();
+        let y_start = axis_y.saturating_sub(text_h);
+        plot.draw(text((right_aligned(&y_bounds[0]), y_start), &y_bounds[0]))
+            .unwrap();
+        plot.draw(text((0, LABEL_GAP), &style.y_label)).unwrap();
+        plot
+    }
+}
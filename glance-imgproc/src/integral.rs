@@ -0,0 +1,121 @@
+use glance_core::img::{Image, Rect, pixel::Luma};
+use glance_core::par::*;
+
+/// Summed-area table of an [`Image<Luma>`]. Entry (x, y) holds the sum of all pixels above and to
+/// the left of (x, y), so the sum over any rectangle can be read in constant time.
+/// Sums are accumulated in f64 to keep large images precise.
+#[derive(Debug, Clone)]
+pub struct IntegralImage {
+    width: usize,
+    height: usize,
+    /// (width + 1) * (height + 1) entries, with a leading row and column of zeros
+    data: Vec<f64>,
+}
+
+impl IntegralImage {
+    /// Returns the dimensions of the source image as a tuple (width, height).
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the sum of the source pixels inside `rect`. The rectangle is clipped to the image
+    /// bounds first, so regions partially outside the image only count their visible part.
+    pub fn sum_region(&self, rect: Rect) -> f64 {
+        let rect = rect.clip_to(self.dimensions());
+        let stride = self.width + 1;
+        let (x0, y0) = (rect.x, rect.y);
+        let (x1, y1) = (rect.x + rect.width, rect.y + rect.height);
+
+        self.data[y1 * stride + x1] - self.data[y0 * stride + x1] - self.data[y1 * stride + x0]
+            + self.data[y0 * stride + x0]
+    }
+
+    /// Returns the mean of the source pixels inside `rect` (clipped to the image bounds).
+    /// Returns 0.0 for empty regions.
+    pub fn mean_region(&self, rect: Rect) -> f64 {
+        let area = rect.clip_to(self.dimensions()).area();
+        if area == 0 {
+            return 0.0;
+        }
+        self.sum_region(rect) / area as f64
+    }
+}
+
+impl From<&Image<Luma>> for IntegralImage {
+    fn from(image: &Image<Luma>) -> Self {
+        let (width, height) = image.dimensions();
+        let stride = width + 1;
+        let mut data = vec![0.0f64; stride * (height + 1)];
+
+        for (y, row) in image
+            .pixels()
+            .collect::<Vec<_>>()
+            .chunks(width.max(1))
+            .enumerate()
+        {
+            let mut row_sum = 0.0;
+            for (x, pixel) in row.iter().enumerate() {
+                row_sum += pixel.l as f64;
+                data[(y + 1) * stride + x + 1] = data[y * stride + x + 1] + row_sum;
+            }
+        }
+
+        IntegralImage {
+            width,
+            height,
+            data,
+        }
+    }
+}
+
+/// Extension trait for [`glance_core::img::Image`] to provide integral image based operations
+/// for Luma images
+pub trait IntegralImageExtLuma {
+    fn integral_image(&self) -> IntegralImage;
+    fn box_blur_integral(self, radius: usize) -> Image<Luma>;
+    fn adaptive_threshold(self, radius: usize, offset: f32) -> Image<Luma>;
+}
+
+impl IntegralImageExtLuma for Image<Luma> {
+    /// Computes the [`IntegralImage`] of the image.
+    fn integral_image(&self) -> IntegralImage {
+        IntegralImage::from(self)
+    }
+
+    /// Box blur over a (2 * radius + 1)² window that runs in constant time per pixel regardless
+    /// of the radius. Near the borders only the pixels inside the image are averaged.
+    fn box_blur_integral(self, radius: usize) -> Image<Luma> {
+        let (width, height) = self.dimensions();
+        let integral = self.integral_image();
+
+        let blurred = (0..width * height)
+            .into_par_iter()
+            .map(|idx| {
+                let (x, y) = (idx % width, idx / width);
+                let x0 = x.saturating_sub(radius);
+                let y0 = y.saturating_sub(radius);
+                let window = Rect::new(x0, y0, x + radius + 1 - x0, y + radius + 1 - y0);
+                Luma {
+                    l: integral.mean_region(window) as f32,
+                }
+            })
+            .collect();
+
+        Image::from_data(width, height, blurred).unwrap()
+    }
+
+    /// Binarizes the image against the mean of the (2 * radius + 1)² window around every pixel:
+    /// pixels darker than the mean minus `offset` become 0.0 and the others 1.0. Unlike a global
+    /// threshold this copes with uneven lighting, e.g. of photographed documents.
+    fn adaptive_threshold(self, radius: usize, offset: f32) -> Image<Luma> {
+        let mean = self.clone().box_blur_integral(radius);
+        let (width, height) = self.dimensions();
+        let binary = self
+            .as_slice()
+            .par_iter()
+            .zip(mean.as_slice().par_iter())
+            .map(|(p, m)| Luma::new(if p.l < m.l - offset { 0.0 } else { 1.0 }))
+            .collect();
+        Image::from_data(width, height, binary).unwrap()
+    }
+}
@@ -0,0 +1,226 @@
+//! Common convolution kernels, represented as single channel [`Image<Luma>`] instances.
+//! Kernels are applied without flipping (see [`crate::linear_filters`]), so derivative kernels
+//! respond positively to intensities increasing along their axis.
+
+use glance_core::img::{Image, pixel::Luma};
+
+fn kernel_from(width: usize, height: usize, values: &[f32]) -> Image<Luma> {
+    let data = values.iter().map(|&l| Luma { l }).collect();
+    Image::from_data(width, height, data).unwrap()
+}
+
+/// 3x3 Sobel kernel for the horizontal derivative.
+pub fn sobel_x() -> Image<Luma> {
+    kernel_from(3, 3, &[-1.0, 0.0, 1.0, -2.0, 0.0, 2.0, -1.0, 0.0, 1.0])
+}
+
+/// 3x3 Sobel kernel for the vertical derivative.
+pub fn sobel_y() -> Image<Luma> {
+    kernel_from(3, 3, &[-1.0, -2.0, -1.0, 0.0, 0.0, 0.0, 1.0, 2.0, 1.0])
+}
+
+/// 3x3 Scharr kernel for the horizontal derivative. More rotationally symmetric than Sobel,
+/// which gives more accurate gradient orientations.
+pub fn scharr_x() -> Image<Luma> {
+    kernel_from(3, 3, &[-3.0, 0.0, 3.0, -10.0, 0.0, 10.0, -3.0, 0.0, 3.0])
+}
+
+/// 3x3 Scharr kernel for the vertical derivative.
+pub fn scharr_y() -> Image<Luma> {
+    kernel_from(3, 3, &[-3.0, -10.0, -3.0, 0.0, 0.0, 0.0, 3.0, 10.0, 3.0])
+}
+
+/// 3x3 Prewitt kernel for the horizontal derivative.
+pub fn prewitt_x() -> Image<Luma> {
+    kernel_from(3, 3, &[-1.0, 0.0, 1.0, -1.0, 0.0, 1.0, -1.0, 0.0, 1.0])
+}
+
+/// 3x3 Prewitt kernel for the vertical derivative.
+pub fn prewitt_y() -> Image<Luma> {
+    kernel_from(3, 3, &[-1.0, -1.0, -1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0])
+}
+
+/// Linear motion blur kernel of the given `length` in pixels along `angle` (degrees,
+/// counter-clockwise from the positive x axis). The line is rasterized with sub-pixel
+/// accuracy so arbitrary angles produce smooth kernels. The kernel is normalized to sum to 1.
+pub fn motion_blur_kernel(length: usize, angle: f32) -> Image<Luma> {
+    let size = (length.max(1) / 2) * 2 + 1;
+    let center = (size / 2) as f32;
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let mut values = vec![0.0f32; size * size];
+
+    // Splat evenly spaced samples along the line bilinearly onto the kernel grid
+    let samples = size * 8;
+    let half_length = (length.max(1) as f32 - 1.0) / 2.0;
+    for i in 0..samples {
+        let t = if samples > 1 {
+            -half_length + 2.0 * half_length * i as f32 / (samples - 1) as f32
+        } else {
+            0.0
+        };
+        // Image y axis points down, so a positive angle moves up
+        let (x, y) = (center + t * cos, center - t * sin);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+
+        for (dx, dy, weight) in [
+            (0, 0, (1.0 - fx) * (1.0 - fy)),
+            (1, 0, fx * (1.0 - fy)),
+            (0, 1, (1.0 - fx) * fy),
+            (1, 1, fx * fy),
+        ] {
+            let (kx, ky) = (x0 as isize + dx, y0 as isize + dy);
+            if kx >= 0 && ky >= 0 && (kx as usize) < size && (ky as usize) < size {
+                values[ky as usize * size + kx as usize] += weight;
+            }
+        }
+    }
+
+    let sum: f32 = values.iter().sum();
+    values.iter_mut().for_each(|v| *v /= sum);
+    kernel_from(size, size, &values)
+}
+
+/// Shape of the aperture of a lens, which out of focus highlights take on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ApertureShape {
+    /// A round aperture, producing circular bokeh
+    Disc,
+    /// A regular polygon formed by `blades` straight diaphragm blades, rotated by `rotation`
+    /// degrees counter-clockwise
+    Polygon { blades: usize, rotation: f32 },
+}
+
+impl ApertureShape {
+    /// Returns whether the offset (x, y), relative to the radius, lies inside the aperture.
+    fn contains(&self, x: f32, y: f32) -> bool {
+        match *self {
+            ApertureShape::Disc => x * x + y * y <= 1.0,
+            ApertureShape::Polygon { blades, rotation } => {
+                let blades = blades.max(3) as f32;
+                let sector = std::f32::consts::TAU / blades;
+                // Image y axis points down, so a positive rotation turns up
+                let angle = (-y).atan2(x) - rotation.to_radians();
+                let local = angle.rem_euclid(sector) - sector / 2.0;
+                x.hypot(y) * local.cos() <= (sector / 2.0).cos()
+            }
+        }
+    }
+}
+
+/// Lens blur kernel: the aperture `shape` with the given `radius` in pixels, with anti-aliased
+/// edges from 4x4 supersampling. The kernel is normalized to sum to 1.
+pub fn aperture_kernel(radius: f32, shape: ApertureShape) -> Image<Luma> {
+    let radius = radius.max(0.5);
+    let size = 2 * radius.ceil() as usize + 1;
+    let center = (size / 2) as f32;
+    let mut values: Vec<f32> = (0..size * size)
+        .map(|i| {
+            let (x, y) = ((i % size) as f32 - center, (i / size) as f32 - center);
+            let inside = (0..16)
+                .filter(|s| {
+                    let sx = x + ((s % 4) as f32 + 0.5) / 4.0 - 0.5;
+                    let sy = y + ((s / 4) as f32 + 0.5) / 4.0 - 0.5;
+                    shape.contains(sx / radius, sy / radius)
+                })
+                .count();
+            inside as f32 / 16.0
+        })
+        .collect();
+
+    let sum: f32 = values.iter().sum();
+    values.iter_mut().for_each(|v| *v /= sum);
+    kernel_from(size, size, &values)
+}
+
+/// Normalized `size`x`size` box (mean) kernel. Sums to 1.
+pub fn box_kernel(size: usize) -> Image<Luma> {
+    let weight = 1.0 / (size * size) as f32;
+    kernel_from(size, size, &vec![weight; size * size])
+}
+
+/// Normalized 1D Gaussian of standard deviation `sigma`, covering three standard deviations on
+/// each side. Sums to 1.
+pub fn gaussian_1d(sigma: f32) -> Vec<f32> {
+    let radius = (3.0 * sigma).ceil().max(0.0) as isize;
+    let mut values: Vec<f32> = (-radius..=radius)
+        .map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let sum: f32 = values.iter().sum();
+    values.iter_mut().for_each(|v| *v /= sum);
+    values
+}
+
+/// Normalized square Gaussian kernel of standard deviation `sigma`. Sums to 1.
+/// Prefer the separable `gaussian_blur()` filters over convolving with this kernel.
+pub fn gaussian(sigma: f32) -> Image<Luma> {
+    let values = gaussian_1d(sigma);
+    let size = values.len();
+    let outer: Vec<f32> = (0..size * size)
+        .map(|i| values[i / size] * values[i % size])
+        .collect();
+    kernel_from(size, size, &outer)
+}
+
+/// Unnormalized `size`x`size` box kernel of ones, computing the neighbourhood sum.
+/// Sums to `size²`.
+pub fn box_kernel_unnormalized(size: usize) -> Image<Luma> {
+    kernel_from(size, size, &vec![1.0; size * size])
+}
+
+/// 3x3 emboss kernel, lighting the image from the top-left. Sums to 1.
+pub fn emboss() -> Image<Luma> {
+    kernel_from(3, 3, &[-2.0, -1.0, 0.0, -1.0, 1.0, 1.0, 0.0, 1.0, 2.0])
+}
+
+/// 3x3 sharpen kernel (identity plus the 4-neighbour Laplacian). Sums to 1.
+pub fn sharpen_3x3() -> Image<Luma> {
+    kernel_from(3, 3, &[0.0, -1.0, 0.0, -1.0, 5.0, -1.0, 0.0, -1.0, 0.0])
+}
+
+/// 5x5 unsharp masking kernel based on a binomial Gaussian. Sums to 1.
+pub fn sharpen_5x5() -> Image<Luma> {
+    let gaussian = [
+        1.0, 4.0, 6.0, 4.0, 1.0, 4.0, 16.0, 24.0, 16.0, 4.0, 6.0, 24.0, 36.0, 24.0, 6.0, 4.0, 16.0,
+        24.0, 16.0, 4.0, 1.0, 4.0, 6.0, 4.0, 1.0,
+    ];
+    // 2 * identity - gaussian
+    let values: Vec<f32> = gaussian
+        .iter()
+        .enumerate()
+        .map(|(i, g)| if i == 12 { 2.0 - g / 256.0 } else { -g / 256.0 })
+        .collect();
+    kernel_from(5, 5, &values)
+}
+
+/// 3x3 ridge (4-neighbour Laplacian) kernel highlighting thin lines. Sums to 0.
+pub fn ridge() -> Image<Luma> {
+    kernel_from(3, 3, &[0.0, -1.0, 0.0, -1.0, 4.0, -1.0, 0.0, -1.0, 0.0])
+}
+
+/// `size`x`size` high-pass kernel (identity minus the box kernel), removing the local mean.
+/// Sums to 0.
+pub fn high_pass(size: usize) -> Image<Luma> {
+    let weight = 1.0 / (size * size) as f32;
+    let center = (size * size) / 2;
+    let values: Vec<f32> = (0..size * size)
+        .map(|i| if i == center { 1.0 - weight } else { -weight })
+        .collect();
+    kernel_from(size, size, &values)
+}
+
+/// Elliptical (disk when `width == height`) structuring element for morphology, inscribed in a
+/// `width` x `height` box. Pixels inside the ellipse are 1.0, all others 0.0.
+pub fn ellipse(width: usize, height: usize) -> Image<Luma> {
+    let (rx, ry) = ((width as f32 - 1.0) / 2.0, (height as f32 - 1.0) / 2.0);
+    let values: Vec<f32> = (0..width * height)
+        .map(|i| {
+            let dx = (i % width) as f32 - rx;
+            let dy = (i / width) as f32 - ry;
+            let nx = if rx > 0.0 { dx / rx } else { 0.0 };
+            let ny = if ry > 0.0 { dy / ry } else { 0.0 };
+            if nx * nx + ny * ny <= 1.0 { 1.0 } else { 0.0 }
+        })
+        .collect();
+    kernel_from(width, height, &values)
+}
@@ -1,5 +1,61 @@
+// Serial builds resolve the adapters of glance_core::par to Iterator, leaving its imports unused
+#![cfg_attr(not(feature = "parallel"), allow(unused_imports))]
+
+pub mod affine;
+pub mod alignment;
+pub mod ascii;
+pub mod background;
+pub mod blending;
+pub mod blobs;
+pub mod calibration;
+pub mod cascade;
+pub mod chessboard;
+#[cfg(feature = "codes")]
+pub mod codes;
+pub mod color;
+pub mod color_vision;
+pub mod components;
+pub mod contours;
+pub mod dct;
+pub mod depth_of_field;
+pub mod deskew;
+pub mod dominant_colors;
+pub mod enhance;
 mod error;
+pub mod estimation;
+pub mod features;
+pub mod fft;
+pub mod geometry;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+pub mod gradient;
+pub mod histogram2d;
+pub mod integral;
+pub mod kernels;
+mod linalg;
+pub mod linear_filters;
+pub mod local_stats;
+pub mod lut;
+pub mod metrics;
+pub mod moments;
+pub mod montage;
+pub mod noise;
+pub mod nonlinear_filters;
+pub mod optical_flow;
+pub mod padding;
+pub mod pipeline;
 pub mod point_ops;
+pub mod quiver;
+pub mod saliency;
+pub mod scanner;
+pub mod stereo;
+pub mod superpixels;
+pub mod temporal;
+pub mod texture;
+pub mod tiled;
+pub mod upscale;
+pub mod vesselness;
+pub mod vignette;
 
 pub use error::{Error, Result};
 
@@ -8,10 +64,26 @@ mod tests {
     use std::path::PathBuf;
 
     use crate::Result;
-    use glance_core::img::Image;
-    use glance_core::img::pixel::Rgba;
+    use glance_core::img::pixel::{Luma, Pixel, Rgba};
+    use glance_core::img::{Image, Rect};
 
+    use crate::affine::{AffineTransformationsExt, Interpolation};
+    use crate::chessboard::ChessboardExtLuma;
+    use crate::components::{ConnectedComponentsExtLuma, Connectivity};
+    use crate::contours::ContourExtLuma;
+    use crate::dct::DctExtLuma;
+    use crate::estimation::{Ransac, estimate_affine, ransac_homography};
+    use crate::geometry::{
+        convex_hull, min_area_rect, min_enclosing_circle, transform_affine, transform_homography,
+    };
+    use crate::gradient::{GradientExtLuma, GradientField};
+    use crate::integral::IntegralImageExtLuma;
+    use crate::linear_filters::{BorderMode, LinearFilterExtLuma, LinearFilterExtRgba};
+    use crate::local_stats::LocalStatsExtLuma;
+    use crate::nonlinear_filters::{NonLinearFilterExtLuma, NonLinearFilterExtRgba};
+    use crate::padding::PaddingExt;
     use crate::point_ops::{PointOpsExtLuma, PointOpsExtRgba};
+    use crate::saliency::SaliencyExtLuma;
 
     use super::*;
 
@@ -45,6 +117,31 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn grayscale_linear_light() -> Result<()> {
+        let saturated = Rgba {
+            r: 0.0,
+            g: 0.0,
+            b: 1.0,
+            a: 1.0,
+        };
+        let img = Image::from_data(1, 1, vec![saturated])?;
+
+        let gamma = img
+            .clone()
+            .grayscale_with(point_ops::GrayscaleMethod::Bt601Gamma);
+        let linear = img
+            .clone()
+            .grayscale_with(point_ops::GrayscaleMethod::Bt709Linear);
+        let max = img.grayscale_with(point_ops::GrayscaleMethod::MaxChannel);
+
+        // Pure blue has a linear luminance of 0.0722, which is ~0.30 once sRGB encoded
+        assert!((gamma.get_pixel((0, 0))?.l - 0.114).abs() < 1e-6);
+        assert!((linear.get_pixel((0, 0))?.l - 0.2986).abs() < 1e-3);
+        assert_eq!(max.get_pixel((0, 0))?.l, 1.0);
+        Ok(())
+    }
+
     #[test]
     fn threshold_image() -> Result<()> {
         let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -87,7 +184,7 @@ mod tests {
         let img1 = Image::<Rgba>::open(path1)?;
         let img2 = Image::<Rgba>::open(path2)?;
 
-        let lerp_img = img1.lerp(&img2, 0.5);
+        let lerp_img = img1.lerp(&img2, 0.5)?;
 
         if std::env::var("NO_DISPLAY").is_err() {
             lerp_img.display("lerp_images")?;
@@ -111,4 +208,3042 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn channel_mix_sepia() -> Result<()> {
+        let mut dir_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir_path.push("../media/test_imgs/");
+        let path1 = dir_path.join("flower.jpg");
+
+        let img = Image::<Rgba>::open(path1)?;
+        let img = img.channel_mix([
+            [0.393, 0.769, 0.189, 0.0],
+            [0.349, 0.686, 0.168, 0.0],
+            [0.272, 0.534, 0.131, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            img.display("channel_mix_sepia")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn channel_mix_swap() -> Result<()> {
+        let pixel = Rgba {
+            r: 0.1,
+            g: 0.2,
+            b: 0.3,
+            a: 1.0,
+        };
+        let img = Image::from_data(1, 1, vec![pixel])?;
+        let img = img.channel_mix([
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+
+        let swapped = img.get_pixel((0, 0))?;
+        assert_eq!((swapped.r, swapped.g, swapped.b), (0.3, 0.2, 0.1));
+        Ok(())
+    }
+
+    #[test]
+    fn in_range_hsv_mask() -> Result<()> {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/eye_orange.png");
+
+        let img = Image::<Rgba>::open(&path)?;
+        let orange = color::Hsv {
+            h: 30.0,
+            s: 1.0,
+            v: 1.0,
+        };
+        assert!(color::Hsv::from_rgba(&orange.to_rgba(1.0)) == orange);
+
+        let mask = img.in_range_hsv(
+            color::Hsv {
+                h: 10.0,
+                s: 0.4,
+                v: 0.3,
+            },
+            color::Hsv {
+                h: 45.0,
+                s: 1.0,
+                v: 1.0,
+            },
+        );
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            mask.display("in_range_hsv_mask")?;
+        }
+
+        assert!(mask.pixels().all(|p| p.l == 0.0 || p.l == 1.0));
+        Ok(())
+    }
+
+    #[test]
+    fn chroma_key_green_screen() -> Result<()> {
+        let green = Rgba {
+            r: 0.1,
+            g: 0.9,
+            b: 0.2,
+            a: 1.0,
+        };
+        let red = Rgba {
+            r: 0.9,
+            g: 0.3,
+            b: 0.1,
+            a: 1.0,
+        };
+        let img = Image::from_data(2, 1, vec![green, red])?;
+        let keyed = img.chroma_key(green, 0.1, 0.1);
+
+        assert_eq!(keyed.get_pixel((0, 0))?.a, 0.0);
+        assert_eq!(keyed.get_pixel((1, 0))?.a, 1.0);
+        assert!(keyed.get_pixel((1, 0))?.g <= 0.3);
+        Ok(())
+    }
+
+    #[test]
+    fn integral_image_box_blur() -> Result<()> {
+        let data = (0..16).map(|v| Luma { l: v as f32 }).collect();
+        let img = Image::from_data(4, 4, data)?;
+        let integral = img.integral_image();
+
+        assert_eq!(integral.sum_region(Rect::new(0, 0, 4, 4)), 120.0);
+        assert_eq!(
+            integral.sum_region(Rect::new(1, 1, 2, 2)),
+            5.0 + 6.0 + 9.0 + 10.0
+        );
+        assert_eq!(integral.sum_region(Rect::new(3, 3, 10, 10)), 15.0);
+
+        let blurred = img.box_blur_integral(1);
+        assert_eq!(blurred.get_pixel((1, 1))?.l, 5.0);
+        assert_eq!(blurred.get_pixel((0, 0))?.l, (0.0 + 1.0 + 4.0 + 5.0) / 4.0);
+        Ok(())
+    }
+
+    #[test]
+    fn sobel_gradient_field() -> Result<()> {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/lichtenstein.png");
+
+        let img = Image::<Rgba>::open(&path)?.grayscale();
+        let gradient = img.sobel();
+
+        // The one pass implementation must agree with two separate convolutions
+        let dx = img
+            .clone()
+            .convolve_2d(&kernels::sobel_x(), BorderMode::Replicate)?;
+        let dy = img.convolve_2d(&kernels::sobel_y(), BorderMode::Replicate)?;
+        let (gx, gy) = (dx.get_pixel((100, 80))?.l, dy.get_pixel((100, 80))?.l);
+        let (field_x, field_y) = gradient.get((100, 80)).unwrap();
+        assert!((field_x - gx).abs() < 1e-5 && (field_y - gy).abs() < 1e-5);
+        let magnitude = gradient.magnitude().get_pixel((100, 80))?.l;
+        assert!((magnitude - (gx * gx + gy * gy).sqrt()).abs() < 1e-5);
+        assert!((gradient.orientation().get_pixel((100, 80))?.l - gy.atan2(gx)).abs() < 1e-5);
+        assert!(GradientField::new(dx, Image::new(3, 3)).is_err());
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            gradient
+                .magnitude()
+                .clone()
+                .normalize()
+                .display("sobel_gradient_field")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn gradient_operators_agree_on_orientation() -> Result<()> {
+        // A diagonal ramp has the same gradient direction under every operator
+        let data = (0..64)
+            .map(|idx| Luma {
+                l: ((idx % 8) + (idx / 8)) as f32 / 14.0,
+            })
+            .collect();
+        let img = Image::from_data(8, 8, data)?;
+
+        for operator in [
+            gradient::GradientOperator::Sobel,
+            gradient::GradientOperator::Scharr,
+            gradient::GradientOperator::Prewitt,
+        ] {
+            let field = img.gradient(operator);
+            let angle = field.orientation().get_pixel((4, 4))?.l;
+            assert!((angle - std::f32::consts::FRAC_PI_4).abs() < 1e-5);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn motion_blur_image() -> Result<()> {
+        let horizontal = kernels::motion_blur_kernel(9, 0.0);
+        let sum: f32 = horizontal.pixels().map(|p| p.l).sum();
+        assert!((sum - 1.0).abs() < 1e-5);
+        // A horizontal kernel only has weight on its middle row
+        assert!(horizontal.get_pixel((4, 4))?.l > 0.0);
+        assert_eq!(horizontal.get_pixel((4, 3))?.l, 0.0);
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/flower.jpg");
+        let img = Image::<Rgba>::open(&path)?.motion_blur(9, 30.0);
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            img.display("motion_blur_image")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn kernel_preset_sums() {
+        let sum = |kernel: Image<Luma>| kernel.pixels().map(|p| p.l).sum::<f32>();
+
+        assert!((sum(kernels::box_kernel(5)) - 1.0).abs() < 1e-5);
+        assert_eq!(sum(kernels::box_kernel_unnormalized(3)), 9.0);
+        assert_eq!(sum(kernels::emboss()), 1.0);
+        assert_eq!(sum(kernels::sharpen_3x3()), 1.0);
+        assert!((sum(kernels::sharpen_5x5()) - 1.0).abs() < 1e-5);
+        assert_eq!(sum(kernels::ridge()), 0.0);
+        assert!(sum(kernels::high_pass(3)).abs() < 1e-5);
+        assert_eq!(sum(kernels::sobel_x()), 0.0);
+        assert_eq!(sum(kernels::scharr_y()), 0.0);
+        assert_eq!(sum(kernels::prewitt_x()), 0.0);
+    }
+
+    #[test]
+    fn rank_filters() -> Result<()> {
+        // A single bright outlier is removed by the median and spread by the max filter
+        let mut data = vec![Luma { l: 0.2 }; 25];
+        data[12] = Luma { l: 1.0 };
+        let img = Image::from_data(5, 5, data)?;
+
+        assert_eq!(img.clone().median_blur(3)?.get_pixel((2, 2))?.l, 0.2);
+        assert_eq!(img.clone().max_filter(3)?.get_pixel((1, 1))?.l, 1.0);
+        assert_eq!(img.clone().min_filter(3)?.get_pixel((2, 2))?.l, 0.2);
+        let p90 = img.rank_filter(3, nonlinear_filters::Rank::Percentile(1.0))?;
+        assert_eq!(p90.get_pixel((3, 3))?.l, 1.0);
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/pepper.bmp");
+        let img = Image::<Rgba>::open(&path)?.median_blur(3)?;
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            img.display("rank_filters")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_kernels_and_dimensions_are_errors() -> Result<()> {
+        let img = Image::<Luma>::new(4, 4);
+
+        let even = kernels::box_kernel(2);
+        assert!(matches!(
+            img.clone().convolve_2d(&even, BorderMode::Replicate),
+            Err(Error::KernelSizeEven {
+                width: 2,
+                height: 2
+            })
+        ));
+        assert!(matches!(
+            img.clone().median_blur(4),
+            Err(Error::KernelSizeEven { .. })
+        ));
+
+        let large = kernels::box_kernel(5);
+        assert!(matches!(
+            img.clone().dilate(&large, BorderMode::Replicate),
+            Err(Error::ImageSmallerThanKernel {
+                image: (4, 4),
+                kernel: (5, 5)
+            })
+        ));
+        // Filters building their own kernels still accept small images
+        img.clone().motion_blur(9, 0.0);
+
+        let other = Image::<Rgba>::new(3, 4);
+        assert!(matches!(
+            Image::<Rgba>::new(4, 4).lerp(&other, 0.5),
+            Err(Error::DimensionMismatch {
+                expected: (4, 4),
+                actual: (3, 4)
+            })
+        ));
+        assert!(matches!(
+            metrics::delta_e(&Image::new(4, 4), &other),
+            Err(Error::DimensionMismatch { .. })
+        ));
+
+        let (small, large) = (Image::<Luma>::new(4, 4), Image::<Luma>::new(6, 4));
+        assert!(matches!(
+            stereo::StereoBm::default().compute(&small, &large),
+            Err(Error::DimensionMismatch {
+                expected: (4, 4),
+                actual: (6, 4)
+            })
+        ));
+        assert!(matches!(
+            optical_flow::Farneback::default().calc(&small, &large),
+            Err(Error::DimensionMismatch { .. })
+        ));
+        let mut subtractor = background::BackgroundSubtractor::new(
+            background::BackgroundModel::RunningAverage { threshold: 0.1 },
+            0.05,
+        );
+        subtractor.apply(&small)?;
+        assert!(matches!(
+            subtractor.apply(&large),
+            Err(Error::DimensionMismatch {
+                expected: (4, 4),
+                actual: (6, 4)
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn histogram_median_matches_sorting_median() -> Result<()> {
+        let data = (0..70 * 45)
+            .map(|idx| Luma {
+                l: ((idx % 70 * 37 + idx / 70 * 91) % 256) as f32 / 255.0,
+            })
+            .collect();
+        let img = Image::from_data(70, 45, data)?;
+
+        let sorted = img.clone().median_blur(7)?;
+        let histogram = img.median_blur_histogram(7)?;
+        assert!(sorted.pixels().zip(histogram.pixels()).all(|(a, b)| a == b));
+        Ok(())
+    }
+
+    #[test]
+    fn fft_convolution_matches_spatial() -> Result<()> {
+        let data = (0..40 * 30)
+            .map(|idx| Luma {
+                l: ((idx % 40 * 7 + idx / 40 * 3) % 17) as f32 / 16.0,
+            })
+            .collect();
+        let img = Image::from_data(40, 30, data)?;
+
+        for border in [
+            BorderMode::Constant(0.5),
+            BorderMode::Replicate,
+            BorderMode::Reflect,
+            BorderMode::Wrap,
+        ] {
+            let kernel = kernels::motion_blur_kernel(7, 20.0);
+            let spatial = img.clone().convolve_2d(&kernel, border)?;
+            let frequency = img.clone().convolve_fft(&kernel, border)?;
+            assert!(
+                spatial
+                    .pixels()
+                    .zip(frequency.pixels())
+                    .all(|(a, b)| (a.l - b.l).abs() < 1e-4)
+            );
+        }
+        Ok(())
+    }
+
+    // Spatial convolution of RGBA images with kernels reaching past the edges on every row
+    #[test]
+    fn spatial_convolution_near_edges() -> Result<()> {
+        let data = (0..9 * 3)
+            .map(|idx| {
+                let v = ((idx * 5) % 11) as f32 / 10.0;
+                Rgba::new(v, 1.0 - v, v * 0.5, 1.0)
+            })
+            .collect();
+        let img = Image::from_data(9, 3, data)?;
+        let weights = (0..5 * 7).map(|i| Luma::new(i as f32 / 100.0)).collect();
+        let kernel = Image::from_data(5, 7, weights)?;
+
+        for border in [BorderMode::Constant(0.25), BorderMode::Reflect] {
+            let spatial = img.clone().convolve_2d(&kernel, border)?;
+            let frequency = img.clone().convolve_fft(&kernel, border)?;
+            for (a, b) in spatial.pixels().zip(frequency.pixels()) {
+                let diff = a.zip_map(&b, |x, y| (x - y).abs());
+                assert!(diff.r.max(diff.g).max(diff.b).max(diff.a) < 1e-4);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn block_dct_round_trip() -> Result<()> {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/eye.png");
+        let img = Image::<Rgba>::open(&path)?.grayscale();
+
+        let blocks: Vec<_> = img.blocks(8)?.collect();
+        assert_eq!(blocks.len(), 64 * 64);
+        assert!(img.blocks(0).is_err() && img.block_dct(0).is_err());
+
+        // A flat block only has a DC coefficient
+        let flat = Image::from_data(8, 8, vec![Luma { l: 0.5 }; 64])?.dct();
+        assert!((flat.get_pixel((0, 0))?.l - 4.0).abs() < 1e-5);
+        assert!(flat.pixels().skip(1).all(|p| p.l.abs() < 1e-5));
+
+        let restored = img.block_dct(8)?.block_idct(8)?;
+        assert!(
+            img.pixels()
+                .zip(restored.pixels())
+                .all(|(a, b)| (a.l - b.l).abs() < 1e-4)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn morphology_border_modes() -> Result<()> {
+        // A bright object touching the left edge
+        let data = (0..25)
+            .map(|idx| Luma {
+                l: if idx % 5 < 2 { 1.0 } else { 0.0 },
+            })
+            .collect();
+        let img = Image::from_data(5, 5, data)?;
+        let square = kernels::box_kernel_unnormalized(3);
+
+        // Replicating the border keeps the object attached to the edge...
+        let eroded = img.clone().erode(&square, BorderMode::Replicate)?;
+        assert_eq!(eroded.get_pixel((0, 2))?.l, 1.0);
+        assert_eq!(eroded.get_pixel((1, 2))?.l, 0.0);
+
+        // ...while a constant background erodes it from the edge as well
+        let eroded = img.clone().erode(&square, BorderMode::Constant(0.0))?;
+        assert_eq!(eroded.get_pixel((0, 2))?.l, 0.0);
+
+        // The 3x3 disk is a cross, so the corner neighbours are not part of it
+        let disk = kernels::ellipse(3, 3);
+        assert_eq!(disk.get_pixel((0, 0))?.l, 0.0);
+        let dilated = img.dilate(&disk, BorderMode::Constant(0.0))?;
+        assert_eq!(dilated.get_pixel((2, 2))?.l, 1.0);
+        assert_eq!(dilated.get_pixel((3, 2))?.l, 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn compound_morphology() -> Result<()> {
+        // A 3x3 bright square with a single pixel speck next to it
+        let mut data = vec![Luma { l: 0.0 }; 100];
+        for y in 3..6 {
+            for x in 3..6 {
+                data[y * 10 + x] = Luma { l: 1.0 };
+            }
+        }
+        data[8 * 10 + 8] = Luma { l: 1.0 };
+        let img = Image::from_data(10, 10, data)?;
+        let square = kernels::box_kernel_unnormalized(3);
+        let border = BorderMode::Replicate;
+
+        let opened = img.clone().open(&square, border, 1)?;
+        assert_eq!(opened.get_pixel((8, 8))?.l, 0.0);
+        assert_eq!(opened.get_pixel((4, 4))?.l, 1.0);
+
+        let tophat = img.clone().tophat(&square, border, 1)?;
+        assert_eq!(tophat.get_pixel((8, 8))?.l, 1.0);
+        assert_eq!(tophat.get_pixel((4, 4))?.l, 0.0);
+
+        let gradient = img.clone().morphological_gradient(&square, border, 1)?;
+        assert_eq!(gradient.get_pixel((4, 4))?.l, 0.0);
+        assert_eq!(gradient.get_pixel((3, 3))?.l, 1.0);
+
+        // Closing fills a hole in the square
+        let mut holed = img;
+        holed.set_pixel((4, 4), Luma { l: 0.0 })?;
+        let closed = holed.clone().close(&square, border, 2)?;
+        assert_eq!(closed.get_pixel((4, 4))?.l, 1.0);
+        let blackhat = holed.blackhat(&square, border, 2)?;
+        assert_eq!(blackhat.get_pixel((4, 4))?.l, 1.0);
+        assert_eq!(blackhat.get_pixel((3, 3))?.l, 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn rotate_about_center_expand() -> Result<()> {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/eye.png");
+        let img = Image::<Rgba>::open(&path)?.grayscale();
+
+        let cropped = img
+            .clone()
+            .rotate_about_center(45.0, Interpolation::Bilinear, false);
+        assert_eq!(cropped.dimensions(), (512, 512));
+
+        let expanded = img
+            .clone()
+            .rotate_about_center(45.0, Interpolation::Bilinear, true);
+        assert_eq!(expanded.dimensions(), (725, 725));
+
+        // A quarter turn is exact and keeps the center pixel in place
+        let quarter = img
+            .clone()
+            .rotate_about_center(90.0, Interpolation::Nearest, true);
+        assert_eq!(quarter.dimensions(), (512, 512));
+        assert_eq!(quarter.get_pixel((0, 511))?, img.get_pixel((0, 0))?);
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            expanded.display("rotate_about_center_expand")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn affine_transform_rgba() -> Result<()> {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/eye_orange.png");
+        let img = Image::<Rgba>::open(&path)?;
+
+        let moved = img.clone().translate(10.0, 5.0, Interpolation::Bilinear);
+        assert_eq!(moved.get_pixel((110, 105))?, img.get_pixel((100, 100))?);
+        // Uncovered areas are transparent
+        assert_eq!(moved.get_pixel((0, 0))?.a, 0.0);
+
+        let scaled = img.clone().scale(0.5, 0.5, Interpolation::Bilinear)?;
+        assert_eq!(scaled.dimensions(), (256, 256));
+
+        let rotated = img.rotate_about_center(30.0, Interpolation::Bilinear, true);
+        if std::env::var("NO_DISPLAY").is_err() {
+            rotated.display("affine_transform_rgba")?;
+        }
+
+        Ok(())
+    }
+
+    // Transformations without an inverse are rejected instead of panicking
+    #[test]
+    fn singular_transformations_are_errors() -> Result<()> {
+        use crate::affine::{CoordinateMap, invert_affine};
+
+        let img = Image::<Luma>::new(8, 6);
+        assert!(invert_affine(&[[1.0, 2.0, 0.0], [2.0, 4.0, 0.0]]).is_none());
+        assert!(matches!(
+            img.clone()
+                .affine([[1.0, 2.0, 0.0], [2.0, 4.0, 0.0]], Interpolation::Bilinear),
+            Err(Error::SingularTransformation { .. })
+        ));
+        for (sx, sy) in [
+            (0.0, 1.0),
+            (1.0, -2.0),
+            (f32::NAN, 1.0),
+            (f32::INFINITY, 1.0),
+        ] {
+            assert!(matches!(
+                img.clone().scale(sx, sy, Interpolation::Bilinear),
+                Err(Error::SingularTransformation { .. })
+            ));
+        }
+        assert!(CoordinateMap::new(2, 2, vec![(0.0, 0.0); 3]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn ransac_homography_rejects_outliers() {
+        let truth = [[0.9, -0.1, 12.0], [0.15, 1.1, -4.0], [0.0005, -0.0002, 1.0]];
+        let mut correspondences: Vec<_> = (0..40)
+            .map(|i| {
+                let p = ((i % 8) as f32 * 20.0, (i / 8) as f32 * 25.0);
+                (p, transform_homography(&truth, p))
+            })
+            .collect();
+        // Corrupt every fifth match
+        for (i, c) in correspondences.iter_mut().enumerate().step_by(5) {
+            c.1 = (c.1.0 + 40.0 + i as f32, c.1.1 - 30.0);
+        }
+
+        let result = ransac_homography(&correspondences, Ransac::default()).unwrap();
+        assert_eq!(result.inliers.len(), 32);
+        assert!(result.inliers.iter().all(|i| i % 5 != 0));
+        let q = transform_homography(&result.model, (50.0, 50.0));
+        let expected = transform_homography(&truth, (50.0, 50.0));
+        assert!((q.0 - expected.0).abs() < 1e-2 && (q.1 - expected.1).abs() < 1e-2);
+
+        let affine = [[1.0, 0.2, 3.0], [-0.3, 0.8, 7.0]];
+        let exact: Vec<_> = [(0.0, 0.0), (10.0, 0.0), (0.0, 10.0), (5.0, 7.0)]
+            .iter()
+            .map(|&p| (p, transform_affine(&affine, p)))
+            .collect();
+        let estimated = estimate_affine(&exact).unwrap();
+        assert!((estimated[1][2] - 7.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn pad_border_modes() -> Result<()> {
+        let data = (0..4).map(|v| Luma { l: v as f32 }).collect();
+        let img = Image::from_data(4, 1, data)?;
+        let row = |img: &Image<Luma>| img.pixels().map(|p| p.l).collect::<Vec<_>>();
+
+        let reflected = img.clone().pad(0, 0, 2, 2, BorderMode::Reflect);
+        assert_eq!(row(&reflected), [2.0, 1.0, 0.0, 1.0, 2.0, 3.0, 2.0, 1.0]);
+        let replicated = img.clone().pad(0, 0, 2, 2, BorderMode::Replicate);
+        assert_eq!(row(&replicated), [0.0, 0.0, 0.0, 1.0, 2.0, 3.0, 3.0, 3.0]);
+        let wrapped = img.clone().pad(0, 0, 2, 2, BorderMode::Wrap);
+        assert_eq!(row(&wrapped), [2.0, 3.0, 0.0, 1.0, 2.0, 3.0, 0.0, 1.0]);
+
+        let letterboxed = img.pad(1, 2, 0, 0, BorderMode::Constant(9.0));
+        assert_eq!(letterboxed.dimensions(), (4, 4));
+        assert_eq!(letterboxed.get_pixel((2, 0))?.l, 9.0);
+        assert_eq!(letterboxed.get_pixel((2, 1))?.l, 2.0);
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/eye_orange.png");
+        let img = Image::<Rgba>::open(&path)?.pad(64, 64, 32, 32, BorderMode::Reflect);
+        assert_eq!(img.dimensions(), (576, 640));
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            img.display("pad_border_modes")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn montage_contact_sheet() -> Result<()> {
+        let mut dir_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir_path.push("../media/test_imgs/");
+
+        let mut montage = montage::Montage::new().cell_size(128, 128).columns(3);
+        for name in ["eye.png", "eye_orange.png", "flower.jpg", "pepper.bmp"] {
+            let img = Image::<Rgba>::open(dir_path.join(name))?;
+            montage = montage.push_labeled(img, name);
+        }
+        let sheet = montage.build();
+
+        // 3 columns and 2 rows of 128px cells, with a 8px label row and 8px padding
+        assert_eq!(sheet.dimensions(), (8 + 3 * 136, 8 + 2 * (128 + 12 + 8)));
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            sheet.display("montage_contact_sheet")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn connected_components_labeling() -> Result<()> {
+        // Two diagonal touching squares and a U shape that needs label merging
+        let mut img = Image::<Luma>::new(8, 6);
+        for position in [
+            (0, 0),
+            (1, 0),
+            (0, 1),
+            (1, 1),
+            (2, 2),
+            (3, 2),
+            (2, 3),
+            (3, 3),
+        ] {
+            img.set_pixel(position, Luma { l: 1.0 })?;
+        }
+        for position in [(5, 1), (7, 1), (5, 2), (7, 2), (5, 3), (6, 3), (7, 3)] {
+            img.set_pixel(position, Luma { l: 1.0 })?;
+        }
+
+        let four = img.connected_components(Connectivity::Four);
+        assert_eq!(four.count(), 3);
+        assert_eq!(four.label((5, 1)), four.label((7, 1)));
+        assert_eq!(four.areas(), vec![48 - 15, 4, 7, 4]);
+        assert_eq!(four.label((0, 0)), Some(1));
+        assert_eq!(four.label((4, 0)), Some(0));
+
+        let eight = img.connected_components(Connectivity::Eight);
+        assert_eq!(eight.count(), 2);
+        assert_eq!(eight.label((0, 0)), eight.label((3, 3)));
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/pepper.bmp");
+        let labels = Image::<Rgba>::open(&path)?
+            .grayscale()
+            .threshold(0.5, 1.0, point_ops::ThresholdType::Binary)
+            .connected_components(Connectivity::Eight);
+        assert!(labels.count() > 0);
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            labels.to_luma().display("connected_components_labeling")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn contours_and_bounding_shapes() -> Result<()> {
+        let mut img = Image::<Luma>::new(12, 10);
+        for y in 2..6 {
+            for x in 3..8 {
+                img.set_pixel((x, y), Luma { l: 1.0 })?;
+            }
+        }
+        img.set_pixel((10, 8), Luma { l: 1.0 })?;
+
+        let contours = img.find_contours();
+        assert_eq!(contours.len(), 2);
+        // Border pixels of a 5x4 block, enclosing 4x3 between their centers
+        assert_eq!(contours[0].points.len(), 14);
+        assert_eq!(contours[0].area(), 12.0);
+        assert_eq!(contours[0].bounding_rect(), Rect::new(3, 2, 5, 4));
+        assert_eq!(contours[1].points, vec![(10.0, 8.0)]);
+        assert_eq!(
+            contours[0].convex_hull(),
+            vec![(3.0, 2.0), (7.0, 2.0), (7.0, 5.0), (3.0, 5.0)]
+        );
+
+        // A square of side 10 rotated by 30 degrees, with points inside it
+        let (sin, cos) = 30f32.to_radians().sin_cos();
+        let square: Vec<_> = [
+            (-5.0, -5.0),
+            (5.0, -5.0),
+            (5.0, 5.0),
+            (-5.0, 5.0),
+            (1.0, 2.0),
+        ]
+        .iter()
+        .map(|&(x, y): &(f32, f32)| (20.0 + x * cos + y * sin, 20.0 - x * sin + y * cos))
+        .collect();
+        assert_eq!(convex_hull(&square).len(), 4);
+
+        let rect = min_area_rect(&square).unwrap();
+        assert!((rect.area() - 100.0).abs() < 1e-3);
+        assert!((rect.angle % 90.0 - 30.0).abs() < 1e-3);
+        assert!((rect.center.0 - 20.0).abs() < 1e-4 && (rect.center.1 - 20.0).abs() < 1e-4);
+
+        let (center, radius) = min_enclosing_circle(&square).unwrap();
+        assert!((center.0 - 20.0).abs() < 1e-4 && (center.1 - 20.0).abs() < 1e-4);
+        assert!((radius - 50f32.sqrt()).abs() < 1e-4);
+
+        Ok(())
+    }
+
+    #[test]
+    fn image_and_contour_moments() -> Result<()> {
+        // A 9x3 bar tilted by 45 degrees (up and to the right on screen)
+        let mut img = Image::<Luma>::new(32, 32);
+        for t in -4..=4 {
+            for w in -1..=1 {
+                let (x, y) = ((16 + t + w) as usize, (16 - t + w) as usize);
+                img.set_pixel((x, y), Luma { l: 1.0 })?;
+            }
+        }
+
+        let m = moments::moments(&img);
+        assert_eq!(m.centroid(), Some((16.0, 16.0)));
+        assert!((m.orientation() - 45.0).abs() < 1e-3);
+
+        // Hu moments do not change under rotation
+        let rotated = img
+            .clone()
+            .rotate_about_center(90.0, Interpolation::Nearest, false);
+        let (hu, hu_rotated) = (m.hu(), moments::moments(&rotated).hu());
+        for (a, b) in hu.iter().zip(hu_rotated) {
+            assert!((a - b).abs() <= 1e-6 * a.abs().max(1e-6));
+        }
+
+        let mut rect = Image::<Luma>::new(12, 10);
+        for y in 2..6 {
+            for x in 3..8 {
+                rect.set_pixel((x, y), Luma { l: 1.0 })?;
+            }
+        }
+        let contour_moments = rect.find_contours()[0].moments();
+        assert_eq!(contour_moments.area(), 12.0);
+        assert_eq!(contour_moments.centroid(), Some((5.0, 3.5)));
+        assert_eq!(moments::moments(&rect).area(), 20.0);
+
+        Ok(())
+    }
+
+    // Round trip between masks and polygons
+    #[test]
+    fn mask_polygon_round_trip() -> Result<()> {
+        use crate::contours::{mask_to_polygons, polygons_to_mask};
+        use glance_core::drawing::shapes::Circle;
+
+        let mut mask = Image::<Luma>::new(64, 48);
+        mask.paste((5, 6), &Image::solid(20, 12, Luma::new(1.0)));
+        mask.draw(Circle {
+            position: (44, 28),
+            radius: 12,
+            color: Luma::new(1.0),
+            filled: true,
+            thickness: 1,
+        })?;
+
+        let exact = mask_to_polygons(&mask, 0.0);
+        assert_eq!(exact.len(), 2);
+        // Only the corners of the rectangle remain
+        assert_eq!(exact[0].len(), 4);
+        assert_eq!(
+            polygons_to_mask(&exact, (64, 48)).as_slice(),
+            mask.as_slice()
+        );
+
+        let simplified = mask_to_polygons(&mask, 1.0);
+        assert!(simplified[1].len() < exact[1].len());
+        let restored = polygons_to_mask(&simplified, (64, 48));
+        let differing = restored
+            .pixels()
+            .zip(mask.pixels())
+            .filter(|(a, b)| a != b)
+            .count();
+        assert!(differing < 30, "{differing} pixels differ");
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            restored.display("mask_polygon_round_trip")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn brief_descriptors() -> Result<()> {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/pepper.bmp");
+        let img = Image::<Rgba>::open(&path)?.grayscale();
+
+        let keypoints = [
+            features::Keypoint::new(200.0, 220.0),
+            features::Keypoint::new(300.0, 150.0),
+            features::Keypoint::new(5.0, 5.0),
+        ];
+        let brief = features::Brief::default();
+        let descriptors = brief.describe(&img, &keypoints);
+        // Too close to the border
+        assert!(descriptors[2].is_none());
+        let (a, b) = (
+            descriptors[0].as_ref().unwrap(),
+            descriptors[1].as_ref().unwrap(),
+        );
+        assert_eq!(a.words.len(), 4);
+        assert!(a.hamming(b) > 32);
+
+        // Steered descriptors follow the rotation of the image
+        let rotated = img
+            .clone()
+            .rotate_about_center(90.0, Interpolation::Nearest, false);
+        let steered = features::Brief {
+            oriented: true,
+            ..brief
+        };
+        let mut turned = keypoints[0];
+        turned.position = (220.0, 511.0 - 200.0);
+        turned.angle = 90.0;
+        let original = steered.describe(&img, &keypoints[..1])[0].clone().unwrap();
+        let after = steered.describe(&rotated, &[turned])[0].clone().unwrap();
+        assert_eq!(original.hamming(&after), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn blob_detection_filters() -> Result<()> {
+        use glance_core::drawing::shapes::Circle;
+
+        let mut img = Image::<Luma>::new(160, 96);
+        for (x, y, r) in [(24, 24, 5), (72, 36, 10), (124, 48, 16)] {
+            img.draw(Circle {
+                position: (x, y),
+                color: Luma { l: 1.0 },
+                radius: r,
+                filled: true,
+                thickness: 1,
+            })?;
+        }
+        // An elongated bar
+        for y in 76..82 {
+            for x in 10..60 {
+                img.set_pixel((x, y), Luma { l: 1.0 })?;
+            }
+        }
+
+        let detector = blobs::BlobDetector {
+            polarity: blobs::BlobPolarity::Bright,
+            min_inertia_ratio: Some(0.5),
+            min_circularity: Some(0.7),
+            ..Default::default()
+        };
+        let mut found = detector.detect(&img);
+        assert_eq!(found.len(), 3);
+        found.sort_by(|a, b| a.area.total_cmp(&b.area));
+        for (blob, (x, y, r)) in
+            found
+                .iter()
+                .zip([(24.0, 24.0, 5.0), (72.0, 36.0, 10.0), (124.0, 48.0, 16.0)])
+        {
+            let (bx, by) = blob.keypoint.position;
+            assert!((bx - x).hypot(by - y) <= 2.0, "{blob:?}");
+            assert!((blob.keypoint.size / 2.0 - r).abs() <= r * 0.35, "{blob:?}");
+        }
+
+        // The same disks are dark blobs on an inverted image
+        let dark = blobs::BlobDetector {
+            polarity: blobs::BlobPolarity::Dark,
+            ..detector
+        };
+        assert_eq!(dark.detect(&img.invert()).len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn slic_superpixels() -> Result<()> {
+        // Two flat colors split by a vertical edge that is not aligned with the grid
+        let (width, height) = (96, 64);
+        let data = (0..width * height)
+            .map(|i| {
+                if i % width < 37 {
+                    Rgba {
+                        r: 0.9,
+                        g: 0.2,
+                        b: 0.1,
+                        a: 1.0,
+                    }
+                } else {
+                    Rgba {
+                        r: 0.1,
+                        g: 0.3,
+                        b: 0.8,
+                        a: 1.0,
+                    }
+                }
+            })
+            .collect();
+        let img = Image::from_data(width, height, data)?;
+
+        let slic = superpixels::Slic {
+            segments: 24,
+            ..Default::default()
+        };
+        let result = slic.segment(&img);
+        let count = result.labels.count();
+        assert!((12..=36).contains(&count), "{count}");
+        assert_eq!(result.means.len(), count);
+
+        // No superpixel crosses the edge
+        for y in 0..height {
+            let left = result.labels.label((36, y)).unwrap();
+            let right = result.labels.label((37, y)).unwrap();
+            assert_ne!(left, right);
+        }
+        let mean = *result.mean_image().get_pixel((0, 0))?;
+        assert!((mean.r - 0.9).abs() < 1e-4 && (mean.b - 0.1).abs() < 1e-4);
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/flower.jpg");
+        let flower = Image::<Rgba>::open(&path)?.scale(0.25, 0.25, Interpolation::Bilinear)?;
+        let segmented = slic.segment(&flower);
+        let overlay = segmented.overlay_boundaries(
+            &flower,
+            Rgba {
+                r: 1.0,
+                g: 1.0,
+                b: 0.0,
+                a: 1.0,
+            },
+        );
+        if std::env::var("NO_DISPLAY").is_err() {
+            overlay.display("slic_superpixels")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn farneback_optical_flow() -> Result<()> {
+        // A smooth texture moving by (2, 1) pixels
+        let (width, height) = (96, 96);
+        let texture = |x: f32, y: f32| {
+            0.5 + 0.2 * (x * 0.35).sin() * (y * 0.3).cos() + 0.2 * ((x + y) * 0.17).sin()
+        };
+        let frame = |dx: f32, dy: f32| {
+            let data = (0..width * height)
+                .map(|i| Luma {
+                    l: texture((i % width) as f32 - dx, (i / width) as f32 - dy),
+                })
+                .collect();
+            Image::from_data(width, height, data)
+        };
+        let (previous, next) = (frame(0.0, 0.0)?, frame(2.0, 1.0)?);
+
+        let flow = optical_flow::Farneback::default().calc(&previous, &next)?;
+        assert_eq!(flow.dimensions(), (width, height));
+
+        // Check the interior, away from the replicated borders
+        let (mut sum_x, mut sum_y, mut count) = (0.0, 0.0, 0.0);
+        for y in 24..72 {
+            for x in 24..72 {
+                let (dx, dy) = flow.get((x, y)).unwrap();
+                sum_x += dx;
+                sum_y += dy;
+                count += 1.0;
+            }
+        }
+        assert!((sum_x / count - 2.0).abs() < 0.2, "{}", sum_x / count);
+        assert!((sum_y / count - 1.0).abs() < 0.2, "{}", sum_y / count);
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            flow.to_rgba(None).display("farneback_optical_flow")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn background_subtraction() -> Result<()> {
+        use background::{BackgroundModel, BackgroundSubtractor};
+
+        // A static gradient with a bright square moving to the right
+        let frame = |t: usize| -> Result<Image<Luma>> {
+            let mut img = Image::from_data(
+                64,
+                48,
+                (0..64 * 48)
+                    .map(|i| Luma {
+                        l: (i % 64) as f32 / 128.0,
+                    })
+                    .collect(),
+            )?;
+            for y in 20..28 {
+                for x in 4 * t..4 * t + 8 {
+                    img.set_pixel((x, y), Luma { l: 1.0 })?;
+                }
+            }
+            Ok(img)
+        };
+
+        for model in [
+            BackgroundModel::RunningAverage { threshold: 0.1 },
+            BackgroundModel::GaussianMixture {
+                components: 3,
+                deviations: 2.5,
+            },
+        ] {
+            let mut subtractor = BackgroundSubtractor::new(model, 0.05);
+            let mut mask = Image::new(0, 0);
+            for t in 0..10 {
+                mask = subtractor.apply(&frame(t)?)?;
+            }
+
+            // The square is now at x in 36..44
+            assert_eq!(mask.get_pixel((40, 24))?.l, 1.0, "{model:?}");
+            assert_eq!(mask.get_pixel((10, 10))?.l, 0.0, "{model:?}");
+            assert_eq!(mask.get_pixel((60, 24))?.l, 0.0, "{model:?}");
+            let background = subtractor.background().unwrap();
+            assert!((background.get_pixel((10, 10))?.l - 10.0 / 128.0).abs() < 1e-3);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn ciede2000_delta_e() -> Result<()> {
+        use color::{Lab, ciede2000};
+
+        // Reference pairs from Sharma, Wu and Dalal (2005)
+        let pairs = [
+            ((50.0, 2.6772, -79.7751), (50.0, 0.0, -82.7485), 2.0425),
+            ((50.0, 0.0, 0.0), (50.0, -1.0, 2.0), 2.3669),
+            ((50.0, 2.5, 0.0), (73.0, 25.0, -18.0), 27.1492),
+            ((2.0776, 0.0795, -1.135), (0.9033, -0.0636, -0.5514), 0.9082),
+        ];
+        for ((l1, a1, b1), (l2, a2, b2), expected) in pairs {
+            let difference = ciede2000(
+                &Lab {
+                    l: l1,
+                    a: a1,
+                    b: b1,
+                },
+                &Lab {
+                    l: l2,
+                    a: a2,
+                    b: b2,
+                },
+            );
+            assert!(
+                (difference - expected).abs() < 1e-3,
+                "{difference} != {expected}"
+            );
+        }
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/flower.jpg");
+        let img = Image::<Rgba>::open(&path)?;
+        let brighter = img.clone().brightness(0.05);
+
+        let same = metrics::DifferenceSummary::from(&metrics::delta_e(&img, &img)?);
+        assert_eq!(same.max, 0.0);
+        let map = metrics::delta_e(&img, &brighter)?;
+        let summary = metrics::DifferenceSummary::from(&map);
+        assert!(summary.mean > 0.5 && summary.mean <= summary.max);
+        assert!(summary.median <= summary.p95 && summary.p95 <= summary.max);
+        assert_eq!(metrics::percentile(&map, 1.0), summary.max);
+
+        Ok(())
+    }
+
+    #[test]
+    fn local_statistics() -> Result<()> {
+        // Left half flat, right half a checkerboard of 0.0 and 1.0
+        let img = Image::from_data(
+            16,
+            8,
+            (0..16 * 8)
+                .map(|i| {
+                    let (x, y) = (i % 16, i / 16);
+                    let l = if x < 8 { 0.5 } else { ((x + y) % 2) as f32 };
+                    Luma { l }
+                })
+                .collect(),
+        )?;
+
+        let variance = img.local_variance(1);
+        let std = img.local_std(1);
+        let entropy = img.local_entropy(1);
+        assert!(variance.get_pixel((2, 4))?.l.abs() < 1e-6);
+        assert_eq!(entropy.get_pixel((2, 4))?.l, 0.0);
+        // 5 of 9 pixels on one value and 4 on the other
+        let expected_variance = 20.0 / 81.0;
+        assert!((variance.get_pixel((12, 4))?.l - expected_variance).abs() < 1e-5);
+        assert!((std.get_pixel((12, 4))?.l - expected_variance.sqrt()).abs() < 1e-5);
+        let expected_entropy =
+            -(5.0f32 / 9.0 * (5.0f32 / 9.0).log2() + 4.0 / 9.0 * (4.0f32 / 9.0).log2());
+        assert!((entropy.get_pixel((12, 4))?.l - expected_entropy).abs() < 1e-5);
+        // Corner windows are clipped to 2x2 pixels, 2 of each value
+        assert!((entropy.get_pixel((15, 0))?.l - 1.0).abs() < 1e-5);
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/pepper.bmp");
+        let texture = Image::<Rgba>::open(&path)?.grayscale().local_entropy(3);
+        if std::env::var("NO_DISPLAY").is_err() {
+            texture.normalize().display("local_statistics")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn frangi_vesselness() -> Result<()> {
+        // A bright diagonal line and a bright blob of similar width on a dark background
+        let mut img = Image::<Luma>::new(64, 64);
+        for t in 4..60 {
+            for w in -1..=1 {
+                img.set_pixel(((t + w) as usize, (63 - t) as usize), Luma { l: 1.0 })?;
+            }
+        }
+        for y in 8..20 {
+            for x in 8..20 {
+                img.set_pixel((x, y), Luma { l: 1.0 })?;
+            }
+        }
+
+        let frangi = vesselness::Frangi::default();
+        let response = frangi.filter(&img);
+        let on_line = response.get_pixel((32, 31))?.l;
+        assert!(on_line > 0.5, "{on_line}");
+        assert!(response.get_pixel((13, 13))?.l < on_line * 0.2);
+        assert_eq!(response.get_pixel((50, 50))?.l, 0.0);
+
+        // Dark ridges on a bright background need the opposite polarity
+        let inverted = img.clone().invert();
+        let dark = vesselness::Frangi {
+            bright_ridges: false,
+            ..frangi
+        };
+        assert!(dark.filter(&inverted).get_pixel((32, 31))?.l > 0.5);
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            response.display("frangi_vesselness")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn saliency_maps() -> Result<()> {
+        // A regular stripe pattern with a single bright square as the odd one out
+        let mut img = Image::from_data(
+            128,
+            128,
+            (0..128 * 128)
+                .map(|i| Luma {
+                    l: 0.3 + 0.1 * (((i % 128) / 4) % 2) as f32,
+                })
+                .collect(),
+        )?;
+        for y in 80..96 {
+            for x in 24..40 {
+                img.set_pixel((x, y), Luma { l: 1.0 })?;
+            }
+        }
+
+        let argmax = |map: &Image<Luma>| {
+            let (idx, _) = map
+                .pixels()
+                .enumerate()
+                .max_by(|a, b| a.1.l.total_cmp(&b.1.l))
+                .unwrap();
+            (idx % 128, idx / 128)
+        };
+        for map in [
+            img.saliency_spectral_residual(),
+            img.saliency_fine_grained(),
+        ] {
+            assert_eq!(map.dimensions(), (128, 128));
+            let (x, y) = argmax(&map);
+            assert!(
+                (16..48).contains(&x) && (72..104).contains(&y),
+                "{:?}",
+                (x, y)
+            );
+        }
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/flower.jpg");
+        let saliency = Image::<Rgba>::open(&path)?
+            .grayscale()
+            .saliency_spectral_residual();
+        if std::env::var("NO_DISPLAY").is_err() {
+            saliency.display("saliency_maps")?;
+        }
+
+        Ok(())
+    }
+
+    /// Renders a chessboard of `squares` (columns, rows) squares of `size` pixels with a white
+    /// margin, rotated by `angle` degrees about the image center.
+    fn render_chessboard(squares: (usize, usize), size: usize, angle: f32) -> Image<Luma> {
+        let margin = 2 * size;
+        let (width, height) = (squares.0 * size + 2 * margin, squares.1 * size + 2 * margin);
+        let data = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                let inside =
+                    (margin..width - margin).contains(&x) && (margin..height - margin).contains(&y);
+                let black = inside && ((x - margin) / size + (y - margin) / size).is_multiple_of(2);
+                Luma {
+                    l: if black { 0.1 } else { 0.9 },
+                }
+            })
+            .collect();
+        let board = Image::from_data(width, height, data).unwrap();
+        // Fill the corners uncovered by the rotation with the margin color
+        board
+            .invert()
+            .rotate_about_center(angle, Interpolation::Bilinear, false)
+            .invert()
+    }
+
+    #[test]
+    fn chessboard_corners() -> Result<()> {
+        let (size, margin) = (20.0, 40.0);
+        let board = render_chessboard((9, 7), size as usize, 10.0);
+        let (width, height) = board.dimensions();
+
+        let corners = board
+            .find_chessboard_corners((8, 6))
+            .expect("board not found");
+        assert_eq!(corners.len(), 48);
+
+        // Compare with the ideal corner positions, rotated like the board
+        let center = ((width as f32 - 1.0) / 2.0, (height as f32 - 1.0) / 2.0);
+        let (sin, cos) = 10f32.to_radians().sin_cos();
+        for (i, corner) in corners.iter().enumerate() {
+            let ideal = (
+                margin + size * (i % 8 + 1) as f32 - 0.5 - center.0,
+                margin + size * (i / 8 + 1) as f32 - 0.5 - center.1,
+            );
+            let expected = (
+                center.0 + cos * ideal.0 + sin * ideal.1,
+                center.1 - sin * ideal.0 + cos * ideal.1,
+            );
+            assert!(
+                geometry::distance(*corner, expected) < 0.3,
+                "corner {i}: {corner:?} != {expected:?}"
+            );
+        }
+
+        // The wrong pattern size is rejected
+        assert!(board.find_chessboard_corners((7, 6)).is_none());
+
+        let mut overlay = Image::<Rgba>::from_data(
+            width,
+            height,
+            board
+                .pixels()
+                .map(|p| Rgba {
+                    r: p.l,
+                    g: p.l,
+                    b: p.l,
+                    a: 1.0,
+                })
+                .collect(),
+        )?;
+        chessboard::draw_chessboard_corners(&mut overlay, (8, 6), &corners, true);
+        if std::env::var("NO_DISPLAY").is_err() {
+            overlay.display("chessboard_corners")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn camera_calibration() -> Result<()> {
+        use crate::calibration::{
+            Camera, Distortion, Intrinsics, Pose, calibrate_camera, chessboard_points,
+        };
+
+        let truth = Camera {
+            intrinsics: Intrinsics {
+                fx: 500.0,
+                fy: 510.0,
+                cx: 165.0,
+                cy: 118.0,
+            },
+            distortion: Distortion {
+                k1: -0.2,
+                k2: 0.05,
+                p1: 0.001,
+                p2: -0.002,
+                k3: 0.0,
+            },
+        };
+        let board = chessboard_points((8, 6), 25.0);
+        let rotations = [
+            [0.3, 0.0, 0.0],
+            [0.0, 0.35, 0.1],
+            [-0.25, 0.2, 0.0],
+            [0.1, -0.3, -0.2],
+        ];
+        let views: Vec<Vec<(f32, f32)>> = rotations
+            .iter()
+            .map(|&rotation| {
+                let pose = Pose {
+                    rotation,
+                    translation: [-87.5, -62.5, 600.0],
+                };
+                board.iter().map(|&p| truth.project(&pose, p)).collect()
+            })
+            .collect();
+
+        let calibration = calibrate_camera(&board, &views, (320, 240)).expect("calibration failed");
+        let Intrinsics { fx, fy, cx, cy } = calibration.camera.intrinsics;
+        assert!(
+            (fx - 500.0).abs() < 1.0 && (fy - 510.0).abs() < 1.0,
+            "{fx} {fy}"
+        );
+        assert!(
+            (cx - 165.0).abs() < 1.0 && (cy - 118.0).abs() < 1.0,
+            "{cx} {cy}"
+        );
+        assert!((calibration.camera.distortion.k1 + 0.2).abs() < 0.01);
+        assert!(calibration.rms_error < 0.01);
+
+        // The undistortion map points back at the distorted projection
+        let map = calibration.camera.undistortion_map((320, 240));
+        let (xd, yd) = truth.distort((0.25, -0.2));
+        let source = map.get((290, 16)).unwrap();
+        assert!(geometry::distance(source, (500.0 * xd + 165.0, 510.0 * yd + 118.0)) < 0.5);
+
+        let image = Image::<Luma>::open(PathBuf::from("../media/test_imgs/flower.jpg"))?;
+        let (width, height) = image.dimensions();
+        let image = image
+            .scale(
+                320.0 / width as f32,
+                240.0 / height as f32,
+                Interpolation::Bilinear,
+            )?
+            .remap(&map, Interpolation::Bilinear);
+        if std::env::var("NO_DISPLAY").is_err() {
+            image.display("camera_calibration")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn stereo_block_matching() -> Result<()> {
+        use crate::stereo::{StereoBm, StereoCost};
+        use rand::{Rng, SeedableRng, rngs::StdRng};
+
+        // A textured square at disparity 16 in front of a background at disparity 8
+        let (width, height) = (160, 120);
+        let mut rng = StdRng::seed_from_u64(7);
+        let texture: Vec<f32> = (0..(width + 32) * height).map(|_| rng.random()).collect();
+        let background = |x: usize, y: usize| texture[y * (width + 32) + x];
+        let foreground = |x: usize, y: usize| 1.0 - texture[y * (width + 32) + width + 31 - x];
+        let in_square = |x: usize, y: usize| (60..100).contains(&x) && (40..80).contains(&y);
+
+        let left = Image::from_data(
+            width,
+            height,
+            (0..width * height)
+                .map(|i| {
+                    let (x, y) = (i % width, i / width);
+                    let l = if in_square(x, y) {
+                        foreground(x, y)
+                    } else {
+                        background(x, y)
+                    };
+                    Luma { l }
+                })
+                .collect(),
+        )?;
+        let right = Image::from_data(
+            width,
+            height,
+            (0..width * height)
+                .map(|i| {
+                    let (x, y) = (i % width, i / width);
+                    let l = if in_square(x + 16, y) {
+                        foreground(x + 16, y)
+                    } else {
+                        background(x + 8, y)
+                    };
+                    Luma { l }
+                })
+                .collect(),
+        )?;
+
+        for cost in [StereoCost::Sad, StereoCost::Census] {
+            let matcher = StereoBm {
+                num_disparities: 24,
+                block_size: 7,
+                cost,
+                ..Default::default()
+            };
+            let disparity = matcher.compute(&left, &right)?;
+            let foreground = disparity.get((80, 60)).expect("foreground not matched");
+            let background = disparity.get((130, 20)).expect("background not matched");
+            assert!((foreground - 16.0).abs() < 0.5, "{cost:?}: {foreground}");
+            assert!((background - 8.0).abs() < 0.5, "{cost:?}: {background}");
+            // The background uncovered left of the square is only visible in the left image
+            assert!(disparity.get((55, 60)).is_none());
+
+            let depth = disparity.depth((80, 60), 400.0, 0.1).unwrap();
+            assert!((depth - 400.0 * 0.1 / foreground).abs() < 1e-4);
+
+            if std::env::var("NO_DISPLAY").is_err() {
+                disparity
+                    .to_luma(Some(24.0))
+                    .display("stereo_block_matching")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // A chain of tone adjustments run as one lookup table pass
+    #[test]
+    fn lut_chain_matches_point_ops() -> Result<()> {
+        use crate::lut::{LutOp, PointOpChain};
+
+        let img = Image::<Rgba>::open(PathBuf::from("../media/test_imgs/flower.jpg"))?;
+        let chain = PointOpChain::new()
+            .gamma(1.5)
+            .gamma(2.0)
+            .brightness(0.1)
+            .invert()
+            .invert()
+            .contrast(1.3);
+        let expected = [
+            LutOp::Gamma(3.0),
+            LutOp::Brightness(0.1),
+            LutOp::Contrast(1.3),
+        ];
+        assert_eq!(chain.ops(), expected);
+
+        let fast = chain.run(&img);
+        let chained = img
+            .clone()
+            .gamma(1.5)
+            .gamma(2.0)
+            .brightness(0.1)
+            .contrast(1.3);
+        for (a, b) in fast.pixels().zip(chained.pixels()) {
+            let diff = a.zip_map(&b, |x, y| (x - y).abs());
+            assert!(diff.r.max(diff.g).max(diff.b).max(diff.a) < 1e-5);
+        }
+
+        let mut bytes = img.to_rgba8_bytes();
+        chain.apply_rgba8(&mut bytes);
+        assert_eq!(bytes, fast.to_rgba8_bytes());
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            fast.display("lut_chain_matches_point_ops")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn pipeline_matches_chained_ops() -> Result<()> {
+        use crate::pipeline::Pipeline;
+
+        let img = Image::<Rgba>::open(PathBuf::from("../media/test_imgs/flower.jpg"))?;
+        let kernel = kernels::ellipse(5, 5);
+
+        let pipeline = Pipeline::new()
+            .gaussian_blur(2.0)
+            .invert()
+            .erode(&kernel, BorderMode::Replicate)?
+            .gamma(2.2)
+            .dilate(&kernel, BorderMode::Constant(0.0))?;
+        // Loading plus one pass per neighbourhood operation, the point ops are fused
+        assert_eq!(pipeline.pass_count(), 4);
+        let fused = pipeline.run(&img);
+
+        let chained = img
+            .grayscale()
+            .gaussian_blur(2.0)
+            .invert()
+            .erode(&kernel, BorderMode::Replicate)?
+            .gamma(2.2)
+            .dilate(&kernel, BorderMode::Constant(0.0))?;
+        assert!(
+            fused
+                .pixels()
+                .zip(chained.pixels())
+                .all(|(a, b)| (a.l - b.l).abs() < 1e-5)
+        );
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            fused.display("pipeline_matches_chained_ops")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn tiled_processing_matches_whole_image() -> Result<()> {
+        use crate::tiled::TiledProcessor;
+
+        let img = Image::<Rgba>::open(PathBuf::from("../media/test_imgs/flower.jpg"))?
+            .grayscale()
+            .scale(0.25, 0.25, Interpolation::Bilinear)?;
+        let kernel = kernels::ellipse(7, 7);
+        let filter = |tile: Image<Luma>| {
+            // Tiles include the halo, so they are larger than the kernel
+            tile.gaussian_blur(2.0)
+                .dilate(&kernel, BorderMode::Replicate)
+                .unwrap()
+        };
+
+        let processor = TiledProcessor {
+            tile_size: (40, 30),
+            halo: TiledProcessor::gaussian_halo(2.0) + TiledProcessor::kernel_halo(&kernel),
+            // Room for about four padded tiles at a time
+            memory_budget: Some(4 * 2 * 64 * 54 * std::mem::size_of::<Luma>()),
+            parallel: true,
+        };
+        let tiled = processor.apply(&img, filter)?;
+        let whole = filter(img);
+        assert!(
+            tiled
+                .pixels()
+                .zip(whole.pixels())
+                .all(|(a, b)| (a.l - b.l).abs() < 1e-6)
+        );
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            tiled.display("tiled_processing_matches_whole_image")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn image_noise() -> Result<()> {
+        use crate::noise::NoiseExtLuma;
+
+        let gray = || Image::from_data(100, 100, vec![Luma { l: 0.5 }; 100 * 100]).unwrap();
+        let stats = |img: &Image<Luma>| {
+            let n = 100.0 * 100.0;
+            let mean = img.pixels().map(|p| p.l).sum::<f32>() / n;
+            let variance = img.pixels().map(|p| (p.l - mean).powi(2)).sum::<f32>() / n;
+            (mean, variance.sqrt())
+        };
+
+        let gaussian = gray().add_gaussian_noise(0.1, 1);
+        let (mean, std) = stats(&gaussian);
+        assert!((mean - 0.5).abs() < 0.01 && (std - 0.1).abs() < 0.005);
+        // The same seed gives the same noise
+        assert!(
+            gaussian
+                .pixels()
+                .eq(gray().add_gaussian_noise(0.1, 1).pixels())
+        );
+
+        let salt_pepper = gray().add_salt_pepper(0.2, 2);
+        let corrupted = salt_pepper.pixels().filter(|p| p.l != 0.5).count();
+        assert!((corrupted as f32 / 10000.0 - 0.2).abs() < 0.02);
+
+        // Shot noise keeps the mean and has a standard deviation of sqrt(0.5 * peak) / peak
+        let (mean, std) = stats(&gray().add_poisson_noise(20.0, 3));
+        assert!((mean - 0.5).abs() < 0.01 && (std - 10f32.sqrt() / 20.0).abs() < 0.01);
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            salt_pepper.display("image_noise")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn fractal_noise_textures() -> Result<()> {
+        use crate::noise::{FractalNoise, NoiseKind};
+
+        for kind in [NoiseKind::Perlin, NoiseKind::Simplex] {
+            let noise = FractalNoise {
+                kind,
+                seed: 5,
+                ..Default::default()
+            };
+            let texture = noise.generate(128, 96);
+            let values: Vec<f32> = texture.pixels().map(|p| p.l).collect();
+            let (min, max) = values
+                .iter()
+                .fold((f32::MAX, f32::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+            assert!((0.0..=1.0).contains(&min) && (0.0..=1.0).contains(&max));
+            assert!(max - min > 0.3, "{kind:?} is too flat: {min}..{max}");
+            // Neighbouring pixels are strongly correlated
+            assert!(
+                values
+                    .chunks_exact(128)
+                    .all(|row| row.windows(2).all(|w| (w[0] - w[1]).abs() < 0.2))
+            );
+            assert!(texture.pixels().eq(noise.generate(128, 96).pixels()));
+
+            if std::env::var("NO_DISPLAY").is_err() {
+                texture.display("fractal_noise_textures")?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn filters_match_goldens() -> Result<()> {
+        use crate::noise::NoiseExtLuma;
+        use glance_core::testing::assert_matches_reference;
+
+        let blurred = Image::<Luma>::zone_plate(128, 128).gaussian_blur(1.5);
+        assert_matches_reference(&blurred, "golden/gaussian_blur_zone_plate.png", 1.0 / 255.0);
+
+        let denoised = Image::<Luma>::siemens_star(128, 128, 16)
+            .add_salt_pepper(0.1, 7)
+            .median_blur(5)?;
+        assert_matches_reference(
+            &denoised,
+            "golden/median_blur_siemens_star.png",
+            1.0 / 255.0,
+        );
+
+        let rotated = Image::<Rgba>::color_bars(140, 90).rotate_about_center(
+            0.3,
+            Interpolation::Bilinear,
+            true,
+        );
+        assert_matches_reference(&rotated, "golden/rotate_color_bars.png", 1.0 / 255.0);
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            rotated.display("filters_match_goldens")?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "gpu")]
+    #[test]
+    fn gpu_backend_matches_cpu() -> Result<()> {
+        use crate::gpu::GpuContext;
+
+        let Ok(gpu) = GpuContext::new() else {
+            eprintln!("No GPU adapter, skipping");
+            return Ok(());
+        };
+        let max_difference = |a: &Image<Rgba>, b: &Image<Rgba>| {
+            assert_eq!(a.dimensions(), b.dimensions());
+            a.pixels()
+                .zip(b.pixels())
+                .map(|(p, q)| {
+                    (p.r - q.r)
+                        .abs()
+                        .max((p.g - q.g).abs())
+                        .max((p.b - q.b).abs())
+                        .max((p.a - q.a).abs())
+                })
+                .fold(0.0, f32::max)
+        };
+
+        let image = Image::<Rgba>::open(PathBuf::from("../media/test_imgs/flower.jpg"))?.scale(
+            0.1,
+            0.1,
+            Interpolation::Bilinear,
+        )?;
+        let uploaded = gpu.upload(&image);
+        assert!(max_difference(&uploaded.download()?, &image) == 0.0);
+
+        let kernel = kernels::sharpen_3x3();
+        for border in [
+            BorderMode::Constant(0.5),
+            BorderMode::Replicate,
+            BorderMode::Reflect,
+            BorderMode::Wrap,
+        ] {
+            let cpu = image.clone().convolve_2d(&kernel, border)?;
+            let on_gpu = uploaded.convolve_2d(&kernel, border)?.download()?;
+            assert!(max_difference(&cpu, &on_gpu) < 1e-5, "{border:?}");
+        }
+
+        let cpu = image.clone().gaussian_blur(1.5);
+        let on_gpu = uploaded.gaussian_blur(1.5).download()?;
+        assert!(max_difference(&cpu, &on_gpu) < 1e-5);
+
+        for interpolation in [
+            Interpolation::Nearest,
+            Interpolation::Bilinear,
+            Interpolation::Bicubic,
+        ] {
+            let cpu = image.clone().scale(1.7, 0.6, interpolation)?;
+            let on_gpu = uploaded.scale(1.7, 0.6, interpolation)?.download()?;
+            assert!(max_difference(&cpu, &on_gpu) < 1e-4, "{interpolation:?}");
+        }
+
+        let luma = image.grayscale();
+        let disk = kernels::ellipse(5, 5);
+        let cpu = luma.clone().dilate(&disk, BorderMode::Constant(0.2))?;
+        let on_gpu = gpu
+            .upload(&luma)
+            .dilate(&disk, BorderMode::Constant(0.2))?
+            .download()?;
+        assert!(cpu.pixels().zip(on_gpu.pixels()).all(|(a, b)| a.l == b.l));
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            on_gpu.display("gpu_backend_matches_cpu")?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn color_vision_deficiency() -> Result<()> {
+        use crate::color_vision::{ColorVisionExtRgba, CvdKind};
+
+        let color = |r, g, b| Rgba { r, g, b, a: 1.0 };
+        // Red, green and white swatches
+        let swatches = Image::from_data(
+            3,
+            1,
+            vec![
+                color(0.8, 0.2, 0.1),
+                color(0.3, 0.45, 0.1),
+                color(1.0, 1.0, 1.0),
+            ],
+        )?;
+        let distance = |img: &Image<Rgba>| {
+            let (a, b) = (
+                img.get_pixel((0, 0)).unwrap(),
+                img.get_pixel((1, 0)).unwrap(),
+            );
+            ((a.r - b.r).powi(2) + (a.g - b.g).powi(2) + (a.b - b.b).powi(2)).sqrt()
+        };
+
+        let unchanged = swatches.clone().simulate_cvd(CvdKind::Deuteranopia, 0.0);
+        assert!(distance(&unchanged) > 0.5);
+        assert!(
+            unchanged
+                .pixels()
+                .zip(swatches.pixels())
+                .all(|(a, b)| (a.r - b.r).abs() < 1e-4 && (a.g - b.g).abs() < 1e-4)
+        );
+
+        for kind in [CvdKind::Protanopia, CvdKind::Deuteranopia] {
+            // Red and green become hard to tell apart, white stays white
+            let seen = swatches.clone().simulate_cvd(kind, 1.0);
+            assert!(distance(&seen) < 0.2, "{kind:?}: {}", distance(&seen));
+            let white = seen.get_pixel((2, 0))?;
+            assert!(white.r > 0.97 && white.g > 0.97 && white.b > 0.97);
+
+            // Daltonized colors stay further apart for the same viewer
+            let corrected = swatches
+                .clone()
+                .daltonize(kind, 1.0)
+                .simulate_cvd(kind, 1.0);
+            assert!(distance(&corrected) > distance(&seen) + 0.05);
+        }
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            let bars = Image::<Rgba>::color_bars(280, 160);
+            bars.simulate_cvd(CvdKind::Tritanopia, 1.0)
+                .display("color_vision_deficiency")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn multiband_blending() -> Result<()> {
+        use crate::blending::blend_multiband;
+
+        let plate = Image::<Rgba>::zone_plate(64, 48);
+        let solid = |v| {
+            Image::solid(
+                64,
+                48,
+                Rgba {
+                    r: v,
+                    g: v,
+                    b: v,
+                    a: 1.0,
+                },
+            )
+        };
+        let (white, black) = (solid(1.0), solid(0.0));
+        let left_half = Image::from_data(
+            64,
+            48,
+            (0..64 * 48)
+                .map(|i| Luma {
+                    l: if i % 64 < 32 { 1.0 } else { 0.0 },
+                })
+                .collect(),
+        )?;
+        // Blending an image with itself reconstructs it exactly
+        let same = blend_multiband(&plate, &plate, &left_half, 5)?;
+        assert!(
+            same.pixels()
+                .zip(plate.pixels())
+                .all(|(a, b)| (a.r - b.r).abs() < 1e-4)
+        );
+
+        // One level is a hard cut, more levels spread the transition over several pixels
+        let row = |img: &Image<Rgba>| {
+            (28..36)
+                .map(|x| img.get_pixel((x, 5)).unwrap().g)
+                .collect::<Vec<_>>()
+        };
+        let cut = row(&blend_multiband(&white, &black, &left_half, 1)?);
+        assert_eq!(cut, [1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, 0.0]);
+        let smooth = row(&blend_multiband(&white, &black, &left_half, 4)?);
+        assert!(smooth.windows(2).all(|w| w[0] >= w[1]));
+        assert!(smooth[2] < 0.95 && smooth[5] > 0.05);
+
+        assert!(
+            blend_multiband(&plate, &black.crop(Rect::new(0, 0, 8, 8))?, &left_half, 3).is_err()
+        );
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            blend_multiband(&plate, &white, &left_half, 5)?.display("multiband_blending")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn retinex_evens_out_illumination() -> Result<()> {
+        use crate::enhance::{EnhanceExtLuma, EnhanceExtRgba, Msrcr};
+
+        // A checkerboard lit from the right, almost black on the left
+        let (width, height) = (128, 32);
+        let board = Image::checkerboard(width, height, 8, Luma { l: 0.4 }, Luma { l: 0.8 });
+        let lit = Image::from_data(
+            width,
+            height,
+            board
+                .pixels()
+                .enumerate()
+                .map(|(i, p)| Luma {
+                    l: p.l * (0.05 + 0.95 * (i % width) as f32 / width as f32),
+                })
+                .collect(),
+        )?;
+        let contrast = |img: &Image<Luma>, x: usize| {
+            (img.get_pixel((x, 4)).unwrap().l - img.get_pixel((x, 12)).unwrap().l).abs()
+        };
+        assert!(contrast(&lit, 4) < 0.1 * contrast(&lit, 108));
+
+        let enhanced = lit.clone().single_scale_retinex(6.0);
+        assert!(contrast(&enhanced, 4) > 0.5 * contrast(&enhanced, 108));
+        let multi = lit.multi_scale_retinex(&[4.0, 16.0]);
+        assert!(contrast(&multi, 4) > 0.3 * contrast(&multi, 108));
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/flower.jpg");
+        let img = Image::<Rgba>::open(&path)?.scale(0.25, 0.25, Interpolation::Bilinear)?;
+        let restored = img.msrcr(&Msrcr {
+            sigmas: vec![4.0, 16.0, 32.0],
+            ..Default::default()
+        });
+        assert!(restored.pixels().all(|p| (0.0..=1.0).contains(&p.r)));
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            restored.display("retinex_evens_out_illumination")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn dehaze_recovers_contrast() -> Result<()> {
+        use crate::enhance::EnhanceExtRgba;
+
+        // Colorful patches below a sky, behind white haze getting denser to the right
+        let (width, height) = (96, 64);
+        let mut clear = Image::checkerboard(
+            width,
+            height,
+            8,
+            Rgba {
+                r: 0.05,
+                g: 0.3,
+                b: 0.6,
+                a: 1.0,
+            },
+            Rgba {
+                r: 0.7,
+                g: 0.2,
+                b: 0.05,
+                a: 1.0,
+            },
+        );
+        for pixel in clear.pixels_mut().take(16 * width) {
+            *pixel = Rgba {
+                r: 0.9,
+                g: 0.9,
+                b: 0.9,
+                a: 1.0,
+            };
+        }
+        let hazy = Image::from_data(
+            width,
+            height,
+            clear
+                .pixels()
+                .enumerate()
+                .map(|(i, p)| {
+                    let t = 0.7 - 0.4 * (i % width) as f32 / width as f32;
+                    let haze = |v: f32| v * t + 0.9 * (1.0 - t);
+                    Rgba {
+                        r: haze(p.r),
+                        g: haze(p.g),
+                        b: haze(p.b),
+                        a: p.a,
+                    }
+                })
+                .collect(),
+        )?;
+        let error = |img: &Image<Rgba>| {
+            img.pixels()
+                .zip(clear.pixels())
+                .map(|(a, b)| (a.r - b.r).abs() + (a.g - b.g).abs() + (a.b - b.b).abs())
+                .sum::<f32>()
+                / (width * height) as f32
+        };
+
+        let dehazed = hazy.clone().dehaze();
+        assert!(error(&dehazed) < 0.3 * error(&hazy));
+        assert!(dehazed.pixels().all(|p| p.a == 1.0));
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            dehazed.display("dehaze_recovers_contrast")?;
+        }
+
+        Ok(())
+    }
+
+    // The guided filter follows the edges of its guide and needs a guide of the same size
+    #[test]
+    fn guided_filter_checks_guide() -> Result<()> {
+        use crate::enhance::EnhanceExtLuma;
+
+        let guide = Image::checkerboard(32, 32, 8, Luma { l: 0.0 }, Luma { l: 1.0 });
+        let noisy = Image::from_data(
+            32,
+            32,
+            guide
+                .pixels()
+                .enumerate()
+                .map(|(i, p)| Luma {
+                    l: p.l + if i % 2 == 0 { 0.1 } else { -0.1 },
+                })
+                .collect(),
+        )?;
+        let filtered = noisy.clone().guided_filter(&guide, 2, 1e-3)?;
+        assert!(
+            filtered
+                .pixels()
+                .zip(guide.pixels())
+                .all(|(f, g)| (f.l - g.l).abs() < 0.1)
+        );
+
+        assert!(matches!(
+            noisy.guided_filter(&Image::new(16, 32), 2, 1e-3),
+            Err(Error::DimensionMismatch {
+                expected: (32, 32),
+                actual: (16, 32)
+            })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn low_light_enhancement_keeps_highlights() -> Result<()> {
+        use crate::enhance::{EnhanceExtLuma, EnhanceExtRgba};
+
+        // A dark room with a bright window on the right
+        let (width, height) = (96, 48);
+        let board = Image::checkerboard(width, height, 8, Luma { l: 0.3 }, Luma { l: 0.9 });
+        let dark = Image::from_data(
+            width,
+            height,
+            board
+                .pixels()
+                .enumerate()
+                .map(|(i, p)| Luma {
+                    l: if i % width < 64 { 0.05 * p.l } else { p.l },
+                })
+                .collect(),
+        )?;
+        let contrast = |img: &Image<Luma>, x: usize| {
+            (img.get_pixel((x, 4)).unwrap().l - img.get_pixel((x, 12)).unwrap().l).abs()
+        };
+
+        let enhanced = dark.clone().enhance_low_light(0.8);
+        assert!(contrast(&enhanced, 20) > 5.0 * contrast(&dark, 20));
+        // The window keeps its contrast and is not blown out
+        assert!(contrast(&enhanced, 84) > 0.9 * contrast(&dark, 84));
+        assert!(enhanced.pixels().all(|p| p.l < 0.99));
+        assert!(
+            dark.clone()
+                .enhance_low_light(0.0)
+                .pixels()
+                .zip(dark.pixels())
+                .all(|(a, b)| (a.l - b.l).abs() < 0.05)
+        );
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/flower.jpg");
+        let img = Image::<Rgba>::open(&path)?
+            .scale(0.25, 0.25, Interpolation::Bilinear)?
+            .gamma(3.0);
+        let brightened = img.enhance_low_light(0.8);
+        assert!(brightened.pixels().all(|p| (0.0..=1.0).contains(&p.g)));
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            brightened.display("low_light_enhancement_keeps_highlights")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn vignette_fit_and_correction() -> Result<()> {
+        use crate::vignette::{Vignette, VignetteExtLuma, VignetteExtRgba};
+
+        let flat = Image::solid(120, 80, Luma { l: 0.8 }).vignette(0.5, 2.5);
+        assert!(flat.get_pixel((0, 0))?.l < 0.8 * flat.get_pixel((60, 40))?.l);
+        let model = Vignette::fit(&flat);
+        assert!((model.strength - 0.5).abs() < 0.05);
+        assert!((model.falloff - 2.5).abs() < 0.25);
+
+        let corrected = flat.devignette(model.strength, model.falloff);
+        assert!(corrected.pixels().all(|p| (p.l - 0.8).abs() < 0.02));
+
+        let bars = Image::<Rgba>::color_bars(120, 80);
+        let restored = bars.clone().vignette(0.4, 2.0).devignette(0.4, 2.0);
+        for (a, b) in bars.pixels().zip(restored.pixels()) {
+            assert!((a.r - b.r).abs() < 1e-3 && (a.b - b.b).abs() < 1e-3 && a.a == b.a);
+        }
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            bars.vignette(0.6, 2.0)
+                .display("vignette_fit_and_correction")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn lens_blur_bokeh() -> Result<()> {
+        use crate::kernels::ApertureShape;
+
+        // A small highlight on black spreads into a flat disc, not a Gaussian bell
+        let mut img = Image::solid(64, 64, Luma { l: 0.0 });
+        for (x, y) in [(31, 31), (32, 31), (31, 32), (32, 32)] {
+            img.set_pixel((x, y), Luma { l: 1.0 })?;
+        }
+        let disc = img.clone().lens_blur(10.0, ApertureShape::Disc, 0.0);
+        let center = disc.get_pixel((32, 32))?.l;
+        assert!(center > 0.0);
+        assert!((disc.get_pixel((38, 32))?.l - center).abs() < 0.01);
+        assert!(disc.get_pixel((45, 32))?.l < 1e-3);
+
+        // Corners of a hexagon with a vertex pointing right reach further than its edges
+        let hexagon = ApertureShape::Polygon {
+            blades: 6,
+            rotation: 0.0,
+        };
+        let hex = img.clone().lens_blur(10.0, hexagon, 0.0);
+        assert!(hex.get_pixel((42, 32))?.l > 0.0);
+        assert!(hex.get_pixel((32, 43))?.l < 1e-3);
+
+        let boosted = img.lens_blur(10.0, ApertureShape::Disc, 4.0);
+        assert!(boosted.get_pixel((32, 32))?.l > 1.5 * center);
+
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../media/test_imgs/flower.jpg");
+        let flower = Image::<Rgba>::open(&path)?.scale(0.25, 0.25, Interpolation::Bilinear)?;
+        let blurred = flower.lens_blur(6.0, hexagon, 2.0);
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            blurred.display("lens_blur_bokeh")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn depth_of_field_keeps_focus_plane_sharp() -> Result<()> {
+        use crate::depth_of_field::depth_of_field;
+
+        // A textured scene, in focus on the left and far away on the right
+        let (width, height) = (64, 32);
+        let white = Rgba {
+            r: 1.0,
+            g: 1.0,
+            b: 1.0,
+            a: 1.0,
+        };
+        let black = Rgba {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+            a: 1.0,
+        };
+        let scene = Image::checkerboard(width, height, 4, white, black);
+        let depth = Image::from_data(
+            width,
+            height,
+            (0..width * height)
+                .map(|i| Luma {
+                    l: if i % width < 32 { 1.0 } else { 10.0 },
+                })
+                .collect(),
+        )?;
+
+        let rendered = depth_of_field(&scene, &depth, 1.0, 6.0)?;
+        // The focus plane is untouched right up to the edge of the blurred background
+        for y in 0..height {
+            for x in 0..32 {
+                let (a, b) = (rendered.get_pixel((x, y))?, scene.get_pixel((x, y))?);
+                assert!((a.r - b.r).abs() < 1e-4);
+            }
+        }
+        let spread = |img: &Image<Rgba>| {
+            let values: Vec<f32> = (40..60)
+                .map(|x| img.get_pixel((x, 16)).unwrap().r)
+                .collect();
+            values.iter().cloned().fold(0.0, f32::max) - values.iter().cloned().fold(1.0, f32::min)
+        };
+        assert!(spread(&rendered) < 0.3 * spread(&scene));
+
+        assert!(depth_of_field(&scene, &Image::solid(8, 8, Luma { l: 1.0 }), 1.0, 6.0).is_err());
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            rendered.display("depth_of_field_keeps_focus_plane_sharp")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn stereo_pair_composition() -> Result<()> {
+        use crate::stereo::{AnaglyphMode, make_anaglyph, over_under, side_by_side};
+
+        let left = Image::<Rgba>::color_bars(40, 30);
+        let right = left
+            .crop(Rect::new(2, 0, 38, 30))?
+            .pad(0, 0, 0, 2, BorderMode::Replicate);
+        let anaglyph = make_anaglyph(&left, &right, AnaglyphMode::RedCyan)?;
+        for ((a, l), r) in anaglyph.pixels().zip(left.pixels()).zip(right.pixels()) {
+            assert_eq!((a.r, a.g, a.b), (l.r, r.g, r.b));
+        }
+        let optimized = make_anaglyph(&left, &right, AnaglyphMode::Optimized)?;
+        assert!(optimized.pixels().all(|p| (0.0..=1.0).contains(&p.r)));
+
+        let sbs = side_by_side(&left, &right)?;
+        assert_eq!(sbs.dimensions(), (80, 30));
+        assert_eq!(sbs.get_pixel((45, 7))?, right.get_pixel((5, 7))?);
+        let stacked = over_under(&left, &right)?;
+        assert_eq!(stacked.dimensions(), (40, 60));
+        assert_eq!(stacked.get_pixel((5, 37))?, right.get_pixel((5, 7))?);
+        assert!(side_by_side(&left, &left.crop(Rect::new(0, 0, 10, 10))?).is_err());
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            anaglyph.display("stereo_pair_composition")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn ascii_art_previews() -> Result<()> {
+        use crate::ascii::{ASCII_RAMP, AsciiArtExtLuma, AsciiArtExtRgba, BLOCK_RAMP};
+
+        // A bright square in the middle of a dark image
+        let mut img = Image::solid(40, 40, Luma { l: 0.0 });
+        img.paste((10, 10), &Image::solid(20, 20, Luma { l: 1.0 }));
+        let text = img.to_ascii(8, ASCII_RAMP);
+        assert_eq!(text, "        \n  @@@@  \n  @@@@  \n        \n");
+        assert_eq!(img.to_ascii(4, BLOCK_RAMP).lines().count(), 2);
+
+        let dots = img.to_braille(4, 0.5);
+        let lines: Vec<&str> = dots.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.chars().count() == 4));
+        // The lower two rows of dots are set where the square starts
+        assert_eq!(lines[0].chars().nth(1), Some('\u{28e4}'));
+
+        let colored = Image::<Rgba>::color_bars(64, 32).to_ascii(16, ASCII_RAMP, true);
+        assert!(colored.starts_with("\x1b[38;2;"));
+        assert_eq!(colored.lines().count(), 4);
+        assert!(colored.lines().all(|line| line.ends_with("\x1b[0m")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn quiver_plot() -> Result<()> {
+        use crate::optical_flow::FlowField;
+        use crate::quiver::draw_quiver;
+
+        // The gradient of a horizontal ramp points right everywhere
+        let (width, height) = (64, 64);
+        let ramp = Image::from_data(
+            width,
+            height,
+            (0..width * height)
+                .map(|i| Luma {
+                    l: (i % width) as f32 / width as f32,
+                })
+                .collect(),
+        )?;
+        let gradient = ramp.sobel();
+        let magnitude = gradient.magnitude().get_pixel((32, 32))?.l;
+        let red = Rgba {
+            r: 1.0,
+            g: 0.0,
+            b: 0.0,
+            a: 1.0,
+        };
+        let black = Rgba {
+            r: 0.0,
+            g: 0.0,
+            b: 0.0,
+            a: 1.0,
+        };
+        let mut canvas = Image::solid(width, height, black);
+        draw_quiver(&mut canvas, &gradient, 16, 10.0 / magnitude, red)?;
+        assert_eq!(*canvas.get_pixel((8, 8))?, red);
+        assert_eq!(*canvas.get_pixel((14, 8))?, red);
+        assert_eq!(*canvas.get_pixel((8, 14))?, black);
+        // The head lines of the arrow ending at (18, 8)
+        assert_eq!(*canvas.get_pixel((16, 7))?, red);
+        assert_eq!(*canvas.get_pixel((16, 9))?, red);
+
+        let mut unchanged = Image::solid(width, height, black);
+        draw_quiver(&mut unchanged, &FlowField::new(width, height), 8, 1.0, red)?;
+        assert!(unchanged.pixels().all(|p| p == black));
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            canvas.display("quiver_plot")?;
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn histogram_2d_plot() -> Result<()> {
+        use crate::histogram2d::{Histogram2d, PlotStyle};
+
+        let bars = Image::<Rgba>::color_bars(80, 40);
+        let histogram = Histogram2d::hue_saturation(&bars, (36, 10));
+        assert_eq!(histogram.counts().iter().sum::<u32>(), 80 * 40);
+        // Fully saturated red lands in the first hue bin, gray and white at zero saturation
+        let red = histogram.bin(0.0, 1.0).unwrap();
+        assert_eq!(red, (0, 9));
+        assert!(histogram.count(red) > 0);
+        assert!(histogram.count((0, 0)) > 0);
+        assert_eq!(histogram.bin(0.0, 1.5), None);
+
+        let channels = Histogram2d::channels(&bars, 0, 1, (16, 16));
+        assert_eq!(channels.counts().iter().sum::<u32>(), 80 * 40);
+
+        let style = PlotStyle {
+            bin_size: 4,
+            x_label: "hue".to_string(),
+            y_label: "saturation".to_string(),
+            ..Default::default()
+        };
+        let plot = histogram.render(&style);
+        let (width, height) = plot.dimensions();
+        assert!(width > 36 * 4 && height > 10 * 4);
+        // Empty bins take the darkest color of the colormap, distinct from the background
+        assert!(plot.pixels().any(|p| p == style.colormap.map(0.0)));
+        assert!(plot.pixels().any(|p| p == style.colormap.map(1.0)));
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            plot.display("histogram_2d_plot")?;
+        }
+
+        Ok(())
+    }
+
+    // Find the skew of a page of text lines and rotate it level
+    #[test]
+    fn deskew_text_page() -> Result<()> {
+        use crate::deskew::{DeskewExt, estimate_skew};
+
+        // Lines of words on white paper, rotated by 3 degrees counter-clockwise
+        let page = |angle: f32| {
+            let (width, height) = (360, 280);
+            let (sin, cos) = angle.to_radians().sin_cos();
+            let (cx, cy) = ((width as f32 - 1.0) / 2.0, (height as f32 - 1.0) / 2.0);
+            let data = (0..width * height)
+                .map(|i| {
+                    let (x, y) = ((i % width) as f32 - cx, (i / width) as f32 - cy);
+                    let (u, v) = (cos * x - sin * y + cx, sin * x + cos * y + cy);
+                    let in_line = v > 30.0 && v < 250.0 && (v as usize) % 24 < 9;
+                    let in_word = u > 30.0 && u < 330.0 && (u as usize * 7 % 50) < 36;
+                    Rgba::from_luminance(if in_line && in_word { 0.1 } else { 0.95 })
+                })
+                .collect();
+            Image::from_data(width, height, data).unwrap()
+        };
+
+        let skewed = page(3.0);
+        let (level, angle) = skewed.clone().deskew(10.0);
+        assert!((angle - 3.0).abs() < 0.1, "{angle}");
+        assert!(estimate_skew(&level, 10.0).abs() < 0.1);
+        assert_eq!(level.dimensions(), skewed.dimensions());
+        // The uncovered corners take the paper color
+        assert!((level.get_pixel((0, 0))?.r - 0.95).abs() < 1e-3);
+
+        let (_, angle) = page(-4.5).deskew(10.0);
+        assert!((angle + 4.5).abs() < 0.1, "{angle}");
+        let (_, angle) = Image::solid(16, 16, Luma::new(0.5)).deskew(10.0);
+        assert_eq!(angle, 0.0);
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            skewed.display("skewed page")?;
+            level.display("deskewed page")?;
+        }
+
+        Ok(())
+    }
+
+    // Summarize an image by its dominant colors and their populations
+    #[test]
+    fn dominant_colors_summary() -> Result<()> {
+        use crate::dominant_colors::{DominantColorsExtRgba, render_swatches};
+
+        // Bands of 60% red, 30% blue and 10% green with a little noise, under a transparent row
+        let (width, height) = (100, 41);
+        let data = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                let noise = ((x * 7 + y * 13) % 5) as f32 * 0.01;
+                match (y, x) {
+                    (0, _) => Rgba::new(1.0, 1.0, 0.0, 0.0),
+                    (_, 0..60) => Rgba::new(0.9 + noise, 0.1, 0.1, 1.0),
+                    (_, 60..90) => Rgba::new(0.1, 0.2, 0.8 + noise, 1.0),
+                    _ => Rgba::new(0.1, 0.7 + noise, 0.2, 1.0),
+                }
+            })
+            .collect();
+        let image = Image::from_data(width, height, data)?;
+
+        let colors = image.dominant_colors(3);
+        assert_eq!(colors.len(), 3);
+        let fractions: Vec<f32> = colors.iter().map(|c| c.fraction).collect();
+        for (fraction, expected) in fractions.iter().zip([0.6, 0.3, 0.1]) {
+            assert!((fraction - expected).abs() < 1e-3, "{fractions:?}");
+        }
+        let red = colors[0].color;
+        assert!(red.r > 0.85 && red.g < 0.15 && red.a == 1.0, "{red:?}");
+        assert!(colors[1].color.b > 0.75 && colors[2].color.g > 0.65);
+
+        // Fewer distinct colors than asked for
+        let flat = Image::solid(8, 8, Rgba::new(0.3, 0.5, 0.7, 1.0));
+        assert_eq!(flat.dominant_colors(5).len(), 1);
+
+        let swatches = render_swatches(&colors, (200, 20));
+        assert_eq!(swatches.dimensions(), (200, 20));
+        assert!((swatches.get_pixel((119, 10))?.r - red.r).abs() < 1e-6);
+        assert!((swatches.get_pixel((121, 10))?.b - colors[1].color.b).abs() < 1e-6);
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            image.display("image")?;
+            swatches.display("dominant colors")?;
+        }
+
+        Ok(())
+    }
+
+    // Detect objects with Haar and LBP cascades in the OpenCV XML format
+    #[test]
+    fn cascade_object_detection() -> Result<()> {
+        use crate::cascade::{Cascade, CascadeDetectionExtLuma, CascadeOptions};
+        use glance_core::drawing::shapes::AABB;
+
+        // One stage of a single stump, looking for a dark (Haar) or bright (LBP) square in the
+        // middle of a 12x12 window
+        let cascade = |feature_type: &str, node: &str, feature: &str| {
+            let xml = format!(
+                "<?xml version=\"1.0\"?>
+                <opencv_storage>
+                <cascade type_id=\"opencv-cascade-classifier\">
+                  <stageType>BOOST</stageType>
+                  <featureType>{feature_type}</featureType>
+                  <height>12</height>
+                  <width>12</width>
+                  <stageNum>1</stageNum>
+                  <stages><_>
+                    <maxWeakCount>1</maxWeakCount>
+                    <stageThreshold>0.</stageThreshold>
+                    <weakClassifiers><_>
+                      <internalNodes>{node}</internalNodes>
+                      <leafValues>1. -1.</leafValues>
+                    </_></weakClassifiers>
+                  </_></stages>
+                  <features><_>{feature}</_></features>
+                </cascade>
+                </opencv_storage>"
+            );
+            Cascade::read(xml.as_bytes())
+        };
+        let haar = cascade(
+            "HAAR",
+            "0 -1 0 -1.5",
+            "<rects><_>0 0 12 12 -1.</_><_>3 3 6 6 4.</_></rects>",
+        )?;
+        // The subset holds only pattern 0, all neighbor cells darker than the center
+        let lbp = cascade("LBP", "0 -1 0 1 0 0 0 0 0 0 0", "<rect>0 0 4 4</rect>")?;
+        assert_eq!(haar.window(), (12, 12));
+
+        // Squares of 12 and 24 pixels, in the middle of objects twice as large
+        let mut image = Image::solid(200, 120, Luma::new(0.8));
+        image.paste((40, 30), &Image::solid(12, 12, Luma::new(0.1)));
+        image.paste((120, 60), &Image::solid(24, 24, Luma::new(0.1)));
+        let expected = [Rect::new(34, 24, 24, 24), Rect::new(108, 48, 48, 48)];
+        // LBP cells only compare with each other, so the bright square matches any window whose
+        // center cell holds it and only the centers are checked
+        let close = |found: &[Rect], check_size: bool| {
+            found.len() == 2
+                && expected.iter().all(|e| {
+                    found.iter().any(|f| {
+                        let tolerance = e.width / 6;
+                        (2 * f.x + f.width).abs_diff(2 * e.x + e.width) <= 2 * tolerance
+                            && (2 * f.y + f.height).abs_diff(2 * e.y + e.height) <= 2 * tolerance
+                            && (!check_size || f.width.abs_diff(e.width) <= tolerance)
+                    })
+                })
+        };
+        let found = image.detect_objects(&haar, &CascadeOptions::default());
+        assert!(close(&found, true), "{found:?}");
+        let found = image
+            .clone()
+            .invert()
+            .detect_objects(&lbp, &CascadeOptions::default());
+        assert!(close(&found, false), "{found:?}");
+
+        // Size limits skip the pyramid levels outside them
+        let options = CascadeOptions {
+            min_size: (36, 36),
+            ..Default::default()
+        };
+        assert_eq!(image.detect_objects(&haar, &options).len(), 1);
+
+        let old =
+            "<opencv_storage><face type_id=\"opencv-haar-classifier\"></face></opencv_storage>";
+        assert!(Cascade::read(old.as_bytes()).is_err());
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            let mut shown = image.clone();
+            for rect in image.detect_objects(&haar, &CascadeOptions::default()) {
+                shown.draw(AABB {
+                    position: (rect.x, rect.y),
+                    size: (rect.width, rect.height),
+                    color: Luma::new(1.0),
+                    filled: false,
+                    thickness: 1,
+                })?;
+            }
+            shown.display("cascade detections")?;
+        }
+
+        Ok(())
+    }
+
+    // Find, rectify and binarize a page photographed in perspective
+    #[test]
+    fn scan_document_in_perspective() -> Result<()> {
+        use crate::contours::polygons_to_mask;
+        use crate::estimation::estimate_homography;
+        use crate::geometry::distance;
+        use crate::scanner::{DocumentScanExt, ScanOptions, find_document};
+
+        // A light page with a dark square in its middle on a dark table
+        let page = [(60.0, 40.0), (250.0, 60.0), (270.0, 205.0), (40.0, 215.0)];
+        let to_image = estimate_homography(&[
+            ((0.0, 0.0), page[0]),
+            ((1.0, 0.0), page[1]),
+            ((1.0, 1.0), page[2]),
+            ((0.0, 1.0), page[3]),
+        ])
+        .unwrap();
+        let square = [(0.45, 0.45), (0.55, 0.45), (0.55, 0.55), (0.45, 0.55)]
+            .map(|p| transform_homography(&to_image, p));
+        let page_mask = polygons_to_mask(&[page.to_vec()], (320, 240));
+        let square_mask = polygons_to_mask(&[square.to_vec()], (320, 240));
+        let image = Image::from_data(
+            320,
+            240,
+            page_mask
+                .pixels()
+                .zip(square_mask.pixels())
+                .map(|(p, s)| Luma::new(0.2 + 0.6 * p.l - 0.5 * s.l))
+                .collect(),
+        )?;
+
+        let corners = find_document(&image, 0.2).unwrap();
+        for (found, truth) in corners.iter().zip(page) {
+            assert!(distance(*found, truth) < 4.0, "{corners:?}");
+        }
+
+        let options = ScanOptions {
+            aspect: Some(1.5),
+            binarize: true,
+            ..Default::default()
+        };
+        let scanned = image.scan_document(&options).unwrap();
+        let (width, height) = scanned.dimensions();
+        assert_eq!(width, (height as f32 * 1.5).round() as usize);
+        assert_eq!(scanned.get_pixel((width / 2, height / 2))?.l, 0.0);
+        assert_eq!(scanned.get_pixel((width / 4, height / 4))?.l, 1.0);
+        assert!(scanned.pixels().all(|p| p.l == 0.0 || p.l == 1.0));
+
+        // Nothing to find on a blank image
+        assert!(
+            Image::solid(64, 64, Luma::new(0.5))
+                .scan_document(&options)
+                .is_none()
+        );
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            scanned.display("scanned document")?;
+        }
+
+        Ok(())
+    }
+
+    // Recover known warps of every motion model by ECC alignment
+    #[test]
+    fn ecc_alignment_recovers_warps() -> Result<()> {
+        use crate::alignment::{Ecc, MotionModel, find_transform_ecc};
+        use crate::estimation::estimate_homography;
+        use crate::geometry::distance;
+
+        let pattern = |(x, y): (f32, f32)| {
+            let blob =
+                |cx: f32, cy: f32, r: f32| (-((x - cx).powi(2) + (y - cy).powi(2)) / (r * r)).exp();
+            0.4 + 0.2 * (x / 7.0).sin() * (y / 9.0).cos() + 0.3 * blob(50.0, 40.0, 15.0)
+                - 0.3 * blob(110.0, 80.0, 20.0)
+        };
+        let (width, height) = (160, 120);
+        let template = Image::from_data(
+            width,
+            height,
+            (0..width * height)
+                .map(|i| Luma::new(pattern(((i % width) as f32, (i / width) as f32))))
+                .collect(),
+        )?;
+        let corners = [(0.0, 0.0), (159.0, 0.0), (159.0, 119.0), (0.0, 119.0)];
+
+        let (sin, cos) = 3f32.to_radians().sin_cos();
+        let warps = [
+            (
+                MotionModel::Translation,
+                [[1.0, 0.0, 2.5], [0.0, 1.0, -1.5], [0.0, 0.0, 1.0]],
+            ),
+            (
+                MotionModel::Euclidean,
+                [[cos, -sin, 3.0], [sin, cos, -2.0], [0.0, 0.0, 1.0]],
+            ),
+            (
+                MotionModel::Affine,
+                [[1.03, 0.02, -2.0], [-0.03, 0.98, 1.0], [0.0, 0.0, 1.0]],
+            ),
+            (
+                MotionModel::Homography,
+                [[1.02, 0.01, 1.0], [0.0, 0.99, -1.0], [1e-4, -1e-4, 1.0]],
+            ),
+        ];
+        for (model, warp) in warps {
+            // The input shows the template content at warp(x, y), with another contrast
+            let inverse =
+                estimate_homography(&corners.map(|p| (transform_homography(&warp, p), p))).unwrap();
+            let input = Image::from_data(
+                width,
+                height,
+                (0..width * height)
+                    .map(|i| {
+                        let p = ((i % width) as f32, (i / width) as f32);
+                        Luma::new(0.1 + 0.7 * pattern(transform_homography(&inverse, p)))
+                    })
+                    .collect(),
+            )?;
+            let params = Ecc {
+                model,
+                ..Default::default()
+            };
+            let found = find_transform_ecc(&template, &input, &params).unwrap();
+            assert!(found.correlation > 0.99, "{model:?}: {found:?}");
+            for corner in corners {
+                let error = distance(
+                    transform_homography(&found.warp, corner),
+                    transform_homography(&warp, corner),
+                );
+                assert!(error < 0.3, "{model:?}: {found:?}");
+            }
+        }
+
+        Ok(())
+    }
+
+    // Denoise a shaking noisy sequence while keeping an object that moves on its own
+    #[test]
+    fn temporal_denoising_of_shaking_frames() -> Result<()> {
+        use crate::alignment::{Ecc, MotionModel};
+        use crate::noise::NoiseExtLuma;
+        use crate::temporal::{
+            FrameAlignment, TemporalDenoiseOptions, TemporalDenoiser, TemporalReduction,
+        };
+
+        let (width, height) = (96, 72);
+        let scene = |(dx, dy): (f32, f32), object: Option<usize>| {
+            let data = (0..width * height)
+                .map(|i| {
+                    let (x, y) = ((i % width) as f32 + dx, (i / width) as f32 + dy);
+                    let mut l = 0.5 + 0.2 * (x / 6.0).sin() * (y / 8.0).cos();
+                    if object.is_some_and(|o| (o..o + 8).contains(&(i % width)) && i / width < 8) {
+                        l = 1.0;
+                    }
+                    Luma::new(l)
+                })
+                .collect();
+            Image::from_data(width, height, data)
+        };
+        let error = |a: &Image<Luma>, b: &Image<Luma>| {
+            let sum: f32 = a
+                .pixels()
+                .zip(b.pixels())
+                .map(|(a, b)| (a.l - b.l).powi(2))
+                .sum();
+            (sum / a.pixels().count() as f32).sqrt()
+        };
+
+        let shakes = [
+            (0.0, 0.0),
+            (1.5, -1.0),
+            (-1.0, 0.5),
+            (0.5, 1.5),
+            (-0.5, -0.5),
+        ];
+        for reduction in [TemporalReduction::Mean, TemporalReduction::Median] {
+            let mut denoiser = TemporalDenoiser::new(TemporalDenoiseOptions {
+                alignment: FrameAlignment::Ecc(Ecc {
+                    model: MotionModel::Translation,
+                    ..Default::default()
+                }),
+                reduction,
+                ..Default::default()
+            });
+            let mut last = None;
+            for (i, &shake) in shakes.iter().enumerate() {
+                // A white square runs along the top edge
+                let clean = scene(shake, Some(20 + 10 * i))?;
+                let noisy = clean.clone().add_gaussian_noise(0.05, i as u64);
+                last = Some((clean, denoiser.apply(&noisy)?, noisy));
+            }
+            let (clean, denoised, noisy) = last.unwrap();
+            assert!(
+                error(&denoised, &clean) < 0.6 * error(&noisy, &clean),
+                "{reduction:?}"
+            );
+            // The square is left where it is now, without trails
+            assert!(denoised.get_pixel((63, 4))?.l > 0.85);
+            assert!(denoised.get_pixel((55, 4))?.l < 0.8);
+            assert!(matches!(
+                denoiser.apply(&Image::new(width / 2, height)),
+                Err(Error::DimensionMismatch { .. })
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Quilt a larger texture from an exemplar and make an image tile seamlessly
+    #[test]
+    fn texture_quilting_and_tiling() -> Result<()> {
+        use crate::texture::{QuiltingOptions, TextureSynthesisExt};
+
+        // Diagonal stripes, which show every misplaced patch as a break
+        let exemplar = Image::from_data(
+            48,
+            40,
+            (0..48 * 40)
+                .map(|i| {
+                    let (x, y) = ((i % 48) as f32, (i / 48) as f32);
+                    Luma::new(0.5 + 0.4 * ((x + y) * std::f32::consts::TAU / 12.0).sin())
+                })
+                .collect(),
+        )?;
+        let roughness = |image: &Image<Luma>| {
+            let (width, height) = image.dimensions();
+            let pixel = |x, y| image.get_pixel((x, y)).unwrap().l;
+            let sum: f32 = (0..height)
+                .flat_map(|y| (1..width).map(move |x| (x, y)))
+                .map(|(x, y)| (pixel(x, y) - pixel(x - 1, y)).abs())
+                .sum();
+            sum / ((width - 1) * height) as f32
+        };
+
+        let options = QuiltingOptions {
+            patch_size: 16,
+            overlap: 4,
+            ..Default::default()
+        };
+        let quilt = exemplar.synthesize_texture((100, 70), &options)?;
+        assert_eq!(quilt.dimensions(), (100, 70));
+        let again = exemplar.synthesize_texture((100, 70), &options)?;
+        assert_eq!(quilt.as_slice(), again.as_slice());
+        assert!(roughness(&quilt) < 1.2 * roughness(&exemplar));
+        let too_large = QuiltingOptions {
+            patch_size: 64,
+            ..options
+        };
+        assert!(exemplar.synthesize_texture((100, 70), &too_large).is_err());
+
+        // A horizontal ramp jumps from white back to black when tiled
+        let ramp = Image::from_data(
+            40,
+            30,
+            (0..40 * 30)
+                .map(|i| Luma::new((i % 40) as f32 / 39.0))
+                .collect(),
+        )?;
+        let tile = ramp.make_tileable(10)?;
+        assert_eq!(tile.dimensions(), (30, 20));
+        let wrap = (tile.get_pixel((29, 5))?.l - tile.get_pixel((0, 5))?.l).abs();
+        let step = (tile.get_pixel((15, 5))?.l - tile.get_pixel((14, 5))?.l).abs();
+        assert!(wrap < 2.0 * step, "{wrap} {step}");
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            quilt.display("quilted texture")?;
+        }
+
+        Ok(())
+    }
+
+    // Upscaling with back-projection restores a downscaled image better than bicubic
+    #[test]
+    fn upscale_methods() -> Result<()> {
+        use crate::upscale::{UpscaleExt, UpscaleMethod};
+
+        // A disc and a diagonal band with sharp, anti-aliased edges
+        let (width, height) = (96, 96);
+        let original = Image::from_data(
+            width,
+            height,
+            (0..width * height)
+                .map(|i| {
+                    let (x, y) = ((i % width) as f32, (i / width) as f32);
+                    let disc = 20.0 - ((x - 30.0).powi(2) + (y - 34.0).powi(2)).sqrt();
+                    let band = 8.0 - ((x - y) * std::f32::consts::FRAC_1_SQRT_2 - 20.0).abs();
+                    Luma::new(0.2 + 0.6 * disc.max(band).clamp(-0.5, 0.5) + 0.3)
+                })
+                .collect(),
+        )?;
+        let small = original
+            .clone()
+            .gaussian_blur(0.8)
+            .scale(0.5, 0.5, Interpolation::Bilinear)?;
+        let error = |method| -> Result<f32> {
+            let upscaled = small.upscale(2.0, method)?;
+            assert_eq!(upscaled.dimensions(), (width, height));
+            let sum: f32 = upscaled
+                .pixels()
+                .zip(original.pixels())
+                .map(|(a, b)| (a.l - b.l).powi(2))
+                .sum();
+            Ok((sum / (width * height) as f32).sqrt())
+        };
+
+        let bicubic = error(UpscaleMethod::Bicubic)?;
+        let lanczos = error(UpscaleMethod::Lanczos)?;
+        let edge_directed = error(UpscaleMethod::EdgeDirected)?;
+        assert!(lanczos < bicubic, "{lanczos} {bicubic}");
+        assert!(edge_directed < 0.85 * bicubic, "{edge_directed} {bicubic}");
+
+        // A stand-in for a learned model, checked for the size of its output
+        let model = |image: &Image<Luma>, factor: f32| {
+            image.clone().scale(factor, factor, Interpolation::Bicubic)
+        };
+        assert!((error(UpscaleMethod::Model(&model))? - bicubic).abs() < 1e-3);
+        let identity = |image: &Image<Luma>, _: f32| Ok(image.clone());
+        assert!(matches!(
+            small.upscale(2.0, UpscaleMethod::Model(&identity)),
+            Err(Error::DimensionMismatch { .. })
+        ));
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            small
+                .upscale(2.0, UpscaleMethod::EdgeDirected)?
+                .display("edge directed upscale")?;
+        }
+
+        Ok(())
+    }
+
+    // Measure the components of a label map
+    #[test]
+    fn region_properties() -> Result<()> {
+        use crate::components::regionprops;
+        use glance_core::drawing::shapes::Circle;
+
+        // A 20x10 rectangle and a disc of radius 8, on an intensity ramp
+        let mut mask = Image::<Luma>::new(64, 40);
+        mask.paste((5, 5), &Image::solid(20, 10, Luma::new(1.0)));
+        mask.draw(Circle {
+            position: (45, 25),
+            radius: 8,
+            color: Luma::new(1.0),
+            filled: true,
+            thickness: 1,
+        })?;
+        let intensity = Image::from_data(
+            64,
+            40,
+            (0..64 * 40)
+                .map(|i| Luma::new((i % 64) as f32 / 63.0))
+                .collect(),
+        )?;
+        let labels = mask.connected_components(Connectivity::Eight);
+
+        let regions = regionprops(&labels, Some(&intensity))?;
+        assert_eq!(regions.len(), 2);
+        let (rectangle, disc) = (&regions[0], &regions[1]);
+        assert_eq!(rectangle.area, 200);
+        assert_eq!(rectangle.bounding_box, Rect::new(5, 5, 20, 10));
+        assert_eq!(rectangle.centroid, (14.5, 9.5));
+        assert!((rectangle.perimeter - 56.0).abs() < 1e-4);
+        assert!((rectangle.eccentricity - (1.0f32 - 99.0 / 399.0).sqrt()).abs() < 1e-4);
+        assert!(rectangle.orientation.abs() < 1e-3);
+        assert!((rectangle.mean_intensity.unwrap() - 14.5 / 63.0).abs() < 1e-4);
+
+        assert!(disc.eccentricity < 0.1);
+        assert!((disc.centroid.0 - 45.0).abs() < 0.1 && (disc.centroid.1 - 25.0).abs() < 0.1);
+        assert!((disc.perimeter / (2.0 * std::f32::consts::PI * 8.0) - 1.0).abs() < 0.1);
+
+        assert!(
+            regionprops(&labels, None)?
+                .iter()
+                .all(|r| r.mean_intensity.is_none())
+        );
+        assert!(regionprops(&labels, Some(&Image::new(8, 8))).is_err());
+
+        Ok(())
+    }
+
+    // Decode QR codes seen rotated and in perspective, and EAN-13 and Code 128 barcodes
+    #[cfg(feature = "codes")]
+    #[test]
+    fn detect_qr_codes_and_barcodes() -> Result<()> {
+        use crate::affine::CoordinateMap;
+        use crate::codes::{CodeDetectionExtLuma, Symbology};
+        use crate::estimation::estimate_homography;
+        use crate::geometry::distance;
+        use qrcode::{EcLevel, QrCode, Version};
+
+        // Codes are drawn light on dark so the areas uncovered by warps, which are black, end
+        // up white once inverted
+        let invert = |image: Image<Luma>| image.invert();
+        let render_qr = |code: &QrCode| {
+            let (modules, scale) = (code.width(), 4);
+            let side = (modules + 8) * scale;
+            let colors = code.to_colors();
+            let data = (0..side * side)
+                .map(|i| {
+                    let (x, y) = ((i % side) / scale, (i / side) / scale);
+                    let inside = (4..modules + 4).contains(&x) && (4..modules + 4).contains(&y);
+                    let dark = inside && colors[(y - 4) * modules + x - 4] == qrcode::Color::Dark;
+                    Luma::new(if dark { 1.0 } else { 0.0 })
+                })
+                .collect();
+            Image::from_data(side, side, data).unwrap()
+        };
+
+        let url = "https://glance.rs/docs?page=codes";
+        let rotated = invert(render_qr(&QrCode::new(url).unwrap()).rotate_about_center(
+            20.0,
+            Interpolation::Bilinear,
+            true,
+        ));
+        let codes = rotated.detect_qr_codes();
+        assert_eq!(codes.len(), 1);
+        assert_eq!(codes[0].symbology, Symbology::QrCode);
+        assert_eq!(codes[0].payload, url);
+
+        // A version 7 code, which carries its version information, seen in perspective
+        let text = "Glance decodes QR codes: 0123456789 ÄÖÜ, level Q.";
+        let code = QrCode::with_version(text, Version::Normal(7), EcLevel::Q).unwrap();
+        let mut flat = render_qr(&code);
+        // Blank out 25 data modules, left for error correction to restore
+        flat.paste((136, 64), &Image::solid(20, 20, Luma::new(0.0)));
+        let side = flat.dimensions().0 as f32;
+        let seen = [
+            (30.0, 10.0),
+            (side + 10.0, 40.0),
+            (side - 20.0, side + 30.0),
+            (0.0, side),
+        ];
+        let to_flat = estimate_homography(
+            &[(0.0, 0.0), (side, 0.0), (side, side), (0.0, side)]
+                .into_iter()
+                .zip(seen)
+                .map(|(flat, seen)| (seen, flat))
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        let map = CoordinateMap::from_fn(side as usize + 60, side as usize + 60, |x, y| {
+            transform_homography(&to_flat, (x as f32, y as f32))
+        });
+        let warped = invert(flat.remap(&map, Interpolation::Bilinear));
+        let codes = warped.detect_codes();
+        assert_eq!(codes.len(), 1);
+        assert_eq!(codes[0].payload, text);
+        for (corner, expected) in codes[0].corners.iter().zip(seen) {
+            // The quiet zone is 16 pixels wide
+            assert!(
+                distance(*corner, expected) < 40.0,
+                "{corner:?} {expected:?}"
+            );
+        }
+
+        // EAN-13 and Code 128 barcodes as bar and space widths in modules, 3 pixels each
+        let ean13 = "111 3211 1123 1114 1411 3121 1222 11111 1411 1411 1411 3112 1411 2221 111";
+        let code128 = "211214 211313 221114 121124 241112 141122 112214 122213 2331112";
+        let mut barcodes = Image::solid(400, 140, Luma::new(1.0));
+        for (row, widths) in [(10, ean13), (80, code128)] {
+            let mut x = 30;
+            for (i, width) in widths.chars().filter(|c| c.is_ascii_digit()).enumerate() {
+                let width = 3 * width.to_digit(10).unwrap() as usize;
+                if i % 2 == 0 {
+                    barcodes.paste((x, row), &Image::solid(width, 50, Luma::new(0.0)));
+                }
+                x += width;
+            }
+        }
+        let codes = barcodes.detect_barcodes();
+        let found: Vec<(Symbology, &str)> = codes
+            .iter()
+            .map(|c| (c.symbology, c.payload.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (Symbology::Ean13, "4006381333931"),
+                (Symbology::Code128, "Glance")
+            ]
+        );
+        assert_eq!(codes[0].corners[0], (30.0, 10.0));
+        // Scan lines are two pixels apart
+        assert!(distance(codes[0].corners[2], (30.0 + 95.0 * 3.0, 60.0)) <= 2.0);
+        // Turned upside down, the barcodes are read right to left
+        let flipped = barcodes
+            .clone()
+            .rotate_about_center(180.0, Interpolation::Nearest, false);
+        assert_eq!(flipped.detect_barcodes().len(), 2);
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            warped.display("qr code in perspective")?;
+            barcodes.display("barcodes")?;
+        }
+
+        Ok(())
+    }
+
+    // Blur and blend in linear light, which keeps the brightness of mixed light
+    #[test]
+    fn linear_light_filters() -> Result<()> {
+        use crate::linear_filters::LinearFilterExtLinear;
+        use crate::point_ops::PointOpsExtLinear;
+
+        // Black on the left, white on the right
+        let srgb = Image::from_data(
+            32,
+            8,
+            (0..32 * 8)
+                .map(|i| Rgba::from_luminance(if i % 32 < 16 { 0.0 } else { 1.0 }))
+                .collect(),
+        )?;
+        let linear = srgb.to_linear();
+
+        let blurred = linear.clone().gaussian_blur(3.0).to_srgb();
+        let naive = srgb.clone().gaussian_blur(3.0);
+        // Half of the light at the edge is brighter than the sRGB midpoint
+        let edge = (blurred.get_pixel((15, 4))?.r + blurred.get_pixel((16, 4))?.r) / 2.0;
+        let naive_edge = (naive.get_pixel((15, 4))?.r + naive.get_pixel((16, 4))?.r) / 2.0;
+        assert!((naive_edge - 0.5).abs() < 0.01, "{naive_edge}");
+        assert!((edge - 0.735).abs() < 0.01, "{edge}");
+
+        // The kernel paths filter the linear values like those of any other image
+        let kernel = kernels::gaussian(1.5);
+        let values = Image::from_data(32, 8, linear.pixels().map(|p| p.0).collect())?;
+        let expected = values.convolve_2d(&kernel, BorderMode::Reflect)?;
+        for result in [
+            linear.clone().convolve_2d(&kernel, BorderMode::Reflect)?,
+            linear.clone().convolve_fft(&kernel, BorderMode::Reflect)?,
+        ] {
+            assert!(
+                result
+                    .pixels()
+                    .zip(expected.pixels())
+                    .all(|(a, b)| (a.0.r - b.r).abs() < 1e-4 && (a.0.a - b.a).abs() < 1e-4)
+            );
+        }
+
+        let black = Image::<Rgba>::new(4, 4).to_linear();
+        assert!(matches!(
+            black.clone().lerp(&linear, 0.5),
+            Err(Error::DimensionMismatch { .. })
+        ));
+        let white = Image::from_data(4, 4, vec![Rgba::from_luminance(1.0); 16])?.to_linear();
+        let mixed = black.lerp(&white, 0.5)?.to_srgb();
+        assert!((mixed.get_pixel((0, 0))?.r - 0.735).abs() < 0.01);
+
+        if std::env::var("NO_DISPLAY").is_err() {
+            blurred.display("linear_light_filters")?;
+        }
+
+        Ok(())
+    }
 }
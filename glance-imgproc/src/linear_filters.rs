@@ -0,0 +1,646 @@
+use glance_core::img::{
+    Image,
+    pixel::{Linear, Luma, Pixel, Rgba, SrgbPixel},
+};
+use glance_core::par::*;
+use glance_core::profiling::OpSpan;
+
+use crate::color::{linear_to_srgb, srgb_to_linear};
+use crate::error::{Error, Result};
+use crate::fft::{Complex, fft_2d};
+use crate::kernels::{self, ApertureShape};
+
+/// Kernels with more taps than this are convolved in the frequency domain by `filter()`.
+const FFT_KERNEL_AREA_THRESHOLD: usize = 15 * 15;
+
+/// Rows of output convolved by one parallel task, so that the source rows it reads stay in
+/// cache.
+const CONVOLUTION_TILE_ROWS: usize = 16;
+
+/// Linear intensity above which `lens_blur()` treats values as clipped highlights.
+const HIGHLIGHT_KNEE: f32 = 0.8;
+
+/// Converts a value to linear light and expands highlights for `lens_blur()`.
+fn expand_highlight(value: f32, boost: f32) -> f32 {
+    let linear = srgb_to_linear(value.clamp(0.0, 1.0));
+    linear + boost * (linear - HIGHLIGHT_KNEE).max(0.0) / (1.0 - HIGHLIGHT_KNEE)
+}
+
+/// Inverse of [`expand_highlight`] after blurring, saturating the spread highlights.
+fn compress_highlight(value: f32) -> f32 {
+    linear_to_srgb(value.clamp(0.0, 1.0))
+}
+
+/// Defines how pixels outside of the image are sampled by neighbourhood operations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BorderMode {
+    /// Out of bounds pixels take the given constant intensity.
+    Constant(f32),
+    /// The nearest edge pixel is repeated: `aaa|abcd|ddd`.
+    Replicate,
+    /// The image is mirrored without repeating the edge pixel: `dcb|abcd|cba`.
+    Reflect,
+    /// The image wraps around: `bcd|abcd|abc`.
+    Wrap,
+}
+
+impl BorderMode {
+    /// Maps a possibly out of bounds coordinate onto the image axis of length `len`.
+    /// Returns `None` when the coordinate should be sampled from the constant border.
+    pub(crate) fn resolve(&self, coord: isize, len: usize) -> Option<usize> {
+        let len = len as isize;
+        if (0..len).contains(&coord) {
+            return Some(coord as usize);
+        }
+
+        match self {
+            BorderMode::Constant(_) => None,
+            BorderMode::Replicate => Some(coord.clamp(0, len - 1) as usize),
+            BorderMode::Reflect => {
+                if len == 1 {
+                    return Some(0);
+                }
+                let period = 2 * (len - 1);
+                let c = coord.rem_euclid(period);
+                Some(if c < len { c } else { period - c } as usize)
+            }
+            BorderMode::Wrap => Some(coord.rem_euclid(len) as usize),
+        }
+    }
+}
+
+/// Checks that a kernel of the given dimensions has odd sides and fits into the image on at
+/// least one axis.
+pub(crate) fn check_kernel(image: (usize, usize), kernel: (usize, usize)) -> Result<()> {
+    let (width, height) = kernel;
+    if width.is_multiple_of(2) || height.is_multiple_of(2) {
+        return Err(Error::KernelSizeEven { width, height });
+    }
+    if width > image.0 && height > image.1 {
+        return Err(Error::ImageSmallerThanKernel { image, kernel });
+    }
+    Ok(())
+}
+
+/// Correlates a single channel plane with `kernel` by multiplying their spectra.
+/// The plane is extended by half the kernel size according to `border` before the transform,
+/// so the result matches the spatial convolution.
+fn convolve_plane_fft(
+    plane: &[f32],
+    dimensions: (usize, usize),
+    kernel: &Image<Luma>,
+    border: BorderMode,
+) -> Vec<f32> {
+    let (width, height) = dimensions;
+    let (k_width, k_height) = kernel.dimensions();
+    let (half_w, half_h) = (k_width / 2, k_height / 2);
+    let (padded_w, padded_h) = (width + 2 * half_w, height + 2 * half_h);
+    let fft_w = (padded_w + k_width - 1).next_power_of_two();
+    let fft_h = (padded_h + k_height - 1).next_power_of_two();
+
+    let mut signal = vec![Complex::default(); fft_w * fft_h];
+    for py in 0..padded_h {
+        for px in 0..padded_w {
+            let sx = border.resolve(px as isize - half_w as isize, width);
+            let sy = border.resolve(py as isize - half_h as isize, height);
+            let value = match (sx, sy, border) {
+                (Some(sx), Some(sy), _) => plane[sy * width + sx],
+                (_, _, BorderMode::Constant(value)) => value,
+                _ => 0.0,
+            };
+            signal[py * fft_w + px].re = value as f64;
+        }
+    }
+
+    // The kernel is flipped so the product of the spectra yields a correlation
+    let mut response = vec![Complex::default(); fft_w * fft_h];
+    for (idx, weight) in kernel.pixels().enumerate() {
+        let (kx, ky) = (idx % k_width, idx / k_width);
+        response[(k_height - 1 - ky) * fft_w + (k_width - 1 - kx)].re = weight.l as f64;
+    }
+
+    fft_2d(&mut signal, fft_w, fft_h, false);
+    fft_2d(&mut response, fft_w, fft_h, false);
+    signal
+        .iter_mut()
+        .zip(&response)
+        .for_each(|(s, r)| *s = *s * *r);
+    fft_2d(&mut signal, fft_w, fft_h, true);
+
+    let mut output = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            output.push(signal[(y + k_height - 1) * fft_w + x + k_width - 1].re as f32);
+        }
+    }
+    output
+}
+
+/// Correlates a single channel plane with the separable kernel `row` x `column`, one axis at a
+/// time. Both kernels must have an odd length.
+pub(crate) fn convolve_plane_separable(
+    plane: &[f32],
+    dimensions: (usize, usize),
+    row: &[f32],
+    column: &[f32],
+    border: BorderMode,
+) -> Vec<f32> {
+    let (width, height) = dimensions;
+    let constant = match border {
+        BorderMode::Constant(value) => value,
+        _ => 0.0,
+    };
+
+    let pass = |input: &[f32], kernel: &[f32], horizontal: bool| -> Vec<f32> {
+        let half = (kernel.len() / 2) as isize;
+        (0..width * height)
+            .into_par_iter()
+            .map(|idx| {
+                let (x, y) = ((idx % width) as isize, (idx / width) as isize);
+                kernel
+                    .iter()
+                    .enumerate()
+                    .map(|(k, weight)| {
+                        let offset = k as isize - half;
+                        let value = if horizontal {
+                            border
+                                .resolve(x + offset, width)
+                                .map(|sx| input[y as usize * width + sx])
+                        } else {
+                            border
+                                .resolve(y + offset, height)
+                                .map(|sy| input[sy * width + x as usize])
+                        };
+                        weight * value.unwrap_or(constant)
+                    })
+                    .sum()
+            })
+            .collect()
+    };
+
+    pass(&pass(plane, row, true), column, false)
+}
+
+/// Unchecked convolutions shared by the extension traits, which validate the kernel first, and
+/// the filters building their own kernels, which may be larger than the image.
+trait Convolution: Sized {
+    fn convolve_spatial(self, kernel: &Image<Luma>, border: BorderMode) -> Self;
+    fn convolve_frequency(self, kernel: &Image<Luma>, border: BorderMode) -> Self;
+
+    /// Picks the spatial implementation for small kernels and the FFT based one for large
+    /// kernels.
+    fn convolve_auto(self, kernel: &Image<Luma>, border: BorderMode) -> Self {
+        let (k_width, k_height) = kernel.dimensions();
+        if k_width * k_height > FFT_KERNEL_AREA_THRESHOLD {
+            self.convolve_frequency(kernel, border)
+        } else {
+            self.convolve_spatial(kernel, border)
+        }
+    }
+}
+
+/// Shared spatial convolution of the pixels in `data`, a `width` x `height` image. Pixels of
+/// the image interior, whose kernel window lies within the image, are summed from contiguous row
+/// slices; only the pixels near the edges resolve their taps through `border`, taking `fill`
+/// for the constant border. Output is computed in tiles of [`CONVOLUTION_TILE_ROWS`] rows in
+/// parallel. `mul_add(sum, weight, value)` accumulates a weighted pixel onto `sum`.
+fn convolve_pixels<T: Copy + Send + Sync>(
+    data: &[T],
+    (width, height): (usize, usize),
+    kernel: &Image<Luma>,
+    border: BorderMode,
+    (zero, fill): (T, T),
+    mul_add: impl Fn(T, f32, T) -> T + Sync,
+) -> Vec<T> {
+    let mut output = vec![zero; width * height];
+    if output.is_empty() {
+        return output;
+    }
+    let (k_width, k_height) = kernel.dimensions();
+    let weights: Vec<f32> = kernel.pixels().map(|p| p.l).collect();
+    let (half_w, half_h) = (k_width / 2, k_height / 2);
+    // Columns whose kernel window lies within the row
+    let x0 = half_w.min(width);
+    let x1 = width.saturating_sub(half_w).max(x0);
+
+    let at_border = |x: usize, y: usize| {
+        let mut sum = zero;
+        for (ky, taps) in weights.chunks_exact(k_width).enumerate() {
+            let sy = border.resolve((y + ky) as isize - half_h as isize, height);
+            for (kx, &weight) in taps.iter().enumerate() {
+                let sx = border.resolve((x + kx) as isize - half_w as isize, width);
+                let value = match (sx, sy) {
+                    (Some(sx), Some(sy)) => data[sy * width + sx],
+                    _ => fill,
+                };
+                sum = mul_add(sum, weight, value);
+            }
+        }
+        sum
+    };
+    let inside = |x: usize, y: usize| {
+        let mut sum = zero;
+        for (ky, taps) in weights.chunks_exact(k_width).enumerate() {
+            let start = (y + ky - half_h) * width + x - half_w;
+            for (&weight, &value) in taps.iter().zip(&data[start..start + k_width]) {
+                sum = mul_add(sum, weight, value);
+            }
+        }
+        sum
+    };
+
+    output
+        .par_chunks_mut(width * CONVOLUTION_TILE_ROWS)
+        .enumerate()
+        .for_each(|(tile, rows)| {
+            for (i, row) in rows.chunks_mut(width).enumerate() {
+                let y = tile * CONVOLUTION_TILE_ROWS + i;
+                if y < half_h || y + half_h >= height {
+                    row.iter_mut()
+                        .enumerate()
+                        .for_each(|(x, out)| *out = at_border(x, y));
+                    continue;
+                }
+                let (left, rest) = row.split_at_mut(x0);
+                let (middle, right) = rest.split_at_mut(x1 - x0);
+                left.iter_mut()
+                    .enumerate()
+                    .for_each(|(x, out)| *out = at_border(x, y));
+                middle
+                    .iter_mut()
+                    .enumerate()
+                    .for_each(|(x, out)| *out = inside(x0 + x, y));
+                right
+                    .iter_mut()
+                    .enumerate()
+                    .for_each(|(x, out)| *out = at_border(x1 + x, y));
+            }
+        });
+    output
+}
+
+/// Returns the value of the constant border, or 0.0 for the other modes whose taps all resolve
+/// into the image.
+fn border_fill(border: BorderMode) -> f32 {
+    match border {
+        BorderMode::Constant(value) => value,
+        _ => 0.0,
+    }
+}
+
+impl Convolution for Image<Luma> {
+    fn convolve_spatial(self, kernel: &Image<Luma>, border: BorderMode) -> Self {
+        let (width, height) = self.dimensions();
+        let _span = OpSpan::enter("convolve_spatial", (width, height));
+        let convolved = convolve_pixels(
+            self.as_slice(),
+            (width, height),
+            kernel,
+            border,
+            (Luma::new(0.0), Luma::new(border_fill(border))),
+            |sum, weight, value| Luma::new(sum.l + weight * value.l),
+        );
+        Image::from_data(width, height, convolved).unwrap()
+    }
+
+    fn convolve_frequency(self, kernel: &Image<Luma>, border: BorderMode) -> Self {
+        let (width, height) = self.dimensions();
+        let _span = OpSpan::enter("convolve_frequency", (width, height));
+
+        let plane: Vec<f32> = self.pixels().map(|p| p.l).collect();
+        let convolved = convolve_plane_fft(&plane, (width, height), kernel, border)
+            .into_iter()
+            .map(|l| Luma { l })
+            .collect();
+
+        Image::from_data(width, height, convolved).unwrap()
+    }
+}
+
+impl Convolution for Image<Rgba> {
+    fn convolve_spatial(self, kernel: &Image<Luma>, border: BorderMode) -> Self {
+        let (width, height) = self.dimensions();
+        let _span = OpSpan::enter("convolve_spatial", (width, height));
+        let fill = border_fill(border);
+        let convolved = convolve_pixels(
+            self.as_slice(),
+            (width, height),
+            kernel,
+            border,
+            (
+                Rgba::new(0.0, 0.0, 0.0, 0.0),
+                Rgba::new(fill, fill, fill, fill),
+            ),
+            |sum, weight, value| Rgba {
+                r: sum.r + weight * value.r,
+                g: sum.g + weight * value.g,
+                b: sum.b + weight * value.b,
+                a: sum.a + weight * value.a,
+            },
+        );
+        Image::from_data(width, height, convolved).unwrap()
+    }
+
+    fn convolve_frequency(self, kernel: &Image<Luma>, border: BorderMode) -> Self {
+        let (width, height) = self.dimensions();
+        let _span = OpSpan::enter("convolve_frequency", (width, height));
+
+        let channel = |f: fn(&Rgba) -> f32| -> Vec<f32> {
+            let plane: Vec<f32> = self.pixels().map(|p| f(&p)).collect();
+            convolve_plane_fft(&plane, (width, height), kernel, border)
+        };
+        let (r, g, b, a) = (
+            channel(|p| p.r),
+            channel(|p| p.g),
+            channel(|p| p.b),
+            channel(|p| p.a),
+        );
+        let convolved = (0..width * height)
+            .map(|i| Rgba {
+                r: r[i],
+                g: g[i],
+                b: b[i],
+                a: a[i],
+            })
+            .collect();
+
+        Image::from_data(width, height, convolved).unwrap()
+    }
+}
+
+/// Rebuilds an image from one filtered plane per channel, see [`Pixel::channel`].
+fn map_planes<P: Pixel>(image: &Image<P>, filter: impl Fn(&[f32]) -> Vec<f32>) -> Image<P> {
+    let (width, height) = image.dimensions();
+    let planes: Vec<Vec<f32>> = (0..P::channel_count())
+        .map(|c| filter(&image.pixels().map(|p| p.channel(c)).collect::<Vec<_>>()))
+        .collect();
+    let data = (0..width * height)
+        .map(|i| {
+            let mut pixel = P::new();
+            for (c, plane) in planes.iter().enumerate() {
+                pixel.set_channel(c, plane[i]);
+            }
+            pixel
+        })
+        .collect();
+    Image::from_data(width, height, data).unwrap()
+}
+
+impl<P: SrgbPixel> Convolution for Image<Linear<P>> {
+    fn convolve_spatial(self, kernel: &Image<Luma>, border: BorderMode) -> Self {
+        let (width, height) = self.dimensions();
+        let _span = OpSpan::enter("convolve_spatial", (width, height));
+        let zero = Linear::<P>::new().map(|_| 0.0);
+        let convolved = convolve_pixels(
+            self.as_slice(),
+            (width, height),
+            kernel,
+            border,
+            (zero, zero.map(|_| border_fill(border))),
+            |sum, weight, value| sum.zip_map(&value, |s, v| s + weight * v),
+        );
+        Image::from_data(width, height, convolved).unwrap()
+    }
+
+    fn convolve_frequency(self, kernel: &Image<Luma>, border: BorderMode) -> Self {
+        let dimensions = self.dimensions();
+        let _span = OpSpan::enter("convolve_frequency", dimensions);
+        map_planes(&self, |plane| {
+            convolve_plane_fft(plane, dimensions, kernel, border)
+        })
+    }
+}
+
+/// Extension trait for [`glance_core::img::Image`] to provide linear filters for Luma images
+pub trait LinearFilterExtLuma {
+    fn convolve_2d(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Luma>>;
+    fn convolve_fft(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Luma>>;
+    fn filter(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Luma>>;
+    fn motion_blur(self, length: usize, angle: f32) -> Image<Luma>;
+    fn gaussian_blur(self, sigma: f32) -> Image<Luma>;
+    fn lens_blur(self, radius: f32, shape: ApertureShape, highlight_boost: f32) -> Image<Luma>;
+}
+
+/// Extension trait for [`glance_core::img::Image`] to provide linear filters for RGBA images
+pub trait LinearFilterExtRgba {
+    fn convolve_2d(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Rgba>>;
+    fn convolve_fft(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Rgba>>;
+    fn filter(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Rgba>>;
+    fn motion_blur(self, length: usize, angle: f32) -> Image<Rgba>;
+    fn gaussian_blur(self, sigma: f32) -> Image<Rgba>;
+    fn lens_blur(self, radius: f32, shape: ApertureShape, highlight_boost: f32) -> Image<Rgba>;
+}
+
+/// Extension trait for [`glance_core::img::Image`] to provide linear filters for images in
+/// linear light, see [`Image::to_linear`]. Weighted sums of linear values mix light like an
+/// optical blur, so edges between bright and dark areas keep their brightness. There is no
+/// `lens_blur`, which already works in linear light on sRGB images.
+pub trait LinearFilterExtLinear<P: SrgbPixel> {
+    fn convolve_2d(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Linear<P>>>;
+    fn convolve_fft(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Linear<P>>>;
+    fn filter(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Linear<P>>>;
+    fn motion_blur(self, length: usize, angle: f32) -> Image<Linear<P>>;
+    fn gaussian_blur(self, sigma: f32) -> Image<Linear<P>>;
+}
+
+impl LinearFilterExtLuma for Image<Luma> {
+    /// Convolves the image with the given kernel. The kernel must have odd dimensions and may
+    /// not exceed the image on both axes, otherwise an error is returned. It is centered on each
+    /// pixel. The kernel is not flipped (i.e. this computes a correlation, as
+    /// most image libraries do). Pixels outside the image are sampled according to `border`.
+    /// The values are weighted as they are stored, sRGB encoded for images loaded from files;
+    /// see [`LinearFilterExtLinear`] for filtering in linear light.
+    fn convolve_2d(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Luma>> {
+        check_kernel(self.dimensions(), kernel.dimensions())?;
+        Ok(self.convolve_spatial(kernel, border))
+    }
+
+    /// Convolves the image with the given kernel in the frequency domain. Produces the same
+    /// result as [`LinearFilterExtLuma::convolve_2d`] (up to floating point error), but the cost
+    /// does not grow with the kernel size, which pays off for kernels larger than ~15x15.
+    fn convolve_fft(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Luma>> {
+        check_kernel(self.dimensions(), kernel.dimensions())?;
+        Ok(self.convolve_frequency(kernel, border))
+    }
+
+    /// Convolves the image with the given kernel, picking the spatial implementation for small
+    /// kernels and the FFT based one for large kernels.
+    fn filter(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Luma>> {
+        check_kernel(self.dimensions(), kernel.dimensions())?;
+        Ok(self.convolve_auto(kernel, border))
+    }
+
+    /// Simulates linear camera motion of `length` pixels along `angle` (degrees,
+    /// counter-clockwise). See [`kernels::motion_blur_kernel`].
+    fn motion_blur(self, length: usize, angle: f32) -> Image<Luma> {
+        self.convolve_auto(
+            &kernels::motion_blur_kernel(length, angle),
+            BorderMode::Replicate,
+        )
+    }
+
+    /// Blurs the image with a Gaussian of standard deviation `sigma` pixels, using two 1D
+    /// passes and replicated borders. A `sigma` of 0 or less returns the image unchanged.
+    /// Blurring sRGB values darkens edges between bright and dark areas, blur images converted
+    /// with [`Image::to_linear`] for a physically correct result, see [`LinearFilterExtLinear`].
+    fn gaussian_blur(self, sigma: f32) -> Image<Luma> {
+        if sigma <= 0.0 {
+            return self;
+        }
+        let dimensions = self.dimensions();
+        let kernel = kernels::gaussian_1d(sigma);
+        let plane: Vec<f32> = self.pixels().map(|p| p.l).collect();
+        let blurred =
+            convolve_plane_separable(&plane, dimensions, &kernel, &kernel, BorderMode::Replicate);
+
+        let data = blurred.into_iter().map(|l| Luma { l }).collect();
+        Image::from_data(dimensions.0, dimensions.1, data).unwrap()
+    }
+
+    /// Simulates an out of focus lens with an aperture of the given `shape` and `radius` in
+    /// pixels, see [`kernels::aperture_kernel`]. Unlike a Gaussian, it spreads highlights into
+    /// sharp edged discs or polygons. The blur works in linear light, and `highlight_boost`
+    /// amplifies the values near white, which are usually clipped in the capture, so that they
+    /// stay bright when spread out. 0.0 disables the boost.
+    fn lens_blur(mut self, radius: f32, shape: ApertureShape, highlight_boost: f32) -> Image<Luma> {
+        let kernel = kernels::aperture_kernel(radius, shape);
+        self.pixels_mut()
+            .for_each(|p| p.l = expand_highlight(p.l, highlight_boost));
+        let mut blurred = self.convolve_auto(&kernel, BorderMode::Replicate);
+        blurred
+            .pixels_mut()
+            .for_each(|p| p.l = compress_highlight(p.l));
+        blurred
+    }
+}
+
+impl LinearFilterExtRgba for Image<Rgba> {
+    /// Convolves every channel (including alpha) of the image with the given kernel.
+    /// See [`LinearFilterExtLuma::convolve_2d`] for the kernel conventions and the encoding.
+    fn convolve_2d(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Rgba>> {
+        check_kernel(self.dimensions(), kernel.dimensions())?;
+        Ok(self.convolve_spatial(kernel, border))
+    }
+
+    /// Convolves every channel (including alpha) of the image with the given kernel in the
+    /// frequency domain. See [`LinearFilterExtLuma::convolve_fft`].
+    fn convolve_fft(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Rgba>> {
+        check_kernel(self.dimensions(), kernel.dimensions())?;
+        Ok(self.convolve_frequency(kernel, border))
+    }
+
+    /// Convolves the image with the given kernel, picking the spatial implementation for small
+    /// kernels and the FFT based one for large kernels.
+    fn filter(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Rgba>> {
+        check_kernel(self.dimensions(), kernel.dimensions())?;
+        Ok(self.convolve_auto(kernel, border))
+    }
+
+    /// Simulates linear camera motion of `length` pixels along `angle` (degrees,
+    /// counter-clockwise). See [`kernels::motion_blur_kernel`].
+    fn motion_blur(self, length: usize, angle: f32) -> Image<Rgba> {
+        self.convolve_auto(
+            &kernels::motion_blur_kernel(length, angle),
+            BorderMode::Replicate,
+        )
+    }
+
+    /// Blurs every channel (including alpha) with a Gaussian of standard deviation `sigma`.
+    /// See [`LinearFilterExtLuma::gaussian_blur`], also for the encoding.
+    fn gaussian_blur(self, sigma: f32) -> Image<Rgba> {
+        if sigma <= 0.0 {
+            return self;
+        }
+        let (width, height) = self.dimensions();
+        let kernel = kernels::gaussian_1d(sigma);
+        let channel = |f: fn(&Rgba) -> f32| -> Vec<f32> {
+            let plane: Vec<f32> = self.pixels().map(|p| f(&p)).collect();
+            convolve_plane_separable(
+                &plane,
+                (width, height),
+                &kernel,
+                &kernel,
+                BorderMode::Replicate,
+            )
+        };
+        let (r, g, b, a) = (
+            channel(|p| p.r),
+            channel(|p| p.g),
+            channel(|p| p.b),
+            channel(|p| p.a),
+        );
+        let blurred = (0..width * height)
+            .map(|i| Rgba {
+                r: r[i],
+                g: g[i],
+                b: b[i],
+                a: a[i],
+            })
+            .collect();
+
+        Image::from_data(width, height, blurred).unwrap()
+    }
+
+    /// Blurs the color channels like [`LinearFilterExtLuma::lens_blur`]. Alpha is blurred with
+    /// the same aperture but without the highlight treatment.
+    fn lens_blur(mut self, radius: f32, shape: ApertureShape, highlight_boost: f32) -> Image<Rgba> {
+        let kernel = kernels::aperture_kernel(radius, shape);
+        self.pixels_mut().for_each(|p| {
+            p.r = expand_highlight(p.r, highlight_boost);
+            p.g = expand_highlight(p.g, highlight_boost);
+            p.b = expand_highlight(p.b, highlight_boost);
+        });
+        let mut blurred = self.convolve_auto(&kernel, BorderMode::Replicate);
+        blurred.pixels_mut().for_each(|p| {
+            p.r = compress_highlight(p.r);
+            p.g = compress_highlight(p.g);
+            p.b = compress_highlight(p.b);
+        });
+        blurred
+    }
+}
+
+impl<P: SrgbPixel> LinearFilterExtLinear<P> for Image<Linear<P>> {
+    /// Convolves every channel (including alpha) of the image with the given kernel.
+    /// See [`LinearFilterExtLuma::convolve_2d`] for the kernel conventions.
+    fn convolve_2d(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Linear<P>>> {
+        check_kernel(self.dimensions(), kernel.dimensions())?;
+        Ok(self.convolve_spatial(kernel, border))
+    }
+
+    /// Convolves every channel (including alpha) of the image with the given kernel in the
+    /// frequency domain. See [`LinearFilterExtLuma::convolve_fft`].
+    fn convolve_fft(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Linear<P>>> {
+        check_kernel(self.dimensions(), kernel.dimensions())?;
+        Ok(self.convolve_frequency(kernel, border))
+    }
+
+    /// Convolves the image with the given kernel, picking the spatial implementation for small
+    /// kernels and the FFT based one for large kernels.
+    fn filter(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Linear<P>>> {
+        check_kernel(self.dimensions(), kernel.dimensions())?;
+        Ok(self.convolve_auto(kernel, border))
+    }
+
+    /// Simulates linear camera motion of `length` pixels along `angle` (degrees,
+    /// counter-clockwise). See [`kernels::motion_blur_kernel`].
+    fn motion_blur(self, length: usize, angle: f32) -> Image<Linear<P>> {
+        self.convolve_auto(
+            &kernels::motion_blur_kernel(length, angle),
+            BorderMode::Replicate,
+        )
+    }
+
+    /// Blurs every channel (including alpha) with a Gaussian of standard deviation `sigma`.
+    /// See [`LinearFilterExtLuma::gaussian_blur`].
+    fn gaussian_blur(self, sigma: f32) -> Image<Linear<P>> {
+        if sigma <= 0.0 {
+            return self;
+        }
+        let dimensions = self.dimensions();
+        let kernel = kernels::gaussian_1d(sigma);
+        map_planes(&self, |plane| {
+            convolve_plane_separable(plane, dimensions, &kernel, &kernel, BorderMode::Replicate)
+        })
+    }
+}
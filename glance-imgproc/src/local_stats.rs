@@ -0,0 +1,157 @@
+use glance_core::img::{Image, Rect, pixel::Luma};
+use glance_core::par::*;
+
+use crate::integral::{IntegralImage, IntegralImageExtLuma};
+
+/// Number of intensity levels the local entropy is computed over.
+const ENTROPY_BINS: usize = 256;
+
+/// Returns the (2 * radius + 1)² window around (x, y), clipped at the top-left border.
+/// The integral image clips the bottom-right side itself.
+fn window(x: usize, y: usize, radius: usize) -> Rect {
+    let (x0, y0) = (x.saturating_sub(radius), y.saturating_sub(radius));
+    Rect::new(x0, y0, x + radius + 1 - x0, y + radius + 1 - y0)
+}
+
+/// Histogram of a sliding window that keeps the sum of n * log2(n) over its bins up to date,
+/// so the entropy can be read without visiting every bin.
+struct SlidingHistogram<'a> {
+    bins: [usize; ENTROPY_BINS],
+    total: usize,
+    sum: f64,
+    /// Precomputed n * log2(n) for every possible bin count
+    n_log_n: &'a [f64],
+}
+
+impl<'a> SlidingHistogram<'a> {
+    fn new(n_log_n: &'a [f64]) -> Self {
+        SlidingHistogram {
+            bins: [0; ENTROPY_BINS],
+            total: 0,
+            sum: 0.0,
+            n_log_n,
+        }
+    }
+
+    fn add(&mut self, levels: impl Iterator<Item = usize>) {
+        for level in levels {
+            self.sum += self.n_log_n[self.bins[level] + 1] - self.n_log_n[self.bins[level]];
+            self.bins[level] += 1;
+            self.total += 1;
+        }
+    }
+
+    fn remove(&mut self, levels: impl Iterator<Item = usize>) {
+        for level in levels {
+            self.sum += self.n_log_n[self.bins[level] - 1] - self.n_log_n[self.bins[level]];
+            self.bins[level] -= 1;
+            self.total -= 1;
+        }
+    }
+
+    /// H = log2(N) - sum(n * log2(n)) / N
+    fn entropy(&self) -> f64 {
+        let n = self.total as f64;
+        (n.log2() - self.sum / n).max(0.0)
+    }
+}
+
+/// Extension trait for [`glance_core::img::Image`] to provide sliding window statistics for
+/// Luma images. Windows are (2 * radius + 1)² pixels; near the borders only the pixels inside
+/// the image are used.
+pub trait LocalStatsExtLuma {
+    fn local_variance(&self, radius: usize) -> Image<Luma>;
+    fn local_std(&self, radius: usize) -> Image<Luma>;
+    fn local_entropy(&self, radius: usize) -> Image<Luma>;
+}
+
+impl LocalStatsExtLuma for Image<Luma> {
+    /// Returns the variance of every window, computed in constant time per pixel from the
+    /// integral images of the values and of their squares.
+    fn local_variance(&self, radius: usize) -> Image<Luma> {
+        let (width, height) = self.dimensions();
+        let sums = self.integral_image();
+        let squares: Image<Luma> = Image::from_data(
+            width,
+            height,
+            self.pixels().map(|p| Luma { l: p.l * p.l }).collect(),
+        )
+        .unwrap();
+        let squares = IntegralImage::from(&squares);
+
+        let variance = (0..width * height)
+            .into_par_iter()
+            .map(|idx| {
+                let rect = window(idx % width, idx / width, radius);
+                let mean = sums.mean_region(rect);
+                // E[x²] - E[x]², clamped against rounding errors
+                Luma {
+                    l: (squares.mean_region(rect) - mean * mean).max(0.0) as f32,
+                }
+            })
+            .collect();
+
+        Image::from_data(width, height, variance).unwrap()
+    }
+
+    /// Returns the standard deviation of every window. See [`LocalStatsExtLuma::local_variance`].
+    fn local_std(&self, radius: usize) -> Image<Luma> {
+        let (width, height) = self.dimensions();
+        let std = self
+            .local_variance(radius)
+            .pixels()
+            .map(|p| Luma { l: p.l.sqrt() })
+            .collect();
+        Image::from_data(width, height, std).unwrap()
+    }
+
+    /// Returns the Shannon entropy in bits of the intensity histogram of every window, with
+    /// intensities in [0.0, 1.0] quantized to 256 levels. Ranges from 0.0 for flat areas to 8.0.
+    /// Uses a histogram sliding along each row, updating the entropy incrementally.
+    fn local_entropy(&self, radius: usize) -> Image<Luma> {
+        let (width, height) = self.dimensions();
+        let levels: Vec<usize> = self
+            .pixels()
+            .map(|p| ((p.l.clamp(0.0, 1.0) * (ENTROPY_BINS - 1) as f32).round()) as usize)
+            .collect();
+        // n * log2(n) for every possible bin count
+        let max_count = (2 * radius + 1).pow(2);
+        let n_log_n: Vec<f64> = (0..=max_count)
+            .map(|n| {
+                if n == 0 {
+                    0.0
+                } else {
+                    n as f64 * (n as f64).log2()
+                }
+            })
+            .collect();
+
+        let levels = &levels;
+        let entropy = (0..height)
+            .into_par_iter()
+            .flat_map_iter(|y| {
+                let rows = y.saturating_sub(radius)..(y + radius + 1).min(height);
+                let mut histogram = SlidingHistogram::new(&n_log_n);
+                let column = |x: usize| rows.clone().map(move |row| levels[row * width + x]);
+
+                (0..radius.min(width)).for_each(|x| histogram.add(column(x)));
+                (0..width)
+                    .map(|x| {
+                        // Remove first so bin counts never exceed the window size
+                        if x > radius {
+                            histogram.remove(column(x - radius - 1));
+                        }
+                        if x + radius < width {
+                            histogram.add(column(x + radius));
+                        }
+                        Luma {
+                            l: histogram.entropy() as f32,
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        Image::from_data(width, height, entropy).unwrap()
+    }
+}
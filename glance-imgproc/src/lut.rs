@@ -0,0 +1,155 @@
+//! Chains of tone adjustments collapsed into a lookup table. A [`PointOpChain`] records
+//! invert, gamma, brightness and contrast steps, simplifies them where that is exact, and
+//! evaluates the whole chain once for each of the 256 levels of an 8 bit channel. Applying the
+//! chain is then a single table lookup per channel, however long the chain is.
+
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Rgba, to_u8},
+};
+use glance_core::par::*;
+
+/// A tone adjustment of a [`PointOpChain`], matching the operation of the same name in
+/// [`crate::point_ops`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LutOp {
+    /// `1 - v`
+    Invert,
+    /// `v ^ (1 / gamma)`
+    Gamma(f32),
+    /// `v + brightness`, clamped to [0.0, 1.0]
+    Brightness(f32),
+    /// `v * contrast`, clamped to [0.0, 1.0]
+    Contrast(f32),
+}
+
+impl LutOp {
+    fn apply(&self, v: f32) -> f32 {
+        match *self {
+            LutOp::Invert => 1.0 - v,
+            LutOp::Gamma(gamma) => v.powf(1.0 / gamma),
+            LutOp::Brightness(brightness) => (v + brightness).clamp(0.0, 1.0),
+            LutOp::Contrast(contrast) => (v * contrast).clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// A recorded chain of tone adjustments, built with chained calls and executed as one lookup
+/// table pass. Color channels are adjusted, alpha is kept.
+///
+/// The table has an entry for each 8 bit level, so the results match the equivalent calls of
+/// [`crate::point_ops::PointOpsExtRgba`] exactly for images decoded from 8 bit files. Other
+/// values are rounded to the nearest level first.
+///
+/// ```
+/// # use glance_core::img::{Image, pixel::Rgba};
+/// # use glance_imgproc::lut::PointOpChain;
+/// let chain = PointOpChain::new().gamma(2.2).brightness(0.1).contrast(1.2);
+///
+/// let image = Image::solid(8, 8, Rgba::new(0.2, 0.4, 0.6, 1.0));
+/// let adjusted = chain.run(&image);
+/// assert_eq!(adjusted.dimensions(), (8, 8));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PointOpChain {
+    ops: Vec<LutOp>,
+}
+
+impl PointOpChain {
+    /// Creates an empty chain, which leaves images unchanged.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends an operation, merging it with the last one where the result is the same:
+    /// consecutive gammas multiply and consecutive inversions cancel.
+    pub fn push(mut self, op: LutOp) -> Self {
+        match (self.ops.last().copied(), op) {
+            (Some(LutOp::Gamma(first)), LutOp::Gamma(second)) => {
+                *self.ops.last_mut().unwrap() = LutOp::Gamma(first * second);
+            }
+            (Some(LutOp::Invert), LutOp::Invert) => {
+                self.ops.pop();
+            }
+            _ => self.ops.push(op),
+        }
+        self
+    }
+
+    /// See [`crate::point_ops::PointOpsExtRgba::invert`].
+    pub fn invert(self) -> Self {
+        self.push(LutOp::Invert)
+    }
+
+    /// See [`crate::point_ops::PointOpsExtRgba::gamma`].
+    pub fn gamma(self, gamma: f32) -> Self {
+        self.push(LutOp::Gamma(gamma))
+    }
+
+    /// See [`crate::point_ops::PointOpsExtRgba::brightness`].
+    pub fn brightness(self, brightness: f32) -> Self {
+        self.push(LutOp::Brightness(brightness))
+    }
+
+    /// See [`crate::point_ops::PointOpsExtRgba::contrast`].
+    pub fn contrast(self, contrast: f32) -> Self {
+        self.push(LutOp::Contrast(contrast))
+    }
+
+    /// Returns the operations of the chain after simplification.
+    pub fn ops(&self) -> &[LutOp] {
+        &self.ops
+    }
+
+    /// Applies the chain to a single value, without the table.
+    pub fn eval(&self, value: f32) -> f32 {
+        self.ops.iter().fold(value, |v, op| op.apply(v))
+    }
+
+    /// Returns the result of the chain for every 8 bit level, i.e. for the values `i / 255`.
+    pub fn lut(&self) -> [f32; 256] {
+        std::array::from_fn(|i| self.eval(i as f32 / 255.0))
+    }
+
+    /// Returns the result of the chain for every 8 bit level, rounded back to 8 bits.
+    pub fn lut8(&self) -> [u8; 256] {
+        self.lut().map(to_u8)
+    }
+
+    /// Applies the chain in place to interleaved 8 bit RGBA bytes, leaving alpha unchanged.
+    pub fn apply_rgba8(&self, bytes: &mut [u8]) {
+        let lut = self.lut8();
+        bytes.par_chunks_mut(4).for_each(|pixel| {
+            for channel in pixel.iter_mut().take(3) {
+                *channel = lut[*channel as usize];
+            }
+        });
+    }
+
+    /// Runs the chain on an RGBA image, leaving alpha unchanged.
+    pub fn run(&self, image: &Image<Rgba>) -> Image<Rgba> {
+        let lut = self.lut();
+        let (width, height) = image.dimensions();
+        let data = image
+            .par_pixels()
+            .map(|p| Rgba {
+                r: lut[to_u8(p.r) as usize],
+                g: lut[to_u8(p.g) as usize],
+                b: lut[to_u8(p.b) as usize],
+                a: p.a,
+            })
+            .collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+
+    /// Runs the chain on a grayscale image.
+    pub fn run_luma(&self, image: &Image<Luma>) -> Image<Luma> {
+        let lut = self.lut();
+        let (width, height) = image.dimensions();
+        let data = image
+            .par_pixels()
+            .map(|p| Luma::new(lut[to_u8(p.l) as usize]))
+            .collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+}
@@ -0,0 +1,80 @@
+//! Measures comparing two images.
+
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Rgba},
+};
+use glance_core::par::*;
+
+use crate::color::{Lab, ciede2000};
+use crate::nonlinear_filters::{Rank, select_rank};
+use crate::{Error, Result};
+
+/// Returns the per pixel CIEDE2000 color difference between two sRGB images. Alpha is ignored.
+/// Fails with [`Error::DimensionMismatch`] if the images have different dimensions.
+pub fn delta_e(reference: &Image<Rgba>, image: &Image<Rgba>) -> Result<Image<Luma>> {
+    if reference.dimensions() != image.dimensions() {
+        return Err(Error::DimensionMismatch {
+            expected: reference.dimensions(),
+            actual: image.dimensions(),
+        });
+    }
+
+    let (width, height) = image.dimensions();
+    let reference: Vec<Rgba> = reference.pixels().collect();
+    let image: Vec<Rgba> = image.pixels().collect();
+    let differences = reference
+        .par_iter()
+        .zip(&image)
+        .map(|(a, b)| Luma {
+            l: ciede2000(&Lab::from_rgba(a), &Lab::from_rgba(b)),
+        })
+        .collect();
+
+    Ok(Image::from_data(width, height, differences)?)
+}
+
+/// Returns the value below which a fraction `p` in [0.0, 1.0] of the pixels of a map falls,
+/// e.g. 0.95 for the 95th percentile. Returns 0.0 for empty maps.
+pub fn percentile(map: &Image<Luma>, p: f32) -> f32 {
+    if map.is_empty() {
+        return 0.0;
+    }
+    let mut values: Vec<f32> = map.pixels().map(|px| px.l).collect();
+    select_rank(&mut values, Rank::Percentile(p))
+}
+
+/// Summary statistics of a difference map such as the output of [`delta_e`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DifferenceSummary {
+    pub mean: f32,
+    pub median: f32,
+    /// 95th percentile, a robust alternative to the maximum
+    pub p95: f32,
+    pub max: f32,
+}
+
+impl From<&Image<Luma>> for DifferenceSummary {
+    fn from(map: &Image<Luma>) -> Self {
+        if map.is_empty() {
+            return DifferenceSummary {
+                mean: 0.0,
+                median: 0.0,
+                p95: 0.0,
+                max: 0.0,
+            };
+        }
+
+        let mut values: Vec<f32> = map.pixels().map(|px| px.l).collect();
+        let mean = (par_sum(&values, |&v| v as f64) / values.len() as f64) as f32;
+        values.sort_by(|a, b| a.total_cmp(b));
+        let at = |p: f32| values[(p * (values.len() - 1) as f32).round() as usize];
+
+        DifferenceSummary {
+            mean,
+            median: at(0.5),
+            p95: at(0.95),
+            max: at(1.0),
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use glance_core::img::{Image, pixel::Luma};
+
+use crate::geometry::Point;
+
+/// Spatial moments of an image or a polygon up to the third order.
+///
+/// `mpq` are the raw moments (the sum of x^p * y^q over the shape), `mupq` the central moments,
+/// which are taken about the centroid and therefore invariant to translation.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Moments {
+    pub m00: f64,
+    pub m10: f64,
+    pub m01: f64,
+    pub m20: f64,
+    pub m11: f64,
+    pub m02: f64,
+    pub m30: f64,
+    pub m21: f64,
+    pub m12: f64,
+    pub m03: f64,
+    pub mu20: f64,
+    pub mu11: f64,
+    pub mu02: f64,
+    pub mu30: f64,
+    pub mu21: f64,
+    pub mu12: f64,
+    pub mu03: f64,
+}
+
+impl Moments {
+    /// Creates the moments from the raw moments
+    /// `[m00, m10, m01, m20, m11, m02, m30, m21, m12, m03]`, deriving the central moments.
+    pub fn from_raw(raw: [f64; 10]) -> Self {
+        let [m00, m10, m01, m20, m11, m02, m30, m21, m12, m03] = raw;
+        let mut moments = Moments {
+            m00,
+            m10,
+            m01,
+            m20,
+            m11,
+            m02,
+            m30,
+            m21,
+            m12,
+            m03,
+            ..Default::default()
+        };
+        if m00 == 0.0 {
+            return moments;
+        }
+
+        let (cx, cy) = (m10 / m00, m01 / m00);
+        moments.mu20 = m20 - cx * m10;
+        moments.mu11 = m11 - cx * m01;
+        moments.mu02 = m02 - cy * m01;
+        moments.mu30 = m30 - cx * (3.0 * moments.mu20 + cx * m10);
+        moments.mu21 = m21 - cx * (2.0 * moments.mu11 + cx * m01) - cy * moments.mu20;
+        moments.mu12 = m12 - cy * (2.0 * moments.mu11 + cy * m10) - cx * moments.mu02;
+        moments.mu03 = m03 - cy * (3.0 * moments.mu02 + cy * m01);
+        moments
+    }
+
+    /// Returns the area (the zeroth moment). For images this is the total intensity.
+    pub fn area(&self) -> f64 {
+        self.m00
+    }
+
+    /// Returns the center of mass, or None if the shape is empty.
+    pub fn centroid(&self) -> Option<Point> {
+        if self.m00 == 0.0 {
+            return None;
+        }
+        Some(((self.m10 / self.m00) as f32, (self.m01 / self.m00) as f32))
+    }
+
+    /// Returns the angle of the major axis in degrees in [0, 180), counter-clockwise on screen
+    /// (y down). Meaningless for shapes without a dominant direction, like circles or squares.
+    pub fn orientation(&self) -> f32 {
+        // Negated because screen y points down
+        let theta = -0.5 * (2.0 * self.mu11).atan2(self.mu20 - self.mu02);
+        (theta.to_degrees() as f32).rem_euclid(180.0)
+    }
+
+    /// Returns the normalized central moment nu_pq, which is also invariant to scale.
+    /// Only orders 2 and 3 are available, other orders return 0.0.
+    pub fn nu(&self, p: u32, q: u32) -> f64 {
+        let mu = match (p, q) {
+            (2, 0) => self.mu20,
+            (1, 1) => self.mu11,
+            (0, 2) => self.mu02,
+            (3, 0) => self.mu30,
+            (2, 1) => self.mu21,
+            (1, 2) => self.mu12,
+            (0, 3) => self.mu03,
+            _ => return 0.0,
+        };
+        if self.m00 == 0.0 {
+            return 0.0;
+        }
+        mu / self.m00.powf(1.0 + (p + q) as f64 / 2.0)
+    }
+
+    /// Returns the seven Hu moments, which are invariant to translation, scale and rotation.
+    /// The seventh changes sign under reflection.
+    pub fn hu(&self) -> [f64; 7] {
+        let (n20, n11, n02) = (self.nu(2, 0), self.nu(1, 1), self.nu(0, 2));
+        let (n30, n21, n12, n03) = (self.nu(3, 0), self.nu(2, 1), self.nu(1, 2), self.nu(0, 3));
+        let (a, b) = (n30 + n12, n21 + n03);
+
+        [
+            n20 + n02,
+            (n20 - n02).powi(2) + 4.0 * n11 * n11,
+            (n30 - 3.0 * n12).powi(2) + (3.0 * n21 - n03).powi(2),
+            a * a + b * b,
+            (n30 - 3.0 * n12) * a * (a * a - 3.0 * b * b)
+                + (3.0 * n21 - n03) * b * (3.0 * a * a - b * b),
+            (n20 - n02) * (a * a - b * b) + 4.0 * n11 * a * b,
+            (3.0 * n21 - n03) * a * (a * a - 3.0 * b * b)
+                - (n30 - 3.0 * n12) * b * (3.0 * a * a - b * b),
+        ]
+    }
+}
+
+/// Computes the intensity weighted moments of an image, where each pixel counts with its value.
+/// For a binary mask this gives the moments of the foreground.
+pub fn moments(image: &Image<Luma>) -> Moments {
+    let width = image.dimensions().0.max(1);
+    let mut raw = [0.0f64; 10];
+
+    for (idx, pixel) in image.pixels().enumerate() {
+        let w = pixel.l as f64;
+        if w == 0.0 {
+            continue;
+        }
+        let (x, y) = ((idx % width) as f64, (idx / width) as f64);
+        let (x2, y2) = (x * x, y * y);
+        let terms = [1.0, x, y, x2, x * y, y2, x2 * x, x2 * y, x * y2, y2 * y];
+        raw.iter_mut().zip(terms).for_each(|(m, t)| *m += w * t);
+    }
+
+    Moments::from_raw(raw)
+}
+
+/// Computes the moments of the area enclosed by a closed polygon using Green's theorem.
+pub fn polygon_moments(points: &[Point]) -> Moments {
+    let n = points.len();
+    let mut a = [0.0f64; 10];
+
+    for i in 0..n {
+        let (x0, y0) = (points[i].0 as f64, points[i].1 as f64);
+        let (x1, y1) = (points[(i + 1) % n].0 as f64, points[(i + 1) % n].1 as f64);
+        let cross = x0 * y1 - x1 * y0;
+        let (sx, sy) = (x0 + x1, y0 + y1);
+        let (x0x0, x1x1, y0y0, y1y1) = (x0 * x0, x1 * x1, y0 * y0, y1 * y1);
+
+        a[0] += cross;
+        a[1] += cross * sx;
+        a[2] += cross * sy;
+        a[3] += cross * (x0 * sx + x1x1);
+        a[4] += cross * (x0 * (sy + y0) + x1 * (sy + y1));
+        a[5] += cross * (y0 * sy + y1y1);
+        a[6] += cross * sx * (x0x0 + x1x1);
+        a[7] += cross * (x0x0 * (3.0 * y0 + y1) + 2.0 * x0 * x1 * sy + x1x1 * (y0 + 3.0 * y1));
+        a[8] += cross * (y0y0 * (3.0 * x0 + x1) + 2.0 * y0 * y1 * sx + y1y1 * (x0 + 3.0 * x1));
+        a[9] += cross * sy * (y0y0 + y1y1);
+    }
+
+    // Make the result independent of the winding direction
+    let sign = if a[0] < 0.0 { -1.0 } else { 1.0 };
+    let divisors = [2.0, 6.0, 6.0, 12.0, 24.0, 12.0, 20.0, 60.0, 60.0, 20.0];
+    let mut raw = [0.0; 10];
+    for i in 0..10 {
+        raw[i] = sign * a[i] / divisors[i];
+    }
+
+    Moments::from_raw(raw)
+}
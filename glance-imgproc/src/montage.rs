@@ -0,0 +1,190 @@
+use glance_core::drawing::text::Text;
+use glance_core::img::{Image, pixel::Rgba};
+
+use crate::affine::{self, Interpolation};
+
+/// Builder laying out several images into a grid (a contact sheet), e.g. to review the results
+/// of a batch side by side.
+///
+/// ```
+/// use glance_core::img::{Image, pixel::Rgba};
+/// use glance_imgproc::montage::Montage;
+///
+/// let sheet = Montage::new()
+///     .push_labeled(Image::<Rgba>::new(64, 48), "first")
+///     .push_labeled(Image::<Rgba>::new(32, 32), "second")
+///     .columns(2)
+///     .padding(4)
+///     .build();
+/// assert_eq!(sheet.dimensions().0, 4 + 2 * (64 + 4));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Montage {
+    cells: Vec<(Image<Rgba>, Option<String>)>,
+    columns: Option<usize>,
+    cell_size: Option<(usize, usize)>,
+    padding: usize,
+    background: Rgba,
+    label_color: Rgba,
+    label_scale: u32,
+}
+
+impl Default for Montage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Montage {
+    /// Creates an empty montage with a black background, white labels and 8 pixels of padding.
+    pub fn new() -> Self {
+        Montage {
+            cells: Vec::new(),
+            columns: None,
+            cell_size: None,
+            padding: 8,
+            background: Rgba {
+                r: 0.0,
+                g: 0.0,
+                b: 0.0,
+                a: 1.0,
+            },
+            label_color: Rgba {
+                r: 1.0,
+                g: 1.0,
+                b: 1.0,
+                a: 1.0,
+            },
+            label_scale: 1,
+        }
+    }
+
+    /// Appends an image to the next free cell.
+    pub fn push(mut self, image: Image<Rgba>) -> Self {
+        self.cells.push((image, None));
+        self
+    }
+
+    /// Appends an image with a text label rendered below it.
+    pub fn push_labeled(mut self, image: Image<Rgba>, label: &str) -> Self {
+        self.cells.push((image, Some(label.to_string())));
+        self
+    }
+
+    /// Sets the number of grid columns. Defaults to a roughly square grid.
+    pub fn columns(mut self, columns: usize) -> Self {
+        self.columns = Some(columns.max(1));
+        self
+    }
+
+    /// Sets a fixed cell size. Larger images are scaled down to fit, preserving their aspect
+    /// ratio. Defaults to the size of the largest image.
+    pub fn cell_size(mut self, width: usize, height: usize) -> Self {
+        self.cell_size = Some((width, height));
+        self
+    }
+
+    /// Sets the spacing in pixels around and between the cells.
+    pub fn padding(mut self, padding: usize) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets the background color.
+    pub fn background(mut self, color: Rgba) -> Self {
+        self.background = color;
+        self
+    }
+
+    /// Sets the color and integer scale of the labels.
+    pub fn label_style(mut self, color: Rgba, scale: u32) -> Self {
+        self.label_color = color;
+        self.label_scale = scale.max(1);
+        self
+    }
+
+    /// Renders the montage. Images are centered in their cells and alpha blended onto the
+    /// background. An empty montage yields an empty image.
+    pub fn build(self) -> Image<Rgba> {
+        let count = self.cells.len();
+        if count == 0 {
+            return Image::new(0, 0);
+        }
+
+        let columns = self
+            .columns
+            .unwrap_or_else(|| (count as f32).sqrt().ceil() as usize);
+        let rows = count.div_ceil(columns);
+        let (cell_w, cell_h) = self.cell_size.unwrap_or_else(|| {
+            self.cells.iter().fold((0, 0), |(w, h), (img, _)| {
+                let (iw, ih) = img.dimensions();
+                (w.max(iw), h.max(ih))
+            })
+        });
+        let label_h = if self.cells.iter().any(|(_, label)| label.is_some()) {
+            Text::<Rgba>::measure("M", self.label_scale).1 + self.padding / 2
+        } else {
+            0
+        };
+
+        let pad = self.padding;
+        let width = pad + columns * (cell_w + pad);
+        let height = pad + rows * (cell_h + label_h + pad);
+        let mut sheet =
+            Image::from_data(width, height, vec![self.background; width * height]).unwrap();
+
+        for (i, (image, label)) in self.cells.into_iter().enumerate() {
+            let cell_x = pad + (i % columns) * (cell_w + pad);
+            let cell_y = pad + (i / columns) * (cell_h + label_h + pad);
+
+            // Shrink images that do not fit the cell
+            let (iw, ih) = image.dimensions();
+            let factor = (cell_w as f32 / iw.max(1) as f32)
+                .min(cell_h as f32 / ih.max(1) as f32)
+                .min(1.0);
+            let image = if factor < 1.0 {
+                affine::scale_unchecked(&image, factor, factor, Interpolation::Bilinear)
+            } else {
+                image
+            };
+
+            let (iw, ih) = image.dimensions();
+            let (ox, oy) = (
+                cell_x + (cell_w - iw.min(cell_w)) / 2,
+                cell_y + (cell_h - ih.min(cell_h)) / 2,
+            );
+            for (idx, px) in image.pixels().enumerate() {
+                let (x, y) = (idx % iw, idx / iw);
+                if x >= cell_w || y >= cell_h {
+                    continue;
+                }
+                let position = (ox + x, oy + y);
+                let bg = *sheet.get_pixel(position).unwrap();
+                let blended = Rgba {
+                    r: px.r * px.a + bg.r * (1.0 - px.a),
+                    g: px.g * px.a + bg.g * (1.0 - px.a),
+                    b: px.b * px.a + bg.b * (1.0 - px.a),
+                    a: px.a + bg.a * (1.0 - px.a),
+                };
+                sheet.set_pixel(position, blended).unwrap();
+            }
+
+            if let Some(text) = label {
+                let (text_w, _) = Text::<Rgba>::measure(&text, self.label_scale);
+                sheet
+                    .draw(Text {
+                        position: (
+                            cell_x + cell_w.saturating_sub(text_w) / 2,
+                            cell_y + cell_h + self.padding / 2,
+                        ),
+                        text,
+                        color: self.label_color,
+                        scale: self.label_scale,
+                    })
+                    .unwrap();
+            }
+        }
+
+        sheet
+    }
+}
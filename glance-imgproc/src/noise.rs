@@ -0,0 +1,284 @@
+//! Synthetic noise, both degrading existing images (e.g. to test denoisers) and generating
+//! procedural noise textures. Every function takes a seed, so results are reproducible.
+
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Rgba},
+};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
+
+/// Draws a sample of the standard normal distribution with the Box-Muller transform.
+fn standard_normal(rng: &mut StdRng) -> f32 {
+    let (u, v): (f32, f32) = (rng.random_range(f32::EPSILON..1.0), rng.random());
+    (-2.0 * u.ln()).sqrt() * (std::f32::consts::TAU * v).cos()
+}
+
+/// Draws a sample of the Poisson distribution with mean `lambda`: exactly by multiplying
+/// uniforms for small means, with the normal approximation for large ones.
+fn poisson(rng: &mut StdRng, lambda: f32) -> f32 {
+    if lambda <= 0.0 {
+        return 0.0;
+    }
+    if lambda > 30.0 {
+        return (lambda + lambda.sqrt() * standard_normal(rng))
+            .round()
+            .max(0.0);
+    }
+    let limit = (-lambda).exp();
+    let (mut count, mut product) = (0.0, rng.random::<f32>());
+    while product > limit {
+        count += 1.0;
+        product *= rng.random::<f32>();
+    }
+    count
+}
+
+/// Shared implementation: applies `f` to every color value, one random stream per image.
+fn map_values(values: &mut [f32], seed: u64, f: impl Fn(&mut StdRng, f32) -> f32) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    values.iter_mut().for_each(|v| *v = f(&mut rng, *v));
+}
+
+/// Extension trait for [`glance_core::img::Image`] to add noise to Luma images
+pub trait NoiseExtLuma {
+    fn add_gaussian_noise(self, sigma: f32, seed: u64) -> Self;
+    fn add_salt_pepper(self, probability: f32, seed: u64) -> Self;
+    fn add_poisson_noise(self, peak: f32, seed: u64) -> Self;
+}
+
+/// Extension trait for [`glance_core::img::Image`] to add noise to RGBA images. The noise is
+/// independent per channel and alpha is left unchanged.
+pub trait NoiseExtRgba {
+    fn add_gaussian_noise(self, sigma: f32, seed: u64) -> Self;
+    fn add_salt_pepper(self, probability: f32, seed: u64) -> Self;
+    fn add_poisson_noise(self, peak: f32, seed: u64) -> Self;
+}
+
+impl NoiseExtLuma for Image<Luma> {
+    /// Adds zero mean Gaussian noise with standard deviation `sigma`. Values are not clamped.
+    fn add_gaussian_noise(self, sigma: f32, seed: u64) -> Self {
+        map_luma(self, seed, |rng, l| l + sigma * standard_normal(rng))
+    }
+
+    /// Sets a fraction `probability` of the pixels to black or white with equal chance.
+    fn add_salt_pepper(self, probability: f32, seed: u64) -> Self {
+        let (width, height) = self.dimensions();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let data = self
+            .pixels()
+            .map(|p| {
+                if rng.random::<f32>() < probability {
+                    Luma {
+                        l: if rng.random() { 1.0 } else { 0.0 },
+                    }
+                } else {
+                    p
+                }
+            })
+            .collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+
+    /// Simulates photon shot noise: a value of 1.0 corresponds to `peak` expected photons, so
+    /// lower peaks give noisier images. The mean intensity is preserved.
+    fn add_poisson_noise(self, peak: f32, seed: u64) -> Self {
+        map_luma(self, seed, |rng, l| poisson(rng, l.max(0.0) * peak) / peak)
+    }
+}
+
+fn map_luma(image: Image<Luma>, seed: u64, f: impl Fn(&mut StdRng, f32) -> f32) -> Image<Luma> {
+    let (width, height) = image.dimensions();
+    let mut values: Vec<f32> = image.pixels().map(|p| p.l).collect();
+    map_values(&mut values, seed, f);
+    let data = values.into_iter().map(|l| Luma { l }).collect();
+    Image::from_data(width, height, data).unwrap()
+}
+
+fn map_rgba(image: Image<Rgba>, seed: u64, f: impl Fn(&mut StdRng, f32) -> f32) -> Image<Rgba> {
+    let (width, height) = image.dimensions();
+    let mut values: Vec<f32> = image.pixels().flat_map(|p| [p.r, p.g, p.b]).collect();
+    map_values(&mut values, seed, f);
+    let data = values
+        .chunks_exact(3)
+        .zip(image.pixels())
+        .map(|(c, p)| Rgba {
+            r: c[0],
+            g: c[1],
+            b: c[2],
+            a: p.a,
+        })
+        .collect();
+    Image::from_data(width, height, data).unwrap()
+}
+
+impl NoiseExtRgba for Image<Rgba> {
+    /// Adds zero mean Gaussian noise with standard deviation `sigma` to every color channel.
+    /// Values are not clamped.
+    fn add_gaussian_noise(self, sigma: f32, seed: u64) -> Self {
+        map_rgba(self, seed, |rng, v| v + sigma * standard_normal(rng))
+    }
+
+    /// Sets a fraction `probability` of the pixels to black or white with equal chance.
+    fn add_salt_pepper(self, probability: f32, seed: u64) -> Self {
+        let (width, height) = self.dimensions();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let data = self
+            .pixels()
+            .map(|p| {
+                if rng.random::<f32>() < probability {
+                    let v = if rng.random() { 1.0 } else { 0.0 };
+                    Rgba {
+                        r: v,
+                        g: v,
+                        b: v,
+                        a: p.a,
+                    }
+                } else {
+                    p
+                }
+            })
+            .collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+
+    /// Simulates photon shot noise per color channel, see [`NoiseExtLuma::add_poisson_noise`].
+    fn add_poisson_noise(self, peak: f32, seed: u64) -> Self {
+        map_rgba(self, seed, |rng, v| poisson(rng, v.max(0.0) * peak) / peak)
+    }
+}
+
+/// The gradient noise function summed by [`FractalNoise`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoiseKind {
+    /// Classic Perlin noise on a square grid.
+    Perlin,
+    /// Simplex noise on a triangular grid. Fewer directional artifacts than Perlin.
+    Simplex,
+}
+
+/// Parameters of procedural fractal noise: several octaves of gradient noise at increasing
+/// frequency and decreasing amplitude.
+#[derive(Debug, Clone, Copy)]
+pub struct FractalNoise {
+    pub kind: NoiseKind,
+    /// Size in pixels of the features of the first octave
+    pub scale: f32,
+    pub octaves: usize,
+    /// Amplitude factor from one octave to the next
+    pub persistence: f32,
+    /// Frequency factor from one octave to the next
+    pub lacunarity: f32,
+    pub seed: u64,
+}
+
+impl Default for FractalNoise {
+    fn default() -> Self {
+        Self {
+            kind: NoiseKind::Perlin,
+            scale: 32.0,
+            octaves: 4,
+            persistence: 0.5,
+            lacunarity: 2.0,
+            seed: 0,
+        }
+    }
+}
+
+/// Gradient directions shared by both noise kinds.
+const GRADIENTS: [(f32, f32); 8] = [
+    (1.0, 1.0),
+    (-1.0, 1.0),
+    (1.0, -1.0),
+    (-1.0, -1.0),
+    (1.0, 0.0),
+    (-1.0, 0.0),
+    (0.0, 1.0),
+    (0.0, -1.0),
+];
+
+impl FractalNoise {
+    /// Generates a noise image with values in [0.0, 1.0].
+    pub fn generate(&self, width: usize, height: usize) -> Image<Luma> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut permutation: Vec<usize> = (0..256).collect();
+        permutation.shuffle(&mut rng);
+        let permutation: Vec<usize> = permutation.iter().chain(&permutation).copied().collect();
+        let gradient = |ix: i64, iy: i64| {
+            let hash = permutation[permutation[(ix & 255) as usize] + (iy & 255) as usize];
+            GRADIENTS[hash % GRADIENTS.len()]
+        };
+
+        let amplitude_sum: f32 = (0..self.octaves.max(1))
+            .map(|o| self.persistence.powi(o as i32))
+            .sum();
+
+        let data = (0..width * height)
+            .map(|idx| {
+                let (x, y) = ((idx % width) as f32, (idx / width) as f32);
+                let (mut frequency, mut amplitude, mut value) = (1.0 / self.scale, 1.0, 0.0);
+                for _ in 0..self.octaves.max(1) {
+                    let (nx, ny) = (x * frequency, y * frequency);
+                    value += amplitude
+                        * match self.kind {
+                            NoiseKind::Perlin => perlin(nx, ny, &gradient),
+                            NoiseKind::Simplex => simplex(nx, ny, &gradient),
+                        };
+                    frequency *= self.lacunarity;
+                    amplitude *= self.persistence;
+                }
+                Luma {
+                    l: (0.5 + 0.5 * value / amplitude_sum).clamp(0.0, 1.0),
+                }
+            })
+            .collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+}
+
+/// 2D Perlin noise in about [-1.0, 1.0].
+fn perlin(x: f32, y: f32, gradient: &impl Fn(i64, i64) -> (f32, f32)) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let (ix, iy) = (x0 as i64, y0 as i64);
+    let fade = |t: f32| t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+    let corner = |cx: i64, cy: i64| {
+        let (gx, gy) = gradient(ix + cx, iy + cy);
+        gx * (fx - cx as f32) + gy * (fy - cy as f32)
+    };
+
+    let (u, v) = (fade(fx), fade(fy));
+    let top = corner(0, 0) + u * (corner(1, 0) - corner(0, 0));
+    let bottom = corner(0, 1) + u * (corner(1, 1) - corner(0, 1));
+    top + v * (bottom - top)
+}
+
+/// 2D simplex noise in about [-1.0, 1.0].
+fn simplex(x: f32, y: f32, gradient: &impl Fn(i64, i64) -> (f32, f32)) -> f32 {
+    let skew = 0.5 * (3.0f32.sqrt() - 1.0);
+    let unskew = (3.0 - 3.0f32.sqrt()) / 6.0;
+
+    // Cell of the skewed grid and position within its first corner
+    let s = (x + y) * skew;
+    let (i, j) = ((x + s).floor(), (y + s).floor());
+    let t = (i + j) * unskew;
+    let (x0, y0) = (x - (i - t), y - (j - t));
+    let (i1, j1) = if x0 > y0 { (1.0, 0.0) } else { (0.0, 1.0) };
+
+    let corners = [
+        (0.0, 0.0, x0, y0),
+        (i1, j1, x0 - i1 + unskew, y0 - j1 + unskew),
+        (1.0, 1.0, x0 - 1.0 + 2.0 * unskew, y0 - 1.0 + 2.0 * unskew),
+    ];
+    let sum: f32 = corners
+        .iter()
+        .map(|&(di, dj, dx, dy)| {
+            let falloff = 0.5 - dx * dx - dy * dy;
+            if falloff <= 0.0 {
+                return 0.0;
+            }
+            let (gx, gy) = gradient(i as i64 + di as i64, j as i64 + dj as i64);
+            falloff.powi(4) * (gx * dx + gy * dy)
+        })
+        .sum();
+    70.0 * sum
+}
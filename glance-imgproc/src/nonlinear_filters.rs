@@ -0,0 +1,491 @@
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Rgba},
+};
+use glance_core::par::*;
+use glance_core::profiling::OpSpan;
+
+use crate::linear_filters::{BorderMode, check_kernel};
+use crate::{Error, Result};
+
+/// Order statistic selected from the neighbourhood by [`NonLinearFilterExtLuma::rank_filter`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rank {
+    /// Smallest value in the neighbourhood.
+    Min,
+    /// Largest value in the neighbourhood.
+    Max,
+    /// Middle value of the neighbourhood.
+    Median,
+    /// Value at the given fraction in [0.0, 1.0] of the sorted neighbourhood.
+    Percentile(f32),
+}
+
+/// Sorts `values` and returns the element selected by `rank`.
+pub(crate) fn select_rank(values: &mut [f32], rank: Rank) -> f32 {
+    values.sort_by(|a, b| a.total_cmp(b));
+    let last = values.len() - 1;
+    let idx = match rank {
+        Rank::Min => 0,
+        Rank::Max => last,
+        Rank::Median => last / 2,
+        Rank::Percentile(p) => (p.clamp(0.0, 1.0) * last as f32).round() as usize,
+    };
+    values[idx]
+}
+
+/// Returns the offsets from the kernel center of all non-zero entries of a structuring element.
+pub(crate) fn structuring_offsets(kernel: &Image<Luma>) -> Result<Vec<(isize, isize)>> {
+    let (k_width, k_height) = kernel.dimensions();
+    if k_width.is_multiple_of(2) || k_height.is_multiple_of(2) {
+        return Err(Error::KernelSizeEven {
+            width: k_width,
+            height: k_height,
+        });
+    }
+    let (half_w, half_h) = ((k_width / 2) as isize, (k_height / 2) as isize);
+
+    Ok(kernel
+        .pixels()
+        .enumerate()
+        .filter(|(_, p)| p.l != 0.0)
+        .map(|(i, _)| {
+            (
+                (i % k_width) as isize - half_w,
+                (i / k_width) as isize - half_h,
+            )
+        })
+        .collect())
+}
+
+/// Returns the [`structuring_offsets`] of a kernel after checking it against the image.
+fn checked_offsets(image: &Image<Luma>, kernel: &Image<Luma>) -> Result<Vec<(isize, isize)>> {
+    check_kernel(image.dimensions(), kernel.dimensions())?;
+    structuring_offsets(kernel)
+}
+
+/// Folds the neighbourhood selected by `offsets` around every pixel with `f`, sampling pixels
+/// outside the image according to `border`.
+fn morphology(
+    image: &Image<Luma>,
+    offsets: &[(isize, isize)],
+    border: BorderMode,
+    init: f32,
+    f: fn(f32, f32) -> f32,
+) -> Image<Luma> {
+    let (width, height) = image.dimensions();
+    let _span = OpSpan::enter("morphology", (width, height));
+
+    let filtered = (0..width * height)
+        .into_par_iter()
+        .map(|idx| {
+            let (x, y) = ((idx % width) as isize, (idx / width) as isize);
+            let l = offsets.iter().fold(init, |acc, (dx, dy)| {
+                let sx = border.resolve(x + dx, width);
+                let sy = border.resolve(y + dy, height);
+                match (sx, sy, border) {
+                    (Some(sx), Some(sy), _) => f(acc, image.get_pixel((sx, sy)).unwrap().l),
+                    (_, _, BorderMode::Constant(value)) => f(acc, value),
+                    _ => acc,
+                }
+            });
+            Luma { l }
+        })
+        .collect();
+
+    Image::from_data(width, height, filtered).unwrap()
+}
+
+/// Applies [`morphology`] `iterations` times (at least once), reusing the previous output as the
+/// next input instead of cloning.
+fn morphology_repeated(
+    image: &Image<Luma>,
+    offsets: &[(isize, isize)],
+    border: BorderMode,
+    iterations: usize,
+    init: f32,
+    f: fn(f32, f32) -> f32,
+) -> Image<Luma> {
+    let mut output = morphology(image, offsets, border, init, f);
+    for _ in 1..iterations {
+        output = morphology(&output, offsets, border, init, f);
+    }
+    output
+}
+
+fn dilate_n(
+    image: &Image<Luma>,
+    offsets: &[(isize, isize)],
+    border: BorderMode,
+    iterations: usize,
+) -> Image<Luma> {
+    morphology_repeated(
+        image,
+        offsets,
+        border,
+        iterations,
+        f32::NEG_INFINITY,
+        f32::max,
+    )
+}
+
+fn erode_n(
+    image: &Image<Luma>,
+    offsets: &[(isize, isize)],
+    border: BorderMode,
+    iterations: usize,
+) -> Image<Luma> {
+    morphology_repeated(image, offsets, border, iterations, f32::INFINITY, f32::min)
+}
+
+/// Returns `a - b` pixel by pixel, reusing the buffer of `a`.
+fn difference(mut a: Image<Luma>, b: &Image<Luma>) -> Image<Luma> {
+    a.pixels_mut()
+        .zip(b.pixels())
+        .for_each(|(pa, pb)| pa.l -= pb.l);
+    a
+}
+
+fn check_kernel_size(kernel_size: usize) -> Result<()> {
+    if kernel_size.is_multiple_of(2) {
+        return Err(Error::KernelSizeEven {
+            width: kernel_size,
+            height: kernel_size,
+        });
+    }
+    Ok(())
+}
+
+/// Extension trait for [`glance_core::img::Image`] to provide non-linear filters for Luma images
+pub trait NonLinearFilterExtLuma {
+    fn rank_filter(self, kernel_size: usize, rank: Rank) -> Result<Image<Luma>>;
+    fn median_blur(self, kernel_size: usize) -> Result<Image<Luma>>;
+    fn median_blur_histogram(self, kernel_size: usize) -> Result<Image<Luma>>;
+    fn min_filter(self, kernel_size: usize) -> Result<Image<Luma>>;
+    fn max_filter(self, kernel_size: usize) -> Result<Image<Luma>>;
+    fn dilate(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Luma>>;
+    fn erode(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Luma>>;
+    fn open(
+        self,
+        kernel: &Image<Luma>,
+        border: BorderMode,
+        iterations: usize,
+    ) -> Result<Image<Luma>>;
+    fn close(
+        self,
+        kernel: &Image<Luma>,
+        border: BorderMode,
+        iterations: usize,
+    ) -> Result<Image<Luma>>;
+    fn morphological_gradient(
+        self,
+        kernel: &Image<Luma>,
+        border: BorderMode,
+        iterations: usize,
+    ) -> Result<Image<Luma>>;
+    fn tophat(
+        self,
+        kernel: &Image<Luma>,
+        border: BorderMode,
+        iterations: usize,
+    ) -> Result<Image<Luma>>;
+    fn blackhat(
+        self,
+        kernel: &Image<Luma>,
+        border: BorderMode,
+        iterations: usize,
+    ) -> Result<Image<Luma>>;
+}
+
+/// Extension trait for [`glance_core::img::Image`] to provide non-linear filters for RGBA images
+pub trait NonLinearFilterExtRgba {
+    fn rank_filter(self, kernel_size: usize, rank: Rank) -> Result<Image<Rgba>>;
+    fn median_blur(self, kernel_size: usize) -> Result<Image<Rgba>>;
+    fn min_filter(self, kernel_size: usize) -> Result<Image<Rgba>>;
+    fn max_filter(self, kernel_size: usize) -> Result<Image<Rgba>>;
+}
+
+impl NonLinearFilterExtLuma for Image<Luma> {
+    /// Replaces every pixel by the given order statistic of its `kernel_size`² neighbourhood.
+    /// The kernel size must be odd. Neighbours outside the image are skipped.
+    fn rank_filter(self, kernel_size: usize, rank: Rank) -> Result<Image<Luma>> {
+        check_kernel_size(kernel_size)?;
+        let (width, height) = self.dimensions();
+        let half = (kernel_size / 2) as isize;
+
+        let filtered = (0..width * height)
+            .into_par_iter()
+            .map(|idx| {
+                let (x, y) = ((idx % width) as isize, (idx / width) as isize);
+                let mut values = Vec::with_capacity(kernel_size * kernel_size);
+                for ny in y - half..=y + half {
+                    for nx in x - half..=x + half {
+                        if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
+                            continue;
+                        }
+                        values.push(self.get_pixel((nx as usize, ny as usize)).unwrap().l);
+                    }
+                }
+                Luma {
+                    l: select_rank(&mut values, rank),
+                }
+            })
+            .collect();
+
+        Ok(Image::from_data(width, height, filtered)?)
+    }
+
+    /// Median filter, effective against salt and pepper noise while preserving edges.
+    /// Sorts every neighbourhood, so the cost grows with the kernel area. For large kernels see
+    /// [`NonLinearFilterExtLuma::median_blur_histogram`].
+    fn median_blur(self, kernel_size: usize) -> Result<Image<Luma>> {
+        self.rank_filter(kernel_size, Rank::Median)
+    }
+
+    /// Median filter running in constant time per pixel regardless of the kernel size, using
+    /// the sliding column histogram algorithm by Perreault and Hébert.
+    /// Intensities are quantized to 256 levels in [0.0, 1.0], so the result matches
+    /// [`NonLinearFilterExtLuma::median_blur`] exactly for 8-bit sources. The kernel size must be
+    /// odd. Neighbours outside the image are skipped.
+    fn median_blur_histogram(self, kernel_size: usize) -> Result<Image<Luma>> {
+        check_kernel_size(kernel_size)?;
+        let (width, height) = self.dimensions();
+        let radius = kernel_size / 2;
+        let levels: Vec<u8> = self
+            .pixels()
+            .map(|p| (p.l.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect();
+
+        // Horizontal strips are filtered independently, each with its own column histograms
+        const STRIP_HEIGHT: usize = 32;
+        let strips: Vec<Vec<Luma>> = (0..height.div_ceil(STRIP_HEIGHT))
+            .into_par_iter()
+            .map(|strip| {
+                let y_start = strip * STRIP_HEIGHT;
+                let y_end = (y_start + STRIP_HEIGHT).min(height);
+                let mut output = Vec::with_capacity((y_end - y_start) * width);
+
+                // One histogram per column, covering the rows of the current window
+                let mut columns = vec![[0u32; 256]; width];
+                for y in y_start.saturating_sub(radius)..(y_start + radius + 1).min(height) {
+                    for (x, column) in columns.iter_mut().enumerate() {
+                        column[levels[y * width + x] as usize] += 1;
+                    }
+                }
+
+                for y in y_start..y_end {
+                    if y > y_start {
+                        for (x, column) in columns.iter_mut().enumerate() {
+                            if y > radius {
+                                column[levels[(y - radius - 1) * width + x] as usize] -= 1;
+                            }
+                            if y + radius < height {
+                                column[levels[(y + radius) * width + x] as usize] += 1;
+                            }
+                        }
+                    }
+                    let rows = (y + radius + 1).min(height) - y.saturating_sub(radius);
+
+                    let mut kernel = [0u32; 256];
+                    let mut count = 0;
+                    for column in columns.iter().take((radius + 1).min(width)) {
+                        kernel.iter_mut().zip(column).for_each(|(k, c)| *k += c);
+                        count += rows;
+                    }
+
+                    for x in 0..width {
+                        if x > 0 {
+                            if x + radius < width {
+                                let column = &columns[x + radius];
+                                kernel.iter_mut().zip(column).for_each(|(k, c)| *k += c);
+                                count += rows;
+                            }
+                            if x > radius {
+                                let column = &columns[x - radius - 1];
+                                kernel.iter_mut().zip(column).for_each(|(k, c)| *k -= c);
+                                count -= rows;
+                            }
+                        }
+
+                        // Same rank as the sorting implementation
+                        let target = (count as u32 - 1) / 2;
+                        let mut cumulative = 0;
+                        let mut level = 0;
+                        for (bin, &n) in kernel.iter().enumerate() {
+                            cumulative += n;
+                            if cumulative > target {
+                                level = bin;
+                                break;
+                            }
+                        }
+                        output.push(Luma {
+                            l: level as f32 / 255.0,
+                        });
+                    }
+                }
+
+                output
+            })
+            .collect();
+
+        Ok(Image::from_data(width, height, strips.concat())?)
+    }
+
+    /// Minimum filter (grayscale erosion with a square structuring element).
+    fn min_filter(self, kernel_size: usize) -> Result<Image<Luma>> {
+        self.rank_filter(kernel_size, Rank::Min)
+    }
+
+    /// Maximum filter (grayscale dilation with a square structuring element).
+    fn max_filter(self, kernel_size: usize) -> Result<Image<Luma>> {
+        self.rank_filter(kernel_size, Rank::Max)
+    }
+
+    /// Grayscale dilation: every pixel becomes the maximum over the structuring element
+    /// centered on it. Non-zero entries of `kernel` select the neighbours, see e.g.
+    /// [`crate::kernels::ellipse`]; it must have odd dimensions and may not exceed the image on
+    /// both axes. Pixels outside the image are sampled according to `border`, just like in
+    /// [`crate::linear_filters`]; use `BorderMode::Constant(0.0)` to keep objects from growing in
+    /// from the edges.
+    fn dilate(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Luma>> {
+        let offsets = checked_offsets(&self, kernel)?;
+        Ok(dilate_n(&self, &offsets, border, 1))
+    }
+
+    /// Grayscale erosion: every pixel becomes the minimum over the structuring element
+    /// centered on it. See [`NonLinearFilterExtLuma::dilate`] for the kernel and border
+    /// conventions; `BorderMode::Replicate` keeps objects touching the edges intact.
+    fn erode(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Image<Luma>> {
+        let offsets = checked_offsets(&self, kernel)?;
+        Ok(erode_n(&self, &offsets, border, 1))
+    }
+
+    /// Morphological opening: `iterations` erosions followed by as many dilations.
+    /// Removes bright details smaller than the structuring element. An `iterations` of 0 is
+    /// treated as 1.
+    fn open(
+        self,
+        kernel: &Image<Luma>,
+        border: BorderMode,
+        iterations: usize,
+    ) -> Result<Image<Luma>> {
+        let offsets = checked_offsets(&self, kernel)?;
+        let eroded = erode_n(&self, &offsets, border, iterations);
+        Ok(dilate_n(&eroded, &offsets, border, iterations))
+    }
+
+    /// Morphological closing: `iterations` dilations followed by as many erosions.
+    /// Fills dark gaps smaller than the structuring element. An `iterations` of 0 is treated
+    /// as 1.
+    fn close(
+        self,
+        kernel: &Image<Luma>,
+        border: BorderMode,
+        iterations: usize,
+    ) -> Result<Image<Luma>> {
+        let offsets = checked_offsets(&self, kernel)?;
+        let dilated = dilate_n(&self, &offsets, border, iterations);
+        Ok(erode_n(&dilated, &offsets, border, iterations))
+    }
+
+    /// Morphological gradient: the dilation minus the erosion, outlining object edges.
+    fn morphological_gradient(
+        self,
+        kernel: &Image<Luma>,
+        border: BorderMode,
+        iterations: usize,
+    ) -> Result<Image<Luma>> {
+        let offsets = checked_offsets(&self, kernel)?;
+        let eroded = erode_n(&self, &offsets, border, iterations);
+        Ok(difference(
+            dilate_n(&self, &offsets, border, iterations),
+            &eroded,
+        ))
+    }
+
+    /// White top-hat: the image minus its opening, extracting bright details smaller than the
+    /// structuring element (e.g. to correct uneven illumination).
+    fn tophat(
+        self,
+        kernel: &Image<Luma>,
+        border: BorderMode,
+        iterations: usize,
+    ) -> Result<Image<Luma>> {
+        let offsets = checked_offsets(&self, kernel)?;
+        let eroded = erode_n(&self, &offsets, border, iterations);
+        let opened = dilate_n(&eroded, &offsets, border, iterations);
+        Ok(difference(self, &opened))
+    }
+
+    /// Black top-hat: the closing minus the image, extracting dark details smaller than the
+    /// structuring element.
+    fn blackhat(
+        self,
+        kernel: &Image<Luma>,
+        border: BorderMode,
+        iterations: usize,
+    ) -> Result<Image<Luma>> {
+        let offsets = checked_offsets(&self, kernel)?;
+        let dilated = dilate_n(&self, &offsets, border, iterations);
+        let closed = erode_n(&dilated, &offsets, border, iterations);
+        Ok(difference(closed, &self))
+    }
+}
+
+impl NonLinearFilterExtRgba for Image<Rgba> {
+    /// Replaces every channel (including alpha) of every pixel by the given order statistic of
+    /// that channel in the `kernel_size`² neighbourhood. Channels are ranked independently, so
+    /// the result may combine channels from different neighbours.
+    /// The kernel size must be odd. Neighbours outside the image are skipped.
+    fn rank_filter(self, kernel_size: usize, rank: Rank) -> Result<Image<Rgba>> {
+        check_kernel_size(kernel_size)?;
+        let (width, height) = self.dimensions();
+        let half = (kernel_size / 2) as isize;
+
+        let filtered = (0..width * height)
+            .into_par_iter()
+            .map(|idx| {
+                let (x, y) = ((idx % width) as isize, (idx / width) as isize);
+                let capacity = kernel_size * kernel_size;
+                let mut channels: [Vec<f32>; 4] =
+                    std::array::from_fn(|_| Vec::with_capacity(capacity));
+                for ny in y - half..=y + half {
+                    for nx in x - half..=x + half {
+                        if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
+                            continue;
+                        }
+                        let pixel = self.get_pixel((nx as usize, ny as usize)).unwrap();
+                        channels[0].push(pixel.r);
+                        channels[1].push(pixel.g);
+                        channels[2].push(pixel.b);
+                        channels[3].push(pixel.a);
+                    }
+                }
+                let [r, g, b, a] = &mut channels;
+                Rgba {
+                    r: select_rank(r, rank),
+                    g: select_rank(g, rank),
+                    b: select_rank(b, rank),
+                    a: select_rank(a, rank),
+                }
+            })
+            .collect();
+
+        Ok(Image::from_data(width, height, filtered)?)
+    }
+
+    /// Per-channel median filter. See [`NonLinearFilterExtRgba::rank_filter`].
+    fn median_blur(self, kernel_size: usize) -> Result<Image<Rgba>> {
+        self.rank_filter(kernel_size, Rank::Median)
+    }
+
+    /// Per-channel minimum filter. See [`NonLinearFilterExtRgba::rank_filter`].
+    fn min_filter(self, kernel_size: usize) -> Result<Image<Rgba>> {
+        self.rank_filter(kernel_size, Rank::Min)
+    }
+
+    /// Per-channel maximum filter. See [`NonLinearFilterExtRgba::rank_filter`].
+    fn max_filter(self, kernel_size: usize) -> Result<Image<Rgba>> {
+        self.rank_filter(kernel_size, Rank::Max)
+    }
+}
@@ -0,0 +1,340 @@
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Rgba},
+};
+use glance_core::par::*;
+
+use crate::affine::{AffineTransformationsExt, Interpolation};
+use crate::color::Hsv;
+use crate::linalg::solve;
+use crate::linear_filters::{BorderMode, LinearFilterExtLuma, convolve_plane_separable};
+use crate::{Error, Result};
+
+/// A dense motion field holding one displacement (dx, dy) in pixels per pixel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowField {
+    width: usize,
+    height: usize,
+    data: Vec<(f32, f32)>,
+}
+
+impl FlowField {
+    /// Creates a field of the given dimensions without any motion.
+    pub fn new(width: usize, height: usize) -> Self {
+        FlowField {
+            width,
+            height,
+            data: vec![(0.0, 0.0); width * height],
+        }
+    }
+
+    /// Returns the dimensions of the field as a tuple (width, height).
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the displacement at the specified position, or None if it is out of bounds.
+    pub fn get(&self, position: (usize, usize)) -> Option<(f32, f32)> {
+        if position.0 >= self.width || position.1 >= self.height {
+            return None;
+        }
+        Some(self.data[position.1 * self.width + position.0])
+    }
+
+    /// Returns all displacements in row-major order.
+    pub fn vectors(&self) -> &[(f32, f32)] {
+        &self.data
+    }
+
+    /// Visualizes the field with the usual color wheel: the hue encodes the direction
+    /// (counter-clockwise on screen, red pointing right) and the brightness the magnitude, where
+    /// `max_magnitude` (or the largest magnitude in the field if None) is fully bright.
+    pub fn to_rgba(&self, max_magnitude: Option<f32>) -> Image<Rgba> {
+        let max = max_magnitude.unwrap_or_else(|| {
+            self.data
+                .iter()
+                .map(|(dx, dy)| dx.hypot(*dy))
+                .fold(0.0, f32::max)
+        });
+        let data = self
+            .data
+            .iter()
+            .map(|&(dx, dy)| {
+                let magnitude = dx.hypot(dy);
+                Hsv {
+                    // Screen y points down
+                    h: (-dy).atan2(dx).to_degrees().rem_euclid(360.0),
+                    s: 1.0,
+                    v: if max > 0.0 {
+                        (magnitude / max).min(1.0)
+                    } else {
+                        0.0
+                    },
+                }
+                .to_rgba(1.0)
+            })
+            .collect();
+        Image::from_data(self.width, self.height, data).unwrap()
+    }
+
+    /// Resamples the field to new dimensions, scaling the displacements accordingly.
+    fn resize(&self, width: usize, height: usize) -> FlowField {
+        let (fx, fy) = (
+            width as f32 / self.width as f32,
+            height as f32 / self.height as f32,
+        );
+        let data = (0..width * height)
+            .map(|idx| {
+                let x = ((idx % width) as f32 + 0.5) / fx - 0.5;
+                let y = ((idx / width) as f32 + 0.5) / fy - 0.5;
+                let dx = sample_plane(&self.data, self.width, self.height, x, y, |v| v.0);
+                let dy = sample_plane(&self.data, self.width, self.height, x, y, |v| v.1);
+                (dx * fx, dy * fy)
+            })
+            .collect();
+        FlowField {
+            width,
+            height,
+            data,
+        }
+    }
+}
+
+/// Bilinearly samples the value `f` of a row-major plane, clamping positions to the edges.
+fn sample_plane<T>(
+    plane: &[T],
+    width: usize,
+    height: usize,
+    x: f32,
+    y: f32,
+    f: impl Fn(&T) -> f32,
+) -> f32 {
+    let x = x.clamp(0.0, (width - 1) as f32);
+    let y = y.clamp(0.0, (height - 1) as f32);
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+    let at = |x: usize, y: usize| f(&plane[y * width + x]);
+
+    (at(x0, y0) * (1.0 - tx) + at(x1, y0) * tx) * (1.0 - ty)
+        + (at(x0, y1) * (1.0 - tx) + at(x1, y1) * tx) * ty
+}
+
+/// Local quadratic model f(x) ≈ xᵀAx + bᵀx + c of a pixel neighbourhood, stored as
+/// [b1, b2, a11, a22, a12] (c is not needed).
+type Expansion = [f32; 5];
+
+/// Parameters of Farnebäck's dense optical flow, which models every neighbourhood with a
+/// quadratic polynomial and estimates the displacement between the polynomials of two frames,
+/// coarse to fine over an image pyramid.
+#[derive(Debug, Clone, Copy)]
+pub struct Farneback {
+    /// Number of pyramid levels, including the full resolution one
+    pub levels: usize,
+    /// Size ratio between consecutive pyramid levels, below 1.0
+    pub pyramid_scale: f32,
+    /// Size of the window the displacement is averaged over. Larger windows are more robust to
+    /// noise and find faster motion, but blur motion boundaries
+    pub window_size: usize,
+    /// Number of refinement iterations per pyramid level
+    pub iterations: usize,
+    /// Radius of the neighbourhood fitted by each polynomial
+    pub poly_radius: usize,
+    /// Standard deviation of the Gaussian weighting the polynomial fit
+    pub poly_sigma: f32,
+}
+
+impl Default for Farneback {
+    fn default() -> Self {
+        Farneback {
+            levels: 3,
+            pyramid_scale: 0.5,
+            window_size: 15,
+            iterations: 3,
+            poly_radius: 5,
+            poly_sigma: 1.1,
+        }
+    }
+}
+
+impl Farneback {
+    /// Estimates the flow from `previous` to `next`, so that the content at (x, y) in
+    /// `previous` is found at (x + dx, y + dy) in `next`. Fails with
+    /// [`Error::DimensionMismatch`] if the frames have different dimensions.
+    pub fn calc(&self, previous: &Image<Luma>, next: &Image<Luma>) -> Result<FlowField> {
+        if previous.dimensions() != next.dimensions() {
+            return Err(Error::DimensionMismatch {
+                expected: previous.dimensions(),
+                actual: next.dimensions(),
+            });
+        }
+
+        let min_size = 2 * self.poly_radius + 1;
+        let mut pyramid = vec![(previous.clone(), next.clone())];
+        for _ in 1..self.levels.max(1) {
+            let (p, n) = pyramid.last().unwrap();
+            let (width, height) = p.dimensions();
+            if ((width.min(height)) as f32 * self.pyramid_scale) < min_size as f32 {
+                break;
+            }
+            let down = |image: &Image<Luma>| {
+                image.clone().gaussian_blur(0.5 / self.pyramid_scale).scale(
+                    self.pyramid_scale,
+                    self.pyramid_scale,
+                    Interpolation::Bilinear,
+                )
+            };
+            pyramid.push((down(p)?, down(n)?));
+        }
+
+        let mut flow: Option<FlowField> = None;
+        for (p, n) in pyramid.iter().rev() {
+            let (width, height) = p.dimensions();
+            let mut current = match flow {
+                Some(coarse) => coarse.resize(width, height),
+                None => FlowField::new(width, height),
+            };
+
+            let (e1, e2) = (self.expand(p), self.expand(n));
+            for _ in 0..self.iterations.max(1) {
+                current = self.refine(&e1, &e2, &current);
+            }
+            flow = Some(current);
+        }
+
+        Ok(flow.unwrap())
+    }
+
+    /// Fits the quadratic model to the neighbourhood of every pixel by weighted least squares,
+    /// computed as separable correlations with the polynomial basis.
+    fn expand(&self, image: &Image<Luma>) -> Vec<Expansion> {
+        let dimensions = image.dimensions();
+        let n = self.poly_radius as isize;
+        let weights: Vec<f32> = (-n..=n)
+            .map(|i| (-((i * i) as f32) / (2.0 * self.poly_sigma * self.poly_sigma)).exp())
+            .collect();
+        let basis_kernel = |power: i32| -> Vec<f32> {
+            weights
+                .iter()
+                .zip(-n..=n)
+                .map(|(w, i)| w * (i as f32).powi(power))
+                .collect()
+        };
+
+        // Basis 1, x, y, x², y², xy as powers of (x, y)
+        let powers = [(0, 0), (1, 0), (0, 1), (2, 0), (0, 2), (1, 1)];
+
+        // Gram matrix of the weighted basis
+        let mut gram = [0.0f64; 36];
+        for y in -n..=n {
+            for x in -n..=n {
+                let weight = (weights[(x + n) as usize] * weights[(y + n) as usize]) as f64;
+                for (i, (pi, qi)) in powers.iter().enumerate() {
+                    for (j, (pj, qj)) in powers.iter().enumerate() {
+                        gram[i * 6 + j] +=
+                            weight * (x as f64).powi(pi + pj) * (y as f64).powi(qi + qj);
+                    }
+                }
+            }
+        }
+        let inverse: Vec<Vec<f64>> = (0..6)
+            .map(|i| {
+                let mut unit = [0.0; 6];
+                unit[i] = 1.0;
+                solve(&gram, &unit, 6).unwrap()
+            })
+            .collect();
+
+        let plane: Vec<f32> = image.pixels().map(|p| p.l).collect();
+        let correlations: Vec<Vec<f32>> = powers
+            .iter()
+            .map(|&(p, q)| {
+                convolve_plane_separable(
+                    &plane,
+                    dimensions,
+                    &basis_kernel(p),
+                    &basis_kernel(q),
+                    BorderMode::Replicate,
+                )
+            })
+            .collect();
+
+        (0..plane.len())
+            .into_par_iter()
+            .map(|idx| {
+                // The inverse is symmetric, so its columns double as rows
+                let r = |k: usize| -> f32 {
+                    (0..6)
+                        .map(|j| inverse[j][k] * correlations[j][idx] as f64)
+                        .sum::<f64>() as f32
+                };
+                [r(1), r(2), r(3), r(4), r(5) / 2.0]
+            })
+            .collect()
+    }
+
+    /// One refinement step: matches the polynomials of the first frame with those of the second
+    /// frame at the currently estimated displacement, and solves for the displacement averaged
+    /// over the window.
+    fn refine(&self, e1: &[Expansion], e2: &[Expansion], flow: &FlowField) -> FlowField {
+        let (width, height) = flow.dimensions();
+
+        // Per pixel terms of the normal equations: [g11, g12, g22, h1, h2]
+        let terms: Vec<[f32; 5]> = (0..width * height)
+            .into_par_iter()
+            .map(|idx| {
+                let (d1, d2) = flow.data[idx];
+                let (x, y) = ((idx % width) as f32 + d1, (idx / width) as f32 + d2);
+                let second: Expansion =
+                    std::array::from_fn(|k| sample_plane(e2, width, height, x, y, |e| e[k]));
+                let first = e1[idx];
+
+                let a11 = (first[2] + second[2]) / 2.0;
+                let a22 = (first[3] + second[3]) / 2.0;
+                let a12 = (first[4] + second[4]) / 2.0;
+                let db1 = -0.5 * (second[0] - first[0]) + a11 * d1 + a12 * d2;
+                let db2 = -0.5 * (second[1] - first[1]) + a12 * d1 + a22 * d2;
+
+                [
+                    a11 * a11 + a12 * a12,
+                    a11 * a12 + a12 * a22,
+                    a12 * a12 + a22 * a22,
+                    a11 * db1 + a12 * db2,
+                    a12 * db1 + a22 * db2,
+                ]
+            })
+            .collect();
+
+        let sigma = self.window_size as f32 / 4.0;
+        let averaged: Vec<Vec<f32>> = (0..5)
+            .map(|k| {
+                let plane: Vec<f32> = terms.iter().map(|t| t[k]).collect();
+                let image = Image::from_data(
+                    width,
+                    height,
+                    plane.into_iter().map(|l| Luma { l }).collect(),
+                )
+                .unwrap();
+                image.gaussian_blur(sigma).pixels().map(|p| p.l).collect()
+            })
+            .collect();
+
+        let data = (0..width * height)
+            .map(|idx| {
+                let (g11, g12, g22) = (averaged[0][idx], averaged[1][idx], averaged[2][idx]);
+                let (h1, h2) = (averaged[3][idx], averaged[4][idx]);
+                let det = g11 * g22 - g12 * g12;
+                if det.abs() < 1e-12 {
+                    return flow.data[idx];
+                }
+                ((g22 * h1 - g12 * h2) / det, (g11 * h2 - g12 * h1) / det)
+            })
+            .collect();
+
+        FlowField {
+            width,
+            height,
+            data,
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use glance_core::img::{Image, pixel::Pixel};
+use glance_core::par::*;
+
+use crate::linear_filters::BorderMode;
+
+/// Extension trait for [`glance_core::img::Image`] to extend images of any pixel type with a
+/// border.
+pub trait PaddingExt {
+    fn pad(self, top: usize, bottom: usize, left: usize, right: usize, border: BorderMode) -> Self;
+}
+
+impl<P> PaddingExt for Image<P>
+where
+    P: Pixel,
+{
+    /// Returns a larger image with `top`, `bottom`, `left` and `right` pixels added on each side,
+    /// filled according to `border`. `BorderMode::Constant(v)` sets every channel (including
+    /// alpha) of the added pixels to `v`. Padding an empty image always uses a constant border.
+    fn pad(self, top: usize, bottom: usize, left: usize, right: usize, border: BorderMode) -> Self {
+        let (width, height) = self.dimensions();
+        let (out_width, out_height) = (width + left + right, height + top + bottom);
+
+        let fill = match border {
+            BorderMode::Constant(value) => value,
+            _ => 0.0,
+        };
+        let constant = P::new().map(|_| fill);
+        let border = if self.is_empty() {
+            BorderMode::Constant(fill)
+        } else {
+            border
+        };
+
+        let padded = (0..out_width * out_height)
+            .into_par_iter()
+            .map(|idx| {
+                let x = (idx % out_width) as isize - left as isize;
+                let y = (idx / out_width) as isize - top as isize;
+                match (border.resolve(x, width), border.resolve(y, height)) {
+                    (Some(sx), Some(sy)) => *self.get_pixel((sx, sy)).unwrap(),
+                    _ => constant,
+                }
+            })
+            .collect();
+
+        Image::from_data(out_width, out_height, padded).unwrap()
+    }
+}
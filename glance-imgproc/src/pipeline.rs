@@ -0,0 +1,285 @@
+//! Lazily evaluated chains of operations. The extension traits consume and return a new image
+//! for every step; a [`Pipeline`] records the steps instead and runs them in as few passes as
+//! possible, fusing per pixel operations into the pass before them and reusing two working
+//! buffers for all neighbourhood operations.
+
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Rgba},
+};
+use glance_core::par::*;
+
+use crate::Result;
+use crate::kernels;
+use crate::linear_filters::BorderMode;
+use crate::nonlinear_filters::structuring_offsets;
+use crate::point_ops::{GrayscaleMethod, ThresholdType, gray_value, threshold_value};
+
+/// An operation on single pixels, fused into the surrounding passes.
+#[derive(Debug, Clone, Copy)]
+enum PointOp {
+    Invert,
+    Gamma(f32),
+    Threshold(f32, f32, ThresholdType),
+}
+
+impl PointOp {
+    fn apply(&self, l: f32) -> f32 {
+        match *self {
+            PointOp::Invert => 1.0 - l,
+            PointOp::Gamma(gamma) => l.powf(1.0 / gamma),
+            PointOp::Threshold(threshold, max, kind) => threshold_value(l, threshold, max, kind),
+        }
+    }
+}
+
+/// An operation reading a neighbourhood of pixels, which needs its own pass.
+#[derive(Debug, Clone)]
+enum NeighbourhoodOp {
+    GaussianBlur(Vec<f32>),
+    Dilate(Vec<(isize, isize)>, BorderMode),
+    Erode(Vec<(isize, isize)>, BorderMode),
+}
+
+/// A pass: an optional neighbourhood operation followed by the point operations fused into it.
+#[derive(Debug, Clone)]
+struct Pass {
+    op: Option<NeighbourhoodOp>,
+    point_ops: Vec<PointOp>,
+}
+
+/// A recorded chain of grayscale operations, built with chained calls and executed with
+/// [`Pipeline::run`] or [`Pipeline::run_luma`]. The results match the equivalent calls of
+/// [`crate::point_ops::PointOpsExtLuma`], [`crate::linear_filters::LinearFilterExtLuma`] and
+/// [`crate::nonlinear_filters::NonLinearFilterExtLuma`].
+///
+/// ```
+/// # use glance_core::img::{Image, pixel::Rgba};
+/// # use glance_imgproc::kernels;
+/// # use glance_imgproc::linear_filters::BorderMode;
+/// # use glance_imgproc::pipeline::Pipeline;
+/// # use glance_imgproc::point_ops::ThresholdType;
+/// let pipeline = Pipeline::new()
+///     .gaussian_blur(1.5)
+///     .threshold(0.5, 1.0, ThresholdType::Binary)
+///     .dilate(&kernels::ellipse(5, 5), BorderMode::Constant(0.0))
+///     .unwrap();
+///
+/// let image = Image::from_data(8, 8, vec![Rgba { r: 1.0, g: 1.0, b: 1.0, a: 1.0 }; 64]).unwrap();
+/// let mask = pipeline.run(&image);
+/// assert_eq!(mask.dimensions(), (8, 8));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pipeline {
+    grayscale: GrayscaleMethod,
+    passes: Vec<Pass>,
+}
+
+impl Default for Pipeline {
+    fn default() -> Self {
+        Self {
+            grayscale: GrayscaleMethod::Bt601Gamma,
+            passes: vec![Pass {
+                op: None,
+                point_ops: Vec::new(),
+            }],
+        }
+    }
+}
+
+impl Pipeline {
+    /// Creates an empty pipeline, converting RGBA inputs with [`GrayscaleMethod::Bt601Gamma`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the grayscale conversion used by [`Pipeline::run`].
+    pub fn grayscale_with(mut self, method: GrayscaleMethod) -> Self {
+        self.grayscale = method;
+        self
+    }
+
+    fn point(mut self, op: PointOp) -> Self {
+        self.passes.last_mut().unwrap().point_ops.push(op);
+        self
+    }
+
+    fn neighbourhood(mut self, op: NeighbourhoodOp) -> Self {
+        self.passes.push(Pass {
+            op: Some(op),
+            point_ops: Vec::new(),
+        });
+        self
+    }
+
+    /// See [`crate::point_ops::PointOpsExtLuma::invert`].
+    pub fn invert(self) -> Self {
+        self.point(PointOp::Invert)
+    }
+
+    /// See [`crate::point_ops::PointOpsExtLuma::gamma`].
+    pub fn gamma(self, gamma: f32) -> Self {
+        self.point(PointOp::Gamma(gamma))
+    }
+
+    /// See [`crate::point_ops::PointOpsExtLuma::threshold`].
+    pub fn threshold(self, threshold: f32, max_intensity: f32, kind: ThresholdType) -> Self {
+        self.point(PointOp::Threshold(threshold, max_intensity, kind))
+    }
+
+    /// See [`crate::linear_filters::LinearFilterExtLuma::gaussian_blur`].
+    pub fn gaussian_blur(self, sigma: f32) -> Self {
+        if sigma <= 0.0 {
+            return self;
+        }
+        self.neighbourhood(NeighbourhoodOp::GaussianBlur(kernels::gaussian_1d(sigma)))
+    }
+
+    /// See [`crate::nonlinear_filters::NonLinearFilterExtLuma::dilate`]. Fails for kernels with
+    /// even dimensions.
+    pub fn dilate(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Self> {
+        let offsets = structuring_offsets(kernel)?;
+        Ok(self.neighbourhood(NeighbourhoodOp::Dilate(offsets, border)))
+    }
+
+    /// See [`crate::nonlinear_filters::NonLinearFilterExtLuma::erode`]. Fails for kernels with
+    /// even dimensions.
+    pub fn erode(self, kernel: &Image<Luma>, border: BorderMode) -> Result<Self> {
+        let offsets = structuring_offsets(kernel)?;
+        Ok(self.neighbourhood(NeighbourhoodOp::Erode(offsets, border)))
+    }
+
+    /// Returns the number of passes over the image a run takes, including loading the input.
+    pub fn pass_count(&self) -> usize {
+        self.passes.len()
+    }
+
+    /// Converts the image to grayscale and runs the pipeline on it.
+    pub fn run(&self, image: &Image<Rgba>) -> Image<Luma> {
+        let (width, height) = image.dimensions();
+        let first = &self.passes[0];
+        let plane = image
+            .par_pixels()
+            .map(|p| fold_points(&first.point_ops, gray_value(p, self.grayscale)))
+            .collect();
+        self.finish(plane, (width, height))
+    }
+
+    /// Runs the pipeline on a grayscale image.
+    pub fn run_luma(&self, image: &Image<Luma>) -> Image<Luma> {
+        let (width, height) = image.dimensions();
+        let first = &self.passes[0];
+        let plane = image
+            .par_pixels()
+            .map(|p| fold_points(&first.point_ops, p.l))
+            .collect();
+        self.finish(plane, (width, height))
+    }
+
+    /// Runs the remaining passes on the loaded plane, ping-ponging between two buffers.
+    fn finish(&self, mut current: Vec<f32>, dimensions: (usize, usize)) -> Image<Luma> {
+        let (width, height) = dimensions;
+        let mut scratch = vec![0.0; current.len()];
+
+        for pass in &self.passes[1..] {
+            let points = &pass.point_ops;
+            match pass.op.as_ref().unwrap() {
+                NeighbourhoodOp::GaussianBlur(kernel) => {
+                    // Rows into the scratch buffer, then columns back with the point ops applied
+                    let radius = (kernel.len() / 2) as isize;
+                    let at = |x: isize, len: usize| x.clamp(0, len as isize - 1) as usize;
+                    scratch
+                        .par_chunks_mut(width.max(1))
+                        .zip(current.par_chunks(width.max(1)))
+                        .for_each(|(out, row)| {
+                            for (x, value) in out.iter_mut().enumerate() {
+                                *value = kernel
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(k, w)| {
+                                        w * row[at(x as isize + k as isize - radius, width)]
+                                    })
+                                    .sum();
+                            }
+                        });
+                    let rows = &scratch;
+                    current.par_iter_mut().enumerate().for_each(|(idx, value)| {
+                        let (x, y) = (idx % width, (idx / width) as isize);
+                        let l: f32 = kernel
+                            .iter()
+                            .enumerate()
+                            .map(|(k, w)| w * rows[at(y + k as isize - radius, height) * width + x])
+                            .sum();
+                        *value = fold_points(points, l);
+                    });
+                }
+                NeighbourhoodOp::Dilate(offsets, border) => {
+                    morphology(
+                        &current,
+                        &mut scratch,
+                        dimensions,
+                        offsets,
+                        *border,
+                        points,
+                        true,
+                    );
+                    std::mem::swap(&mut current, &mut scratch);
+                }
+                NeighbourhoodOp::Erode(offsets, border) => {
+                    morphology(
+                        &current,
+                        &mut scratch,
+                        dimensions,
+                        offsets,
+                        *border,
+                        points,
+                        false,
+                    );
+                    std::mem::swap(&mut current, &mut scratch);
+                }
+            }
+        }
+
+        let data = current.into_iter().map(|l| Luma { l }).collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+}
+
+fn fold_points(ops: &[PointOp], l: f32) -> f32 {
+    ops.iter().fold(l, |l, op| op.apply(l))
+}
+
+/// Writes the dilation (`max` true) or erosion of `input` into `output`, with the point ops
+/// applied.
+fn morphology(
+    input: &[f32],
+    output: &mut [f32],
+    dimensions: (usize, usize),
+    offsets: &[(isize, isize)],
+    border: BorderMode,
+    points: &[PointOp],
+    max: bool,
+) {
+    let (width, height) = dimensions;
+    let (init, f): (f32, fn(f32, f32) -> f32) = if max {
+        (f32::NEG_INFINITY, f32::max)
+    } else {
+        (f32::INFINITY, f32::min)
+    };
+
+    output.par_iter_mut().enumerate().for_each(|(idx, value)| {
+        let (x, y) = ((idx % width) as isize, (idx / width) as isize);
+        let l = offsets.iter().fold(init, |acc, (dx, dy)| {
+            match (
+                border.resolve(x + dx, width),
+                border.resolve(y + dy, height),
+                border,
+            ) {
+                (Some(sx), Some(sy), _) => f(acc, input[sy * width + sx]),
+                (_, _, BorderMode::Constant(c)) => f(acc, c),
+                _ => acc,
+            }
+        });
+        *value = fold_points(points, l);
+    });
+}
@@ -1,8 +1,11 @@
 use glance_core::img::{
     Image,
-    pixel::{Luma, Rgba},
+    pixel::{Linear, Luma, Pixel, Rgba, SrgbPixel},
 };
-use rayon::iter::ParallelIterator;
+use glance_core::par::ParallelIterator;
+
+use crate::color::{Hsv, linear_to_srgb, srgb_to_linear};
+use crate::{Error, Result};
 
 #[derive(Debug, Clone, Copy)]
 pub enum ThresholdType {
@@ -14,15 +17,87 @@ pub enum ThresholdType {
     ToZero,
 }
 
+#[derive(Debug, Clone, Copy)]
+pub enum GrayscaleMethod {
+    /// BT.601 weights applied directly to the gamma-encoded values. Fast, but darkens
+    /// saturated colors.
+    Bt601Gamma,
+    /// BT.709 (sRGB) luminance computed in linear light and re-encoded to sRGB.
+    Bt709Linear,
+    /// Unweighted mean of the red, green and blue channels.
+    Average,
+    /// Midpoint of the largest and smallest of the red, green and blue channels.
+    Lightness,
+    /// Largest of the red, green and blue channels.
+    MaxChannel,
+}
+
+/// Intensity of a pixel according to `method`, see [`PointOpsExtRgba::grayscale_with`].
+pub(crate) fn gray_value(pixel: &Rgba, method: GrayscaleMethod) -> f32 {
+    match method {
+        GrayscaleMethod::Bt601Gamma => pixel.luminance(),
+        GrayscaleMethod::Bt709Linear => {
+            let luminance = srgb_to_linear(pixel.r) * 0.2126
+                + srgb_to_linear(pixel.g) * 0.7152
+                + srgb_to_linear(pixel.b) * 0.0722;
+            linear_to_srgb(luminance)
+        }
+        GrayscaleMethod::Average => (pixel.r + pixel.g + pixel.b) / 3.0,
+        GrayscaleMethod::Lightness => {
+            let max = pixel.r.max(pixel.g).max(pixel.b);
+            let min = pixel.r.min(pixel.g).min(pixel.b);
+            (max + min) / 2.0
+        }
+        GrayscaleMethod::MaxChannel => pixel.r.max(pixel.g).max(pixel.b),
+    }
+}
+
+/// Thresholded intensity of `l`, see [`PointOpsExtLuma::threshold`].
+pub(crate) fn threshold_value(
+    l: f32,
+    threshold: f32,
+    max_intensity: f32,
+    kind: ThresholdType,
+) -> f32 {
+    match kind {
+        ThresholdType::Binary => {
+            if l >= threshold {
+                max_intensity
+            } else {
+                0.0
+            }
+        }
+        ThresholdType::Truncate => {
+            if l > threshold {
+                threshold
+            } else {
+                l
+            }
+        }
+        ThresholdType::ToZero => {
+            if l > threshold {
+                l
+            } else {
+                0.0
+            }
+        }
+    }
+}
+
 /// Extension trait for [`glance_core::img::Image`] to provide point operations for RGBA images
 pub trait PointOpsExtRgba {
     fn invert(self) -> Self;
     fn gamma(self, gamma: f32) -> Self;
     fn grayscale(self) -> Image<Luma>;
+    fn grayscale_with(self, method: GrayscaleMethod) -> Image<Luma>;
     //fn histrogram_equalize(self) -> Self;
-    fn lerp(self, other: &Image<Rgba>, alpha: f32) -> Image<Rgba>;
+    fn lerp(self, other: &Image<Rgba>, alpha: f32) -> Result<Image<Rgba>>;
     fn brightness(self, brightness: f32) -> Image<Rgba>;
     fn contrast(self, contrast: f32) -> Image<Rgba>;
+    fn channel_mix(self, matrix: [[f32; 4]; 4]) -> Image<Rgba>;
+    fn in_range(self, lower: Rgba, upper: Rgba) -> Image<Luma>;
+    fn in_range_hsv(self, lower: Hsv, upper: Hsv) -> Image<Luma>;
+    fn chroma_key(self, key_color: Rgba, tolerance: f32, softness: f32) -> Image<Rgba>;
 }
 
 /// Extension trait for [`glance_core::img::Image`] to provide point operations for Luma images
@@ -33,6 +108,12 @@ pub trait PointOpsExtLuma {
     fn histrogram_equalize(self) -> Self;
 }
 
+/// Extension trait for [`glance_core::img::Image`] to provide point operations for images in
+/// linear light, see [`Image::to_linear`].
+pub trait PointOpsExtLinear<P: SrgbPixel> {
+    fn lerp(self, other: &Image<Linear<P>>, alpha: f32) -> Result<Image<Linear<P>>>;
+}
+
 impl PointOpsExtRgba for Image<Rgba> {
     /// Inverts the colors of the image by subtracting each pixel's RGB values from the maximum value
     fn invert(mut self) -> Self {
@@ -50,6 +131,9 @@ impl PointOpsExtRgba for Image<Rgba> {
 
     /// Returns an image with given gamma applied.
     /// final = initial ^ (1 / gamma)
+    /// The exponent applies to the values as they are stored, sRGB encoded for images loaded
+    /// from files, so a `gamma` of 2.2 roughly encodes linear values rather than decoding sRGB;
+    /// use [`Image::to_linear`] for an exact conversion.
     fn gamma(mut self, gamma: f32) -> Self {
         let inv_gamma = 1.0 / gamma;
 
@@ -71,42 +155,46 @@ impl PointOpsExtRgba for Image<Rgba> {
     /// Returns a grayscale image from the RGBA image. Weights are in accordance with the BT.601
     /// standard. The returned image maintains the prcision of the original image's pixel type, but with only
     /// one channel (luminance) (see [`Luma`]).
+    /// Equivalent to [`PointOpsExtRgba::grayscale_with`] with [`GrayscaleMethod::Bt601Gamma`].
     fn grayscale(self) -> Image<Luma> {
+        self.grayscale_with(GrayscaleMethod::Bt601Gamma)
+    }
+
+    /// Returns a grayscale image from the RGBA image using the given [`GrayscaleMethod`].
+    /// [`GrayscaleMethod::Bt709Linear`] decodes the sRGB values to linear light before weighting
+    /// them, which keeps the perceived brightness of saturated colors intact.
+    fn grayscale_with(self, method: GrayscaleMethod) -> Image<Luma> {
         let (width, height) = self.dimensions();
         let gray_pixels = self
             .pixels()
-            .map(|pixel| {
-                let intensity = pixel.r * 0.299 + pixel.g * 0.587 + pixel.b * 0.114;
-                Luma { l: intensity }
+            .map(|pixel| Luma {
+                l: gray_value(&pixel, method),
             })
             .collect();
 
         Image::from_data(width, height, gray_pixels).unwrap()
     }
 
-    /// Linearly interpolates between two images of the same dimensions.
-    /// The alpha parameter controls the interpolation factor.
-    fn lerp(self, other: &Image<Rgba>, alpha: f32) -> Image<Rgba> {
+    /// Linearly interpolates between two images of the same dimensions, failing with
+    /// [`Error::DimensionMismatch`] otherwise. The alpha parameter controls the interpolation
+    /// factor. The sRGB values are interpolated as they are, which darkens the blend; blend
+    /// images converted with [`Image::to_linear`] for physically correct mixing, see
+    /// [`PointOpsExtLinear::lerp`].
+    fn lerp(self, other: &Image<Rgba>, alpha: f32) -> Result<Image<Rgba>> {
         let (width, height) = self.dimensions();
         if (width, height) != other.dimensions() {
-            panic!(
-                "Cannot lerp images of different dimensions: {:?} and {:?}",
-                (width, height),
-                other.dimensions()
-            );
+            return Err(Error::DimensionMismatch {
+                expected: (width, height),
+                actual: other.dimensions(),
+            });
         }
         let lerped_pixels = self
             .pixels()
             .zip(other.pixels())
-            .map(|(px1, px2)| Rgba {
-                r: px1.r * (1.0 - alpha) + px2.r * alpha,
-                g: px1.g * (1.0 - alpha) + px2.g * alpha,
-                b: px1.b * (1.0 - alpha) + px2.b * alpha,
-                a: px1.a * (1.0 - alpha) + px2.a * alpha,
-            })
+            .map(|(px1, px2)| px1.lerp(&px2, alpha))
             .collect::<Vec<_>>();
 
-        Image::from_data(width, height, lerped_pixels).unwrap()
+        Ok(Image::from_data(width, height, lerped_pixels)?)
     }
 
     /// Adjusts the brightness of the image by adding a value to each pixel's RGB channels.
@@ -142,6 +230,120 @@ impl PointOpsExtRgba for Image<Rgba> {
 
         Image::from_data(width, height, adjusted_pixels).unwrap()
     }
+
+    /// Applies a linear channel-mixing matrix to every pixel. Each row of `matrix` produces one
+    /// output channel as a weighted sum of the input `[r, g, b, a]` channels, so
+    /// `matrix[0] = [0.0, 0.0, 1.0, 0.0]` writes the input blue channel into the output red
+    /// channel. The intensities are clamped to the [0.0, 1.0] range.
+    fn channel_mix(mut self, matrix: [[f32; 4]; 4]) -> Image<Rgba> {
+        self.par_pixels_mut().for_each(|pixel| {
+            let input = [pixel.r, pixel.g, pixel.b, pixel.a];
+            let mix = |row: [f32; 4]| {
+                (row[0] * input[0] + row[1] * input[1] + row[2] * input[2] + row[3] * input[3])
+                    .clamp(0.0, 1.0)
+            };
+            *pixel = Rgba {
+                r: mix(matrix[0]),
+                g: mix(matrix[1]),
+                b: mix(matrix[2]),
+                a: mix(matrix[3]),
+            };
+        });
+
+        self
+    }
+
+    /// Returns a binary mask where pixels whose red, green and blue channels all lie within
+    /// `[lower, upper]` (inclusive) are set to 1.0 and all others to 0.0. Alpha is ignored.
+    fn in_range(self, lower: Rgba, upper: Rgba) -> Image<Luma> {
+        let (width, height) = self.dimensions();
+        let mask_pixels = self
+            .pixels()
+            .map(|pixel| {
+                let inside = (lower.r..=upper.r).contains(&pixel.r)
+                    && (lower.g..=upper.g).contains(&pixel.g)
+                    && (lower.b..=upper.b).contains(&pixel.b);
+                Luma {
+                    l: if inside { 1.0 } else { 0.0 },
+                }
+            })
+            .collect();
+
+        Image::from_data(width, height, mask_pixels).unwrap()
+    }
+
+    /// Returns a binary mask of the pixels whose HSV representation lies within `[lower, upper]`
+    /// (inclusive). If `lower.h > upper.h` the hue range wraps around 360 degrees, so reds can be
+    /// selected with e.g. `lower.h = 340.0` and `upper.h = 20.0`. Alpha is ignored.
+    fn in_range_hsv(self, lower: Hsv, upper: Hsv) -> Image<Luma> {
+        let (width, height) = self.dimensions();
+        let mask_pixels = self
+            .pixels()
+            .map(|pixel| {
+                let hsv = Hsv::from_rgba(&pixel);
+                let hue_inside = if lower.h <= upper.h {
+                    (lower.h..=upper.h).contains(&hsv.h)
+                } else {
+                    hsv.h >= lower.h || hsv.h <= upper.h
+                };
+                let inside = hue_inside
+                    && (lower.s..=upper.s).contains(&hsv.s)
+                    && (lower.v..=upper.v).contains(&hsv.v);
+                Luma {
+                    l: if inside { 1.0 } else { 0.0 },
+                }
+            })
+            .collect();
+
+        Image::from_data(width, height, mask_pixels).unwrap()
+    }
+
+    /// Removes the background of the given `key_color` (e.g. a green or blue screen) by writing an
+    /// alpha matte into the alpha channel.
+    /// Pixels are compared to the key by their distance in the CbCr chroma plane, so changes in
+    /// brightness alone matter less than changes in hue. Distances below `tolerance` become
+    /// fully transparent, distances above `tolerance + softness` stay opaque and the band in
+    /// between fades linearly. The chroma distance between two colors lies in [0.0, ~0.7].
+    /// Remaining pixels are despilled by clamping the key's dominant channel to the larger of the
+    /// other two, which removes the colored fringe the screen reflects onto edges.
+    fn chroma_key(mut self, key_color: Rgba, tolerance: f32, softness: f32) -> Image<Rgba> {
+        let chroma = |pixel: &Rgba| {
+            let cb = -0.168_736 * pixel.r - 0.331_264 * pixel.g + 0.5 * pixel.b;
+            let cr = 0.5 * pixel.r - 0.418_688 * pixel.g - 0.081_312 * pixel.b;
+            (cb, cr)
+        };
+        let (key_cb, key_cr) = chroma(&key_color);
+
+        // Channel the screen spills onto the subject
+        let spill_channel = if key_color.g >= key_color.r && key_color.g >= key_color.b {
+            1
+        } else if key_color.b >= key_color.r {
+            2
+        } else {
+            0
+        };
+
+        self.par_pixels_mut().for_each(|pixel| {
+            let (cb, cr) = chroma(pixel);
+            let distance = ((cb - key_cb).powi(2) + (cr - key_cr).powi(2)).sqrt();
+            let matte = if distance <= tolerance {
+                0.0
+            } else if softness <= 0.0 || distance >= tolerance + softness {
+                1.0
+            } else {
+                (distance - tolerance) / softness
+            };
+
+            match spill_channel {
+                0 => pixel.r = pixel.r.min(pixel.g.max(pixel.b)),
+                1 => pixel.g = pixel.g.min(pixel.r.max(pixel.b)),
+                _ => pixel.b = pixel.b.min(pixel.r.max(pixel.g)),
+            }
+            pixel.a *= matte;
+        });
+
+        self
+    }
 }
 
 impl PointOpsExtLuma for Image<Luma> {
@@ -154,7 +356,8 @@ impl PointOpsExtLuma for Image<Luma> {
         self
     }
 
-    /// Returns an image with given gamma applied.
+    /// Returns an image with given gamma applied, to the values as they are stored. See
+    /// [`PointOpsExtRgba::gamma`] for the encoding.
     fn gamma(mut self, gamma: f32) -> Self {
         let inv_gamma = 1.0 / gamma;
 
@@ -177,32 +380,8 @@ impl PointOpsExtLuma for Image<Luma> {
         let (width, height) = self.dimensions();
         let thresholded_pixels = self
             .pixels()
-            .map(|pixel| {
-                let l = pixel.l;
-                let new_l = match kind {
-                    ThresholdType::Binary => {
-                        if l >= threshold {
-                            max_intensity
-                        } else {
-                            0.0
-                        }
-                    }
-                    ThresholdType::Truncate => {
-                        if l > threshold {
-                            threshold
-                        } else {
-                            l
-                        }
-                    }
-                    ThresholdType::ToZero => {
-                        if l > threshold {
-                            l
-                        } else {
-                            0.0
-                        }
-                    }
-                };
-                Luma { l: new_l }
+            .map(|pixel| Luma {
+                l: threshold_value(pixel.l, threshold, max_intensity, kind),
             })
             .collect();
 
@@ -214,7 +393,7 @@ impl PointOpsExtLuma for Image<Luma> {
     fn histrogram_equalize(mut self) -> Self {
         let (width, height) = self.dimensions();
         let pixel_count = (width * height) as u32;
-        let channel_max = 255 as usize;
+        let channel_max = 255_usize;
 
         // Find histogram
         let mut hist = vec![0u32; channel_max + 1];
@@ -253,3 +432,25 @@ impl PointOpsExtLuma for Image<Luma> {
         self
     }
 }
+
+impl<P: SrgbPixel> PointOpsExtLinear<P> for Image<Linear<P>> {
+    /// Linearly interpolates between two images of the same dimensions, failing with
+    /// [`Error::DimensionMismatch`] otherwise. Mixing linear values blends light physically
+    /// correctly, unlike [`PointOpsExtRgba::lerp`] on sRGB images.
+    fn lerp(self, other: &Image<Linear<P>>, alpha: f32) -> Result<Image<Linear<P>>> {
+        let (width, height) = self.dimensions();
+        if (width, height) != other.dimensions() {
+            return Err(Error::DimensionMismatch {
+                expected: (width, height),
+                actual: other.dimensions(),
+            });
+        }
+        let lerped_pixels = self
+            .pixels()
+            .zip(other.pixels())
+            .map(|(px1, px2)| px1.lerp(&px2, alpha))
+            .collect();
+
+        Ok(Image::from_data(width, height, lerped_pixels)?)
+    }
+}
@@ -0,0 +1,74 @@
+//! Quiver plots, drawing a grid of arrows to visualize dense vector fields like optical flow
+//! or image gradients.
+
+use glance_core::drawing::shapes::Arrow;
+use glance_core::drawing::traits::Drawable;
+use glance_core::img::{Image, pixel::Pixel};
+
+use crate::Result;
+use crate::gradient::GradientField;
+use crate::optical_flow::FlowField;
+
+/// A dense field of 2D vectors in pixel units, one per pixel.
+pub trait VectorField {
+    /// Returns the dimensions of the field as a tuple (width, height).
+    fn dimensions(&self) -> (usize, usize);
+    /// Returns the vector (dx, dy) at the specified position, which must be in bounds.
+    fn vector(&self, position: (usize, usize)) -> (f32, f32);
+}
+
+impl VectorField for FlowField {
+    fn dimensions(&self) -> (usize, usize) {
+        FlowField::dimensions(self)
+    }
+
+    fn vector(&self, position: (usize, usize)) -> (f32, f32) {
+        self.get(position).unwrap_or((0.0, 0.0))
+    }
+}
+
+impl VectorField for GradientField {
+    fn dimensions(&self) -> (usize, usize) {
+        GradientField::dimensions(self)
+    }
+
+    fn vector(&self, position: (usize, usize)) -> (f32, f32) {
+        self.get(position).unwrap_or((0.0, 0.0))
+    }
+}
+
+/// Draws the vectors of `field` as arrows onto `image`, one every `stride` pixels in both
+/// directions starting half a stride from the top left corner. Arrows are `scale` times as long
+/// as their vectors; vectors shorter than half a pixel after scaling are skipped.
+pub fn draw_quiver<P: Pixel>(
+    image: &mut Image<P>,
+    field: &impl VectorField,
+    stride: usize,
+    scale: f32,
+    color: P,
+) -> Result<()> {
+    let stride = stride.max(1);
+    let (width, height) = field.dimensions();
+    for y in (stride / 2..height).step_by(stride) {
+        for x in (stride / 2..width).step_by(stride) {
+            let (dx, dy) = field.vector((x, y));
+            let (dx, dy) = (dx * scale, dy * scale);
+            if dx.hypot(dy) < 0.5 {
+                continue;
+            }
+            let end = (
+                (x as f32 + dx).round().max(0.0) as usize,
+                (y as f32 + dy).round().max(0.0) as usize,
+            );
+            Arrow {
+                start: (x, y),
+                end,
+                color,
+                thickness: 1,
+                tip_length: 0.3,
+            }
+            .draw_on(image)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use glance_core::img::{Image, Rect, pixel::Luma};
+use glance_core::par::*;
+
+use crate::affine::{self, Interpolation};
+use crate::fft::{Complex, fft_2d};
+use crate::integral::IntegralImageExtLuma;
+use crate::linear_filters::LinearFilterExtLuma;
+
+/// Side length the image is resized to for the spectral residual. Saliency is a coarse cue,
+/// and a small spectrum keeps the method fast and insensitive to fine texture.
+const SPECTRAL_SIZE: usize = 64;
+/// Radii of the surround windows compared against every pixel by the fine grained saliency.
+const SURROUND_RADII: [usize; 4] = [1, 3, 7, 15];
+
+/// Rescales the values to [0.0, 1.0], leaving constant planes at 0.0.
+fn normalize(values: &mut [f32]) {
+    let (min, max) = values
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let range = max - min;
+    values
+        .iter_mut()
+        .for_each(|v| *v = if range > 0.0 { (*v - min) / range } else { 0.0 });
+}
+
+/// Extension trait for [`glance_core::img::Image`] to provide saliency maps for Luma images.
+/// Saliency maps are in [0.0, 1.0], higher values marking regions likely to attract attention.
+pub trait SaliencyExtLuma {
+    fn saliency_spectral_residual(&self) -> Image<Luma>;
+    fn saliency_fine_grained(&self) -> Image<Luma>;
+}
+
+impl SaliencyExtLuma for Image<Luma> {
+    /// Spectral residual saliency (Hou and Zhang, 2007). The log amplitude spectrum of natural
+    /// images is smooth, so what remains after subtracting its local average (the residual) is
+    /// attributed to unexpected, salient content. Cheap and coarse, well suited to pick crops.
+    fn saliency_spectral_residual(&self) -> Image<Luma> {
+        let (width, height) = self.dimensions();
+        if width == 0 || height == 0 {
+            return self.clone();
+        }
+
+        let small = affine::scale_unchecked(
+            self,
+            SPECTRAL_SIZE as f32 / width as f32,
+            SPECTRAL_SIZE as f32 / height as f32,
+            Interpolation::Bilinear,
+        );
+        let n = SPECTRAL_SIZE;
+        let mut spectrum: Vec<Complex> = small
+            .pixels()
+            .map(|p| Complex::new(p.l as f64, 0.0))
+            .collect();
+        fft_2d(&mut spectrum, n, n, false);
+
+        // log(1 + amplitude) keeps the exact zeros of synthetic images finite
+        let log_amplitude: Vec<f64> = spectrum.iter().map(|c| c.norm().ln_1p()).collect();
+        let empty = 1e-9 * spectrum.iter().map(|c| c.norm()).fold(0.0, f64::max);
+        // Residual against the 3x3 mean of the log amplitude (wrapping, as the spectrum is periodic)
+        for (idx, c) in spectrum.iter_mut().enumerate() {
+            let (x, y) = (idx % n, idx / n);
+            let mut mean = 0.0;
+            for dy in [n - 1, 0, 1] {
+                for dx in [n - 1, 0, 1] {
+                    mean += log_amplitude[((y + dy) % n) * n + (x + dx) % n];
+                }
+            }
+            let residual = log_amplitude[idx] - mean / 9.0;
+            // Empty bins carry no phase information and stay empty
+            let amplitude = if c.norm() > empty {
+                residual.exp()
+            } else {
+                0.0
+            };
+            *c = Complex::from_polar(amplitude, c.arg());
+        }
+        fft_2d(&mut spectrum, n, n, true);
+
+        let energy: Vec<Luma> = spectrum
+            .iter()
+            .map(|c| Luma {
+                l: (c.norm() * c.norm()) as f32,
+            })
+            .collect();
+        let map = affine::scale_unchecked(
+            &Image::from_data(n, n, energy).unwrap().gaussian_blur(2.5),
+            width as f32 / n as f32,
+            height as f32 / n as f32,
+            Interpolation::Bilinear,
+        );
+
+        let mut values: Vec<f32> = map.pixels().map(|p| p.l).collect();
+        normalize(&mut values);
+        let data = values.into_iter().map(|l| Luma { l }).collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+
+    /// Fine grained saliency (Montabone and Soto, 2010): center-surround contrast of every
+    /// pixel against windows of several sizes, computed with an integral image. Keeps the full
+    /// resolution and follows object outlines more closely than the spectral residual.
+    fn saliency_fine_grained(&self) -> Image<Luma> {
+        let (width, height) = self.dimensions();
+        let integral = self.integral_image();
+        let pixels: Vec<f32> = self.pixels().map(|p| p.l).collect();
+
+        let mut values: Vec<f32> = (0..width * height)
+            .into_par_iter()
+            .map(|idx| {
+                let (x, y) = (idx % width, idx / width);
+                let (mut on, mut off) = (0.0, 0.0);
+                for radius in SURROUND_RADII {
+                    let (x0, y0) = (x.saturating_sub(radius), y.saturating_sub(radius));
+                    let rect = Rect::new(x0, y0, x + radius + 1 - x0, y + radius + 1 - y0);
+                    let contrast = pixels[idx] - integral.mean_region(rect) as f32;
+                    // Brighter (on-center) and darker (off-center) than the surround
+                    on += contrast.max(0.0);
+                    off += (-contrast).max(0.0);
+                }
+                on + off
+            })
+            .collect();
+
+        normalize(&mut values);
+        let data = values.into_iter().map(|l| Luma { l }).collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+}
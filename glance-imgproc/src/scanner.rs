@@ -0,0 +1,242 @@
+//! Scanning of photographed documents: the page is found as the largest quadrilateral outline
+//! among the contours of the edge map, rectified with a homography onto an upright rectangle and
+//! optionally binarized for a clean black-and-white result.
+
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Pixel},
+};
+
+use crate::affine::{self, AffineTransformationsExt, CoordinateMap, Interpolation};
+use crate::contours::{Contour, ContourExtLuma};
+use crate::estimation::estimate_homography;
+use crate::geometry::{self, Point};
+use crate::gradient::GradientExtLuma;
+use crate::integral::IntegralImageExtLuma;
+use crate::linear_filters::LinearFilterExtLuma;
+use crate::nonlinear_filters::NonLinearFilterExtLuma;
+
+/// Longest side of the downscaled copy the page outline is searched in.
+const DETECTION_SIZE: f32 = 512.0;
+/// Edges are gradients stronger than this fraction of the strongest one.
+const EDGE_FRACTION: f32 = 0.2;
+/// Sobel magnitude below which nothing is an edge, that of a step of about 0.03 after blurring.
+const MIN_EDGE_STRENGTH: f32 = 0.1;
+/// A contour is a quadrilateral if its best inscribed quadrilateral covers this much of its
+/// convex hull.
+const QUAD_COVERAGE: f32 = 0.9;
+/// Hull vertices closer than this many pixels to the simplified hull are dropped before the
+/// quadrilateral search.
+const HULL_EPSILON: f32 = 1.0;
+/// Dark pixels must be this much darker than their neighborhood mean when binarizing.
+const BINARIZE_OFFSET: f32 = 0.05;
+
+/// Options of [`DocumentScanExt::scan_document`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanOptions {
+    /// Width over height of the output, e.g. `1.0 / 2f32.sqrt()` for an upright A4 page. By
+    /// default it follows the side lengths of the page outline in the image.
+    pub aspect: Option<f32>,
+    /// Binarize the rectified page with an adaptive threshold
+    pub binarize: bool,
+    /// Smallest page area, as a fraction of the image area
+    pub min_area: f32,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            aspect: None,
+            binarize: false,
+            min_area: 0.2,
+        }
+    }
+}
+
+/// Returns twice the signed area of the triangle (a, b, c).
+fn cross(a: Point, b: Point, c: Point) -> f32 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+/// Returns the quadrilateral of largest area with vertices on the convex polygon `hull`, and
+/// its area.
+fn largest_quad(hull: &[Point]) -> Option<([Point; 4], f32)> {
+    let n = hull.len();
+    if n < 4 {
+        return None;
+    }
+    // With the diagonal (a, c) fixed, the other two vertices are the farthest on each side
+    let farthest = |a: usize, c: usize, range: std::ops::Range<usize>| {
+        range
+            .map(|i| (i % n, cross(hull[a], hull[c], hull[i % n]).abs()))
+            .max_by(|x, y| x.1.total_cmp(&y.1))
+    };
+    let mut best: Option<([Point; 4], f32)> = None;
+    for a in 0..n {
+        for c in a + 2..n {
+            let (Some((b, left)), Some((d, right))) =
+                (farthest(a, c, a + 1..c), farthest(a, c, c + 1..a + n))
+            else {
+                continue;
+            };
+            let area = (left + right) / 2.0;
+            if best.is_none_or(|(_, best)| area > best) {
+                best = Some(([hull[a], hull[b], hull[c], hull[d]], area));
+            }
+        }
+    }
+    best
+}
+
+/// Orders the corners of a convex quadrilateral as top-left, top-right, bottom-right and
+/// bottom-left, the top-left being the one closest to the image origin.
+fn order_corners(mut corners: [Point; 4]) -> [Point; 4] {
+    let center = corners
+        .iter()
+        .fold((0.0, 0.0), |c, p| (c.0 + p.0 / 4.0, c.1 + p.1 / 4.0));
+    // Increasing angles are clockwise on screen
+    corners.sort_by(|p, q| {
+        let angle = |p: &Point| (p.1 - center.1).atan2(p.0 - center.0);
+        angle(p).total_cmp(&angle(q))
+    });
+    let first = (0..4)
+        .min_by(|&i, &j| {
+            let sum = |p: Point| p.0 + p.1;
+            sum(corners[i]).total_cmp(&sum(corners[j]))
+        })
+        .unwrap();
+    corners.rotate_left(first);
+    corners
+}
+
+/// Finds the outline of the page in a photographed document: the largest quadrilateral contour
+/// of the edge map covering at least `min_area` of the image, as a fraction. The page must
+/// stand out from its background along its whole outline. Returns its corners in image
+/// coordinates ordered as top-left, top-right, bottom-right and bottom-left.
+pub fn find_document<P: Pixel>(image: &Image<P>, min_area: f32) -> Option<[Point; 4]> {
+    let (width, height) = image.dimensions();
+    if width < 3 || height < 3 {
+        return None;
+    }
+    let gray = Image::from_data(
+        width,
+        height,
+        image.pixels().map(|p| Luma::new(p.luminance())).collect(),
+    )
+    .ok()?;
+    let scale = (DETECTION_SIZE / width.max(height) as f32).min(1.0);
+    let small = match scale < 1.0 {
+        true => affine::scale_unchecked(
+            &gray.gaussian_blur(0.5 / scale),
+            scale,
+            scale,
+            Interpolation::Bilinear,
+        ),
+        false => gray,
+    }
+    .gaussian_blur(1.0);
+
+    let gradient = small.sobel();
+    let magnitude = gradient.magnitude();
+    let strongest = magnitude.pixels().fold(0.0f32, |m, p| m.max(p.l));
+    let threshold = (EDGE_FRACTION * strongest).max(MIN_EDGE_STRENGTH);
+    let (small_width, small_height) = magnitude.dimensions();
+    let edges = Image::from_data(
+        small_width,
+        small_height,
+        magnitude
+            .pixels()
+            .map(|p| Luma::new((p.l > threshold) as u8 as f32))
+            .collect(),
+    )
+    .ok()?
+    // Bridge small gaps in the outline
+    .max_filter(3)
+    .ok()?;
+
+    let min_area = min_area * (small_width * small_height) as f32;
+    let (corners, _) = edges
+        .find_contours()
+        .iter()
+        .filter(|contour| contour.bounding_rect().area() as f32 >= min_area)
+        .filter_map(|contour| {
+            let points = geometry::simplify_polygon(&contour.convex_hull(), HULL_EPSILON);
+            let (quad, area) = largest_quad(&points)?;
+            let hull = Contour { points };
+            (area >= min_area && area >= QUAD_COVERAGE * hull.area()).then_some((quad, area))
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    Some(order_corners(corners.map(|(x, y)| (x / scale, y / scale))))
+}
+
+/// Extension trait for [`glance_core::img::Image`] to scan photographed documents of any pixel
+/// type.
+pub trait DocumentScanExt: Sized {
+    fn scan_document(&self, options: &ScanOptions) -> Option<Self>;
+    fn rectify(&self, corners: [Point; 4], aspect: Option<f32>) -> Option<Self>;
+}
+
+impl<P> DocumentScanExt for Image<P>
+where
+    P: Pixel,
+{
+    /// Finds the page with [`find_document`], rectifies it with
+    /// [`DocumentScanExt::rectify`] and, if asked to, binarizes it with
+    /// [`IntegralImageExtLuma::adaptive_threshold`] into black ink on white. Returns `None` if
+    /// no page outline was found.
+    fn scan_document(&self, options: &ScanOptions) -> Option<Self> {
+        let corners = find_document(self, options.min_area)?;
+        let page = self.rectify(corners, options.aspect)?;
+        if !options.binarize {
+            return Some(page);
+        }
+        let (width, height) = page.dimensions();
+        let gray = Image::from_data(
+            width,
+            height,
+            page.pixels().map(|p| Luma::new(p.luminance())).collect(),
+        )
+        .ok()?;
+        let radius = (width.max(height) / 8).max(8);
+        let binary = gray.adaptive_threshold(radius, BINARIZE_OFFSET);
+        Image::from_data(
+            width,
+            height,
+            binary.pixels().map(|p| P::from_luminance(p.l)).collect(),
+        )
+        .ok()
+    }
+
+    /// Warps the quadrilateral with `corners` (top-left, top-right, bottom-right, bottom-left)
+    /// onto an upright rectangle with a perspective transformation. The output is as tall as the
+    /// longer of the left and right sides and, unless an `aspect` (width over height) is given,
+    /// as wide as the longer of the top and bottom sides. Returns `None` for degenerate
+    /// corners.
+    fn rectify(&self, corners: [Point; 4], aspect: Option<f32>) -> Option<Self> {
+        let [top_left, top_right, bottom_right, bottom_left] = corners;
+        let height = geometry::distance(top_left, bottom_left)
+            .max(geometry::distance(top_right, bottom_right))
+            .round();
+        let width = match aspect {
+            Some(aspect) => height * aspect,
+            None => geometry::distance(top_left, top_right)
+                .max(geometry::distance(bottom_left, bottom_right)),
+        }
+        .round();
+        if width < 1.0 || height < 1.0 || !width.is_finite() {
+            return None;
+        }
+
+        let (right, bottom) = (width - 1.0, height - 1.0);
+        let homography = estimate_homography(&[
+            ((0.0, 0.0), top_left),
+            ((right, 0.0), top_right),
+            ((right, bottom), bottom_right),
+            ((0.0, bottom), bottom_left),
+        ])?;
+        let map = CoordinateMap::from_fn(width as usize, height as usize, |x, y| {
+            geometry::transform_homography(&homography, (x as f32, y as f32))
+        });
+        Some(self.clone().remap(&map, Interpolation::Bilinear))
+    }
+}
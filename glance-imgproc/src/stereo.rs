@@ -0,0 +1,358 @@
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Pixel, Rgba},
+};
+use glance_core::par::*;
+
+use crate::linear_filters::{BorderMode, convolve_plane_separable};
+use crate::{Error, Result};
+
+/// Matching cost between blocks of the left and right image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StereoCost {
+    /// Sum of absolute intensity differences. Fast, but sensitive to exposure differences
+    /// between the cameras.
+    Sad,
+    /// Hamming distance between census transforms (the order of every pixel relative to the
+    /// center of its 7x7 neighbourhood). Robust to brightness and contrast differences.
+    Census,
+}
+
+/// A dense disparity map, holding for every pixel of the left image the horizontal offset to
+/// the matching pixel of the right image, or None where no reliable match was found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisparityMap {
+    width: usize,
+    height: usize,
+    data: Vec<Option<f32>>,
+}
+
+impl DisparityMap {
+    /// Returns the dimensions of the map as a tuple (width, height).
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the disparity at the specified position, or None if it is invalid or out of
+    /// bounds.
+    pub fn get(&self, position: (usize, usize)) -> Option<f32> {
+        if position.0 >= self.width || position.1 >= self.height {
+            return None;
+        }
+        self.data[position.1 * self.width + position.0]
+    }
+
+    /// Returns all disparities in row-major order.
+    pub fn disparities(&self) -> &[Option<f32>] {
+        &self.data
+    }
+
+    /// Returns the depth at the specified position for a rectified camera pair with
+    /// `focal_length` in pixels and `baseline` in the unit of the returned depth.
+    pub fn depth(&self, position: (usize, usize), focal_length: f32, baseline: f32) -> Option<f32> {
+        self.get(position)
+            .filter(|&d| d > 0.0)
+            .map(|d| focal_length * baseline / d)
+    }
+
+    /// Reprojects every valid pixel with a positive disparity to a 3D point (x, y, z) in the
+    /// coordinate frame of the left camera, with its principal point at `principal_point`.
+    pub fn to_points(
+        &self,
+        focal_length: f32,
+        baseline: f32,
+        principal_point: (f32, f32),
+    ) -> Vec<[f32; 3]> {
+        (0..self.width * self.height)
+            .filter_map(|idx| {
+                let (x, y) = (idx % self.width, idx / self.width);
+                let z = self.depth((x, y), focal_length, baseline)?;
+                Some([
+                    (x as f32 - principal_point.0) * z / focal_length,
+                    (y as f32 - principal_point.1) * z / focal_length,
+                    z,
+                ])
+            })
+            .collect()
+    }
+
+    /// Visualizes the map with `max_disparity` (or the largest disparity if None) in white and
+    /// invalid pixels in black.
+    pub fn to_luma(&self, max_disparity: Option<f32>) -> Image<Luma> {
+        let max = max_disparity
+            .unwrap_or_else(|| self.data.iter().flatten().copied().fold(0.0, f32::max))
+            .max(f32::EPSILON);
+        let data = self
+            .data
+            .iter()
+            .map(|d| Luma {
+                l: d.map_or(0.0, |d| (d / max).clamp(0.0, 1.0)),
+            })
+            .collect();
+        Image::from_data(self.width, self.height, data).unwrap()
+    }
+}
+
+/// Parameters of the block matching stereo matcher for rectified image pairs, where matching
+/// pixels lie on the same row and the right image is shifted to the left.
+#[derive(Debug, Clone, Copy)]
+pub struct StereoBm {
+    /// Number of disparities searched, from 0 to `num_disparities - 1`
+    pub num_disparities: usize,
+    /// Side length of the matched square blocks, should be odd
+    pub block_size: usize,
+    pub cost: StereoCost,
+    /// Maximum difference between the left-to-right and right-to-left disparity of a pixel, or
+    /// None to skip the consistency check. Rejects occluded pixels and mismatches.
+    pub max_disparity_difference: Option<f32>,
+    /// Minimum relative margin by which the best cost must beat every cost at least 2
+    /// disparities away. Rejects matches in ambiguous or textureless areas.
+    pub uniqueness_ratio: f32,
+}
+
+impl Default for StereoBm {
+    fn default() -> Self {
+        Self {
+            num_disparities: 64,
+            block_size: 9,
+            cost: StereoCost::Sad,
+            max_disparity_difference: Some(1.0),
+            uniqueness_ratio: 0.1,
+        }
+    }
+}
+
+impl StereoBm {
+    /// Computes the disparity map of the left image. Disparities are refined to subpixel
+    /// accuracy by fitting a parabola through the costs around the best match. Fails with
+    /// [`Error::DimensionMismatch`] if the images differ in size.
+    pub fn compute(&self, left: &Image<Luma>, right: &Image<Luma>) -> Result<DisparityMap> {
+        check_pair(left, right)?;
+        let (width, height) = left.dimensions();
+        let volume = self.cost_volume(left, right);
+        let disparities = self.num_disparities.max(1);
+
+        let best_left: Vec<Option<f32>> = (0..width * height)
+            .into_par_iter()
+            .map(|idx| {
+                let x = idx % width;
+                let costs: Vec<f32> = (0..disparities.min(x + 1))
+                    .map(|d| volume[d][idx])
+                    .collect();
+                self.select(&costs)
+            })
+            .collect();
+
+        let data = match self.max_disparity_difference {
+            None => best_left,
+            Some(tolerance) => {
+                // Best match of every right pixel, searching the left image to the right
+                let best_right: Vec<Option<f32>> = (0..width * height)
+                    .into_par_iter()
+                    .map(|idx| {
+                        let x = idx % width;
+                        let costs: Vec<f32> = (0..disparities.min(width - x))
+                            .map(|d| volume[d][idx + d])
+                            .collect();
+                        self.select(&costs)
+                    })
+                    .collect();
+
+                best_left
+                    .par_iter()
+                    .enumerate()
+                    .map(|(idx, &d)| {
+                        let d = d?;
+                        let (x, y) = (idx % width, idx / width);
+                        let xr = (x as f32 - d).round();
+                        if xr < 0.0 {
+                            return None;
+                        }
+                        let dr = best_right[y * width + xr as usize]?;
+                        ((d - dr).abs() <= tolerance).then_some(d)
+                    })
+                    .collect()
+            }
+        };
+
+        Ok(DisparityMap {
+            width,
+            height,
+            data,
+        })
+    }
+
+    /// Returns one plane of aggregated block costs per disparity, indexed by the position in the
+    /// left image. Positions without a counterpart in the right image are penalized.
+    fn cost_volume(&self, left: &Image<Luma>, right: &Image<Luma>) -> Vec<Vec<f32>> {
+        let (width, height) = left.dimensions();
+        let ones = vec![1.0; self.block_size.max(1)];
+
+        let pixel_costs: Box<dyn Fn(usize) -> Vec<f32> + Sync> = match self.cost {
+            StereoCost::Sad => {
+                let l: Vec<f32> = left.pixels().map(|p| p.l).collect();
+                let r: Vec<f32> = right.pixels().map(|p| p.l).collect();
+                Box::new(move |d| {
+                    (0..width * height)
+                        .map(|idx| {
+                            if idx % width >= d {
+                                (l[idx] - r[idx - d]).abs()
+                            } else {
+                                1.0
+                            }
+                        })
+                        .collect()
+                })
+            }
+            StereoCost::Census => {
+                let (l, r) = (census(left), census(right));
+                Box::new(move |d| {
+                    (0..width * height)
+                        .map(|idx| {
+                            if idx % width >= d {
+                                (l[idx] ^ r[idx - d]).count_ones() as f32
+                            } else {
+                                48.0
+                            }
+                        })
+                        .collect()
+                })
+            }
+        };
+
+        (0..self.num_disparities.max(1))
+            .into_par_iter()
+            .map(|d| {
+                convolve_plane_separable(
+                    &pixel_costs(d),
+                    (width, height),
+                    &ones,
+                    &ones,
+                    BorderMode::Replicate,
+                )
+            })
+            .collect()
+    }
+
+    /// Picks the disparity with the lowest cost, if it is unique enough, with subpixel
+    /// refinement.
+    fn select(&self, costs: &[f32]) -> Option<f32> {
+        let (best, &best_cost) = costs.iter().enumerate().min_by(|a, b| a.1.total_cmp(b.1))?;
+
+        let runner_up = costs
+            .iter()
+            .enumerate()
+            .filter(|(d, _)| d.abs_diff(best) > 1)
+            .map(|(_, &c)| c)
+            .fold(f32::INFINITY, f32::min);
+        if runner_up.is_finite() && best_cost * (1.0 + self.uniqueness_ratio) > runner_up {
+            return None;
+        }
+
+        if best == 0 || best + 1 >= costs.len() {
+            return Some(best as f32);
+        }
+        let (before, after) = (costs[best - 1], costs[best + 1]);
+        let curvature = before - 2.0 * best_cost + after;
+        let offset = if curvature > 0.0 {
+            ((before - after) / (2.0 * curvature)).clamp(-0.5, 0.5)
+        } else {
+            0.0
+        };
+        Some(best as f32 + offset)
+    }
+}
+
+/// Census transform over a 7x7 neighbourhood: one bit per neighbour, set if it is darker than
+/// the center. Neighbours outside the image replicate the edge.
+fn census(image: &Image<Luma>) -> Vec<u64> {
+    let (width, height) = image.dimensions();
+    let plane: Vec<f32> = image.pixels().map(|p| p.l).collect();
+    (0..width * height)
+        .into_par_iter()
+        .map(|idx| {
+            let (x, y) = ((idx % width) as isize, (idx / width) as isize);
+            let center = plane[idx];
+            let mut bits = 0u64;
+            for dy in -3..=3isize {
+                for dx in -3..=3isize {
+                    if dx == 0 && dy == 0 {
+                        continue;
+                    }
+                    let nx = (x + dx).clamp(0, width as isize - 1) as usize;
+                    let ny = (y + dy).clamp(0, height as isize - 1) as usize;
+                    bits = (bits << 1) | (plane[ny * width + nx] < center) as u64;
+                }
+            }
+            bits
+        })
+        .collect()
+}
+
+/// Color mixing of [`make_anaglyph`], for viewing with red-cyan glasses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnaglyphMode {
+    /// Red channel of the left image, green and blue of the right one. Keeps the most color,
+    /// but red objects flicker between the eyes.
+    RedCyan,
+    /// Like [`AnaglyphMode::RedCyan`], but the red channel is mixed from the green and blue of
+    /// the left image, which avoids the retinal rivalry of red objects at the cost of reds.
+    Optimized,
+}
+
+fn check_pair<P: Pixel>(left: &Image<P>, right: &Image<P>) -> Result<()> {
+    if left.dimensions() != right.dimensions() {
+        return Err(Error::DimensionMismatch {
+            expected: left.dimensions(),
+            actual: right.dimensions(),
+        });
+    }
+    Ok(())
+}
+
+/// Combines a stereo pair into a single red-cyan anaglyph image, a quick way to review the
+/// alignment of a pair in 3D. Alpha is taken from the left image.
+pub fn make_anaglyph(
+    left: &Image<Rgba>,
+    right: &Image<Rgba>,
+    mode: AnaglyphMode,
+) -> Result<Image<Rgba>> {
+    check_pair(left, right)?;
+    let (width, height) = left.dimensions();
+    let data = left
+        .pixels()
+        .zip(right.pixels())
+        .map(|(l, r)| Rgba {
+            r: match mode {
+                AnaglyphMode::RedCyan => l.r,
+                AnaglyphMode::Optimized => 0.7 * l.g + 0.3 * l.b,
+            },
+            g: r.g,
+            b: r.b,
+            a: l.a,
+        })
+        .collect();
+    Ok(Image::from_data(width, height, data)?)
+}
+
+/// Packs a stereo pair next to each other, left image on the left, in the side-by-side format
+/// of 3D displays and cross-eyed viewing (with the images swapped).
+pub fn side_by_side<P: Pixel>(left: &Image<P>, right: &Image<P>) -> Result<Image<P>> {
+    check_pair(left, right)?;
+    let (width, height) = left.dimensions();
+    let mut packed = Image::new(2 * width, height);
+    packed.paste((0, 0), left);
+    packed.paste((width, 0), right);
+    Ok(packed)
+}
+
+/// Packs a stereo pair above each other, left image on top, in the over-under (top-bottom)
+/// format.
+pub fn over_under<P: Pixel>(left: &Image<P>, right: &Image<P>) -> Result<Image<P>> {
+    check_pair(left, right)?;
+    let (width, height) = left.dimensions();
+    let mut packed = Image::new(width, 2 * height);
+    packed.paste((0, 0), left);
+    packed.paste((0, height), right);
+    Ok(packed)
+}
@@ -0,0 +1,275 @@
+use std::collections::VecDeque;
+
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Rgba},
+};
+
+use crate::color::Lab;
+use crate::components::LabelImage;
+
+/// Parameters of SLIC (Simple Linear Iterative Clustering) superpixel segmentation.
+#[derive(Debug, Clone, Copy)]
+pub struct Slic {
+    /// Approximate number of superpixels
+    pub segments: usize,
+    /// Weight of spatial proximity against color similarity. Higher values give more regular,
+    /// compact superpixels, lower values follow the image edges more closely
+    pub compactness: f32,
+    /// Number of k-means iterations
+    pub iterations: usize,
+}
+
+impl Default for Slic {
+    fn default() -> Self {
+        Slic {
+            segments: 200,
+            compactness: 10.0,
+            iterations: 10,
+        }
+    }
+}
+
+/// The result of [`Slic::segment`].
+#[derive(Debug, Clone)]
+pub struct Superpixels {
+    /// Label of every pixel, superpixels are numbered 1..=count (there is no background)
+    pub labels: LabelImage,
+    /// Mean color of every superpixel, superpixel `label` is at index `label - 1`
+    pub means: Vec<Rgba>,
+}
+
+impl Superpixels {
+    /// Returns an image where every pixel is painted with the mean color of its superpixel.
+    pub fn mean_image(&self) -> Image<Rgba> {
+        let (width, height) = self.labels.dimensions();
+        let data = self
+            .labels
+            .labels()
+            .iter()
+            .map(|&l| self.means[l as usize - 1])
+            .collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+
+    /// Returns a copy of `image` with the superpixel borders drawn in `color`.
+    pub fn overlay_boundaries(&self, image: &Image<Rgba>, color: Rgba) -> Image<Rgba> {
+        let (width, height) = image.dimensions();
+        let data = image
+            .pixels()
+            .zip(self.labels.boundaries().pixels())
+            .map(|(pixel, edge): (Rgba, Luma)| if edge.l > 0.0 { color } else { pixel })
+            .collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+}
+
+/// A cluster center in (L, a, b, x, y).
+#[derive(Debug, Clone, Copy)]
+struct Center {
+    color: Lab,
+    x: f32,
+    y: f32,
+}
+
+impl Slic {
+    /// Segments the image into superpixels. Every superpixel is 4-connected.
+    pub fn segment(&self, image: &Image<Rgba>) -> Superpixels {
+        let (width, height) = image.dimensions();
+        let lab: Vec<Lab> = image.pixels().map(|p| Lab::from_rgba(&p)).collect();
+        let step = ((width * height) as f32 / self.segments.max(1) as f32)
+            .sqrt()
+            .max(1.0);
+
+        let mut centers = initial_centers(&lab, width, height, step);
+        let mut labels = vec![0usize; width * height];
+        let mut distances = vec![f32::MAX; width * height];
+        let spatial_weight = (self.compactness / step).powi(2);
+        let reach = step.ceil() as isize;
+
+        for _ in 0..self.iterations.max(1) {
+            distances.iter_mut().for_each(|d| *d = f32::MAX);
+
+            // Every center only competes for pixels within a 2S x 2S window
+            for (k, center) in centers.iter().enumerate() {
+                let (cx, cy) = (center.x.round() as isize, center.y.round() as isize);
+                let ys = (cy - reach).max(0) as usize..((cy + reach + 1) as usize).min(height);
+                for y in ys {
+                    let xs = (cx - reach).max(0) as usize..((cx + reach + 1) as usize).min(width);
+                    for x in xs {
+                        let idx = y * width + x;
+                        let color = lab[idx];
+                        let dc = (color.l - center.color.l).powi(2)
+                            + (color.a - center.color.a).powi(2)
+                            + (color.b - center.color.b).powi(2);
+                        let ds = (x as f32 - center.x).powi(2) + (y as f32 - center.y).powi(2);
+                        let distance = dc + ds * spatial_weight;
+                        if distance < distances[idx] {
+                            distances[idx] = distance;
+                            labels[idx] = k;
+                        }
+                    }
+                }
+            }
+
+            // Move the centers to the mean of their pixels
+            let mut sums = vec![(0.0f32, 0.0f32, 0.0f32, 0.0f32, 0.0f32, 0usize); centers.len()];
+            for (idx, &k) in labels.iter().enumerate() {
+                let s = &mut sums[k];
+                s.0 += lab[idx].l;
+                s.1 += lab[idx].a;
+                s.2 += lab[idx].b;
+                s.3 += (idx % width) as f32;
+                s.4 += (idx / width) as f32;
+                s.5 += 1;
+            }
+            for (center, s) in centers.iter_mut().zip(&sums) {
+                if s.5 > 0 {
+                    let n = s.5 as f32;
+                    *center = Center {
+                        color: Lab {
+                            l: s.0 / n,
+                            a: s.1 / n,
+                            b: s.2 / n,
+                        },
+                        x: s.3 / n,
+                        y: s.4 / n,
+                    };
+                }
+            }
+        }
+
+        let min_size = ((step * step) / 4.0) as usize;
+        let (labels, count) = enforce_connectivity(&labels, width, height, min_size);
+
+        let mut sums = vec![(0.0f32, 0.0f32, 0.0f32, 0.0f32, 0usize); count];
+        for (pixel, &label) in image.pixels().zip(&labels) {
+            let s = &mut sums[label as usize - 1];
+            s.0 += pixel.r;
+            s.1 += pixel.g;
+            s.2 += pixel.b;
+            s.3 += pixel.a;
+            s.4 += 1;
+        }
+        let means = sums
+            .iter()
+            .map(|s| {
+                let n = s.4.max(1) as f32;
+                Rgba {
+                    r: s.0 / n,
+                    g: s.1 / n,
+                    b: s.2 / n,
+                    a: s.3 / n,
+                }
+            })
+            .collect();
+
+        Superpixels {
+            labels: LabelImage::from_labels(width, height, labels, count),
+            means,
+        }
+    }
+}
+
+/// Places the centers on a regular grid with spacing `step`, each moved to the lowest gradient
+/// position in its 3x3 neighbourhood so it does not start on an edge.
+fn initial_centers(lab: &[Lab], width: usize, height: usize, step: f32) -> Vec<Center> {
+    let gradient = |x: usize, y: usize| {
+        let at = |x: usize, y: usize| lab[y.min(height - 1) * width + x.min(width - 1)];
+        let (l, r) = (at(x.saturating_sub(1), y), at(x + 1, y));
+        let (t, b) = (at(x, y.saturating_sub(1)), at(x, y + 1));
+        (r.l - l.l).powi(2)
+            + (r.a - l.a).powi(2)
+            + (r.b - l.b).powi(2)
+            + (b.l - t.l).powi(2)
+            + (b.a - t.a).powi(2)
+            + (b.b - t.b).powi(2)
+    };
+
+    let mut centers = Vec::new();
+    let mut y = step / 2.0;
+    while y < height as f32 {
+        let mut x = step / 2.0;
+        while x < width as f32 {
+            let (gx, gy) = (x as usize, y as usize);
+            let mut best = (gx, gy);
+            for ny in gy.saturating_sub(1)..=(gy + 1).min(height - 1) {
+                for nx in gx.saturating_sub(1)..=(gx + 1).min(width - 1) {
+                    if gradient(nx, ny) < gradient(best.0, best.1) {
+                        best = (nx, ny);
+                    }
+                }
+            }
+            centers.push(Center {
+                color: lab[best.1 * width + best.0],
+                x: best.0 as f32,
+                y: best.1 as f32,
+            });
+            x += step;
+        }
+        y += step;
+    }
+    centers
+}
+
+/// Relabels the clusters so every label is one 4-connected region numbered from 1. Regions
+/// smaller than `min_size` are merged into the previously labeled neighbouring region.
+/// Returns the labels and their count.
+fn enforce_connectivity(
+    labels: &[usize],
+    width: usize,
+    height: usize,
+    min_size: usize,
+) -> (Vec<u32>, usize) {
+    const NEIGHBOURS: [(isize, isize); 4] = [(-1, 0), (0, -1), (1, 0), (0, 1)];
+    let mut output = vec![0u32; width * height];
+    let mut count = 0u32;
+    let mut region = Vec::new();
+
+    for start in 0..width * height {
+        if output[start] != 0 {
+            continue;
+        }
+
+        // A labeled neighbour to merge into if the region is too small
+        let (sx, sy) = ((start % width) as isize, (start / width) as isize);
+        let adjacent = NEIGHBOURS.iter().find_map(|(dx, dy)| {
+            let (nx, ny) = (sx + dx, sy + dy);
+            if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
+                return None;
+            }
+            let label = output[ny as usize * width + nx as usize];
+            (label != 0).then_some(label)
+        });
+
+        count += 1;
+        region.clear();
+        region.push(start);
+        output[start] = count;
+        let mut queue = VecDeque::from([start]);
+        while let Some(idx) = queue.pop_front() {
+            let (x, y) = ((idx % width) as isize, (idx / width) as isize);
+            for (dx, dy) in NEIGHBOURS {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= width as isize || ny >= height as isize {
+                    continue;
+                }
+                let n = ny as usize * width + nx as usize;
+                if output[n] == 0 && labels[n] == labels[start] {
+                    output[n] = count;
+                    region.push(n);
+                    queue.push_back(n);
+                }
+            }
+        }
+
+        if region.len() < min_size
+            && let Some(label) = adjacent
+        {
+            region.iter().for_each(|&idx| output[idx] = label);
+            count -= 1;
+        }
+    }
+
+    (output, count as usize)
+}
@@ -0,0 +1,215 @@
+//! Multi-frame denoising of video: every new frame is combined with the frames before it, after
+//! aligning them onto the new one. Pixels where an aligned frame still differs from the new one,
+//! because something moved on its own or the alignment failed there, are left out, so moving
+//! objects stay sharp instead of leaving ghosts.
+
+use std::collections::VecDeque;
+
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Pixel},
+};
+use glance_core::par::*;
+
+use crate::alignment::{Ecc, find_transform_ecc};
+use crate::geometry::transform_homography;
+use crate::linear_filters::LinearFilterExtLuma;
+use crate::optical_flow::Farneback;
+use crate::{Error, Result};
+
+/// Standard deviation of the blur applied before comparing frames for motion, so that the noise
+/// itself is not mistaken for motion.
+const MOTION_BLUR_SIGMA: f32 = 1.0;
+
+/// How previous frames are brought onto the current one.
+#[derive(Debug, Clone, Copy)]
+pub enum FrameAlignment {
+    /// Frames are used as they are, for a camera on a tripod
+    None,
+    /// One global warp per frame, see [`find_transform_ecc`]. Frames it fails on are used
+    /// unaligned
+    Ecc(Ecc),
+    /// Dense optical flow, which also follows objects moving on their own
+    Flow(Farneback),
+}
+
+/// How the aligned samples of a pixel are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TemporalReduction {
+    /// Mean of the samples, the strongest noise reduction
+    Mean,
+    /// Median of the samples, which also rejects outliers like flickering pixels
+    Median,
+}
+
+/// Options of [`TemporalDenoiser`].
+#[derive(Debug, Clone, Copy)]
+pub struct TemporalDenoiseOptions {
+    /// Number of frames combined, including the current one
+    pub frames: usize,
+    pub alignment: FrameAlignment,
+    pub reduction: TemporalReduction,
+    /// Aligned pixels whose luminance differs from the current frame by more than this after a
+    /// light blur are treated as motion and left out
+    pub motion_threshold: f32,
+}
+
+impl Default for TemporalDenoiseOptions {
+    fn default() -> Self {
+        TemporalDenoiseOptions {
+            frames: 5,
+            alignment: FrameAlignment::Ecc(Ecc::default()),
+            reduction: TemporalReduction::Mean,
+            motion_threshold: 0.1,
+        }
+    }
+}
+
+/// Stateful denoiser for a stream of frames of the same size, keeping the last
+/// [`TemporalDenoiseOptions::frames`] frames.
+#[derive(Debug, Clone)]
+pub struct TemporalDenoiser<P: Pixel> {
+    options: TemporalDenoiseOptions,
+    history: VecDeque<Image<P>>,
+}
+
+fn luminance<P: Pixel>(image: &Image<P>) -> Image<Luma> {
+    let (width, height) = image.dimensions();
+    let data = image.pixels().map(|p| Luma::new(p.luminance())).collect();
+    Image::from_data(width, height, data).unwrap()
+}
+
+/// Samples `image` at `positions`, returning None for positions outside it.
+fn sample_at<P: Pixel>(image: &Image<P>, positions: &[(f32, f32)]) -> Vec<Option<P>> {
+    let (width, height) = image.dimensions();
+    positions
+        .par_iter()
+        .map(|&(x, y)| {
+            let inside =
+                x >= 0.0 && y >= 0.0 && x <= (width - 1) as f32 && y <= (height - 1) as f32;
+            inside.then(|| image.sample_bilinear(x, y))
+        })
+        .collect()
+}
+
+fn median(values: &mut [f32]) -> f32 {
+    values.sort_by(f32::total_cmp);
+    let middle = values.len() / 2;
+    match values.len() % 2 {
+        0 => (values[middle - 1] + values[middle]) / 2.0,
+        _ => values[middle],
+    }
+}
+
+impl<P: Pixel> TemporalDenoiser<P> {
+    /// Creates a denoiser without previous frames.
+    pub fn new(options: TemporalDenoiseOptions) -> Self {
+        TemporalDenoiser {
+            options,
+            history: VecDeque::new(),
+        }
+    }
+
+    /// Discards the previous frames, e.g. at a scene cut.
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+
+    /// Returns the positions in `previous` of the pixels of `current`.
+    fn align(&self, current: &Image<Luma>, previous: &Image<Luma>) -> Result<Vec<(f32, f32)>> {
+        let (width, height) = current.dimensions();
+        let identity = |idx: usize| ((idx % width) as f32, (idx / width) as f32);
+        Ok(match self.options.alignment {
+            FrameAlignment::None => (0..width * height).map(identity).collect(),
+            FrameAlignment::Ecc(params) => match find_transform_ecc(current, previous, &params) {
+                Some(alignment) => (0..width * height)
+                    .into_par_iter()
+                    .map(|idx| transform_homography(&alignment.warp, identity(idx)))
+                    .collect(),
+                None => (0..width * height).map(identity).collect(),
+            },
+            FrameAlignment::Flow(farneback) => farneback
+                .calc(current, previous)?
+                .vectors()
+                .iter()
+                .enumerate()
+                .map(|(idx, (dx, dy))| {
+                    let (x, y) = identity(idx);
+                    (x + dx, y + dy)
+                })
+                .collect(),
+        })
+    }
+
+    /// Denoises `frame` with the previous frames aligned onto it, then keeps it for the next
+    /// ones. The first frame is returned as it is. Fails with [`Error::DimensionMismatch`] if
+    /// the frame size differs from the previous frames.
+    pub fn apply(&mut self, frame: &Image<P>) -> Result<Image<P>> {
+        let (width, height) = frame.dimensions();
+        if let Some(previous) = self.history.back()
+            && previous.dimensions() != (width, height)
+        {
+            return Err(Error::DimensionMismatch {
+                expected: previous.dimensions(),
+                actual: (width, height),
+            });
+        }
+
+        let current = luminance(frame);
+        let current_blurred = current.clone().gaussian_blur(MOTION_BLUR_SIGMA);
+        // Aligned samples of every previous frame, None where they are missing or moved
+        let aligned: Vec<Vec<Option<P>>> = self
+            .history
+            .iter()
+            .map(|previous| {
+                let previous_luma = luminance(previous);
+                let positions = self.align(&current, &previous_luma)?;
+                let blurred = previous_luma.gaussian_blur(MOTION_BLUR_SIGMA);
+                let luma = sample_at(&blurred, &positions);
+                Ok(sample_at(previous, &positions)
+                    .into_iter()
+                    .zip(luma)
+                    .zip(current_blurred.pixels())
+                    .map(|((sample, luma), reference)| {
+                        let still = luma.is_some_and(|l| {
+                            (l.l - reference.l).abs() <= self.options.motion_threshold
+                        });
+                        sample.filter(|_| still)
+                    })
+                    .collect())
+            })
+            .collect::<Result<_>>()?;
+
+        let reduction = self.options.reduction;
+        let denoised = frame
+            .as_slice()
+            .par_iter()
+            .enumerate()
+            .map(|(idx, pixel)| {
+                let samples: Vec<P> = std::iter::once(*pixel)
+                    .chain(aligned.iter().filter_map(|frame| frame[idx]))
+                    .collect();
+                let mut out = P::new();
+                let mut values = vec![0.0; samples.len()];
+                for c in 0..P::channel_count() {
+                    values
+                        .iter_mut()
+                        .zip(&samples)
+                        .for_each(|(v, s)| *v = s.channel(c));
+                    let value = match reduction {
+                        TemporalReduction::Mean => values.iter().sum::<f32>() / values.len() as f32,
+                        TemporalReduction::Median => median(&mut values),
+                    };
+                    out.set_channel(c, value);
+                }
+                out
+            })
+            .collect();
+
+        self.history.push_back(frame.clone());
+        while self.history.len() >= self.options.frames.max(1) {
+            self.history.pop_front();
+        }
+        Ok(Image::from_data(width, height, denoised)?)
+    }
+}
@@ -0,0 +1,219 @@
+//! Texture synthesis by image quilting (Efros and Freeman): the output is tiled with square
+//! patches of an exemplar that overlap their neighbors, each chosen among the patches agreeing
+//! best with what is already there and stitched in along the seam of least difference.
+
+use glance_core::CoreError;
+use glance_core::img::{Image, Rect, pixel::Pixel};
+use glance_core::par::*;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::Result;
+
+/// Options of [`TextureSynthesisExt::synthesize_texture`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuiltingOptions {
+    /// Side of the square patches, larger than the structures of the texture to preserve them
+    pub patch_size: usize,
+    /// Width of the strip shared by neighboring patches, below `patch_size`
+    pub overlap: usize,
+    /// Patches whose overlap error is within this fraction of the best one are picked at
+    /// random, which avoids repeating the same patch
+    pub tolerance: f32,
+    /// Seed of the patch selection, making results reproducible
+    pub seed: u64,
+}
+
+impl Default for QuiltingOptions {
+    fn default() -> Self {
+        QuiltingOptions {
+            patch_size: 32,
+            overlap: 6,
+            tolerance: 0.1,
+            seed: 0,
+        }
+    }
+}
+
+fn squared_difference<P: Pixel>(a: &P, b: &P) -> f32 {
+    (0..P::channel_count())
+        .map(|c| (a.channel(c) - b.channel(c)).powi(2))
+        .sum()
+}
+
+/// Returns the cheapest path through the `rows` x `columns` row-major `errors`, moving at most
+/// one column per row, as its column in every row.
+fn min_cut(errors: &[f32], rows: usize, columns: usize) -> Vec<usize> {
+    let mut cost = errors.to_vec();
+    for row in 1..rows {
+        for column in 0..columns {
+            let above = (column.saturating_sub(1)..=(column + 1).min(columns - 1))
+                .map(|c| cost[(row - 1) * columns + c])
+                .fold(f32::MAX, f32::min);
+            cost[row * columns + column] += above;
+        }
+    }
+    let cheapest = |row: usize, range: std::ops::RangeInclusive<usize>| {
+        range
+            .min_by(|&a, &b| cost[row * columns + a].total_cmp(&cost[row * columns + b]))
+            .unwrap()
+    };
+    let mut path = vec![cheapest(rows - 1, 0..=columns - 1)];
+    for row in (0..rows - 1).rev() {
+        let last = *path.last().unwrap();
+        path.push(cheapest(
+            row,
+            last.saturating_sub(1)..=(last + 1).min(columns - 1),
+        ));
+    }
+    path.reverse();
+    path
+}
+
+/// Extension trait for [`glance_core::img::Image`] to synthesize textures of any pixel type.
+pub trait TextureSynthesisExt: Sized {
+    fn synthesize_texture(&self, size: (usize, usize), options: &QuiltingOptions) -> Result<Self>;
+    fn make_tileable(&self, blend: usize) -> Result<Self>;
+}
+
+impl<P> TextureSynthesisExt for Image<P>
+where
+    P: Pixel,
+{
+    /// Grows a texture of `size` = (width, height) from the image used as exemplar by image
+    /// quilting. Every exemplar position is a candidate patch, so the cost grows with the
+    /// exemplar area times the output area. Returns an error if the exemplar is smaller than
+    /// a patch or the overlap is not below the patch size.
+    fn synthesize_texture(&self, size: (usize, usize), options: &QuiltingOptions) -> Result<Self> {
+        let QuiltingOptions {
+            patch_size: patch,
+            overlap,
+            ..
+        } = *options;
+        let (exemplar_width, exemplar_height) = self.dimensions();
+        if patch == 0 || overlap >= patch {
+            return Err(CoreError::InvalidData(format!(
+                "overlap {overlap} must be below the patch size {patch}"
+            ))
+            .into());
+        }
+        if exemplar_width < patch || exemplar_height < patch {
+            return Err(CoreError::InvalidData(format!(
+                "exemplar of {:?} is smaller than a patch of {patch}",
+                self.dimensions()
+            ))
+            .into());
+        }
+
+        // Whole patches cover the output, cropped at the end
+        let step = patch - overlap;
+        let count = |length: usize| length.saturating_sub(overlap).div_ceil(step).max(1);
+        let (columns, rows) = (count(size.0), count(size.1));
+        let (width, height) = (columns * step + overlap, rows * step + overlap);
+        let mut output = vec![P::new(); width * height];
+
+        let exemplar = self.as_slice();
+        let candidates: Vec<(usize, usize)> = (0..=exemplar_height - patch)
+            .flat_map(|y| (0..=exemplar_width - patch).map(move |x| (x, y)))
+            .collect();
+        let mut rng = StdRng::seed_from_u64(options.seed);
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let (px, py) = (column * step, row * step);
+                let (left, top) = (column > 0, row > 0);
+                // Overlap pixels with the patches already placed, relative to the patch
+                let shared: Vec<(usize, usize)> = (0..patch)
+                    .flat_map(|v| (0..patch).map(move |u| (u, v)))
+                    .filter(|&(u, v)| (left && u < overlap) || (top && v < overlap))
+                    .collect();
+                let error = |(cx, cy): (usize, usize), (u, v): (usize, usize)| {
+                    squared_difference(
+                        &exemplar[(cy + v) * exemplar_width + cx + u],
+                        &output[(py + v) * width + px + u],
+                    )
+                };
+
+                let errors: Vec<f32> = candidates
+                    .par_iter()
+                    .map(|&candidate| shared.iter().map(|&uv| error(candidate, uv)).sum())
+                    .collect();
+                let best = errors.iter().copied().fold(f32::MAX, f32::min);
+                let limit = best * (1.0 + options.tolerance) + f32::EPSILON;
+                let good: Vec<usize> = (0..candidates.len())
+                    .filter(|&i| errors[i] <= limit)
+                    .collect();
+                let chosen = candidates[good[rng.random_range(0..good.len())]];
+
+                // Seams of least difference through the left and top overlaps
+                let vertical = left.then(|| {
+                    let errors: Vec<f32> = (0..patch)
+                        .flat_map(|v| (0..overlap).map(move |u| (u, v)))
+                        .map(|uv| error(chosen, uv))
+                        .collect();
+                    min_cut(&errors, patch, overlap)
+                });
+                let horizontal = top.then(|| {
+                    let errors: Vec<f32> = (0..patch)
+                        .flat_map(|u| (0..overlap).map(move |v| (u, v)))
+                        .map(|uv| error(chosen, uv))
+                        .collect();
+                    min_cut(&errors, patch, overlap)
+                });
+
+                for v in 0..patch {
+                    for u in 0..patch {
+                        let keep = vertical.as_ref().is_some_and(|cut| u < cut[v])
+                            || horizontal.as_ref().is_some_and(|cut| v < cut[u]);
+                        if !keep {
+                            output[(py + v) * width + px + u] =
+                                exemplar[(chosen.1 + v) * exemplar_width + chosen.0 + u];
+                        }
+                    }
+                }
+            }
+        }
+
+        let quilt = Image::from_data(width, height, output)?;
+        Ok(quilt.crop(Rect::new(0, 0, size.0, size.1))?)
+    }
+
+    /// Makes the image tile seamlessly by cross-fading a strip of `blend` pixels at its right
+    /// and bottom edges into the opposite edges, so the output is `blend` pixels narrower and
+    /// shorter. Returns an error if `blend` is not below both sides of the image.
+    fn make_tileable(&self, blend: usize) -> Result<Self> {
+        let (width, height) = self.dimensions();
+        if blend >= width || blend >= height {
+            return Err(CoreError::InvalidData(format!(
+                "blend of {blend} pixels does not fit into an image of {:?}",
+                self.dimensions()
+            ))
+            .into());
+        }
+        let (out_width, out_height) = (width - blend, height - blend);
+        // Near the left and top edges the wrapped around content of the strips fades in
+        let weight = |position: usize| match position < blend {
+            true => (position as f32 + 0.5) / blend as f32,
+            false => 1.0,
+        };
+        let pixel = |x: usize, y: usize| self.as_slice()[y * width + x];
+        let blend_row = |x: usize, y: usize| {
+            let t = weight(x);
+            match t < 1.0 {
+                true => pixel(x + out_width, y).lerp(&pixel(x, y), t),
+                false => pixel(x, y),
+            }
+        };
+        let data = (0..out_width * out_height)
+            .into_par_iter()
+            .map(|idx| {
+                let (x, y) = (idx % out_width, idx / out_width);
+                let t = weight(y);
+                match t < 1.0 {
+                    true => blend_row(x, y + out_height).lerp(&blend_row(x, y), t),
+                    false => blend_row(x, y),
+                }
+            })
+            .collect();
+        Ok(Image::from_data(out_width, out_height, data)?)
+    }
+}
@@ -0,0 +1,168 @@
+//! Tile by tile processing of images too large to filter in one piece. Tiles are read with a
+//! halo of context around them, processed independently and cropped back to their core before
+//! being written, so neighbourhood operations give the same result as on the whole image as long
+//! as the halo covers their radius.
+
+use glance_core::img::{Image, Rect, pixel::Luma, pixel::Pixel};
+use glance_core::par::*;
+
+use crate::{Error, Result};
+
+/// Random access to the pixels of a large image, e.g. a decoder reading strips of a scan.
+pub trait TileSource<P: Pixel>: Sync {
+    fn dimensions(&self) -> (usize, usize);
+    /// Returns the pixels inside `rect`, which always lies within the image.
+    fn read_tile(&self, rect: Rect) -> Result<Image<P>>;
+}
+
+/// Destination for processed tiles, e.g. an encoder writing strips of the output.
+pub trait TileSink<P: Pixel> {
+    /// Stores `tile` with its top-left corner at `position`. Tiles are written in row-major
+    /// order and never overlap.
+    fn write_tile(&mut self, position: (usize, usize), tile: &Image<P>) -> Result<()>;
+}
+
+impl<P: Pixel> TileSource<P> for Image<P> {
+    fn dimensions(&self) -> (usize, usize) {
+        Image::dimensions(self)
+    }
+
+    fn read_tile(&self, rect: Rect) -> Result<Image<P>> {
+        Ok(self.crop(rect)?)
+    }
+}
+
+impl<P: Pixel> TileSink<P> for Image<P> {
+    fn write_tile(&mut self, position: (usize, usize), tile: &Image<P>) -> Result<()> {
+        self.paste(position, tile);
+        Ok(())
+    }
+}
+
+/// Parameters of tiled processing.
+#[derive(Debug, Clone, Copy)]
+pub struct TiledProcessor {
+    /// Size (width, height) of the tiles written to the output
+    pub tile_size: (usize, usize),
+    /// Pixels of context read on every side of a tile, at least the radius of the largest
+    /// neighbourhood the processing reads. See [`TiledProcessor::kernel_halo`] and
+    /// [`TiledProcessor::gaussian_halo`].
+    pub halo: usize,
+    /// Upper bound in bytes for the tiles held in memory at once (input and output), or None to
+    /// process all tiles of the image at once. At least one tile is always processed.
+    pub memory_budget: Option<usize>,
+    /// Whether tiles held in memory at the same time are processed in parallel
+    pub parallel: bool,
+}
+
+impl Default for TiledProcessor {
+    fn default() -> Self {
+        Self {
+            tile_size: (512, 512),
+            halo: 0,
+            memory_budget: None,
+            parallel: true,
+        }
+    }
+}
+
+impl TiledProcessor {
+    /// Returns the halo needed by a filter with the given kernel.
+    pub fn kernel_halo(kernel: &Image<Luma>) -> usize {
+        let (width, height) = kernel.dimensions();
+        width.max(height) / 2
+    }
+
+    /// Returns the halo needed by a Gaussian blur with standard deviation `sigma`.
+    pub fn gaussian_halo(sigma: f32) -> usize {
+        (3.0 * sigma).ceil().max(0.0) as usize
+    }
+
+    /// Returns the output tiles covering an image of `dimensions` in row-major order.
+    pub fn tiles(&self, dimensions: (usize, usize)) -> Vec<Rect> {
+        let (tile_width, tile_height) = (self.tile_size.0.max(1), self.tile_size.1.max(1));
+        (0..dimensions.1)
+            .step_by(tile_height)
+            .flat_map(|y| {
+                (0..dimensions.0)
+                    .step_by(tile_width)
+                    .map(move |x| Rect::new(x, y, tile_width, tile_height).clip_to(dimensions))
+            })
+            .collect()
+    }
+
+    /// Returns how many tiles fit into the memory budget at once.
+    fn tiles_in_flight<P: Pixel>(&self, tile_count: usize) -> usize {
+        let padded = (self.tile_size.0 + 2 * self.halo) * (self.tile_size.1 + 2 * self.halo);
+        let tile_bytes = 2 * padded * std::mem::size_of::<P>();
+        let fitting = match self.memory_budget {
+            Some(budget) => budget / tile_bytes.max(1),
+            None => tile_count,
+        };
+        if self.parallel { fitting.max(1) } else { 1 }
+    }
+
+    /// Reads every tile with its halo from `source`, runs `f` on it and writes the core of the
+    /// result to `sink`. `f` must return an image of the size it was given.
+    pub fn process<P, S, K, F>(&self, source: &S, sink: &mut K, f: F) -> Result<()>
+    where
+        P: Pixel,
+        S: TileSource<P>,
+        K: TileSink<P>,
+        F: Fn(Image<P>) -> Image<P> + Sync,
+    {
+        let dimensions = source.dimensions();
+        let tiles = self.tiles(dimensions);
+        let in_flight = self.tiles_in_flight::<P>(tiles.len());
+
+        let run = |tile: &Rect| -> Result<Image<P>> {
+            let padded = Rect::new(
+                tile.x.saturating_sub(self.halo),
+                tile.y.saturating_sub(self.halo),
+                tile.width + tile.x.min(self.halo) + self.halo,
+                tile.height + tile.y.min(self.halo) + self.halo,
+            )
+            .clip_to(dimensions);
+
+            let input = source.read_tile(padded)?;
+            let output = f(input);
+            if output.dimensions() != (padded.width, padded.height) {
+                return Err(Error::DimensionMismatch {
+                    expected: (padded.width, padded.height),
+                    actual: output.dimensions(),
+                });
+            }
+            let core = Rect::new(
+                tile.x - padded.x,
+                tile.y - padded.y,
+                tile.width,
+                tile.height,
+            );
+            Ok(output.crop(core)?)
+        };
+
+        for batch in tiles.chunks(in_flight) {
+            let processed: Vec<Result<Image<P>>> = if self.parallel {
+                batch.par_iter().map(run).collect()
+            } else {
+                batch.iter().map(run).collect()
+            };
+            for (tile, output) in batch.iter().zip(processed) {
+                sink.write_tile((tile.x, tile.y), &output?)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Processes an image held in memory tile by tile, see [`TiledProcessor::process`].
+    pub fn apply<P, F>(&self, image: &Image<P>, f: F) -> Result<Image<P>>
+    where
+        P: Pixel,
+        F: Fn(Image<P>) -> Image<P> + Sync,
+    {
+        let (width, height) = image.dimensions();
+        let mut output = Image::from_data(width, height, vec![P::new(); width * height])?;
+        self.process(image, &mut output, f)?;
+        Ok(output)
+    }
+}
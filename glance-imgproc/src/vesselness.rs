@@ -0,0 +1,113 @@
+use glance_core::img::{Image, pixel::Luma};
+use glance_core::par::*;
+
+use crate::linear_filters::LinearFilterExtLuma;
+
+/// Parameters of Frangi's multi-scale vesselness filter, which enhances tubular structures
+/// (vessels, cracks, fibers) from the eigenvalues of the Hessian matrix.
+///
+/// At every scale a pixel scores high when the image curves strongly across one direction but
+/// barely along the other. The output is the maximum response over all scales.
+#[derive(Debug, Clone)]
+pub struct Frangi {
+    /// Gaussian scales in pixels, roughly the radii of the structures to enhance
+    pub sigmas: Vec<f32>,
+    /// Sensitivity to blob-like structures, lower values suppress blobs more
+    pub beta: f32,
+    /// Sensitivity to the overall curvature (structureness). None uses half of the largest
+    /// Hessian norm at every scale
+    pub c: Option<f32>,
+    /// Enhance bright ridges on a dark background (true) or dark ridges on a bright one (false)
+    pub bright_ridges: bool,
+}
+
+impl Default for Frangi {
+    fn default() -> Self {
+        Frangi {
+            sigmas: vec![1.0, 2.0, 3.0, 4.0],
+            beta: 0.5,
+            c: None,
+            bright_ridges: true,
+        }
+    }
+}
+
+impl Frangi {
+    /// Returns the vesselness of every pixel, in [0.0, 1.0].
+    pub fn filter(&self, image: &Image<Luma>) -> Image<Luma> {
+        let (width, height) = image.dimensions();
+        let mut vesselness = vec![0.0f32; width * height];
+
+        for &sigma in &self.sigmas {
+            let smoothed: Vec<f32> = image
+                .clone()
+                .gaussian_blur(sigma)
+                .pixels()
+                .map(|p| p.l)
+                .collect();
+            let eigenvalues = hessian_eigenvalues(&smoothed, width, height, sigma);
+
+            let c = self.c.unwrap_or_else(|| {
+                let max_norm = eigenvalues
+                    .iter()
+                    .map(|(l1, l2)| l1.hypot(*l2))
+                    .fold(0.0, f32::max);
+                max_norm / 2.0
+            });
+            if c <= 0.0 {
+                continue;
+            }
+
+            vesselness
+                .par_iter_mut()
+                .zip(&eigenvalues)
+                .for_each(|(best, &(l1, l2))| {
+                    // A bright ridge curves down across it, so its large eigenvalue is negative
+                    if (self.bright_ridges && l2 > 0.0) || (!self.bright_ridges && l2 < 0.0) {
+                        return;
+                    }
+                    if l2 == 0.0 {
+                        return;
+                    }
+                    let blobness = l1 / l2;
+                    let structureness = l1 * l1 + l2 * l2;
+                    let v = (-blobness * blobness / (2.0 * self.beta * self.beta)).exp()
+                        * (1.0 - (-structureness / (2.0 * c * c)).exp());
+                    *best = best.max(v);
+                });
+        }
+
+        let data = vesselness.into_iter().map(|l| Luma { l }).collect();
+        Image::from_data(width, height, data).unwrap()
+    }
+}
+
+/// Returns the eigenvalues (λ1, λ2) of the scale normalized Hessian of every pixel, ordered so
+/// that |λ1| <= |λ2|. Derivatives are central differences with replicated borders.
+fn hessian_eigenvalues(plane: &[f32], width: usize, height: usize, sigma: f32) -> Vec<(f32, f32)> {
+    let at = |x: isize, y: isize| {
+        let x = x.clamp(0, width as isize - 1) as usize;
+        let y = y.clamp(0, height as isize - 1) as usize;
+        plane[y * width + x]
+    };
+    let scale = sigma * sigma;
+
+    (0..width * height)
+        .into_par_iter()
+        .map(|idx| {
+            let (x, y) = ((idx % width) as isize, (idx / width) as isize);
+            let center = at(x, y);
+            let dxx = (at(x + 1, y) - 2.0 * center + at(x - 1, y)) * scale;
+            let dyy = (at(x, y + 1) - 2.0 * center + at(x, y - 1)) * scale;
+            let dxy = (at(x + 1, y + 1) - at(x + 1, y - 1) - at(x - 1, y + 1) + at(x - 1, y - 1))
+                / 4.0
+                * scale;
+
+            // Eigenvalues of the symmetric 2x2 matrix [[dxx, dxy], [dxy, dyy]]
+            let mean = (dxx + dyy) / 2.0;
+            let spread = (((dxx - dyy) / 2.0).powi(2) + dxy * dxy).sqrt();
+            let (a, b) = (mean + spread, mean - spread);
+            if a.abs() <= b.abs() { (a, b) } else { (b, a) }
+        })
+        .collect()
+}
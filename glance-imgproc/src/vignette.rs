@@ -0,0 +1,173 @@
+//! Vignetting, the darkening of an image towards its corners, with a radial model: the light
+//! reaching a pixel is attenuated by `1 - strength * rho^falloff`, where rho is the distance to
+//! the image center relative to the half diagonal. The model works in linear light, where lens
+//! vignetting is a plain multiplication.
+
+use glance_core::img::{
+    Image,
+    pixel::{Luma, Pixel, Rgba},
+};
+
+use crate::color::{linear_to_srgb, srgb_to_linear};
+use crate::linear_filters::LinearFilterExtLuma;
+
+/// Lower bound of the attenuation, so that correcting a strong model doesn't divide by zero.
+const MIN_ATTENUATION: f32 = 0.01;
+
+/// The radial vignetting model of a lens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vignette {
+    /// Light lost in the corners, 0.0 for none and 1.0 for black corners
+    pub strength: f32,
+    /// Exponent of the radial falloff, larger values keep the center brighter for longer
+    pub falloff: f32,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            strength: 0.0,
+            falloff: 2.0,
+        }
+    }
+}
+
+impl Vignette {
+    /// Returns the fraction of light reaching a pixel at relative distance `rho` from the
+    /// center.
+    pub fn attenuation(&self, rho: f32) -> f32 {
+        (1.0 - self.strength * rho.powf(self.falloff)).max(MIN_ATTENUATION)
+    }
+
+    /// Fits the model to a flat-field frame, a photo of an evenly lit uniform surface taken with
+    /// the same lens and aperture. The frame is compared to its center brightness, and the
+    /// strength and falloff are found by a least squares fit in log space.
+    pub fn fit(flat: &Image<Luma>) -> Self {
+        let (width, height) = flat.dimensions();
+        let smooth = flat.clone().gaussian_blur(2.0);
+        let samples: Vec<(f32, f32)> = smooth
+            .pixels()
+            .enumerate()
+            .map(|(i, p)| {
+                let rho = relative_radius(i % width, i / width, width, height);
+                (rho, srgb_to_linear(p.l))
+            })
+            .collect();
+
+        let center: Vec<f32> = samples
+            .iter()
+            .filter(|(rho, _)| *rho < 0.1)
+            .map(|(_, v)| *v)
+            .collect();
+        if center.is_empty() {
+            return Self::default();
+        }
+        let center = center.iter().sum::<f32>() / center.len() as f32;
+
+        // ln(1 - v / center) = ln(strength) + falloff * ln(rho)
+        let points: Vec<(f32, f32)> = samples
+            .iter()
+            .filter(|(rho, _)| *rho > 0.2)
+            .filter_map(|&(rho, v)| {
+                let lost = 1.0 - v / center.max(f32::EPSILON);
+                (lost > 1e-3).then(|| (rho.ln(), lost.ln()))
+            })
+            .collect();
+        if points.len() < 2 {
+            return Self::default();
+        }
+        let n = points.len() as f32;
+        let mean_x = points.iter().map(|p| p.0).sum::<f32>() / n;
+        let mean_y = points.iter().map(|p| p.1).sum::<f32>() / n;
+        let covariance: f32 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+        let variance: f32 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+        let falloff = covariance / variance.max(f32::EPSILON);
+        Self {
+            strength: (mean_y - falloff * mean_x).exp().clamp(0.0, 1.0),
+            falloff,
+        }
+    }
+}
+
+/// Returns the distance of pixel (x, y) to the image center relative to the half diagonal.
+fn relative_radius(x: usize, y: usize, width: usize, height: usize) -> f32 {
+    let (cx, cy) = ((width as f32 - 1.0) / 2.0, (height as f32 - 1.0) / 2.0);
+    let half_diagonal = cx.hypot(cy).max(f32::EPSILON);
+    (x as f32 - cx).hypot(y as f32 - cy) / half_diagonal
+}
+
+/// Shared implementation: multiplies the linear light of every pixel by `gain(attenuation)`.
+fn apply_gain<P: Pixel>(
+    image: &mut Image<P>,
+    model: Vignette,
+    gain: impl Fn(f32) -> f32,
+    mut scale: impl FnMut(&mut P, f32),
+) {
+    let (width, height) = image.dimensions();
+    for (i, pixel) in image.pixels_mut().enumerate() {
+        let rho = relative_radius(i % width, i / width, width, height);
+        scale(pixel, gain(model.attenuation(rho)));
+    }
+}
+
+fn scale_value(value: f32, gain: f32) -> f32 {
+    linear_to_srgb((srgb_to_linear(value) * gain).clamp(0.0, 1.0))
+}
+
+fn scale_rgba(pixel: &mut Rgba, gain: f32) {
+    pixel.r = scale_value(pixel.r, gain);
+    pixel.g = scale_value(pixel.g, gain);
+    pixel.b = scale_value(pixel.b, gain);
+}
+
+/// Extension trait for [`glance_core::img::Image`] to add and correct vignetting of Luma images
+pub trait VignetteExtLuma {
+    fn vignette(self, strength: f32, falloff: f32) -> Self;
+    fn devignette(self, strength: f32, falloff: f32) -> Self;
+}
+
+/// Extension trait for [`glance_core::img::Image`] to add and correct vignetting of RGBA images.
+/// Alpha is left unchanged.
+pub trait VignetteExtRgba {
+    fn vignette(self, strength: f32, falloff: f32) -> Self;
+    fn devignette(self, strength: f32, falloff: f32) -> Self;
+}
+
+impl VignetteExtLuma for Image<Luma> {
+    /// Darkens the image towards the corners with the [`Vignette`] model, as a stylistic effect.
+    fn vignette(mut self, strength: f32, falloff: f32) -> Self {
+        let model = Vignette { strength, falloff };
+        apply_gain(&mut self, model, |a| a, |p, g| p.l = scale_value(p.l, g));
+        self
+    }
+
+    /// Undoes the vignetting described by the [`Vignette`] model, e.g. one returned by
+    /// [`Vignette::fit`]. Values brightened beyond 1.0 are clamped.
+    fn devignette(mut self, strength: f32, falloff: f32) -> Self {
+        let model = Vignette { strength, falloff };
+        apply_gain(
+            &mut self,
+            model,
+            |a| 1.0 / a,
+            |p, g| p.l = scale_value(p.l, g),
+        );
+        self
+    }
+}
+
+impl VignetteExtRgba for Image<Rgba> {
+    /// Darkens the image towards the corners with the [`Vignette`] model, as a stylistic effect.
+    fn vignette(mut self, strength: f32, falloff: f32) -> Self {
+        let model = Vignette { strength, falloff };
+        apply_gain(&mut self, model, |a| a, scale_rgba);
+        self
+    }
+
+    /// Undoes the vignetting described by the [`Vignette`] model, e.g. one returned by
+    /// [`Vignette::fit`]. Values brightened beyond 1.0 are clamped.
+    fn devignette(mut self, strength: f32, falloff: f32) -> Self {
+        let model = Vignette { strength, falloff };
+        apply_gain(&mut self, model, |a| 1.0 / a, scale_rgba);
+        self
+    }
+}
@@ -0,0 +1,620 @@
+//! Python bindings for glance. Build and install the `glance` module into the active virtual
+//! environment with `maturin develop -m glance-py/Cargo.toml`.
+//!
+//! ```python
+//! import glance
+//!
+//! img = glance.Image.open("flower.jpg")
+//! edges = img.gaussian_blur(1.5).grayscale().threshold(0.5)
+//! pixels = edges.to_numpy()  # read-only float32 view of shape (height, width)
+//! ```
+//!
+//! Images are immutable on the Python side: every operation returns a new image, like the
+//! consuming methods of the Rust API.
+
+use std::path::PathBuf;
+
+use glance_core::drawing::shapes::{AABB, Circle, Line};
+use glance_core::drawing::text::Text;
+use glance_core::img::Image;
+use glance_core::img::pixel::{Luma, Rgba};
+use glance_imgproc::kernels;
+use glance_imgproc::linear_filters::{BorderMode, LinearFilterExtLuma, LinearFilterExtRgba};
+use glance_imgproc::nonlinear_filters::{NonLinearFilterExtLuma, NonLinearFilterExtRgba};
+use glance_imgproc::point_ops::{PointOpsExtLuma, PointOpsExtRgba, ThresholdType};
+use numpy::ndarray::{ArrayViewD, IxDyn};
+use numpy::{PyArrayDyn, PyReadonlyArray2, PyReadonlyArrayDyn, PyUntypedArrayMethods};
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+
+/// The pixel data of an [`PyImage`], one variant per supported pixel type.
+#[derive(Debug, Clone)]
+enum Pixels {
+    Rgba(Image<Rgba>),
+    Luma(Image<Luma>),
+}
+
+/// Applies the same expression to the image of either pixel type, returning a new [`PyImage`].
+macro_rules! map_pixels {
+    ($self:expr, |$image:ident| $body:expr) => {
+        PyImage {
+            pixels: match &$self.pixels {
+                Pixels::Rgba(image) => {
+                    let $image = image.clone();
+                    Pixels::Rgba($body)
+                }
+                Pixels::Luma(image) => {
+                    let $image = image.clone();
+                    Pixels::Luma($body)
+                }
+            },
+        }
+    };
+}
+
+/// Draws the shape built by the expression on a copy of the image, with `$color` converted to
+/// the pixel type of the image.
+macro_rules! draw_shape {
+    ($self:expr, $color:expr, |$c:ident| $shape:expr) => {{
+        let color = parse_color(&$color)?;
+        let pixels = match &$self.pixels {
+            Pixels::Rgba(image) => {
+                let mut image = image.clone();
+                let $c = color;
+                image.draw($shape).map_err(core_error)?;
+                Pixels::Rgba(image)
+            }
+            Pixels::Luma(image) => {
+                let mut image = image.clone();
+                let $c = luma_of(color);
+                image.draw($shape).map_err(core_error)?;
+                Pixels::Luma(image)
+            }
+        };
+        Ok(PyImage { pixels })
+    }};
+}
+
+fn value_error(message: impl Into<String>) -> PyErr {
+    PyValueError::new_err(message.into())
+}
+
+fn core_error(error: glance_core::CoreError) -> PyErr {
+    match error {
+        glance_core::CoreError::Io(e) => PyIOError::new_err(e.to_string()),
+        e => value_error(format!("{e:?}")),
+    }
+}
+
+fn parse_border(border: &str) -> PyResult<BorderMode> {
+    match border {
+        "replicate" => Ok(BorderMode::Replicate),
+        "reflect" => Ok(BorderMode::Reflect),
+        "wrap" => Ok(BorderMode::Wrap),
+        "zero" => Ok(BorderMode::Constant(0.0)),
+        _ => Err(value_error(format!(
+            "unknown border '{border}', expected 'replicate', 'reflect', 'wrap' or 'zero'"
+        ))),
+    }
+}
+
+fn imgproc_error(error: glance_imgproc::Error) -> PyErr {
+    use glance_imgproc::Error;
+    match error {
+        Error::CoreError(e) => core_error(e),
+        Error::KernelSizeEven { width, height } => {
+            value_error(format!("kernel size must be odd, got {width}x{height}"))
+        }
+        Error::DimensionMismatch { expected, actual } => value_error(format!(
+            "expected an image of {}x{}, got {}x{}",
+            expected.0, expected.1, actual.0, actual.1
+        )),
+        Error::ImageSmallerThanKernel { image, kernel } => value_error(format!(
+            "kernel of {}x{} is larger than the {}x{} image",
+            kernel.0, kernel.1, image.0, image.1
+        )),
+        Error::SingularTransformation { matrix } => {
+            value_error(format!("transformation {matrix:?} is not invertible"))
+        }
+    }
+}
+
+/// Converts a color given as a gray value or an (r, g, b[, a]) tuple in [0, 1].
+fn parse_color(color: &[f32]) -> PyResult<Rgba> {
+    match *color {
+        [l] => Ok(Rgba {
+            r: l,
+            g: l,
+            b: l,
+            a: 1.0,
+        }),
+        [r, g, b] => Ok(Rgba { r, g, b, a: 1.0 }),
+        [r, g, b, a] => Ok(Rgba { r, g, b, a }),
+        _ => Err(value_error(format!(
+            "a color needs 1, 3 or 4 values, got {}",
+            color.len()
+        ))),
+    }
+}
+
+fn luma_of(color: Rgba) -> Luma {
+    Luma {
+        l: 0.299 * color.r + 0.587 * color.g + 0.114 * color.b,
+    }
+}
+
+/// Pixel types that are `repr(C)` structs of `f32` channels only, so a pixel slice can be
+/// viewed as a slice of its channels.
+///
+/// # Safety
+///
+/// Implementors must have the size and alignment of `[f32; N]` with `N` channels.
+unsafe trait F32Channels: Sized {}
+
+// SAFETY: Both are repr(C) structs of f32 fields
+unsafe impl F32Channels for Rgba {}
+unsafe impl F32Channels for Luma {}
+
+/// Views pixels as their channels in memory order.
+fn as_channels<P: F32Channels>(pixels: &[P]) -> &[f32] {
+    let len = std::mem::size_of_val(pixels) / std::mem::size_of::<f32>();
+    // SAFETY: P is laid out like [f32; N], see F32Channels
+    unsafe { std::slice::from_raw_parts(pixels.as_ptr().cast(), len) }
+}
+
+/// Views channel values as pixels, the length must be a multiple of the channel count.
+fn from_channels<P: F32Channels>(values: &[f32]) -> &[P] {
+    let channels = std::mem::size_of::<P>() / std::mem::size_of::<f32>();
+    assert_eq!(values.len() % channels, 0);
+    // SAFETY: P is laid out like [f32; N] and has the alignment of f32, see F32Channels
+    unsafe { std::slice::from_raw_parts(values.as_ptr().cast(), values.len() / channels) }
+}
+
+/// Builds the pixels of an image from values in row-major order, with the shape of the
+/// numpy array they came from.
+fn image_from_values(shape: &[usize], values: &[f32]) -> PyResult<PyImage> {
+    let (height, width, channels) = match *shape {
+        [h, w] => (h, w, 1),
+        [h, w, c] if [1, 3, 4].contains(&c) => (h, w, c),
+        _ => return Err(value_error(format!("unsupported array shape {shape:?}"))),
+    };
+    let pixels = match channels {
+        1 => Pixels::Luma(
+            Image::from_data(width, height, from_channels(values).to_vec()).map_err(core_error)?,
+        ),
+        4 => Pixels::Rgba(
+            Image::from_data(width, height, from_channels(values).to_vec()).map_err(core_error)?,
+        ),
+        _ => Pixels::Rgba(
+            Image::from_data(
+                width,
+                height,
+                values
+                    .chunks_exact(3)
+                    .map(|c| Rgba {
+                        r: c[0],
+                        g: c[1],
+                        b: c[2],
+                        a: 1.0,
+                    })
+                    .collect(),
+            )
+            .map_err(core_error)?,
+        ),
+    };
+    Ok(PyImage { pixels })
+}
+
+/// An image with RGBA ("RGBA") or grayscale ("L") float pixels in [0, 1].
+#[pyclass(name = "Image", module = "glance", frozen)]
+#[derive(Debug, Clone)]
+struct PyImage {
+    pixels: Pixels,
+}
+
+impl PyImage {
+    fn luma(&self, operation: &str) -> PyResult<&Image<Luma>> {
+        match &self.pixels {
+            Pixels::Luma(image) => Ok(image),
+            Pixels::Rgba(_) => Err(value_error(format!(
+                "{operation} needs a grayscale image, call grayscale() first"
+            ))),
+        }
+    }
+
+    fn rgba(&self, operation: &str) -> PyResult<&Image<Rgba>> {
+        match &self.pixels {
+            Pixels::Rgba(image) => Ok(image),
+            Pixels::Luma(_) => Err(value_error(format!(
+                "{operation} needs an RGBA image, call to_rgba() first"
+            ))),
+        }
+    }
+}
+
+#[pymethods]
+impl PyImage {
+    /// Creates a black image of the given size and mode ("RGBA" or "L").
+    #[new]
+    #[pyo3(signature = (width, height, mode = "RGBA"))]
+    fn new(width: usize, height: usize, mode: &str) -> PyResult<Self> {
+        let pixels = match mode {
+            "RGBA" => Pixels::Rgba(Image::new(width, height)),
+            "L" => Pixels::Luma(Image::new(width, height)),
+            _ => return Err(value_error(format!("unknown mode '{mode}'"))),
+        };
+        Ok(PyImage { pixels })
+    }
+
+    /// Opens an image file, converting it to the given mode.
+    #[staticmethod]
+    #[pyo3(signature = (path, mode = "RGBA"))]
+    fn open(path: PathBuf, mode: &str) -> PyResult<Self> {
+        let pixels = match mode {
+            "RGBA" => Pixels::Rgba(Image::open(&path).map_err(core_error)?),
+            "L" => Pixels::Luma(Image::open(&path).map_err(core_error)?),
+            _ => return Err(value_error(format!("unknown mode '{mode}'"))),
+        };
+        Ok(PyImage { pixels })
+    }
+
+    /// Creates an image from a numpy array of shape (height, width) for grayscale or
+    /// (height, width, channels) with 1, 3 or 4 channels. float32/float64 values are taken as
+    /// is, uint8 values are scaled to [0, 1].
+    ///
+    /// Unlike `to_numpy`, this always copies: an image owns its pixel buffer and outlives the
+    /// array, which numpy may modify or free at any time, so it cannot borrow the array's
+    /// memory. C-contiguous float32 arrays are copied once, straight into the pixel buffer;
+    /// uint8, float64 and non-contiguous arrays are converted value by value first.
+    #[staticmethod]
+    fn from_numpy(array: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if let Ok(a) = array.extract::<PyReadonlyArrayDyn<'_, f32>>() {
+            return match a.as_slice() {
+                Ok(values) => image_from_values(a.shape(), values),
+                // Iterating the array in logical order handles non-contiguous views
+                Err(_) => {
+                    image_from_values(a.shape(), &a.as_array().iter().copied().collect::<Vec<_>>())
+                }
+            };
+        }
+        if let Ok(a) = array.extract::<PyReadonlyArrayDyn<'_, f64>>() {
+            let values: Vec<f32> = a.as_array().iter().map(|&v| v as f32).collect();
+            return image_from_values(a.shape(), &values);
+        }
+        if let Ok(a) = array.extract::<PyReadonlyArrayDyn<'_, u8>>() {
+            let values: Vec<f32> = a.as_array().iter().map(|&v| v as f32 / 255.0).collect();
+            return image_from_values(a.shape(), &values);
+        }
+        Err(value_error("expected a float32, float64 or uint8 array"))
+    }
+
+    /// Returns the pixels as a float32 numpy array of shape (height, width) for grayscale or
+    /// (height, width, 4) for RGBA images. The array is a read-only view of the image buffer
+    /// without a copy, call `.copy()` on it to modify the values.
+    fn to_numpy<'py>(slf: &Bound<'py, Self>) -> PyResult<Bound<'py, PyArrayDyn<f32>>> {
+        let this = slf.get();
+        let (width, height) = this.size();
+        let (shape, values) = match &this.pixels {
+            Pixels::Rgba(image) => (vec![height, width, 4], as_channels(image.as_slice())),
+            Pixels::Luma(image) => (vec![height, width], as_channels(image.as_slice())),
+        };
+        let view = ArrayViewD::from_shape(IxDyn(&shape), values)
+            .map_err(|e| value_error(e.to_string()))?;
+        // SAFETY: The image is frozen, so its buffer is neither modified nor reallocated while
+        // `slf`, the base object of the array, is alive.
+        let array = unsafe { PyArrayDyn::borrow_from_array(&view, slf.clone().into_any()) };
+        array.getattr("flags")?.setattr("writeable", false)?;
+        Ok(array)
+    }
+
+    /// Saves the image, the format is determined by the file extension.
+    fn save(&self, path: PathBuf) -> PyResult<()> {
+        match &self.pixels {
+            Pixels::Rgba(image) => image.save(&path),
+            Pixels::Luma(image) => image.save(&path),
+        }
+        .map_err(core_error)
+    }
+
+    /// Shows the image in a window until it is closed or Escape is pressed.
+    fn display(&self, title: &str) -> PyResult<()> {
+        match &self.pixels {
+            Pixels::Rgba(image) => image.display(title),
+            Pixels::Luma(image) => image.display(title),
+        }
+        .map_err(core_error)
+    }
+
+    #[getter]
+    fn width(&self) -> usize {
+        self.size().0
+    }
+
+    #[getter]
+    fn height(&self) -> usize {
+        self.size().1
+    }
+
+    /// (width, height) of the image
+    #[getter]
+    fn size(&self) -> (usize, usize) {
+        match &self.pixels {
+            Pixels::Rgba(image) => image.dimensions(),
+            Pixels::Luma(image) => image.dimensions(),
+        }
+    }
+
+    /// "RGBA" or "L"
+    #[getter]
+    fn mode(&self) -> &'static str {
+        match &self.pixels {
+            Pixels::Rgba(_) => "RGBA",
+            Pixels::Luma(_) => "L",
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        let (width, height) = self.size();
+        format!("<glance.Image {} {width}x{height}>", self.mode())
+    }
+
+    /// Converts the image to grayscale with the Rec. 601 luma weights.
+    fn grayscale(&self) -> PyImage {
+        let pixels = match &self.pixels {
+            Pixels::Rgba(image) => Pixels::Luma(image.clone().grayscale()),
+            Pixels::Luma(image) => Pixels::Luma(image.clone()),
+        };
+        PyImage { pixels }
+    }
+
+    /// Converts the image to RGBA, repeating the gray value in every color channel.
+    fn to_rgba(&self) -> PyImage {
+        let pixels = match &self.pixels {
+            Pixels::Rgba(image) => Pixels::Rgba(image.clone()),
+            Pixels::Luma(image) => {
+                let (width, height) = image.dimensions();
+                let data = image
+                    .pixels()
+                    .map(|p| Rgba {
+                        r: p.l,
+                        g: p.l,
+                        b: p.l,
+                        a: 1.0,
+                    })
+                    .collect();
+                Pixels::Rgba(Image::from_data(width, height, data).unwrap())
+            }
+        };
+        PyImage { pixels }
+    }
+
+    fn invert(&self) -> PyImage {
+        map_pixels!(self, |image| image.invert())
+    }
+
+    fn gamma(&self, gamma: f32) -> PyImage {
+        map_pixels!(self, |image| image.gamma(gamma))
+    }
+
+    /// Sets grayscale pixels of at least `threshold` to `max_value` and others to 0.
+    #[pyo3(signature = (threshold, max_value = 1.0))]
+    fn threshold(&self, threshold: f32, max_value: f32) -> PyResult<PyImage> {
+        let image = self.luma("threshold")?.clone();
+        Ok(PyImage {
+            pixels: Pixels::Luma(image.threshold(threshold, max_value, ThresholdType::Binary)),
+        })
+    }
+
+    fn equalize_histogram(&self) -> PyResult<PyImage> {
+        let image = self.luma("equalize_histogram")?.clone();
+        Ok(PyImage {
+            pixels: Pixels::Luma(image.histrogram_equalize()),
+        })
+    }
+
+    fn brightness(&self, brightness: f32) -> PyResult<PyImage> {
+        let image = self.rgba("brightness")?.clone();
+        Ok(PyImage {
+            pixels: Pixels::Rgba(image.brightness(brightness)),
+        })
+    }
+
+    fn contrast(&self, contrast: f32) -> PyResult<PyImage> {
+        let image = self.rgba("contrast")?.clone();
+        Ok(PyImage {
+            pixels: Pixels::Rgba(image.contrast(contrast)),
+        })
+    }
+
+    fn gaussian_blur(&self, sigma: f32) -> PyImage {
+        map_pixels!(self, |image| image.gaussian_blur(sigma))
+    }
+
+    fn median_blur(&self, size: usize) -> PyResult<PyImage> {
+        Ok(map_pixels!(self, |image| image
+            .median_blur(size)
+            .map_err(imgproc_error)?))
+    }
+
+    /// Correlates every channel with a 2D float32 kernel of odd size.
+    #[pyo3(signature = (kernel, border = "replicate"))]
+    fn convolve(&self, kernel: PyReadonlyArray2<'_, f32>, border: &str) -> PyResult<PyImage> {
+        let border = parse_border(border)?;
+        let kernel = kernel.as_array();
+        let (k_height, k_width) = kernel.dim();
+        let kernel = Image::from_data(
+            k_width,
+            k_height,
+            kernel.iter().map(|&l| Luma { l }).collect(),
+        )
+        .map_err(core_error)?;
+        Ok(map_pixels!(self, |image| image
+            .convolve_2d(&kernel, border)
+            .map_err(imgproc_error)?))
+    }
+
+    /// Grayscale dilation with a square structuring element.
+    #[pyo3(signature = (size = 3, border = "replicate"))]
+    fn dilate(&self, size: usize, border: &str) -> PyResult<PyImage> {
+        let image = self.luma("dilate")?.clone();
+        let kernel = kernels::box_kernel(size);
+        Ok(PyImage {
+            pixels: Pixels::Luma(
+                image
+                    .dilate(&kernel, parse_border(border)?)
+                    .map_err(imgproc_error)?,
+            ),
+        })
+    }
+
+    /// Grayscale erosion with a square structuring element.
+    #[pyo3(signature = (size = 3, border = "replicate"))]
+    fn erode(&self, size: usize, border: &str) -> PyResult<PyImage> {
+        let image = self.luma("erode")?.clone();
+        let kernel = kernels::box_kernel(size);
+        Ok(PyImage {
+            pixels: Pixels::Luma(
+                image
+                    .erode(&kernel, parse_border(border)?)
+                    .map_err(imgproc_error)?,
+            ),
+        })
+    }
+
+    #[pyo3(signature = (x, y, width, height, color, filled = false, thickness = 1))]
+    #[allow(clippy::too_many_arguments)]
+    fn draw_rect(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        color: Vec<f32>,
+        filled: bool,
+        thickness: u32,
+    ) -> PyResult<PyImage> {
+        draw_shape!(self, color, |color| AABB {
+            position: (x, y),
+            size: (width, height),
+            color,
+            filled,
+            thickness,
+        })
+    }
+
+    #[pyo3(signature = (x, y, radius, color, filled = false, thickness = 1))]
+    fn draw_circle(
+        &self,
+        x: usize,
+        y: usize,
+        radius: u32,
+        color: Vec<f32>,
+        filled: bool,
+        thickness: u32,
+    ) -> PyResult<PyImage> {
+        draw_shape!(self, color, |color| Circle {
+            position: (x, y),
+            color,
+            radius,
+            filled,
+            thickness,
+        })
+    }
+
+    #[pyo3(signature = (start, end, color, thickness = 1))]
+    fn draw_line(
+        &self,
+        start: (usize, usize),
+        end: (usize, usize),
+        color: Vec<f32>,
+        thickness: u32,
+    ) -> PyResult<PyImage> {
+        draw_shape!(self, color, |color| Line {
+            start,
+            end,
+            color,
+            thickness,
+        })
+    }
+
+    #[pyo3(signature = (x, y, text, color, scale = 1))]
+    fn draw_text(
+        &self,
+        x: usize,
+        y: usize,
+        text: String,
+        color: Vec<f32>,
+        scale: u32,
+    ) -> PyResult<PyImage> {
+        draw_shape!(self, color, |color| Text {
+            position: (x, y),
+            text: text.clone(),
+            color,
+            scale,
+        })
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "glance")]
+fn glance_py(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyImage>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use super::*;
+
+    // Drive the bindings from Python code, like a user of the module would
+    #[test]
+    fn python_api() -> PyResult<()> {
+        Python::attach(|py| {
+            let module = PyModule::new(py, "glance")?;
+            glance_py(&module)?;
+            let globals = pyo3::types::PyDict::new(py);
+            globals.set_item("glance", module)?;
+            globals.set_item("has_numpy", py.import("numpy").is_ok())?;
+
+            let code = CString::new(
+                r#"
+img = glance.Image(40, 30)
+assert (img.width, img.height, img.mode) == (40, 30, "RGBA")
+img = img.draw_rect(5, 5, 20, 10, (1.0, 0.0, 0.0), filled=True)
+gray = img.gaussian_blur(1.0).grayscale()
+assert repr(gray) == "<glance.Image L 40x30>"
+mask = gray.threshold(0.25).dilate(3)
+assert mask.size == (40, 30)
+
+try:
+    img.threshold(0.5)
+    raise AssertionError("threshold on RGBA must fail")
+except ValueError:
+    pass
+try:
+    gray.median_blur(4)
+    raise AssertionError("even kernel sizes must fail")
+except ValueError:
+    pass
+
+if has_numpy:
+    import numpy as np
+    pixels = img.to_numpy()
+    assert pixels.shape == (30, 40, 4) and pixels.dtype == np.float32
+    assert pixels[10, 10, 0] == 1.0
+    # A read-only view that keeps the image alive
+    assert pixels.base is img and not pixels.flags.writeable
+    copied = glance.Image.from_numpy(pixels.copy())
+    assert copied.to_numpy()[10, 10, 0] == 1.0
+    assert glance.Image.from_numpy(pixels[:, ::2, :3]).size == (20, 30)
+    back = glance.Image.from_numpy((pixels * 255).astype(np.uint8))
+    assert back.mode == "RGBA" and back.size == (40, 30)
+    assert glance.Image.from_numpy(np.zeros((4, 6))).mode == "L"
+"#,
+            )?;
+            py.run(&code, Some(&globals), None)
+        })
+    }
+}
@@ -4,6 +4,21 @@ pub mod core {
         pub use glance_core::drawing::traits::*;
         pub use glance_core::img::pixel::*;
     }
+    pub mod colors {
+        pub use glance_core::colors::*;
+    }
+    pub mod annotations {
+        pub use glance_core::annotations::*;
+    }
+    pub mod profiling {
+        pub use glance_core::profiling::*;
+    }
+    pub mod raw {
+        pub use glance_core::raw::*;
+    }
+    pub mod video {
+        pub use glance_core::video::*;
+    }
 }
 
 pub mod imgproc {